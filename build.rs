@@ -25,8 +25,11 @@ use clap::CommandFactory;
 use clap_complete::generate_to;
 use clap_complete::Shell::{Bash, Fish, Zsh};
 use cli::Args;
+use std::env;
 use std::fs;
+use std::io::Error;
 use std::path::PathBuf;
+use std::process;
 
 const NAME: &str = "ironbar";
 
@@ -43,11 +46,54 @@ fn generate_shell_completions(mut cmd: Command) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Compiles the bundled fallback icons into a GResource bundle,
+/// which is embedded into the binary.
+fn compile_resources() -> std::io::Result<()> {
+    const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+    let resources_dir = PathBuf::from(MANIFEST_DIR).join("resources");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR to be set by cargo"));
+
+    let status = process::Command::new("glib-compile-resources")
+        .arg("--sourcedir")
+        .arg(&resources_dir)
+        .arg("--target")
+        .arg(out_dir.join("icons.gresource"))
+        .arg(resources_dir.join("icons.gresource.xml"))
+        .status()
+        .map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Failed to run glib-compile-resources, is glib installed? {err}"),
+            )
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!(
+            "glib-compile-resources exited with {status}"
+        )))
+    }
+}
+
 fn main() -> std::io::Result<()> {
+    // printing any of these disables the default of re-running on every change,
+    // so every input of the build script needs to be listed.
+    for path in [
+        "build.rs",
+        "src/cli.rs",
+        "src/error.rs",
+        "src/ipc",
+        "resources",
+    ] {
+        println!("cargo:rerun-if-changed={path}");
+    }
+
     let mut cmd = Args::command();
     cmd.set_bin_name(NAME);
 
     generate_shell_completions(cmd)?;
+    compile_resources()?;
 
     Ok(())
 }
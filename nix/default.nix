@@ -44,6 +44,7 @@
 
     nativeBuildInputs = [
         pkg-config
+        glib
         wrapGAppsHook
         gobject-introspection
         installShellFiles
@@ -0,0 +1,175 @@
+use gtk::prelude::*;
+use gtk::{LevelBar, ProgressBar, Scale, TickCallbackId, Widget};
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Time taken to animate between two values.
+const DURATION: Duration = Duration::from_millis(250);
+
+/// Global switch, set from the top-level `animations` option.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables all value animations.
+/// While disabled, values are set immediately.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A widget displaying a single numeric value,
+/// which can be animated by [`AnimatedValue`].
+pub trait Animatable: IsA<Widget> {
+    fn animated_value(&self) -> f64;
+    fn set_animated_value(&self, value: f64);
+}
+
+impl Animatable for ProgressBar {
+    fn animated_value(&self) -> f64 {
+        self.fraction()
+    }
+
+    fn set_animated_value(&self, value: f64) {
+        self.set_fraction(value);
+    }
+}
+
+impl Animatable for Scale {
+    fn animated_value(&self) -> f64 {
+        self.value()
+    }
+
+    fn set_animated_value(&self, value: f64) {
+        self.set_value(value);
+    }
+}
+
+impl Animatable for LevelBar {
+    fn animated_value(&self) -> f64 {
+        self.value()
+    }
+
+    fn set_animated_value(&self, value: f64) {
+        self.set_value(value);
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The displayed value when the current animation started.
+    from: f64,
+    target: f64,
+    /// Frame clock time (in microseconds) of the first frame
+    /// of the current animation.
+    start_time: Option<i64>,
+    tick_id: Option<TickCallbackId>,
+}
+
+/// Smoothly interpolates a widget's displayed value towards a target.
+///
+/// Progress is based on the widget's frame clock time
+/// rather than the number of frames drawn,
+/// so animations take the same time regardless of refresh rate.
+///
+/// Values are set immediately if animations are disabled
+/// or the widget is not currently visible.
+#[derive(Clone)]
+pub struct AnimatedValue<W: Animatable> {
+    widget: W,
+    state: Rc<RefCell<State>>,
+}
+
+impl<W: Animatable + Debug> Debug for AnimatedValue<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.borrow();
+
+        f.debug_struct("AnimatedValue")
+            .field("widget", &self.widget)
+            .field("target", &state.target)
+            .field("animating", &state.tick_id.is_some())
+            .finish()
+    }
+}
+
+impl<W: Animatable> AnimatedValue<W> {
+    pub fn new(widget: &W) -> Self {
+        Self {
+            widget: widget.clone(),
+            state: Rc::new(RefCell::new(State {
+                target: widget.animated_value(),
+                ..State::default()
+            })),
+        }
+    }
+
+    /// Starts animating from the displayed value towards `target`.
+    /// If an animation is already running, it continues
+    /// from its current position.
+    pub fn set(&self, target: f64) {
+        let mut state = self.state.borrow_mut();
+
+        if !enabled() || !self.widget.is_mapped() {
+            if let Some(tick_id) = state.tick_id.take() {
+                tick_id.remove();
+            }
+
+            state.target = target;
+            drop(state);
+
+            self.widget.set_animated_value(target);
+            return;
+        }
+
+        #[allow(clippy::float_cmp)]
+        if state.target == target && state.tick_id.is_some() {
+            return;
+        }
+
+        state.from = self.widget.animated_value();
+        state.target = target;
+        state.start_time = None;
+
+        if state.tick_id.is_none() {
+            let value = self.clone();
+            let tick_id = self
+                .widget
+                .add_tick_callback(move |_, clock| value.tick(clock.frame_time()));
+
+            state.tick_id = Some(tick_id);
+        }
+    }
+
+    fn tick(&self, frame_time: i64) -> glib::ControlFlow {
+        let mut state = self.state.borrow_mut();
+
+        let start_time = *state.start_time.get_or_insert(frame_time);
+        let elapsed = Duration::from_micros(u64::try_from(frame_time - start_time).unwrap_or(0));
+
+        let progress = (elapsed.as_secs_f64() / DURATION.as_secs_f64()).min(1.0);
+        let value = state.from + (state.target - state.from) * ease_out_cubic(progress);
+
+        let finished = progress >= 1.0;
+        if finished {
+            // returning `Break` removes the callback
+            state.tick_id = None;
+        }
+
+        drop(state);
+        self.widget.set_animated_value(value);
+
+        if finished {
+            glib::ControlFlow::Break
+        } else {
+            glib::ControlFlow::Continue
+        }
+    }
+}
+
+fn ease_out_cubic(progress: f64) -> f64 {
+    1.0 - (1.0 - progress).powi(3)
+}
@@ -1,20 +1,58 @@
 use crate::config::{BarConfig, BarPosition, MarginConfig, ModuleConfig};
+use crate::display_mode::{self, DisplayModeController};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::hotspot;
 use crate::modules::{BarModuleFactory, ModuleInfo, ModuleLocation};
+use crate::overflow::Overflow;
 use crate::popup::Popup;
+#[cfg(feature = "ipc")]
+use crate::write_lock;
 use crate::Ironbar;
 use color_eyre::Result;
 use glib::Propagation;
-use gtk::gdk::Monitor;
+use gtk::gdk::{Gravity, Monitor};
 use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, IconTheme, Orientation, Window, WindowType};
+use gtk::{
+    Application, ApplicationWindow, Button, CssProvider, IconTheme, Menu, MenuItem, Orientation,
+    Window, WindowType,
+};
 use gtk_layer_shell::LayerShell;
+use serde::Serialize;
+use std::cell::Cell;
+#[cfg(feature = "ipc")]
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
 use std::rc::Rc;
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
+
+/// The size and position of a bar on its output,
+/// in logical pixels relative to the output's top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BarGeometry {
+    pub name: String,
+    pub output: String,
+    pub position: BarPosition,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub margin: MarginConfig,
+    pub exclusive_zone: i32,
+    pub visible: bool,
+}
+
+impl Display for BarGeometry {
+    /// Writes the geometry as `<width>x<height>+<x>+<y>`,
+    /// the format used by tools such as `slurp` and `grim`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
 
 #[derive(Debug, Clone)]
 enum Inner {
-    New { config: Option<BarConfig> },
+    New { config: Option<Box<BarConfig>> },
     Loaded { popup: Rc<Popup> },
 }
 
@@ -30,14 +68,29 @@ pub struct Bar {
     window: ApplicationWindow,
 
     content: gtk::Box,
+    /// Holds the start and end groups,
+    /// with the center group overlaid on top.
+    layout: gtk::Overlay,
+    sides: gtk::Box,
 
     start: gtk::Box,
     center: gtk::Box,
     end: gtk::Box,
 
+    /// The config the bar was created from,
+    /// kept if the bar should be hidden or moved when the lid is closed.
+    #[cfg(feature = "lid")]
+    lid_config: Option<Rc<BarConfig>>,
+
     inner: Inner,
 }
 
+impl PartialEq for Bar {
+    fn eq(&self, other: &Self) -> bool {
+        self.window == other.window
+    }
+}
+
 impl Bar {
     pub fn new(
         app: &Application,
@@ -61,6 +114,10 @@ impl Bar {
         let position = config.position;
         let orientation = position.orientation();
 
+        #[cfg(feature = "lid")]
+        let lid_config = (config.on_lid_close != crate::config::LidCloseAction::None)
+            .then(|| Rc::new(config.clone()));
+
         let content = gtk::Box::builder()
             .orientation(orientation)
             .spacing(0)
@@ -80,6 +137,12 @@ impl Bar {
         let center = create_container("center", orientation);
         let end = create_container("end", orientation);
 
+        let sides = gtk::Box::new(orientation, 0);
+
+        let layout = gtk::Overlay::new();
+        layout.add(&sides);
+        content.pack_start(&layout, true, true, 0);
+
         window.add(&content);
 
         window.connect_destroy_event(|_, _| {
@@ -96,11 +159,15 @@ impl Bar {
             ironbar,
             window,
             content,
+            layout,
+            sides,
             start,
             center,
             end,
+            #[cfg(feature = "lid")]
+            lid_config,
             inner: Inner::New {
-                config: Some(config),
+                config: Some(Box::new(config)),
             },
         }
     }
@@ -132,6 +199,8 @@ impl Bar {
             monitor,
         );
 
+        self.setup_compositor_hints(&config);
+
         if let Some(autohide) = config.autohide {
             let hotspot_window = Window::new(WindowType::Toplevel);
             Self::setup_autohide(&self.window, &hotspot_window, autohide);
@@ -149,10 +218,44 @@ impl Bar {
             }
         }
 
-        let load_result = self.load_modules(config, monitor, self.monitor_size)?;
+        if config.recovery_button {
+            let recovery_window = self.setup_recovery_button(config.margin, monitor);
+
+            if start_hidden {
+                recovery_window.show_all();
+            }
+        }
+
+        #[cfg(feature = "workspaces")]
+        let workspace_filter = (
+            config.show_if_workspace.clone(),
+            config.hide_if_workspace.clone(),
+        );
+
+        let margin = config.margin;
+        let overflow = config.overflow;
+        if config.anchor_to_edges {
+            self.setup_center(margin);
+        }
+
+        let hotspots = config.hotspots.clone();
+
+        let load_result = self.load_modules(*config, monitor, self.monitor_size)?;
+
+        // hotspots can open popups, so are created once modules are loaded
+        hotspot::create(hotspots, &self.window, monitor, &load_result.popup);
+
+        let overflow = overflow.then(|| self.setup_overflow(&load_result.popup));
+        self.setup_adaptive_width(margin, overflow);
 
         self.show(!start_hidden);
 
+        #[cfg(feature = "workspaces")]
+        self.setup_workspace_visibility(workspace_filter.0, workspace_filter.1);
+
+        #[cfg(feature = "ipc")]
+        self.setup_geometry_var();
+
         self.inner = Inner::Loaded {
             popup: load_result.popup,
         };
@@ -211,6 +314,80 @@ impl Bar {
         );
     }
 
+    /// Applies the `namespace`, `opacity` and `blur` options to the bar window.
+    fn setup_compositor_hints(&self, config: &BarConfig) {
+        // each bar gets its own namespace by default,
+        // so that compositor rules can target them separately.
+        let namespace = match (&config.namespace, config.blur) {
+            (Some(namespace), _) => namespace.clone(),
+            (None, true) => format!("{}-blur-{}", env!("CARGO_PKG_NAME"), self.name),
+            (None, false) => format!("{}-{}", env!("CARGO_PKG_NAME"), self.name),
+        };
+
+        self.window.set_namespace(&namespace);
+
+        let opacity = config.opacity.clamp(0.0, 1.0);
+        if opacity < 1.0 {
+            self.content.set_opacity(opacity);
+        }
+
+        if opacity < 1.0 || config.blur {
+            // the theme usually gives windows an opaque background,
+            // which would hide anything behind the bar.
+            // this only applies to the window itself, and user styles take precedence.
+            let provider = CssProvider::new();
+            if let Err(err) = provider.load_from_data(b"window { background-color: transparent; }")
+            {
+                error!("Failed to make bar background transparent: {err:?}");
+                return;
+            }
+
+            self.window
+                .style_context()
+                .add_provider(&provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        }
+    }
+
+    /// Shows or hides the bar window as the focused workspace changes,
+    /// according to the `show_if_workspace` and `hide_if_workspace` options.
+    #[cfg(feature = "workspaces")]
+    fn setup_workspace_visibility(
+        &self,
+        show_if: Option<Vec<String>>,
+        hide_if: Option<Vec<String>>,
+    ) {
+        use crate::event_bus::{self, workspace_visible};
+        use crate::glib_recv;
+
+        if show_if.is_none() && hide_if.is_none() {
+            return;
+        }
+
+        event_bus::watch_workspaces(&self.ironbar);
+
+        let bus = Ironbar::event_bus();
+        let window = self.window.downgrade();
+
+        let update = move |workspace: Option<&str>| {
+            let Some(window) = window.upgrade() else {
+                return;
+            };
+
+            let visible = workspace_visible(show_if.as_deref(), hide_if.as_deref(), workspace);
+            if visible != window.is_visible() {
+                window.set_visible(visible);
+            }
+        };
+
+        update(bus.state().workspace.as_deref());
+
+        glib_recv!(bus.subscribe(), event => {
+            if let Some(workspace) = event.workspace() {
+                update(Some(workspace));
+            }
+        });
+    }
+
     fn setup_autohide(window: &ApplicationWindow, hotspot_window: &Window, timeout: u64) {
         hotspot_window.hide();
 
@@ -245,6 +422,133 @@ impl Bar {
         }
     }
 
+    /// Creates a small window in the start corner of the bar's edge,
+    /// which is shown while the bar is hidden.
+    /// Clicking it opens a menu to show the bar, reload or quit.
+    fn setup_recovery_button(&self, margin: MarginConfig, monitor: &Monitor) -> Window {
+        use gtk_layer_shell::Edge;
+
+        let recovery_window = Window::new(WindowType::Toplevel);
+        recovery_window.set_decorated(false);
+        recovery_window.set_widget_name("recovery");
+
+        self.setup_layer_shell(
+            &recovery_window,
+            false,
+            false,
+            margin,
+            gtk_layer_shell::Layer::Top,
+            monitor,
+        );
+
+        if self.position.orientation() == Orientation::Horizontal {
+            recovery_window.set_anchor(Edge::Left, true);
+        } else {
+            recovery_window.set_anchor(Edge::Top, true);
+        }
+
+        let button = Button::with_label("󰍜");
+        button.add_class("recovery-button");
+        recovery_window.add(&button);
+
+        let menu = Menu::new();
+
+        let show = MenuItem::with_label("Show bar");
+        {
+            let window = self.window.clone();
+            show.connect_activate(move |_| window.show());
+        }
+
+        let reload = MenuItem::with_label("Reload");
+        {
+            let ironbar = self.ironbar.clone();
+            let app = self.window.application().expect("to exist");
+            reload.connect_activate(move |_| {
+                let ironbar = ironbar.clone();
+                let app = app.clone();
+
+                // reloading destroys this menu, so wait until the signal has finished
+                glib::idle_add_local_once(move || crate::reload(&ironbar, &app));
+            });
+        }
+
+        let quit = MenuItem::with_label("Quit");
+        {
+            let app = self.window.application().expect("to exist");
+            quit.connect_activate(move |_| {
+                info!("Shutting down");
+                app.quit();
+            });
+        }
+
+        menu.append(&show);
+        menu.append(&reload);
+        menu.append(&quit);
+        menu.show_all();
+
+        button.connect_clicked(move |button| {
+            menu.popup_at_widget(button, Gravity::SouthWest, Gravity::NorthWest, None);
+        });
+
+        // the bar is hidden as it closes,
+        // which must not show the button again
+        let closing = Rc::new(Cell::new(false));
+
+        {
+            let recovery_window = recovery_window.clone();
+            let closing = closing.clone();
+            self.window.connect_delete_event(move |_, _| {
+                closing.set(true);
+                recovery_window.close();
+                Propagation::Proceed
+            });
+        }
+
+        {
+            let recovery_window = recovery_window.clone();
+            self.window.connect_hide(move |_| {
+                if !closing.get() {
+                    recovery_window.show_all();
+                }
+            });
+        }
+
+        {
+            let recovery_window = recovery_window.clone();
+            self.window.connect_show(move |_| recovery_window.hide());
+        }
+
+        recovery_window
+    }
+
+    /// Keeps the `bar_geometry_<name>` ironvar up to date
+    /// with the bar's geometry each time it is resized.
+    #[cfg(feature = "ipc")]
+    fn setup_geometry_var(&self) {
+        let key: Box<str> = format!("bar_geometry_{}", self.name).into();
+
+        let name = self.name.clone();
+        let monitor_name = self.monitor_name.clone();
+        let monitor_size = self.monitor_size;
+        let position = self.position;
+
+        let last = RefCell::new(String::new());
+
+        self.window.connect_size_allocate(move |window, _| {
+            let value = geometry(window, &name, &monitor_name, monitor_size, position).to_string();
+
+            if *last.borrow() != value {
+                last.replace(value.clone());
+
+                let variable_manager = Ironbar::variable_manager();
+                let mut variable_manager = write_lock!(variable_manager);
+                if let Err(err) = variable_manager.set(key.clone(), value) {
+                    debug!("Not setting geometry variable for bar '{name}': {err}");
+                }
+            }
+        });
+    }
+
     /// Loads the configured modules onto a bar.
     fn load_modules(
         &self,
@@ -282,21 +586,27 @@ impl Bar {
         let popup = Rc::new(popup);
 
         if let Some(modules) = config.start {
-            self.content.add(&self.start);
+            self.sides.add(&self.start);
 
             let info = info!(ModuleLocation::Left);
             add_modules(&self.start, modules, &info, &self.ironbar, &popup)?;
         }
 
         if let Some(modules) = config.center {
-            self.content.set_center_widget(Some(&self.center));
+            // unanchored bars are sized to their content,
+            // so the center group must be part of it.
+            if config.anchor_to_edges {
+                self.layout.add_overlay(&self.center);
+            } else {
+                self.sides.set_center_widget(Some(&self.center));
+            }
 
             let info = info!(ModuleLocation::Center);
             add_modules(&self.center, modules, &info, &self.ironbar, &popup)?;
         }
 
         if let Some(modules) = config.end {
-            self.content.pack_end(&self.end, false, true, 0);
+            self.sides.pack_end(&self.end, false, true, 0);
 
             let info = info!(ModuleLocation::Right);
             add_modules(&self.end, modules, &info, &self.ironbar, &popup)?;
@@ -307,6 +617,156 @@ impl Bar {
         Ok(result)
     }
 
+    /// Positions the center group of an anchored bar so that it is centered on the output,
+    /// regardless of the size of the start and end groups.
+    ///
+    /// If the center group would overlap either side,
+    /// it is pushed away from it as far as the space allows.
+    fn setup_center(&self, margin: MarginConfig) {
+        let orientation = self.position.orientation();
+        let start = self.start.clone();
+        let end = self.end.clone();
+
+        // the bar is inset by its margins,
+        // so the center of the output is offset from the center of the bar.
+        let offset = if orientation == Orientation::Horizontal {
+            (margin.right - margin.left) / 2
+        } else {
+            (margin.bottom - margin.top) / 2
+        };
+
+        // groups without any modules are never added to the bar.
+        // the natural size is used as spacers can expand a group to fill the bar.
+        let natural_size = move |group: &gtk::Box| {
+            if group.parent().is_none() || !group.is_visible() {
+                0
+            } else if orientation == Orientation::Horizontal {
+                group.preferred_width().1
+            } else {
+                group.preferred_height().1
+            }
+        };
+
+        self.layout
+            .connect_get_child_position(move |layout, widget| {
+                let (width, height) = (layout.allocated_width(), layout.allocated_height());
+
+                let (length, size) = if orientation == Orientation::Horizontal {
+                    (width, widget.preferred_width().1)
+                } else {
+                    (height, widget.preferred_height().1)
+                };
+
+                let size = size.min(length);
+                let min = natural_size(&start);
+                let max = length - natural_size(&end) - size;
+
+                let position = (length / 2 + offset - size / 2)
+                    .min(max)
+                    .max(min)
+                    .min(length - size);
+
+                let rect = if orientation == Orientation::Horizontal {
+                    gtk::gdk::Rectangle::new(position, 0, size, height)
+                } else {
+                    gtk::gdk::Rectangle::new(0, position, width, size)
+                };
+
+                Some(rect)
+            });
+    }
+
+    /// Adds the overflow button to the end of the bar.
+    fn setup_overflow(&self, popup: &Rc<Popup>) -> Overflow {
+        if self.end.parent().is_none() {
+            self.sides.pack_end(&self.end, false, true, 0);
+        }
+
+        Overflow::new(
+            &[&self.start, &self.center, &self.end],
+            &self.end,
+            popup.clone(),
+        )
+    }
+
+    /// Sets up collapsing and expanding modules with the `auto` display mode,
+    /// and moving modules into and out of the overflow popup,
+    /// as the space required by the bar's content changes.
+    ///
+    /// Modules are collapsed to icons before being moved into the overflow,
+    /// and brought back from the overflow before being expanded.
+    fn setup_adaptive_width(&self, margin: MarginConfig, overflow: Option<Overflow>) {
+        let controllers = [&self.start, &self.center, &self.end]
+            .iter()
+            .flat_map(|container| container.children())
+            .filter_map(|widget| DisplayModeController::get(&widget))
+            .filter(DisplayModeController::is_auto)
+            .collect::<Vec<_>>();
+
+        if controllers.is_empty() && overflow.is_none() {
+            return;
+        }
+
+        let orientation = self.position.orientation();
+        let available = if orientation == Orientation::Horizontal {
+            self.monitor_size.0 - margin.left - margin.right
+        } else {
+            self.monitor_size.1 - margin.top - margin.bottom
+        };
+
+        let groups = [self.start.clone(), self.center.clone(), self.end.clone()];
+        let pending = Rc::new(Cell::new(false));
+
+        self.layout.connect_size_allocate(move |_, _| {
+            // resizing here would trigger another allocation,
+            // so wait until the current one has finished.
+            if pending.replace(true) {
+                return;
+            }
+
+            let groups = groups.clone();
+            let controllers = controllers.clone();
+            let overflow = overflow.clone();
+            let pending = pending.clone();
+
+            glib::idle_add_local_once(move || {
+                pending.set(false);
+
+                // the center group may be overlaid, so is not always included in the content size.
+                let required = groups
+                    .iter()
+                    .filter(|group| group.is_visible())
+                    .map(|group| {
+                        if orientation == Orientation::Horizontal {
+                            group.preferred_width().1
+                        } else {
+                            group.preferred_height().1
+                        }
+                    })
+                    .sum::<i32>();
+
+                if required > available {
+                    let collapsed =
+                        display_mode::fit(&controllers, required, available, orientation);
+
+                    if !collapsed {
+                        if let Some(overflow) = &overflow {
+                            overflow.collapse();
+                        }
+                    }
+                } else {
+                    let overflowing = overflow
+                        .as_ref()
+                        .is_some_and(|overflow| overflow.expand(required, available));
+
+                    if !overflowing {
+                        display_mode::fit(&controllers, required, available, orientation);
+                    }
+                }
+            });
+        });
+    }
+
     fn show(&self, include_window: bool) {
         debug!("Showing bar: {}", self.name);
 
@@ -315,6 +775,8 @@ impl Bar {
         self.start.show();
         self.center.show();
         self.end.show();
+        self.sides.show();
+        self.layout.show();
         self.content.show();
 
         if include_window {
@@ -344,11 +806,39 @@ impl Bar {
         self.window.is_visible()
     }
 
+    /// Gets the current size and position of the bar on its output.
+    pub fn geometry(&self) -> BarGeometry {
+        geometry(
+            &self.window,
+            &self.name,
+            &self.monitor_name,
+            self.monitor_size,
+            self.position,
+        )
+    }
+
     /// Sets the window visibility status
     pub fn set_visible(&self, visible: bool) {
         self.window.set_visible(visible);
     }
 
+    /// The config the bar was created from,
+    /// if it has an `on_lid_close` action.
+    #[cfg(feature = "lid")]
+    pub fn lid_config(&self) -> Option<&BarConfig> {
+        self.lid_config.as_deref()
+    }
+
+    /// Closes the bar and its popup windows.
+    #[cfg(feature = "lid")]
+    pub fn close(&self) {
+        if let Inner::Loaded { popup } = &self.inner {
+            popup.window.close();
+        }
+
+        self.window.close();
+    }
+
     pub fn set_exclusive(&self, exclusive: bool) {
         if exclusive {
             self.window.auto_exclusive_zone_enable();
@@ -358,6 +848,62 @@ impl Bar {
     }
 }
 
+/// Calculates the geometry of a bar window from its size and layer-shell anchors.
+fn geometry(
+    window: &ApplicationWindow,
+    name: &str,
+    monitor_name: &str,
+    monitor_size: (i32, i32),
+    position: BarPosition,
+) -> BarGeometry {
+    use gtk_layer_shell::Edge;
+
+    let allocation = window.allocation();
+    let (width, height) = (allocation.width(), allocation.height());
+
+    let margin = MarginConfig {
+        top: window.layer_shell_margin(Edge::Top),
+        bottom: window.layer_shell_margin(Edge::Bottom),
+        left: window.layer_shell_margin(Edge::Left),
+        right: window.layer_shell_margin(Edge::Right),
+    };
+
+    // a window anchored to neither opposite edge is centered between them
+    let offset = |(start, end): (Edge, Edge),
+                  (start_margin, end_margin): (i32, i32),
+                  size: i32,
+                  output_size: i32| {
+        match (window.is_anchor(start), window.is_anchor(end)) {
+            (true, _) => start_margin,
+            (false, true) => output_size - size - end_margin,
+            (false, false) => (output_size - size) / 2,
+        }
+    };
+
+    BarGeometry {
+        name: name.to_string(),
+        output: monitor_name.to_string(),
+        position,
+        x: offset(
+            (Edge::Left, Edge::Right),
+            (margin.left, margin.right),
+            width,
+            monitor_size.0,
+        ),
+        y: offset(
+            (Edge::Top, Edge::Bottom),
+            (margin.top, margin.bottom),
+            height,
+            monitor_size.1,
+        ),
+        width,
+        height,
+        margin,
+        exclusive_zone: window.exclusive_zone(),
+        visible: window.is_visible(),
+    }
+}
+
 /// Creates a `gtk::Box` container to place widgets inside.
 fn create_container(name: &str, orientation: Orientation) -> gtk::Box {
     let container = gtk::Box::builder()
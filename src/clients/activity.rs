@@ -0,0 +1,334 @@
+//! Daily activity statistics for focus mode.
+//!
+//! Statistics are stored locally, as one file per day
+//! in the `ironbar/activity` state directory.
+
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+/// Activity totals for a single day.
+/// All times are in seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyStats {
+    pub date: NaiveDate,
+    /// Total time spent active, rather than idle.
+    pub active: u64,
+    /// The longest period of continuous activity.
+    pub longest_streak: u64,
+    /// The number of focus sessions which ran to completion.
+    pub focus_sessions: u32,
+    /// Total time spent in focus sessions.
+    pub focus_time: u64,
+    /// Time each application spent focused while active, by app ID.
+    pub apps: HashMap<String, u64>,
+}
+
+impl DailyStats {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            active: 0,
+            longest_streak: 0,
+            focus_sessions: 0,
+            focus_time: 0,
+            apps: HashMap::new(),
+        }
+    }
+
+    /// Gets the `count` applications with the most foreground time,
+    /// most used first.
+    pub fn top_apps(&self, count: usize) -> Vec<(&str, u64)> {
+        let mut apps = self
+            .apps
+            .iter()
+            .map(|(app, time)| (app.as_str(), *time))
+            .collect::<Vec<_>>();
+
+        apps.sort_by(|(app_a, a), (app_b, b)| b.cmp(a).then_with(|| app_a.cmp(app_b)));
+        apps.truncate(count);
+        apps
+    }
+
+    /// Loads the stats for a day, or empty stats if there are none.
+    fn load(date: NaiveDate) -> Self {
+        Self::path(date)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map_or_else(|| Self::new(date), |contents| Self::parse(date, &contents))
+    }
+
+    /// Parses stats from tab-separated lines.
+    /// Unknown or malformed lines are skipped.
+    fn parse(date: NaiveDate, contents: &str) -> Self {
+        let mut stats = Self::new(date);
+
+        for line in contents.lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let value = |index: usize| fields.get(index).and_then(|value| value.parse().ok());
+
+            match fields.first() {
+                Some(&"active") => stats.active = value(1).unwrap_or_default(),
+                Some(&"longest_streak") => stats.longest_streak = value(1).unwrap_or_default(),
+                Some(&"focus_sessions") => {
+                    stats.focus_sessions = fields
+                        .get(1)
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or_default();
+                }
+                Some(&"focus_time") => stats.focus_time = value(1).unwrap_or_default(),
+                Some(&"app") => {
+                    if let (Some(app), Some(time)) = (fields.get(1), value(2)) {
+                        stats.apps.insert((*app).to_string(), time);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        stats
+    }
+
+    fn serialize(&self) -> String {
+        let mut lines = vec![
+            format!("active\t{}", self.active),
+            format!("longest_streak\t{}", self.longest_streak),
+            format!("focus_sessions\t{}", self.focus_sessions),
+            format!("focus_time\t{}", self.focus_time),
+        ];
+
+        let mut apps = self.apps.iter().collect::<Vec<_>>();
+        apps.sort();
+
+        lines.extend(
+            apps.into_iter()
+                .map(|(app, time)| format!("app\t{app}\t{time}")),
+        );
+        lines.join("\n")
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path(self.date) else {
+            warn!("Failed to locate state directory, activity statistics will not be saved");
+            return;
+        };
+
+        let res = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, self.serialize()));
+
+        if let Err(err) = res {
+            error!("Failed to save activity statistics: {err:?}");
+        }
+    }
+
+    fn path(date: NaiveDate) -> Option<PathBuf> {
+        dirs::state_dir().map(|dir| {
+            dir.join("ironbar")
+                .join("activity")
+                .join(date.format("%Y-%m-%d").to_string())
+        })
+    }
+}
+
+/// A snapshot of the tracker, for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    pub today: DailyStats,
+    /// Whether collection has been paused from the popup.
+    pub paused: bool,
+}
+
+/// Accumulates time into today's stats
+/// as the user's idle state, focused application and focus sessions change.
+#[derive(Debug)]
+pub struct Tracker {
+    stats: DailyStats,
+    /// Whether to record time per application.
+    track_apps: bool,
+    paused: bool,
+
+    idle: bool,
+    /// The app ID of the focused application.
+    app: Option<String>,
+    in_focus_session: bool,
+    /// Length of the current period of activity, in seconds.
+    streak: u64,
+
+    last_update: Instant,
+}
+
+impl Tracker {
+    pub fn new(today: NaiveDate, track_apps: bool) -> Self {
+        Self {
+            stats: DailyStats::load(today),
+            track_apps,
+            paused: Self::paused_path().is_some_and(|path| path.exists()),
+            idle: false,
+            app: None,
+            in_focus_session: false,
+            streak: 0,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Adds the time since the last update to the stats for `today`,
+    /// saving and replacing the previous day's stats if the date has changed.
+    pub fn update(&mut self, now: Instant, today: NaiveDate) {
+        // whole seconds are counted, and the remainder carried over to the next update
+        let elapsed = now.saturating_duration_since(self.last_update).as_secs();
+        self.last_update += Duration::from_secs(elapsed);
+
+        if today != self.stats.date {
+            self.stats.save();
+            self.stats = DailyStats::load(today);
+            self.streak = 0;
+        }
+
+        if self.paused {
+            return;
+        }
+
+        if self.in_focus_session {
+            self.stats.focus_time += elapsed;
+        }
+
+        if self.idle {
+            return;
+        }
+
+        self.stats.active += elapsed;
+        self.streak += elapsed;
+        self.stats.longest_streak = self.stats.longest_streak.max(self.streak);
+
+        if let (true, Some(app)) = (self.track_apps, &self.app) {
+            *self.stats.apps.entry(app.clone()).or_default() += elapsed;
+        }
+    }
+
+    pub fn set_idle(&mut self, now: Instant, today: NaiveDate, idle: bool) {
+        self.update(now, today);
+        self.idle = idle;
+
+        if idle {
+            self.streak = 0;
+        }
+    }
+
+    pub fn set_app(&mut self, now: Instant, today: NaiveDate, app: Option<String>) {
+        self.update(now, today);
+        self.app = app;
+    }
+
+    pub fn start_focus(&mut self, now: Instant, today: NaiveDate) {
+        self.update(now, today);
+        self.in_focus_session = true;
+    }
+
+    pub fn end_focus(&mut self, now: Instant, today: NaiveDate, completed: bool) {
+        self.update(now, today);
+        self.in_focus_session = false;
+
+        if completed && !self.paused {
+            self.stats.focus_sessions += 1;
+        }
+    }
+
+    /// Pauses or resumes collection.
+    /// This is remembered between restarts.
+    pub fn set_paused(&mut self, now: Instant, today: NaiveDate, paused: bool) {
+        self.update(now, today);
+        self.paused = paused;
+
+        let Some(path) = Self::paused_path() else {
+            return;
+        };
+
+        let res = if paused {
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(&path, ""))
+        } else if path.exists() {
+            fs::remove_file(&path)
+        } else {
+            Ok(())
+        };
+
+        if let Err(err) = res {
+            error!("Failed to save activity tracking state: {err:?}");
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn save(&self) {
+        self.stats.save();
+    }
+
+    pub fn activity(&self) -> Activity {
+        Activity {
+            today: self.stats.clone(),
+            paused: self.paused,
+        }
+    }
+
+    fn paused_path() -> Option<PathBuf> {
+        dirs::state_dir().map(|dir| dir.join("ironbar").join("activity").join("paused"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+
+        let mut stats = DailyStats::new(date);
+        stats.active = 5400;
+        stats.longest_streak = 2700;
+        stats.focus_sessions = 2;
+        stats.focus_time = 3000;
+        stats.apps.insert(String::from("firefox"), 1800);
+        stats.apps.insert(String::from("org.gnome.Nautilus"), 60);
+
+        assert_eq!(DailyStats::parse(date, &stats.serialize()), stats);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut tracker = Tracker {
+            stats: DailyStats::new(date),
+            track_apps: true,
+            paused: false,
+            idle: false,
+            app: None,
+            in_focus_session: false,
+            streak: 0,
+            last_update: start,
+        };
+
+        tracker.set_app(at(0), date, Some(String::from("firefox")));
+        tracker.set_idle(at(600), date, true);
+        tracker.set_idle(at(900), date, false);
+        tracker.set_app(at(1000), date, Some(String::from("kitty")));
+        tracker.update(at(1200), date);
+
+        let stats = tracker.activity().today;
+
+        assert_eq!(stats.active, 900);
+        assert_eq!(stats.longest_streak, 600);
+        assert_eq!(stats.apps["firefox"], 700);
+        assert_eq!(stats.top_apps(1), [("firefox", 700)]);
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Path to the kernel's backlight device directory.
+const BACKLIGHT_PATH: &str = "/sys/class/backlight";
+
+/// A screen backlight device, read through sysfs.
+///
+/// The kernel does not emit file change events for backlight values,
+/// so consumers must poll for changes.
+#[derive(Debug, Clone)]
+pub struct Backlight {
+    path: PathBuf,
+}
+
+impl Backlight {
+    /// Gets the first backlight device, if there is one.
+    pub fn find() -> Option<Self> {
+        fs::read_dir(BACKLIGHT_PATH)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| Self { path: entry.path() })
+            .next()
+    }
+
+    /// Gets the kernel name of the device.
+    pub fn name(&self) -> &str {
+        self.path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    fn read(&self, file: &str) -> Option<u32> {
+        fs::read_to_string(self.path.join(file))
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Gets the maximum raw brightness value of the device.
+    pub fn max_brightness(&self) -> Option<u32> {
+        self.read("max_brightness").filter(|&max| max > 0)
+    }
+
+    /// Gets the current brightness, as a fraction of its maximum.
+    pub fn brightness(&self) -> Option<f64> {
+        let brightness = self.read("brightness")?;
+        let max = self.max_brightness()?;
+
+        Some(f64::from(brightness) / f64::from(max))
+    }
+
+    /// Sets the brightness to a fraction of its maximum.
+    ///
+    /// This goes through logind,
+    /// so does not require write access to sysfs.
+    #[cfg(any(feature = "brightness", feature = "media_keys"))]
+    pub async fn set_brightness(&self, fraction: f64) -> color_eyre::Result<()> {
+        let max = self
+            .max_brightness()
+            .ok_or_else(|| color_eyre::Report::msg("Failed to read max brightness"))?;
+
+        let value = (fraction.clamp(0.0, 1.0) * f64::from(max)).round() as u32;
+
+        let dbus = zbus::Connection::system().await?;
+        let session = crate::clients::logind::SessionProxy::new(&dbus).await?;
+        session
+            .set_brightness("backlight", self.name(), value)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use color_eyre::Result;
+use std::collections::HashMap;
+use zbus::fdo::ObjectManagerProxy;
+use zbus::names::OwnedInterfaceName;
+use zbus::zvariant::{ObjectPath, OwnedValue};
+use zbus::{dbus_proxy, CacheProperties, Connection, MatchRule, MessageStream, MessageType};
+
+const SERVICE: &str = "org.bluez";
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+
+#[dbus_proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter {
+    #[dbus_proxy(property)]
+    fn set_powered(&self, value: bool) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device {
+    fn connect(&self) -> zbus::Result<()>;
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn set_trusted(&self, value: bool) -> zbus::Result<()>;
+}
+
+/// A paired Bluetooth device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// D-Bus object path, used to identify the device.
+    pub path: String,
+    pub name: String,
+    /// Freedesktop icon name describing the device type,
+    /// such as `audio-headset`.
+    pub icon: Option<String>,
+    pub connected: bool,
+    pub trusted: bool,
+    /// Battery percentage, if the device reports it.
+    pub battery: Option<u8>,
+}
+
+/// The state of the default adapter and its paired devices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// D-Bus object path of the adapter.
+    pub adapter: String,
+    pub powered: bool,
+    /// Paired devices, sorted by name.
+    pub devices: Vec<Device>,
+}
+
+/// Client for the `BlueZ` daemon.
+#[derive(Debug, Clone)]
+pub struct Client {
+    connection: Connection,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let connection = Connection::system().await?;
+        Ok(Self { connection })
+    }
+
+    /// Gets the state of the first adapter,
+    /// or `None` if there is no adapter.
+    ///
+    /// This fails if `BlueZ` is not running.
+    pub async fn state(&self) -> Result<Option<State>> {
+        let object_manager = ObjectManagerProxy::builder(&self.connection)
+            .destination(SERVICE)?
+            .path("/")?
+            .build()
+            .await?;
+
+        let objects = object_manager.get_managed_objects().await?;
+
+        let mut adapters = objects
+            .iter()
+            .filter_map(|(path, interfaces)| {
+                let adapter = interface(interfaces, ADAPTER_INTERFACE)?;
+                Some((path.as_str(), adapter))
+            })
+            .collect::<Vec<_>>();
+
+        // adapters are named hci0, hci1... so the first is the default
+        adapters.sort_by_key(|(path, _)| *path);
+
+        let Some((adapter_path, adapter)) = adapters.first() else {
+            return Ok(None);
+        };
+
+        let mut devices = objects
+            .iter()
+            .filter_map(|(path, interfaces)| {
+                let device = interface(interfaces, DEVICE_INTERFACE)?;
+
+                let adapter = string(device, "Adapter");
+                let paired = boolean(device, "Paired");
+
+                if adapter.as_deref() != Some(*adapter_path) || !paired {
+                    return None;
+                }
+
+                let name = string(device, "Alias")
+                    .or_else(|| string(device, "Name"))
+                    .or_else(|| string(device, "Address"))
+                    .unwrap_or_default();
+
+                let battery = interface(interfaces, BATTERY_INTERFACE)
+                    .and_then(|battery| battery.get("Percentage"))
+                    .and_then(|value| value.downcast_ref::<u8>())
+                    .copied();
+
+                Some(Device {
+                    path: path.to_string(),
+                    name,
+                    icon: string(device, "Icon"),
+                    connected: boolean(device, "Connected"),
+                    trusted: boolean(device, "Trusted"),
+                    battery,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Some(State {
+            adapter: (*adapter_path).to_string(),
+            powered: boolean(adapter, "Powered"),
+            devices,
+        }))
+    }
+
+    /// Gets a stream of all signals sent by `BlueZ`,
+    /// which are emitted whenever an adapter or device changes.
+    pub async fn changes(&self) -> Result<MessageStream> {
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(SERVICE)?
+            .build();
+
+        Ok(MessageStream::for_match_rule(rule, &self.connection, None).await?)
+    }
+
+    async fn adapter(&self, path: &str) -> Result<AdapterProxy<'static>> {
+        let adapter = AdapterProxy::builder(&self.connection)
+            .path(path.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        Ok(adapter)
+    }
+
+    async fn device(&self, path: &str) -> Result<DeviceProxy<'static>> {
+        let device = DeviceProxy::builder(&self.connection)
+            .path(path.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        Ok(device)
+    }
+
+    pub async fn set_powered(&self, adapter: &str, powered: bool) -> Result<()> {
+        self.adapter(adapter).await?.set_powered(powered).await?;
+        Ok(())
+    }
+
+    pub async fn connect(&self, device: &str) -> Result<()> {
+        self.device(device).await?.connect().await?;
+        Ok(())
+    }
+
+    pub async fn disconnect(&self, device: &str) -> Result<()> {
+        self.device(device).await?.disconnect().await?;
+        Ok(())
+    }
+
+    pub async fn set_trusted(&self, device: &str, trusted: bool) -> Result<()> {
+        self.device(device).await?.set_trusted(trusted).await?;
+        Ok(())
+    }
+}
+
+/// Gets the properties of an interface on an object.
+fn interface<'a>(
+    interfaces: &'a HashMap<OwnedInterfaceName, HashMap<String, OwnedValue>>,
+    name: &str,
+) -> Option<&'a HashMap<String, OwnedValue>> {
+    interfaces
+        .iter()
+        .find(|(interface, _)| interface.as_str() == name)
+        .map(|(_, properties)| properties)
+}
+
+fn string(properties: &HashMap<String, OwnedValue>, name: &str) -> Option<String> {
+    properties
+        .get(name)
+        .and_then(|value| value.downcast_ref::<str>())
+        .map(ToString::to_string)
+        .or_else(|| {
+            // object paths, such as `Adapter`, are not strings
+            properties
+                .get(name)
+                .and_then(|value| value.downcast_ref::<ObjectPath>())
+                .map(ToString::to_string)
+        })
+}
+
+fn boolean(properties: &HashMap<String, OwnedValue>, name: &str) -> bool {
+    properties
+        .get(name)
+        .and_then(|value| value.downcast_ref::<bool>())
+        .copied()
+        .unwrap_or_default()
+}
@@ -0,0 +1,231 @@
+use super::{send, Room};
+use color_eyre::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Only the events needed to name rooms are requested,
+/// which keeps sync responses small.
+const SYNC_FILTER: &str = r#"{
+    "presence": { "types": [] },
+    "account_data": { "types": [] },
+    "room": {
+        "state": { "types": ["m.room.name", "m.room.canonical_alias"], "lazy_load_members": true },
+        "timeline": { "types": ["m.room.name", "m.room.canonical_alias"], "limit": 1 },
+        "ephemeral": { "types": [] },
+        "account_data": { "types": [] }
+    }
+}"#;
+
+#[derive(Debug, Deserialize)]
+struct SyncResponse {
+    next_batch: String,
+    #[serde(default)]
+    rooms: SyncRooms,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SyncRooms {
+    #[serde(default)]
+    join: HashMap<String, JoinedRoom>,
+    #[serde(default)]
+    leave: HashMap<String, serde::de::IgnoredAny>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JoinedRoom {
+    #[serde(default)]
+    state: Events,
+    #[serde(default)]
+    timeline: Events,
+    unread_notifications: Option<UnreadNotifications>,
+    summary: Option<RoomSummary>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Events {
+    #[serde(default)]
+    events: Vec<StateEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    content: EventContent,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EventContent {
+    name: Option<String>,
+    alias: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnreadNotifications {
+    #[serde(default)]
+    notification_count: u32,
+    #[serde(default)]
+    highlight_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoomSummary {
+    #[serde(rename = "m.heroes", default)]
+    heroes: Option<Vec<String>>,
+}
+
+/// The last known state of a joined room.
+#[derive(Debug, Default)]
+struct RoomState {
+    name: Option<String>,
+    alias: Option<String>,
+    /// Members used to name rooms without a name or alias, such as direct messages.
+    heroes: Vec<String>,
+    notifications: u32,
+    highlights: u32,
+}
+
+impl RoomState {
+    fn display_name(&self, id: &str) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.alias.clone())
+            .or_else(|| {
+                // user IDs are in the form `@name:server`
+                let heroes = self
+                    .heroes
+                    .iter()
+                    .map(|hero| {
+                        hero.trim_start_matches('@')
+                            .split(':')
+                            .next()
+                            .unwrap_or(hero)
+                    })
+                    .collect::<Vec<_>>();
+
+                (!heroes.is_empty()).then(|| heroes.join(", "))
+            })
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+/// A Matrix account, accessed through the client-server sync API.
+///
+/// The first sync fetches every joined room.
+/// Later syncs only return rooms which have changed,
+/// so room state is kept between calls.
+#[derive(Debug)]
+pub struct Account {
+    /// Base URL of the homeserver.
+    homeserver: String,
+    token: String,
+    /// The `next_batch` token of the last sync.
+    since: Option<String>,
+    rooms: HashMap<String, RoomState>,
+}
+
+impl Account {
+    pub fn new(homeserver: &str, token: String) -> Self {
+        Self {
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            token,
+            since: None,
+            rooms: HashMap::new(),
+        }
+    }
+
+    pub async fn rooms(&mut self, http: &reqwest::Client) -> Result<Vec<Room>> {
+        let mut query = vec![("filter", SYNC_FILTER), ("timeout", "0")];
+        if let Some(since) = &self.since {
+            query.push(("since", since.as_str()));
+        }
+
+        let request = http
+            .get(format!("{}/_matrix/client/v3/sync", self.homeserver))
+            .bearer_auth(&self.token)
+            .query(&query);
+
+        let body = send(request).await?;
+        let response: SyncResponse = serde_json::from_str(&body)?;
+
+        for id in response.rooms.leave.keys() {
+            self.rooms.remove(id);
+        }
+
+        for (id, joined) in response.rooms.join {
+            let room = self.rooms.entry(id).or_default();
+
+            for event in joined
+                .state
+                .events
+                .into_iter()
+                .chain(joined.timeline.events)
+            {
+                match event.event_type.as_str() {
+                    "m.room.name" => room.name = event.content.name.filter(|name| !name.is_empty()),
+                    "m.room.canonical_alias" => room.alias = event.content.alias,
+                    _ => {}
+                }
+            }
+
+            if let Some(heroes) = joined.summary.and_then(|summary| summary.heroes) {
+                room.heroes = heroes;
+            }
+
+            if let Some(unread) = joined.unread_notifications {
+                room.notifications = unread.notification_count;
+                room.highlights = unread.highlight_count;
+            }
+        }
+
+        self.since = Some(response.next_batch);
+
+        let rooms = self
+            .rooms
+            .iter()
+            .map(|(id, room)| Room {
+                id: id.clone(),
+                name: room.display_name(id),
+                unread: room.notifications,
+                highlights: room.highlights,
+            })
+            .collect();
+
+        Ok(rooms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_name() {
+        let id = "!abc:example.org";
+
+        let room = RoomState {
+            name: Some(String::from("Ironbar")),
+            alias: Some(String::from("#ironbar:example.org")),
+            ..RoomState::default()
+        };
+        assert_eq!(room.display_name(id), "Ironbar");
+
+        let room = RoomState {
+            alias: Some(String::from("#ironbar:example.org")),
+            ..RoomState::default()
+        };
+        assert_eq!(room.display_name(id), "#ironbar:example.org");
+
+        let room = RoomState {
+            heroes: vec![
+                String::from("@alice:example.org"),
+                String::from("@bob:matrix.org"),
+            ],
+            ..RoomState::default()
+        };
+        assert_eq!(room.display_name(id), "alice, bob");
+
+        assert_eq!(RoomState::default().display_name(id), id);
+    }
+}
@@ -0,0 +1,50 @@
+use color_eyre::Result;
+
+pub mod matrix;
+pub mod weechat;
+
+/// A buffer, channel or room with unread messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Room {
+    /// Service-specific identifier.
+    pub id: String,
+    pub name: String,
+    /// The number of unread messages.
+    pub unread: u32,
+    /// The number of unread messages which mention the user.
+    pub highlights: u32,
+}
+
+/// An account on a chat service.
+#[derive(Debug)]
+pub enum Account {
+    WeeChat(weechat::Account),
+    Matrix(matrix::Account),
+}
+
+impl Account {
+    /// Gets the rooms with unread messages,
+    /// ordered by highlights and then by unread messages.
+    pub async fn rooms(&mut self, http: &reqwest::Client) -> Result<Vec<Room>> {
+        let mut rooms = match self {
+            Self::WeeChat(account) => account.rooms(http).await?,
+            Self::Matrix(account) => account.rooms(http).await?,
+        };
+
+        rooms.retain(|room| room.unread > 0 || room.highlights > 0);
+        rooms.sort_by(|a, b| {
+            b.highlights
+                .cmp(&a.highlights)
+                .then(b.unread.cmp(&a.unread))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(rooms)
+    }
+}
+
+/// Sends `request`, returning the response body
+/// if the request was successful.
+async fn send(request: reqwest::RequestBuilder) -> Result<String> {
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
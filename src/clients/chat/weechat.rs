@@ -0,0 +1,98 @@
+use super::{send, Room};
+use color_eyre::Result;
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Positions of the message counts in a hotlist entry.
+const HOTLIST_MESSAGE: usize = 1;
+const HOTLIST_PRIVATE: usize = 2;
+const HOTLIST_HIGHLIGHT: usize = 3;
+
+#[derive(Debug, Deserialize)]
+struct Buffer {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    short_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HotlistEntry {
+    buffer_id: u64,
+    /// Counts of low priority messages (such as joins),
+    /// messages, private messages and highlights.
+    count: [u32; 4],
+}
+
+/// A WeeChat instance, accessed through the relay `api` protocol.
+///
+/// This requires WeeChat 4.3 or later.
+#[derive(Debug)]
+pub struct Account {
+    /// Base URL of the relay.
+    url: String,
+    password: Option<String>,
+}
+
+impl Account {
+    pub fn new(url: &str, password: Option<String>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            password,
+        }
+    }
+
+    fn request(&self, http: &reqwest::Client, path: &str) -> RequestBuilder {
+        let request = http.get(format!("{}/api{path}", self.url));
+
+        match &self.password {
+            Some(password) => request.basic_auth("plain", Some(password)),
+            None => request,
+        }
+    }
+
+    pub async fn rooms(&self, http: &reqwest::Client) -> Result<Vec<Room>> {
+        let body = send(self.request(http, "/hotlist")).await?;
+        let hotlist: Vec<HotlistEntry> = serde_json::from_str(&body)?;
+
+        if hotlist.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let body = send(self.request(http, "/buffers")).await?;
+        let buffers: Vec<Buffer> = serde_json::from_str(&body)?;
+        let buffers = buffers
+            .into_iter()
+            .map(|buffer| (buffer.id, buffer))
+            .collect::<HashMap<_, _>>();
+
+        let rooms = hotlist
+            .into_iter()
+            .map(|entry| {
+                let name = buffers.get(&entry.buffer_id).map_or_else(
+                    || entry.buffer_id.to_string(),
+                    |buffer| {
+                        buffer
+                            .short_name
+                            .clone()
+                            .filter(|name| !name.is_empty())
+                            .unwrap_or_else(|| buffer.name.clone())
+                    },
+                );
+
+                // private messages notify in the same way as highlights
+                let highlights = entry.count[HOTLIST_PRIVATE] + entry.count[HOTLIST_HIGHLIGHT];
+
+                Room {
+                    id: entry.buffer_id.to_string(),
+                    name,
+                    unread: entry.count[HOTLIST_MESSAGE] + highlights,
+                    highlights,
+                }
+            })
+            .collect();
+
+        Ok(rooms)
+    }
+}
@@ -0,0 +1,133 @@
+use super::{send, Run, Status};
+use color_eyre::Result;
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.github.com";
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRuns {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRun {
+    id: u64,
+    status: Option<String>,
+    conclusion: Option<String>,
+    html_url: String,
+}
+
+impl From<WorkflowRun> for Run {
+    fn from(run: WorkflowRun) -> Self {
+        let status = match (run.status.as_deref(), run.conclusion.as_deref()) {
+            (Some("completed"), Some("success")) => Status::Success,
+            (Some("completed"), Some("failure" | "timed_out" | "startup_failure")) => {
+                Status::Failure
+            }
+            (Some("completed"), _) => Status::Unknown,
+            (Some(_), _) => Status::Running,
+            (None, _) => Status::Unknown,
+        };
+
+        Self {
+            id: run.id.to_string(),
+            status,
+            url: run.html_url,
+        }
+    }
+}
+
+/// A GitHub Actions workflow, or all workflows in a repository.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    /// Repository in `owner/name` form.
+    repo: String,
+    /// Workflow file name or ID.
+    workflow: Option<String>,
+    branch: Option<String>,
+    token: Option<String>,
+}
+
+impl Pipeline {
+    pub fn new(
+        repo: String,
+        workflow: Option<String>,
+        branch: Option<String>,
+        token: Option<String>,
+    ) -> Self {
+        Self {
+            repo,
+            workflow,
+            branch,
+            token,
+        }
+    }
+
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        method: reqwest::Method,
+        path: &str,
+    ) -> RequestBuilder {
+        // the GitHub API rejects requests without a user agent
+        let request = http
+            .request(method, format!("{API_URL}/repos/{}{path}", self.repo))
+            .header("User-Agent", env!("CARGO_PKG_NAME"))
+            .header("Accept", "application/vnd.github+json");
+
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    pub async fn latest(&self, http: &reqwest::Client) -> Result<Option<Run>> {
+        let mut path = match &self.workflow {
+            Some(workflow) => format!("/actions/workflows/{workflow}/runs?per_page=1"),
+            None => String::from("/actions/runs?per_page=1"),
+        };
+
+        if let Some(branch) = &self.branch {
+            path.push_str("&branch=");
+            path.push_str(branch);
+        }
+
+        let body = send(self.request(http, reqwest::Method::GET, &path)).await?;
+        let runs: WorkflowRuns = serde_json::from_str(&body)?;
+
+        Ok(runs.workflow_runs.into_iter().next().map(Run::from))
+    }
+
+    pub async fn rerun(&self, http: &reqwest::Client, run: &Run) -> Result<()> {
+        let path = format!("/actions/runs/{}/rerun", run.id);
+        send(self.request(http, reqwest::Method::POST, &path)).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: &str, conclusion: Option<&str>) -> Status {
+        let run = WorkflowRun {
+            id: 1,
+            status: Some(status.to_string()),
+            conclusion: conclusion.map(str::to_string),
+            html_url: String::new(),
+        };
+
+        Run::from(run).status
+    }
+
+    #[test]
+    fn test_run_status() {
+        assert_eq!(status("completed", Some("success")), Status::Success);
+        assert_eq!(status("completed", Some("timed_out")), Status::Failure);
+        assert_eq!(status("completed", Some("cancelled")), Status::Unknown);
+        assert_eq!(status("in_progress", None), Status::Running);
+        assert_eq!(status("queued", None), Status::Running);
+    }
+}
@@ -0,0 +1,90 @@
+use super::{send, Run, Status};
+use color_eyre::Result;
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct GitLabPipeline {
+    id: u64,
+    status: String,
+    web_url: String,
+}
+
+impl From<GitLabPipeline> for Run {
+    fn from(pipeline: GitLabPipeline) -> Self {
+        let status = match pipeline.status.as_str() {
+            "success" => Status::Success,
+            "failed" => Status::Failure,
+            "created" | "waiting_for_resource" | "preparing" | "pending" | "running" => {
+                Status::Running
+            }
+            _ => Status::Unknown,
+        };
+
+        Self {
+            id: pipeline.id.to_string(),
+            status,
+            url: pipeline.web_url,
+        }
+    }
+}
+
+/// The pipelines of a GitLab project.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    /// Base URL of the GitLab instance.
+    url: String,
+    /// Project ID or path, URL-encoded.
+    project: String,
+    git_ref: Option<String>,
+    token: Option<String>,
+}
+
+impl Pipeline {
+    pub fn new(url: &str, project: &str, git_ref: Option<String>, token: Option<String>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            project: project.replace('/', "%2F"),
+            git_ref,
+            token,
+        }
+    }
+
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        method: reqwest::Method,
+        path: &str,
+    ) -> RequestBuilder {
+        let request = http.request(
+            method,
+            format!("{}/api/v4/projects/{}{path}", self.url, self.project),
+        );
+
+        match &self.token {
+            Some(token) => request.header("PRIVATE-TOKEN", token),
+            None => request,
+        }
+    }
+
+    pub async fn latest(&self, http: &reqwest::Client) -> Result<Option<Run>> {
+        let mut path = String::from("/pipelines?per_page=1");
+
+        if let Some(git_ref) = &self.git_ref {
+            path.push_str("&ref=");
+            path.push_str(git_ref);
+        }
+
+        let body = send(self.request(http, reqwest::Method::GET, &path)).await?;
+        let pipelines: Vec<GitLabPipeline> = serde_json::from_str(&body)?;
+
+        Ok(pipelines.into_iter().next().map(Run::from))
+    }
+
+    pub async fn rerun(&self, http: &reqwest::Client, run: &Run) -> Result<()> {
+        let path = format!("/pipelines/{}/retry", run.id);
+        send(self.request(http, reqwest::Method::POST, &path)).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use super::{send, Run, Status};
+use color_eyre::Result;
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct Build {
+    number: u64,
+    building: bool,
+    result: Option<String>,
+    url: String,
+}
+
+impl From<Build> for Run {
+    fn from(build: Build) -> Self {
+        let status = if build.building {
+            Status::Running
+        } else {
+            match build.result.as_deref() {
+                Some("SUCCESS") => Status::Success,
+                Some("FAILURE" | "UNSTABLE") => Status::Failure,
+                _ => Status::Unknown,
+            }
+        };
+
+        Self {
+            id: build.number.to_string(),
+            status,
+            url: build.url,
+        }
+    }
+}
+
+/// A Jenkins job.
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    /// Full URL of the job.
+    url: String,
+    /// Username and API token.
+    auth: Option<(String, String)>,
+}
+
+impl Pipeline {
+    pub fn new(url: &str, auth: Option<(String, String)>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            auth,
+        }
+    }
+
+    fn request(
+        &self,
+        http: &reqwest::Client,
+        method: reqwest::Method,
+        path: &str,
+    ) -> RequestBuilder {
+        let request = http.request(method, format!("{}{path}", self.url));
+
+        match &self.auth {
+            Some((user, token)) => request.basic_auth(user, Some(token)),
+            None => request,
+        }
+    }
+
+    pub async fn latest(&self, http: &reqwest::Client) -> Result<Option<Run>> {
+        let res = self
+            .request(http, reqwest::Method::GET, "/lastBuild/api/json")
+            .send()
+            .await?;
+
+        // jobs which have never been built have no last build
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let body = res.error_for_status()?.text().await?;
+        let build: Build = serde_json::from_str(&body)?;
+
+        Ok(Some(build.into()))
+    }
+
+    /// Triggers a new build of the job.
+    ///
+    /// Requests authenticated with an API token
+    /// do not require a CSRF crumb.
+    pub async fn rerun(&self, http: &reqwest::Client) -> Result<()> {
+        send(self.request(http, reqwest::Method::POST, "/build")).await?;
+        Ok(())
+    }
+}
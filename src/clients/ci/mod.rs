@@ -0,0 +1,112 @@
+use color_eyre::Result;
+
+pub mod github;
+pub mod gitlab;
+pub mod jenkins;
+
+/// The overall state of a pipeline run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    /// Queued or in progress.
+    Running,
+    /// Cancelled, skipped, or otherwise unrecognised.
+    Unknown,
+}
+
+impl Status {
+    /// Gets the CSS class name for the status.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Running => "running",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Combines statuses so that any failure takes priority,
+    /// followed by any running pipeline.
+    ///
+    /// Unknown statuses are ignored unless there are no others.
+    pub fn aggregate(statuses: impl IntoIterator<Item = Self>) -> Self {
+        statuses
+            .into_iter()
+            .fold(Self::Unknown, |aggregate, status| {
+                match (aggregate, status) {
+                    (_, Self::Failure) | (Self::Failure, _) => Self::Failure,
+                    (_, Self::Running) | (Self::Running, _) => Self::Running,
+                    (_, Self::Success) | (Self::Success, _) => Self::Success,
+                    (Self::Unknown, Self::Unknown) => Self::Unknown,
+                }
+            })
+    }
+}
+
+/// The latest run of a pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    /// Service-specific identifier, used for re-running.
+    pub id: String,
+    pub status: Status,
+    /// Web page for the run.
+    pub url: String,
+}
+
+/// A pipeline on a CI service.
+#[derive(Debug, Clone)]
+pub enum Pipeline {
+    GitHub(github::Pipeline),
+    GitLab(gitlab::Pipeline),
+    Jenkins(jenkins::Pipeline),
+}
+
+impl Pipeline {
+    /// Gets the most recent run of the pipeline, if there is one.
+    pub async fn latest(&self, http: &reqwest::Client) -> Result<Option<Run>> {
+        match self {
+            Self::GitHub(pipeline) => pipeline.latest(http).await,
+            Self::GitLab(pipeline) => pipeline.latest(http).await,
+            Self::Jenkins(pipeline) => pipeline.latest(http).await,
+        }
+    }
+
+    /// Re-runs `run`.
+    /// Most services require an authentication token for this.
+    pub async fn rerun(&self, http: &reqwest::Client, run: &Run) -> Result<()> {
+        match self {
+            Self::GitHub(pipeline) => pipeline.rerun(http, run).await,
+            Self::GitLab(pipeline) => pipeline.rerun(http, run).await,
+            Self::Jenkins(pipeline) => pipeline.rerun(http).await,
+        }
+    }
+}
+
+/// Sends `request`, returning the response body
+/// if the request was successful.
+async fn send(request: reqwest::RequestBuilder) -> Result<String> {
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        assert_eq!(Status::aggregate([]), Status::Unknown);
+        assert_eq!(
+            Status::aggregate([Status::Unknown, Status::Success]),
+            Status::Success
+        );
+        assert_eq!(
+            Status::aggregate([Status::Success, Status::Running, Status::Unknown]),
+            Status::Running
+        );
+        assert_eq!(
+            Status::aggregate([Status::Failure, Status::Running, Status::Success]),
+            Status::Failure
+        );
+    }
+}
@@ -1,30 +1,75 @@
-use super::{Visibility, Workspace, WorkspaceClient, WorkspaceUpdate};
+use super::{
+    KeyboardLayoutClient, KeyboardLayoutUpdate, LayoutClient, Visibility, Window, Workspace,
+    WorkspaceClient, WorkspaceUpdate, ZoomClient,
+};
+use crate::clients::reconnect;
 use crate::{arc_mut, lock, send, spawn_blocking};
-use color_eyre::Result;
-use hyprland::data::{Workspace as HWorkspace, Workspaces};
-use hyprland::dispatch::{Dispatch, DispatchType, WorkspaceIdentifierWithSpecial};
+use color_eyre::{Report, Result};
+use hyprland::ctl::switch_xkb_layout::{self, SwitchXKBLayoutCmdTypes};
+use hyprland::data::{Clients, Devices, Workspace as HWorkspace, Workspaces};
+use hyprland::dispatch::{
+    Dispatch, DispatchType, WindowIdentifier, WorkspaceIdentifierWithSpecial,
+};
 use hyprland::event_listener::EventListener;
+use hyprland::keyword::{Keyword, OptionValue};
 use hyprland::prelude::*;
-use hyprland::shared::{HyprDataVec, WorkspaceType};
+use hyprland::shared::{Address, HyprDataVec, WorkspaceType};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 use tokio::sync::broadcast::{channel, Receiver, Sender};
 use tracing::{debug, error, info};
 
+/// Config option holding the active layout.
+const LAYOUT_KEYWORD: &str = "general:layout";
+
+/// Config option holding the cursor zoom factor.
+const ZOOM_KEYWORD: &str = "misc:cursor_zoom_factor";
+
+/// Layouts built into Hyprland.
+const LAYOUTS: [&str; 2] = ["dwindle", "master"];
+
+/// The XKB rules file listing the descriptions of each keyboard layout.
+const XKB_RULES: &str = "/usr/share/X11/xkb/rules/evdev.lst";
+
 #[derive(Debug)]
 pub struct Client {
     workspace_tx: Sender<WorkspaceUpdate>,
     _workspace_rx: Receiver<WorkspaceUpdate>,
+
+    layout_tx: Sender<String>,
+    _layout_rx: Receiver<String>,
+
+    keyboard_layout_tx: Sender<KeyboardLayoutUpdate>,
+    _keyboard_layout_rx: Receiver<KeyboardLayoutUpdate>,
+
+    window_focus_tx: Sender<String>,
+    _window_focus_rx: Receiver<String>,
 }
 
 impl Client {
     pub(crate) fn new() -> Self {
         let (workspace_tx, workspace_rx) = channel(16);
+        let (layout_tx, layout_rx) = channel(4);
+        let (keyboard_layout_tx, keyboard_layout_rx) = channel(4);
+        let (window_focus_tx, window_focus_rx) = channel(16);
 
         let instance = Self {
             workspace_tx,
             _workspace_rx: workspace_rx,
+            layout_tx,
+            _layout_rx: layout_rx,
+            keyboard_layout_tx,
+            _keyboard_layout_rx: keyboard_layout_rx,
+            window_focus_tx,
+            _window_focus_rx: window_focus_rx,
         };
 
         instance.listen_workspace_events();
+        instance.listen_config_reloads();
         instance
     }
 
@@ -32,183 +77,226 @@ impl Client {
         info!("Starting Hyprland event listener");
 
         let tx = self.workspace_tx.clone();
+        let keyboard_layout_tx = self.keyboard_layout_tx.clone();
+        let window_focus_tx = self.window_focus_tx.clone();
 
         spawn_blocking(move || {
-            let mut event_listener = EventListener::new();
-
-            // we need a lock to ensure events don't run at the same time
-            let lock = arc_mut!(());
-
-            // cache the active workspace since Hyprland doesn't give us the prev active
-            let active = Self::get_active_workspace().expect("Failed to get active workspace");
-            let active = arc_mut!(Some(active));
-
-            {
+            reconnect::run_blocking("Hyprland IPC", |reconnecting| {
                 let tx = tx.clone();
-                let lock = lock.clone();
-                let active = active.clone();
+                let keyboard_layout_tx = keyboard_layout_tx.clone();
+                let window_focus_tx = window_focus_tx.clone();
 
-                event_listener.add_workspace_added_handler(move |workspace_type| {
-                    let _lock = lock!(lock);
-                    debug!("Added workspace: {workspace_type:?}");
+                let mut event_listener = EventListener::new();
 
-                    let workspace_name = get_workspace_name(workspace_type);
-                    let prev_workspace = lock!(active);
+                // we need a lock to ensure events don't run at the same time
+                let lock = arc_mut!(());
 
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                // cache the active workspace since Hyprland doesn't give us the prev active
+                let active = Self::get_active_workspace()?;
 
-                    if let Some(workspace) = workspace {
-                        send!(tx, WorkspaceUpdate::Add(workspace));
-                    }
-                });
-            }
+                // focus may have moved while disconnected
+                if reconnecting {
+                    send!(
+                        tx,
+                        WorkspaceUpdate::Focus {
+                            old: None,
+                            new: active.clone(),
+                        }
+                    );
+                }
 
-            {
-                let tx = tx.clone();
-                let lock = lock.clone();
-                let active = active.clone();
+                let active = arc_mut!(Some(active));
 
-                event_listener.add_workspace_change_handler(move |workspace_type| {
-                    let _lock = lock!(lock);
+                {
+                    let tx = tx.clone();
+                    let lock = lock.clone();
+                    let active = active.clone();
 
-                    let mut prev_workspace = lock!(active);
+                    event_listener.add_workspace_added_handler(move |workspace_type| {
+                        let _lock = lock!(lock);
+                        debug!("Added workspace: {workspace_type:?}");
 
-                    debug!(
-                        "Received workspace change: {:?} -> {workspace_type:?}",
-                        prev_workspace.as_ref().map(|w| &w.id)
-                    );
+                        let workspace_name = get_workspace_name(workspace_type);
+                        let prev_workspace = lock!(active);
 
-                    let workspace_name = get_workspace_name(workspace_type);
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                        let workspace =
+                            Self::get_workspace(&workspace_name, prev_workspace.as_ref());
 
-                    workspace.map_or_else(
-                        || {
-                            error!("Unable to locate workspace");
-                        },
-                        |workspace| {
-                            // there may be another type of update so dispatch that regardless of focus change
-                            if !workspace.visibility.is_focused() {
-                                Self::send_focus_change(&mut prev_workspace, workspace, &tx);
-                            }
-                        },
-                    );
-                });
-            }
+                        if let Some(workspace) = workspace {
+                            send!(tx, WorkspaceUpdate::Add(workspace));
+                        }
+                    });
+                }
 
-            {
-                let tx = tx.clone();
-                let lock = lock.clone();
-                let active = active.clone();
+                {
+                    let tx = tx.clone();
+                    let lock = lock.clone();
+                    let active = active.clone();
+
+                    event_listener.add_workspace_change_handler(move |workspace_type| {
+                        let _lock = lock!(lock);
+
+                        let mut prev_workspace = lock!(active);
+
+                        debug!(
+                            "Received workspace change: {:?} -> {workspace_type:?}",
+                            prev_workspace.as_ref().map(|w| &w.id)
+                        );
+
+                        let workspace_name = get_workspace_name(workspace_type);
+                        let workspace =
+                            Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+
+                        workspace.map_or_else(
+                            || {
+                                error!("Unable to locate workspace");
+                            },
+                            |workspace| {
+                                // there may be another type of update
+                                // so dispatch that regardless of focus change
+                                if !workspace.visibility.is_focused() {
+                                    Self::send_focus_change(&mut prev_workspace, workspace, &tx);
+                                }
+                            },
+                        );
+                    });
+                }
 
-                event_listener.add_active_monitor_change_handler(move |event_data| {
-                    let _lock = lock!(lock);
-                    let workspace_type = event_data.workspace;
+                {
+                    let tx = tx.clone();
+                    let lock = lock.clone();
+                    let active = active.clone();
 
-                    let mut prev_workspace = lock!(active);
+                    event_listener.add_active_monitor_change_handler(move |event_data| {
+                        let _lock = lock!(lock);
+                        let workspace_type = event_data.workspace;
 
-                    debug!(
-                        "Received active monitor change: {:?} -> {workspace_type:?}",
-                        prev_workspace.as_ref().map(|w| &w.name)
-                    );
+                        let mut prev_workspace = lock!(active);
 
-                    let workspace_name = get_workspace_name(workspace_type);
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                        debug!(
+                            "Received active monitor change: {:?} -> {workspace_type:?}",
+                            prev_workspace.as_ref().map(|w| &w.name)
+                        );
 
-                    if let Some((false, workspace)) =
-                        workspace.map(|w| (w.visibility.is_focused(), w))
-                    {
-                        Self::send_focus_change(&mut prev_workspace, workspace, &tx);
-                    } else {
-                        error!("unable to locate workspace: {workspace_name}");
-                    }
-                });
-            }
+                        let workspace_name = get_workspace_name(workspace_type);
+                        let workspace =
+                            Self::get_workspace(&workspace_name, prev_workspace.as_ref());
 
-            {
-                let tx = tx.clone();
-                let lock = lock.clone();
+                        if let Some((false, workspace)) =
+                            workspace.map(|w| (w.visibility.is_focused(), w))
+                        {
+                            Self::send_focus_change(&mut prev_workspace, workspace, &tx);
+                        } else {
+                            error!("unable to locate workspace: {workspace_name}");
+                        }
+                    });
+                }
 
-                event_listener.add_workspace_moved_handler(move |event_data| {
-                    let _lock = lock!(lock);
-                    let workspace_type = event_data.workspace;
-                    debug!("Received workspace move: {workspace_type:?}");
+                {
+                    let tx = tx.clone();
+                    let lock = lock.clone();
 
-                    let mut prev_workspace = lock!(active);
+                    event_listener.add_workspace_moved_handler(move |event_data| {
+                        let _lock = lock!(lock);
+                        let workspace_type = event_data.workspace;
+                        debug!("Received workspace move: {workspace_type:?}");
 
-                    let workspace_name = get_workspace_name(workspace_type);
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                        let mut prev_workspace = lock!(active);
 
-                    if let Some(workspace) = workspace {
-                        send!(tx, WorkspaceUpdate::Move(workspace.clone()));
+                        let workspace_name = get_workspace_name(workspace_type);
+                        let workspace =
+                            Self::get_workspace(&workspace_name, prev_workspace.as_ref());
 
-                        if !workspace.visibility.is_focused() {
-                            Self::send_focus_change(&mut prev_workspace, workspace, &tx);
+                        if let Some(workspace) = workspace {
+                            send!(tx, WorkspaceUpdate::Move(workspace.clone()));
+
+                            if !workspace.visibility.is_focused() {
+                                Self::send_focus_change(&mut prev_workspace, workspace, &tx);
+                            }
                         }
-                    }
-                });
-            }
+                    });
+                }
 
-            {
-                let tx = tx.clone();
-                let lock = lock.clone();
+                {
+                    let tx = tx.clone();
+                    let lock = lock.clone();
 
-                event_listener.add_workspace_rename_handler(move |data| {
-                    let _lock = lock!(lock);
-                    debug!("Received workspace rename: {data:?}");
+                    event_listener.add_workspace_rename_handler(move |data| {
+                        let _lock = lock!(lock);
+                        debug!("Received workspace rename: {data:?}");
 
-                    send!(
-                        tx,
-                        WorkspaceUpdate::Rename {
-                            id: data.workspace_id as i64,
-                            name: data.workspace_name
-                        }
-                    );
-                });
-            }
+                        send!(
+                            tx,
+                            WorkspaceUpdate::Rename {
+                                id: data.workspace_id as i64,
+                                name: data.workspace_name
+                            }
+                        );
+                    });
+                }
 
-            {
-                let tx = tx.clone();
-                let lock = lock.clone();
+                {
+                    let tx = tx.clone();
+                    let lock = lock.clone();
 
-                event_listener.add_workspace_destroy_handler(move |data| {
-                    let _lock = lock!(lock);
-                    debug!("Received workspace destroy: {data:?}");
-                    send!(tx, WorkspaceUpdate::Remove(data.workspace_id as i64));
-                });
-            }
+                    event_listener.add_workspace_destroy_handler(move |data| {
+                        let _lock = lock!(lock);
+                        debug!("Received workspace destroy: {data:?}");
+                        send!(tx, WorkspaceUpdate::Remove(data.workspace_id as i64));
+                    });
+                }
 
-            {
-                event_listener.add_urgent_state_handler(move |address| {
-                    let _lock = lock!(lock);
-                    debug!("Received urgent state: {address:?}");
+                {
+                    event_listener.add_keyboard_layout_change_handler(move |event| {
+                        debug!("Received keyboard layout change: {event:?}");
+                        Self::send_keyboard_layout(&keyboard_layout_tx);
+                    });
+                }
 
-                    let clients = match hyprland::data::Clients::get() {
-                        Ok(clients) => clients,
-                        Err(err) => {
-                            error!("Failed to get clients: {err}");
-                            return;
+                {
+                    event_listener.add_active_window_change_handler(move |_| {
+                        match hyprland::data::Client::get_active() {
+                            Ok(Some(client)) => {
+                                send!(window_focus_tx, client.address.to_string());
+                            }
+                            Ok(None) => {}
+                            Err(err) => error!("Failed to get active window: {err}"),
                         }
-                    };
-                    clients.iter().find(|c| c.address == address).map_or_else(
-                        || {
-                            error!("Unable to locate client");
-                        },
-                        |c| {
-                            send!(
-                                tx,
-                                WorkspaceUpdate::Urgent {
-                                    id: c.workspace.id as i64,
-                                    urgent: true,
-                                }
-                            );
-                        },
-                    );
-                });
-            }
+                    });
+                }
+
+                {
+                    event_listener.add_urgent_state_handler(move |address| {
+                        let _lock = lock!(lock);
+                        debug!("Received urgent state: {address:?}");
+
+                        let clients = match hyprland::data::Clients::get() {
+                            Ok(clients) => clients,
+                            Err(err) => {
+                                error!("Failed to get clients: {err}");
+                                return;
+                            }
+                        };
+                        clients.iter().find(|c| c.address == address).map_or_else(
+                            || {
+                                error!("Unable to locate client");
+                            },
+                            |c| {
+                                send!(
+                                    tx,
+                                    WorkspaceUpdate::Urgent {
+                                        id: c.workspace.id as i64,
+                                        urgent: true,
+                                    }
+                                );
+                            },
+                        );
+                    });
+                }
 
-            event_listener
-                .start_listener()
-                .expect("Failed to start listener");
+                event_listener.start_listener()?;
+                Ok(())
+            });
         });
     }
 
@@ -256,6 +344,84 @@ impl Client {
             })
     }
 
+    /// Reloading the config resets any layout set through a keyword,
+    /// and may change the configured keyboard layouts.
+    /// Hyprland announces this with a `configreloaded` event,
+    /// which the `hyprland` crate does not expose,
+    /// so its event socket is read directly.
+    fn listen_config_reloads(&self) {
+        let layout_tx = self.layout_tx.clone();
+        let keyboard_layout_tx = self.keyboard_layout_tx.clone();
+
+        spawn_blocking(move || {
+            reconnect::run_blocking("Hyprland event socket", |reconnecting| {
+                let socket = UnixStream::connect(event_socket_path()?)?;
+
+                // the config may have been reloaded while disconnected
+                if reconnecting {
+                    Self::send_layout(&layout_tx);
+                    Self::send_keyboard_layout(&keyboard_layout_tx);
+                }
+
+                for line in BufReader::new(socket).lines() {
+                    if is_config_reload(&line?) {
+                        debug!("Received config reload");
+                        Self::send_layout(&layout_tx);
+                        Self::send_keyboard_layout(&keyboard_layout_tx);
+                    }
+                }
+
+                Ok(())
+            });
+        });
+    }
+
+    fn send_layout(tx: &Sender<String>) {
+        match Self::get_layout() {
+            Ok(layout) => {
+                send!(tx, layout);
+            }
+            Err(err) => error!("Failed to get layout: {err}"),
+        }
+    }
+
+    /// Gets the name of the active layout from the server.
+    fn get_layout() -> Result<String> {
+        let keyword = Keyword::get(LAYOUT_KEYWORD)?;
+
+        match keyword.value {
+            OptionValue::String(layout) => Ok(layout),
+            value => Err(Report::msg(format!("Unexpected layout value: {value:?}"))),
+        }
+    }
+
+    /// Gets the layouts of the main keyboard from the server.
+    fn get_keyboard_layout() -> Result<KeyboardLayoutUpdate> {
+        let keyboard = Devices::get()?
+            .keyboards
+            .into_iter()
+            .find(|keyboard| keyboard.main)
+            .ok_or_else(|| Report::msg("No main keyboard found"))?;
+
+        // Hyprland gives the configured layouts as codes,
+        // but the active layout by its description.
+        let layouts = layout_descriptions(&keyboard.layout, &keyboard.variant);
+        let active = layouts
+            .iter()
+            .position(|layout| layout == &keyboard.active_keymap);
+
+        Ok(KeyboardLayoutUpdate { layouts, active })
+    }
+
+    fn send_keyboard_layout(tx: &Sender<KeyboardLayoutUpdate>) {
+        match Self::get_keyboard_layout() {
+            Ok(update) => {
+                send!(tx, update);
+            }
+            Err(err) => error!("Failed to get keyboard layout: {err}"),
+        }
+    }
+
     /// Gets the active workspace from the server.
     fn get_active_workspace() -> Result<Workspace> {
         let w = HWorkspace::get_active().map(|w| Workspace::from((Visibility::focused(), w)))?;
@@ -298,6 +464,185 @@ impl WorkspaceClient for Client {
 
         rx
     }
+
+    fn windows(&self) -> Result<Vec<Window>> {
+        let windows = Clients::get()?
+            .into_iter()
+            .map(|client| Window {
+                id: client.address.to_string(),
+                title: client.title,
+                app_id: client.class,
+                workspace_id: client.workspace.id as i64,
+            })
+            .collect();
+
+        Ok(windows)
+    }
+
+    fn move_window(&self, id: &str, workspace: &str) -> Result<()> {
+        let identifier = workspace.parse::<i32>().map_or_else(
+            |_| WorkspaceIdentifierWithSpecial::Name(workspace),
+            WorkspaceIdentifierWithSpecial::Id,
+        );
+
+        Dispatch::call(DispatchType::MoveToWorkspaceSilent(
+            identifier,
+            Some(WindowIdentifier::Address(Address::new(id))),
+        ))?;
+        Ok(())
+    }
+}
+
+impl LayoutClient for Client {
+    fn layouts(&self) -> Vec<String> {
+        LAYOUTS.iter().map(ToString::to_string).collect()
+    }
+
+    fn set_layout(&self, layout: &str) -> Result<()> {
+        Keyword::set(LAYOUT_KEYWORD, layout.to_string())?;
+
+        // Hyprland does not send an event when a keyword changes
+        send!(self.layout_tx, layout.to_string());
+        Ok(())
+    }
+
+    fn subscribe_layout_change(&self) -> Receiver<String> {
+        let rx = self.layout_tx.subscribe();
+        Self::send_layout(&self.layout_tx);
+        rx
+    }
+}
+
+impl KeyboardLayoutClient for Client {
+    fn set_keyboard_layout(&self, index: usize) -> Result<()> {
+        let index = u8::try_from(index)?;
+        switch_xkb_layout::call("all", SwitchXKBLayoutCmdTypes::Id(index))?;
+        Ok(())
+    }
+
+    fn subscribe_keyboard_layout(&self) -> Receiver<KeyboardLayoutUpdate> {
+        let rx = self.keyboard_layout_tx.subscribe();
+        Self::send_keyboard_layout(&self.keyboard_layout_tx);
+        rx
+    }
+
+    fn subscribe_window_focus(&self) -> Receiver<String> {
+        self.window_focus_tx.subscribe()
+    }
+}
+
+impl ZoomClient for Client {
+    fn zoom(&self) -> Result<f64> {
+        let keyword = Keyword::get(ZOOM_KEYWORD)?;
+
+        match keyword.value {
+            OptionValue::Float(factor) => Ok(factor),
+            value => Err(Report::msg(format!("Unexpected zoom value: {value:?}"))),
+        }
+    }
+
+    fn set_zoom(&self, factor: f64) -> Result<()> {
+        Keyword::set(ZOOM_KEYWORD, factor.to_string())?;
+        Ok(())
+    }
+}
+
+/// Gets the path to Hyprland's event socket,
+/// which is in the runtime directory since Hyprland v0.40
+/// and in `/tmp` before that.
+fn event_socket_path() -> Result<PathBuf> {
+    let instance = env::var("HYPRLAND_INSTANCE_SIGNATURE")?;
+
+    let dir = env::var("XDG_RUNTIME_DIR")
+        .map(|dir| Path::new(&dir).join("hypr").join(&instance))
+        .ok()
+        .filter(|dir| dir.exists())
+        .unwrap_or_else(|| Path::new("/tmp/hypr").join(&instance));
+
+    Ok(dir.join(".socket2.sock"))
+}
+
+/// Checks whether a line from the event socket,
+/// in the format `event>>data`, is a config reload.
+fn is_config_reload(line: &str) -> bool {
+    line.split_once(">>")
+        .is_some_and(|(event, _)| event == "configreloaded")
+}
+
+/// Converts comma-separated lists of XKB layout and variant codes,
+/// as used in the Hyprland config, into their descriptions.
+///
+/// Codes without a known description are returned as-is.
+fn layout_descriptions(layouts: &str, variants: &str) -> Vec<String> {
+    let descriptions = fs::read_to_string(XKB_RULES)
+        .map(|rules| parse_xkb_rules(&rules))
+        .unwrap_or_else(|err| {
+            error!("Failed to read '{XKB_RULES}': {err}");
+            HashMap::new()
+        });
+
+    let mut variants = variants.split(',').map(str::trim);
+
+    layouts
+        .split(',')
+        .map(str::trim)
+        .filter(|layout| !layout.is_empty())
+        .map(|layout| {
+            let variant = variants.next().unwrap_or_default();
+
+            descriptions
+                .get(&(layout.to_string(), variant.to_string()))
+                .cloned()
+                .unwrap_or_else(|| layout.to_string())
+        })
+        .collect()
+}
+
+/// Parses the layout and variant sections of an XKB rules list
+/// into a map of `(layout, variant)` codes to descriptions.
+/// Layouts without a variant use an empty variant code.
+///
+/// The sections are in the form:
+///
+/// ```text
+/// ! layout
+///   us              English (US)
+///
+/// ! variant
+///   intl            us: English (US, intl., with dead keys)
+/// ```
+fn parse_xkb_rules(rules: &str) -> HashMap<(String, String), String> {
+    let mut descriptions = HashMap::new();
+    let mut section = "";
+
+    for line in rules.lines() {
+        if let Some(name) = line.strip_prefix('!') {
+            section = name.trim();
+            continue;
+        }
+
+        let Some((code, description)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let description = description.trim();
+
+        match section {
+            "layout" => {
+                descriptions.insert((code.to_string(), String::new()), description.to_string());
+            }
+            "variant" => {
+                if let Some((layout, description)) = description.split_once(": ") {
+                    descriptions.insert(
+                        (layout.to_string(), code.to_string()),
+                        description.to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    descriptions
 }
 
 fn get_workspace_name(name: WorkspaceType) -> String {
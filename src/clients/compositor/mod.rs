@@ -6,17 +6,17 @@ use std::sync::Arc;
 use tokio::sync::broadcast;
 use tracing::debug;
 
-#[cfg(feature = "workspaces+hyprland")]
+#[cfg(feature = "hyprland")]
 pub mod hyprland;
 #[cfg(feature = "workspaces+niri")]
 pub mod niri;
-#[cfg(feature = "workspaces+sway")]
+#[cfg(feature = "sway")]
 pub mod sway;
 
 pub enum Compositor {
-    #[cfg(feature = "workspaces+sway")]
+    #[cfg(feature = "sway")]
     Sway,
-    #[cfg(feature = "workspaces+hyprland")]
+    #[cfg(feature = "hyprland")]
     Hyprland,
     #[cfg(feature = "workspaces+niri")]
     Niri,
@@ -29,9 +29,9 @@ impl Display for Compositor {
             f,
             "{}",
             match self {
-                #[cfg(feature = "workspaces+sway")]
+                #[cfg(feature = "sway")]
                 Self::Sway => "Sway",
-                #[cfg(feature = "workspaces+hyprland")]
+                #[cfg(feature = "hyprland")]
                 Self::Hyprland => "Hyprland",
                 #[cfg(feature = "workspaces+niri")]
                 Self::Niri => "Niri",
@@ -47,12 +47,12 @@ impl Compositor {
     fn get_current() -> Self {
         if std::env::var("SWAYSOCK").is_ok() {
             cfg_if! {
-                if #[cfg(feature = "workspaces+sway")] { Self::Sway }
+                if #[cfg(feature = "sway")] { Self::Sway }
                 else { tracing::error!("Not compiled with Sway support"); Self::Unsupported }
             }
         } else if std::env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok() {
             cfg_if! {
-                if #[cfg(feature = "workspaces+hyprland")] { Self::Hyprland }
+                if #[cfg(feature = "hyprland")] { Self::Hyprland }
                 else { tracing::error!("Not compiled with Hyprland support"); Self::Unsupported }
             }
         } else if std::env::var("NIRI_SOCKET").is_ok() {
@@ -67,24 +67,85 @@ impl Compositor {
 
     /// Creates a new instance of
     /// the workspace client for the current compositor.
+    #[cfg(feature = "workspaces")]
     pub fn create_workspace_client(
         clients: &mut super::Clients,
     ) -> Result<Arc<dyn WorkspaceClient + Send + Sync>> {
         let current = Self::get_current();
         debug!("Getting workspace client for: {current}");
         match current {
-            #[cfg(feature = "workspaces+sway")]
+            #[cfg(feature = "sway")]
             Self::Sway => clients
                 .sway()
                 .map(|client| client as Arc<dyn WorkspaceClient + Send + Sync>),
-            #[cfg(feature = "workspaces+hyprland")]
-            Self::Hyprland => Ok(Arc::new(hyprland::Client::new())),
+            #[cfg(feature = "hyprland")]
+            Self::Hyprland => Ok(clients.hyprland()),
             #[cfg(feature = "workspaces+niri")]
             Self::Niri => Ok(Arc::new(niri::Client::new())),
             Self::Unsupported => Err(Report::msg("Unsupported compositor")
                 .note("Currently workspaces are only supported by Sway, Niri and Hyprland")),
         }
     }
+
+    /// Creates a new instance of
+    /// the layout client for the current compositor.
+    #[cfg(feature = "layout")]
+    pub fn create_layout_client(
+        clients: &mut super::Clients,
+    ) -> Result<Arc<dyn LayoutClient + Send + Sync>> {
+        let current = Self::get_current();
+        debug!("Getting layout client for: {current}");
+        match current {
+            #[cfg(feature = "sway")]
+            Self::Sway => clients
+                .sway()
+                .map(|client| client as Arc<dyn LayoutClient + Send + Sync>),
+            #[cfg(feature = "hyprland")]
+            Self::Hyprland => Ok(clients.hyprland()),
+            _ => Err(Report::msg("Unsupported compositor")
+                .note("Currently layouts are only supported by Sway and Hyprland")),
+        }
+    }
+
+    /// Creates a new instance of
+    /// the keyboard layout client for the current compositor.
+    #[cfg(feature = "keys")]
+    pub fn create_keyboard_layout_client(
+        clients: &mut super::Clients,
+    ) -> Result<Arc<dyn KeyboardLayoutClient + Send + Sync>> {
+        let current = Self::get_current();
+        debug!("Getting keyboard layout client for: {current}");
+        match current {
+            #[cfg(feature = "sway")]
+            Self::Sway => clients
+                .sway()
+                .map(|client| client as Arc<dyn KeyboardLayoutClient + Send + Sync>),
+            #[cfg(feature = "hyprland")]
+            Self::Hyprland => Ok(clients.hyprland()),
+            _ => Err(Report::msg("Unsupported compositor")
+                .note("Currently keyboard layouts are only supported by Sway and Hyprland")),
+        }
+    }
+
+    /// Creates a new instance of
+    /// the zoom client for the current compositor.
+    #[cfg(feature = "accessibility")]
+    pub fn create_zoom_client(
+        clients: &mut super::Clients,
+    ) -> Result<Arc<dyn ZoomClient + Send + Sync>> {
+        let current = Self::get_current();
+        debug!("Getting zoom client for: {current}");
+        match current {
+            #[cfg(feature = "sway")]
+            Self::Sway => clients
+                .sway()
+                .map(|client| client as Arc<dyn ZoomClient + Send + Sync>),
+            #[cfg(feature = "hyprland")]
+            Self::Hyprland => Ok(clients.hyprland()),
+            _ => Err(Report::msg("Unsupported compositor")
+                .note("Currently zoom is only supported by Sway and Hyprland")),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +160,19 @@ pub struct Workspace {
     pub visibility: Visibility,
 }
 
+/// An open window (toplevel) which belongs to a workspace.
+#[derive(Debug, Clone)]
+pub struct Window {
+    /// Unique identifier, in the compositor's own format.
+    pub id: String,
+    /// Window title
+    pub title: String,
+    /// Application ID (or class on X11)
+    pub app_id: String,
+    /// ID of the workspace the window is located on
+    pub workspace_id: i64,
+}
+
 /// Indicates workspace visibility.
 /// Visible workspaces have a boolean flag to indicate if they are also focused.
 #[derive(Debug, Copy, Clone)]
@@ -116,6 +190,10 @@ impl Visibility {
         Self::Visible { focused: true }
     }
 
+    pub fn is_visible(self) -> bool {
+        matches!(self, Self::Visible { .. })
+    }
+
     pub fn is_focused(self) -> bool {
         if let Self::Visible { focused } = self {
             focused
@@ -163,6 +241,84 @@ pub trait WorkspaceClient: Debug + Send + Sync {
 
     /// Creates a new to workspace event receiver.
     fn subscribe_workspace_change(&self) -> broadcast::Receiver<WorkspaceUpdate>;
+
+    /// Gets all currently open windows across all workspaces.
+    fn windows(&self) -> Result<Vec<Window>>;
+
+    /// Requests the window with this ID is moved
+    /// to the workspace with this name.
+    ///
+    /// Focus is not moved with the window.
+    fn move_window(&self, id: &str, workspace: &str) -> Result<()>;
 }
 
+#[cfg(feature = "workspaces")]
 register_fallible_client!(dyn WorkspaceClient, workspaces);
+
+/// Shows and switches the compositor's tiling layout.
+pub trait LayoutClient: Debug + Send + Sync {
+    /// Gets the names of all layouts which can be switched to.
+    fn layouts(&self) -> Vec<String>;
+
+    /// Requests the layout with this name is applied.
+    ///
+    /// On Sway, this applies to the focused container.
+    /// On Hyprland, this applies globally.
+    fn set_layout(&self, layout: &str) -> Result<()>;
+
+    /// Creates a new layout change receiver.
+    /// The current layout is sent immediately.
+    fn subscribe_layout_change(&self) -> broadcast::Receiver<String>;
+}
+
+#[cfg(feature = "layout")]
+register_fallible_client!(dyn LayoutClient, layout);
+
+/// The keyboard layouts configured in the compositor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardLayoutUpdate {
+    /// The names of all configured layouts, in order.
+    pub layouts: Vec<String>,
+    /// The index of the active layout in `layouts`, if known.
+    pub active: Option<usize>,
+}
+
+impl KeyboardLayoutUpdate {
+    /// Gets the name of the active layout.
+    pub fn active_name(&self) -> Option<&str> {
+        self.active
+            .and_then(|index| self.layouts.get(index))
+            .map(String::as_str)
+    }
+}
+
+pub trait KeyboardLayoutClient: Debug + Send + Sync {
+    /// Requests all keyboards switch to the layout at `index`.
+    fn set_keyboard_layout(&self, index: usize) -> Result<()>;
+
+    /// Creates a new keyboard layout change receiver.
+    /// The current layouts are sent immediately.
+    fn subscribe_keyboard_layout(&self) -> broadcast::Receiver<KeyboardLayoutUpdate>;
+
+    /// Creates a new receiver for the ID of the focused window,
+    /// which is sent each time focus changes.
+    fn subscribe_window_focus(&self) -> broadcast::Receiver<String>;
+}
+
+#[cfg(feature = "keys")]
+register_fallible_client!(dyn KeyboardLayoutClient, keyboard_layout);
+
+pub trait ZoomClient: Debug + Send + Sync {
+    /// Gets the current zoom level.
+    ///
+    /// On Sway, this is the scale of the focused output.
+    /// On Hyprland, this is the cursor zoom factor,
+    /// where `1.0` is unzoomed.
+    fn zoom(&self) -> Result<f64>;
+
+    /// Requests the zoom level is set to `factor`.
+    fn set_zoom(&self, factor: f64) -> Result<()>;
+}
+
+#[cfg(feature = "accessibility")]
+register_fallible_client!(dyn ZoomClient, zoom);
@@ -1,4 +1,4 @@
-use crate::clients::compositor::Workspace as IronWorkspace;
+use crate::clients::compositor::{Window as IronWindow, Workspace as IronWorkspace};
 use crate::{await_sync, clients::compositor::Visibility};
 use color_eyre::eyre::{eyre, Result};
 use core::str;
@@ -14,6 +14,7 @@ use tokio::{
 pub enum Request {
     Action(Action),
     EventStream,
+    Windows,
 }
 
 pub type Reply = Result<Response, String>;
@@ -22,11 +23,19 @@ pub type Reply = Result<Response, String>;
 pub enum Response {
     Handled,
     Workspaces(Vec<Workspace>),
+    Windows(Vec<Window>),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Action {
-    FocusWorkspace { reference: WorkspaceReferenceArg },
+    FocusWorkspace {
+        reference: WorkspaceReferenceArg,
+    },
+    MoveWindowToWorkspace {
+        window_id: Option<u64>,
+        reference: WorkspaceReferenceArg,
+        focus: bool,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -62,6 +71,25 @@ impl From<&Workspace> for IronWorkspace {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Window {
+    pub id: u64,
+    pub title: Option<String>,
+    pub app_id: Option<String>,
+    pub workspace_id: Option<u64>,
+}
+
+impl From<Window> for IronWindow {
+    fn from(window: Window) -> IronWindow {
+        IronWindow {
+            id: window.id.to_string(),
+            title: window.title.unwrap_or_default(),
+            app_id: window.app_id.unwrap_or_default(),
+            workspace_id: window.workspace_id.map_or(-1, |id| id as i64),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Event {
     WorkspacesChanged { workspaces: Vec<Workspace> },
@@ -1,8 +1,17 @@
-use super::{Visibility, Workspace, WorkspaceClient, WorkspaceUpdate};
-use crate::{await_sync, send};
-use color_eyre::Result;
-use swayipc_async::{Node, WorkspaceChange, WorkspaceEvent};
-use tokio::sync::broadcast::{channel, Receiver};
+use super::{
+    KeyboardLayoutClient, KeyboardLayoutUpdate, LayoutClient, Visibility, Window, Workspace,
+    WorkspaceClient, WorkspaceUpdate, ZoomClient,
+};
+use crate::{await_sync, send, spawn};
+use color_eyre::{Report, Result};
+use std::sync::Arc;
+use swayipc_async::{
+    BindingEvent, Connection, InputChange, InputEvent, Node, NodeLayout, NodeType, Output,
+    WindowChange, WindowEvent, WorkspaceChange, WorkspaceEvent,
+};
+use tokio::sync::broadcast::{channel, Receiver, Sender};
+use tokio::sync::Mutex;
+use tracing::error;
 
 use crate::clients::sway::Client;
 
@@ -41,6 +50,284 @@ impl WorkspaceClient for Client {
 
         rx
     }
+
+    fn windows(&self) -> Result<Vec<Window>> {
+        let tree = await_sync(async move {
+            let mut client = self.connection().lock().await;
+            client.get_tree().await
+        })?;
+
+        let mut windows = vec![];
+
+        for workspace in tree
+            .nodes
+            .iter()
+            .flat_map(|output| &output.nodes)
+            .filter(|node| node.node_type == NodeType::Workspace)
+        {
+            collect_windows(workspace, workspace.id, &mut windows);
+        }
+
+        Ok(windows)
+    }
+
+    fn move_window(&self, id: &str, workspace: &str) -> Result<()> {
+        await_sync(async move {
+            let mut client = self.connection().lock().await;
+            client
+                .run_command(format!(
+                    "[con_id={id}] move container to workspace \"{workspace}\""
+                ))
+                .await
+        })?;
+        Ok(())
+    }
+}
+
+/// Container layouts which can be set using the `layout` command.
+const LAYOUTS: [&str; 4] = ["splith", "splitv", "stacking", "tabbed"];
+
+impl LayoutClient for Client {
+    fn layouts(&self) -> Vec<String> {
+        LAYOUTS.iter().map(ToString::to_string).collect()
+    }
+
+    fn set_layout(&self, layout: &str) -> Result<()> {
+        await_sync(async move {
+            let mut client = self.connection().lock().await;
+            client.run_command(format!("layout {layout}")).await
+        })?;
+        Ok(())
+    }
+
+    fn subscribe_layout_change(&self) -> Receiver<String> {
+        let (tx, rx) = channel(16);
+
+        let client = self.connection().clone();
+        send_focused_layout(client.clone(), tx.clone());
+
+        // Sway has no dedicated layout event,
+        // so re-check whenever focus moves or a binding may have changed it.
+        await_sync(async {
+            {
+                let client = client.clone();
+                let tx = tx.clone();
+                self.add_listener::<WindowEvent>(move |_| {
+                    send_focused_layout(client.clone(), tx.clone());
+                })
+                .await
+                .expect("to add listener");
+            }
+
+            {
+                let client = client.clone();
+                let tx = tx.clone();
+                self.add_listener::<WorkspaceEvent>(move |_| {
+                    send_focused_layout(client.clone(), tx.clone());
+                })
+                .await
+                .expect("to add listener");
+            }
+
+            self.add_listener::<BindingEvent>(move |_| {
+                send_focused_layout(client.clone(), tx.clone());
+            })
+            .await
+            .expect("to add listener");
+        });
+
+        rx
+    }
+}
+
+/// Fetches the layout of the focused container in the background
+/// and sends it on `tx`.
+fn send_focused_layout(client: Arc<Mutex<Connection>>, tx: Sender<String>) {
+    spawn(async move {
+        let tree = client.lock().await.get_tree().await;
+
+        match tree {
+            Ok(tree) => {
+                if let Some(layout) = focused_layout(&tree).and_then(layout_name) {
+                    send!(tx, layout.to_string());
+                }
+            }
+            Err(err) => error!("Failed to get tree: {err:?}"),
+        }
+    });
+}
+
+impl KeyboardLayoutClient for Client {
+    fn set_keyboard_layout(&self, index: usize) -> Result<()> {
+        await_sync(async move {
+            let mut client = self.connection().lock().await;
+            client
+                .run_command(format!("input type:keyboard xkb_switch_layout {index}"))
+                .await
+        })?;
+        Ok(())
+    }
+
+    fn subscribe_keyboard_layout(&self) -> Receiver<KeyboardLayoutUpdate> {
+        let (tx, rx) = channel(16);
+
+        let client = self.connection().clone();
+        send_keyboard_layout(client.clone(), tx.clone());
+
+        await_sync(async {
+            self.add_listener::<InputEvent>(move |event| {
+                if matches!(
+                    event.change,
+                    InputChange::XkbLayout | InputChange::XkbKeymap | InputChange::Added
+                ) {
+                    send_keyboard_layout(client.clone(), tx.clone());
+                }
+            })
+            .await
+            .expect("to add listener");
+        });
+
+        rx
+    }
+
+    fn subscribe_window_focus(&self) -> Receiver<String> {
+        let (tx, rx) = channel(16);
+
+        await_sync(async {
+            self.add_listener::<WindowEvent>(move |event| {
+                if matches!(event.change, WindowChange::Focus) {
+                    send!(tx, event.container.id.to_string());
+                }
+            })
+            .await
+            .expect("to add listener");
+        });
+
+        rx
+    }
+}
+
+impl ZoomClient for Client {
+    fn zoom(&self) -> Result<f64> {
+        Ok(focused_output(self)?.scale.unwrap_or(1.0))
+    }
+
+    fn set_zoom(&self, factor: f64) -> Result<()> {
+        let output = focused_output(self)?;
+
+        await_sync(async move {
+            let mut client = self.connection().lock().await;
+            client
+                .run_command(format!("output {} scale {factor}", output.name))
+                .await
+        })?;
+        Ok(())
+    }
+}
+
+/// Gets the output which is currently focused.
+fn focused_output(client: &Client) -> Result<Output> {
+    let outputs = await_sync(async { client.connection().lock().await.get_outputs().await })?;
+
+    outputs
+        .into_iter()
+        .find(|output| output.focused)
+        .ok_or_else(|| Report::msg("No focused output"))
+}
+
+/// Fetches the layouts of the first keyboard in the background
+/// and sends them on `tx`.
+///
+/// Sway applies `xkb_switch_layout` to all keyboards,
+/// so the first one is taken to represent them all.
+fn send_keyboard_layout(client: Arc<Mutex<Connection>>, tx: Sender<KeyboardLayoutUpdate>) {
+    spawn(async move {
+        let inputs = client.lock().await.get_inputs().await;
+
+        match inputs {
+            Ok(inputs) => {
+                if let Some(input) = inputs.into_iter().find(|input| {
+                    input.input_type == "keyboard" && !input.xkb_layout_names.is_empty()
+                }) {
+                    send!(
+                        tx,
+                        KeyboardLayoutUpdate {
+                            layouts: input.xkb_layout_names,
+                            active: input
+                                .xkb_active_layout_index
+                                .and_then(|index| usize::try_from(index).ok()),
+                        }
+                    );
+                }
+            }
+            Err(err) => error!("Failed to get inputs: {err:?}"),
+        }
+    });
+}
+
+/// Recursively searches for the focused node,
+/// returning the layout of the container holding it.
+///
+/// If a workspace itself is focused, its own layout is returned.
+fn focused_layout(node: &Node) -> Option<&NodeLayout> {
+    for child in node.nodes.iter().chain(&node.floating_nodes) {
+        if child.focused {
+            return if child.node_type == NodeType::Workspace {
+                Some(&child.layout)
+            } else {
+                Some(&node.layout)
+            };
+        }
+
+        if let Some(layout) = focused_layout(child) {
+            return Some(layout);
+        }
+    }
+
+    None
+}
+
+/// Gets the `layout` command argument for a container layout.
+fn layout_name(layout: &NodeLayout) -> Option<&'static str> {
+    match layout {
+        NodeLayout::SplitH => Some("splith"),
+        NodeLayout::SplitV => Some("splitv"),
+        NodeLayout::Stacked => Some("stacking"),
+        NodeLayout::Tabbed => Some("tabbed"),
+        _ => None,
+    }
+}
+
+/// Recursively walks the node tree below a workspace,
+/// adding each leaf container (window) to `windows`.
+fn collect_windows(node: &Node, workspace_id: i64, windows: &mut Vec<Window>) {
+    let children = node.nodes.iter().chain(&node.floating_nodes);
+
+    if node.node_type != NodeType::Workspace
+        && node.nodes.is_empty()
+        && node.floating_nodes.is_empty()
+    {
+        let app_id = node
+            .app_id
+            .clone()
+            .or_else(|| {
+                node.window_properties
+                    .as_ref()
+                    .and_then(|props| props.class.clone())
+            })
+            .unwrap_or_default();
+
+        windows.push(Window {
+            id: node.id.to_string(),
+            title: node.name.clone().unwrap_or_default(),
+            app_id,
+            workspace_id,
+        });
+    }
+
+    for child in children {
+        collect_windows(child, workspace_id, windows);
+    }
 }
 
 impl From<Node> for Workspace {
@@ -0,0 +1,254 @@
+use crate::clients::wayland::{self, OutputEventType};
+use crate::{register_client, spawn};
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// The MCCS VCP code for luminance.
+const VCP_BRIGHTNESS: &str = "10";
+
+/// An external display which can be controlled over DDC/CI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Display {
+    /// The I2C bus number used to address the display.
+    pub bus: u32,
+    /// The name of the output the display is connected to, such as `DP-1`.
+    pub output: Option<String>,
+    /// The model name reported by the display.
+    pub model: String,
+    /// The maximum raw brightness value.
+    pub max_brightness: u32,
+}
+
+/// Controls the brightness of external displays over DDC/CI,
+/// using `ddcutil`.
+///
+/// Probing displays is slow, often taking several seconds,
+/// so the detected displays and their capabilities are cached
+/// until an output is connected or disconnected.
+#[derive(Debug)]
+pub struct Client {
+    displays: Arc<Mutex<Option<Arc<[Display]>>>>,
+    /// Held while running `ddcutil`,
+    /// as concurrent commands on the same bus interfere with each other.
+    bus: Mutex<()>,
+}
+
+impl Client {
+    pub fn new(wayland: &wayland::Client) -> Self {
+        let displays = Arc::new(Mutex::new(None::<Arc<[Display]>>));
+
+        {
+            let displays = displays.clone();
+            let mut rx = wayland.subscribe_outputs();
+
+            spawn(async move {
+                while let Ok(event) = rx.recv().await {
+                    if matches!(
+                        event.event_type,
+                        OutputEventType::New | OutputEventType::Destroyed
+                    ) {
+                        debug!("Outputs changed, clearing DDC/CI display cache");
+                        displays.lock().await.take();
+                    }
+                }
+            });
+        }
+
+        Self {
+            displays,
+            bus: Mutex::new(()),
+        }
+    }
+
+    /// Gets the displays which support DDC/CI,
+    /// probing for them if they have not been already.
+    pub async fn displays(&self) -> Arc<[Display]> {
+        let mut displays = self.displays.lock().await;
+
+        if let Some(displays) = &*displays {
+            return displays.clone();
+        }
+
+        let detected: Arc<[Display]> = match self.detect().await {
+            Ok(detected) => detected.into(),
+            Err(err) => {
+                warn!("Failed to detect DDC/CI displays: {err:?}");
+                Vec::new().into()
+            }
+        };
+
+        displays.replace(detected.clone());
+        detected
+    }
+
+    async fn detect(&self) -> Result<Vec<Display>> {
+        let output = self.run(&["detect", "--brief"]).await?;
+
+        let mut displays = vec![];
+
+        for (bus, output, model) in parse_detect(&output) {
+            // reading the brightness also reads its maximum,
+            // which is cached to avoid querying it again.
+            match self.read_brightness(bus).await {
+                Ok((_, max_brightness)) if max_brightness > 0 => {
+                    debug!("Found DDC/CI display '{model}' on bus {bus}");
+                    displays.push(Display {
+                        bus,
+                        output,
+                        model,
+                        max_brightness,
+                    });
+                }
+                Ok(_) => debug!("Display '{model}' reports no brightness range"),
+                Err(err) => debug!("Display '{model}' does not support brightness: {err:?}"),
+            }
+        }
+
+        Ok(displays)
+    }
+
+    /// Gets the current brightness of a display, as a fraction of its maximum.
+    pub async fn brightness(&self, display: &Display) -> Result<f64> {
+        let (brightness, _) = self.read_brightness(display.bus).await?;
+        Ok(f64::from(brightness) / f64::from(display.max_brightness))
+    }
+
+    /// Sets the brightness of a display to a fraction of its maximum.
+    pub async fn set_brightness(&self, display: &Display, fraction: f64) -> Result<()> {
+        let value = (fraction.clamp(0.0, 1.0) * f64::from(display.max_brightness)).round() as u32;
+
+        self.run(&[
+            "--bus",
+            &display.bus.to_string(),
+            "--noverify",
+            "setvcp",
+            VCP_BRIGHTNESS,
+            &value.to_string(),
+        ])
+        .await?;
+
+        Ok(())
+    }
+
+    /// Reads the current and maximum raw brightness values.
+    async fn read_brightness(&self, bus: u32) -> Result<(u32, u32)> {
+        let output = self
+            .run(&[
+                "--bus",
+                &bus.to_string(),
+                "--brief",
+                "getvcp",
+                VCP_BRIGHTNESS,
+            ])
+            .await?;
+
+        parse_getvcp(&output).ok_or_else(|| eyre!("Unexpected getvcp output: '{output}'"))
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<String> {
+        let _lock = self.bus.lock().await;
+
+        let output = Command::new("ddcutil")
+            .args(args)
+            .output()
+            .await
+            .wrap_err("Failed to run ddcutil")?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(eyre!(
+                "ddcutil exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+}
+
+/// Parses the output of `ddcutil detect --brief`
+/// into the bus number, output name and model of each valid display.
+///
+/// Each display is a block in the form:
+///
+/// ```text
+/// Display 1
+///    I2C bus:  /dev/i2c-6
+///    DRM connector:           card1-DP-1
+///    Monitor:                 DEL:DELL U2719D:ABC123
+/// ```
+fn parse_detect(output: &str) -> Vec<(u32, Option<String>, String)> {
+    output
+        .split("\n\n")
+        .filter(|block| block.trim_start().starts_with("Display "))
+        .filter_map(|block| {
+            let field = |name: &str| {
+                block.lines().find_map(|line| {
+                    line.trim()
+                        .strip_prefix(name)
+                        .and_then(|value| value.strip_prefix(':'))
+                        .map(str::trim)
+                })
+            };
+
+            let bus = field("I2C bus")?
+                .rsplit('-')
+                .next()
+                .and_then(|bus| bus.parse().ok())?;
+
+            // connectors are prefixed with the card, such as `card1-DP-1`
+            let output = field("DRM connector")
+                .and_then(|connector| connector.split_once('-'))
+                .map(|(_, output)| output.to_string());
+
+            let model = field("Monitor")
+                .and_then(|monitor| monitor.split(':').nth(1))
+                .filter(|model| !model.is_empty())
+                .map_or_else(|| format!("Display on bus {bus}"), str::to_string);
+
+            Some((bus, output, model))
+        })
+        .collect()
+}
+
+/// Parses the output of `ddcutil getvcp --brief`,
+/// in the form `VCP 10 C <current> <max>`.
+fn parse_getvcp(output: &str) -> Option<(u32, u32)> {
+    let mut parts = output.split_whitespace().rev();
+    let max = parts.next()?.parse().ok()?;
+    let current = parts.next()?.parse().ok()?;
+
+    Some((current, max))
+}
+
+register_client!(Client, ddc);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_detect() {
+        let output = "Display 1\n   I2C bus:  /dev/i2c-6\n   DRM connector:           card1-DP-1\n   Monitor:                 DEL:DELL U2719D:ABC123\n\n\
+            Invalid display\n   I2C bus:  /dev/i2c-7\n\n\
+            Display 2\n   I2C bus:  /dev/i2c-8\n   Monitor:                 GSM::\n";
+
+        assert_eq!(
+            parse_detect(output),
+            vec![
+                (6, Some(String::from("DP-1")), String::from("DELL U2719D")),
+                (8, None, String::from("Display on bus 8")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_getvcp() {
+        assert_eq!(parse_getvcp("VCP 10 C 50 100\n"), Some((50, 100)));
+        assert_eq!(parse_getvcp("VCP 10 ERR"), None);
+    }
+}
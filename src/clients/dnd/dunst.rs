@@ -0,0 +1,24 @@
+//! # D-Bus interface proxy for: `org.dunstproject.cmd0`
+//!
+//! Only the properties used by Ironbar are included.
+
+#[zbus::dbus_proxy(
+    interface = "org.dunstproject.cmd0",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Dunst {
+    /// paused property
+    #[dbus_proxy(property, name = "paused")]
+    fn paused(&self) -> zbus::Result<bool>;
+    #[dbus_proxy(property, name = "paused")]
+    fn set_paused(&self, value: bool) -> zbus::Result<()>;
+
+    /// displayedLength property
+    #[dbus_proxy(property, name = "displayedLength")]
+    fn displayed_length(&self) -> zbus::Result<u32>;
+
+    /// waitingLength property
+    #[dbus_proxy(property, name = "waitingLength")]
+    fn waiting_length(&self) -> zbus::Result<u32>;
+}
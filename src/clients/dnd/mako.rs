@@ -0,0 +1,23 @@
+//! # D-Bus interface proxy for: `fr.emersion.Mako`
+//!
+//! Only the methods used by Ironbar are included.
+//! Modes require mako 1.8 or later.
+
+use std::collections::HashMap;
+use zbus::zvariant::OwnedValue;
+
+#[zbus::dbus_proxy(
+    interface = "fr.emersion.Mako",
+    default_service = "fr.emersion.Mako",
+    default_path = "/fr/emersion/Mako"
+)]
+trait Mako {
+    /// ListNotifications method
+    fn list_notifications(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    /// ListModes method
+    fn list_modes(&self) -> zbus::Result<Vec<String>>;
+
+    /// SetModes method
+    fn set_modes(&self, modes: &[&str]) -> zbus::Result<()>;
+}
@@ -0,0 +1,238 @@
+mod dunst;
+mod mako;
+
+use self::dunst::DunstProxy;
+use self::mako::MakoProxy;
+use super::swaync;
+use crate::polling;
+use crate::{send, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+use zbus::{CacheProperties, Connection};
+
+/// The mako mode toggled to enable do-not-disturb.
+/// This must be defined in the mako config with `invisible=1`.
+pub const MAKO_MODE: &str = "do-not-disturb";
+
+/// How often to poll daemons which do not send change events.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// Uses the first running daemon out of SwayNC, mako and dunst.
+    #[default]
+    Auto,
+    #[serde(rename = "swaync")]
+    SwayNc,
+    Mako,
+    Dunst,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// Whether do-not-disturb is enabled.
+    pub dnd: bool,
+    /// The number of notifications waiting to be read,
+    /// as far as the daemon reports it.
+    pub count: u32,
+}
+
+impl From<swaync::Event> for Status {
+    fn from(event: swaync::Event) -> Self {
+        Self {
+            dnd: event.dnd,
+            count: event.count,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Daemon {
+    SwayNc(swaync::Client),
+    Mako(MakoProxy<'static>),
+    Dunst(DunstProxy<'static>),
+}
+
+impl Daemon {
+    async fn status(&self) -> Result<Status> {
+        match self {
+            Self::SwayNc(client) => client.state().await.map(Status::from),
+            Self::Mako(proxy) => {
+                let modes = proxy.list_modes().await?;
+                let notifications = proxy.list_notifications().await?;
+
+                Ok(Status {
+                    dnd: modes.iter().any(|mode| mode == MAKO_MODE),
+                    count: u32::try_from(notifications.len()).unwrap_or(u32::MAX),
+                })
+            }
+            Self::Dunst(proxy) => {
+                let dnd = proxy.paused().await?;
+                let count = proxy.displayed_length().await? + proxy.waiting_length().await?;
+
+                Ok(Status { dnd, count })
+            }
+        }
+    }
+
+    async fn set_dnd(&self, enabled: bool) -> Result<()> {
+        match self {
+            Self::SwayNc(client) => client.set_dnd(enabled).await,
+            Self::Mako(proxy) => {
+                let mut modes = proxy.list_modes().await?;
+                modes.retain(|mode| mode != MAKO_MODE);
+                if enabled {
+                    modes.push(MAKO_MODE.to_string());
+                }
+
+                let modes = modes.iter().map(String::as_str).collect::<Vec<_>>();
+                proxy.set_modes(&modes).await.map_err(Into::into)
+            }
+            Self::Dunst(proxy) => proxy.set_paused(enabled).await.map_err(Into::into),
+        }
+    }
+}
+
+/// Controls the do-not-disturb mode of an external notification daemon.
+#[derive(Debug)]
+pub struct Client {
+    daemon: Arc<Daemon>,
+    tx: broadcast::Sender<Status>,
+    _rx: broadcast::Receiver<Status>,
+}
+
+impl Client {
+    pub async fn new(backend: Backend) -> Result<Self> {
+        let dbus = Box::pin(Connection::session()).await?;
+
+        let backend = match backend {
+            Backend::Auto => detect(&dbus).await?,
+            backend => backend,
+        };
+
+        debug!("Using {backend:?} for do-not-disturb");
+
+        let daemon = match backend {
+            Backend::SwayNc => Daemon::SwayNc(swaync::Client::new().await?),
+            Backend::Mako => Daemon::Mako(
+                MakoProxy::builder(&dbus)
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?,
+            ),
+            Backend::Dunst => Daemon::Dunst(
+                DunstProxy::builder(&dbus)
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?,
+            ),
+            Backend::Auto => unreachable!(),
+        };
+
+        let daemon = Arc::new(daemon);
+        let (tx, rx) = broadcast::channel(8);
+
+        if let Daemon::SwayNc(client) = daemon.as_ref() {
+            let mut rx = client.subscribe();
+            let tx = tx.clone();
+
+            spawn(async move {
+                while let Ok(event) = rx.recv().await {
+                    send!(tx, Status::from(event));
+                }
+            });
+        } else {
+            // mako and dunst do not send events, so must be polled
+            let daemon = daemon.clone();
+            let tx = tx.clone();
+
+            spawn(async move {
+                let mut last = None;
+
+                loop {
+                    match daemon.status().await {
+                        Ok(status) if last != Some(status) => {
+                            last = Some(status);
+                            send!(tx, status);
+                        }
+                        Ok(_) => {}
+                        Err(err) => error!("Failed to get do-not-disturb status: {err:?}"),
+                    }
+
+                    polling::sleep(POLL_INTERVAL).await;
+                }
+            });
+        }
+
+        Ok(Self {
+            daemon,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Status> {
+        self.tx.subscribe()
+    }
+
+    pub async fn status(&self) -> Result<Status> {
+        self.daemon.status().await
+    }
+
+    pub async fn set_dnd(&self, enabled: bool) -> Result<()> {
+        debug!("Setting DnD: {enabled}");
+        self.daemon.set_dnd(enabled).await?;
+
+        // polled daemons would otherwise take up to a second to update
+        if !matches!(self.daemon.as_ref(), Daemon::SwayNc(_)) {
+            send!(self.tx, self.daemon.status().await?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the first supported notification daemon running on the session bus.
+async fn detect(dbus: &Connection) -> Result<Backend> {
+    let proxy = DBusProxy::new(dbus).await?;
+
+    if has_owner(&proxy, "org.erikreider.swaync.cc").await? {
+        return Ok(Backend::SwayNc);
+    }
+
+    if has_owner(&proxy, "fr.emersion.Mako").await? {
+        return Ok(Backend::Mako);
+    }
+
+    // dunst only owns the standard notifications name,
+    // so check for its own interface instead.
+    // checking the owner first avoids activating a daemon.
+    if !has_owner(&proxy, "org.freedesktop.Notifications").await? {
+        return Err(eyre!("No notification daemon is running"));
+    }
+
+    let dunst = DunstProxy::builder(dbus)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    if dunst.paused().await.is_ok() {
+        return Ok(Backend::Dunst);
+    }
+
+    Err(eyre!("No supported notification daemon is running"))
+}
+
+async fn has_owner(proxy: &DBusProxy<'_>, name: &str) -> Result<bool> {
+    let name = BusName::try_from(name)?;
+    Ok(proxy.name_has_owner(name).await?)
+}
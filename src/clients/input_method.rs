@@ -0,0 +1,311 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use gtk::gio;
+use gtk::prelude::*;
+use serde::Deserialize;
+use tokio::process::Command;
+use tracing::debug;
+use zbus::dbus_proxy;
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+use zbus::zvariant::{OwnedValue, Value};
+
+const FCITX5_SERVICE: &str = "org.fcitx.Fcitx5";
+
+/// The GSettings schema holding the engines configured in IBus.
+const IBUS_SCHEMA: &str = "org.freedesktop.ibus.general";
+
+// Positions of fields in a serialized `IBusEngineDesc`.
+// The first two fields are the type name and attachments.
+const IBUS_FIELD_NAME: usize = 2;
+const IBUS_FIELD_LONGNAME: usize = 3;
+const IBUS_FIELD_LANGUAGE: usize = 5;
+const IBUS_FIELD_ICON: usize = 8;
+const IBUS_FIELD_SYMBOL: usize = 12;
+
+/// The Fcitx5 controller, on the session bus.
+#[dbus_proxy(
+    default_service = "org.fcitx.Fcitx5",
+    interface = "org.fcitx.Fcitx.Controller1",
+    default_path = "/controller"
+)]
+trait Fcitx5Controller {
+    fn current_input_method(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(name = "SetCurrentIM")]
+    fn set_current_im(&self, name: &str) -> zbus::Result<()>;
+
+    fn toggle(&self) -> zbus::Result<()>;
+
+    fn current_input_method_group(&self) -> zbus::Result<String>;
+
+    fn input_method_group_info(&self, name: &str) -> zbus::Result<(String, Vec<(String, String)>)>;
+
+    /// Each method is in the form
+    /// `(unique name, name, native name, icon, label, language code, configurable)`.
+    #[allow(clippy::type_complexity)]
+    fn available_input_methods(
+        &self,
+    ) -> zbus::Result<Vec<(String, String, String, String, String, String, bool)>>;
+}
+
+/// The IBus daemon, on its own private bus.
+#[dbus_proxy(
+    default_service = "org.freedesktop.IBus",
+    interface = "org.freedesktop.IBus",
+    default_path = "/org/freedesktop/IBus"
+)]
+trait IBus {
+    fn set_global_engine(&self, engine_name: &str) -> zbus::Result<()>;
+
+    fn get_engines_by_names(&self, names: &[&str]) -> zbus::Result<Vec<OwnedValue>>;
+
+    #[dbus_proxy(property)]
+    fn global_engine(&self) -> zbus::Result<OwnedValue>;
+}
+
+/// The input method framework to connect to.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// Use Fcitx5 if it is running, otherwise IBus.
+    #[default]
+    Auto,
+    Fcitx5,
+    Ibus,
+}
+
+/// An input method engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Engine {
+    /// The unique name of the engine, used to switch to it.
+    pub id: String,
+    /// The human-readable name of the engine.
+    pub name: String,
+    /// A short label for the engine, such as `拼` or `EN`.
+    pub label: String,
+    /// The name of the engine's icon in the icon theme.
+    pub icon: Option<String>,
+}
+
+#[derive(Debug)]
+enum Connection {
+    Fcitx5(Fcitx5ControllerProxy<'static>),
+    Ibus(IBusProxy<'static>),
+}
+
+/// Controls the active engine of Fcitx5 or IBus over D-Bus.
+///
+/// Neither framework reliably signals engine changes,
+/// so the current engine should be polled.
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+}
+
+impl Client {
+    pub async fn connect(backend: Backend) -> Result<Self> {
+        let connection = match backend {
+            Backend::Fcitx5 => Self::connect_fcitx5().await?,
+            Backend::Ibus => Self::connect_ibus().await?,
+            Backend::Auto => {
+                let session = zbus::Connection::session().await?;
+                let fcitx5_running = DBusProxy::new(&session)
+                    .await?
+                    .name_has_owner(BusName::try_from(FCITX5_SERVICE)?)
+                    .await?;
+
+                if fcitx5_running {
+                    Self::connect_fcitx5().await?
+                } else {
+                    Self::connect_ibus().await?
+                }
+            }
+        };
+
+        Ok(Self { connection })
+    }
+
+    async fn connect_fcitx5() -> Result<Connection> {
+        debug!("Connecting to Fcitx5");
+
+        let dbus = zbus::Connection::session().await?;
+        let proxy = Fcitx5ControllerProxy::new(&dbus).await?;
+        Ok(Connection::Fcitx5(proxy))
+    }
+
+    async fn connect_ibus() -> Result<Connection> {
+        debug!("Connecting to IBus");
+
+        let address = ibus_address().await?;
+        let dbus = zbus::ConnectionBuilder::address(address.as_str())?
+            .build()
+            .await
+            .wrap_err("Failed to connect to the IBus daemon")?;
+
+        let proxy = IBusProxy::new(&dbus).await?;
+        Ok(Connection::Ibus(proxy))
+    }
+
+    /// Gets the unique name of the active engine.
+    pub async fn current(&self) -> Result<String> {
+        match &self.connection {
+            Connection::Fcitx5(proxy) => Ok(proxy.current_input_method().await?),
+            Connection::Ibus(proxy) => {
+                let engine = proxy.global_engine().await?;
+                parse_ibus_engine(&engine)
+                    .map(|engine| engine.id)
+                    .ok_or_else(|| eyre!("Unexpected IBus engine: {engine:?}"))
+            }
+        }
+    }
+
+    /// Gets the engines which the user has configured,
+    /// in the order they are configured.
+    pub async fn engines(&self) -> Result<Vec<Engine>> {
+        match &self.connection {
+            Connection::Fcitx5(proxy) => {
+                let group = proxy.current_input_method_group().await?;
+                let (_, items) = proxy.input_method_group_info(&group).await?;
+                let available = proxy.available_input_methods().await?;
+
+                let engines = items
+                    .into_iter()
+                    .filter_map(|(id, _)| {
+                        available.iter().find(|method| method.0 == id).map(
+                            |(id, name, _, icon, label, _, _)| Engine {
+                                id: id.clone(),
+                                name: name.clone(),
+                                label: if label.is_empty() {
+                                    name.clone()
+                                } else {
+                                    label.clone()
+                                },
+                                icon: Some(icon.clone()).filter(|icon| !icon.is_empty()),
+                            },
+                        )
+                    })
+                    .collect();
+
+                Ok(engines)
+            }
+            Connection::Ibus(proxy) => {
+                let names = ibus_preload_engines();
+                let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+
+                let engines = proxy
+                    .get_engines_by_names(&names)
+                    .await?
+                    .iter()
+                    .filter_map(|engine| parse_ibus_engine(engine))
+                    .collect();
+
+                Ok(engines)
+            }
+        }
+    }
+
+    /// Switches to the engine with the given unique name.
+    pub async fn set_engine(&self, id: &str) -> Result<()> {
+        match &self.connection {
+            Connection::Fcitx5(proxy) => proxy.set_current_im(id).await?,
+            Connection::Ibus(proxy) => proxy.set_global_engine(id).await?,
+        }
+
+        Ok(())
+    }
+
+    /// Toggles between engines.
+    ///
+    /// On Fcitx5, this toggles between the keyboard and the last used input method.
+    /// On IBus, this switches to the next configured engine.
+    pub async fn toggle(&self) -> Result<()> {
+        match &self.connection {
+            Connection::Fcitx5(proxy) => proxy.toggle().await?,
+            Connection::Ibus(_) => {
+                let engines = self.engines().await?;
+                let current = self.current().await?;
+
+                let next = engines
+                    .iter()
+                    .position(|engine| engine.id == current)
+                    .map_or(0, |index| (index + 1) % engines.len());
+
+                if let Some(engine) = engines.get(next) {
+                    self.set_engine(&engine.id).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the address of the IBus daemon's bus,
+/// from `$IBUS_ADDRESS` or by asking `ibus`.
+async fn ibus_address() -> Result<String> {
+    if let Ok(address) = std::env::var("IBUS_ADDRESS") {
+        return Ok(address);
+    }
+
+    let output = Command::new("ibus")
+        .arg("address")
+        .output()
+        .await
+        .wrap_err("Failed to run ibus")?;
+
+    let address = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if !output.status.success() || address.is_empty() || address == "(null)" {
+        Err(eyre!("The IBus daemon is not running"))
+    } else {
+        Ok(address)
+    }
+}
+
+/// Gets the names of the engines configured in IBus.
+fn ibus_preload_engines() -> Vec<String> {
+    let schema_installed = gio::SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(IBUS_SCHEMA, true))
+        .is_some();
+
+    if schema_installed {
+        gio::Settings::new(IBUS_SCHEMA)
+            .strv("preload-engines")
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Parses a serialized `IBusEngineDesc`.
+fn parse_ibus_engine(value: &Value) -> Option<Engine> {
+    match value {
+        Value::Value(value) => parse_ibus_engine(value),
+        Value::Structure(desc) => {
+            let field = |index: usize| match desc.fields().get(index) {
+                Some(Value::Str(value)) if !value.is_empty() => Some(value.to_string()),
+                _ => None,
+            };
+
+            let id = field(IBUS_FIELD_NAME)?;
+            let name = field(IBUS_FIELD_LONGNAME).unwrap_or_else(|| id.clone());
+
+            // not all engines have a symbol, so fall back to the language
+            let label = field(IBUS_FIELD_SYMBOL)
+                .or_else(|| field(IBUS_FIELD_LANGUAGE).map(|lang| lang.to_uppercase()))
+                .unwrap_or_else(|| name.clone());
+
+            Some(Engine {
+                id,
+                name,
+                label,
+                icon: field(IBUS_FIELD_ICON),
+            })
+        }
+        _ => None,
+    }
+}
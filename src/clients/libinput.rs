@@ -59,6 +59,32 @@ impl Key {
     }
 }
 
+/// Media and hardware control keys,
+/// such as those for volume and brightness.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MediaKey {
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    BrightnessUp,
+    BrightnessDown,
+}
+
+impl TryFrom<EV_KEY> for MediaKey {
+    type Error = Report;
+
+    fn try_from(value: EV_KEY) -> std::result::Result<Self, Self::Error> {
+        match value {
+            EV_KEY::KEY_VOLUMEUP => Ok(Self::VolumeUp),
+            EV_KEY::KEY_VOLUMEDOWN => Ok(Self::VolumeDown),
+            EV_KEY::KEY_MUTE => Ok(Self::Mute),
+            EV_KEY::KEY_BRIGHTNESSUP => Ok(Self::BrightnessUp),
+            EV_KEY::KEY_BRIGHTNESSDOWN => Ok(Self::BrightnessDown),
+            _ => Err(Report::msg("provided key is not a supported media key")),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct KeyEvent {
     pub key: Key,
@@ -69,6 +95,8 @@ pub struct KeyEvent {
 pub enum Event {
     Device,
     Key(KeyEvent),
+    /// A media key was pressed.
+    MediaKey(MediaKey),
 }
 
 struct KeyData<P: AsRef<Path>> {
@@ -187,6 +215,15 @@ impl Client {
                             });
                         }
                     }
+                    input::Event::Keyboard(KeyboardEvent::Key(event))
+                        if event.key_state() == KeyState::Pressed =>
+                    {
+                        if let Some(key) =
+                            int_to_ev_key(event.key()).and_then(|key| MediaKey::try_from(key).ok())
+                        {
+                            send!(self.tx, Event::MediaKey(key));
+                        }
+                    }
                     input::Event::Device(DeviceEvent::Added(event)) => {
                         let device = event.device();
                         if !device.has_capability(DeviceCapability::Keyboard) {
@@ -0,0 +1,106 @@
+use color_eyre::Result;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{dbus_proxy, CacheProperties, Connection};
+
+/// Object path of the QEMU/KVM driver connection.
+const CONNECT_PATH: &str = "/org/libvirt/QEMU";
+
+#[dbus_proxy(
+    interface = "org.libvirt.Connect",
+    default_service = "org.libvirt",
+    default_path = "/org/libvirt/QEMU"
+)]
+trait Connect {
+    fn list_domains(&self, flags: u32) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(interface = "org.libvirt.Domain", default_service = "org.libvirt")]
+trait Domain {
+    fn create(&self, flags: u32) -> zbus::Result<()>;
+    fn shutdown(&self, flags: u32) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn name(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn active(&self) -> zbus::Result<bool>;
+}
+
+/// A libvirt virtual machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Domain {
+    /// D-Bus object path, used to identify the domain.
+    pub path: String,
+    pub name: String,
+    pub active: bool,
+}
+
+/// Client for the `libvirt-dbus` service.
+///
+/// The system service manages `qemu:///system` domains,
+/// while the session service manages `qemu:///session` domains.
+#[derive(Debug, Clone)]
+pub struct Client {
+    connection: Connection,
+}
+
+impl Client {
+    pub async fn new(session: bool) -> Result<Self> {
+        let connection = if session {
+            Connection::session().await?
+        } else {
+            Connection::system().await?
+        };
+
+        Ok(Self { connection })
+    }
+
+    async fn domain(&self, path: &str) -> Result<DomainProxy<'static>> {
+        // libvirt does not emit property change signals,
+        // so cached values would go stale.
+        let domain = DomainProxy::builder(&self.connection)
+            .path(path.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        Ok(domain)
+    }
+
+    /// Gets all defined domains, sorted by name.
+    ///
+    /// This fails if libvirt is not running.
+    pub async fn domains(&self) -> Result<Vec<Domain>> {
+        let connect = ConnectProxy::builder(&self.connection)
+            .path(CONNECT_PATH)?
+            .build()
+            .await?;
+
+        let mut domains = vec![];
+
+        for path in connect.list_domains(0).await? {
+            let domain = self.domain(path.as_str()).await?;
+
+            domains.push(Domain {
+                path: path.to_string(),
+                name: domain.name().await?,
+                active: domain.active().await?,
+            });
+        }
+
+        domains.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(domains)
+    }
+
+    pub async fn start(&self, path: &str) -> Result<()> {
+        self.domain(path).await?.create(0).await?;
+        Ok(())
+    }
+
+    /// Requests a graceful shutdown of the domain.
+    pub async fn shutdown(&self, path: &str) -> Result<()> {
+        self.domain(path).await?.shutdown(0).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use zbus::dbus_proxy;
+use zbus::zvariant::OwnedFd;
+
+/// The logind session manager,
+/// used to control the power state of the system.
+#[dbus_proxy(
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Manager",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn lid_closed(&self) -> zbus::Result<bool>;
+}
+
+/// The logind session which Ironbar is running in.
+#[dbus_proxy(
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Session",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait Session {
+    fn lock(&self) -> zbus::Result<()>;
+
+    fn terminate(&self) -> zbus::Result<()>;
+
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
+
+/// Takes a logind lock which prevents the system from going idle,
+/// such as locking or blanking the screen.
+///
+/// The lock is released when the returned fd is dropped.
+#[cfg(any(feature = "idle_inhibit", feature = "presentation"))]
+pub async fn inhibit_idle(why: &str) -> color_eyre::Result<OwnedFd> {
+    let dbus = zbus::Connection::system().await?;
+    let manager = ManagerProxy::new(&dbus).await?;
+
+    let fd = manager.inhibit("idle", "Ironbar", why, "block").await?;
+    Ok(fd)
+}
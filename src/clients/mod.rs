@@ -5,22 +5,67 @@ use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
 
+#[cfg(feature = "reminders")]
+pub mod activity;
+#[cfg(any(feature = "brightness", feature = "osd", feature = "media_keys"))]
+pub mod backlight;
+#[cfg(feature = "bluetooth")]
+pub mod bluetooth;
+#[cfg(feature = "chat")]
+pub mod chat;
+#[cfg(feature = "ci")]
+pub mod ci;
 #[cfg(feature = "clipboard")]
 pub mod clipboard;
-#[cfg(feature = "workspaces")]
+#[cfg(any(
+    feature = "accessibility",
+    feature = "workspaces",
+    feature = "layout",
+    feature = "keys"
+))]
 pub mod compositor;
+#[cfg(feature = "brightness")]
+pub mod ddc;
+#[cfg(feature = "dnd")]
+pub mod dnd;
+#[cfg(feature = "input_method")]
+pub mod input_method;
 #[cfg(feature = "keys")]
 pub mod libinput;
+#[cfg(feature = "vm")]
+pub mod libvirt;
+#[cfg(any(
+    feature = "brightness",
+    feature = "idle_inhibit",
+    feature = "lid",
+    feature = "media_keys",
+    feature = "menu",
+    feature = "presentation"
+))]
+pub mod logind;
 #[cfg(feature = "cairo")]
 pub mod lua;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
 #[cfg(feature = "music")]
 pub mod music;
 #[cfg(feature = "network_manager")]
 pub mod networkmanager;
+#[cfg(feature = "notifications")]
+pub mod notification_daemon;
+pub mod reconnect;
+#[cfg(feature = "reminders")]
+pub mod reminders;
 #[cfg(feature = "sway")]
 pub mod sway;
-#[cfg(feature = "notifications")]
+#[cfg(any(feature = "notifications", feature = "dnd"))]
 pub mod swaync;
+#[cfg(feature = "systemd")]
+pub mod systemd;
+#[cfg(any(feature = "reminders", feature = "timer"))]
+pub mod timer;
+#[cfg(feature = "timetracker")]
+pub mod timetracker;
 #[cfg(feature = "tray")]
 pub mod tray;
 #[cfg(feature = "upower")]
@@ -28,6 +73,8 @@ pub mod upower;
 #[cfg(feature = "volume")]
 pub mod volume;
 pub mod wayland;
+#[cfg(feature = "weather")]
+pub mod weather;
 
 /// Singleton wrapper consisting of
 /// all the singleton client types used by modules.
@@ -36,24 +83,46 @@ pub struct Clients {
     wayland: Option<Arc<wayland::Client>>,
     #[cfg(feature = "workspaces")]
     workspaces: Option<Arc<dyn compositor::WorkspaceClient>>,
+    #[cfg(feature = "layout")]
+    layout: Option<Arc<dyn compositor::LayoutClient>>,
+    #[cfg(feature = "keys")]
+    keyboard_layout: Option<Arc<dyn compositor::KeyboardLayoutClient>>,
+    #[cfg(feature = "accessibility")]
+    zoom: Option<Arc<dyn compositor::ZoomClient>>,
     #[cfg(feature = "sway")]
     sway: Option<Arc<sway::Client>>,
+    #[cfg(feature = "hyprland")]
+    hyprland: Option<Arc<compositor::hyprland::Client>>,
     #[cfg(feature = "clipboard")]
     clipboard: Option<Arc<clipboard::Client>>,
+    #[cfg(feature = "brightness")]
+    ddc: Option<Arc<ddc::Client>>,
+    #[cfg(feature = "dnd")]
+    dnd: HashMap<dnd::Backend, Arc<dnd::Client>>,
     #[cfg(feature = "keys")]
     libinput: HashMap<Box<str>, Arc<libinput::Client>>,
     #[cfg(feature = "cairo")]
     lua: Option<Rc<lua::LuaEngine>>,
+    #[cfg(feature = "mqtt")]
+    mqtt: HashMap<mqtt::Broker, Arc<mqtt::Client>>,
     #[cfg(feature = "music")]
     music: HashMap<music::ClientType, Arc<dyn music::MusicClient>>,
     #[cfg(feature = "network_manager")]
     network_manager: Option<Arc<networkmanager::Client>>,
     #[cfg(feature = "notifications")]
     notifications: Option<Arc<swaync::Client>>,
+    #[cfg(feature = "notifications")]
+    notification_daemon: Option<Arc<notification_daemon::Client>>,
+    #[cfg(feature = "reminders")]
+    reminders: Option<Arc<reminders::Client>>,
+    #[cfg(any(feature = "reminders", feature = "timer"))]
+    timer: Option<Arc<timer::Client>>,
     #[cfg(feature = "tray")]
     tray: Option<Arc<tray::Client>>,
     #[cfg(feature = "upower")]
     upower: Option<Arc<zbus::fdo::PropertiesProxy<'static>>>,
+    #[cfg(feature = "upower")]
+    upower_events: Option<Arc<upower::UPowerEventsProxy<'static>>>,
     #[cfg(feature = "volume")]
     volume: Option<Arc<volume::Client>>,
 }
@@ -80,6 +149,15 @@ impl Clients {
             .clone()
     }
 
+    #[cfg(feature = "brightness")]
+    pub fn ddc(&mut self) -> Arc<ddc::Client> {
+        let wayland = self.wayland();
+
+        self.ddc
+            .get_or_insert_with(|| Arc::new(ddc::Client::new(&wayland)))
+            .clone()
+    }
+
     #[cfg(feature = "workspaces")]
     pub fn workspaces(&mut self) -> ClientResult<dyn compositor::WorkspaceClient> {
         let client = if let Some(workspaces) = &self.workspaces {
@@ -93,6 +171,45 @@ impl Clients {
         Ok(client)
     }
 
+    #[cfg(feature = "layout")]
+    pub fn layout(&mut self) -> ClientResult<dyn compositor::LayoutClient> {
+        let client = if let Some(layout) = &self.layout {
+            layout.clone()
+        } else {
+            let client = compositor::Compositor::create_layout_client(self)?;
+            self.layout.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    #[cfg(feature = "keys")]
+    pub fn keyboard_layout(&mut self) -> ClientResult<dyn compositor::KeyboardLayoutClient> {
+        let client = if let Some(keyboard_layout) = &self.keyboard_layout {
+            keyboard_layout.clone()
+        } else {
+            let client = compositor::Compositor::create_keyboard_layout_client(self)?;
+            self.keyboard_layout.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    #[cfg(feature = "accessibility")]
+    pub fn zoom(&mut self) -> ClientResult<dyn compositor::ZoomClient> {
+        let client = if let Some(zoom) = &self.zoom {
+            zoom.clone()
+        } else {
+            let client = compositor::Compositor::create_zoom_client(self)?;
+            self.zoom.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "sway")]
     pub fn sway(&mut self) -> ClientResult<sway::Client> {
         let client = if let Some(client) = &self.sway {
@@ -107,6 +224,13 @@ impl Clients {
         Ok(client)
     }
 
+    #[cfg(feature = "hyprland")]
+    pub fn hyprland(&mut self) -> Arc<compositor::hyprland::Client> {
+        self.hyprland
+            .get_or_insert_with(|| Arc::new(compositor::hyprland::Client::new()))
+            .clone()
+    }
+
     #[cfg(feature = "cairo")]
     pub fn lua(&mut self, config_dir: &Path) -> Rc<lua::LuaEngine> {
         self.lua
@@ -114,6 +238,20 @@ impl Clients {
             .clone()
     }
 
+    #[cfg(feature = "dnd")]
+    pub fn dnd(&mut self, backend: dnd::Backend) -> ClientResult<dnd::Client> {
+        let client = if let Some(client) = self.dnd.get(&backend) {
+            client.clone()
+        } else {
+            let client = await_sync(async { dnd::Client::new(backend).await })?;
+            let client = Arc::new(client);
+            self.dnd.insert(backend, client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "keys")]
     pub fn libinput(&mut self, seat: &str) -> Arc<libinput::Client> {
         self.libinput
@@ -122,6 +260,14 @@ impl Clients {
             .clone()
     }
 
+    #[cfg(feature = "mqtt")]
+    pub fn mqtt(&mut self, broker: &mqtt::Broker) -> Arc<mqtt::Client> {
+        self.mqtt
+            .entry(broker.clone())
+            .or_insert_with(|| Arc::new(mqtt::Client::new(broker)))
+            .clone()
+    }
+
     #[cfg(feature = "music")]
     pub fn music(&mut self, client_type: music::ClientType) -> Arc<dyn music::MusicClient> {
         self.music
@@ -155,6 +301,36 @@ impl Clients {
         Ok(client)
     }
 
+    #[cfg(feature = "notifications")]
+    pub fn notification_daemon(&mut self) -> ClientResult<notification_daemon::Client> {
+        let client = if let Some(client) = &self.notification_daemon {
+            client.clone()
+        } else {
+            let client = await_sync(async { notification_daemon::Client::new().await })?;
+            let client = Arc::new(client);
+            self.notification_daemon.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    #[cfg(feature = "reminders")]
+    pub fn reminders(&mut self) -> Arc<reminders::Client> {
+        let timer = self.timer();
+
+        self.reminders
+            .get_or_insert_with(|| Arc::new(reminders::Client::new(timer)))
+            .clone()
+    }
+
+    #[cfg(any(feature = "reminders", feature = "timer"))]
+    pub fn timer(&mut self) -> Arc<timer::Client> {
+        self.timer
+            .get_or_insert_with(|| Arc::new(timer::Client::new()))
+            .clone()
+    }
+
     #[cfg(feature = "tray")]
     pub fn tray(&mut self) -> ClientResult<tray::Client> {
         let client = if let Some(client) = &self.tray {
@@ -182,6 +358,19 @@ impl Clients {
         Ok(client)
     }
 
+    #[cfg(feature = "upower")]
+    pub fn upower_events(&mut self) -> ClientResult<upower::UPowerEventsProxy<'static>> {
+        let client = if let Some(client) = &self.upower_events {
+            client.clone()
+        } else {
+            let client = await_sync(async { upower::create_events_proxy().await })?;
+            self.upower_events.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "volume")]
     pub fn volume(&mut self) -> Arc<volume::Client> {
         self.volume
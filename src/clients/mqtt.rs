@@ -0,0 +1,167 @@
+use crate::clients::reconnect::Backoff;
+use crate::{arc_mut, lock, send, spawn};
+use color_eyre::Result;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::{debug, error};
+
+/// Connection details for an MQTT broker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Broker {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// A message received on a subscribed topic.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub topic: String,
+    pub payload: String,
+}
+
+/// A shared connection to an MQTT broker.
+///
+/// Messages for all subscribed topics are sent to every subscriber,
+/// so consumers should filter them using [`topic_matches`].
+#[derive(Debug)]
+pub struct Client {
+    client: AsyncClient,
+    /// Topic filters to restore after reconnecting.
+    topics: Arc<Mutex<HashSet<String>>>,
+    tx: broadcast::Sender<Message>,
+    _rx: broadcast::Receiver<Message>,
+}
+
+impl Client {
+    pub(crate) fn new(broker: &Broker) -> Self {
+        let client_id = format!("{}-{}", env!("CARGO_PKG_NAME"), std::process::id());
+
+        let mut options = MqttOptions::new(client_id, &broker.host, broker.port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let (Some(username), Some(password)) = (&broker.username, &broker.password) {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, 32);
+        let (tx, rx) = broadcast::channel(64);
+        let topics = arc_mut!(HashSet::<String>::new());
+
+        {
+            let client = client.clone();
+            let tx = tx.clone();
+            let topics = topics.clone();
+            let host = broker.host.clone();
+
+            spawn(async move {
+                let mut backoff = Backoff::default();
+
+                loop {
+                    match event_loop.poll().await {
+                        Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                            debug!("Connected to MQTT broker '{host}'");
+                            backoff.reset();
+
+                            // the session is not persisted, so subscriptions are lost on reconnect.
+                            // the event loop must keep polling, so requests cannot be awaited here.
+                            let topics = lock!(topics).clone();
+                            for topic in topics {
+                                if let Err(err) = client.try_subscribe(&topic, QoS::AtMostOnce) {
+                                    error!("Failed to subscribe to '{topic}': {err:?}");
+                                }
+                            }
+                        }
+                        Ok(Event::Incoming(Packet::Publish(publish))) => {
+                            send!(
+                                tx,
+                                Message {
+                                    topic: publish.topic,
+                                    payload: String::from_utf8_lossy(&publish.payload).into_owned(),
+                                }
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            let delay = backoff.next_delay();
+                            error!("MQTT connection to '{host}' failed: {err}");
+                            debug!("Reconnecting to '{host}' in {delay:?}");
+                            sleep(delay).await;
+                        }
+                    }
+                }
+            });
+        }
+
+        Self {
+            client,
+            topics,
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Subscribes to the topic filter.
+    ///
+    /// Subscribing to an existing filter again is allowed,
+    /// and causes the broker to resend any retained message.
+    pub async fn subscribe(&self, topic: &str) -> Result<()> {
+        lock!(self.topics).insert(topic.to_string());
+        self.client.subscribe(topic, QoS::AtMostOnce).await?;
+
+        Ok(())
+    }
+
+    pub async fn publish(&self, topic: &str, payload: &str, retain: bool) -> Result<()> {
+        self.client
+            .publish(topic, QoS::AtLeastOnce, retain, payload.as_bytes().to_vec())
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn subscribe_messages(&self) -> broadcast::Receiver<Message> {
+        self.tx.subscribe()
+    }
+}
+
+/// Checks whether `topic` matches the topic `filter`,
+/// which may contain `+` and `#` wildcards.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) | (None, None) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(filter), Some(topic)) if filter == topic => {}
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches() {
+        assert!(topic_matches("home/kitchen/temp", "home/kitchen/temp"));
+        assert!(!topic_matches("home/kitchen/temp", "home/kitchen/humidity"));
+
+        assert!(topic_matches("home/+/temp", "home/kitchen/temp"));
+        assert!(!topic_matches("home/+/temp", "home/kitchen/fridge/temp"));
+
+        assert!(topic_matches("home/#", "home/kitchen/fridge/temp"));
+        assert!(topic_matches("#", "home"));
+
+        assert!(!topic_matches("home/kitchen", "home/kitchen/temp"));
+        assert!(!topic_matches("home/kitchen/temp", "home/kitchen"));
+    }
+}
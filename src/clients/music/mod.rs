@@ -1,4 +1,4 @@
-use color_eyre::Result;
+use color_eyre::{Report, Result};
 use std::fmt::Debug;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -20,6 +20,9 @@ pub enum PlayerUpdate {
     /// Triggered at regular intervals while a track is playing.
     /// Used to keep track of the progress through the current track.
     ProgressTick(ProgressTick),
+    /// Triggered when the play queue changes.
+    /// Only sent by players which expose their queue.
+    Queue(Vec<QueueItem>),
 }
 
 #[derive(Clone, Debug)]
@@ -34,6 +37,17 @@ pub struct Track {
     pub cover_path: Option<String>,
 }
 
+/// A track in the player's play queue.
+#[derive(Clone, Debug)]
+pub struct QueueItem {
+    /// Unique ID of the entry, which stays the same if the queue is reordered.
+    pub id: u64,
+    /// Position of the entry in the queue, starting at zero.
+    pub position: u32,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub enum PlayerState {
     #[default]
@@ -64,6 +78,19 @@ pub trait MusicClient: Debug + Send + Sync {
 
     fn set_volume_percent(&self, vol: u8) -> Result<()>;
     fn seek(&self, duration: Duration) -> Result<()>;
+    /// Seeks forwards or backwards by the number of seconds
+    /// from the current position.
+    fn seek_relative(&self, offset: i64) -> Result<()>;
+
+    /// Starts playing the queue entry with the ID.
+    fn play_queue_item(&self, _id: u64) -> Result<()> {
+        Err(Report::msg("Player does not support queue control"))
+    }
+
+    /// Removes the entry with the ID from the queue.
+    fn remove_queue_item(&self, _id: u64) -> Result<()> {
+        Err(Report::msg("Player does not support queue control"))
+    }
 
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate>;
 }
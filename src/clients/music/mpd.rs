@@ -1,11 +1,12 @@
 use super::{
-    MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, Track, TICK_INTERVAL_MS,
+    MusicClient, PlayerState, PlayerUpdate, ProgressTick, QueueItem, Status, Track,
+    TICK_INTERVAL_MS,
 };
 use crate::{await_sync, send, spawn, Ironbar};
 use color_eyre::Report;
 use color_eyre::Result;
 use mpd_client::client::{ConnectionEvent, Subsystem};
-use mpd_client::commands::{self, SeekMode};
+use mpd_client::commands::{self, SeekMode, SongId};
 use mpd_client::responses::{PlayState, Song};
 use mpd_client::tag::Tag;
 use mpd_utils::{mpd_client, PersistentClient};
@@ -49,6 +50,7 @@ impl Client {
                 Self::send_update(&client, &tx, &music_dir)
                     .await
                     .expect("Failed to send update");
+                Self::send_queue_update(&client, &tx).await;
 
                 while let Ok(change) = client_rx.recv().await {
                     debug!("Received state change: {change:?}");
@@ -60,6 +62,10 @@ impl Client {
                             .await
                             .expect("Failed to send update");
                     }
+
+                    if let ConnectionEvent::SubsystemChange(Subsystem::Queue) = *change {
+                        Self::send_queue_update(&client, &tx).await;
+                    }
                 }
             });
         }
@@ -103,6 +109,23 @@ impl Client {
         Ok(())
     }
 
+    async fn send_queue_update(client: &PersistentClient, tx: &broadcast::Sender<PlayerUpdate>) {
+        if let Ok(queue) = client.command(commands::Queue).await {
+            let queue = queue
+                .into_iter()
+                .map(|entry| QueueItem {
+                    id: entry.id.0,
+                    position: entry.position.0 as u32,
+                    title: entry.song.title().map(ToString::to_string),
+                    artist: Some(entry.song.artists().join(", "))
+                        .filter(|artist| !artist.is_empty()),
+                })
+                .collect();
+
+            send!(tx, PlayerUpdate::Queue(queue));
+        }
+    }
+
     async fn send_tick_update(client: &PersistentClient, tx: &broadcast::Sender<PlayerUpdate>) {
         let status = client.command(commands::Status).await;
 
@@ -144,12 +167,33 @@ impl MusicClient for Client {
         command!(self, commands::Seek(SeekMode::Absolute(duration)))
     }
 
+    fn seek_relative(&self, offset: i64) -> Result<()> {
+        let duration = Duration::from_secs(offset.unsigned_abs());
+
+        let mode = if offset < 0 {
+            SeekMode::Backward(duration)
+        } else {
+            SeekMode::Forward(duration)
+        };
+
+        command!(self, commands::Seek(mode))
+    }
+
+    fn play_queue_item(&self, id: u64) -> Result<()> {
+        command!(self, commands::Play::song(SongId(id)))
+    }
+
+    fn remove_queue_item(&self, id: u64) -> Result<()> {
+        command!(self, commands::Delete::id(SongId(id)))
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         let rx = self.tx.subscribe();
         await_sync(async move {
             Self::send_update(&self.client, &self.tx, &self.music_dir)
                 .await
                 .expect("to be able to send update");
+            Self::send_queue_update(&self.client, &self.tx).await;
         });
         rx
     }
@@ -304,6 +304,15 @@ impl MusicClient for Client {
         Ok(())
     }
 
+    fn seek_relative(&self, offset: i64) -> Result<()> {
+        if let Some(player) = Self::get_player(self) {
+            player.seek(offset.saturating_mul(1_000_000))?;
+        } else {
+            error!("Could not find player");
+        }
+        Ok(())
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         debug!("Creating new subscription");
         let rx = self.tx.subscribe();
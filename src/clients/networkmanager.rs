@@ -1,22 +1,39 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use futures_signals::signal::{Mutable, MutableSignalCloned};
-use tracing::error;
+use tracing::debug;
 use zbus::blocking::fdo::PropertiesProxy;
 use zbus::blocking::Connection;
 use zbus::{
     dbus_proxy,
     names::InterfaceName,
-    zvariant::{ObjectPath, Str},
+    zvariant::{Array, ObjectPath, OwnedObjectPath, OwnedValue, Str, Value},
+    CacheProperties,
 };
 
+use crate::clients::reconnect;
 use crate::{register_fallible_client, spawn_blocking};
 
 const DBUS_BUS: &str = "org.freedesktop.NetworkManager";
 const DBUS_PATH: &str = "/org/freedesktop/NetworkManager";
 const DBUS_INTERFACE: &str = "org.freedesktop.NetworkManager";
 
+/// `NM_DEVICE_TYPE_WIFI`
+const DEVICE_TYPE_WIFI: u32 = 2;
+/// `NM_802_11_AP_FLAGS_PRIVACY`
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+/// `NM_802_11_AP_SEC_KEY_MGMT_PSK`
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+/// `NM_802_11_AP_SEC_KEY_MGMT_802_1X`
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+/// `NM_802_11_AP_SEC_KEY_MGMT_SAE`
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+/// `NM_802_11_AP_SEC_KEY_MGMT_OWE`
+const AP_SEC_KEY_MGMT_OWE: u32 = 0x800;
+
 #[derive(Debug)]
 pub struct Client {
     client_state: Mutable<ClientState>,
@@ -43,22 +60,192 @@ pub enum ClientState {
 )]
 trait NetworkManagerDbus {
     #[dbus_proxy(property)]
-    fn active_connections(&self) -> Result<Vec<ObjectPath>>;
+    fn active_connections(&self) -> Result<Vec<ObjectPath<'_>>>;
 
     #[dbus_proxy(property)]
-    fn devices(&self) -> Result<Vec<ObjectPath>>;
+    fn devices(&self) -> Result<Vec<ObjectPath<'_>>>;
 
     #[dbus_proxy(property)]
     fn networking_enabled(&self) -> Result<bool>;
 
     #[dbus_proxy(property)]
-    fn primary_connection(&self) -> Result<ObjectPath>;
+    fn primary_connection(&self) -> Result<ObjectPath<'_>>;
 
     #[dbus_proxy(property)]
-    fn primary_connection_type(&self) -> Result<Str>;
+    fn primary_connection_type(&self) -> Result<Str<'_>>;
 
     #[dbus_proxy(property)]
     fn wireless_enabled(&self) -> Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn set_wireless_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn set_wwan_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Device"
+)]
+trait Device {
+    #[dbus_proxy(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Device.Wireless"
+)]
+trait WirelessDevice {
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    #[dbus_proxy(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.AccessPoint"
+)]
+trait AccessPointDbus {
+    #[dbus_proxy(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    #[dbus_proxy(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+
+    #[dbus_proxy(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+
+    #[dbus_proxy(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+
+    #[dbus_proxy(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Connection.Active"
+)]
+trait ActiveConnection {
+    #[dbus_proxy(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property, name = "Type")]
+    fn connection_type(&self) -> zbus::Result<String>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Settings.Connection"
+)]
+trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
+/// A visible Wi-Fi network.
+///
+/// Access points sharing an SSID are merged into one network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessPoint {
+    pub ssid: String,
+    /// Signal strength, as a percentage.
+    pub strength: u8,
+    pub secured: bool,
+    /// Whether a password is needed to create a connection for the network.
+    pub needs_password: bool,
+    /// Whether there is a saved connection for the network.
+    pub saved: bool,
+    pub active: bool,
+}
+
+/// The key management used to connect to a Wi-Fi network,
+/// as the `key-mgmt` connection setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyMgmt {
+    Wep,
+    WpaPsk,
+    Sae,
+    Owe,
+}
+
+impl KeyMgmt {
+    /// Chooses the key management for an access point from its flags,
+    /// or `None` for open networks.
+    ///
+    /// Networks which offer both WPA2 and WPA3 use WPA2,
+    /// which NetworkManager upgrades to WPA3 where the device supports it.
+    fn from_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Result<Option<Self>> {
+        let security = wpa_flags | rsn_flags;
+
+        if security & AP_SEC_KEY_MGMT_PSK != 0 {
+            Ok(Some(Self::WpaPsk))
+        } else if security & AP_SEC_KEY_MGMT_SAE != 0 {
+            Ok(Some(Self::Sae))
+        } else if security & AP_SEC_KEY_MGMT_OWE != 0 {
+            Ok(Some(Self::Owe))
+        } else if security & AP_SEC_KEY_MGMT_802_1X != 0 {
+            Err(eyre!(
+                "Enterprise (802.1X) networks must be set up outside of Ironbar"
+            ))
+        } else if security == 0 && flags & AP_FLAGS_PRIVACY != 0 {
+            Ok(Some(Self::Wep))
+        } else {
+            Ok(None)
+        }
+    }
+
+    const fn needs_password(self) -> bool {
+        !matches!(self, Self::Owe)
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            // WEP keys are set separately, with no key management
+            Self::Wep => "none",
+            Self::WpaPsk => "wpa-psk",
+            Self::Sae => "sae",
+            Self::Owe => "owe",
+        }
+    }
+}
+
+/// Wi-Fi and VPN state, which is not covered by [`ClientState`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkState {
+    pub airplane_mode: bool,
+    /// Signal strength of the active Wi-Fi network, as a percentage.
+    pub strength: Option<u8>,
+    /// Visible Wi-Fi networks, with the active network first
+    /// and the rest sorted by strength.
+    pub access_points: Vec<AccessPoint>,
+    /// Names of the active VPN connections.
+    pub vpns: Vec<String>,
 }
 
 impl Client {
@@ -129,6 +316,271 @@ impl Client {
     pub fn subscribe(&self) -> MutableSignalCloned<ClientState> {
         self.client_state.signal_cloned()
     }
+
+    async fn proxy(&self) -> Result<NetworkManagerDbusProxy<'static>> {
+        Ok(
+            NetworkManagerDbusProxy::builder(self.dbus_connection.inner())
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?,
+        )
+    }
+
+    /// Gets the first Wi-Fi device, if there is one.
+    async fn wifi_device(&self) -> Result<Option<WirelessDeviceProxy<'static>>> {
+        let connection = self.dbus_connection.inner();
+
+        for path in self.proxy().await?.devices().await? {
+            let path = path.to_string();
+
+            let device = DeviceProxy::builder(connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            if device.device_type().await? == DEVICE_TYPE_WIFI {
+                let device = WirelessDeviceProxy::builder(connection)
+                    .path(path)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                return Ok(Some(device));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn access_point(&self, path: &str) -> Result<AccessPointDbusProxy<'static>> {
+        Ok(AccessPointDbusProxy::builder(self.dbus_connection.inner())
+            .path(path.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?)
+    }
+
+    /// Gets the saved Wi-Fi connections, keyed by SSID.
+    async fn saved_connections(&self) -> Result<HashMap<Vec<u8>, OwnedObjectPath>> {
+        let connection = self.dbus_connection.inner();
+        let settings = SettingsProxy::new(connection).await?;
+
+        let mut saved = HashMap::new();
+
+        for path in settings.list_connections().await? {
+            let settings = SettingsConnectionProxy::builder(connection)
+                .path(path.clone())?
+                .build()
+                .await?
+                .get_settings()
+                .await?;
+
+            let ssid = settings
+                .get("802-11-wireless")
+                .and_then(|wireless| wireless.get("ssid"))
+                .and_then(|ssid| ssid.downcast_ref::<Array>())
+                .map(|ssid| {
+                    ssid.get()
+                        .iter()
+                        .filter_map(|byte| byte.downcast_ref::<u8>().copied())
+                        .collect::<Vec<_>>()
+                });
+
+            if let Some(ssid) = ssid {
+                saved.insert(ssid, path);
+            }
+        }
+
+        Ok(saved)
+    }
+
+    /// Gets the current Wi-Fi and VPN state.
+    pub async fn network_state(&self) -> Result<NetworkState> {
+        let proxy = self.proxy().await?;
+
+        let mut vpns = vec![];
+        for path in proxy.active_connections().await? {
+            let active = ActiveConnectionProxy::builder(self.dbus_connection.inner())
+                .path(path.to_string())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            if matches!(
+                active.connection_type().await?.as_str(),
+                "vpn" | "wireguard"
+            ) {
+                vpns.push(active.id().await?);
+            }
+        }
+
+        let mut state = NetworkState {
+            airplane_mode: !proxy.wireless_enabled().await?,
+            vpns,
+            ..NetworkState::default()
+        };
+
+        let Some(device) = self.wifi_device().await? else {
+            return Ok(state);
+        };
+
+        let active = device.active_access_point().await?;
+        let saved = self.saved_connections().await?;
+
+        let mut access_points: Vec<AccessPoint> = vec![];
+
+        for path in device.get_all_access_points().await? {
+            let access_point = self.access_point(path.as_str()).await?;
+
+            // hidden networks do not broadcast an ssid
+            let ssid = access_point.ssid().await?;
+            if ssid.is_empty() {
+                continue;
+            }
+
+            let strength = access_point.strength().await?;
+            let is_active = path == active;
+
+            if is_active {
+                state.strength = Some(strength);
+            }
+
+            let name = String::from_utf8_lossy(&ssid).to_string();
+
+            if let Some(existing) = access_points.iter_mut().find(|ap| ap.ssid == name) {
+                existing.strength = existing.strength.max(strength);
+                existing.active |= is_active;
+                continue;
+            }
+
+            let flags = access_point.flags().await?;
+            let wpa_flags = access_point.wpa_flags().await?;
+            let rsn_flags = access_point.rsn_flags().await?;
+
+            let secured = flags & AP_FLAGS_PRIVACY != 0 || wpa_flags != 0 || rsn_flags != 0;
+            let needs_password = KeyMgmt::from_flags(flags, wpa_flags, rsn_flags)
+                .is_ok_and(|key_mgmt| key_mgmt.is_some_and(KeyMgmt::needs_password));
+
+            access_points.push(AccessPoint {
+                ssid: name,
+                strength,
+                secured,
+                needs_password,
+                saved: saved.contains_key(&ssid),
+                active: is_active,
+            });
+        }
+
+        access_points.sort_by(|a, b| {
+            b.active
+                .cmp(&a.active)
+                .then_with(|| b.strength.cmp(&a.strength))
+        });
+
+        state.access_points = access_points;
+
+        Ok(state)
+    }
+
+    /// Connects to the Wi-Fi network with the given SSID.
+    ///
+    /// If there is a saved connection for the network and no password is given,
+    /// it is activated. Otherwise a new connection is created,
+    /// with the key management advertised by the network
+    /// and `password` as its key if set.
+    /// NetworkManager asks the running secret agent for any other missing secrets.
+    pub async fn connect_wifi(&self, ssid: &str, password: Option<&str>) -> Result<()> {
+        let proxy = self.proxy().await?;
+
+        let device = self
+            .wifi_device()
+            .await?
+            .ok_or_else(|| eyre!("No Wi-Fi device found"))?;
+
+        // use the strongest access point for the network
+        let mut best: Option<(u8, OwnedObjectPath, AccessPointDbusProxy)> = None;
+        for path in device.get_all_access_points().await? {
+            let access_point = self.access_point(path.as_str()).await?;
+
+            if access_point.ssid().await? != ssid.as_bytes() {
+                continue;
+            }
+
+            let strength = access_point.strength().await?;
+            if best.as_ref().is_none_or(|(best, _, _)| strength > *best) {
+                best = Some((strength, path, access_point));
+            }
+        }
+
+        let (_, access_point, access_point_proxy) =
+            best.ok_or_else(|| eyre!("Network '{ssid}' not found"))?;
+        let device_path = device.inner().path();
+
+        let saved = self.saved_connections().await?;
+
+        match (saved.get(ssid.as_bytes()), password) {
+            (Some(connection), None) => {
+                debug!("Activating saved connection for '{ssid}'");
+                proxy
+                    .activate_connection(connection, device_path, &access_point)
+                    .await?;
+            }
+            (_, password) => {
+                debug!("Creating connection for '{ssid}'");
+
+                let mut settings = HashMap::new();
+                settings.insert(
+                    "802-11-wireless",
+                    HashMap::from([("ssid", Value::from(ssid.as_bytes().to_vec()))]),
+                );
+
+                let key_mgmt = KeyMgmt::from_flags(
+                    access_point_proxy.flags().await?,
+                    access_point_proxy.wpa_flags().await?,
+                    access_point_proxy.rsn_flags().await?,
+                )?;
+
+                if let Some(key_mgmt) = key_mgmt {
+                    let mut security =
+                        HashMap::from([("key-mgmt", Value::from(key_mgmt.as_str()))]);
+
+                    match (key_mgmt, password) {
+                        (KeyMgmt::Wep, Some(password)) => {
+                            security.insert("wep-key0", Value::from(password));
+                            // NM_WEP_KEY_TYPE_KEY, for hex or ASCII keys
+                            security.insert("wep-key-type", Value::from(1_u32));
+                        }
+                        (KeyMgmt::WpaPsk | KeyMgmt::Sae, Some(password)) => {
+                            security.insert("psk", Value::from(password));
+                        }
+                        _ => {}
+                    }
+
+                    settings.insert("802-11-wireless-security", security);
+                }
+
+                proxy
+                    .add_and_activate_connection(settings, device_path, &access_point)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disables or re-enables all wireless radios.
+    pub async fn set_airplane_mode(&self, enabled: bool) -> Result<()> {
+        let proxy = self.proxy().await?;
+        proxy.set_wireless_enabled(!enabled).await?;
+
+        // fails on devices without mobile broadband
+        if let Err(err) = proxy.set_wwan_enabled(!enabled).await {
+            debug!("Failed to set WWAN state: {err:?}");
+        }
+
+        Ok(())
+    }
 }
 
 pub fn create_client() -> Result<Arc<Client>> {
@@ -136,9 +588,7 @@ pub fn create_client() -> Result<Arc<Client>> {
     {
         let client = client.clone();
         spawn_blocking(move || {
-            if let Err(error) = client.run() {
-                error!("{}", error);
-            };
+            reconnect::run_blocking("NetworkManager", |_| client.run());
         });
     }
     Ok(client)
@@ -167,3 +617,40 @@ fn determine_state(
 }
 
 register_fallible_client!(Client, network_manager);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_mgmt_from_flags() {
+        let key_mgmt = |flags, wpa_flags, rsn_flags| {
+            KeyMgmt::from_flags(flags, wpa_flags, rsn_flags)
+                .ok()
+                .flatten()
+        };
+
+        assert_eq!(key_mgmt(0, 0, 0), None);
+        assert_eq!(key_mgmt(AP_FLAGS_PRIVACY, 0, 0), Some(KeyMgmt::Wep));
+        assert_eq!(
+            key_mgmt(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_PSK),
+            Some(KeyMgmt::WpaPsk)
+        );
+        assert_eq!(
+            key_mgmt(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_SAE),
+            Some(KeyMgmt::Sae)
+        );
+        // WPA2/WPA3 transition mode
+        assert_eq!(
+            key_mgmt(
+                AP_FLAGS_PRIVACY,
+                0,
+                AP_SEC_KEY_MGMT_PSK | AP_SEC_KEY_MGMT_SAE
+            ),
+            Some(KeyMgmt::WpaPsk)
+        );
+        assert_eq!(key_mgmt(0, 0, AP_SEC_KEY_MGMT_OWE), Some(KeyMgmt::Owe));
+
+        assert!(KeyMgmt::from_flags(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_802_1X).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{lock, register_fallible_client, send};
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+use zbus::zvariant::OwnedValue;
+use zbus::{dbus_interface, SignalContext};
+
+const SERVICE: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// Version of the notifications specification implemented.
+const SPEC_VERSION: &str = "1.2";
+
+/// Reasons sent with the `NotificationClosed` signal.
+#[derive(Debug, Clone, Copy)]
+#[repr(u32)]
+enum CloseReason {
+    /// Dismissed by the user.
+    Dismissed = 2,
+    /// Closed by a call to `CloseNotification`.
+    Closed = 3,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl From<u8> for Urgency {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Low,
+            2 => Self::Critical,
+            _ => Self::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// Icon name or path, if provided.
+    pub icon: Option<String>,
+    pub summary: String,
+    pub body: String,
+    /// Actions as `(key, label)` pairs.
+    /// The `default` action is invoked by clicking the notification.
+    pub actions: Vec<(String, String)>,
+    pub urgency: Urgency,
+    /// The number of milliseconds the notification should be shown for.
+    /// `-1` leaves this up to the server, and `0` means it should not expire.
+    pub timeout: i32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A notification was received.
+    /// If it replaces an existing notification, it has the same ID.
+    Add(Notification),
+    /// A notification was closed.
+    Remove(u32),
+}
+
+#[derive(Debug)]
+struct State {
+    notifications: Vec<Notification>,
+    next_id: u32,
+}
+
+#[derive(Debug)]
+struct Server {
+    state: Arc<Mutex<State>>,
+    tx: broadcast::Sender<Event>,
+}
+
+#[dbus_interface(name = "org.freedesktop.Notifications")]
+impl Server {
+    fn get_capabilities(&self) -> Vec<String> {
+        ["actions", "body", "persistence"]
+            .map(ToString::to_string)
+            .to_vec()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let mut state = lock!(self.state);
+
+        let id = if replaces_id != 0
+            && state
+                .notifications
+                .iter()
+                .any(|notification| notification.id == replaces_id)
+        {
+            replaces_id
+        } else {
+            // IDs must never be zero
+            state.next_id = state.next_id.wrapping_add(1).max(1);
+            state.next_id
+        };
+
+        let icon = Some(app_icon).filter(|icon| !icon.is_empty()).or_else(|| {
+            hints
+                .get("image-path")
+                .and_then(|value| <&str>::try_from(value).ok())
+                .map(ToString::to_string)
+        });
+
+        let urgency = hints
+            .get("urgency")
+            .and_then(|value| u8::try_from(value).ok())
+            .map(Urgency::from)
+            .unwrap_or_default();
+
+        let notification = Notification {
+            id,
+            app_name,
+            icon,
+            summary,
+            body,
+            actions: actions
+                .chunks_exact(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect(),
+            urgency,
+            timeout: expire_timeout,
+        };
+
+        debug!("Received notification: {notification:?}");
+
+        match state
+            .notifications
+            .iter_mut()
+            .find(|existing| existing.id == id)
+        {
+            Some(existing) => *existing = notification.clone(),
+            None => state.notifications.push(notification.clone()),
+        }
+
+        send!(self.tx, Event::Add(notification));
+        id
+    }
+
+    async fn close_notification(
+        &self,
+        id: u32,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        if remove(&self.state, &self.tx, id) {
+            Self::notification_closed(&ctxt, id, CloseReason::Closed as u32).await?;
+        }
+
+        Ok(())
+    }
+
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            String::from("ironbar"),
+            String::from("ironbar"),
+            String::from(env!("CARGO_PKG_VERSION")),
+            String::from(SPEC_VERSION),
+        )
+    }
+
+    #[dbus_interface(signal)]
+    async fn notification_closed(
+        ctxt: &SignalContext<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn action_invoked(
+        ctxt: &SignalContext<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Removes the notification with the ID from the state,
+/// returning whether it existed.
+fn remove(state: &Mutex<State>, tx: &broadcast::Sender<Event>, id: u32) -> bool {
+    let mut state = lock!(state);
+
+    let len = state.notifications.len();
+    state
+        .notifications
+        .retain(|notification| notification.id != id);
+
+    let removed = state.notifications.len() != len;
+    if removed {
+        send!(tx, Event::Remove(id));
+    }
+
+    removed
+}
+
+/// A notification daemon, implementing the
+/// [Desktop Notifications Specification](https://specifications.freedesktop.org/notification-spec/latest/).
+///
+/// Only one daemon can run at once,
+/// so this fails if another daemon such as SwayNC or mako is running.
+#[derive(Debug)]
+pub struct Client {
+    connection: zbus::Connection,
+    state: Arc<Mutex<State>>,
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let (tx, rx) = broadcast::channel(32);
+
+        let state = Arc::new(Mutex::new(State {
+            notifications: vec![],
+            next_id: 0,
+        }));
+
+        let server = Server {
+            state: state.clone(),
+            tx: tx.clone(),
+        };
+
+        let connection = zbus::ConnectionBuilder::session()?
+            .name(SERVICE)?
+            .serve_at(PATH, server)?
+            .build()
+            .await
+            .wrap_err("Failed to start notification daemon. Is another one already running?")?;
+
+        debug!("Started notification daemon");
+
+        Ok(Self {
+            connection,
+            state,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// Gets all open notifications, oldest first.
+    pub fn notifications(&self) -> Vec<Notification> {
+        lock!(self.state).notifications.clone()
+    }
+
+    /// Closes the notification, as dismissed by the user.
+    pub async fn dismiss(&self, id: u32) {
+        if remove(&self.state, &self.tx, id) {
+            if let Err(err) = self.notification_closed(id).await {
+                error!("{err:?}");
+            }
+        }
+    }
+
+    /// Closes all notifications, as dismissed by the user.
+    pub async fn dismiss_all(&self) {
+        let ids = lock!(self.state)
+            .notifications
+            .iter()
+            .map(|notification| notification.id)
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            self.dismiss(id).await;
+        }
+    }
+
+    /// Invokes the action on the notification,
+    /// which is then dismissed.
+    pub async fn invoke_action(&self, id: u32, action: &str) {
+        let result = async {
+            let iface = self
+                .connection
+                .object_server()
+                .interface::<_, Server>(PATH)
+                .await?;
+
+            Server::action_invoked(iface.signal_context(), id, action).await
+        };
+
+        if let Err(err) = result.await {
+            error!("{err:?}");
+        }
+
+        self.dismiss(id).await;
+    }
+
+    async fn notification_closed(&self, id: u32) -> zbus::Result<()> {
+        let iface = self
+            .connection
+            .object_server()
+            .interface::<_, Server>(PATH)
+            .await?;
+
+        Server::notification_closed(iface.signal_context(), id, CloseReason::Dismissed as u32).await
+    }
+}
+
+register_fallible_client!(Client, notification_daemon);
@@ -0,0 +1,142 @@
+//! Helpers for keeping connections to external services alive,
+//! such as compositor IPC sockets and D-Bus daemons,
+//! so that restarting a service does not break modules until Ironbar restarts.
+
+use color_eyre::Result;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Delay before the first reconnection attempt.
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum delay between reconnection attempts.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Connections which stay up for at least this long
+/// are considered healthy, resetting the backoff.
+const HEALTHY_DURATION: Duration = Duration::from_secs(30);
+
+/// Exponential backoff between reconnection attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    delay: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(INITIAL_DELAY, MAX_DELAY)
+    }
+}
+
+impl Backoff {
+    pub const fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            delay: initial,
+        }
+    }
+
+    /// Gets the delay before the next attempt,
+    /// doubling it for the attempt after, up to the maximum.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(self.max);
+        delay
+    }
+
+    /// Resets the delay after a successful connection.
+    pub fn reset(&mut self) {
+        self.delay = self.initial;
+    }
+
+    /// Resets the delay if the connection started at `connected`
+    /// stayed up long enough to be considered healthy.
+    fn reset_if_healthy(&mut self, connected: Instant) {
+        if connected.elapsed() >= HEALTHY_DURATION {
+            self.reset();
+        }
+    }
+}
+
+/// Runs a connection task forever,
+/// restarting it with exponential backoff each time it exits,
+/// whether it failed or the service closed the connection.
+///
+/// The task is passed `true` when reconnecting,
+/// so it can re-send any state missed while disconnected.
+pub async fn run<F, Fut>(name: &str, mut connect: F)
+where
+    F: FnMut(bool) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut backoff = Backoff::default();
+    let mut reconnecting = false;
+
+    loop {
+        let connected = Instant::now();
+        let result = connect(reconnecting).await;
+
+        backoff.reset_if_healthy(connected);
+        let delay = backoff.next_delay();
+
+        match result {
+            Ok(()) => info!("{name} connection closed, reconnecting in {delay:?}"),
+            Err(err) => warn!("{name} connection failed, reconnecting in {delay:?}: {err:?}"),
+        }
+
+        sleep(delay).await;
+        reconnecting = true;
+    }
+}
+
+/// Blocking equivalent of [`run`],
+/// for services which are only accessible through blocking APIs.
+///
+/// This must be called from a blocking thread.
+pub fn run_blocking<F>(name: &str, mut connect: F)
+where
+    F: FnMut(bool) -> Result<()>,
+{
+    let mut backoff = Backoff::default();
+    let mut reconnecting = false;
+
+    loop {
+        let connected = Instant::now();
+        let result = connect(reconnecting);
+
+        backoff.reset_if_healthy(connected);
+        let delay = backoff.next_delay();
+
+        match result {
+            Ok(()) => info!("{name} connection closed, reconnecting in {delay:?}"),
+            Err(err) => warn!("{name} connection failed, reconnecting in {delay:?}: {err:?}"),
+        }
+
+        std::thread::sleep(delay);
+        reconnecting = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(2));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(4));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+}
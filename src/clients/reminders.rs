@@ -0,0 +1,495 @@
+use crate::clients::activity::{Activity, Tracker};
+use crate::clients::timer::{self, Phase, TimerState};
+use crate::clients::wayland::{self, ToplevelEvent};
+use crate::config::parse_duration;
+use crate::{lock, send, spawn};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeDelta};
+use color_eyre::{Report, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::time::{interval, sleep};
+use tracing::warn;
+use zbus::{dbus_proxy, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, zbus::zvariant::Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    pub id: usize,
+    pub message: String,
+    pub due: DateTime<Local>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The full list of upcoming reminders,
+    /// sorted by due time.
+    Reminders(Vec<Reminder>),
+    /// A reminder has become due.
+    ///
+    /// The flag is shared between all receivers,
+    /// so that only one module runs its actions
+    /// when there are multiple reminders modules.
+    Due(Reminder, Arc<AtomicBool>),
+    /// The focus session has been started, paused, resumed or replaced.
+    /// This is `None` once it has ended.
+    Focus(Option<TimerState>),
+    /// A focus session has started.
+    ///
+    /// The flag is shared as with [`Event::Due`].
+    FocusStarted(Arc<AtomicBool>),
+    /// The focus session has ended.
+    /// The first value is `true` if it ran to completion,
+    /// or `false` if it was stopped early.
+    ///
+    /// The flag is shared as with [`Event::Due`].
+    FocusEnded(bool, Arc<AtomicBool>),
+    /// Today's activity statistics have been updated.
+    Activity(Activity),
+}
+
+/// The default length of a focus session,
+/// used when neither the command nor a module specifies one.
+const DEFAULT_FOCUS_DURATION: Duration = Duration::from_secs(25 * 60);
+
+/// How often activity statistics are saved
+/// and sent to modules.
+const ACTIVITY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub struct Client {
+    reminders: Arc<Mutex<Vec<Reminder>>>,
+    next_id: AtomicUsize,
+
+    /// Focus sessions run as a phase of the shared timer.
+    timer: Arc<timer::Client>,
+    focus_duration: Mutex<Duration>,
+
+    /// Records daily activity statistics,
+    /// once started by a module.
+    tracker: Arc<Mutex<Option<Tracker>>>,
+
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+impl Client {
+    pub fn new(timer: Arc<timer::Client>) -> Self {
+        let (tx, rx) = broadcast::channel(32);
+        let tracker = Arc::new(Mutex::new(None));
+
+        watch_focus(&timer, tracker.clone(), tx.clone());
+
+        Self {
+            reminders: Arc::new(Mutex::new(vec![])),
+            next_id: AtomicUsize::new(0),
+            timer,
+            focus_duration: Mutex::new(DEFAULT_FOCUS_DURATION),
+            tracker,
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Schedules a reminder with `message`,
+    /// which becomes due after `duration`.
+    pub fn add(&self, message: String, duration: Duration) -> Reminder {
+        let reminder = Reminder {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            message,
+            due: Local::now() + TimeDelta::from_std(duration).unwrap_or_default(),
+        };
+
+        {
+            let mut reminders = lock!(self.reminders);
+            reminders.push(reminder.clone());
+            reminders.sort_by_key(|reminder| reminder.due);
+            send!(self.tx, Event::Reminders(reminders.clone()));
+        }
+
+        let reminders = self.reminders.clone();
+        let tx = self.tx.clone();
+        let id = reminder.id;
+
+        spawn(async move {
+            sleep(duration).await;
+
+            let mut reminders = lock!(reminders);
+
+            // the reminder may have been cancelled in the meantime
+            if let Some(index) = reminders.iter().position(|reminder| reminder.id == id) {
+                let reminder = reminders.remove(index);
+
+                send!(tx, Event::Due(reminder, Arc::new(AtomicBool::new(false))));
+                send!(tx, Event::Reminders(reminders.clone()));
+            }
+        });
+
+        reminder
+    }
+
+    /// Cancels the reminder with `id`, if it is still upcoming.
+    pub fn cancel(&self, id: usize) {
+        let mut reminders = lock!(self.reminders);
+
+        if let Some(index) = reminders.iter().position(|reminder| reminder.id == id) {
+            reminders.remove(index);
+            send!(self.tx, Event::Reminders(reminders.clone()));
+        }
+    }
+
+    /// Starts a focus session lasting `duration`,
+    /// or the default focus duration if not set.
+    ///
+    /// This replaces whatever the timer is running.
+    /// An existing focus session is replaced without ending it.
+    /// Returns the time the session ends.
+    pub fn start_focus(&self, duration: Option<Duration>) -> DateTime<Local> {
+        let duration = duration.unwrap_or_else(|| *lock!(self.focus_duration));
+        self.timer.start(Phase::Focus, duration);
+
+        Local::now() + TimeDelta::from_std(duration).unwrap_or_default()
+    }
+
+    /// Stops the current focus session early, if there is one.
+    pub fn stop_focus(&self) {
+        if self.focus().is_some() {
+            self.timer.stop();
+        }
+    }
+
+    /// Gets the state of the current focus session,
+    /// if there is one.
+    pub fn focus(&self) -> Option<TimerState> {
+        self.timer
+            .state()
+            .filter(|state| state.phase == Phase::Focus)
+    }
+
+    /// Sets the focus session length used when none is specified.
+    pub fn set_focus_duration(&self, duration: Duration) {
+        *lock!(self.focus_duration) = duration;
+    }
+
+    /// Starts recording daily activity statistics,
+    /// if this has not already been started by another module.
+    ///
+    /// The user is counted as idle after `idle_timeout` without input.
+    /// Foreground time is recorded per application if `track_apps` is set.
+    pub fn start_tracking(
+        &self,
+        wayland: Arc<wayland::Client>,
+        idle_timeout: Duration,
+        track_apps: bool,
+    ) {
+        {
+            let mut tracker = lock!(self.tracker);
+            if tracker.is_some() {
+                return;
+            }
+
+            tracker.replace(Tracker::new(Local::now().date_naive(), track_apps));
+        }
+
+        {
+            let tracker = self.tracker.clone();
+            let wayland = wayland.clone();
+
+            spawn(async move {
+                let mut rx = match wayland.subscribe_idle(idle_timeout) {
+                    Ok(rx) => rx,
+                    Err(err) => {
+                        warn!("{err:?}");
+                        warn!("Idle time cannot be detected, so all time will count as active");
+                        return;
+                    }
+                };
+
+                loop {
+                    match rx.recv().await {
+                        Ok(idle) => with_tracker(&tracker, |tracker, now, today| {
+                            tracker.set_idle(now, today, idle);
+                        }),
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        if track_apps {
+            let tracker = self.tracker.clone();
+
+            spawn(async move {
+                let mut rx = wayland.subscribe_toplevels();
+
+                let focused = wayland
+                    .toplevel_info_all()
+                    .into_iter()
+                    .find(|info| info.focused);
+
+                let mut current = focused.as_ref().map(|info| info.id);
+                let app = focused.map(|info| info.app_id);
+
+                with_tracker(&tracker, |tracker, now, today| {
+                    tracker.set_app(now, today, app.filter(|app| !app.is_empty()));
+                });
+
+                while let Ok(event) = rx.recv().await {
+                    let app = match event {
+                        ToplevelEvent::Update(info) if info.focused => {
+                            current = Some(info.id);
+                            Some(info.app_id).filter(|app| !app.is_empty())
+                        }
+                        ToplevelEvent::Update(info) | ToplevelEvent::Remove(info)
+                            if current == Some(info.id) =>
+                        {
+                            current = None;
+                            None
+                        }
+                        _ => continue,
+                    };
+
+                    with_tracker(&tracker, |tracker, now, today| {
+                        tracker.set_app(now, today, app);
+                    });
+                }
+            });
+        }
+
+        let tracker = self.tracker.clone();
+        let tx = self.tx.clone();
+
+        spawn(async move {
+            let mut ticker = interval(ACTIVITY_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                let activity = {
+                    let mut tracker = lock!(tracker);
+                    let Some(tracker) = tracker.as_mut() else {
+                        break;
+                    };
+
+                    tracker.update(Instant::now(), Local::now().date_naive());
+                    tracker.save();
+                    tracker.activity()
+                };
+
+                send!(tx, Event::Activity(activity));
+            }
+        });
+    }
+
+    /// Pauses or resumes recording activity statistics.
+    pub fn set_tracking(&self, enabled: bool) {
+        let activity = {
+            let mut tracker = lock!(self.tracker);
+            let Some(tracker) = tracker.as_mut() else {
+                return;
+            };
+
+            if tracker.is_paused() != enabled {
+                return;
+            }
+
+            tracker.set_paused(Instant::now(), Local::now().date_naive(), !enabled);
+            tracker.save();
+            tracker.activity()
+        };
+
+        send!(self.tx, Event::Activity(activity));
+    }
+
+    /// Gets today's activity statistics,
+    /// if recording has been started.
+    pub fn activity(&self) -> Option<Activity> {
+        let mut tracker = lock!(self.tracker);
+
+        tracker.as_mut().map(|tracker| {
+            tracker.update(Instant::now(), Local::now().date_naive());
+            tracker.activity()
+        })
+    }
+
+    pub fn reminders(&self) -> Vec<Reminder> {
+        lock!(self.reminders).clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// Checks whether any module is listening for reminders.
+    pub fn has_subscribers(&self) -> bool {
+        // the client holds one receiver itself
+        self.tx.receiver_count() > 1
+    }
+}
+
+/// Follows the timer for focus sessions starting and ending,
+/// recording them in the activity tracker.
+fn watch_focus(
+    timer: &timer::Client,
+    tracker: Arc<Mutex<Option<Tracker>>>,
+    tx: broadcast::Sender<Event>,
+) {
+    let mut rx = timer.subscribe_client();
+    let mut focusing = timer
+        .state()
+        .is_some_and(|state| state.phase == Phase::Focus);
+
+    spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            match event {
+                timer::Event::State(state) => {
+                    let state = state.filter(|state| state.phase == Phase::Focus);
+
+                    match (focusing, state.is_some()) {
+                        (false, true) => {
+                            with_tracker(&tracker, |tracker, now, today| {
+                                tracker.start_focus(now, today);
+                            });
+
+                            send!(tx, Event::FocusStarted(Arc::new(AtomicBool::new(false))));
+                        }
+                        // stopped, or replaced by another phase
+                        (true, false) => {
+                            with_tracker(&tracker, |tracker, now, today| {
+                                tracker.end_focus(now, today, false);
+                            });
+
+                            send!(
+                                tx,
+                                Event::FocusEnded(false, Arc::new(AtomicBool::new(false)))
+                            );
+                        }
+                        _ => {}
+                    }
+
+                    if focusing || state.is_some() {
+                        focusing = state.is_some();
+                        send!(tx, Event::Focus(state));
+                    }
+                }
+                // sent before the timer moves on,
+                // so the session is no longer counted as stopped
+                timer::Event::Finished(Phase::Focus, _) => {
+                    focusing = false;
+
+                    with_tracker(&tracker, |tracker, now, today| {
+                        tracker.end_focus(now, today, true);
+                    });
+
+                    send!(
+                        tx,
+                        Event::FocusEnded(true, Arc::new(AtomicBool::new(false)))
+                    );
+                    send!(tx, Event::Focus(None));
+                }
+                timer::Event::Finished(..) => {}
+            }
+        }
+    });
+}
+
+/// Runs `f` against the activity tracker,
+/// if recording has been started.
+fn with_tracker(
+    tracker: &Mutex<Option<Tracker>>,
+    f: impl FnOnce(&mut Tracker, Instant, NaiveDate),
+) {
+    if let Some(tracker) = lock!(tracker).as_mut() {
+        f(tracker, Instant::now(), Local::now().date_naive());
+    }
+}
+
+/// Parses the time until a reminder is due.
+///
+/// This is either a duration made up of `h`, `m` and `s` parts,
+/// such as `10m` or `1h30m`, a plain number of minutes,
+/// or a clock time such as `14:30`,
+/// which refers to its next occurrence.
+pub fn parse_time(input: &str) -> Result<Duration> {
+    let input = input.trim();
+
+    if let Ok(time) = NaiveTime::parse_from_str(input, "%H:%M") {
+        let now = Local::now();
+
+        let mut due = now.date_naive().and_time(time);
+        if due <= now.naive_local() {
+            due += TimeDelta::days(1);
+        }
+
+        return (due - now.naive_local())
+            .to_std()
+            .map_err(|_| Report::msg("Invalid time"));
+    }
+
+    parse_duration(input)
+}
+
+/// Shows a desktop notification for a due reminder.
+pub async fn notify(reminder: &Reminder) -> Result<()> {
+    let connection = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+
+    proxy
+        .notify(
+            "Ironbar",
+            0,
+            "alarm-symbolic",
+            "Reminder",
+            &reminder.message,
+            &[],
+            HashMap::new(),
+            -1,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("10m").ok(), Some(Duration::from_secs(600)));
+        assert_eq!(parse_time(" 90 ").ok(), Some(Duration::from_secs(5400)));
+        assert!(parse_time("soon").is_err());
+
+        // clock times refer to their next occurrence
+        let due = parse_time("14:30").expect("should parse clock time");
+        assert!(due > Duration::ZERO && due <= Duration::from_secs(24 * 3600));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::clients::reconnect;
 use crate::spawn;
 use color_eyre::{Report, Result};
 use futures_lite::StreamExt;
@@ -9,7 +10,7 @@ use tracing::{info, trace};
 type SyncFn<T> = dyn Fn(&T) + Sync + Send;
 
 struct TaskState {
-    join_handle: Option<tokio::task::JoinHandle<Result<()>>>,
+    join_handle: Option<tokio::task::JoinHandle<()>>,
     // could have been a `HashMap<EventType, Vec<Box<dyn Fn(&Event) + Sync + Send>>>`, but we don't
     // expect enough listeners to justify the constant overhead of a hashmap.
     listeners: Arc<Vec<(EventType, Box<SyncFn<Event>>)>>,
@@ -85,28 +86,41 @@ impl Client {
 
         listeners_mut.push((event_type, f));
 
-        // create new client as subscription takes ownership
-        let client = Connection::new().await?;
-
         let event_types = listeners.iter().map(|(t, _)| *t).collect::<Vec<_>>();
         let listeners = listeners.clone();
+        let connection = self.connection.clone();
+
+        let handle = spawn(reconnect::run("Sway IPC", move |reconnecting| {
+            let event_types = event_types.clone();
+            let listeners = listeners.clone();
+            let connection = connection.clone();
 
-        let handle = spawn(async move {
-            let mut events = client.subscribe(&event_types).await?;
+            async move {
+                // create new client as subscription takes ownership
+                let client = Connection::new().await?;
+
+                // the command connection will have been lost too
+                if reconnecting {
+                    *connection.lock().await = Connection::new().await?;
+                    info!("Sway IPC client reconnected");
+                }
 
-            while let Some(event) = events.next().await {
-                trace!("event: {:?}", event);
-                let event = event?;
-                let ty = sway_event_to_event_type(&event);
-                for (t, f) in listeners.iter() {
-                    if *t == ty {
-                        f(&event);
+                let mut events = client.subscribe(&event_types).await?;
+
+                while let Some(event) = events.next().await {
+                    trace!("event: {:?}", event);
+                    let event = event?;
+                    let ty = sway_event_to_event_type(&event);
+                    for (t, f) in listeners.iter() {
+                        if *t == ty {
+                            f(&event);
+                        }
                     }
                 }
-            }
 
-            Ok::<(), Report>(())
-        });
+                Ok(())
+            }
+        }));
 
         *join_handle = Some(handle);
 
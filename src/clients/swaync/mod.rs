@@ -1,11 +1,15 @@
 mod dbus;
 
-use crate::{register_fallible_client, send, spawn};
+#[cfg(feature = "notifications")]
+use crate::register_fallible_client;
+use crate::{send, spawn};
 use color_eyre::{Report, Result};
 use dbus::SwayNcProxy;
 use serde::Deserialize;
 use tokio::sync::broadcast;
-use tracing::{debug, error};
+use tracing::debug;
+#[cfg(feature = "notifications")]
+use tracing::error;
 use zbus::export::ordered_stream::OrderedStreamExt;
 use zbus::zvariant::Type;
 
@@ -76,12 +80,20 @@ impl Client {
         }
     }
 
+    #[cfg(feature = "notifications")]
     pub async fn toggle_visibility(&self) {
         debug!("Toggling visibility");
         if let Err(err) = self.proxy.toggle_visibility().await {
             error!("{err:?}");
         }
     }
+
+    #[cfg(any(feature = "presentation", feature = "dnd"))]
+    pub async fn set_dnd(&self, state: bool) -> Result<()> {
+        debug!("Setting DnD: {state}");
+        self.proxy.set_dnd(state).await.map_err(Report::new)
+    }
 }
 
+#[cfg(feature = "notifications")]
 register_fallible_client!(Client, notifications);
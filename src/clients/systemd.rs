@@ -0,0 +1,44 @@
+use zbus::dbus_proxy;
+use zbus::zvariant::OwnedObjectPath;
+
+/// The systemd service manager,
+/// for either the system or the user session.
+#[dbus_proxy(
+    default_service = "org.freedesktop.systemd1",
+    interface = "org.freedesktop.systemd1.Manager",
+    default_path = "/org/freedesktop/systemd1"
+)]
+pub trait Manager {
+    /// Enables unit change signals,
+    /// which are otherwise only sent while a client has subscribed.
+    fn subscribe(&self) -> zbus::Result<()>;
+
+    /// Gets the object path for a unit,
+    /// loading it from disk if it is not already loaded.
+    fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[dbus_proxy(allow_interactive_auth)]
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[dbus_proxy(allow_interactive_auth)]
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[dbus_proxy(allow_interactive_auth)]
+    fn restart_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// A single systemd unit.
+/// This must be built with the path returned by [`ManagerProxy::load_unit`].
+#[dbus_proxy(
+    default_service = "org.freedesktop.systemd1",
+    interface = "org.freedesktop.systemd1.Unit"
+)]
+pub trait Unit {
+    #[dbus_proxy(property)]
+    fn description(&self) -> zbus::Result<String>;
+
+    /// One of `active`, `reloading`, `inactive`, `failed`,
+    /// `activating` or `deactivating`.
+    #[dbus_proxy(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,316 @@
+use crate::{lock, send, spawn};
+use color_eyre::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use zbus::{dbus_proxy, Connection};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, zbus::zvariant::Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// The default length of a work phase.
+const DEFAULT_WORK_DURATION: Duration = Duration::from_secs(25 * 60);
+
+/// The default length of a break phase.
+const DEFAULT_BREAK_DURATION: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    Break,
+    /// A focus session, started by the reminders module.
+    Focus,
+    /// A one-off countdown, with its label.
+    Countdown(String),
+}
+
+impl Phase {
+    /// Gets the name used for CSS classes and script arguments.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Work => "work",
+            Self::Break => "break",
+            Self::Focus => "focus",
+            Self::Countdown(_) => "countdown",
+        }
+    }
+
+    /// Gets the text shown to the user.
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Work => "Work",
+            Self::Break => "Break",
+            Self::Focus => "Focus",
+            Self::Countdown(label) => label,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerState {
+    pub phase: Phase,
+    /// The time the phase ends, while running.
+    ends: Option<Instant>,
+    /// The time left in the phase, while paused.
+    paused_remaining: Duration,
+}
+
+impl TimerState {
+    fn new(phase: Phase, duration: Duration, running: bool) -> Self {
+        Self {
+            phase,
+            ends: running.then(|| Instant::now() + duration),
+            paused_remaining: duration,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.ends.map_or(self.paused_remaining, |ends| {
+            ends.saturating_duration_since(Instant::now())
+        })
+    }
+
+    pub const fn is_running(&self) -> bool {
+        self.ends.is_some()
+    }
+}
+
+/// Formats a duration as `MM:SS`,
+/// or `H:MM:SS` for an hour or more.
+pub fn format_remaining(remaining: Duration) -> String {
+    // round up, so the timer reaches zero as it ends
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The timer has changed.
+    /// This is `None` while the timer is stopped.
+    State(Option<TimerState>),
+    /// A phase has run to completion.
+    ///
+    /// The flag is shared between all receivers,
+    /// so that only one module sends its notification
+    /// when there are multiple timer modules.
+    Finished(Phase, Arc<AtomicBool>),
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: Option<TimerState>,
+    /// Incremented on every state change,
+    /// so that pending phase ends can tell they are stale.
+    generation: usize,
+
+    work_duration: Duration,
+    break_duration: Duration,
+    auto_continue: bool,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    inner: Arc<Mutex<Inner>>,
+    /// The number of receivers held by other clients, rather than modules.
+    client_subscribers: AtomicUsize,
+
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        let (tx, rx) = broadcast::channel(32);
+
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                state: None,
+                generation: 0,
+                work_duration: DEFAULT_WORK_DURATION,
+                break_duration: DEFAULT_BREAK_DURATION,
+                auto_continue: false,
+            })),
+            client_subscribers: AtomicUsize::new(0),
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Sets the phase lengths, and whether the next phase
+    /// starts automatically when one ends.
+    pub fn configure(
+        &self,
+        work_duration: Duration,
+        break_duration: Duration,
+        auto_continue: bool,
+    ) {
+        let mut inner = lock!(self.inner);
+        inner.work_duration = work_duration;
+        inner.break_duration = break_duration;
+        inner.auto_continue = auto_continue;
+    }
+
+    /// Starts a work phase if the timer is stopped,
+    /// otherwise pauses or resumes the current phase.
+    pub fn toggle(&self) {
+        let mut inner = lock!(self.inner);
+
+        let state = match inner.state.take() {
+            None => TimerState::new(Phase::Work, inner.work_duration, true),
+            Some(state) => {
+                let running = !state.is_running();
+                TimerState::new(state.phase.clone(), state.remaining(), running)
+            }
+        };
+
+        set_state(&self.inner, &mut inner, Some(state), &self.tx);
+    }
+
+    /// Starts a phase lasting `duration`,
+    /// replacing the current phase.
+    pub fn start(&self, phase: Phase, duration: Duration) {
+        let mut inner = lock!(self.inner);
+        let state = TimerState::new(phase, duration, true);
+        set_state(&self.inner, &mut inner, Some(state), &self.tx);
+    }
+
+    /// Stops the timer, discarding the current phase.
+    pub fn stop(&self) {
+        let mut inner = lock!(self.inner);
+        set_state(&self.inner, &mut inner, None, &self.tx);
+    }
+
+    pub fn state(&self) -> Option<TimerState> {
+        lock!(self.inner).state.clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// Subscribes to the timer from another client.
+    /// Unlike [`Client::subscribe`], this does not count towards [`Client::has_subscribers`].
+    pub fn subscribe_client(&self) -> broadcast::Receiver<Event> {
+        self.client_subscribers.fetch_add(1, Ordering::Relaxed);
+        self.tx.subscribe()
+    }
+
+    /// Checks whether any module is listening for the timer.
+    pub fn has_subscribers(&self) -> bool {
+        // the client holds one receiver itself
+        self.tx.receiver_count() > 1 + self.client_subscribers.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces the timer state,
+/// scheduling the end of the phase if it is running.
+fn set_state(
+    shared: &Arc<Mutex<Inner>>,
+    inner: &mut Inner,
+    state: Option<TimerState>,
+    tx: &broadcast::Sender<Event>,
+) {
+    inner.generation += 1;
+    inner.state.clone_from(&state);
+
+    if let Some(remaining) = state
+        .as_ref()
+        .filter(|state| state.is_running())
+        .map(TimerState::remaining)
+    {
+        let shared = shared.clone();
+        let tx = tx.clone();
+        let generation = inner.generation;
+
+        spawn(async move {
+            sleep(remaining).await;
+
+            let mut inner = lock!(shared);
+
+            // the timer may have been paused, stopped or replaced in the meantime
+            if inner.generation != generation {
+                return;
+            }
+
+            let Some(finished) = inner.state.take() else {
+                return;
+            };
+
+            send!(
+                tx,
+                Event::Finished(finished.phase.clone(), Arc::new(AtomicBool::new(false)))
+            );
+
+            let next = match finished.phase {
+                Phase::Work => Some((Phase::Break, inner.break_duration)),
+                Phase::Break => Some((Phase::Work, inner.work_duration)),
+                Phase::Focus | Phase::Countdown(_) => None,
+            }
+            .map(|(phase, duration)| TimerState::new(phase, duration, inner.auto_continue));
+
+            set_state(&shared, &mut inner, next, &tx);
+        });
+    }
+
+    send!(tx, Event::State(state));
+}
+
+/// Shows a desktop notification for a finished phase.
+pub async fn notify(phase: &Phase) -> Result<()> {
+    let connection = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+
+    let body = match phase {
+        Phase::Work => String::from("Time for a break"),
+        Phase::Break => String::from("Break is over"),
+        Phase::Focus => String::from("Focus session finished"),
+        Phase::Countdown(label) => format!("{label} finished"),
+    };
+
+    proxy
+        .notify(
+            "Ironbar",
+            0,
+            "alarm-symbolic",
+            "Timer",
+            &body,
+            &[],
+            HashMap::new(),
+            -1,
+        )
+        .await?;
+
+    Ok(())
+}
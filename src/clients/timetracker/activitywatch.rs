@@ -0,0 +1,161 @@
+use super::{unique_tasks, Activity};
+use chrono::{DateTime, Utc};
+use color_eyre::Result;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Bucket used by the ActivityWatch web UI stopwatch.
+const BUCKET_ID: &str = "aw-stopwatch";
+
+/// Number of events to fetch when looking for the running or recent tasks.
+const EVENT_LIMIT: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Event {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    timestamp: DateTime<Utc>,
+    /// Duration in seconds.
+    duration: f64,
+    data: StopwatchData,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StopwatchData {
+    running: bool,
+    label: String,
+}
+
+/// Tracks time using the ActivityWatch stopwatch,
+/// which is shared with the ActivityWatch web UI.
+#[derive(Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl Client {
+    pub fn new(url: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn events_url(&self) -> String {
+        format!("{}/api/0/buckets/{BUCKET_ID}/events", self.url)
+    }
+
+    /// Gets the latest stopwatch events, newest first.
+    async fn events(&self) -> Result<Vec<Event>> {
+        let res = self
+            .http
+            .get(format!("{}?limit={EVENT_LIMIT}", self.events_url()))
+            .send()
+            .await?;
+
+        // bucket is only created once the stopwatch is first used
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+
+        let body = res.error_for_status()?.text().await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Inserts `event`, replacing any existing event with the same id.
+    async fn insert(&self, event: &Event) -> Result<()> {
+        self.http
+            .post(self.events_url())
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(event)?)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn create_bucket(&self) -> Result<()> {
+        let hostname = std::fs::read_to_string("/etc/hostname")
+            .map_or_else(|_| String::from("unknown"), |name| name.trim().to_string());
+
+        let body = json!({
+            "client": env!("CARGO_PKG_NAME"),
+            "type": "general.stopwatch",
+            "hostname": hostname,
+        });
+
+        let res = self
+            .http
+            .post(format!("{}/api/0/buckets/{BUCKET_ID}", self.url))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await?;
+
+        // 304 is returned if the bucket already exists
+        if res.status() != StatusCode::NOT_MODIFIED {
+            res.error_for_status()?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn current(&self) -> Result<Option<Activity>> {
+        Ok(self
+            .events()
+            .await?
+            .into_iter()
+            .find(|event| event.data.running)
+            .map(|event| Activity {
+                task: event.data.label,
+                start: event.timestamp,
+            }))
+    }
+
+    pub async fn start(&self, task: &str) -> Result<()> {
+        self.create_bucket().await?;
+        self.stop().await?;
+
+        let event = Event {
+            id: None,
+            timestamp: Utc::now(),
+            duration: 0.0,
+            data: StopwatchData {
+                running: true,
+                label: task.to_string(),
+            },
+        };
+
+        self.insert(&event).await
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        let now = Utc::now();
+
+        for mut event in self.events().await? {
+            if !event.data.running {
+                continue;
+            }
+
+            event.data.running = false;
+            event.duration = (now - event.timestamp).num_milliseconds() as f64 / 1000.0;
+
+            self.insert(&event).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn recent(&self, count: usize) -> Result<Vec<String>> {
+        let tasks = self
+            .events()
+            .await?
+            .into_iter()
+            .map(|event| event.data.label);
+
+        Ok(unique_tasks(tasks, count))
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use color_eyre::Result;
+
+pub mod activitywatch;
+pub mod timewarrior;
+pub mod toggl;
+
+/// A task currently being tracked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    pub task: String,
+    pub start: DateTime<Utc>,
+}
+
+/// A time tracking service backend.
+///
+/// Backends are stateless wrappers around each service's CLI or API,
+/// so a client is created per module instance.
+#[derive(Debug)]
+pub enum Client {
+    Timewarrior(timewarrior::Client),
+    Toggl(toggl::Client),
+    ActivityWatch(activitywatch::Client),
+}
+
+impl Client {
+    /// Gets the currently running activity, if any.
+    pub async fn current(&self) -> Result<Option<Activity>> {
+        match self {
+            Self::Timewarrior(client) => client.current().await,
+            Self::Toggl(client) => client.current().await,
+            Self::ActivityWatch(client) => client.current().await,
+        }
+    }
+
+    /// Starts tracking `task`,
+    /// stopping any currently running activity.
+    pub async fn start(&self, task: &str) -> Result<()> {
+        match self {
+            Self::Timewarrior(client) => client.start(task).await,
+            Self::Toggl(client) => client.start(task).await,
+            Self::ActivityWatch(client) => client.start(task).await,
+        }
+    }
+
+    /// Stops the currently running activity.
+    pub async fn stop(&self) -> Result<()> {
+        match self {
+            Self::Timewarrior(client) => client.stop().await,
+            Self::Toggl(client) => client.stop().await,
+            Self::ActivityWatch(client) => client.stop().await,
+        }
+    }
+
+    /// Gets up to `count` recently tracked task names,
+    /// most recent first and without duplicates.
+    pub async fn recent(&self, count: usize) -> Result<Vec<String>> {
+        match self {
+            Self::Timewarrior(client) => client.recent(count).await,
+            Self::Toggl(client) => client.recent(count).await,
+            Self::ActivityWatch(client) => client.recent(count).await,
+        }
+    }
+}
+
+/// Takes up to `count` unique, non-empty task names
+/// in the order they are provided.
+fn unique_tasks(tasks: impl Iterator<Item = String>, count: usize) -> Vec<String> {
+    let mut unique = Vec::new();
+
+    for task in tasks {
+        if unique.len() >= count {
+            break;
+        }
+
+        if !task.is_empty() && !unique.contains(&task) {
+            unique.push(task);
+        }
+    }
+
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_tasks() {
+        let tasks = ["review", "", "review", "docs", "triage", "release"].map(String::from);
+
+        assert_eq!(
+            unique_tasks(tasks.into_iter(), 3),
+            vec!["review", "docs", "triage"]
+        );
+    }
+}
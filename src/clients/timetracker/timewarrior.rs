@@ -0,0 +1,98 @@
+use super::{unique_tasks, Activity};
+use chrono::NaiveDateTime;
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+
+/// Format used by Timewarrior for interval dates.
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Deserialize)]
+struct Interval {
+    start: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl Interval {
+    fn into_activity(self) -> Result<Activity> {
+        let start = NaiveDateTime::parse_from_str(&self.start, DATE_FORMAT)?.and_utc();
+
+        Ok(Activity {
+            task: self.tags.join(" "),
+            start,
+        })
+    }
+}
+
+/// Tracks time using the `timew` CLI.
+///
+/// Tasks are stored as interval tags,
+/// split on whitespace.
+#[derive(Debug, Default)]
+pub struct Client;
+
+impl Client {
+    async fn run(args: &[&str]) -> Result<String> {
+        let output = Command::new("timew").args(args).output().await?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(Report::msg(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
+    pub async fn current(&self) -> Result<Option<Activity>> {
+        if Self::run(&["get", "dom.active"]).await?.trim() != "1" {
+            return Ok(None);
+        }
+
+        let interval: Interval =
+            serde_json::from_str(&Self::run(&["get", "dom.active.json"]).await?)?;
+        interval.into_activity().map(Some)
+    }
+
+    pub async fn start(&self, task: &str) -> Result<()> {
+        let mut args = vec!["start"];
+        args.extend(task.split_whitespace());
+
+        Self::run(&args).await.map(|_| ())
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        Self::run(&["stop"]).await.map(|_| ())
+    }
+
+    pub async fn recent(&self, count: usize) -> Result<Vec<String>> {
+        let intervals: Vec<Interval> =
+            serde_json::from_str(&Self::run(&["export", ":week"]).await?)?;
+
+        // intervals are exported oldest first
+        let tasks = intervals
+            .into_iter()
+            .rev()
+            .map(|interval| interval.tags.join(" "));
+
+        Ok(unique_tasks(tasks, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_activity() {
+        let interval = Interval {
+            start: String::from("20240601T083000Z"),
+            tags: vec![String::from("write"), String::from("docs")],
+        };
+
+        let activity = interval.into_activity().expect("should parse interval");
+        assert_eq!(activity.task, "write docs");
+        assert_eq!(activity.start.to_rfc3339(), "2024-06-01T08:30:00+00:00");
+    }
+}
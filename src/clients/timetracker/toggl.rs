@@ -0,0 +1,136 @@
+use super::{unique_tasks, Activity};
+use chrono::{DateTime, Utc};
+use color_eyre::Result;
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::OnceCell;
+
+const API_URL: &str = "https://api.track.toggl.com/api/v9";
+
+#[derive(Debug, Deserialize)]
+struct TimeEntry {
+    id: u64,
+    workspace_id: u64,
+    description: Option<String>,
+    start: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Me {
+    default_workspace_id: u64,
+}
+
+/// Tracks time using the Toggl Track v9 API.
+///
+/// New entries are created in the user's default workspace.
+#[derive(Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    api_token: String,
+    workspace_id: OnceCell<u64>,
+}
+
+impl Client {
+    pub fn new(api_token: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_token,
+            workspace_id: OnceCell::new(),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{API_URL}{path}"))
+            .basic_auth(&self.api_token, Some("api_token"))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self
+            .request(Method::GET, path)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    async fn workspace_id(&self) -> Result<u64> {
+        self.workspace_id
+            .get_or_try_init(|| async {
+                let me: Me = self.get("/me").await?;
+                Ok(me.default_workspace_id)
+            })
+            .await
+            .copied()
+    }
+
+    async fn current_entry(&self) -> Result<Option<TimeEntry>> {
+        self.get("/me/time_entries/current").await
+    }
+
+    pub async fn current(&self) -> Result<Option<Activity>> {
+        Ok(self.current_entry().await?.map(|entry| Activity {
+            task: entry.description.unwrap_or_default(),
+            start: entry.start,
+        }))
+    }
+
+    pub async fn start(&self, task: &str) -> Result<()> {
+        self.stop().await?;
+
+        let workspace_id = self.workspace_id().await?;
+        let body = json!({
+            "created_with": env!("CARGO_PKG_NAME"),
+            "description": task,
+            "workspace_id": workspace_id,
+            "start": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            // negative duration marks the entry as running
+            "duration": -1,
+        });
+
+        self.request(
+            Method::POST,
+            &format!("/workspaces/{workspace_id}/time_entries"),
+        )
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+
+        Ok(())
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        let Some(entry) = self.current_entry().await? else {
+            return Ok(());
+        };
+
+        self.request(
+            Method::PATCH,
+            &format!(
+                "/workspaces/{}/time_entries/{}/stop",
+                entry.workspace_id, entry.id
+            ),
+        )
+        .send()
+        .await?
+        .error_for_status()?;
+
+        Ok(())
+    }
+
+    pub async fn recent(&self, count: usize) -> Result<Vec<String>> {
+        // entries are returned newest first
+        let entries: Vec<TimeEntry> = self.get("/me/time_entries").await?;
+
+        let tasks = entries.into_iter().filter_map(|entry| entry.description);
+
+        Ok(unique_tasks(tasks, count))
+    }
+}
@@ -1,8 +1,159 @@
 use crate::clients::ClientResult;
 use crate::register_fallible_client;
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::debug;
 use upower_dbus::UPowerProxy;
+use zbus::dbus_proxy;
 use zbus::fdo::PropertiesProxy;
+use zbus::zvariant::OwnedObjectPath;
+
+/// Top-level `UPower` daemon properties and signals
+/// which are not exposed by `upower_dbus`.
+#[dbus_proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+pub trait UPowerEvents {
+    #[dbus_proxy(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(signal)]
+    fn device_added(&self, device: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn device_removed(&self, device: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+/// Properties of any `UPower` device.
+///
+/// This is used instead of `upower_dbus::DeviceProxy`
+/// as its device type enum does not include newer types, such as headsets.
+#[dbus_proxy(
+    default_service = "org.freedesktop.UPower",
+    interface = "org.freedesktop.UPower.Device",
+    assume_defaults = false
+)]
+trait UPowerDevice {
+    #[dbus_proxy(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<u32>;
+
+    #[dbus_proxy(property)]
+    fn model(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    #[dbus_proxy(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    #[dbus_proxy(property)]
+    fn icon_name(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+}
+
+/// `UpDeviceKind` values which need special handling.
+const KIND_LINE_POWER: u32 = 1;
+const KIND_BATTERY: u32 = 2;
+
+/// A battery-powered device known to `UPower`,
+/// such as a laptop battery, mouse or headset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub name: String,
+    pub kind: u32,
+    pub percentage: f64,
+    pub state: u32,
+    pub icon_name: String,
+}
+
+impl Device {
+    /// Gets a readable name for a `UpDeviceKind` value.
+    pub const fn kind_name(kind: u32) -> &'static str {
+        match kind {
+            2 => "Battery",
+            3 => "UPS",
+            4 => "Monitor",
+            5 => "Mouse",
+            6 => "Keyboard",
+            7 => "PDA",
+            8 => "Phone",
+            9 => "Media player",
+            10 => "Tablet",
+            11 => "Computer",
+            12 => "Controller",
+            13 => "Pen",
+            14 => "Touchpad",
+            15 => "Modem",
+            16 => "Network",
+            17 => "Headset",
+            18 => "Speakers",
+            19 => "Headphones",
+            20 => "Video",
+            21 => "Audio device",
+            22 => "Remote control",
+            23 => "Printer",
+            24 => "Scanner",
+            25 => "Camera",
+            26 => "Wearable",
+            27 => "Toy",
+            28 => "Bluetooth device",
+            _ => "Device",
+        }
+    }
+}
+
+/// Gets every battery-powered device known to `UPower`,
+/// sorted by type and then name.
+///
+/// Mains power supplies and empty battery bays are skipped.
+pub async fn devices(dbus: &zbus::Connection) -> Result<Vec<Device>> {
+    let upower = UPowerProxy::new(dbus).await?;
+
+    let mut devices = vec![];
+
+    for path in upower.enumerate_devices().await? {
+        let proxy = UPowerDeviceProxy::builder(dbus)
+            .path(path)?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+
+        let kind = proxy.kind().await?;
+        if kind == KIND_LINE_POWER
+            || (kind == KIND_BATTERY && !proxy.is_present().await.unwrap_or(true))
+        {
+            continue;
+        }
+
+        let name = proxy
+            .model()
+            .await
+            .ok()
+            .filter(|model| !model.is_empty())
+            .unwrap_or_else(|| Device::kind_name(kind).to_string());
+
+        devices.push(Device {
+            name,
+            kind,
+            percentage: proxy.percentage().await.unwrap_or_default(),
+            state: proxy.state().await.unwrap_or_default(),
+            icon_name: proxy.icon_name().await.unwrap_or_default(),
+        });
+    }
+
+    devices.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(devices)
+}
 
 pub async fn create_display_proxy() -> ClientResult<PropertiesProxy<'static>> {
     let dbus = Box::pin(zbus::Connection::system()).await?;
@@ -25,4 +176,127 @@ pub async fn create_display_proxy() -> ClientResult<PropertiesProxy<'static>> {
     Ok(Arc::new(proxy))
 }
 
+pub async fn create_events_proxy() -> ClientResult<UPowerEventsProxy<'static>> {
+    let dbus = Box::pin(zbus::Connection::system()).await?;
+    let proxy = UPowerEventsProxy::new(&dbus).await?;
+
+    Ok(Arc::new(proxy))
+}
+
 register_fallible_client!(PropertiesProxy<'static>, upower);
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Finds the sysfs file controlling the charge threshold of a battery.
+/// If `battery` is not set, the first battery which supports it is used.
+///
+/// Returns `None` if no battery supports setting a threshold.
+pub fn find_charge_threshold(battery: Option<&str>) -> Option<PathBuf> {
+    let batteries = std::fs::read_dir(POWER_SUPPLY_DIR)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            std::fs::read_to_string(path.join("type")).is_ok_and(|typ| typ.trim() == "Battery")
+        });
+
+    let mut paths = batteries
+        .filter(|path| battery.is_none_or(|battery| path.ends_with(battery)))
+        .map(|path| path.join("charge_control_end_threshold"))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths.into_iter().next()
+}
+
+/// Reads the charge threshold, as a percentage.
+pub fn read_charge_threshold(path: &Path) -> Option<u8> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Writes the charge threshold, as a percentage.
+///
+/// The file is usually only writable by root,
+/// so if writing directly fails this falls back to `pkexec`,
+/// letting polkit prompt for authentication.
+pub async fn write_charge_threshold(path: &Path, value: u8) -> Result<()> {
+    if std::fs::write(path, value.to_string()).is_ok() {
+        return Ok(());
+    }
+
+    debug!("Writing charge threshold directly failed, falling back to pkexec");
+
+    let mut child = Command::new("pkexec")
+        .arg("tee")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .wrap_err("Failed to run pkexec")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(value.to_string().as_bytes()).await?;
+    }
+
+    let status = child.wait().await?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "Failed to write charge threshold: pkexec exited with {status}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::upower::{device_path, serve, MockDevice};
+
+    #[tokio::test]
+    async fn test_devices() -> Result<()> {
+        let mut empty_bay = MockDevice::new(KIND_BATTERY, "Second battery", 0.0);
+        empty_bay.is_present = false;
+
+        let (_server, client) = serve(vec![
+            MockDevice::new(17, "Headset", 40.0),
+            MockDevice::new(KIND_LINE_POWER, "AC", 0.0),
+            MockDevice::new(KIND_BATTERY, "", 80.0),
+            empty_bay,
+        ])
+        .await?;
+
+        let devices = devices(&client).await?;
+
+        let names = devices
+            .iter()
+            .map(|device| device.name.as_str())
+            .collect::<Vec<_>>();
+
+        // line power and empty bays are skipped,
+        // and unnamed devices use their type name.
+        assert_eq!(names, ["Battery", "Headset"]);
+        assert!((devices[1].percentage - 40.0).abs() < f64::EPSILON);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_devices_update() -> Result<()> {
+        let (server, client) = serve(vec![MockDevice::new(KIND_BATTERY, "", 80.0)]).await?;
+
+        server
+            .object_server()
+            .interface::<_, MockDevice>(device_path(0))
+            .await?
+            .get_mut()
+            .await
+            .percentage = 20.0;
+
+        let devices = devices(&client).await?;
+        assert!((devices[0].percentage - 20.0).abs() < f64::EPSILON);
+
+        Ok(())
+    }
+}
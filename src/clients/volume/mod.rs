@@ -1,5 +1,7 @@
 mod sink;
 mod sink_input;
+mod source;
+mod source_output;
 
 use crate::{arc_mut, lock, register_client, send, spawn_blocking, APP_ID};
 use libpulse_binding::callbacks::ListResult;
@@ -16,6 +18,8 @@ use tracing::{debug, error, info, warn};
 
 pub use sink::Sink;
 pub use sink_input::SinkInput;
+pub use source::Source;
+pub use source_output::SourceOutput;
 
 type ArcMutVec<T> = Arc<Mutex<Vec<T>>>;
 
@@ -28,6 +32,14 @@ pub enum Event {
     AddInput(SinkInput),
     UpdateInput(SinkInput),
     RemoveInput(u32),
+
+    /// An input device was added, updated or removed.
+    /// The current devices can be read using [`Client::sources`].
+    SourcesChanged,
+
+    /// An application started, updated or stopped capturing from an input device.
+    /// The current applications can be read using [`Client::capturing_applications`].
+    SourceOutputsChanged,
 }
 
 #[derive(Debug)]
@@ -46,6 +58,11 @@ struct Data {
     sink_inputs: ArcMutVec<SinkInput>,
 
     default_sink_name: Arc<Mutex<Option<String>>>,
+
+    sources: ArcMutVec<Source>,
+    source_outputs: ArcMutVec<SourceOutput>,
+
+    default_source_name: Arc<Mutex<Option<String>>>,
 }
 
 pub enum ConnectionState {
@@ -198,6 +215,36 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
                 move |info| sink_input::add(info, &inputs, &tx)
             });
 
+            let introspect3 = lock!(context).introspect();
+
+            introspect.get_source_info_list({
+                let sources = data.sources.clone();
+                let default_source = data.default_source_name.clone();
+
+                let tx = tx.clone();
+
+                move |info| match info {
+                    ListResult::Item(_) => source::add(info, &sources, &default_source, &tx),
+                    ListResult::End => {
+                        introspect3.get_server_info({
+                            let sources = sources.clone();
+                            let default_source = default_source.clone();
+                            let tx = tx.clone();
+
+                            move |info| source::set_default(info, &sources, &default_source, &tx)
+                        });
+                    }
+                    ListResult::Error => error!("Error while receiving sources"),
+                }
+            });
+
+            introspect.get_source_output_info_list({
+                let outputs = data.source_outputs.clone();
+                let tx = tx.clone();
+
+                move |info| source_output::add(info, &outputs, &tx)
+            });
+
             let subscribe_callback = Box::new({
                 let context = context.clone();
                 let data = data.clone();
@@ -208,7 +255,11 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
 
             lock!(context).set_subscribe_callback(Some(subscribe_callback));
             lock!(context).subscribe(
-                InterestMaskSet::SERVER | InterestMaskSet::SINK_INPUT | InterestMaskSet::SINK,
+                InterestMaskSet::SERVER
+                    | InterestMaskSet::SINK_INPUT
+                    | InterestMaskSet::SINK
+                    | InterestMaskSet::SOURCE_OUTPUT
+                    | InterestMaskSet::SOURCE,
                 |_| (),
             );
         }
@@ -231,25 +282,28 @@ fn on_event(
     };
 
     match facility {
-        Facility::Server => on_server_event(context, &data.sinks, &data.default_sink_name, tx),
+        Facility::Server => on_server_event(context, data, tx),
         Facility::Sink => sink::on_event(context, &data.sinks, &data.default_sink_name, tx, op, i),
         Facility::SinkInput => sink_input::on_event(context, &data.sink_inputs, tx, op, i),
+        Facility::Source => {
+            source::on_event(context, &data.sources, &data.default_source_name, tx, op, i)
+        }
+        Facility::SourceOutput => {
+            source_output::on_event(context, &data.source_outputs, tx, op, i);
+        }
         _ => error!("Received unhandled facility: {facility:?}"),
     }
 }
 
-fn on_server_event(
-    context: &Arc<Mutex<Context>>,
-    sinks: &ArcMutVec<Sink>,
-    default_sink: &Arc<Mutex<Option<String>>>,
-    tx: &broadcast::Sender<Event>,
-) {
+fn on_server_event(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::Sender<Event>) {
     lock!(context).introspect().get_server_info({
-        let sinks = sinks.clone();
-        let default_sink = default_sink.clone();
+        let data = data.clone();
         let tx = tx.clone();
 
-        move |info| set_default_sink(info, &sinks, &default_sink, &tx)
+        move |info| {
+            set_default_sink(info, &data.sinks, &data.default_sink_name, &tx);
+            source::set_default(info, &data.sources, &data.default_source_name, &tx);
+        }
     });
 }
 
@@ -0,0 +1,207 @@
+use super::{percent_to_volume, volume_to_percent, ArcMutVec, Client, ConnectionState, Event};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::introspect::{ServerInfo, SourceInfo};
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::context::Context;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: f64,
+    pub muted: bool,
+    /// Whether this is the default source.
+    pub active: bool,
+}
+
+impl From<&SourceInfo<'_>> for Source {
+    fn from(value: &SourceInfo) -> Self {
+        Self {
+            index: value.index,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            muted: value.mute,
+            volume: volume_to_percent(value.volume),
+            active: false,
+        }
+    }
+}
+
+impl Client {
+    pub fn sources(&self) -> Arc<Mutex<Vec<Source>>> {
+        self.data.sources.clone()
+    }
+
+    pub fn set_source_volume(&self, name: &str, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let (tx, rx) = mpsc::channel();
+
+            introspector.get_source_info_by_name(name, move |info| {
+                let ListResult::Item(info) = info else {
+                    return;
+                };
+                send!(tx, info.volume);
+            });
+
+            let new_volume = percent_to_volume(volume_percent);
+
+            let mut volume = rx.recv().expect("to receive info");
+            for v in volume.get_mut() {
+                v.0 = new_volume;
+            }
+
+            introspector.set_source_volume_by_name(name, &volume, None);
+        }
+    }
+
+    pub fn set_source_muted(&self, name: &str, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_mute_by_name(name, muted, None);
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let default_source = default_source.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &sources, &default_source, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source changed");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let default_source = default_source.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &sources, &default_source, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source removed");
+            remove(i, sources, tx);
+        }
+    }
+}
+
+/// Converts the source info,
+/// ignoring monitor sources as these capture playback rather than an input device.
+fn from_info(info: &SourceInfo, default_source: &Arc<Mutex<Option<String>>>) -> Option<Source> {
+    if info.monitor_of_sink.is_some() {
+        return None;
+    }
+
+    let mut source: Source = info.into();
+    source.active = lock!(default_source).as_deref() == Some(source.name.as_str());
+
+    Some(source)
+}
+
+pub fn add(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    let Some(source) = from_info(info, default_source) else {
+        return;
+    };
+
+    lock!(sources).push(source);
+    send!(tx, Event::SourcesChanged);
+}
+
+fn update(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    let Some(source) = from_info(info, default_source) else {
+        return;
+    };
+
+    {
+        let mut sources = lock!(sources);
+        let Some(pos) = sources.iter().position(|s| s.index == source.index) else {
+            error!("received update to untracked source");
+            return;
+        };
+
+        sources[pos] = source;
+    }
+
+    send!(tx, Event::SourcesChanged);
+}
+
+fn remove(index: u32, sources: &ArcMutVec<Source>, tx: &broadcast::Sender<Event>) {
+    let mut sources = lock!(sources);
+
+    if let Some(pos) = sources.iter().position(|s| s.index == index) {
+        sources.remove(pos);
+        send!(tx, Event::SourcesChanged);
+    }
+}
+
+/// Marks the server's default source as active,
+/// and any previously active source as inactive.
+pub fn set_default(
+    info: &ServerInfo,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let default_source_name = info.default_source_name.as_ref().map(ToString::to_string);
+
+    if default_source_name == *lock!(default_source) {
+        return;
+    }
+
+    for source in lock!(sources).iter_mut() {
+        let active = default_source_name.as_deref() == Some(source.name.as_str());
+
+        if source.active != active {
+            source.active = active;
+            debug!("Set source active: {} ({active})", source.name);
+            send!(tx, Event::SourcesChanged);
+        }
+    }
+
+    *lock!(default_source) = default_source_name;
+}
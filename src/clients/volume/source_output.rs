@@ -0,0 +1,137 @@
+use super::{ArcMutVec, Client, Event};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::introspect::SourceOutputInfo;
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::context::Context;
+use libpulse_binding::proplist::properties::APPLICATION_NAME;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+/// An application stream capturing from a source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceOutput {
+    pub index: u32,
+    /// The index of the source being captured from.
+    pub source: u32,
+    pub name: String,
+    pub application: Option<String>,
+    pub corked: bool,
+}
+
+impl From<&SourceOutputInfo<'_>> for SourceOutput {
+    fn from(value: &SourceOutputInfo) -> Self {
+        Self {
+            index: value.index,
+            source: value.source,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            application: value.proplist.get_str(APPLICATION_NAME),
+            corked: value.corked,
+        }
+    }
+}
+
+impl Client {
+    /// Gets the sorted names of applications currently capturing from an input device.
+    pub fn capturing_applications(&self) -> Vec<String> {
+        let sources = lock!(self.data.sources);
+        let outputs = lock!(self.data.source_outputs);
+
+        // monitor sources are not tracked,
+        // so this skips applications capturing playback
+        let mut applications = outputs
+            .iter()
+            .filter(|output| !output.corked)
+            .filter(|output| sources.iter().any(|source| source.index == output.source))
+            .map(|output| output.application.as_ref().unwrap_or(&output.name).clone())
+            .collect::<Vec<_>>();
+
+        applications.sort();
+        applications.dedup();
+        applications
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source output");
+            introspect.get_source_output_info(i, {
+                let outputs = outputs.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &outputs, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source output changed");
+            introspect.get_source_output_info(i, {
+                let outputs = outputs.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &outputs, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source output removed");
+            remove(i, outputs, tx);
+        }
+    }
+}
+
+pub fn add(
+    info: ListResult<&SourceOutputInfo>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    lock!(outputs).push(info.into());
+    send!(tx, Event::SourceOutputsChanged);
+}
+
+fn update(
+    info: ListResult<&SourceOutputInfo>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    {
+        let mut outputs = lock!(outputs);
+        let Some(pos) = outputs.iter().position(|output| output.index == info.index) else {
+            error!("received update to untracked source output");
+            return;
+        };
+
+        outputs[pos] = info.into();
+    }
+
+    send!(tx, Event::SourceOutputsChanged);
+}
+
+fn remove(index: u32, outputs: &ArcMutVec<SourceOutput>, tx: &broadcast::Sender<Event>) {
+    let mut outputs = lock!(outputs);
+
+    if let Some(pos) = outputs.iter().position(|s| s.index == index) {
+        outputs.remove(pos);
+        send!(tx, Event::SourceOutputsChanged);
+    }
+}
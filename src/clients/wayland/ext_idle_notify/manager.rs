@@ -0,0 +1,90 @@
+use smithay_client_toolkit::error::GlobalError;
+use smithay_client_toolkit::globals::{GlobalData, ProvidesBoundGlobal};
+use tracing::debug;
+use wayland_client::globals::{BindError, GlobalList};
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{Event, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+
+#[derive(Debug)]
+pub struct IdleNotifierState {
+    notifier: ExtIdleNotifierV1,
+}
+
+impl IdleNotifierState {
+    pub fn bind<State>(globals: &GlobalList, qh: &QueueHandle<State>) -> Result<Self, BindError>
+    where
+        State: Dispatch<ExtIdleNotifierV1, GlobalData, State> + 'static,
+    {
+        let notifier = globals.bind(qh, 1..=1, GlobalData)?;
+        debug!("Bound to ExtIdleNotifierV1 global");
+        Ok(Self { notifier })
+    }
+
+    /// Creates a notification for when the seat has been idle for `timeout` milliseconds.
+    pub fn get_idle_notification<D>(
+        &self,
+        qh: &QueueHandle<D>,
+        timeout: u32,
+        seat: &WlSeat,
+    ) -> ExtIdleNotificationV1
+    where
+        D: Dispatch<ExtIdleNotificationV1, GlobalData> + 'static,
+    {
+        self.notifier
+            .get_idle_notification(timeout, seat, qh, GlobalData)
+    }
+}
+
+pub trait IdleNotificationHandler: Sized {
+    /// The seat has been idle for the notification's timeout.
+    fn idled(&mut self, conn: &Connection, qh: &QueueHandle<Self>);
+
+    /// The seat is no longer idle.
+    fn resumed(&mut self, conn: &Connection, qh: &QueueHandle<Self>);
+}
+
+impl ProvidesBoundGlobal<ExtIdleNotifierV1, 1> for IdleNotifierState {
+    fn bound_global(&self) -> Result<ExtIdleNotifierV1, GlobalError> {
+        Ok(self.notifier.clone())
+    }
+}
+
+impl<D> Dispatch<ExtIdleNotifierV1, GlobalData, D> for IdleNotifierState
+where
+    D: Dispatch<ExtIdleNotifierV1, GlobalData>,
+{
+    fn event(
+        _state: &mut D,
+        _proxy: &ExtIdleNotifierV1,
+        _event: <ExtIdleNotifierV1 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<D>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl<D> Dispatch<ExtIdleNotificationV1, GlobalData, D> for IdleNotifierState
+where
+    D: Dispatch<ExtIdleNotificationV1, GlobalData> + IdleNotificationHandler,
+{
+    fn event(
+        state: &mut D,
+        _notification: &ExtIdleNotificationV1,
+        event: Event,
+        _data: &GlobalData,
+        conn: &Connection,
+        qh: &QueueHandle<D>,
+    ) {
+        match event {
+            Event::Idled => state.idled(conn, qh),
+            Event::Resumed => state.resumed(conn, qh),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,63 @@
+pub mod manager;
+
+use self::manager::IdleNotificationHandler;
+use super::{Client, Environment, Event, Request, Response};
+use crate::try_send;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::debug;
+use wayland_client::{Connection, QueueHandle};
+
+impl Client {
+    /// Subscribes to changes in whether the user is idle,
+    /// where `true` means the user has been idle for `timeout`.
+    ///
+    /// The compositor is only asked to notify about a single timeout,
+    /// so the first timeout requested is used for all subscribers.
+    pub fn subscribe_idle(&self, timeout: Duration) -> Result<broadcast::Receiver<bool>> {
+        match self.send_request(Request::IdleNotification(timeout)) {
+            Response::Ok => Ok(self.idle_channel.0.subscribe()),
+            Response::IdleNotifyUnsupported => Err(eyre!(
+                "Compositor does not support the ext-idle-notify protocol"
+            )),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Environment {
+    /// Creates the idle notification, if it does not already exist.
+    ///
+    /// Returns `false` if the compositor does not support idle notifications.
+    pub fn add_idle_notification(&mut self, timeout: Duration) -> bool {
+        let Some(notifier) = &self.idle_notifier_state else {
+            return false;
+        };
+
+        if self.idle_notification.is_none() {
+            debug!("Creating idle notification with timeout {timeout:?}");
+
+            let timeout = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+            let seat = self.default_seat();
+
+            self.idle_notification =
+                Some(notifier.get_idle_notification(&self.queue_handle, timeout, &seat));
+        }
+
+        true
+    }
+}
+
+impl IdleNotificationHandler for Environment {
+    fn idled(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>) {
+        debug!("Seat is idle");
+        try_send!(self.event_tx, Event::Idle(true));
+    }
+
+    fn resumed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>) {
+        debug!("Seat is no longer idle");
+        try_send!(self.event_tx, Event::Idle(false));
+    }
+}
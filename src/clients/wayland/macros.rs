@@ -99,3 +99,51 @@ macro_rules! delegate_foreign_toplevel_handle {
         );
     };
 }
+
+// --- Gamma Control --- \\
+
+#[macro_export]
+macro_rules! delegate_gamma_control_manager {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        wayland_client::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                wayland_protocols_wlr::gamma_control::v1::client::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: smithay_client_toolkit::globals::GlobalData
+            ] => $crate::clients::wayland::wlr_gamma_control::manager::GammaControlManagerState
+        );
+    };
+}
+
+#[macro_export]
+macro_rules! delegate_gamma_control {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        wayland_client::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                wayland_protocols_wlr::gamma_control::v1::client::zwlr_gamma_control_v1::ZwlrGammaControlV1: smithay_client_toolkit::globals::GlobalData
+            ] => $crate::clients::wayland::wlr_gamma_control::manager::GammaControlManagerState
+        );
+    };
+}
+
+// --- Idle Notify --- \\
+
+#[macro_export]
+macro_rules! delegate_idle_notifier {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        wayland_client::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                wayland_protocols::ext::idle_notify::v1::client::ext_idle_notifier_v1::ExtIdleNotifierV1: smithay_client_toolkit::globals::GlobalData
+            ] => $crate::clients::wayland::ext_idle_notify::manager::IdleNotifierState
+        );
+    };
+}
+
+#[macro_export]
+macro_rules! delegate_idle_notification {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        wayland_client::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty:
+            [
+                wayland_protocols::ext::idle_notify::v1::client::ext_idle_notification_v1::ExtIdleNotificationV1: smithay_client_toolkit::globals::GlobalData
+            ] => $crate::clients::wayland::ext_idle_notify::manager::IdleNotifierState
+        );
+    };
+}
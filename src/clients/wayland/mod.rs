@@ -26,7 +26,12 @@ use wayland_client::{Connection, QueueHandle};
 pub use wl_output::{OutputEvent, OutputEventType};
 
 cfg_if! {
-    if #[cfg(any(feature = "focused", feature = "launcher"))] {
+    if #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))] {
         mod wlr_foreign_toplevel;
         use crate::{delegate_foreign_toplevel_handle, delegate_foreign_toplevel_manager};
         use wlr_foreign_toplevel::manager::ToplevelManagerState;
@@ -56,25 +61,59 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "reminders")] {
+        mod ext_idle_notify;
+
+        use crate::{delegate_idle_notification, delegate_idle_notifier};
+        use self::ext_idle_notify::manager::IdleNotifierState;
+        use std::time::Duration;
+        use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notification_v1::ExtIdleNotificationV1;
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "night_light")] {
+        mod wlr_gamma_control;
+
+        use crate::{delegate_gamma_control, delegate_gamma_control_manager};
+        use self::wlr_gamma_control::manager::GammaControlManagerState;
+        use self::wlr_gamma_control::GammaControl;
+
+        pub use wlr_gamma_control::NEUTRAL_TEMPERATURE;
+    }
+}
+
 #[derive(Debug)]
 pub enum Event {
     Output(OutputEvent),
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))]
     Toplevel(ToplevelEvent),
     #[cfg(feature = "clipboard")]
     Clipboard(ClipboardItem),
+    #[cfg(feature = "reminders")]
+    Idle(bool),
 }
 
 #[derive(Debug)]
 pub enum Request {
     Roundtrip,
 
-    #[cfg(feature = "ipc")]
     OutputInfoAll,
 
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))]
     ToplevelInfoAll,
-    #[cfg(feature = "launcher")]
+    #[cfg(any(feature = "chat", feature = "launcher"))]
     ToplevelFocus(usize),
     #[cfg(feature = "launcher")]
     ToplevelMinimize(usize),
@@ -83,6 +122,12 @@ pub enum Request {
     CopyToClipboard(ClipboardItem),
     #[cfg(feature = "clipboard")]
     ClipboardItem,
+
+    #[cfg(feature = "night_light")]
+    SetColorTemperature(Option<u32>),
+
+    #[cfg(feature = "reminders")]
+    IdleNotification(Duration),
 }
 
 #[derive(Debug)]
@@ -90,14 +135,24 @@ pub enum Response {
     /// An empty success response
     Ok,
 
-    #[cfg(feature = "ipc")]
     OutputInfoAll(Vec<smithay_client_toolkit::output::OutputInfo>),
 
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))]
     ToplevelInfoAll(Vec<ToplevelInfo>),
 
     #[cfg(feature = "clipboard")]
     ClipboardItem(Option<ClipboardItem>),
+
+    #[cfg(feature = "night_light")]
+    GammaControlUnsupported,
+
+    #[cfg(feature = "reminders")]
+    IdleNotifyUnsupported,
 }
 
 #[derive(Debug)]
@@ -116,10 +171,17 @@ pub struct Client {
     rx: Arc<Mutex<std::sync::mpsc::Receiver<Response>>>,
 
     output_channel: BroadcastChannel<OutputEvent>,
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))]
     toplevel_channel: BroadcastChannel<ToplevelEvent>,
     #[cfg(feature = "clipboard")]
     clipboard_channel: BroadcastChannel<ClipboardItem>,
+    #[cfg(feature = "reminders")]
+    idle_channel: BroadcastChannel<bool>,
 }
 
 impl Client {
@@ -130,12 +192,20 @@ impl Client {
         let (response_tx, response_rx) = std::sync::mpsc::channel();
 
         let output_channel = broadcast::channel(32);
-        #[cfg(any(feature = "focused", feature = "launcher"))]
+        #[cfg(any(
+            feature = "chat",
+            feature = "focused",
+            feature = "launcher",
+            feature = "reminders"
+        ))]
         let toplevel_channel = broadcast::channel(32);
 
         #[cfg(feature = "clipboard")]
         let clipboard_channel = broadcast::channel(32);
 
+        #[cfg(feature = "reminders")]
+        let idle_channel = broadcast::channel(8);
+
         spawn_blocking(move || {
             Environment::spawn(event_tx, request_rx, response_tx);
         });
@@ -143,20 +213,35 @@ impl Client {
         // listen to events
         {
             let output_tx = output_channel.0.clone();
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(any(
+                feature = "chat",
+                feature = "focused",
+                feature = "launcher",
+                feature = "reminders"
+            ))]
             let toplevel_tx = toplevel_channel.0.clone();
 
             #[cfg(feature = "clipboard")]
             let clipboard_tx = clipboard_channel.0.clone();
 
+            #[cfg(feature = "reminders")]
+            let idle_tx = idle_channel.0.clone();
+
             spawn(async move {
                 while let Some(event) = event_rx.recv().await {
                     match event {
                         Event::Output(event) => send!(output_tx, event),
-                        #[cfg(any(feature = "focused", feature = "launcher"))]
+                        #[cfg(any(
+                            feature = "chat",
+                            feature = "focused",
+                            feature = "launcher",
+                            feature = "reminders"
+                        ))]
                         Event::Toplevel(event) => send!(toplevel_tx, event),
                         #[cfg(feature = "clipboard")]
                         Event::Clipboard(item) => send!(clipboard_tx, item),
+                        #[cfg(feature = "reminders")]
+                        Event::Idle(idle) => send!(idle_tx, idle),
                     };
                 }
             });
@@ -167,10 +252,17 @@ impl Client {
             rx: arc_mut!(response_rx),
 
             output_channel: output_channel.into(),
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(any(
+                feature = "chat",
+                feature = "focused",
+                feature = "launcher",
+                feature = "reminders"
+            ))]
             toplevel_channel: toplevel_channel.into(),
             #[cfg(feature = "clipboard")]
             clipboard_channel: clipboard_channel.into(),
+            #[cfg(feature = "reminders")]
+            idle_channel: idle_channel.into(),
         }
     }
 
@@ -201,7 +293,12 @@ pub struct Environment {
     response_tx: std::sync::mpsc::Sender<Response>,
 
     // local state
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))]
     handles: Vec<ToplevelHandle>,
 
     // -- clipboard --
@@ -218,6 +315,22 @@ pub struct Environment {
     // local state
     #[cfg(feature = "clipboard")]
     clipboard: Arc<Mutex<Option<ClipboardItem>>>,
+
+    // -- gamma control --
+    #[cfg(feature = "night_light")]
+    gamma_control_manager_state: Option<GammaControlManagerState>,
+    #[cfg(feature = "night_light")]
+    gamma_controls: Vec<GammaControl>,
+
+    // local state
+    #[cfg(feature = "night_light")]
+    color_temperature: Option<u32>,
+
+    // -- idle notify --
+    #[cfg(feature = "reminders")]
+    idle_notifier_state: Option<IdleNotifierState>,
+    #[cfg(feature = "reminders")]
+    idle_notification: Option<ExtIdleNotificationV1>,
 }
 
 delegate_registry!(Environment);
@@ -226,7 +339,12 @@ delegate_output!(Environment);
 delegate_seat!(Environment);
 
 cfg_if! {
-    if #[cfg(any(feature = "focused", feature = "launcher"))] {
+    if #[cfg(any(
+        feature = "chat",
+        feature = "focused",
+        feature = "launcher",
+        feature = "reminders"
+    ))] {
         delegate_foreign_toplevel_manager!(Environment);
         delegate_foreign_toplevel_handle!(Environment);
     }
@@ -241,6 +359,20 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "night_light")] {
+        delegate_gamma_control_manager!(Environment);
+        delegate_gamma_control!(Environment);
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "reminders")] {
+        delegate_idle_notifier!(Environment);
+        delegate_idle_notification!(Environment);
+    }
+}
+
 impl Environment {
     pub fn spawn(
         event_tx: mpsc::Sender<Event>,
@@ -266,7 +398,12 @@ impl Environment {
 
         let output_state = OutputState::new(&globals, &qh);
         let seat_state = SeatState::new(&globals, &qh);
-        #[cfg(any(feature = "focused", feature = "launcher"))]
+        #[cfg(any(
+            feature = "chat",
+            feature = "focused",
+            feature = "launcher",
+            feature = "reminders"
+        ))]
         ToplevelManagerState::bind(&globals, &qh)
             .expect("to bind to wlr_foreign_toplevel_manager global");
 
@@ -274,6 +411,17 @@ impl Environment {
         let data_control_device_manager_state = DataControlDeviceManagerState::bind(&globals, &qh)
             .expect("to bind to wlr_data_control_device_manager global");
 
+        // gamma control is privileged, so is not available on all compositors
+        #[cfg(feature = "night_light")]
+        let gamma_control_manager_state = GammaControlManagerState::bind(&globals, &qh)
+            .map_err(|err| debug!("Gamma control is unavailable: {err:?}"))
+            .ok();
+
+        #[cfg(feature = "reminders")]
+        let idle_notifier_state = IdleNotifierState::bind(&globals, &qh)
+            .map_err(|err| debug!("Idle notifications are unavailable: {err:?}"))
+            .ok();
+
         let mut env = Self {
             registry_state,
             output_state,
@@ -284,7 +432,12 @@ impl Environment {
             loop_handle: loop_handle.clone(),
             event_tx,
             response_tx,
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(any(
+                feature = "chat",
+                feature = "focused",
+                feature = "launcher",
+                feature = "reminders"
+            ))]
             handles: vec![],
 
             #[cfg(feature = "clipboard")]
@@ -295,6 +448,18 @@ impl Environment {
             selection_offers: vec![],
             #[cfg(feature = "clipboard")]
             clipboard: arc_mut!(None),
+
+            #[cfg(feature = "night_light")]
+            gamma_control_manager_state,
+            #[cfg(feature = "night_light")]
+            gamma_controls: vec![],
+            #[cfg(feature = "night_light")]
+            color_temperature: None,
+
+            #[cfg(feature = "reminders")]
+            idle_notifier_state,
+            #[cfg(feature = "reminders")]
+            idle_notification: None,
         };
 
         loop_handle
@@ -324,12 +489,16 @@ impl Environment {
                 debug!("received roundtrip request");
                 send!(env.response_tx, Response::Ok);
             }
-            #[cfg(feature = "ipc")]
             Msg(Request::OutputInfoAll) => {
                 let infos = env.output_info_all();
                 send!(env.response_tx, Response::OutputInfoAll(infos));
             }
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(any(
+                feature = "chat",
+                feature = "focused",
+                feature = "launcher",
+                feature = "reminders"
+            ))]
             Msg(Request::ToplevelInfoAll) => {
                 let infos = env
                     .handles
@@ -338,12 +507,12 @@ impl Environment {
                     .collect();
                 send!(env.response_tx, Response::ToplevelInfoAll(infos));
             }
-            #[cfg(feature = "launcher")]
+            #[cfg(any(feature = "chat", feature = "launcher"))]
             Msg(Request::ToplevelFocus(id)) => {
                 let handle = env
                     .handles
                     .iter()
-                    .find(|handle| handle.info().map_or(false, |info| info.id == id));
+                    .find(|handle| handle.info().is_some_and(|info| info.id == id));
 
                 if let Some(handle) = handle {
                     let seat = env.default_seat();
@@ -357,7 +526,7 @@ impl Environment {
                 let handle = env
                     .handles
                     .iter()
-                    .find(|handle| handle.info().map_or(false, |info| info.id == id));
+                    .find(|handle| handle.info().is_some_and(|info| info.id == id));
 
                 if let Some(handle) = handle {
                     handle.minimize();
@@ -375,6 +544,26 @@ impl Environment {
                 let item = lock!(env.clipboard).clone();
                 send!(env.response_tx, Response::ClipboardItem(item));
             }
+            #[cfg(feature = "night_light")]
+            Msg(Request::SetColorTemperature(temperature)) => {
+                let response = if env.set_color_temperature(temperature) {
+                    Response::Ok
+                } else {
+                    Response::GammaControlUnsupported
+                };
+
+                send!(env.response_tx, response);
+            }
+            #[cfg(feature = "reminders")]
+            Msg(Request::IdleNotification(timeout)) => {
+                let response = if env.add_idle_notification(timeout) {
+                    Response::Ok
+                } else {
+                    Response::IdleNotifyUnsupported
+                };
+
+                send!(env.response_tx, response);
+            }
             calloop_channel::Event::Closed => error!("request channel unexpectedly closed"),
         }
     }
@@ -21,7 +21,6 @@ pub enum OutputEventType {
 
 impl Client {
     /// Gets the information for all outputs.
-    #[cfg(feature = "ipc")]
     pub fn output_info_all(&self) -> Vec<OutputInfo> {
         use super::{Request, Response};
         match self.send_request(Request::OutputInfoAll) {
@@ -37,7 +36,6 @@ impl Client {
 }
 
 impl Environment {
-    #[cfg(feature = "ipc")]
     pub fn output_info_all(&mut self) -> Vec<OutputInfo> {
         self.output_state
             .outputs()
@@ -62,6 +60,12 @@ impl OutputHandler for Environment {
 
     fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
         debug!("Handler received new output");
+
+        #[cfg(feature = "night_light")]
+        if self.color_temperature.is_some() {
+            self.add_gamma_control(output.clone());
+        }
+
         if let Some(info) = self.output_state.info(&output) {
             try_send!(
                 self.event_tx,
@@ -92,6 +96,10 @@ impl OutputHandler for Environment {
 
     fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
         debug!("Handle received output destruction");
+
+        #[cfg(feature = "night_light")]
+        self.remove_gamma_control(&output);
+
         if let Some(info) = self.output_state.info(&output) {
             try_send!(
                 self.event_tx,
@@ -0,0 +1,93 @@
+use smithay_client_toolkit::error::GlobalError;
+use smithay_client_toolkit::globals::{GlobalData, ProvidesBoundGlobal};
+use tracing::debug;
+use wayland_client::globals::{BindError, GlobalList};
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols_wlr::gamma_control::v1::client::{
+    zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1,
+    zwlr_gamma_control_v1::{Event, ZwlrGammaControlV1},
+};
+
+#[derive(Debug)]
+pub struct GammaControlManagerState {
+    manager: ZwlrGammaControlManagerV1,
+}
+
+impl GammaControlManagerState {
+    pub fn bind<State>(globals: &GlobalList, qh: &QueueHandle<State>) -> Result<Self, BindError>
+    where
+        State: Dispatch<ZwlrGammaControlManagerV1, GlobalData, State> + 'static,
+    {
+        let manager = globals.bind(qh, 1..=1, GlobalData)?;
+        debug!("Bound to ZwlrGammaControlManagerV1 global");
+        Ok(Self { manager })
+    }
+
+    /// Creates a gamma control for the given output.
+    /// Only one client can control an output's gamma at once.
+    pub fn get_gamma_control<D>(&self, qh: &QueueHandle<D>, output: &WlOutput) -> ZwlrGammaControlV1
+    where
+        D: Dispatch<ZwlrGammaControlV1, GlobalData> + 'static,
+    {
+        self.manager.get_gamma_control(output, qh, GlobalData)
+    }
+}
+
+pub trait GammaControlHandler: Sized {
+    /// Advertises the size of each gamma ramp for a control.
+    /// This is sent once, immediately after the control is created.
+    fn gamma_size(
+        &mut self,
+        conn: &Connection,
+        qh: &QueueHandle<Self>,
+        control: &ZwlrGammaControlV1,
+        size: u32,
+    );
+
+    /// The control is no longer valid,
+    /// for example because another client has taken control of the output.
+    fn failed(&mut self, conn: &Connection, qh: &QueueHandle<Self>, control: &ZwlrGammaControlV1);
+}
+
+impl ProvidesBoundGlobal<ZwlrGammaControlManagerV1, 1> for GammaControlManagerState {
+    fn bound_global(&self) -> Result<ZwlrGammaControlManagerV1, GlobalError> {
+        Ok(self.manager.clone())
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlManagerV1, GlobalData, D> for GammaControlManagerState
+where
+    D: Dispatch<ZwlrGammaControlManagerV1, GlobalData>,
+{
+    fn event(
+        _state: &mut D,
+        _proxy: &ZwlrGammaControlManagerV1,
+        _event: <ZwlrGammaControlManagerV1 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<D>,
+    ) {
+        unreachable!()
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlV1, GlobalData, D> for GammaControlManagerState
+where
+    D: Dispatch<ZwlrGammaControlV1, GlobalData> + GammaControlHandler,
+{
+    fn event(
+        state: &mut D,
+        control: &ZwlrGammaControlV1,
+        event: Event,
+        _data: &GlobalData,
+        conn: &Connection,
+        qh: &QueueHandle<D>,
+    ) {
+        match event {
+            Event::GammaSize { size } => state.gamma_size(conn, qh, control, size),
+            Event::Failed => state.failed(conn, qh, control),
+            _ => {}
+        }
+    }
+}
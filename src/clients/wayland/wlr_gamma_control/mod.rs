@@ -0,0 +1,232 @@
+pub mod manager;
+
+use self::manager::GammaControlHandler;
+use super::{Client, Environment, Request, Response};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::os::fd::AsFd;
+use tracing::{debug, error, warn};
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::{Connection, QueueHandle};
+use wayland_protocols_wlr::gamma_control::v1::client::zwlr_gamma_control_v1::ZwlrGammaControlV1;
+
+/// The colour temperature, in Kelvin,
+/// at which the gamma ramps are left unchanged.
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+
+#[derive(Debug)]
+pub struct GammaControl {
+    output: WlOutput,
+    control: ZwlrGammaControlV1,
+    /// The number of elements in each gamma ramp,
+    /// once it has been advertised by the compositor.
+    size: Option<u32>,
+}
+
+impl Client {
+    /// Tints all outputs to the given colour temperature, in Kelvin.
+    /// Passing `None` restores the original gamma tables.
+    pub fn set_color_temperature(&self, temperature: Option<u32>) -> Result<()> {
+        match self.send_request(Request::SetColorTemperature(temperature)) {
+            Response::Ok => Ok(()),
+            Response::GammaControlUnsupported => Err(eyre!(
+                "Compositor does not support the wlr-gamma-control protocol"
+            )),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Environment {
+    /// Sets the colour temperature of all outputs,
+    /// creating or destroying gamma controls as required.
+    ///
+    /// Returns `false` if the compositor does not support gamma control.
+    pub fn set_color_temperature(&mut self, temperature: Option<u32>) -> bool {
+        if self.gamma_control_manager_state.is_none() {
+            return false;
+        }
+
+        self.color_temperature = temperature;
+
+        match temperature {
+            Some(temperature) => {
+                let outputs = self.output_state.outputs().collect::<Vec<_>>();
+                for output in outputs {
+                    self.add_gamma_control(output);
+                }
+
+                // new controls are set once their size is known
+                for control in &self.gamma_controls {
+                    if let Some(size) = control.size {
+                        if let Err(err) = set_gamma(&control.control, size, temperature) {
+                            error!("{err:?}");
+                        }
+                    }
+                }
+            }
+            // destroying a control restores the original gamma tables
+            None => {
+                for control in self.gamma_controls.drain(..) {
+                    control.control.destroy();
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Creates a gamma control for the output,
+    /// if it does not already have one.
+    pub fn add_gamma_control(&mut self, output: WlOutput) {
+        if self
+            .gamma_controls
+            .iter()
+            .any(|control| control.output == output)
+        {
+            return;
+        }
+
+        if let Some(manager) = &self.gamma_control_manager_state {
+            debug!("Creating gamma control for output");
+            let control = manager.get_gamma_control(&self.queue_handle, &output);
+
+            self.gamma_controls.push(GammaControl {
+                output,
+                control,
+                size: None,
+            });
+        }
+    }
+
+    /// Destroys the gamma control for the output, if it has one.
+    pub fn remove_gamma_control(&mut self, output: &WlOutput) {
+        self.gamma_controls.retain(|control| {
+            if &control.output == output {
+                control.control.destroy();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+impl GammaControlHandler for Environment {
+    fn gamma_size(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        control: &ZwlrGammaControlV1,
+        size: u32,
+    ) {
+        debug!("Gamma control has ramp size {size}");
+
+        let Some(entry) = self
+            .gamma_controls
+            .iter_mut()
+            .find(|entry| &entry.control == control)
+        else {
+            return;
+        };
+
+        entry.size = Some(size);
+
+        if let Some(temperature) = self.color_temperature {
+            if let Err(err) = set_gamma(control, size, temperature) {
+                error!("{err:?}");
+            }
+        }
+    }
+
+    fn failed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        control: &ZwlrGammaControlV1,
+    ) {
+        warn!("Gamma control failed. Another application may be controlling the output's gamma");
+
+        self.gamma_controls
+            .retain(|entry| &entry.control != control);
+        control.destroy();
+    }
+}
+
+/// Writes gamma ramps for the colour temperature to a memfd,
+/// and sends it to the compositor.
+fn set_gamma(control: &ZwlrGammaControlV1, size: u32, temperature: u32) -> Result<()> {
+    let ramps = gamma_ramps(size, temperature);
+
+    let fd = memfd_create(c"ironbar-gamma", MemFdCreateFlag::MFD_CLOEXEC)?;
+    let mut file = File::from(fd);
+    file.write_all(&ramps)?;
+    file.rewind()?;
+
+    control.set_gamma(file.as_fd());
+    Ok(())
+}
+
+/// Builds the gamma table for the colour temperature,
+/// made up of a linear ramp for each of the red, green and blue channels,
+/// as native-endian `u16` values.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn gamma_ramps(size: u32, temperature: u32) -> Vec<u8> {
+    let (red, green, blue) = whitepoint(temperature);
+    let max = f64::from(size.saturating_sub(1).max(1));
+
+    [red, green, blue]
+        .into_iter()
+        .flat_map(|channel| {
+            (0..size).map(move |i| {
+                let value = f64::from(i) / max * channel * f64::from(u16::MAX);
+                value.round() as u16
+            })
+        })
+        .flat_map(u16::to_ne_bytes)
+        .collect()
+}
+
+/// Gets the colour of a black body at the temperature,
+/// as red, green and blue multipliers relative to the neutral temperature.
+///
+/// This uses Tanner Helland's approximation of the black body curve.
+fn whitepoint(temperature: u32) -> (f64, f64, f64) {
+    fn rgb(temperature: u32) -> (f64, f64, f64) {
+        let temp = f64::from(temperature.clamp(1000, 40000)) / 100.0;
+
+        let red = if temp <= 66.0 {
+            255.0
+        } else {
+            329.698_727_446 * (temp - 60.0).powf(-0.133_204_759_2)
+        };
+
+        let green = if temp <= 66.0 {
+            99.470_802_586_1 * temp.ln() - 161.119_568_166_1
+        } else {
+            288.122_169_528_3 * (temp - 60.0).powf(-0.075_514_849_2)
+        };
+
+        let blue = if temp >= 66.0 {
+            255.0
+        } else if temp <= 19.0 {
+            0.0
+        } else {
+            138.517_731_223_1 * (temp - 10.0).ln() - 305.044_792_730_7
+        };
+
+        (red, green, blue)
+    }
+
+    let (red, green, blue) = rgb(temperature);
+    let (neutral_red, neutral_green, neutral_blue) = rgb(NEUTRAL_TEMPERATURE);
+
+    (
+        (red / neutral_red).clamp(0.0, 1.0),
+        (green / neutral_green).clamp(0.0, 1.0),
+        (blue / neutral_blue).clamp(0.0, 1.0),
+    )
+}
@@ -0,0 +1,91 @@
+use super::Coordinates;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use std::time::Duration;
+use tokio::time::timeout;
+use zbus::dbus_proxy;
+use zbus::zvariant::OwnedObjectPath;
+
+/// How long to wait for GeoClue to find a location.
+const LOCATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Accuracy level to request.
+/// Weather does not need anything finer than the city.
+const ACCURACY_CITY: u32 = 4;
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.GeoClue2",
+    interface = "org.freedesktop.GeoClue2.Manager",
+    default_path = "/org/freedesktop/GeoClue2/Manager"
+)]
+trait Manager {
+    fn get_client(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.GeoClue2",
+    interface = "org.freedesktop.GeoClue2.Client",
+    assume_defaults = false
+)]
+trait Client {
+    fn start(&self) -> zbus::Result<()>;
+
+    fn stop(&self) -> zbus::Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn location_updated(&self, old: OwnedObjectPath, new: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn set_desktop_id(&self, value: &str) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn set_requested_accuracy_level(&self, value: u32) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    default_service = "org.freedesktop.GeoClue2",
+    interface = "org.freedesktop.GeoClue2.Location",
+    assume_defaults = false
+)]
+trait Location {
+    #[dbus_proxy(property)]
+    fn latitude(&self) -> zbus::Result<f64>;
+
+    #[dbus_proxy(property)]
+    fn longitude(&self) -> zbus::Result<f64>;
+}
+
+/// Gets the current location from GeoClue.
+///
+/// GeoClue only allows known applications access,
+/// so this may require an agent or an entry in its config
+/// for the `ironbar` desktop ID.
+pub async fn locate() -> Result<Coordinates> {
+    let dbus = zbus::Connection::system().await?;
+
+    let manager = ManagerProxy::new(&dbus).await?;
+    let path = manager.get_client().await?;
+
+    let client = ClientProxy::builder(&dbus).path(path)?.build().await?;
+    client.set_desktop_id(env!("CARGO_PKG_NAME")).await?;
+    client.set_requested_accuracy_level(ACCURACY_CITY).await?;
+
+    let mut updates = client.receive_location_updated().await?;
+    client.start().await?;
+
+    let update = timeout(LOCATE_TIMEOUT, updates.next()).await;
+    client.stop().await?;
+
+    let update = update
+        .map_err(|_| eyre!("Timed out waiting for location"))?
+        .ok_or_else(|| eyre!("Location stream closed"))?;
+
+    let path = update.args()?.new;
+    let location = LocationProxy::builder(&dbus).path(path)?.build().await?;
+
+    Ok(Coordinates {
+        latitude: location.latitude().await?,
+        longitude: location.longitude().await?,
+    })
+}
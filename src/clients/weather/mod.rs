@@ -0,0 +1,207 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use color_eyre::Result;
+
+pub mod geoclue;
+pub mod open_meteo;
+pub mod openweathermap;
+
+/// A simplified weather condition,
+/// shared between providers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Snow,
+    Thunderstorm,
+    Unknown,
+}
+
+impl Condition {
+    /// Converts a WMO weather interpretation code,
+    /// as used by Open-Meteo.
+    pub const fn from_wmo(code: u32) -> Self {
+        match code {
+            0 | 1 => Self::Clear,
+            2 => Self::PartlyCloudy,
+            3 => Self::Cloudy,
+            45 | 48 => Self::Fog,
+            51..=57 => Self::Drizzle,
+            61..=67 | 80..=82 => Self::Rain,
+            71..=77 | 85 | 86 => Self::Snow,
+            95..=99 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Converts an OpenWeatherMap condition ID.
+    pub const fn from_owm(id: u32) -> Self {
+        match id {
+            200..=299 => Self::Thunderstorm,
+            300..=399 => Self::Drizzle,
+            500..=599 => Self::Rain,
+            600..=699 => Self::Snow,
+            700..=799 => Self::Fog,
+            800 | 801 => Self::Clear,
+            802 => Self::PartlyCloudy,
+            803 | 804 => Self::Cloudy,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Gets the CSS class name for the condition.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::PartlyCloudy => "partly-cloudy",
+            Self::Cloudy => "cloudy",
+            Self::Fog => "fog",
+            Self::Drizzle => "drizzle",
+            Self::Rain => "rain",
+            Self::Snow => "snow",
+            Self::Thunderstorm => "thunderstorm",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Gets a human-readable description of the condition.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Clear => "Clear",
+            Self::PartlyCloudy => "Partly cloudy",
+            Self::Cloudy => "Cloudy",
+            Self::Fog => "Fog",
+            Self::Drizzle => "Drizzle",
+            Self::Rain => "Rain",
+            Self::Snow => "Snow",
+            Self::Thunderstorm => "Thunderstorm",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+/// The current weather.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Current {
+    pub temperature: f64,
+    pub condition: Condition,
+    /// Whether the sun is up, used to pick day or night icons.
+    pub is_day: bool,
+}
+
+/// The forecast for a single hour,
+/// or a three-hour period for OpenWeatherMap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hourly {
+    /// Start time, in the location's time zone.
+    pub time: DateTime<FixedOffset>,
+    pub temperature: f64,
+    pub condition: Condition,
+    pub is_day: bool,
+    /// Chance of precipitation, as a percentage.
+    pub precipitation: Option<f64>,
+}
+
+/// The forecast for a single day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Daily {
+    pub date: NaiveDate,
+    pub min: f64,
+    pub max: f64,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weather {
+    pub current: Current,
+    /// Upcoming hours, starting with the current one.
+    pub hourly: Vec<Hourly>,
+    /// Upcoming days, starting with today.
+    pub daily: Vec<Daily>,
+}
+
+/// Temperature units to request from the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub const fn temperature_symbol(self) -> &'static str {
+        match self {
+            Self::Metric => "°C",
+            Self::Imperial => "°F",
+        }
+    }
+}
+
+/// A position on Earth, in decimal degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A weather data service.
+#[derive(Debug, Clone)]
+pub enum Provider {
+    OpenMeteo,
+    OpenWeatherMap { api_key: String },
+}
+
+impl Provider {
+    /// Fetches the current weather and forecast for `location`.
+    pub async fn fetch(
+        &self,
+        http: &reqwest::Client,
+        location: Coordinates,
+        units: Units,
+    ) -> Result<Weather> {
+        match self {
+            Self::OpenMeteo => open_meteo::fetch(http, location, units).await,
+            Self::OpenWeatherMap { api_key } => {
+                openweathermap::fetch(http, api_key, location, units).await
+            }
+        }
+    }
+}
+
+/// Converts a Unix timestamp to a time with the given UTC offset in seconds.
+fn local_time(timestamp: i64, offset: i32) -> Option<DateTime<FixedOffset>> {
+    let offset = FixedOffset::east_opt(offset)?;
+    DateTime::from_timestamp(timestamp, 0).map(|time| time.with_timezone(&offset))
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<String> {
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions() {
+        assert_eq!(Condition::from_wmo(0), Condition::Clear);
+        assert_eq!(Condition::from_wmo(81), Condition::Rain);
+        assert_eq!(Condition::from_wmo(86), Condition::Snow);
+        assert_eq!(Condition::from_wmo(100), Condition::Unknown);
+
+        assert_eq!(Condition::from_owm(211), Condition::Thunderstorm);
+        assert_eq!(Condition::from_owm(741), Condition::Fog);
+        assert_eq!(Condition::from_owm(802), Condition::PartlyCloudy);
+        assert_eq!(Condition::from_owm(900), Condition::Unknown);
+    }
+
+    #[test]
+    fn test_local_time() {
+        let time = local_time(1_717_200_000, 2 * 3600).expect("time should be valid");
+        assert_eq!(time.to_rfc3339(), "2024-06-01T02:00:00+02:00");
+
+        assert_eq!(local_time(0, 24 * 3600), None);
+    }
+}
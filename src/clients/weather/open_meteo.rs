@@ -0,0 +1,122 @@
+use super::{local_time, send, Condition, Coordinates, Current, Daily, Hourly, Units, Weather};
+use chrono::Utc;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+/// Number of days to request.
+/// Hourly data is returned for the whole period.
+const FORECAST_DAYS: u32 = 7;
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    utc_offset_seconds: i32,
+    current: CurrentResponse,
+    hourly: HourlyResponse,
+    daily: DailyResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentResponse {
+    temperature_2m: f64,
+    weather_code: u32,
+    is_day: u8,
+}
+
+/// Hourly values, as parallel arrays.
+#[derive(Debug, Deserialize)]
+struct HourlyResponse {
+    time: Vec<i64>,
+    temperature_2m: Vec<f64>,
+    weather_code: Vec<u32>,
+    is_day: Vec<u8>,
+    precipitation_probability: Vec<Option<f64>>,
+}
+
+/// Daily values, as parallel arrays.
+#[derive(Debug, Deserialize)]
+struct DailyResponse {
+    time: Vec<i64>,
+    weather_code: Vec<u32>,
+    temperature_2m_max: Vec<f64>,
+    temperature_2m_min: Vec<f64>,
+}
+
+pub async fn fetch(http: &reqwest::Client, location: Coordinates, units: Units) -> Result<Weather> {
+    let temperature_unit = match units {
+        Units::Metric => "celsius",
+        Units::Imperial => "fahrenheit",
+    };
+
+    let request = http.get(API_URL).query(&[
+        ("latitude", location.latitude.to_string().as_str()),
+        ("longitude", location.longitude.to_string().as_str()),
+        ("current", "temperature_2m,weather_code,is_day"),
+        (
+            "hourly",
+            "temperature_2m,weather_code,is_day,precipitation_probability",
+        ),
+        (
+            "daily",
+            "weather_code,temperature_2m_max,temperature_2m_min",
+        ),
+        ("temperature_unit", temperature_unit),
+        ("timeformat", "unixtime"),
+        ("timezone", "auto"),
+        ("forecast_days", FORECAST_DAYS.to_string().as_str()),
+    ]);
+
+    let body = send(request).await?;
+    let response = serde_json::from_str::<Response>(&body)?;
+    let offset = response.utc_offset_seconds;
+
+    let current = Current {
+        temperature: response.current.temperature_2m,
+        condition: Condition::from_wmo(response.current.weather_code),
+        is_day: response.current.is_day == 1,
+    };
+
+    // the first hour returned is midnight, so skip to the current hour
+    let now = Utc::now().timestamp();
+    let hourly = response.hourly;
+    let hourly = hourly
+        .time
+        .iter()
+        .enumerate()
+        .filter(|(_, time)| **time + 3600 > now)
+        .map(|(i, &time)| {
+            Some(Hourly {
+                time: local_time(time, offset)?,
+                temperature: *hourly.temperature_2m.get(i)?,
+                condition: Condition::from_wmo(*hourly.weather_code.get(i)?),
+                is_day: *hourly.is_day.get(i)? == 1,
+                precipitation: hourly.precipitation_probability.get(i).copied().flatten(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| eyre!("Hourly forecast is incomplete"))?;
+
+    let daily = response.daily;
+    let daily = daily
+        .time
+        .iter()
+        .enumerate()
+        .map(|(i, &time)| {
+            Some(Daily {
+                date: local_time(time, offset)?.date_naive(),
+                min: *daily.temperature_2m_min.get(i)?,
+                max: *daily.temperature_2m_max.get(i)?,
+                condition: Condition::from_wmo(*daily.weather_code.get(i)?),
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| eyre!("Daily forecast is incomplete"))?;
+
+    Ok(Weather {
+        current,
+        hourly,
+        daily,
+    })
+}
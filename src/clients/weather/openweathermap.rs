@@ -0,0 +1,207 @@
+use super::{local_time, send, Condition, Coordinates, Current, Daily, Hourly, Units, Weather};
+use chrono::Timelike;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.openweathermap.org/data/2.5";
+
+#[derive(Debug, Deserialize)]
+struct CurrentResponse {
+    weather: Vec<ConditionResponse>,
+    main: MainResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+    list: Vec<ForecastEntry>,
+    city: City,
+}
+
+#[derive(Debug, Deserialize)]
+struct City {
+    /// UTC offset in seconds.
+    timezone: i32,
+}
+
+/// A three-hour forecast period.
+#[derive(Debug, Deserialize)]
+struct ForecastEntry {
+    dt: i64,
+    main: MainResponse,
+    weather: Vec<ConditionResponse>,
+    /// Probability of precipitation, from 0 to 1.
+    pop: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MainResponse {
+    temp: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConditionResponse {
+    id: u32,
+    /// Icon code, such as `01d`.
+    /// The suffix is `d` during the day and `n` at night.
+    icon: String,
+}
+
+impl ConditionResponse {
+    fn is_day(&self) -> bool {
+        !self.icon.ends_with('n')
+    }
+}
+
+/// Gets the first condition of a response,
+/// which is the primary one.
+fn primary(conditions: &[ConditionResponse]) -> Result<&ConditionResponse> {
+    conditions
+        .first()
+        .ok_or_else(|| eyre!("Response has no weather condition"))
+}
+
+/// Fetches weather using the free current weather and 5-day forecast APIs.
+///
+/// The free forecast is in three-hour periods,
+/// so the hourly forecast has a three-hour step
+/// and days are summarised from their periods.
+pub async fn fetch(
+    http: &reqwest::Client,
+    api_key: &str,
+    location: Coordinates,
+    units: Units,
+) -> Result<Weather> {
+    let query = [
+        ("lat", location.latitude.to_string()),
+        ("lon", location.longitude.to_string()),
+        ("appid", api_key.to_string()),
+        (
+            "units",
+            match units {
+                Units::Metric => "metric",
+                Units::Imperial => "imperial",
+            }
+            .to_string(),
+        ),
+    ];
+
+    let body = send(http.get(format!("{API_URL}/weather")).query(&query)).await?;
+    let current = serde_json::from_str::<CurrentResponse>(&body)?;
+
+    let body = send(http.get(format!("{API_URL}/forecast")).query(&query)).await?;
+    let forecast = serde_json::from_str::<ForecastResponse>(&body)?;
+
+    let condition = primary(&current.weather)?;
+    let current = Current {
+        temperature: current.main.temp,
+        condition: Condition::from_owm(condition.id),
+        is_day: condition.is_day(),
+    };
+
+    let hourly = forecast
+        .list
+        .iter()
+        .map(|entry| {
+            let condition = primary(&entry.weather)?;
+
+            Ok(Hourly {
+                time: local_time(entry.dt, forecast.city.timezone)
+                    .ok_or_else(|| eyre!("Invalid forecast time"))?,
+                temperature: entry.main.temp,
+                condition: Condition::from_owm(condition.id),
+                is_day: condition.is_day(),
+                precipitation: entry.pop.map(|pop| pop * 100.0),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Weather {
+        current,
+        daily: summarise_days(&hourly),
+        hourly,
+    })
+}
+
+/// Groups forecast periods by date,
+/// using the lowest and highest temperatures of each day
+/// and the condition closest to midday.
+fn summarise_days(hourly: &[Hourly]) -> Vec<Daily> {
+    let mut daily: Vec<(Daily, u32)> = vec![];
+
+    for hour in hourly {
+        let date = hour.time.date_naive();
+        let distance = hour.time.hour().abs_diff(12);
+
+        match daily.last_mut() {
+            Some((day, closest)) if day.date == date => {
+                day.min = day.min.min(hour.temperature);
+                day.max = day.max.max(hour.temperature);
+
+                if distance < *closest {
+                    day.condition = hour.condition;
+                    *closest = distance;
+                }
+            }
+            _ => daily.push((
+                Daily {
+                    date,
+                    min: hour.temperature,
+                    max: hour.temperature,
+                    condition: hour.condition,
+                },
+                distance,
+            )),
+        }
+    }
+
+    daily.into_iter().map(|(day, _)| day).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn hour(timestamp: i64, temperature: f64, condition: Condition) -> Hourly {
+        Hourly {
+            time: local_time(timestamp, 0).expect("timestamp should be valid"),
+            temperature,
+            condition,
+            is_day: true,
+            precipitation: None,
+        }
+    }
+
+    #[test]
+    fn test_summarise_days() {
+        // 2024-06-01 00:00 UTC
+        const MIDNIGHT: i64 = 1_717_200_000;
+        const HOUR: i64 = 3600;
+
+        let hourly = [
+            hour(MIDNIGHT + 9 * HOUR, 10.0, Condition::Rain),
+            hour(MIDNIGHT + 12 * HOUR, 15.0, Condition::Clear),
+            hour(MIDNIGHT + 21 * HOUR, 8.0, Condition::Cloudy),
+            hour(MIDNIGHT + 27 * HOUR, 5.0, Condition::Snow),
+        ];
+
+        assert_eq!(
+            summarise_days(&hourly),
+            vec![
+                Daily {
+                    date: NaiveDate::from_ymd_opt(2024, 6, 1).expect("date should be valid"),
+                    min: 8.0,
+                    max: 15.0,
+                    condition: Condition::Clear,
+                },
+                Daily {
+                    date: NaiveDate::from_ymd_opt(2024, 6, 2).expect("date should be valid"),
+                    min: 5.0,
+                    max: 5.0,
+                    condition: Condition::Snow,
+                },
+            ]
+        );
+    }
+}
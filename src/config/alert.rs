@@ -0,0 +1,167 @@
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::script::{Script, ScriptInput};
+use gtk::prelude::*;
+use gtk::Widget;
+use serde::Deserialize;
+use tracing::debug;
+
+/// Modules which display a numeric value can react when it crosses a threshold.
+/// This is controlled using a common `Alert` type,
+/// which is defined below.
+///
+/// An alert is active while the value is above `above` and below `below`.
+/// At least one of the two should be set.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Alert {
+    /// The alert is active while the value is above this number.
+    ///
+    /// **Default**: `null`
+    above: Option<f64>,
+
+    /// The alert is active while the value is below this number.
+    ///
+    /// **Default**: `null`
+    below: Option<f64>,
+
+    /// The CSS class to add to the widget while the alert is active.
+    ///
+    /// **Default**: `null`
+    class: Option<String>,
+
+    /// A [script](scripts) to run when the alert becomes active.
+    /// The value is passed as the first argument.
+    ///
+    /// **Supported script types**: `oneshot`.
+    ///
+    /// **Default**: `null`
+    exec: Option<ScriptInput>,
+}
+
+impl Alert {
+    /// Creates an alert which adds `class` while the value is above `above`,
+    /// for modules which provide default alerts.
+    #[cfg(feature = "temperature")]
+    pub fn above(above: f64, class: &str) -> Self {
+        Self {
+            above: Some(above),
+            below: None,
+            class: Some(class.to_string()),
+            exec: None,
+        }
+    }
+
+    pub fn is_active(&self, value: f64) -> bool {
+        self.above.is_none_or(|above| value > above) && self.below.is_none_or(|below| value < below)
+    }
+
+    #[cfg(feature = "temperature")]
+    pub fn class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+}
+
+/// Tracks which of a set of alerts are active,
+/// so that scripts only run when an alert becomes active
+/// rather than on every update.
+#[derive(Debug)]
+pub struct Alerts {
+    alerts: Vec<(Alert, Option<Script>)>,
+    active: Vec<bool>,
+}
+
+impl Alerts {
+    pub fn new(alerts: Vec<Alert>) -> Self {
+        let active = vec![false; alerts.len()];
+
+        let alerts = alerts
+            .into_iter()
+            .map(|alert| {
+                let script = alert.exec.clone().map(Script::new_polling);
+                (alert, script)
+            })
+            .collect();
+
+        Self { alerts, active }
+    }
+
+    /// Checks each alert against `value`,
+    /// updating the classes on `widget` and running scripts for alerts which became active.
+    pub fn update<W: IsA<Widget>>(&mut self, widget: &W, value: f64) {
+        for ((alert, script), active) in self.alerts.iter().zip(self.active.iter_mut()) {
+            let is_active = alert.is_active(value);
+            if is_active == *active {
+                continue;
+            }
+
+            *active = is_active;
+
+            if let Some(class) = &alert.class {
+                if is_active {
+                    widget.add_class(class);
+                } else {
+                    widget.remove_class(class);
+                }
+            }
+
+            if is_active {
+                if let Some(script) = script {
+                    debug!("Running alert script for value {value}");
+                    script.run_as_oneshot(Some(&[value.to_string()]));
+                }
+            }
+        }
+    }
+
+    /// Deactivates every alert, removing their classes from `widget`,
+    /// for when there is no longer a value to check.
+    #[cfg(any(
+        feature = "disk",
+        feature = "gpu",
+        feature = "http",
+        feature = "net_speed",
+        feature = "prometheus",
+        feature = "temperature"
+    ))]
+    pub fn clear<W: IsA<Widget>>(&mut self, widget: &W) {
+        for ((alert, _), active) in self.alerts.iter().zip(self.active.iter_mut()) {
+            if !std::mem::take(active) {
+                continue;
+            }
+
+            if let Some(class) = &alert.class {
+                widget.remove_class(class);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(above: Option<f64>, below: Option<f64>) -> Alert {
+        Alert {
+            above,
+            below,
+            class: None,
+            exec: None,
+        }
+    }
+
+    #[test]
+    fn test_is_active() {
+        let high = alert(Some(80.0), None);
+        assert!(high.is_active(90.0));
+        assert!(!high.is_active(80.0));
+
+        let low = alert(None, Some(20.0));
+        assert!(low.is_active(10.0));
+        assert!(!low.is_active(20.0));
+
+        let range = alert(Some(20.0), Some(80.0));
+        assert!(range.is_active(50.0));
+        assert!(!range.is_active(10.0));
+        assert!(!range.is_active(90.0));
+    }
+}
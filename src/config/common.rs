@@ -1,10 +1,18 @@
+use super::parse_duration;
 use crate::dynamic_value::{dynamic_string, DynamicBool};
+#[cfg(feature = "workspaces")]
+use crate::event_bus::workspace_visible;
 use crate::script::{Script, ScriptInput};
+#[cfg(any(feature = "presentation", feature = "workspaces"))]
+use crate::{glib_recv, Ironbar};
 use glib::Propagation;
 use gtk::gdk::ScrollDirection;
 use gtk::prelude::*;
-use gtk::{EventBox, Orientation, Revealer, RevealerTransitionType};
+use gtk::{Align, EventBox, Orientation, Revealer, RevealerTransitionType};
 use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
 use tracing::trace;
 
 /// The following are module-level options which are present on **all** modules.
@@ -44,6 +52,17 @@ pub struct CommonConfig {
     /// **Default**: `null`
     pub tooltip: Option<String>,
 
+    /// Shows a second label stacked below the module's own content,
+    /// for example the date below the time,
+    /// or the album below the track title.
+    /// This is a [Dynamic String](dynamic-values#dynamic-string).
+    ///
+    /// The module's content is given the `.primary` class,
+    /// and this label the `.secondary` class.
+    ///
+    /// **Default**: `null`
+    pub secondary_label: Option<String>,
+
     /// Shows the module only if the dynamic boolean evaluates to true.
     ///
     /// This allows for modules to be dynamically shown or hidden
@@ -52,6 +71,32 @@ pub struct CommonConfig {
     /// **Default**: `null`
     pub show_if: Option<DynamicBool>,
 
+    /// Hides the module while presentation mode is enabled.
+    ///
+    /// Useful for distracting modules such as mail or chat counts.
+    /// Can be combined with `show_if`.
+    ///
+    /// **Default**: `false`
+    #[cfg(feature = "presentation")]
+    #[serde(default)]
+    pub hide_in_presentation: bool,
+
+    /// Shows the module only while one of the named workspaces is focused.
+    ///
+    /// Can be combined with `show_if`.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "workspaces")]
+    pub show_if_workspace: Option<Vec<String>>,
+
+    /// Hides the module while one of the named workspaces is focused.
+    ///
+    /// Can be combined with `show_if`.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "workspaces")]
+    pub hide_if_workspace: Option<Vec<String>>,
+
     /// The transition animation to use when showing/hiding the widget.
     ///
     /// Note this has no effect if `show_if` is not configured.
@@ -157,6 +202,143 @@ pub struct CommonConfig {
     /// Prevents the popup from opening on-click for this widget.
     #[serde(default)]
     pub disable_popup: bool,
+
+    /// Delays starting the module's background work,
+    /// such as polling or connecting to services,
+    /// so that the bar can be drawn sooner.
+    /// The module is shown empty until then.
+    ///
+    /// This can be set to a duration after the bar is created,
+    /// such as `5s` or `500ms`,
+    /// `on-first-view` to start once the module is first shown,
+    /// or `on-demand` to start when the module is first hovered.
+    ///
+    /// **Default**: `null`
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub defer_start: Option<DeferStart>,
+
+    /// Whether to show the module's icons, text, or both.
+    ///
+    /// The `auto` mode shows both,
+    /// but collapses the module to icon-only
+    /// when the bar runs out of space.
+    ///
+    /// Icons are images and any widget with the `.icon` class.
+    /// Text is any other label.
+    /// Modules without any icons are never collapsed.
+    ///
+    /// **Valid options**: `icon`, `text`, `both`, `auto`
+    /// <br>
+    /// **Default**: `both`
+    #[serde(default)]
+    pub display_mode: DisplayMode,
+
+    /// The priority of the module when using the `auto` display mode,
+    /// or the bar `overflow` option.
+    /// Modules with a lower priority are collapsed first,
+    /// and expanded last.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub display_priority: i32,
+
+    /// Fixes the size of the module along the bar, in pixels.
+    /// This is the width on horizontal bars, and the height on vertical bars.
+    ///
+    /// Content larger than this is clipped,
+    /// which stops modules with changing labels
+    /// from moving their neighbours on every update.
+    ///
+    /// Modules with their own `width` option, such as `menu` or `cairo`,
+    /// use that instead.
+    ///
+    /// **Default**: `null`
+    pub width: Option<i32>,
+
+    /// The minimum size of the module along the bar, in pixels.
+    /// This is the width on horizontal bars, and the height on vertical bars.
+    ///
+    /// Unlike `width`, the module can grow past this if its content requires it.
+    ///
+    /// **Default**: `null`
+    pub min_width: Option<i32>,
+
+    /// How to align the module's content
+    /// when `width` or `min_width` gives it more space than it needs.
+    ///
+    /// **Valid options**: `start`, `center`, `end`
+    /// <br>
+    /// **Default**: `center`
+    #[serde(default)]
+    pub justify: Justify,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DisplayMode {
+    Icon,
+    Text,
+    #[default]
+    Both,
+    Auto,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Justify {
+    Start,
+    #[default]
+    Center,
+    End,
+}
+
+impl From<Justify> for Align {
+    fn from(justify: Justify) -> Self {
+        match justify {
+            Justify::Start => Self::Start,
+            Justify::Center => Self::Center,
+            Justify::End => Self::End,
+        }
+    }
+}
+
+/// When to start a module's controller.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub enum DeferStart {
+    /// Starts after a fixed delay.
+    Delay(Duration),
+    /// Starts when the module is first mapped.
+    OnFirstView,
+    /// Starts when the module is first hovered.
+    OnDemand,
+}
+
+impl TryFrom<String> for DeferStart {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "on-first-view" => return Ok(Self::OnFirstView),
+            "on-demand" => return Ok(Self::OnDemand),
+            _ => {}
+        }
+
+        // unlike `parse_duration`, a plain number is a number of milliseconds here
+        let delay = match value.trim().parse::<u64>() {
+            Ok(millis) => Ok(Duration::from_millis(millis)),
+            Err(_) => parse_duration(&value),
+        };
+
+        delay.map(Self::Delay).map_err(|err| {
+            format!(
+                "invalid value '{value}' ({err}), expected a duration such as '5s', \
+                 'on-first-view' or 'on-demand'"
+            )
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -215,6 +397,12 @@ impl TransitionType {
 }
 
 impl CommonConfig {
+    /// Whether the visibility depends on the focused workspace.
+    #[cfg(feature = "workspaces")]
+    pub fn has_workspace_filter(&self) -> bool {
+        self.show_if_workspace.is_some() || self.hide_if_workspace.is_some()
+    }
+
     /// Configures the module's container according to the common config options.
     pub fn install_events(mut self, container: &EventBox, revealer: &Revealer) {
         self.install_show_if(container, revealer);
@@ -287,31 +475,107 @@ impl CommonConfig {
     }
 
     fn install_show_if(&mut self, container: &EventBox, revealer: &Revealer) {
-        self.show_if.take().map_or_else(
-            || {
-                container.show_all();
-            },
-            |show_if| {
-                let container = container.clone();
-
-                {
-                    let revealer = revealer.clone();
-                    let container = container.clone();
-
-                    show_if.subscribe(move |success| {
-                        if success {
-                            container.show_all();
-                        }
-                        revealer.set_reveal_child(success);
-                    });
+        let show_if = self.show_if.take();
+
+        #[cfg(feature = "presentation")]
+        let hide_in_presentation = self.hide_in_presentation;
+        #[cfg(not(feature = "presentation"))]
+        let hide_in_presentation = false;
+
+        #[cfg(feature = "workspaces")]
+        let has_workspace_filter = self.has_workspace_filter();
+        #[cfg(not(feature = "workspaces"))]
+        let has_workspace_filter = false;
+
+        if show_if.is_none() && !hide_in_presentation && !has_workspace_filter {
+            container.show_all();
+            return;
+        }
+
+        // (`show_if` result, hidden by presentation mode, hidden by workspace)
+        let state = Rc::new(Cell::new((show_if.is_none(), false, false)));
+
+        let update = {
+            let container = container.clone();
+            let revealer = revealer.clone();
+            let state = state.clone();
+
+            Rc::new(move || {
+                let (shown, hidden_presentation, hidden_workspace) = state.get();
+                let visible = shown && !hidden_presentation && !hidden_workspace;
+
+                if visible {
+                    container.show_all();
                 }
+                revealer.set_reveal_child(visible);
+            })
+        };
+
+        if let Some(show_if) = show_if {
+            let state = state.clone();
+            let update = update.clone();
+
+            show_if.subscribe(move |success| {
+                let (_, presentation, workspace) = state.get();
+                state.set((success, presentation, workspace));
+                update();
+            });
+        }
 
-                revealer.connect_child_revealed_notify(move |revealer| {
-                    if !revealer.reveals_child() {
-                        container.hide();
-                    }
-                });
-            },
-        );
+        #[cfg(feature = "presentation")]
+        if hide_in_presentation {
+            let event_bus = Ironbar::event_bus();
+            let (shown, _, workspace) = state.get();
+            state.set((shown, event_bus.state().presentation, workspace));
+            update();
+
+            let state = state.clone();
+            let update = update.clone();
+            glib_recv!(event_bus.subscribe(), event => {
+                if let Some(enabled) = event.presentation() {
+                    let (shown, _, workspace) = state.get();
+                    state.set((shown, enabled, workspace));
+                    update();
+                }
+            });
+        }
+
+        #[cfg(feature = "workspaces")]
+        if has_workspace_filter {
+            let event_bus = Ironbar::event_bus();
+            let show_if_workspace = self.show_if_workspace.take();
+            let hide_if_workspace = self.hide_if_workspace.take();
+
+            let visible = move |workspace: Option<&str>| {
+                workspace_visible(
+                    show_if_workspace.as_deref(),
+                    hide_if_workspace.as_deref(),
+                    workspace,
+                )
+            };
+
+            let (shown, presentation, _) = state.get();
+            state.set((
+                shown,
+                presentation,
+                !visible(event_bus.state().workspace.as_deref()),
+            ));
+            update();
+
+            glib_recv!(event_bus.subscribe(), event => {
+                if let Some(workspace) = event.workspace() {
+                    let (shown, presentation, _) = state.get();
+                    state.set((shown, presentation, !visible(Some(workspace))));
+                    update();
+                }
+            });
+        }
+
+        let container = container.clone();
+        revealer.connect_child_revealed_notify(move |revealer| {
+            if !revealer.reveals_child() {
+                container.hide();
+            }
+        });
     }
 }
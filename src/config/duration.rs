@@ -0,0 +1,78 @@
+use color_eyre::{Report, Result};
+use std::time::Duration;
+
+/// Parses a duration made up of `h`, `m`, `s` and `ms` parts,
+/// such as `10m` or `1h30m`, or a plain number of minutes.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+
+    if let Ok(minutes) = input.parse::<u64>() {
+        return minutes
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| Report::msg(format!("Duration '{input}' is too long")));
+    }
+
+    let mut millis = 0_u64;
+    let mut number = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch.is_ascii_digit() {
+            number.push(ch);
+            continue;
+        }
+
+        let (unit, multiplier) = match ch {
+            'h' => ("h", 3_600_000),
+            'm' if chars.next_if_eq(&'s').is_some() => ("ms", 1),
+            'm' => ("m", 60_000),
+            's' => ("s", 1000),
+            _ => return Err(Report::msg(format!("Invalid duration unit '{ch}'"))),
+        };
+
+        let value = number
+            .parse::<u64>()
+            .map_err(|_| Report::msg(format!("Missing value for '{unit}'")))?;
+
+        millis = value
+            .checked_mul(multiplier)
+            .and_then(|value| millis.checked_add(value))
+            .ok_or_else(|| Report::msg(format!("Duration '{input}' is too long")))?;
+
+        number.clear();
+    }
+
+    if !number.is_empty() || millis == 0 {
+        return Err(Report::msg(format!("Invalid duration '{input}'")));
+    }
+
+    Ok(Duration::from_millis(millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        let parse = |input| parse_duration(input).ok();
+
+        assert_eq!(parse("25"), Some(Duration::from_secs(25 * 60)));
+        assert_eq!(parse("1h30m"), Some(Duration::from_secs(90 * 60)));
+        assert_eq!(parse("1m30s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse("1s500ms"), Some(Duration::from_millis(1500)));
+
+        assert_eq!(parse("10x"), None);
+        assert_eq!(parse("m"), None);
+        assert_eq!(parse("10m5"), None);
+        assert_eq!(parse("0s"), None);
+    }
+
+    #[test]
+    fn test_parse_duration_overflow() {
+        assert!(parse_duration(&u64::MAX.to_string()).is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX / 1000)).is_err());
+        assert!(parse_duration(&format!("{0}s{0}s", u64::MAX / 1000)).is_err());
+    }
+}
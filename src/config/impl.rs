@@ -16,7 +16,7 @@ impl<'de> Deserialize<'de> for MonitorConfig {
         match <BarConfig as serde::Deserialize>::deserialize(
             serde::__private::de::ContentRefDeserializer::<D::Error>::new(&content),
         ) {
-            Ok(config) => Ok(Self::Single(config)),
+            Ok(config) => Ok(Self::Single(Box::new(config))),
             Err(outer) => match <Vec<BarConfig> as serde::Deserialize>::deserialize(
                 serde::__private::de::ContentRefDeserializer::<D::Error>::new(&content),
             ) {
@@ -1,92 +1,276 @@
+#[cfg(any(
+    feature = "disk",
+    feature = "gpu",
+    feature = "http",
+    feature = "net_speed",
+    feature = "prometheus",
+    feature = "sys_info",
+    feature = "temperature",
+    feature = "upower"
+))]
+mod alert;
 mod common;
+mod duration;
 mod r#impl;
 mod truncate;
 
+#[cfg(feature = "accessibility")]
+use crate::modules::accessibility::AccessibilityModule;
+#[cfg(feature = "backup")]
+use crate::modules::backup::BackupModule;
+#[cfg(feature = "bluetooth")]
+use crate::modules::bluetooth::BluetoothModule;
+#[cfg(feature = "brightness")]
+use crate::modules::brightness::BrightnessModule;
 #[cfg(feature = "cairo")]
 use crate::modules::cairo::CairoModule;
+#[cfg(feature = "camera")]
+use crate::modules::camera::CameraModule;
+#[cfg(feature = "chat")]
+use crate::modules::chat::ChatModule;
+#[cfg(feature = "ci")]
+use crate::modules::ci::CiModule;
 #[cfg(feature = "clipboard")]
 use crate::modules::clipboard::ClipboardModule;
 #[cfg(feature = "clock")]
 use crate::modules::clock::ClockModule;
 use crate::modules::custom::CustomModule;
+#[cfg(feature = "dbus")]
+use crate::modules::dbus::DbusModule;
+#[cfg(feature = "disk")]
+use crate::modules::disk::DiskModule;
+#[cfg(feature = "dnd")]
+use crate::modules::dnd::DndModule;
+use crate::modules::file::FileModule;
 #[cfg(feature = "focused")]
 use crate::modules::focused::FocusedModule;
+#[cfg(feature = "gpu")]
+use crate::modules::gpu::GpuModule;
+#[cfg(feature = "graph")]
+use crate::modules::graph::GraphModule;
+#[cfg(feature = "http")]
+use crate::modules::http::HttpModule;
+#[cfg(feature = "idle_inhibit")]
+use crate::modules::idle_inhibit::IdleInhibitModule;
+#[cfg(feature = "input_method")]
+use crate::modules::input_method::InputMethodModule;
 #[cfg(feature = "keys")]
 use crate::modules::keys::KeysModule;
 use crate::modules::label::LabelModule;
 #[cfg(feature = "launcher")]
 use crate::modules::launcher::LauncherModule;
+#[cfg(feature = "layout")]
+use crate::modules::layout::LayoutModule;
+#[cfg(feature = "listen")]
+use crate::modules::listen::ListenModule;
+#[cfg(feature = "lock_keys")]
+use crate::modules::lock_keys::LockKeysModule;
 #[cfg(feature = "menu")]
 use crate::modules::menu::MenuModule;
+#[cfg(feature = "mic_meter")]
+use crate::modules::mic_meter::MicMeterModule;
+#[cfg(feature = "microphone")]
+use crate::modules::microphone::MicrophoneModule;
+#[cfg(feature = "mqtt")]
+use crate::modules::mqtt::MqttModule;
 #[cfg(feature = "music")]
 use crate::modules::music::MusicModule;
+#[cfg(feature = "net_speed")]
+use crate::modules::net_speed::NetSpeedModule;
 #[cfg(feature = "network_manager")]
 use crate::modules::networkmanager::NetworkManagerModule;
+#[cfg(feature = "night_light")]
+use crate::modules::night_light::NightLightModule;
 #[cfg(feature = "notifications")]
 use crate::modules::notifications::NotificationsModule;
+#[cfg(feature = "palette")]
+use crate::modules::palette::PaletteModule;
+#[cfg(feature = "presentation")]
+use crate::modules::presentation::PresentationModule;
+#[cfg(feature = "privacy")]
+use crate::modules::privacy::PrivacyModule;
+#[cfg(feature = "pro_audio")]
+use crate::modules::pro_audio::ProAudioModule;
+#[cfg(feature = "prometheus")]
+use crate::modules::prometheus::PrometheusModule;
+#[cfg(feature = "public_ip")]
+use crate::modules::public_ip::PublicIpModule;
+#[cfg(feature = "reminders")]
+use crate::modules::reminders::RemindersModule;
 use crate::modules::script::ScriptModule;
+use crate::modules::separator::SeparatorModule;
+use crate::modules::spacer::SpacerModule;
+#[cfg(feature = "ssh")]
+use crate::modules::ssh::SshModule;
 #[cfg(feature = "sway")]
 use crate::modules::sway::mode::SwayModeModule;
 #[cfg(feature = "sys_info")]
 use crate::modules::sysinfo::SysInfoModule;
+#[cfg(feature = "systemd")]
+use crate::modules::systemd::SystemdModule;
+#[cfg(feature = "temperature")]
+use crate::modules::temperature::TemperatureModule;
+#[cfg(feature = "timer")]
+use crate::modules::timer::TimerModule;
+#[cfg(feature = "timetracker")]
+use crate::modules::timetracker::TimeTrackerModule;
 #[cfg(feature = "tray")]
 use crate::modules::tray::TrayModule;
+#[cfg(feature = "updates")]
+use crate::modules::updates::UpdatesModule;
 #[cfg(feature = "upower")]
 use crate::modules::upower::UpowerModule;
+#[cfg(feature = "vm")]
+use crate::modules::vm::VmModule;
 #[cfg(feature = "volume")]
 use crate::modules::volume::VolumeModule;
+#[cfg(feature = "weather")]
+use crate::modules::weather::WeatherModule;
 #[cfg(feature = "workspaces")]
 use crate::modules::workspaces::WorkspacesModule;
 
 use crate::modules::{AnyModuleFactory, ModuleFactory, ModuleInfo};
+use crate::script::ScriptInput;
 use cfg_if::cfg_if;
 use color_eyre::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[cfg(feature = "schema")]
 use schemars::JsonSchema;
 
-pub use self::common::{CommonConfig, ModuleOrientation, TransitionType};
+#[cfg(any(
+    feature = "disk",
+    feature = "gpu",
+    feature = "http",
+    feature = "net_speed",
+    feature = "prometheus",
+    feature = "sys_info",
+    feature = "temperature",
+    feature = "upower"
+))]
+pub use self::alert::{Alert, Alerts};
+pub use self::common::{CommonConfig, DeferStart, DisplayMode, ModuleOrientation, TransitionType};
+pub use self::duration::parse_duration;
 pub use self::truncate::{EllipsizeMode, TruncateMode};
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum ModuleConfig {
+    #[cfg(feature = "accessibility")]
+    Accessibility(Box<AccessibilityModule>),
+    #[cfg(feature = "backup")]
+    Backup(Box<BackupModule>),
+    #[cfg(feature = "bluetooth")]
+    Bluetooth(Box<BluetoothModule>),
+    #[cfg(feature = "brightness")]
+    Brightness(Box<BrightnessModule>),
     #[cfg(feature = "cairo")]
     Cairo(Box<CairoModule>),
+    #[cfg(feature = "camera")]
+    Camera(Box<CameraModule>),
+    #[cfg(feature = "chat")]
+    Chat(Box<ChatModule>),
+    #[cfg(feature = "ci")]
+    Ci(Box<CiModule>),
     #[cfg(feature = "clipboard")]
     Clipboard(Box<ClipboardModule>),
     #[cfg(feature = "clock")]
     Clock(Box<ClockModule>),
     Custom(Box<CustomModule>),
+    #[cfg(feature = "dbus")]
+    Dbus(Box<DbusModule>),
+    #[cfg(feature = "disk")]
+    Disk(Box<DiskModule>),
+    #[cfg(feature = "dnd")]
+    Dnd(Box<DndModule>),
+    File(Box<FileModule>),
     #[cfg(feature = "focused")]
     Focused(Box<FocusedModule>),
+    #[cfg(feature = "gpu")]
+    Gpu(Box<GpuModule>),
+    #[cfg(feature = "graph")]
+    Graph(Box<GraphModule>),
+    #[cfg(feature = "http")]
+    Http(Box<HttpModule>),
+    #[cfg(feature = "idle_inhibit")]
+    IdleInhibit(Box<IdleInhibitModule>),
+    #[cfg(feature = "input_method")]
+    InputMethod(Box<InputMethodModule>),
     #[cfg(feature = "keys")]
+    #[serde(alias = "keyboard")]
     Keys(Box<KeysModule>),
     Label(Box<LabelModule>),
     #[cfg(feature = "launcher")]
     Launcher(Box<LauncherModule>),
+    #[cfg(feature = "layout")]
+    Layout(Box<LayoutModule>),
+    #[cfg(feature = "listen")]
+    Listen(Box<ListenModule>),
+    #[cfg(feature = "lock_keys")]
+    LockKeys(Box<LockKeysModule>),
     #[cfg(feature = "menu")]
     Menu(Box<MenuModule>),
+    #[cfg(feature = "mic_meter")]
+    MicMeter(Box<MicMeterModule>),
+    #[cfg(feature = "microphone")]
+    Microphone(Box<MicrophoneModule>),
+    #[cfg(feature = "mqtt")]
+    Mqtt(Box<MqttModule>),
     #[cfg(feature = "music")]
     Music(Box<MusicModule>),
+    #[cfg(feature = "net_speed")]
+    NetSpeed(Box<NetSpeedModule>),
     #[cfg(feature = "network_manager")]
     NetworkManager(Box<NetworkManagerModule>),
+    #[cfg(feature = "night_light")]
+    NightLight(Box<NightLightModule>),
     #[cfg(feature = "notifications")]
     Notifications(Box<NotificationsModule>),
+    #[cfg(feature = "palette")]
+    Palette(Box<PaletteModule>),
+    #[cfg(feature = "presentation")]
+    Presentation(Box<PresentationModule>),
+    #[cfg(feature = "privacy")]
+    Privacy(Box<PrivacyModule>),
+    #[cfg(feature = "pro_audio")]
+    ProAudio(Box<ProAudioModule>),
+    #[cfg(feature = "prometheus")]
+    Prometheus(Box<PrometheusModule>),
+    #[cfg(feature = "public_ip")]
+    PublicIp(Box<PublicIpModule>),
+    #[cfg(feature = "reminders")]
+    Reminders(Box<RemindersModule>),
     Script(Box<ScriptModule>),
+    Separator(Box<SeparatorModule>),
+    Spacer(Box<SpacerModule>),
+    #[cfg(feature = "ssh")]
+    Ssh(Box<SshModule>),
     #[cfg(feature = "sys_info")]
     SysInfo(Box<SysInfoModule>),
     #[cfg(feature = "sway")]
     SwayMode(Box<SwayModeModule>),
+    #[cfg(feature = "systemd")]
+    Systemd(Box<SystemdModule>),
+    #[cfg(feature = "temperature")]
+    Temperature(Box<TemperatureModule>),
+    #[cfg(feature = "timer")]
+    Timer(Box<TimerModule>),
+    #[cfg(feature = "timetracker")]
+    Timetracker(Box<TimeTrackerModule>),
     #[cfg(feature = "tray")]
     Tray(Box<TrayModule>),
+    #[cfg(feature = "updates")]
+    Updates(Box<UpdatesModule>),
     #[cfg(feature = "upower")]
     Upower(Box<UpowerModule>),
+    #[cfg(feature = "vm")]
+    Vm(Box<VmModule>),
     #[cfg(feature = "volume")]
     Volume(Box<VolumeModule>),
+    #[cfg(feature = "weather")]
+    Weather(Box<WeatherModule>),
     #[cfg(feature = "workspaces")]
     Workspaces(Box<WorkspacesModule>),
 }
@@ -105,39 +289,118 @@ impl ModuleConfig {
         }
 
         match self {
+            #[cfg(feature = "accessibility")]
+            Self::Accessibility(module) => create!(module),
+            #[cfg(feature = "backup")]
+            Self::Backup(module) => create!(module),
+            #[cfg(feature = "bluetooth")]
+            Self::Bluetooth(module) => create!(module),
+            #[cfg(feature = "brightness")]
+            Self::Brightness(module) => create!(module),
             #[cfg(feature = "cairo")]
             Self::Cairo(module) => create!(module),
+            #[cfg(feature = "camera")]
+            Self::Camera(module) => create!(module),
+            #[cfg(feature = "chat")]
+            Self::Chat(module) => create!(module),
+            #[cfg(feature = "ci")]
+            Self::Ci(module) => create!(module),
             #[cfg(feature = "clipboard")]
             Self::Clipboard(module) => create!(module),
             #[cfg(feature = "clock")]
             Self::Clock(module) => create!(module),
             Self::Custom(module) => create!(module),
+            #[cfg(feature = "dbus")]
+            Self::Dbus(module) => create!(module),
+            #[cfg(feature = "disk")]
+            Self::Disk(module) => create!(module),
+            #[cfg(feature = "dnd")]
+            Self::Dnd(module) => create!(module),
+            Self::File(module) => create!(module),
             #[cfg(feature = "focused")]
             Self::Focused(module) => create!(module),
+            #[cfg(feature = "gpu")]
+            Self::Gpu(module) => create!(module),
+            #[cfg(feature = "graph")]
+            Self::Graph(module) => create!(module),
+            #[cfg(feature = "http")]
+            Self::Http(module) => create!(module),
+            #[cfg(feature = "idle_inhibit")]
+            Self::IdleInhibit(module) => create!(module),
+            #[cfg(feature = "input_method")]
+            Self::InputMethod(module) => create!(module),
             #[cfg(feature = "keys")]
             Self::Keys(module) => create!(module),
             Self::Label(module) => create!(module),
             #[cfg(feature = "launcher")]
             Self::Launcher(module) => create!(module),
+            #[cfg(feature = "layout")]
+            Self::Layout(module) => create!(module),
+            #[cfg(feature = "listen")]
+            Self::Listen(module) => create!(module),
+            #[cfg(feature = "lock_keys")]
+            Self::LockKeys(module) => create!(module),
             #[cfg(feature = "menu")]
             Self::Menu(module) => create!(module),
+            #[cfg(feature = "mic_meter")]
+            Self::MicMeter(module) => create!(module),
+            #[cfg(feature = "microphone")]
+            Self::Microphone(module) => create!(module),
+            #[cfg(feature = "mqtt")]
+            Self::Mqtt(module) => create!(module),
             #[cfg(feature = "music")]
             Self::Music(module) => create!(module),
+            #[cfg(feature = "net_speed")]
+            Self::NetSpeed(module) => create!(module),
             #[cfg(feature = "network_manager")]
             Self::NetworkManager(module) => create!(module),
+            #[cfg(feature = "night_light")]
+            Self::NightLight(module) => create!(module),
             #[cfg(feature = "notifications")]
             Self::Notifications(module) => create!(module),
+            #[cfg(feature = "palette")]
+            Self::Palette(module) => create!(module),
+            #[cfg(feature = "presentation")]
+            Self::Presentation(module) => create!(module),
+            #[cfg(feature = "privacy")]
+            Self::Privacy(module) => create!(module),
+            #[cfg(feature = "pro_audio")]
+            Self::ProAudio(module) => create!(module),
+            #[cfg(feature = "prometheus")]
+            Self::Prometheus(module) => create!(module),
+            #[cfg(feature = "public_ip")]
+            Self::PublicIp(module) => create!(module),
+            #[cfg(feature = "reminders")]
+            Self::Reminders(module) => create!(module),
             Self::Script(module) => create!(module),
+            Self::Separator(module) => create!(module),
+            Self::Spacer(module) => create!(module),
+            #[cfg(feature = "ssh")]
+            Self::Ssh(module) => create!(module),
             #[cfg(feature = "sys_info")]
             Self::SysInfo(module) => create!(module),
             #[cfg(feature = "sway")]
             Self::SwayMode(module) => create!(module),
+            #[cfg(feature = "systemd")]
+            Self::Systemd(module) => create!(module),
+            #[cfg(feature = "temperature")]
+            Self::Temperature(module) => create!(module),
+            #[cfg(feature = "timer")]
+            Self::Timer(module) => create!(module),
+            #[cfg(feature = "timetracker")]
+            Self::Timetracker(module) => create!(module),
             #[cfg(feature = "tray")]
             Self::Tray(module) => create!(module),
+            #[cfg(feature = "updates")]
+            Self::Updates(module) => create!(module),
             #[cfg(feature = "upower")]
             Self::Upower(module) => create!(module),
+            #[cfg(feature = "vm")]
+            Self::Vm(module) => create!(module),
             #[cfg(feature = "volume")]
             Self::Volume(module) => create!(module),
+            #[cfg(feature = "weather")]
+            Self::Weather(module) => create!(module),
             #[cfg(feature = "workspaces")]
             Self::Workspaces(module) => create!(module),
         }
@@ -147,27 +410,123 @@ impl ModuleConfig {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum MonitorConfig {
-    Single(BarConfig),
+    Single(Box<BarConfig>),
     Multiple(Vec<BarConfig>),
 }
 
-#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum BarPosition {
     Top,
+    #[default]
     Bottom,
     Left,
     Right,
 }
 
-impl Default for BarPosition {
-    fn default() -> Self {
-        Self::Bottom
-    }
+/// What to do with a bar on the laptop's internal display
+/// when the lid is closed.
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum LidCloseAction {
+    /// Leave the bar as it is.
+    #[default]
+    None,
+    /// Hide the bar until the lid is opened.
+    Hide,
+    /// Hide the bar, and show a copy of it on an external display
+    /// until the lid is opened.
+    Move,
+}
+
+/// The corner or edge of an output a hotspot is placed on.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum HotspotPosition {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
 }
 
+/// What activates a hotspot.
 #[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum HotspotTrigger {
+    /// Moving the pointer into the hotspot.
+    #[default]
+    Hover,
+    /// Clicking the hotspot.
+    Click,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct HotspotConfig {
+    /// The corner or edge of the bar's output to place the hotspot on.
+    ///
+    /// **Valid options**: `top_left`, `top`, `top_right`, `left`, `right`,
+    /// `bottom_left`, `bottom`, `bottom_right`
+    pub position: HotspotPosition,
+
+    /// The size of the hotspot in pixels.
+    /// Corners are square, and edges span the whole output at this thickness.
+    ///
+    /// **Default**: `2`
+    #[serde(default = "default_hotspot_size")]
+    pub size: i32,
+
+    /// What activates the hotspot.
+    ///
+    /// **Valid options**: `hover`, `click`
+    /// <br>
+    /// **Default**: `hover`
+    #[serde(default)]
+    pub trigger: HotspotTrigger,
+
+    /// The number of milliseconds the pointer must stay in the hotspot
+    /// before a `hover` hotspot activates.
+    /// This avoids activating it when the pointer only passes by.
+    ///
+    /// **Default**: `150`
+    #[serde(default = "default_hotspot_delay")]
+    pub delay: u64,
+
+    /// A [script](scripts) to run when the hotspot activates.
+    ///
+    /// **Supported script types**: `oneshot`.
+    /// <br>
+    /// **Default**: `null`
+    pub on_trigger: Option<ScriptInput>,
+
+    /// The name of a module on this bar to open the popup of
+    /// when the hotspot activates.
+    /// Clicking a `click` hotspot again closes the popup.
+    ///
+    /// This is the module's `name` option if it has one,
+    /// or its type otherwise.
+    ///
+    /// **Default**: `null`
+    pub popup: Option<String>,
+}
+
+const fn default_hotspot_size() -> i32 {
+    2
+}
+
+const fn default_hotspot_delay() -> u64 {
+    150
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct MarginConfig {
     #[serde(default)]
@@ -261,6 +620,15 @@ pub struct BarConfig {
     #[cfg_attr(feature = "schema", schemars(schema_with = "r#impl::schema_layer"))]
     pub layer: gtk_layer_shell::Layer,
 
+    /// The layer-shell namespace of the bar window,
+    /// which compositors use to match the bar in their rules.
+    ///
+    /// Each bar gets its own namespace by default, including the bar's `name`,
+    /// so that rules can target them separately.
+    ///
+    /// **Default**: `ironbar-<name>`, or `ironbar-blur-<name>` if `blur` is set.
+    pub namespace: Option<String>,
+
     /// Whether the bar should reserve an exclusive zone around it.
     ///
     /// When true, this prevents windows from rendering in the same space
@@ -270,6 +638,27 @@ pub struct BarConfig {
     #[serde(default)]
     pub exclusive_zone: Option<bool>,
 
+    /// The opacity of the bar's contents, from `0.0` to `1.0`.
+    ///
+    /// Below `1.0`, the bar window's own background is made transparent
+    /// so that windows behind the bar show through.
+    ///
+    /// **Default**: `1.0`
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+
+    /// Whether to request that the compositor blurs behind the bar.
+    ///
+    /// This makes the bar window's background transparent
+    /// and changes the default namespace to `ironbar-blur-<name>`.
+    /// Compositors which blur layer surfaces by namespace,
+    /// such as Hyprland, then need a single rule matching the `ironbar-blur-` prefix
+    /// to blur all bars which set this.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub blur: bool,
+
     /// The size of the gap in pixels
     /// between the bar and the popup window.
     ///
@@ -290,6 +679,64 @@ pub struct BarConfig {
     #[serde(default)]
     pub autohide: Option<u64>,
 
+    /// Whether to show a small button in the corner of the screen while the bar is hidden.
+    /// Clicking it opens a menu to show the bar again, reload Ironbar, or quit.
+    ///
+    /// This allows a bar hidden using IPC to be recovered without a terminal.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub recovery_button: bool,
+
+    /// Shows the bar only while one of the named workspaces is focused.
+    ///
+    /// Combined with several bars on the same monitor,
+    /// this allows the whole bar layout to change per workspace.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "workspaces")]
+    #[serde(default)]
+    pub show_if_workspace: Option<Vec<String>>,
+
+    /// Hides the bar while one of the named workspaces is focused.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "workspaces")]
+    #[serde(default)]
+    pub hide_if_workspace: Option<Vec<String>>,
+
+    /// Whether to move modules which do not fit onto the bar
+    /// into the popup of a `»` button at the end of the bar,
+    /// rather than letting them be clipped.
+    ///
+    /// Modules with the lowest `display_priority` are moved first.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub overflow: bool,
+
+    /// What to do with the bar when the laptop lid is closed,
+    /// if it is on the internal display.
+    /// The bar is restored when the lid is opened.
+    ///
+    /// Requires the `lid` feature.
+    ///
+    /// **Valid options**: `none`, `hide`, `move`
+    /// <br>
+    /// **Default**: `none`
+    #[serde(default)]
+    #[cfg_attr(not(feature = "lid"), allow(dead_code))]
+    pub on_lid_close: LidCloseAction,
+
+    /// Invisible areas in the corners or along the edges of the bar's output,
+    /// which run a script or open a module's popup when hovered or clicked.
+    ///
+    /// See [hotspots](#hotspots).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pub hotspots: Vec<HotspotConfig>,
+
     /// The name of the GTK icon theme to use.
     /// Leave unset to use the default Adwaita theme.
     ///
@@ -339,10 +786,21 @@ impl Default for BarConfig {
             margin: MarginConfig::default(),
             name: None,
             layer: default_layer(),
+            namespace: None,
             exclusive_zone: None,
+            opacity: default_opacity(),
+            blur: false,
             height: default_bar_height(),
             start_hidden: None,
             autohide: None,
+            recovery_button: false,
+            #[cfg(feature = "workspaces")]
+            show_if_workspace: None,
+            #[cfg(feature = "workspaces")]
+            hide_if_workspace: None,
+            overflow: false,
+            on_lid_close: LidCloseAction::default(),
+            hotspots: vec![],
             icon_theme: None,
             start: Some(vec![ModuleConfig::Label(
                 LabelModule::new("ℹ️ Using default config".to_string()).into(),
@@ -394,6 +852,80 @@ pub struct Config {
     ///
     /// Providing this option overrides the single, global `bar` option.
     pub monitors: Option<HashMap<String, MonitorConfig>>,
+
+    /// Whether to smoothly animate changes to displayed values,
+    /// such as progress bars and volume/brightness sliders.
+    /// Setting this to `false` makes all values update immediately.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    pub animations: bool,
+
+    /// Options for how modules schedule polling,
+    /// such as aligning intervals so that pollers wake up together.
+    ///
+    /// See [polling](polling) for more info.
+    ///
+    /// **Default**: `{ align = true, jitter = 0 }`
+    #[serde(default)]
+    pub polling: crate::polling::PollingConfig,
+
+    /// When to use the fallback icons bundled with Ironbar,
+    /// which cover common battery, network, volume and bluetooth states.
+    ///
+    /// See [images](images#bundled-icons) for more info.
+    ///
+    /// **Valid options**: `fallback`, `prefer`, `disabled`
+    /// <br>
+    /// **Default**: `fallback`
+    #[serde(default)]
+    pub bundled_icons: crate::image::bundled::BundledIcons,
+
+    /// Configuration for the on-screen display,
+    /// shown when the volume, brightness or caps lock state change.
+    /// The OSD is disabled if not set.
+    ///
+    /// See [OSD](osd) for more info.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "osd")]
+    pub osd: Option<crate::osd::OsdConfig>,
+
+    /// Scripts to run or sounds to play when system events occur,
+    /// such as AC power being connected or the network going down.
+    ///
+    /// See [hooks](hooks) for more info.
+    ///
+    /// **Default**: `null`
+    #[cfg(any(feature = "upower", feature = "network_manager"))]
+    pub hooks: Option<crate::hooks::HooksConfig>,
+
+    /// Enables handling of volume and brightness media keys directly,
+    /// for when no other tool is bound to them.
+    ///
+    /// See [media keys](media-keys) for more info.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "media_keys")]
+    pub media_keys: Option<crate::media_keys::MediaKeysConfig>,
+
+    /// Side effects to apply while presentation mode is active.
+    ///
+    /// See [presentation](presentation) for more info.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "presentation")]
+    pub presentation: Option<crate::presentation::PresentationConfig>,
+
+    /// Enables keeping a history of recent numeric module values,
+    /// which can be shown by the `graph` module or queried over IPC.
+    /// History is disabled if not set.
+    ///
+    /// See [history](history) for more info.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "history")]
+    pub history: Option<crate::history::HistoryConfig>,
 }
 
 const fn default_layer() -> gtk_layer_shell::Layer {
@@ -408,6 +940,10 @@ const fn default_popup_gap() -> i32 {
     5
 }
 
+const fn default_opacity() -> f64 {
+    1.0
+}
+
 pub const fn default_false() -> bool {
     false
 }
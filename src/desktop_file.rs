@@ -1,12 +1,26 @@
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
 use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::mem;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::sync::{Mutex, OnceLock};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use tokio::sync::{mpsc, Semaphore};
 use tracing::warn;
 use walkdir::{DirEntry, WalkDir};
 
-use crate::lock;
+use crate::{lock, spawn};
+
+/// Maximum directory depth searched for desktop files.
+const MAX_DEPTH: usize = 5;
+
+/// Maximum number of desktop files read at once by [`read_desktop_files`].
+const MAX_CONCURRENT_READS: usize = 16;
 
 type DesktopFile = HashMap<String, Vec<String>>;
 
@@ -23,7 +37,7 @@ fn desktop_files_look_out_keys() -> &'static HashSet<&'static str> {
 
 /// Finds directories that should contain `.desktop` files
 /// and exist on the filesystem.
-fn find_application_dirs() -> Vec<PathBuf> {
+pub fn find_application_dirs() -> Vec<PathBuf> {
     let mut dirs = vec![
         PathBuf::from("/usr/share/applications"), // system installed apps
         PathBuf::from("/var/lib/flatpak/exports/share/applications"), // flatpak apps
@@ -31,7 +45,7 @@ fn find_application_dirs() -> Vec<PathBuf> {
 
     let xdg_dirs = env::var_os("XDG_DATA_DIRS");
     if let Some(xdg_dirs) = xdg_dirs {
-        for mut xdg_dir in env::split_paths(&xdg_dirs).map(PathBuf::from) {
+        for mut xdg_dir in env::split_paths(&xdg_dirs) {
             xdg_dir.push("applications");
             dirs.push(xdg_dir);
         }
@@ -52,7 +66,7 @@ pub fn find_desktop_files() -> Vec<PathBuf> {
     dirs.into_iter()
         .flat_map(|dir| {
             WalkDir::new(dir)
-                .max_depth(5)
+                .max_depth(MAX_DEPTH)
                 .into_iter()
                 .filter_map(Result::ok)
                 .map(DirEntry::into_path)
@@ -61,6 +75,101 @@ pub fn find_desktop_files() -> Vec<PathBuf> {
         .collect()
 }
 
+/// Finds all the desktop files without blocking,
+/// searching each application directory concurrently.
+///
+/// Directories are returned in the same order as [`find_desktop_files`].
+pub async fn find_desktop_files_async() -> Vec<PathBuf> {
+    let handles = find_application_dirs()
+        .into_iter()
+        .map(|dir| spawn(walk_desktop_files(dir)))
+        .collect::<Vec<_>>();
+
+    let mut files = vec![];
+    for handle in handles {
+        match handle.await {
+            Ok(mut dir_files) => files.append(&mut dir_files),
+            Err(err) => warn!("Failed to search for desktop files: {err:?}"),
+        }
+    }
+
+    files
+}
+
+/// Recursively finds the desktop files in `dir`, up to [`MAX_DEPTH`] levels deep.
+async fn walk_desktop_files(dir: PathBuf) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut pending = vec![(dir, 0)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+
+            // like `WalkDir`, symlinked directories are not followed,
+            // but symlinked files are included.
+            if depth + 1 < MAX_DEPTH && entry.file_type().await.is_ok_and(|typ| typ.is_dir()) {
+                pending.push((path, depth + 1));
+            } else if path.extension().unwrap_or_default() == "desktop"
+                && tokio::fs::metadata(&path)
+                    .await
+                    .is_ok_and(|metadata| metadata.is_file())
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files
+}
+
+/// Reads and parses desktop files in the background,
+/// at most [`MAX_CONCURRENT_READS`] at a time.
+///
+/// Each result of `parse` is sent along with the index of its file in `files`
+/// as soon as it is ready, so results arrive out of order.
+/// Files which cannot be read, or for which `parse` returns `None`, are skipped.
+/// The channel closes once every file has been handled.
+pub fn read_desktop_files<T, F>(files: Vec<PathBuf>, parse: F) -> mpsc::Receiver<(usize, T)>
+where
+    T: Send + 'static,
+    F: Fn(&Path, Vec<u8>) -> Option<T> + Send + Sync + 'static,
+{
+    let (tx, rx) = mpsc::channel(MAX_CONCURRENT_READS);
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_READS));
+    let parse = Arc::new(parse);
+
+    for (index, path) in files.into_iter().enumerate() {
+        let tx = tx.clone();
+        let permits = permits.clone();
+        let parse = parse.clone();
+
+        spawn(async move {
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+
+            let contents = match tokio::fs::read(&path).await {
+                Ok(contents) => contents,
+                Err(err) => {
+                    warn!("Couldn't Open File: {}: {err}", path.display());
+                    return;
+                }
+            };
+
+            if let Some(parsed) = parse(&path, contents) {
+                // the receiver may have given up on the scan, which is fine.
+                let _ = tx.send((index, parsed)).await;
+            }
+        });
+    }
+
+    rx
+}
+
 /// Attempts to locate a `.desktop` file for an app id
 pub fn find_desktop_file(app_id: &str) -> Option<PathBuf> {
     // this is necessary to invalidate the cache
@@ -197,3 +306,282 @@ pub fn get_desktop_icon_name(app_id: &str) -> Option<String> {
 
     icons.next().map(std::string::ToString::to_string)
 }
+
+/// Checks whether a program exists and is executable,
+/// as used by the `TryExec` key.
+/// Names without a path are searched for in `$PATH`.
+pub fn is_executable(program: &str) -> bool {
+    let check = |path: &Path| {
+        fs::metadata(path)
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    };
+
+    if program.contains('/') {
+        return check(Path::new(program));
+    }
+
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| check(&dir.join(program))))
+}
+
+/// Launches the application for a desktop file,
+/// or one of its desktop actions if `action` is set.
+///
+/// Field codes in the `Exec` key are expanded,
+/// and applications with `Terminal=true` are run using the `terminal` command.
+/// The process is detached from Ironbar and its output is discarded.
+pub fn launch(path: &Path, action: Option<&str>, terminal: &str) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read desktop file '{}'", path.display()))?;
+
+    let entry = parse_group(&contents, "Desktop Entry");
+
+    let exec = match action {
+        Some(action) => parse_group(&contents, &format!("Desktop Action {action}"))
+            .get("Exec")
+            .copied(),
+        None => entry.get("Exec").copied(),
+    }
+    .ok_or_else(|| eyre!("Desktop file '{}' has no Exec key", path.display()))?;
+
+    let mut args = expand_field_codes(&split_exec(&unescape(exec))?, &entry, path);
+
+    if entry.get("Terminal") == Some(&"true") {
+        let mut command = terminal
+            .split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        command.append(&mut args);
+        args = command;
+    }
+
+    let Some((program, args)) = args.split_first() else {
+        return Err(eyre!(
+            "Desktop file '{}' has an empty Exec key",
+            path.display()
+        ));
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // run in a new process group so signals sent to Ironbar do not reach it
+        .process_group(0);
+
+    if let Some(dir) = entry.get("Path").filter(|dir| !dir.is_empty()) {
+        command.current_dir(dir);
+    }
+
+    let mut child = command
+        .spawn()
+        .wrap_err_with(|| format!("Failed to launch '{program}'"))?;
+
+    // reap the process once it exits to avoid leaving a zombie behind.
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Gets the keys of a group in a desktop file.
+/// Localized keys are ignored.
+fn parse_group<'a>(contents: &'a str, group: &str) -> HashMap<&'a str, &'a str> {
+    let header = format!("[{group}]");
+
+    contents
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .filter(|(key, _)| !key.contains('['))
+        .collect()
+}
+
+/// Unescapes a desktop file string value.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(char) => unescaped.push(char),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Splits an `Exec` value into its arguments.
+/// Arguments may be wrapped in double quotes,
+/// inside which characters can be escaped using a backslash.
+fn split_exec(exec: &str) -> Result<Vec<String>> {
+    let mut args = vec![];
+    let mut arg = String::new();
+
+    // tracked separately to allow empty quoted arguments
+    let mut in_arg = false;
+    let mut quoted = false;
+
+    let mut chars = exec.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => {
+                if let Some(char) = chars.next() {
+                    arg.push(char);
+                }
+            }
+            char if char.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            char => {
+                arg.push(char);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quoted {
+        return Err(eyre!("Unterminated quote in Exec key '{exec}'"));
+    }
+
+    if in_arg {
+        args.push(arg);
+    }
+
+    Ok(args)
+}
+
+/// Expands the field codes in `Exec` arguments.
+///
+/// Files and URLs are never passed when launching from the bar,
+/// so `%f`, `%F`, `%u` and `%U` are removed, along with deprecated codes.
+/// Arguments which only contained removed codes are dropped.
+fn expand_field_codes(args: &[String], entry: &HashMap<&str, &str>, path: &Path) -> Vec<String> {
+    let mut expanded = vec![];
+
+    for arg in args {
+        if arg == "%i" {
+            if let Some(icon) = entry.get("Icon") {
+                expanded.push("--icon".to_string());
+                expanded.push((*icon).to_string());
+            }
+
+            continue;
+        }
+
+        let mut value = String::with_capacity(arg.len());
+        let mut chars = arg.chars();
+
+        while let Some(char) = chars.next() {
+            if char != '%' {
+                value.push(char);
+                continue;
+            }
+
+            match chars.next() {
+                Some('%') => value.push('%'),
+                Some('c') => value.push_str(entry.get("Name").copied().unwrap_or_default()),
+                Some('k') => value.push_str(&path.to_string_lossy()),
+                _ => {}
+            }
+        }
+
+        if !value.is_empty() || arg.is_empty() {
+            expanded.push(value);
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_group() {
+        let contents = "[Desktop Entry]\n\
+            Name=Firefox\n\
+            Name[de]=Firefox-Browser\n\
+            # comment\n\
+            Exec=firefox %u\n\
+            \n\
+            [Desktop Action new-window]\n\
+            Exec=firefox --new-window %u\n";
+
+        let entry = parse_group(contents, "Desktop Entry");
+        assert_eq!(entry.len(), 2);
+        assert_eq!(entry.get("Name"), Some(&"Firefox"));
+        assert_eq!(entry.get("Exec"), Some(&"firefox %u"));
+
+        let action = parse_group(contents, "Desktop Action new-window");
+        assert_eq!(action.get("Exec"), Some(&"firefox --new-window %u"));
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"a\sb\tc\\d"), "a b\tc\\d");
+        assert_eq!(unescape(r"trailing\"), "trailing\\");
+    }
+
+    #[test]
+    fn test_split_exec() {
+        assert_eq!(
+            split_exec(r#"code  --new-window "my project" "a \"quoted\" arg" """#).ok(),
+            Some(vec![
+                String::from("code"),
+                String::from("--new-window"),
+                String::from("my project"),
+                String::from(r#"a "quoted" arg"#),
+                String::new(),
+            ])
+        );
+
+        assert!(split_exec(r#"code "unterminated"#).is_err());
+    }
+
+    #[test]
+    fn test_expand_field_codes() {
+        let entry = HashMap::from([("Name", "Editor"), ("Icon", "editor")]);
+        let path = Path::new("/usr/share/applications/editor.desktop");
+
+        let args = ["editor", "%i", "--title=%c", "%F", "100%%", "%k"].map(String::from);
+
+        assert_eq!(
+            expand_field_codes(&args, &entry, path),
+            vec![
+                "editor",
+                "--icon",
+                "editor",
+                "--title=Editor",
+                "100%",
+                "/usr/share/applications/editor.desktop"
+            ]
+        );
+    }
+}
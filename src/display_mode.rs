@@ -0,0 +1,218 @@
+use crate::config::DisplayMode;
+use crate::gtk_helpers::IronbarGtkExt;
+use glib::WeakRef;
+use gtk::prelude::*;
+use gtk::{Container, EventBox, Image, Label, Orientation, Widget};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Tag set on a module's container, holding its controller.
+const CONTROLLER_TAG: &str = "display-mode";
+/// Tag set on widgets hidden by a controller.
+const HIDDEN_TAG: &str = "display-mode-hidden";
+/// Tag set on containers which re-apply the mode when children are added.
+const WATCHED_TAG: &str = "display-mode-watched";
+
+/// Shows and hides the icon and text parts of a module's widget tree
+/// according to its display mode.
+///
+/// Icon parts are images and any widget with the `.icon` class.
+/// Text parts are all other labels.
+/// If a module has no parts of the requested type,
+/// it is left unchanged rather than hiding everything.
+#[derive(Debug, Clone)]
+pub struct DisplayModeController {
+    root: WeakRef<Widget>,
+    mode: DisplayMode,
+    priority: i32,
+
+    /// Whether an `auto` module is currently collapsed to icon-only.
+    collapsed: Rc<Cell<bool>>,
+    /// The size of the module when it was last collapsed.
+    expanded_size: Rc<Cell<i32>>,
+    /// Whether an update is already queued.
+    pending: Rc<Cell<bool>>,
+}
+
+impl DisplayModeController {
+    /// Sets up the display mode for a module's widget,
+    /// storing the controller on its container.
+    ///
+    /// Nothing is done for the `both` mode.
+    pub fn install(
+        container: &EventBox,
+        root: &impl IsA<Widget>,
+        mode: DisplayMode,
+        priority: i32,
+    ) {
+        if mode == DisplayMode::Both {
+            return;
+        }
+
+        let controller = Self {
+            root: root.upcast_ref::<Widget>().downgrade(),
+            mode,
+            priority,
+            collapsed: Rc::new(Cell::new(false)),
+            expanded_size: Rc::new(Cell::new(0)),
+            pending: Rc::new(Cell::new(false)),
+        };
+
+        controller.apply();
+        container.set_tag(CONTROLLER_TAG, controller);
+    }
+
+    /// Gets the controller for a module container, if it has one.
+    pub fn get(container: &Widget) -> Option<Self> {
+        container.get_tag::<Self>(CONTROLLER_TAG).cloned()
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.mode == DisplayMode::Auto
+    }
+
+    fn effective_mode(&self) -> DisplayMode {
+        match self.mode {
+            DisplayMode::Auto if self.collapsed.get() => DisplayMode::Icon,
+            DisplayMode::Auto => DisplayMode::Both,
+            mode => mode,
+        }
+    }
+
+    fn size(&self, orientation: Orientation) -> i32 {
+        self.root.upgrade().map_or(0, |root| {
+            let allocation = root.allocation();
+            if orientation == Orientation::Horizontal {
+                allocation.width()
+            } else {
+                allocation.height()
+            }
+        })
+    }
+
+    fn collapse(&self, orientation: Orientation) {
+        self.expanded_size.set(self.size(orientation));
+        self.collapsed.set(true);
+        self.apply();
+    }
+
+    fn expand(&self) {
+        self.collapsed.set(false);
+        self.apply();
+    }
+
+    /// Queues the mode to be applied once the main loop is idle,
+    /// allowing newly added widgets to be shown first.
+    fn queue_apply(&self) {
+        if self.pending.replace(true) {
+            return;
+        }
+
+        let controller = self.clone();
+        glib::idle_add_local_once(move || {
+            controller.pending.set(false);
+            controller.apply();
+        });
+    }
+
+    /// Shows and hides the parts of the widget tree for the current mode.
+    fn apply(&self) {
+        let Some(root) = self.root.upgrade() else {
+            return;
+        };
+
+        let mut icons = vec![];
+        let mut text = vec![];
+        self.collect(&root, &mut icons, &mut text);
+
+        let (show, hide) = match self.effective_mode() {
+            DisplayMode::Icon if !icons.is_empty() => (icons, text),
+            DisplayMode::Text if !text.is_empty() => (text, icons),
+            _ => (icons.into_iter().chain(text).collect(), vec![]),
+        };
+
+        for widget in show {
+            if widget
+                .get_tag::<bool>(HIDDEN_TAG)
+                .copied()
+                .unwrap_or_default()
+            {
+                widget.set_tag(HIDDEN_TAG, false);
+                widget.set_no_show_all(false);
+                widget.show();
+            }
+        }
+
+        for widget in hide {
+            widget.set_tag(HIDDEN_TAG, true);
+            widget.set_no_show_all(true);
+            widget.hide();
+        }
+    }
+
+    /// Recursively finds the icon and text parts of the widget tree,
+    /// watching any containers for new children along the way.
+    fn collect(&self, widget: &Widget, icons: &mut Vec<Widget>, text: &mut Vec<Widget>) {
+        if widget.is::<Image>() || widget.style_context().has_class("icon") {
+            icons.push(widget.clone());
+        } else if widget.is::<Label>() {
+            text.push(widget.clone());
+        } else if let Some(container) = widget.downcast_ref::<Container>() {
+            self.watch(container);
+
+            for child in container.children() {
+                self.collect(&child, icons, text);
+            }
+        }
+    }
+
+    fn watch(&self, container: &Container) {
+        if container.get_tag::<bool>(WATCHED_TAG).is_some() {
+            return;
+        }
+
+        container.set_tag(WATCHED_TAG, true);
+
+        let controller = self.clone();
+        container.connect_add(move |_, _| controller.queue_apply());
+    }
+}
+
+/// Collapses or expands a single `auto` module
+/// so that the bar content fits into the available space.
+///
+/// Modules with the lowest priority are collapsed first,
+/// and expanded last.
+/// Modules are only expanded if there is room for their full size.
+///
+/// Returns whether a module was collapsed or expanded.
+pub fn fit(
+    controllers: &[DisplayModeController],
+    required: i32,
+    available: i32,
+    orientation: Orientation,
+) -> bool {
+    if required > available {
+        if let Some(controller) = controllers
+            .iter()
+            .rev()
+            .filter(|controller| !controller.collapsed.get())
+            .min_by_key(|controller| controller.priority)
+        {
+            controller.collapse(orientation);
+            return true;
+        }
+    } else if let Some(controller) = controllers
+        .iter()
+        .filter(|controller| controller.collapsed.get())
+        .max_by_key(|controller| controller.priority)
+    {
+        let growth = controller.expanded_size.get() - controller.size(orientation);
+        if required + growth <= available {
+            controller.expand();
+            return true;
+        }
+    }
+
+    false
+}
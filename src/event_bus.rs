@@ -0,0 +1,271 @@
+#[cfg(feature = "workspaces")]
+use crate::clients::compositor::WorkspaceUpdate;
+use crate::{lock, send};
+#[cfg(feature = "workspaces")]
+use crate::{spawn, Ironbar};
+#[cfg(feature = "workspaces")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+#[cfg(feature = "workspaces")]
+use tracing::{debug, error};
+
+/// Bar-wide state shared through the event bus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BusState {
+    /// Whether presentation mode is enabled.
+    #[cfg(feature = "presentation")]
+    pub presentation: bool,
+    /// The name of the focused workspace,
+    /// once it is known.
+    #[cfg(feature = "workspaces")]
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusEvent {
+    #[cfg(feature = "presentation")]
+    Presentation(bool),
+    /// The focused workspace changed, or was renamed.
+    #[cfg(feature = "workspaces")]
+    Workspace(String),
+}
+
+impl BusEvent {
+    /// Gets whether presentation mode is enabled,
+    /// if this is a presentation mode event.
+    #[cfg(feature = "presentation")]
+    pub fn presentation(&self) -> Option<bool> {
+        match self {
+            Self::Presentation(enabled) => Some(*enabled),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Gets the focused workspace name,
+    /// if this is a workspace event.
+    #[cfg(feature = "workspaces")]
+    pub fn workspace(&self) -> Option<&str> {
+        match self {
+            Self::Workspace(name) => Some(name),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+/// Global singleton event bus,
+/// used to coordinate bar-wide state changes
+/// between modules, the IPC server and common module options.
+#[derive(Debug)]
+pub struct EventBus {
+    state: Mutex<BusState>,
+
+    tx: broadcast::Sender<BusEvent>,
+    _rx: broadcast::Receiver<BusEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, rx) = broadcast::channel(32);
+
+        Self {
+            state: Mutex::new(BusState::default()),
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Gets a copy of the current state.
+    pub fn state(&self) -> BusState {
+        lock!(self.state).clone()
+    }
+
+    /// Updates the state and sends the event to all subscribers.
+    ///
+    /// Events which do not change the state are not sent.
+    pub fn publish(&self, event: BusEvent) {
+        let mut state = lock!(self.state);
+
+        let changed = match &event {
+            #[cfg(feature = "presentation")]
+            BusEvent::Presentation(enabled) => {
+                std::mem::replace(&mut state.presentation, *enabled) != *enabled
+            }
+            #[cfg(feature = "workspaces")]
+            BusEvent::Workspace(name) => {
+                state.workspace.replace(name.clone()).as_ref() != Some(name)
+            }
+        };
+
+        if changed {
+            send!(self.tx, event);
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BusEvent> {
+        self.tx.subscribe()
+    }
+}
+
+/// Starts tracking the focused workspace,
+/// publishing changes to the event bus.
+///
+/// This only connects to the compositor on the first call,
+/// so is safe to call for each module or bar which needs it.
+#[cfg(feature = "workspaces")]
+pub fn watch_workspaces(ironbar: &Ironbar) {
+    static STARTED: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+
+    let client = match ironbar.clients.borrow_mut().workspaces() {
+        Ok(client) => client,
+        Err(err) => {
+            error!("Failed to watch workspaces, `show_if_workspace` will have no effect: {err:?}");
+            return;
+        }
+    };
+
+    let rx = client.subscribe_workspace_change();
+    spawn(forward_workspaces(rx, Ironbar::event_bus()));
+}
+
+/// Publishes the name of the focused workspace each time it changes,
+/// until the workspace client is dropped.
+#[cfg(feature = "workspaces")]
+async fn forward_workspaces(
+    mut rx: broadcast::Receiver<WorkspaceUpdate>,
+    event_bus: Arc<EventBus>,
+) {
+    // tracked to catch the focused workspace being renamed
+    let mut focused = None;
+
+    while let Ok(update) = rx.recv().await {
+        let workspace = match update {
+            WorkspaceUpdate::Init(workspaces) => workspaces
+                .into_iter()
+                .find(|workspace| workspace.visibility.is_focused()),
+            WorkspaceUpdate::Focus { new, .. } => Some(new),
+            WorkspaceUpdate::Rename { id, name } if focused == Some(id) => {
+                event_bus.publish(BusEvent::Workspace(name));
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(workspace) = workspace {
+            debug!("Focused workspace: {}", workspace.name);
+            focused = Some(workspace.id);
+            event_bus.publish(BusEvent::Workspace(workspace.name));
+        }
+    }
+}
+
+/// Checks whether something configured with
+/// `show_if_workspace` and `hide_if_workspace` should be visible
+/// while the named workspace is focused.
+///
+/// Everything is shown until the focused workspace is known.
+#[cfg(feature = "workspaces")]
+pub fn workspace_visible(
+    show_if: Option<&[String]>,
+    hide_if: Option<&[String]>,
+    workspace: Option<&str>,
+) -> bool {
+    let Some(workspace) = workspace else {
+        return true;
+    };
+
+    let contains = |names: &[String]| names.iter().any(|name| name == workspace);
+
+    show_if.is_none_or(contains) && !hide_if.is_some_and(contains)
+}
+
+#[cfg(all(test, feature = "workspaces"))]
+mod tests {
+    use super::*;
+    use crate::clients::compositor::WorkspaceClient;
+    use crate::testing::compositor::{workspace, MockWorkspaceClient};
+    use crate::testing::{assert_no_event, recv};
+
+    #[tokio::test]
+    async fn test_forward_focused_workspace() {
+        let client = MockWorkspaceClient::new();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx = event_bus.subscribe();
+
+        tokio::spawn(forward_workspaces(
+            client.subscribe_workspace_change(),
+            event_bus.clone(),
+        ));
+
+        client.send(WorkspaceUpdate::Init(vec![
+            workspace(1, "one", false),
+            workspace(2, "two", true),
+        ]));
+        assert_eq!(
+            recv(&mut rx).await,
+            BusEvent::Workspace(String::from("two"))
+        );
+
+        client.send(WorkspaceUpdate::Focus {
+            old: Some(workspace(2, "two", false)),
+            new: workspace(1, "one", true),
+        });
+        assert_eq!(
+            recv(&mut rx).await,
+            BusEvent::Workspace(String::from("one"))
+        );
+        assert_eq!(event_bus.state().workspace.as_deref(), Some("one"));
+    }
+
+    #[tokio::test]
+    async fn test_forward_workspace_rename() {
+        let client = MockWorkspaceClient::new();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx = event_bus.subscribe();
+
+        tokio::spawn(forward_workspaces(
+            client.subscribe_workspace_change(),
+            event_bus.clone(),
+        ));
+
+        client.send(WorkspaceUpdate::Init(vec![workspace(1, "one", true)]));
+        recv(&mut rx).await;
+
+        client.send(WorkspaceUpdate::Rename {
+            id: 2,
+            name: String::from("other"),
+        });
+        assert_no_event(&mut rx).await;
+
+        client.send(WorkspaceUpdate::Rename {
+            id: 1,
+            name: String::from("renamed"),
+        });
+        assert_eq!(
+            recv(&mut rx).await,
+            BusEvent::Workspace(String::from("renamed"))
+        );
+    }
+
+    #[test]
+    fn test_workspace_visible() {
+        let names = [String::from("one")];
+
+        assert!(workspace_visible(Some(&names), None, None));
+        assert!(workspace_visible(Some(&names), None, Some("one")));
+        assert!(!workspace_visible(Some(&names), None, Some("two")));
+        assert!(!workspace_visible(None, Some(&names), Some("one")));
+        assert!(workspace_visible(None, Some(&names), Some("two")));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{write_lock, Ironbar};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::SystemTime;
+use tokio::sync::broadcast;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HistoryConfig {
+    /// The number of samples to keep for each series.
+    /// Samples are recorded each time a module refreshes,
+    /// so the time covered depends on the module's interval.
+    ///
+    /// **Default**: `300`
+    #[serde(default = "default_samples")]
+    pub samples: usize,
+}
+
+const fn default_samples() -> usize {
+    300
+}
+
+/// A single recorded value.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub time: SystemTime,
+    pub value: f64,
+}
+
+/// Global singleton store of recent numeric module values.
+///
+/// Each series is a ring buffer of the last `capacity` samples,
+/// identified by the recording module's name,
+/// and optionally the token the value belongs to.
+///
+/// History is disabled until a capacity is set,
+/// in which case recording is a no-op.
+pub struct HistoryManager {
+    capacity: usize,
+    series: HashMap<Box<str>, Series>,
+}
+
+struct Series {
+    samples: VecDeque<Sample>,
+    tx: broadcast::Sender<Sample>,
+}
+
+impl Series {
+    fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(16);
+
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            tx,
+        }
+    }
+}
+
+impl Default for HistoryManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HistoryManager {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Sets the number of samples to keep for each series,
+    /// enabling history if it is non-zero.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+
+        for series in self.series.values_mut() {
+            while series.samples.len() > capacity {
+                series.samples.pop_front();
+            }
+        }
+    }
+
+    pub const fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Records a new value for a series,
+    /// dropping the oldest sample if the series is full.
+    pub fn record(&mut self, name: &str, value: f64) {
+        if !self.is_enabled() || !value.is_finite() {
+            return;
+        }
+
+        let capacity = self.capacity;
+        let series = self
+            .series
+            .entry(name.into())
+            .or_insert_with(|| Series::new(capacity));
+
+        if series.samples.len() >= capacity {
+            series.samples.pop_front();
+        }
+
+        let sample = Sample {
+            time: SystemTime::now(),
+            value,
+        };
+
+        series.samples.push_back(sample);
+
+        // there may be no subscribers
+        let _ = series.tx.send(sample);
+    }
+
+    /// Gets the recorded samples for a series, oldest first.
+    pub fn get(&self, name: &str) -> Option<Vec<Sample>> {
+        self.series
+            .get(name)
+            .map(|series| series.samples.iter().copied().collect())
+    }
+
+    /// Gets the names of all recorded series, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.series.keys().map(AsRef::as_ref).collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Subscribes to new samples for a series,
+    /// creating it if it does not exist yet.
+    pub fn subscribe(&mut self, name: &str) -> broadcast::Receiver<Sample> {
+        let capacity = self.capacity;
+        self.series
+            .entry(name.into())
+            .or_insert_with(|| Series::new(capacity))
+            .tx
+            .subscribe()
+    }
+}
+
+/// Records a value in the global history.
+///
+/// Shorthand for locking [`Ironbar::history`].
+pub fn record(name: &str, value: f64) {
+    write_lock!(Ironbar::history()).record(name, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(history: &HistoryManager, name: &str) -> Option<Vec<f64>> {
+        history
+            .get(name)
+            .map(|samples| samples.iter().map(|sample| sample.value).collect())
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut history = HistoryManager::new();
+        history.record("cpu", 1.0);
+
+        assert!(!history.is_enabled());
+        assert_eq!(values(&history, "cpu"), None);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut history = HistoryManager::new();
+        history.set_capacity(3);
+
+        for value in [1.0, 2.0, f64::NAN, 3.0, 4.0] {
+            history.record("cpu", value);
+        }
+        history.record("memory", 50.0);
+
+        // the oldest sample is dropped once full, and non-finite values are ignored
+        assert_eq!(values(&history, "cpu"), Some(vec![2.0, 3.0, 4.0]));
+        assert_eq!(history.names(), vec!["cpu", "memory"]);
+
+        history.set_capacity(2);
+        assert_eq!(values(&history, "cpu"), Some(vec![3.0, 4.0]));
+    }
+}
@@ -0,0 +1,365 @@
+use crate::clients::Clients;
+use crate::script::{Script, ScriptInput};
+use crate::{spawn, Ironbar};
+use color_eyre::Report;
+use serde::Deserialize;
+use tokio::process::Command;
+use tracing::{debug, error, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HooksConfig {
+    /// Runs when AC power is connected.
+    ///
+    /// Requires the `upower` feature.
+    ///
+    /// **Default**: `null`
+    ac_plugged: Option<Hook>,
+
+    /// Runs when AC power is disconnected.
+    ///
+    /// Requires the `upower` feature.
+    ///
+    /// **Default**: `null`
+    ac_unplugged: Option<Hook>,
+
+    /// Runs when the battery drops to or below `battery_low_threshold` while discharging.
+    /// The battery percentage is passed to the script.
+    ///
+    /// Requires the `upower` feature.
+    ///
+    /// **Default**: `null`
+    battery_low: Option<Hook>,
+
+    /// The battery percentage at which `battery_low` runs.
+    ///
+    /// **Default**: `15`
+    #[serde(default = "default_battery_low_threshold")]
+    battery_low_threshold: f64,
+
+    /// Runs when a power device (for example a wireless mouse or headset) is connected.
+    /// The device model is passed to the script.
+    ///
+    /// Requires the `upower` feature.
+    ///
+    /// **Default**: `null`
+    device_connected: Option<Hook>,
+
+    /// Runs when a power device is disconnected.
+    /// The device's UPower object path is passed to the script.
+    ///
+    /// Requires the `upower` feature.
+    ///
+    /// **Default**: `null`
+    device_disconnected: Option<Hook>,
+
+    /// Runs when a network connection becomes available.
+    /// The connection type (`wired`, `wifi`, `cellular` or `vpn`) is passed to the script.
+    ///
+    /// Requires the `network_manager` feature.
+    ///
+    /// **Default**: `null`
+    network_up: Option<Hook>,
+
+    /// Runs when the network connection is lost.
+    ///
+    /// Requires the `network_manager` feature.
+    ///
+    /// **Default**: `null`
+    network_down: Option<Hook>,
+}
+
+const fn default_battery_low_threshold() -> f64 {
+    15.0
+}
+
+/// Actions to perform when a system event occurs.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Hook {
+    /// A script to run.
+    /// Any event details are passed as an argument, accessible using `$0`.
+    ///
+    /// **Default**: `null`
+    script: Option<ScriptInput>,
+
+    /// Path to a sound file to play, using `paplay`.
+    ///
+    /// **Default**: `null`
+    sound: Option<String>,
+}
+
+impl Hook {
+    /// Runs the hook's script and plays its sound, if set.
+    fn run(&self, arg: Option<String>) {
+        if let Some(script) = &self.script {
+            let args = arg.map(|arg| vec![arg]);
+            Script::new_polling(script.clone()).run_as_oneshot(args.as_deref());
+        }
+
+        if let Some(sound) = self.sound.clone() {
+            spawn(async move {
+                match Command::new("paplay").arg(&sound).status().await {
+                    Ok(status) if !status.success() => {
+                        warn!("Failed to play sound '{sound}': {status}");
+                    }
+                    Err(err) => error!("Failed to run paplay: {err:?}"),
+                    _ => {}
+                }
+            });
+        }
+    }
+}
+
+/// Runs `hook` if set.
+fn run_hook(hook: Option<&Hook>, arg: Option<String>) {
+    if let Some(hook) = hook {
+        hook.run(arg);
+    }
+}
+
+/// Starts listening for each configured system event.
+///
+/// Events are received directly from the client backends,
+/// so hooks run regardless of which modules are on a bar.
+pub fn start(ironbar: &Ironbar) {
+    let Some(config) = ironbar.config.borrow().hooks.clone() else {
+        return;
+    };
+
+    debug!("Starting hooks");
+
+    let mut clients = ironbar.clients.borrow_mut();
+
+    #[cfg(feature = "upower")]
+    {
+        watch_ac(&config, &mut clients);
+        watch_battery(&config, &mut clients);
+        watch_devices(&config, &mut clients);
+    }
+
+    #[cfg(feature = "network_manager")]
+    watch_network(&config, &mut clients);
+}
+
+#[cfg(feature = "upower")]
+fn watch_ac(config: &HooksConfig, clients: &mut Clients) {
+    use futures_lite::StreamExt;
+
+    if config.ac_plugged.is_none() && config.ac_unplugged.is_none() {
+        return;
+    }
+
+    let events = match clients.upower_events() {
+        Ok(events) => events,
+        Err(err) => {
+            error!("{err:?}");
+            return;
+        }
+    };
+
+    let plugged = config.ac_plugged.clone();
+    let unplugged = config.ac_unplugged.clone();
+
+    spawn(async move {
+        let mut on_battery = events.on_battery().await.ok();
+        let mut changes = events.receive_on_battery_changed().await;
+
+        while let Some(change) = changes.next().await {
+            let Ok(value) = change.get().await else {
+                continue;
+            };
+
+            if on_battery.replace(value).is_some_and(|prev| prev != value) {
+                if value {
+                    run_hook(unplugged.as_ref(), None);
+                } else {
+                    run_hook(plugged.as_ref(), None);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(feature = "upower")]
+fn watch_battery(config: &HooksConfig, clients: &mut Clients) {
+    use futures_lite::StreamExt;
+    use zbus::names::InterfaceName;
+
+    let Some(hook) = config.battery_low.clone() else {
+        return;
+    };
+
+    let (display, events) = match (clients.upower(), clients.upower_events()) {
+        (Ok(display), Ok(events)) => (display, events),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("{err:?}");
+            return;
+        }
+    };
+
+    let threshold = config.battery_low_threshold;
+
+    spawn(async move {
+        let interface = InterfaceName::from_static_str("org.freedesktop.UPower.Device")?;
+
+        let mut percentage = display
+            .get(interface.clone(), "Percentage")
+            .await?
+            .downcast_ref::<f64>()
+            .copied()
+            .unwrap_or_default();
+
+        let mut changes = display.receive_properties_changed().await?;
+
+        while let Some(signal) = changes.next().await {
+            let args = signal.args()?;
+            if args.interface_name != interface {
+                continue;
+            }
+
+            let Some(new) = args
+                .changed_properties
+                .get("Percentage")
+                .and_then(|value| value.downcast_ref::<f64>())
+                .copied()
+            else {
+                continue;
+            };
+
+            let crossed = percentage > threshold && new <= threshold;
+            percentage = new;
+
+            if crossed && events.on_battery().await.unwrap_or_default() {
+                hook.run(Some(new.to_string()));
+            }
+        }
+
+        Ok::<(), Report>(())
+    });
+}
+
+#[cfg(feature = "upower")]
+fn watch_devices(config: &HooksConfig, clients: &mut Clients) {
+    use futures_lite::StreamExt;
+    use zbus::fdo::PropertiesProxy;
+    use zbus::names::InterfaceName;
+
+    let connected = config.device_connected.clone();
+    let disconnected = config.device_disconnected.clone();
+
+    if connected.is_none() && disconnected.is_none() {
+        return;
+    }
+
+    let events = match clients.upower_events() {
+        Ok(events) => events,
+        Err(err) => {
+            error!("{err:?}");
+            return;
+        }
+    };
+
+    if let Some(hook) = connected {
+        let events = events.clone();
+
+        spawn(async move {
+            let mut added = events.receive_device_added().await?;
+
+            while let Some(signal) = added.next().await {
+                let path = signal.args()?.device;
+
+                // fall back to the object path if the model is not available
+                let model = async {
+                    let device = PropertiesProxy::builder(events.inner().connection())
+                        .destination("org.freedesktop.UPower")?
+                        .path(path.clone())?
+                        .build()
+                        .await?;
+
+                    let model = device
+                        .get(
+                            InterfaceName::from_static_str("org.freedesktop.UPower.Device")?,
+                            "Model",
+                        )
+                        .await?;
+
+                    Ok::<_, Report>(model.downcast_ref::<str>().map(ToString::to_string))
+                }
+                .await
+                .ok()
+                .flatten()
+                .filter(|model| !model.is_empty());
+
+                hook.run(Some(model.unwrap_or_else(|| path.to_string())));
+            }
+
+            Ok::<(), Report>(())
+        });
+    }
+
+    if let Some(hook) = disconnected {
+        spawn(async move {
+            let mut removed = events.receive_device_removed().await?;
+
+            while let Some(signal) = removed.next().await {
+                hook.run(Some(signal.args()?.device.to_string()));
+            }
+
+            Ok::<(), Report>(())
+        });
+    }
+}
+
+#[cfg(feature = "network_manager")]
+fn watch_network(config: &HooksConfig, clients: &mut Clients) {
+    use crate::clients::networkmanager::ClientState;
+    use futures_lite::StreamExt;
+    use futures_signals::signal::SignalExt;
+
+    let up = config.network_up.clone();
+    let down = config.network_down.clone();
+
+    if up.is_none() && down.is_none() {
+        return;
+    }
+
+    let client = match clients.network_manager() {
+        Ok(client) => client,
+        Err(err) => {
+            error!("{err:?}");
+            return;
+        }
+    };
+
+    let mut states = client.subscribe().to_stream();
+
+    spawn(async move {
+        let mut connected = None;
+
+        while let Some(state) = states.next().await {
+            let connection_type = match state {
+                ClientState::WiredConnected => Some("wired"),
+                ClientState::WifiConnected => Some("wifi"),
+                ClientState::CellularConnected => Some("cellular"),
+                ClientState::VpnConnected => Some("vpn"),
+                ClientState::WifiDisconnected | ClientState::Offline => None,
+                ClientState::Unknown => continue,
+            };
+
+            let is_connected = connection_type.is_some();
+
+            // the first known state is the current state, not a change
+            if connected
+                .replace(is_connected)
+                .is_some_and(|prev| prev != is_connected)
+            {
+                if let Some(connection_type) = connection_type {
+                    run_hook(up.as_ref(), Some(connection_type.to_string()));
+                } else {
+                    run_hook(down.as_ref(), None);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,181 @@
+use crate::config::{HotspotConfig, HotspotPosition, HotspotTrigger};
+use crate::modules::PopupButton;
+use crate::popup::Popup;
+use crate::script::Script;
+use glib::Propagation;
+use gtk::gdk::{EventMask, Monitor};
+use gtk::prelude::*;
+use gtk::{ApplicationWindow, Window, WindowType};
+use gtk_layer_shell::{Edge, LayerShell};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Creates an invisible window for each hotspot on the bar's output.
+///
+/// The windows are closed along with the bar.
+pub fn create(
+    hotspots: Vec<HotspotConfig>,
+    bar_window: &ApplicationWindow,
+    monitor: &Monitor,
+    popup: &Rc<Popup>,
+) {
+    for config in hotspots {
+        debug!("Creating hotspot at {:?}", config.position);
+
+        let window = create_window(&config, monitor);
+        let action = Rc::new(Action::new(&config, popup.clone()));
+
+        match config.trigger {
+            HotspotTrigger::Hover => setup_hover(&window, action, config.delay),
+            HotspotTrigger::Click => {
+                window.connect_button_press_event(move |_, _| {
+                    action.run(true);
+                    Propagation::Stop
+                });
+            }
+        }
+
+        {
+            let window = window.clone();
+            bar_window.connect_delete_event(move |_, _| {
+                window.close();
+                Propagation::Proceed
+            });
+        }
+
+        window.show();
+    }
+}
+
+fn create_window(config: &HotspotConfig, monitor: &Monitor) -> Window {
+    let window = Window::new(WindowType::Toplevel);
+    window.set_decorated(false);
+    window.set_widget_name("hotspot");
+    window.set_opacity(0.0);
+    window.set_size_request(config.size, config.size);
+    window.add_events(
+        EventMask::ENTER_NOTIFY_MASK | EventMask::LEAVE_NOTIFY_MASK | EventMask::BUTTON_PRESS_MASK,
+    );
+
+    window.init_layer_shell();
+    window.set_monitor(monitor);
+    window.set_layer(gtk_layer_shell::Layer::Top);
+    window.set_namespace(concat!(env!("CARGO_PKG_NAME"), "-hotspot"));
+
+    // ignore the bar's (and any other) exclusive zone,
+    // so that the hotspot sits right at the edge of the output.
+    window.set_exclusive_zone(-1);
+
+    for edge in anchors(config.position) {
+        window.set_anchor(*edge, true);
+    }
+
+    window
+}
+
+/// Gets the edges of the output a hotspot window is anchored to.
+/// Anchoring to opposite edges stretches it across the output.
+const fn anchors(position: HotspotPosition) -> &'static [Edge] {
+    match position {
+        HotspotPosition::TopLeft => &[Edge::Top, Edge::Left],
+        HotspotPosition::Top => &[Edge::Top, Edge::Left, Edge::Right],
+        HotspotPosition::TopRight => &[Edge::Top, Edge::Right],
+        HotspotPosition::Left => &[Edge::Left, Edge::Top, Edge::Bottom],
+        HotspotPosition::Right => &[Edge::Right, Edge::Top, Edge::Bottom],
+        HotspotPosition::BottomLeft => &[Edge::Bottom, Edge::Left],
+        HotspotPosition::Bottom => &[Edge::Bottom, Edge::Left, Edge::Right],
+        HotspotPosition::BottomRight => &[Edge::Bottom, Edge::Right],
+    }
+}
+
+/// Activates the hotspot once the pointer has stayed inside it for `delay` milliseconds.
+fn setup_hover(window: &Window, action: Rc<Action>, delay: u64) {
+    let pending = Rc::new(RefCell::new(None::<glib::SourceId>));
+
+    {
+        let pending = pending.clone();
+        window.connect_enter_notify_event(move |_, _| {
+            let action = action.clone();
+            let source = {
+                let pending = pending.clone();
+                glib::timeout_add_local_once(Duration::from_millis(delay), move || {
+                    pending.take();
+                    action.run(false);
+                })
+            };
+
+            if let Some(previous) = pending.replace(Some(source)) {
+                previous.remove();
+            }
+
+            Propagation::Proceed
+        });
+    }
+
+    window.connect_leave_notify_event(move |_, _| {
+        if let Some(source) = pending.take() {
+            source.remove();
+        }
+
+        Propagation::Proceed
+    });
+}
+
+/// What to do when a hotspot activates.
+#[derive(Debug)]
+struct Action {
+    script: Option<Script>,
+    popup_name: Option<String>,
+    popup: Rc<Popup>,
+}
+
+impl Action {
+    fn new(config: &HotspotConfig, popup: Rc<Popup>) -> Self {
+        Self {
+            script: config.on_trigger.clone().map(Script::new_polling),
+            popup_name: config.popup.clone(),
+            popup,
+        }
+    }
+
+    /// Runs the script and opens the popup.
+    /// If `toggle` is set and the popup is already open, it is closed instead.
+    fn run(&self, toggle: bool) {
+        if let Some(script) = &self.script {
+            script.run_as_oneshot(None);
+        }
+
+        if let Some(name) = &self.popup_name {
+            self.show_popup(name, toggle);
+        }
+    }
+
+    fn show_popup(&self, name: &str, toggle: bool) {
+        let data = self
+            .popup
+            .container_cache
+            .borrow()
+            .iter()
+            .find(|(_, value)| value.name == name)
+            .map(|(id, value)| (*id, value.content.buttons.first().cloned()));
+
+        let Some((id, Some(button))) = data else {
+            warn!("Hotspot popup '{name}' does not exist on this bar");
+            return;
+        };
+
+        if self.popup.visible() && self.popup.current_widget() == Some(id) {
+            if toggle {
+                self.popup.hide();
+            }
+
+            return;
+        }
+
+        // only one popup per bar, so hide if open for another widget
+        self.popup.hide();
+        self.popup.show(id, button.popup_id());
+    }
+}
@@ -0,0 +1,154 @@
+//! Fallback icons bundled into the binary as a GResource,
+//! so that common module icons still render
+//! on systems with a missing or incomplete icon theme.
+//!
+//! The icons are compiled from `resources/icons` by the build script.
+
+use crate::lock;
+use color_eyre::Result;
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::gio::{self, ResourceLookupFlags};
+use gtk::prelude::*;
+use gtk::IconTheme;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::Mutex;
+use tracing::error;
+
+/// Resource path the icons are registered under.
+const RESOURCE_PATH: &str = "/dev/jstanger/ironbar/icons";
+
+static RESOURCES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/icons.gresource"));
+
+/// Set from the top-level `bundled_icons` option.
+static MODE: Mutex<BundledIcons> = Mutex::new(BundledIcons::Fallback);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum BundledIcons {
+    /// Use bundled icons only when the icon theme does not contain the icon.
+    #[default]
+    Fallback,
+    /// Use bundled icons instead of the icon theme's, where available.
+    Prefer,
+    /// Never use bundled icons.
+    Disabled,
+}
+
+/// Sets when bundled icons are used.
+pub fn set_mode(mode: BundledIcons) {
+    *lock!(MODE) = mode;
+}
+
+fn mode() -> BundledIcons {
+    *lock!(MODE)
+}
+
+/// Registers the bundled icons with GIO.
+///
+/// Unless disabled, they are also added to the default icon theme as a fallback,
+/// for widgets which look up icons directly rather than through the image provider.
+///
+/// This must be called once GTK is initialized.
+pub fn register() {
+    match gio::Resource::from_data(&glib::Bytes::from_static(RESOURCES)) {
+        Ok(resource) => gio::resources_register(&resource),
+        Err(err) => {
+            error!("Failed to load bundled icons: {err:?}");
+            return;
+        }
+    }
+
+    if mode() != BundledIcons::Disabled {
+        if let Some(theme) = IconTheme::default() {
+            theme.add_resource_path(RESOURCE_PATH);
+        }
+    }
+}
+
+/// Gets the resource path of the bundled icon for `name`,
+/// if there is one and bundled icons are enabled.
+fn path(name: &str) -> Option<String> {
+    if mode() == BundledIcons::Disabled {
+        return None;
+    }
+
+    let path = format!("{RESOURCE_PATH}/{}.svg", alias(name));
+
+    gio::resources_get_info(&path, ResourceLookupFlags::NONE)
+        .is_ok()
+        .then_some(path)
+}
+
+/// Checks whether there is a bundled icon for `name`.
+pub fn contains(name: &str) -> bool {
+    path(name).is_some()
+}
+
+/// Checks whether bundled icons should be used before the icon theme.
+pub fn preferred() -> bool {
+    mode() == BundledIcons::Prefer
+}
+
+/// Loads the bundled icon for `name` at `size` pixels,
+/// or `None` if there is no bundled icon.
+pub fn load(name: &str, size: i32) -> Option<Result<Pixbuf>> {
+    path(name).map(|path| Ok(Pixbuf::from_resource_at_scale(&path, size, size, true)?))
+}
+
+/// Maps the precise `battery-level-<percent>` icon names used by UPower
+/// onto the coarser battery icons which are bundled.
+fn alias(name: &str) -> Cow<'_, str> {
+    let Some(level) = name
+        .strip_prefix("battery-level-")
+        .and_then(|name| name.strip_suffix("-symbolic"))
+    else {
+        return Cow::Borrowed(name);
+    };
+
+    let (level, state) = level
+        .split_once('-')
+        .map_or((level, None), |(level, state)| (level, Some(state)));
+
+    let Ok(level) = level.parse::<u8>() else {
+        return Cow::Borrowed(name);
+    };
+
+    let level = match level {
+        90.. => "full",
+        50.. => "good",
+        20.. => "low",
+        10.. => "caution",
+        _ => "empty",
+    };
+
+    match state {
+        Some("charged") => Cow::Borrowed("battery-full-charged-symbolic"),
+        Some("charging") => Cow::Owned(format!("battery-{level}-charging-symbolic")),
+        _ => Cow::Owned(format!("battery-{level}-symbolic")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias() {
+        assert_eq!(
+            alias("audio-volume-high-symbolic"),
+            "audio-volume-high-symbolic"
+        );
+        assert_eq!(alias("battery-level-100-symbolic"), "battery-full-symbolic");
+        assert_eq!(
+            alias("battery-level-60-charging-symbolic"),
+            "battery-good-charging-symbolic"
+        );
+        assert_eq!(
+            alias("battery-level-100-charged-symbolic"),
+            "battery-full-charged-symbolic"
+        );
+        assert_eq!(alias("battery-level-0-symbolic"), "battery-empty-symbolic");
+    }
+}
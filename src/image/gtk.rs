@@ -1,10 +1,24 @@
 use super::ImageProvider;
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use gtk::prelude::*;
-use gtk::{Button, IconTheme, Image, Label, Orientation};
+#[cfg(any(
+    feature = "music",
+    feature = "workspaces",
+    feature = "clipboard",
+    feature = "keys",
+    feature = "layout"
+))]
+use gtk::Button;
+use gtk::{IconTheme, Image, Label, Orientation};
 use std::ops::Deref;
 
-#[cfg(any(feature = "music", feature = "workspaces", feature = "clipboard"))]
+#[cfg(any(
+    feature = "music",
+    feature = "workspaces",
+    feature = "clipboard",
+    feature = "keys",
+    feature = "layout"
+))]
 pub fn new_icon_button(input: &str, icon_theme: &IconTheme, size: i32) -> Button {
     let button = Button::new();
 
@@ -31,7 +45,12 @@ pub fn new_icon_button(input: &str, icon_theme: &IconTheme, size: i32) -> Button
     button
 }
 
-#[cfg(any(feature = "music", feature = "keys"))]
+#[cfg(any(
+    feature = "music",
+    feature = "keys",
+    feature = "layout",
+    feature = "lock_keys"
+))]
 pub struct IconLabel {
     container: gtk::Box,
     label: Label,
@@ -41,7 +60,12 @@ pub struct IconLabel {
     size: i32,
 }
 
-#[cfg(any(feature = "music", feature = "keys"))]
+#[cfg(any(
+    feature = "music",
+    feature = "keys",
+    feature = "layout",
+    feature = "lock_keys"
+))]
 impl IconLabel {
     pub fn new(input: &str, icon_theme: &IconTheme, size: i32) -> Self {
         let container = gtk::Box::new(Orientation::Horizontal, 0);
@@ -1,7 +1,21 @@
-#[cfg(any(feature = "music", feature = "workspaces", feature = "clipboard"))]
+pub mod bundled;
+#[cfg(any(
+    feature = "music",
+    feature = "workspaces",
+    feature = "clipboard",
+    feature = "keys",
+    feature = "layout",
+    feature = "lock_keys"
+))]
 mod gtk;
 mod provider;
 
-#[cfg(any(feature = "music", feature = "workspaces"))]
+#[cfg(any(
+    feature = "music",
+    feature = "workspaces",
+    feature = "keys",
+    feature = "layout",
+    feature = "lock_keys"
+))]
 pub use self::gtk::*;
 pub use provider::ImageProvider;
@@ -1,3 +1,4 @@
+use super::bundled;
 use crate::desktop_file::get_desktop_icon_name;
 #[cfg(feature = "http")]
 use crate::{glib_recv_mpsc, send_async, spawn};
@@ -103,7 +104,8 @@ impl<'a> ImageProvider<'a> {
             )),
             None if theme
                 .lookup_icon(input, size, IconLookupFlags::empty())
-                .is_some() =>
+                .is_some()
+                || bundled::contains(input) =>
             {
                 Some(ImageLocation::Icon {
                     name: input_name.to_string(),
@@ -227,18 +229,29 @@ impl<'a> ImageProvider<'a> {
         Ok(())
     }
 
-    /// Attempts to get a `Pixbuf` from the GTK icon theme.
+    /// Attempts to get a `Pixbuf` from the GTK icon theme,
+    /// or from the bundled icons if the theme does not contain it.
     fn get_from_icon(&self, name: &str, theme: &IconTheme, scale: i32) -> Result<Pixbuf> {
+        if bundled::preferred() {
+            if let Some(pixbuf) = bundled::load(name, self.size * scale) {
+                return pixbuf;
+            }
+        }
+
         let pixbuf =
             match theme.lookup_icon_for_scale(name, self.size, scale, IconLookupFlags::empty()) {
                 Some(_) => theme.load_icon(name, self.size * scale, IconLookupFlags::FORCE_SIZE),
                 None => Ok(None),
             }?;
 
-        pixbuf.map_or_else(
-            || Err(Report::msg("Icon theme does not contain icon '{name}'")),
-            Ok,
-        )
+        match pixbuf {
+            Some(pixbuf) => Ok(pixbuf),
+            None => bundled::load(name, self.size * scale).unwrap_or_else(|| {
+                Err(Report::msg(format!(
+                    "Icon theme does not contain icon '{name}'"
+                )))
+            }),
+        }
     }
 
     /// Attempts to get a `Pixbuf` from a local file.
@@ -29,6 +29,56 @@ pub enum Command {
 
     /// Interact with a specific bar.
     Bar(BarCommand),
+
+    /// Toggle a module's popup open/closed, without needing to know which bar it is on.
+    /// The popup is opened on the first bar with a matching module.
+    OpenPopup {
+        /// The configured name of the widget.
+        /// This is the module type, such as `menu`, unless the module sets a `name`.
+        widget_name: String,
+
+        /// Only look for the widget on bars with this name.
+        #[clap(long)]
+        bar_name: Option<String>,
+    },
+
+    /// Schedule a one-shot reminder,
+    /// shown by the `reminders` module.
+    #[cfg(feature = "reminders")]
+    Remind {
+        /// The reminder message.
+        message: String,
+        /// When the reminder is due.
+        /// Either a duration such as `10m` or `1h30m`,
+        /// a number of minutes, or a time such as `14:30`.
+        time: String,
+    },
+
+    /// Start or stop a focus session,
+    /// shown by the `reminders` module.
+    #[cfg(feature = "reminders")]
+    #[command(subcommand)]
+    Focus(FocusCommand),
+
+    /// Control the countdown timer,
+    /// shown by the `timer` module.
+    #[cfg(feature = "timer")]
+    #[command(subcommand)]
+    Timer(TimerCommand),
+
+    /// Control bar-wide presentation mode.
+    #[cfg(feature = "presentation")]
+    #[command(subcommand)]
+    Presentation(PresentationCommand),
+
+    /// Get the recorded history of a numeric module value,
+    /// as one `<unix timestamp> <value>` line per sample, oldest first.
+    #[cfg(feature = "history")]
+    History {
+        /// The name of the series, such as `sysinfo.cpu_percent`.
+        /// If not set, the names of all recorded series are listed instead.
+        name: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
@@ -55,6 +105,57 @@ pub enum IronvarCommand {
     List,
 }
 
+#[cfg(feature = "reminders")]
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum FocusCommand {
+    /// Start a focus session, replacing any running session.
+    Start {
+        /// How long the session lasts.
+        /// Accepts the same values as `remind`.
+        /// If not set, the module's configured focus duration is used.
+        duration: Option<String>,
+    },
+    /// Stop the running focus session early.
+    Stop,
+    /// Get the time remaining in the running focus session.
+    Get,
+}
+
+#[cfg(feature = "timer")]
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum TimerCommand {
+    /// Start a countdown, replacing any running timer.
+    Start {
+        /// How long the countdown lasts.
+        /// Either a duration such as `10m` or `1h30m`, or a number of minutes.
+        duration: String,
+        /// The label to show for the countdown.
+        label: Option<String>,
+    },
+    /// Start a work phase if stopped, otherwise pause or resume the timer.
+    Toggle,
+    /// Stop the timer.
+    Stop,
+    /// Get the time remaining in the current phase.
+    Get,
+}
+
+#[cfg(feature = "presentation")]
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum PresentationCommand {
+    /// Enable presentation mode.
+    Enable,
+    /// Disable presentation mode, restoring the previous state.
+    Disable,
+    /// Toggle presentation mode on or off.
+    Toggle,
+    /// Get whether presentation mode is enabled.
+    Get,
+}
+
 #[derive(Args, Debug, Serialize, Deserialize)]
 pub struct BarCommand {
     /// The name of the bar.
@@ -88,6 +189,11 @@ pub enum BarCommandType {
     /// Get the bar's visibility state.
     GetVisible,
 
+    // == Geometry == \\
+    /// Get the bar's size and position on its output, as JSON.
+    /// Returns one value for each output the bar is on.
+    GetGeometry,
+
     // == Popup visibility == \\
     /// Open a popup, regardless of current state.
     /// If opening this popup, and a different popup on the same bar is already open, the other is closed.
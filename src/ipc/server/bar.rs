@@ -19,6 +19,10 @@ pub fn handle_command(command: BarCommand, ironbar: &Rc<Ironbar>) -> Response {
             GetVisible => Response::OkValue {
                 value: bar.visible().to_string(),
             },
+            GetGeometry => match serde_json::to_string(&bar.geometry()) {
+                Ok(value) => Response::OkValue { value },
+                Err(err) => Response::error(&err.to_string()),
+            },
             ShowPopup { widget_name } => show_popup(&bar, widget_name),
             HidePopup => hide_popup(&bar),
             SetPopupVisible {
@@ -94,6 +98,47 @@ fn show_popup(bar: &Bar, widget_name: &str) -> Response {
     }
 }
 
+/// Toggles the popup of the named widget on the first bar which has it.
+/// If the popup is already open on any bar, it is closed instead.
+pub fn toggle_popup_any(
+    widget_name: &str,
+    bar_name: Option<&str>,
+    ironbar: &Rc<Ironbar>,
+) -> Response {
+    let bars = match bar_name {
+        Some(name) => ironbar.bars_by_name(name),
+        None => ironbar.bars.borrow().clone(),
+    };
+
+    let bars = bars
+        .into_iter()
+        .filter(|bar| {
+            bar.popup()
+                .container_cache
+                .borrow()
+                .values()
+                .any(|value| value.name == widget_name)
+        })
+        .collect::<Vec<_>>();
+
+    let open = bars.iter().find(|bar| {
+        let popup = bar.popup();
+        let current = popup.current_widget();
+        let cache = popup.container_cache.borrow();
+
+        popup.visible()
+            && current
+                .and_then(|id| cache.get(&id))
+                .is_some_and(|value| value.name == widget_name)
+    });
+
+    match (open, bars.first()) {
+        (Some(bar), _) => hide_popup(bar),
+        (None, Some(bar)) => show_popup(bar, widget_name),
+        (None, None) => Response::error("Invalid module name"),
+    }
+}
+
 fn hide_popup(bar: &Bar) -> Response {
     let popup = bar.popup();
     popup.hide();
@@ -0,0 +1,37 @@
+use crate::ipc::Response;
+use crate::{read_lock, Ironbar};
+use std::time::UNIX_EPOCH;
+
+pub fn handle_command(name: Option<String>) -> Response {
+    let history = Ironbar::history();
+    let history = read_lock!(history);
+
+    if !history.is_enabled() {
+        return Response::error("History is not enabled");
+    }
+
+    let Some(name) = name else {
+        let values = history.names().into_iter().map(String::from).collect();
+        return Response::Multi { values };
+    };
+
+    match history.get(&name) {
+        Some(samples) => {
+            let values = samples
+                .iter()
+                .map(|sample| {
+                    let time = sample
+                        .time
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+
+                    format!("{time} {}", sample.value)
+                })
+                .collect();
+
+            Response::Multi { values }
+        }
+        None => Response::error("Series not found"),
+    }
+}
@@ -1,18 +1,31 @@
 mod bar;
+#[cfg(feature = "history")]
+mod history;
 mod ironvar;
+#[cfg(feature = "presentation")]
+mod presentation;
 
 use std::fs;
 use std::path::Path;
 use std::rc::Rc;
 
 use color_eyre::{Report, Result};
-use gtk::prelude::*;
 use gtk::Application;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tracing::{debug, error, info, warn};
 
+#[cfg(feature = "reminders")]
+use crate::clients::reminders;
+#[cfg(feature = "timer")]
+use crate::clients::timer;
+#[cfg(feature = "timer")]
+use crate::config::parse_duration;
+#[cfg(feature = "reminders")]
+use crate::ipc::commands::FocusCommand;
+#[cfg(feature = "timer")]
+use crate::ipc::commands::TimerCommand;
 use crate::ipc::{Command, Response};
 use crate::style::load_css;
 use crate::{glib_recv_mpsc, send_async, spawn, try_send, Ironbar};
@@ -119,26 +132,7 @@ impl Ipc {
                 Response::Ok
             }
             Command::Reload => {
-                info!("Closing existing bars");
-                ironbar.bars.borrow_mut().clear();
-
-                let windows = application.windows();
-                for window in windows {
-                    window.close();
-                }
-
-                let wl = ironbar.clients.borrow_mut().wayland();
-                let outputs = wl.output_info_all();
-
-                ironbar.reload_config();
-
-                for output in outputs {
-                    match crate::load_output_bars(ironbar, application, &output) {
-                        Ok(mut bars) => ironbar.bars.borrow_mut().append(&mut bars),
-                        Err(err) => error!("{err:?}"),
-                    }
-                }
-
+                crate::reload(ironbar, application);
                 Response::Ok
             }
             Command::LoadCss { path } => {
@@ -151,6 +145,94 @@ impl Ipc {
             }
             Command::Var(cmd) => ironvar::handle_command(cmd),
             Command::Bar(cmd) => bar::handle_command(cmd, ironbar),
+            Command::OpenPopup {
+                widget_name,
+                bar_name,
+            } => bar::toggle_popup_any(&widget_name, bar_name.as_deref(), ironbar),
+            #[cfg(feature = "reminders")]
+            Command::Remind { message, time } => {
+                let client = ironbar.clients.borrow_mut().reminders();
+
+                if !client.has_subscribers() {
+                    return Response::error("No reminders module is configured");
+                }
+
+                match reminders::parse_time(&time) {
+                    Ok(duration) => {
+                        let reminder = client.add(message, duration);
+                        Response::OkValue {
+                            value: reminder.due.format("%H:%M:%S").to_string(),
+                        }
+                    }
+                    Err(err) => Response::error(&err.to_string()),
+                }
+            }
+            #[cfg(feature = "reminders")]
+            Command::Focus(cmd) => {
+                let client = ironbar.clients.borrow_mut().reminders();
+
+                if !client.has_subscribers() {
+                    return Response::error("No reminders module is configured");
+                }
+
+                match cmd {
+                    FocusCommand::Start { duration } => {
+                        match duration.as_deref().map(reminders::parse_time).transpose() {
+                            Ok(duration) => Response::OkValue {
+                                value: client.start_focus(duration).format("%H:%M:%S").to_string(),
+                            },
+                            Err(err) => Response::error(&err.to_string()),
+                        }
+                    }
+                    FocusCommand::Stop => {
+                        client.stop_focus();
+                        Response::Ok
+                    }
+                    FocusCommand::Get => match client.focus() {
+                        Some(focus) => Response::OkValue {
+                            value: focus.remaining().as_secs().to_string(),
+                        },
+                        None => Response::error("No focus session is running"),
+                    },
+                }
+            }
+            #[cfg(feature = "timer")]
+            Command::Timer(cmd) => {
+                let client = ironbar.clients.borrow_mut().timer();
+
+                if !client.has_subscribers() {
+                    return Response::error("No timer module is configured");
+                }
+
+                match cmd {
+                    TimerCommand::Start { duration, label } => match parse_duration(&duration) {
+                        Ok(duration) => {
+                            let label = label.unwrap_or_else(|| String::from("Countdown"));
+                            client.start(timer::Phase::Countdown(label), duration);
+                            Response::Ok
+                        }
+                        Err(err) => Response::error(&err.to_string()),
+                    },
+                    TimerCommand::Toggle => {
+                        client.toggle();
+                        Response::Ok
+                    }
+                    TimerCommand::Stop => {
+                        client.stop();
+                        Response::Ok
+                    }
+                    TimerCommand::Get => match client.state() {
+                        Some(state) => Response::OkValue {
+                            value: state.remaining().as_secs().to_string(),
+                        },
+                        None => Response::error("No timer is running"),
+                    },
+                }
+            }
+            #[cfg(feature = "presentation")]
+            Command::Presentation(cmd) => presentation::handle_command(cmd),
+            #[cfg(feature = "history")]
+            Command::History { name } => history::handle_command(name),
         }
     }
 
@@ -0,0 +1,22 @@
+use crate::event_bus::BusEvent;
+use crate::ipc::commands::PresentationCommand;
+use crate::ipc::Response;
+use crate::Ironbar;
+
+pub fn handle_command(command: PresentationCommand) -> Response {
+    let event_bus = Ironbar::event_bus();
+    let enabled = event_bus.state().presentation;
+
+    match command {
+        PresentationCommand::Enable => event_bus.publish(BusEvent::Presentation(true)),
+        PresentationCommand::Disable => event_bus.publish(BusEvent::Presentation(false)),
+        PresentationCommand::Toggle => event_bus.publish(BusEvent::Presentation(!enabled)),
+        PresentationCommand::Get => {
+            return Response::OkValue {
+                value: enabled.to_string(),
+            }
+        }
+    }
+
+    Response::Ok
+}
@@ -0,0 +1,180 @@
+use crate::bar::{create_bar, Bar};
+use crate::clients::logind::ManagerProxy;
+use crate::config::{BarConfig, LidCloseAction};
+use crate::polling;
+use crate::{get_display, get_monitor, glib_recv_mpsc, send_async, spawn, Ironbar};
+use color_eyre::Result;
+use gtk::Application;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+use zbus::CacheProperties;
+
+/// Prefixes of output names used for laptops' internal displays.
+const INTERNAL_OUTPUTS: [&str; 3] = ["eDP", "LVDS", "DSI"];
+
+/// logind does not emit change signals for the lid state,
+/// so it is polled instead.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn is_internal(monitor_name: &str) -> bool {
+    INTERNAL_OUTPUTS
+        .iter()
+        .any(|prefix| monitor_name.starts_with(prefix))
+}
+
+/// Bars which have been hidden or moved while the lid is closed.
+#[derive(Debug, Default)]
+struct LidState {
+    /// Bars on the internal display which have been hidden.
+    hidden: Vec<Bar>,
+    /// Copies of bars created on an external display.
+    moved: Vec<Bar>,
+}
+
+impl LidState {
+    /// Hides or moves any bars on the internal display which have not been already.
+    ///
+    /// This is safe to call repeatedly,
+    /// which allows bars created after the lid is closed to be handled.
+    fn close(&mut self, app: &Application, ironbar: &Rc<Ironbar>) {
+        let bars = ironbar
+            .bars
+            .borrow()
+            .iter()
+            .filter(|&bar| is_internal(bar.monitor_name()) && !self.hidden.contains(bar))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for bar in bars {
+            let Some(config) = bar.lid_config() else {
+                continue;
+            };
+
+            if config.on_lid_close == LidCloseAction::Move {
+                match Self::move_bar(app, ironbar, config.clone()) {
+                    Ok(Some(moved)) => {
+                        ironbar.bars.borrow_mut().push(moved.clone());
+                        self.moved.push(moved);
+                    }
+                    Ok(None) => debug!("No external output to move bar '{}' to", bar.name()),
+                    Err(err) => error!("{err:?}"),
+                }
+            }
+
+            info!("Lid closed, hiding bar '{}'", bar.name());
+            bar.set_visible(false);
+            self.hidden.push(bar);
+        }
+    }
+
+    /// Creates a copy of a bar on the first external output.
+    fn move_bar(
+        app: &Application,
+        ironbar: &Rc<Ironbar>,
+        config: BarConfig,
+    ) -> Result<Option<Bar>> {
+        let wl = ironbar.clients.borrow_mut().wayland();
+
+        let Some((monitor_name, output_size)) =
+            wl.output_info_all().into_iter().find_map(|output| {
+                output
+                    .name
+                    .filter(|name| !is_internal(name))
+                    .map(|name| (name, output.logical_size.unwrap_or_default()))
+            })
+        else {
+            return Ok(None);
+        };
+
+        info!("Lid closed, moving bar to '{monitor_name}'");
+
+        let monitor = get_monitor(&get_display(), &monitor_name);
+        create_bar(
+            app,
+            &monitor,
+            monitor_name,
+            output_size,
+            config,
+            ironbar.clone(),
+        )
+        .map(Some)
+    }
+
+    /// Closes any moved bars, and shows the hidden bars again.
+    fn open(&mut self, ironbar: &Rc<Ironbar>) {
+        if self.hidden.is_empty() && self.moved.is_empty() {
+            return;
+        }
+
+        info!("Lid opened, restoring bars");
+
+        let moved = std::mem::take(&mut self.moved);
+        ironbar.bars.borrow_mut().retain(|bar| !moved.contains(bar));
+
+        for bar in moved {
+            bar.close();
+        }
+
+        // bars which were removed while the lid was closed,
+        // for example when reloading the config, should stay closed.
+        for bar in self.hidden.drain(..) {
+            if ironbar.bars.borrow().contains(&bar) {
+                bar.set_visible(true);
+            }
+        }
+    }
+}
+
+/// Starts watching the laptop lid,
+/// hiding or moving bars on the internal display while it is closed
+/// according to their `on_lid_close` option.
+pub fn start(app: &Application, ironbar: &Rc<Ironbar>) {
+    let (tx, rx) = mpsc::channel(4);
+
+    spawn(async move {
+        if let Err(err) = watch(tx).await {
+            error!("Failed to watch lid state: {err:?}");
+        }
+    });
+
+    let app = app.clone();
+    let ironbar = ironbar.clone();
+    let mut state = LidState::default();
+
+    glib_recv_mpsc!(rx, closed => {
+        if closed {
+            state.close(&app, &ironbar);
+        } else {
+            state.open(&ironbar);
+        }
+    });
+}
+
+/// Polls the lid state, sending it whenever it changes.
+/// While the lid is closed, the state is re-sent on each poll
+/// so that any newly created bars are also handled.
+async fn watch(tx: mpsc::Sender<bool>) -> Result<()> {
+    let dbus = zbus::Connection::system().await?;
+    let manager = ManagerProxy::builder(&dbus)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let mut closed = false;
+
+    loop {
+        let lid_closed = manager.lid_closed().await?;
+
+        if lid_closed != closed {
+            debug!("Lid closed: {lid_closed}");
+            closed = lid_closed;
+            send_async!(tx, closed);
+        } else if closed {
+            send_async!(tx, closed);
+        }
+
+        polling::sleep(POLL_INTERVAL).await;
+    }
+}
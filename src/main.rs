@@ -17,7 +17,7 @@ use clap::Parser;
 use color_eyre::eyre::Result;
 use color_eyre::Report;
 use dirs::config_dir;
-use gtk::gdk::Display;
+use gtk::gdk::{Display, Monitor};
 use gtk::prelude::*;
 use gtk::Application;
 use smithay_client_toolkit::output::OutputInfo;
@@ -31,30 +31,56 @@ use crate::clients::wayland::OutputEventType;
 use crate::clients::Clients;
 use crate::config::{Config, MonitorConfig};
 use crate::error::ExitCode;
+#[cfg(any(feature = "presentation", feature = "workspaces"))]
+use crate::event_bus::EventBus;
+#[cfg(feature = "history")]
+use crate::history::HistoryManager;
 #[cfg(feature = "ipc")]
 use crate::ironvar::VariableManager;
 use crate::style::load_css;
 
+mod animation;
 mod bar;
 #[cfg(feature = "cli")]
 mod cli;
 mod clients;
 mod config;
 mod desktop_file;
+mod display_mode;
 mod dynamic_value;
 mod error;
+#[cfg(any(feature = "presentation", feature = "workspaces"))]
+mod event_bus;
 mod gtk_helpers;
+#[cfg(feature = "history")]
+mod history;
+#[cfg(any(feature = "upower", feature = "network_manager"))]
+mod hooks;
+mod hotspot;
 mod image;
 #[cfg(feature = "ipc")]
 mod ipc;
 #[cfg(feature = "ipc")]
 mod ironvar;
+#[cfg(feature = "lid")]
+mod lid;
 mod logging;
 mod macros;
+#[cfg(feature = "media_keys")]
+mod media_keys;
 mod modules;
+#[cfg(feature = "osd")]
+mod osd;
+mod overflow;
+mod polling;
 mod popup;
+#[cfg(feature = "presentation")]
+mod presentation;
 mod script;
 mod style;
+#[cfg(test)]
+mod testing;
+mod text;
 
 pub const APP_ID: &str = "dev.jstanger.ironbar";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -182,6 +208,23 @@ impl Ironbar {
                 load_css(style_path, app.clone());
             }
 
+            image::bundled::register();
+
+            #[cfg(feature = "osd")]
+            osd::start(app, &instance);
+
+            #[cfg(any(feature = "upower", feature = "network_manager"))]
+            hooks::start(&instance);
+
+            #[cfg(feature = "media_keys")]
+            media_keys::start(&instance);
+
+            #[cfg(feature = "presentation")]
+            presentation::start(&instance);
+
+            #[cfg(feature = "lid")]
+            lid::start(app, &instance);
+
             let (tx, rx) = mpsc::channel();
 
             #[cfg(feature = "ipc")]
@@ -267,6 +310,24 @@ impl Ironbar {
             .clone()
     }
 
+    /// Gets the metrics history singleton.
+    #[cfg(feature = "history")]
+    #[must_use]
+    pub fn history() -> Arc<RwLock<HistoryManager>> {
+        static HISTORY: OnceLock<Arc<RwLock<HistoryManager>>> = OnceLock::new();
+        HISTORY
+            .get_or_init(|| arc_rw!(HistoryManager::new()))
+            .clone()
+    }
+
+    /// Gets the event bus singleton.
+    #[cfg(any(feature = "presentation", feature = "workspaces"))]
+    #[must_use]
+    pub fn event_bus() -> Arc<EventBus> {
+        static EVENT_BUS: OnceLock<Arc<EventBus>> = OnceLock::new();
+        EVENT_BUS.get_or_init(|| Arc::new(EventBus::new())).clone()
+    }
+
     /// Gets clones of bars by their name.
     ///
     /// Since the bars contain mostly GTK objects,
@@ -283,12 +344,35 @@ impl Ironbar {
 
     /// Re-reads the config file from disk and replaces the active config.
     /// Note this does *not* reload bars, which must be performed separately.
-    #[cfg(feature = "ipc")]
     fn reload_config(&self) {
         self.config.replace(load_config().0);
     }
 }
 
+/// Closes all bars, re-reads the config file,
+/// and loads new bars onto each output.
+pub fn reload(ironbar: &Rc<Ironbar>, application: &Application) {
+    info!("Closing existing bars");
+    ironbar.bars.borrow_mut().clear();
+
+    let windows = application.windows();
+    for window in windows {
+        window.close();
+    }
+
+    let wl = ironbar.clients.borrow_mut().wayland();
+    let outputs = wl.output_info_all();
+
+    ironbar.reload_config();
+
+    for output in outputs {
+        match load_output_bars(ironbar, application, &output) {
+            Ok(mut bars) => ironbar.bars.borrow_mut().append(&mut bars),
+            Err(err) => error!("{err:?}"),
+        }
+    }
+}
+
 fn start_ironbar() {
     let ironbar = Ironbar::new();
     ironbar.start();
@@ -339,58 +423,69 @@ fn load_config() -> (Config, PathBuf) {
         }
     }
 
+    animation::set_enabled(config.animations);
+    polling::configure(&config.polling);
+    image::bundled::set_mode(config.bundled_icons);
+
+    #[cfg(feature = "history")]
+    if let Some(history) = &config.history {
+        write_lock!(Ironbar::history()).set_capacity(history.samples);
+    }
+
     (config, directory)
 }
 
 /// Gets the GDK `Display` instance.
 fn get_display() -> Display {
-    Display::default().map_or_else(
-        || {
-            let report = Report::msg("Failed to get default GTK display");
-            error!("{:?}", report);
-            exit(ExitCode::GtkDisplay as i32)
-        },
-        |display| display,
-    )
+    Display::default().unwrap_or_else(|| {
+        let report = Report::msg("Failed to get default GTK display");
+        error!("{:?}", report);
+        exit(ExitCode::GtkDisplay as i32)
+    })
 }
 
-/// Loads all the bars associated with an output.
-fn load_output_bars(
-    ironbar: &Rc<Ironbar>,
-    app: &Application,
-    output: &OutputInfo,
-) -> Result<Vec<Bar>> {
+/// Gets the GDK monitor for an output.
+fn get_monitor(display: &Display, monitor_name: &str) -> Monitor {
     // Hack to track monitor positions due to new GTK3/wlroots bug:
     // https://github.com/swaywm/sway/issues/8164
     // This relies on Wayland always tracking monitors in the same order as GDK.
     // We also need this static to ensure hot-reloading continues to work as best we can.
     static INDEX_MAP: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
 
-    let output_size = output.logical_size.unwrap_or_default();
-
-    let Some(monitor_name) = &output.name else {
-        return Err(Report::msg("Output missing monitor name"));
-    };
-
     let map = INDEX_MAP.get_or_init(|| Mutex::new(vec![]));
 
     let index = lock!(map).iter().position(|n| n == monitor_name);
     let index = if let Some(index) = index {
         index
     } else {
-        lock!(map).push(monitor_name.clone());
+        lock!(map).push(monitor_name.to_string());
         lock!(map).len() - 1
     };
 
-    let config = ironbar.config.borrow();
-    let display = get_display();
-
     // let pos = output.logical_position.unwrap_or_default();
     // let monitor = display
     //     .monitor_at_point(pos.0, pos.1)
     //     .expect("monitor to exist");
 
-    let monitor = display.monitor(index as i32).expect("monitor to exist");
+    display.monitor(index as i32).expect("monitor to exist")
+}
+
+/// Loads all the bars associated with an output.
+fn load_output_bars(
+    ironbar: &Rc<Ironbar>,
+    app: &Application,
+    output: &OutputInfo,
+) -> Result<Vec<Bar>> {
+    let output_size = output.logical_size.unwrap_or_default();
+
+    let Some(monitor_name) = &output.name else {
+        return Err(Report::msg("Output missing monitor name"));
+    };
+
+    let config = ironbar.config.borrow();
+    let display = get_display();
+
+    let monitor = get_monitor(&display, monitor_name);
 
     let show_default_bar =
         config.bar.start.is_some() || config.bar.center.is_some() || config.bar.end.is_some();
@@ -406,7 +501,7 @@ fn load_output_bars(
                 &monitor,
                 monitor_name.to_string(),
                 output_size,
-                config.clone(),
+                config.as_ref().clone(),
                 ironbar.clone(),
             )?]
         }
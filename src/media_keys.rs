@@ -0,0 +1,126 @@
+use crate::clients::backlight::Backlight;
+use crate::clients::libinput::{Event, MediaKey};
+use crate::clients::volume;
+use crate::config::default_true;
+use crate::{lock, spawn, spawn_blocking, Ironbar};
+use serde::Deserialize;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MediaKeysConfig {
+    /// Whether to handle the volume up, volume down and mute keys.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    volume: bool,
+
+    /// Whether to handle the brightness up and down keys.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    brightness: bool,
+
+    /// The percentage to change the volume by on each key press.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_step")]
+    volume_step: f64,
+
+    /// The percentage to change the brightness by on each key press.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_step")]
+    brightness_step: f64,
+
+    /// The Wayland seat to listen for key presses on.
+    ///
+    /// **Default**: `seat0`
+    #[serde(default = "default_seat")]
+    seat: String,
+}
+
+const fn default_step() -> f64 {
+    5.0
+}
+
+fn default_seat() -> String {
+    String::from("seat0")
+}
+
+/// Starts listening for media key presses,
+/// applying volume and brightness changes directly.
+///
+/// This allows these keys to work without any other tool bound to them.
+/// Changes are picked up by the relevant modules and the OSD as normal.
+pub fn start(ironbar: &Ironbar) {
+    let Some(config) = ironbar.config.borrow().media_keys.clone() else {
+        return;
+    };
+
+    debug!("Starting media key handler");
+
+    let mut clients = ironbar.clients.borrow_mut();
+    let mut rx = clients.libinput(&config.seat).subscribe();
+
+    let volume = config.volume.then(|| clients.volume());
+
+    let backlight = if config.brightness {
+        Backlight::find()
+    } else {
+        None
+    };
+
+    spawn(async move {
+        while let Ok(event) = rx.recv().await {
+            let Event::MediaKey(key) = event else {
+                continue;
+            };
+
+            match key {
+                MediaKey::VolumeUp | MediaKey::VolumeDown | MediaKey::Mute => {
+                    if let Some(client) = volume.clone() {
+                        let step = config.volume_step;
+
+                        // the volume client blocks waiting for the server
+                        spawn_blocking(move || change_volume(&client, key, step));
+                    }
+                }
+                MediaKey::BrightnessUp | MediaKey::BrightnessDown => {
+                    let Some(backlight) = &backlight else {
+                        continue;
+                    };
+
+                    let Some(current) = backlight.brightness() else {
+                        continue;
+                    };
+
+                    let step = config.brightness_step / 100.0;
+                    let brightness = if key == MediaKey::BrightnessUp {
+                        current + step
+                    } else {
+                        current - step
+                    };
+
+                    if let Err(err) = backlight.set_brightness(brightness).await {
+                        error!("Failed to set brightness: {err:?}");
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn change_volume(client: &volume::Client, key: MediaKey, step: f64) {
+    let sinks = client.sinks();
+    let Some(sink) = lock!(sinks).iter().find(|s| s.active).cloned() else {
+        return;
+    };
+
+    match key {
+        MediaKey::VolumeUp => client.set_sink_volume(&sink.name, (sink.volume + step).min(100.0)),
+        MediaKey::VolumeDown => client.set_sink_volume(&sink.name, (sink.volume - step).max(0.0)),
+        MediaKey::Mute => client.set_sink_muted(&sink.name, !sink.muted),
+        MediaKey::BrightnessUp | MediaKey::BrightnessDown => {}
+    }
+}
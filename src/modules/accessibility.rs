@@ -0,0 +1,313 @@
+use crate::clients::compositor::ZoomClient;
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::gio;
+use gtk::prelude::*;
+use gtk::{Button, Orientation};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+/// Settings schema holding the text scale and theme,
+/// which GTK reads its settings from.
+const INTERFACE_SCHEMA: &str = "org.gnome.desktop.interface";
+
+const TEXT_SCALE_KEY: &str = "text-scaling-factor";
+const THEME_KEY: &str = "gtk-theme";
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AccessibilityModule {
+    /// Whether to show the zoom toggle.
+    /// This requires Sway or Hyprland.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    zoom: bool,
+
+    /// Whether to show the large text toggle.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    large_text: bool,
+
+    /// Whether to show the high contrast toggle.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    high_contrast: bool,
+
+    /// The amount to multiply the zoom level by when zoom is enabled.
+    ///
+    /// On Sway, this scales the focused output.
+    /// On Hyprland, this zooms around the cursor.
+    ///
+    /// **Default**: `2`
+    #[serde(default = "default_zoom_factor")]
+    zoom_factor: f64,
+
+    /// The text scaling factor to use when large text is enabled.
+    ///
+    /// **Default**: `1.25`
+    #[serde(default = "default_text_scale")]
+    text_scale: f64,
+
+    /// The GTK theme to use when high contrast is enabled.
+    ///
+    /// **Default**: `HighContrast`
+    #[serde(default = "default_high_contrast_theme")]
+    high_contrast_theme: String,
+
+    /// Toggle button icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_zoom_factor() -> f64 {
+    2.0
+}
+
+const fn default_text_scale() -> f64 {
+    1.25
+}
+
+fn default_high_contrast_theme() -> String {
+    String::from("HighContrast")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon for the zoom toggle.
+    ///
+    /// **Default**: `󰛭`
+    #[serde(default = "default_icon_zoom")]
+    zoom: String,
+
+    /// Icon for the large text toggle.
+    ///
+    /// **Default**: `󰉔`
+    #[serde(default = "default_icon_large_text")]
+    large_text: String,
+
+    /// Icon for the high contrast toggle.
+    ///
+    /// **Default**: `󰆅`
+    #[serde(default = "default_icon_high_contrast")]
+    high_contrast: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            zoom: default_icon_zoom(),
+            large_text: default_icon_large_text(),
+            high_contrast: default_icon_high_contrast(),
+        }
+    }
+}
+
+fn default_icon_zoom() -> String {
+    String::from("󰛭")
+}
+
+fn default_icon_large_text() -> String {
+    String::from("󰉔")
+}
+
+fn default_icon_high_contrast() -> String {
+    String::from("󰆅")
+}
+
+/// Gets the GTK interface settings,
+/// if the schema is installed.
+fn interface_settings() -> Option<gio::Settings> {
+    gio::SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(INTERFACE_SCHEMA, true))
+        .map(|_| gio::Settings::new(INTERFACE_SCHEMA))
+}
+
+/// Adds the `active` class to `button` if `active` is true,
+/// or removes it otherwise.
+fn set_active(button: &Button, active: bool) {
+    if active {
+        button.add_class("active");
+    } else {
+        button.remove_class("active");
+    }
+}
+
+impl Module<gtk::Box> for AccessibilityModule {
+    /// Whether zoom is enabled.
+    type SendMessage = bool;
+    /// Toggles zoom.
+    type ReceiveMessage = ();
+
+    module_impl!("accessibility");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        if !self.zoom {
+            return Ok(());
+        }
+
+        let client = match context.try_client::<dyn ZoomClient>() {
+            Ok(client) => client,
+            Err(err) => {
+                warn!("Zoom is not available: {err:?}");
+                return Ok(());
+            }
+        };
+
+        let factor = self.zoom_factor;
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            // the zoom level from before zoom was enabled,
+            // which is restored when it is disabled
+            let mut normal = None;
+
+            while let Some(()) = rx.recv().await {
+                let result = match normal {
+                    Some(level) => client.set_zoom(level).map(|()| None),
+                    None => client
+                        .zoom()
+                        .and_then(|level| client.set_zoom(level * factor).map(|()| Some(level))),
+                };
+
+                match result {
+                    Ok(level) => {
+                        normal = level;
+                        send_async!(tx, ModuleUpdateEvent::Update(normal.is_some()));
+                    }
+                    Err(err) => error!("Failed to toggle zoom: {err:?}"),
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+
+        if self.zoom && context.try_client::<dyn ZoomClient>().is_ok() {
+            let button = Button::with_label(&self.icons.zoom);
+            button.add_class("zoom");
+            button.set_tooltip_text(Some("Zoom"));
+            container.add(&button);
+
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, ());
+            });
+
+            glib_recv!(context.subscribe(), enabled => set_active(&button, enabled));
+        }
+
+        let settings = interface_settings();
+        if settings.is_none() && (self.large_text || self.high_contrast) {
+            warn!("Schema '{INTERFACE_SCHEMA}' is not installed, so only zoom is available");
+        }
+
+        if let Some(settings) = settings.as_ref().filter(|_| self.large_text) {
+            let button = Button::with_label(&self.icons.large_text);
+            button.add_class("large-text");
+            button.set_tooltip_text(Some("Large text"));
+            container.add(&button);
+
+            let scale = self.text_scale;
+            let is_active =
+                move |settings: &gio::Settings| settings.double(TEXT_SCALE_KEY) >= scale;
+
+            set_active(&button, is_active(settings));
+
+            {
+                let settings = settings.clone();
+                button.connect_clicked(move |_| {
+                    let result = if is_active(&settings) {
+                        settings.reset(TEXT_SCALE_KEY);
+                        Ok(())
+                    } else {
+                        settings.set_double(TEXT_SCALE_KEY, scale)
+                    };
+
+                    if let Err(err) = result {
+                        error!("Failed to set text scale: {err:?}");
+                    }
+                });
+            }
+
+            settings.connect_changed(Some(TEXT_SCALE_KEY), move |settings, _| {
+                set_active(&button, is_active(settings));
+            });
+        }
+
+        if let Some(settings) = settings.as_ref().filter(|_| self.high_contrast) {
+            let button = Button::with_label(&self.icons.high_contrast);
+            button.add_class("high-contrast");
+            button.set_tooltip_text(Some("High contrast"));
+            container.add(&button);
+
+            let theme = self.high_contrast_theme.clone();
+            let is_active =
+                move |settings: &gio::Settings| settings.string(THEME_KEY).as_str() == theme;
+
+            set_active(&button, is_active(settings));
+
+            {
+                let settings = settings.clone();
+                let theme = self.high_contrast_theme.clone();
+                let is_active = is_active.clone();
+
+                // the theme from before high contrast was enabled,
+                // which is restored when it is disabled
+                let previous: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+                button.connect_clicked(move |_| {
+                    let result = if is_active(&settings) {
+                        match previous.take() {
+                            Some(previous) => settings.set_string(THEME_KEY, previous.as_str()),
+                            None => {
+                                settings.reset(THEME_KEY);
+                                Ok(())
+                            }
+                        }
+                    } else {
+                        previous.replace(Some(settings.string(THEME_KEY).to_string()));
+                        settings.set_string(THEME_KEY, theme.as_str())
+                    };
+
+                    if let Err(err) = result {
+                        error!("Failed to set theme: {err:?}");
+                    }
+                });
+            }
+
+            settings.connect_changed(Some(THEME_KEY), move |settings, _| {
+                set_active(&button, is_active(settings));
+            });
+        }
+
+        Ok(ModuleParts::new(container, None))
+    }
+}
@@ -0,0 +1,381 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::polling;
+use crate::script::{OutputStream, Script};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use chrono::{DateTime, Local, NaiveDateTime, TimeDelta, TimeZone};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Source {
+    /// Queries a restic repository using the `restic` CLI.
+    Restic {
+        /// The repository to query.
+        /// If not set, `$RESTIC_REPOSITORY` is used.
+        ///
+        /// **Default**: `null`
+        repository: Option<String>,
+        /// A file containing the repository password.
+        /// If not set, `$RESTIC_PASSWORD_FILE` or `$RESTIC_PASSWORD_COMMAND` is used.
+        ///
+        /// **Default**: `null`
+        password_file: Option<String>,
+    },
+    /// Queries a borg repository using the `borg` CLI.
+    Borg {
+        /// The repository to query.
+        /// If not set, `$BORG_REPO` is used.
+        ///
+        /// **Default**: `null`
+        repository: Option<String>,
+    },
+    /// Reads the time of the last backup from a file.
+    File {
+        /// Path to a file containing a Unix timestamp or RFC 3339 date.
+        /// If the file is empty, its modification time is used.
+        path: PathBuf,
+    },
+}
+
+impl Source {
+    /// Gets the time of the latest backup,
+    /// or `None` if there are no backups yet.
+    async fn latest(&self) -> Result<Option<DateTime<Local>>> {
+        match self {
+            Self::Restic {
+                repository,
+                password_file,
+            } => {
+                let mut command = Command::new("restic");
+
+                if let Some(repository) = repository {
+                    command.args(["--repo", repository]);
+                }
+
+                if let Some(password_file) = password_file {
+                    command.args(["--password-file", password_file]);
+                }
+
+                command.args(["snapshots", "--latest", "1", "--json", "--no-lock"]);
+
+                let output = run(command).await?;
+                let snapshots = serde_json::from_str::<Vec<serde_json::Value>>(&output)?;
+
+                // the latest snapshot is returned for each host and set of paths
+                let mut latest = None;
+                for snapshot in snapshots {
+                    let Some(time) = snapshot.get("time").and_then(serde_json::Value::as_str)
+                    else {
+                        continue;
+                    };
+
+                    let time = DateTime::parse_from_rfc3339(time)?.with_timezone(&Local);
+                    latest = latest.max(Some(time));
+                }
+
+                Ok(latest)
+            }
+            Self::Borg { repository } => {
+                let mut command = Command::new("borg");
+                command.args(["list", "--last", "1", "--json"]);
+
+                if let Some(repository) = repository {
+                    command.arg(repository);
+                }
+
+                let output = run(command).await?;
+                let list = serde_json::from_str::<serde_json::Value>(&output)?;
+
+                let Some(time) = list
+                    .get("archives")
+                    .and_then(|archives| archives.get(0))
+                    .and_then(|archive| archive.get("time"))
+                    .and_then(serde_json::Value::as_str)
+                else {
+                    return Ok(None);
+                };
+
+                // borg uses the local time, without an offset
+                let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.f")?;
+                Ok(Local.from_local_datetime(&time).earliest())
+            }
+            Self::File { path } => {
+                let contents = fs::read_to_string(path)?;
+                let contents = contents.trim();
+
+                if contents.is_empty() {
+                    let modified = fs::metadata(path)?.modified()?;
+                    return Ok(Some(DateTime::<Local>::from(modified)));
+                }
+
+                if let Ok(timestamp) = contents.parse::<i64>() {
+                    return Ok(DateTime::from_timestamp(timestamp, 0)
+                        .map(|time| time.with_timezone(&Local)));
+                }
+
+                Ok(Some(
+                    DateTime::parse_from_rfc3339(contents)?.with_timezone(&Local),
+                ))
+            }
+        }
+    }
+}
+
+/// Runs `command`, returning its stdout if it succeeds
+/// or its stderr as the error if not.
+async fn run(mut command: Command) -> Result<String> {
+    let output = command.stdin(Stdio::null()).output().await?;
+
+    if output.status.success() {
+        Ok(String::from_utf8(output.stdout)?)
+    } else {
+        Err(eyre!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BackupModule {
+    /// Where to get the time of the last backup from.
+    /// See [sources](#sources).
+    ///
+    /// **Required**
+    source: Source,
+
+    /// Number of milliseconds to wait between checks.
+    ///
+    /// **Default**: `300000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// Number of hours after the last backup before it is considered stale.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_max_age")]
+    max_age: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰁯 {age}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format to use for the `{time}` token.
+    /// See [chrono's documentation](https://docs.rs/chrono/latest/chrono/format/strftime/index.html) for the syntax.
+    ///
+    /// **Default**: `%d/%m %H:%M`
+    #[serde(default = "default_time_format")]
+    time_format: String,
+
+    /// Command to run when the widget is clicked, to start a backup.
+    /// The last backup is checked again once it exits.
+    ///
+    /// **Default**: `null`
+    backup_command: Option<String>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    300_000
+}
+
+const fn default_max_age() -> u64 {
+    24
+}
+
+fn default_format() -> String {
+    String::from("󰁯 {age}")
+}
+
+fn default_time_format() -> String {
+    String::from("%d/%m %H:%M")
+}
+
+#[derive(Debug, Clone)]
+pub enum BackupEvent {
+    /// The time of the latest backup, if there is one.
+    Latest(Option<DateTime<Local>>),
+    /// The backup command is running.
+    Running,
+    /// The latest backup could not be checked.
+    Error(String),
+}
+
+impl Module<Button> for BackupModule {
+    type SendMessage = BackupEvent;
+    type ReceiveMessage = ();
+
+    module_impl!("backup");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let source = self.source.clone();
+        let backup_command = self.backup_command.clone();
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            loop {
+                let event = match source.latest().await {
+                    Ok(latest) => BackupEvent::Latest(latest),
+                    Err(err) => {
+                        error!("Failed to get latest backup: {err:?}");
+                        BackupEvent::Error(err.to_string())
+                    }
+                };
+
+                send_async!(tx, ModuleUpdateEvent::Update(event));
+
+                tokio::select! {
+                    () = polling::sleep(interval) => {}
+                    Some(()) = rx.recv() => {
+                        let Some(command) = &backup_command else {
+                            continue;
+                        };
+
+                        debug!("Starting backup");
+                        send_async!(tx, ModuleUpdateEvent::Update(BackupEvent::Running));
+
+                        match Script::from(command.as_str()).get_output(None).await {
+                            Ok((OutputStream::Stderr(err), _)) => error!("Backup command failed: {err}"),
+                            Err(err) => error!("{err:?}"),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        if self.backup_command.is_some() {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, ());
+            });
+        }
+
+        {
+            let button = button.clone();
+            let max_age = i64::try_from(self.max_age)
+                .ok()
+                .and_then(TimeDelta::try_hours)
+                .unwrap_or(TimeDelta::MAX);
+
+            let mut latest = None;
+
+            glib_recv!(context.subscribe(), event => {
+                button.remove_class("error");
+                button.remove_class("running");
+                button.remove_class("stale");
+
+                match &event {
+                    BackupEvent::Latest(time) => latest = Some(*time),
+                    BackupEvent::Running => button.add_class("running"),
+                    BackupEvent::Error(err) => {
+                        button.add_class("error");
+                        button.set_tooltip_text(Some(err));
+                    }
+                }
+
+                // keep showing the last known backup while running or on error
+                let Some(time) = latest else {
+                    label.set_label_escaped(&self.format.replace("{age}", "?").replace("{time}", "?"));
+                    continue;
+                };
+
+                let (age, formatted_time) = match time {
+                    Some(time) => {
+                        let age = Local::now() - time;
+                        if age > max_age {
+                            button.add_class("stale");
+                        }
+
+                        (format_age(age), time.format(&self.time_format).to_string())
+                    }
+                    None => {
+                        button.add_class("stale");
+                        (String::from("never"), String::from("never"))
+                    }
+                };
+
+                if matches!(event, BackupEvent::Latest(_)) {
+                    button.set_tooltip_text(Some(&format!("Last backup: {formatted_time}")));
+                }
+
+                label.set_label_escaped(
+                    &self
+                        .format
+                        .replace("{age}", &age)
+                        .replace("{time}", &formatted_time),
+                );
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: button,
+            popup: None,
+        })
+    }
+}
+
+/// Formats the age of a backup using its largest unit, such as `3h`.
+fn format_age(age: TimeDelta) -> String {
+    if age.num_hours() >= 48 {
+        format!("{}d", age.num_days())
+    } else if age.num_minutes() >= 60 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes().max(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(TimeDelta::seconds(-5)), "0m");
+        assert_eq!(format_age(TimeDelta::minutes(45)), "45m");
+        assert_eq!(format_age(TimeDelta::minutes(90)), "1h");
+        assert_eq!(format_age(TimeDelta::hours(47)), "47h");
+        assert_eq!(format_age(TimeDelta::hours(50)), "2d");
+    }
+}
@@ -0,0 +1,415 @@
+use crate::clients::bluetooth::{Client, Device, State};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, spawn, text, try_send};
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{Align, Button, IconSize, Image, Label, Orientation, Switch};
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BluetoothModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Adapter state icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{icon}")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show when the adapter is powered off.
+    ///
+    /// **Default**: `󰂲`
+    #[serde(default = "default_icon_off")]
+    off: String,
+
+    /// Icon to show when the adapter is powered on,
+    /// but no devices are connected.
+    ///
+    /// **Default**: `󰂯`
+    #[serde(default = "default_icon_on")]
+    on: String,
+
+    /// Icon to show when at least one device is connected.
+    ///
+    /// **Default**: `󰂱`
+    #[serde(default = "default_icon_connected")]
+    connected: String,
+}
+
+impl Icons {
+    fn state_icon(&self, state: &State) -> &str {
+        if !state.powered {
+            &self.off
+        } else if state.devices.iter().any(|device| device.connected) {
+            &self.connected
+        } else {
+            &self.on
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            off: default_icon_off(),
+            on: default_icon_on(),
+            connected: default_icon_connected(),
+        }
+    }
+}
+
+fn default_icon_off() -> String {
+    String::from("󰂲")
+}
+
+fn default_icon_on() -> String {
+    String::from("󰂯")
+}
+
+fn default_icon_connected() -> String {
+    String::from("󰂱")
+}
+
+#[derive(Debug, Clone)]
+pub enum BluetoothCommand {
+    /// Powers the adapter at the path on or off.
+    SetPowered(String, bool),
+    Connect(String),
+    Disconnect(String),
+    SetTrusted(String, bool),
+}
+
+impl Module<Button> for BluetoothModule {
+    /// The current adapter state,
+    /// or `None` if `BlueZ` or an adapter is unavailable.
+    type SendMessage = Option<State>;
+    type ReceiveMessage = BluetoothCommand;
+
+    module_impl!("bluetooth");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let client = match Client::new().await {
+                Ok(client) => client,
+                Err(err) => {
+                    error!("Failed to connect to D-Bus: {err:?}");
+                    send_async!(tx, ModuleUpdateEvent::Update(None));
+                    return;
+                }
+            };
+
+            let mut changes = match client.changes().await {
+                Ok(changes) => changes,
+                Err(err) => {
+                    error!("Failed to listen for BlueZ signals: {err:?}");
+                    send_async!(tx, ModuleUpdateEvent::Update(None));
+                    return;
+                }
+            };
+
+            let mut last = None;
+
+            loop {
+                let state = match client.state().await {
+                    Ok(state) => state,
+                    Err(err) => {
+                        debug!("BlueZ unavailable: {err:?}");
+                        None
+                    }
+                };
+
+                // signals such as signal strength changes do not affect the state
+                if last.as_ref() != Some(&state) {
+                    last = Some(state.clone());
+                    send_async!(tx, ModuleUpdateEvent::Update(state));
+                }
+
+                tokio::select! {
+                    Some(_) = changes.next() => {}
+                    Some(command) = rx.recv() => {
+                        let res = match &command {
+                            BluetoothCommand::SetPowered(adapter, powered) => {
+                                client.set_powered(adapter, *powered).await
+                            }
+                            BluetoothCommand::Connect(device) => client.connect(device).await,
+                            BluetoothCommand::Disconnect(device) => client.disconnect(device).await,
+                            BluetoothCommand::SetTrusted(device, trusted) => {
+                                client.set_trusted(device, *trusted).await
+                            }
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to run {command:?}: {err:?}");
+                        }
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let icons = self.icons.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, state => {
+                let Some(state) = state else {
+                    button.hide();
+                    continue;
+                };
+
+                let connected = state
+                    .devices
+                    .iter()
+                    .filter(|device| device.connected)
+                    .collect::<Vec<_>>();
+
+                let text = format
+                    .replace("{icon}", icons.state_icon(&state))
+                    .replace("{connected}", &connected.len().to_string())
+                    .replace(
+                        "{device}",
+                        &text::isolate(connected.first().map_or("", |device| device.name.as_str())),
+                    );
+
+                label.set_label_escaped(&text);
+
+                button.remove_class("off");
+                button.remove_class("connected");
+
+                if !state.powered {
+                    button.add_class("off");
+                } else if !connected.is_empty() {
+                    button.add_class("connected");
+                }
+
+                let tooltip = connected
+                    .iter()
+                    .map(|device| match device.battery {
+                        Some(battery) => format!("{} ({battery}%)", device.name),
+                        None => device.name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                if tooltip.is_empty() {
+                    button.set_tooltip_text(None);
+                } else {
+                    button.set_tooltip_text(Some(&tooltip));
+                }
+
+                button.show();
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 10);
+        header.add_class("header");
+
+        let title = Label::builder()
+            .label("Bluetooth")
+            .halign(Align::Start)
+            .hexpand(true)
+            .build();
+        title.add_class("title");
+
+        let power = Switch::builder().valign(Align::Center).build();
+        power.add_class("power");
+
+        header.add(&title);
+        header.add(&power);
+        container.add(&header);
+
+        let devices = gtk::Box::new(Orientation::Vertical, 5);
+        devices.add_class("devices");
+        container.add(&devices);
+
+        // the adapter path and its last known power state,
+        // used to ignore switch changes made when updating it
+        let adapter = Rc::new(RefCell::new(String::new()));
+        let powered = Rc::new(Cell::new(false));
+
+        {
+            let tx = tx.clone();
+            let adapter = adapter.clone();
+            let powered = powered.clone();
+
+            power.connect_state_set(move |_, state| {
+                if state != powered.get() {
+                    powered.set(state);
+                    try_send!(
+                        tx,
+                        BluetoothCommand::SetPowered(adapter.borrow().clone(), state)
+                    );
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        glib_recv!(rx, state => {
+            for child in devices.children() {
+                devices.remove(&child);
+            }
+
+            let Some(state) = state else {
+                continue;
+            };
+
+            adapter.replace(state.adapter.clone());
+            powered.set(state.powered);
+            power.set_active(state.powered);
+
+            devices.set_sensitive(state.powered);
+
+            for device in state.devices {
+                devices.add(&device_row(device, &tx));
+            }
+
+            devices.show_all();
+        });
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for a paired device.
+fn device_row(device: Device, tx: &mpsc::Sender<BluetoothCommand>) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_class("device");
+
+    if device.connected {
+        row.add_class("connected");
+    }
+
+    if let Some(icon) = &device.icon {
+        let image = Image::from_icon_name(Some(icon), IconSize::Button);
+        image.add_class("icon");
+        row.add(&image);
+    }
+
+    let name = Label::builder()
+        .label(&device.name)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    name.add_class("name");
+    row.add(&name);
+
+    if let Some(battery) = device.battery {
+        let label = Label::new(Some(&format!("{battery}%")));
+        label.add_class("battery");
+        row.add(&label);
+    }
+
+    let (action, command) = if device.connected {
+        (
+            "Disconnect",
+            BluetoothCommand::Disconnect(device.path.clone()),
+        )
+    } else {
+        ("Connect", BluetoothCommand::Connect(device.path.clone()))
+    };
+
+    let connect = Button::with_label(action);
+    connect.add_class("connect");
+
+    {
+        let tx = tx.clone();
+        connect.connect_clicked(move |_| {
+            try_send!(tx, command.clone());
+        });
+    }
+
+    let trust = Button::with_label(if device.trusted { "Untrust" } else { "Trust" });
+    trust.add_class("trust");
+
+    {
+        let tx = tx.clone();
+        let command = BluetoothCommand::SetTrusted(device.path, !device.trusted);
+        trust.connect_clicked(move |_| {
+            try_send!(tx, command.clone());
+        });
+    }
+
+    row.add(&connect);
+    row.add(&trust);
+
+    row
+}
@@ -0,0 +1,404 @@
+use crate::animation::AnimatedValue;
+use crate::clients::backlight::Backlight;
+use crate::clients::ddc;
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::{EventMask, ScrollDirection};
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation, Scale};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BrightnessModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {percentage}%`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The icon to show in the `{icon}` token.
+    ///
+    /// **Default**: `󰃠`
+    #[serde(default = "default_icon")]
+    icon: String,
+
+    /// Whether to control external displays over DDC/CI.
+    /// This requires `ddcutil` to be installed,
+    /// and the user to have access to the `/dev/i2c-*` devices.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    ddc: bool,
+
+    /// The number of milliseconds between checks for brightness changes.
+    ///
+    /// **Default**: `5000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The percentage to change the brightness by
+    /// when scrolling over the widget button.
+    /// Set to `0` to disable.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_scroll_step")]
+    scroll_step: f64,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{icon} {percentage}%")
+}
+
+fn default_icon() -> String {
+    String::from("󰃠")
+}
+
+const fn default_scroll_step() -> f64 {
+    5.0
+}
+
+const fn default_interval() -> u64 {
+    5000
+}
+
+/// A display whose brightness can be controlled.
+#[derive(Debug, Clone)]
+enum Target {
+    /// The built-in display's backlight.
+    Backlight(Backlight),
+    /// An external display, controlled over DDC/CI.
+    Ddc(ddc::Display),
+}
+
+impl Target {
+    fn id(&self) -> String {
+        match self {
+            Self::Backlight(backlight) => format!("backlight:{}", backlight.name()),
+            Self::Ddc(display) => format!("ddc:{}", display.bus),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Self::Backlight(_) => String::from("Built-in display"),
+            Self::Ddc(display) => display.output.as_ref().map_or_else(
+                || display.model.clone(),
+                |output| format!("{} ({output})", display.model),
+            ),
+        }
+    }
+
+    async fn brightness(&self, ddc: Option<&ddc::Client>) -> Option<f64> {
+        match (self, ddc) {
+            (Self::Backlight(backlight), _) => backlight.brightness(),
+            (Self::Ddc(display), Some(ddc)) => match ddc.brightness(display).await {
+                Ok(brightness) => Some(brightness),
+                Err(err) => {
+                    error!("{err:?}");
+                    None
+                }
+            },
+            (Self::Ddc(_), None) => None,
+        }
+    }
+
+    async fn set_brightness(&self, ddc: Option<&ddc::Client>, fraction: f64) -> Result<()> {
+        match (self, ddc) {
+            (Self::Backlight(backlight), _) => backlight.set_brightness(fraction).await,
+            (Self::Ddc(display), Some(ddc)) => ddc.set_brightness(display, fraction).await,
+            (Self::Ddc(_), None) => Ok(()),
+        }
+    }
+}
+
+/// Finds the built-in backlight and any external displays.
+async fn find_targets(ddc: Option<&ddc::Client>) -> Vec<Target> {
+    let mut targets = Backlight::find()
+        .map(Target::Backlight)
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    if let Some(ddc) = ddc {
+        targets.extend(ddc.displays().await.iter().cloned().map(Target::Ddc));
+    }
+
+    targets
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayBrightness {
+    id: String,
+    name: String,
+    /// The brightness as a fraction of its maximum.
+    brightness: f64,
+}
+
+impl DisplayBrightness {
+    fn percentage(&self) -> f64 {
+        (self.brightness * 100.0).round()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BrightnessCommand {
+    /// Sets the brightness of the display with the given ID,
+    /// as a fraction of its maximum.
+    Set(String, f64),
+    /// Changes the brightness of the display shown on the widget
+    /// by the given percentage.
+    Step(f64),
+}
+
+impl Module<Button> for BrightnessModule {
+    type SendMessage = Vec<DisplayBrightness>;
+    type ReceiveMessage = BrightnessCommand;
+
+    module_impl!("brightness");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let ddc = self.ddc.then(|| context.client::<ddc::Client>());
+        let interval = Duration::from_millis(self.interval);
+
+        spawn(async move {
+            let ddc = ddc.as_deref();
+            let mut interval = polling::interval(interval);
+            let mut last = None;
+
+            loop {
+                // the client caches displays until outputs change,
+                // so this only probes for displays when required.
+                let targets = find_targets(ddc).await;
+
+                tokio::select! {
+                    () = interval.tick() => {}
+                    Some(command) = rx.recv() => {
+                        let change = match command {
+                            BrightnessCommand::Set(id, fraction) => targets
+                                .iter()
+                                .find(|target| target.id() == id)
+                                .map(|target| (target, fraction)),
+                            BrightnessCommand::Step(delta) => match targets.first() {
+                                Some(target) => target.brightness(ddc).await.map(|brightness| {
+                                    (target, (brightness + delta / 100.0).clamp(0.0, 1.0))
+                                }),
+                                None => None,
+                            },
+                        };
+
+                        if let Some((target, fraction)) = change {
+                            if let Err(err) = target.set_brightness(ddc, fraction).await {
+                                error!("{err:?}");
+                            }
+                        }
+                    }
+                }
+
+                let mut displays = vec![];
+                for target in &targets {
+                    if let Some(brightness) = target.brightness(ddc).await {
+                        displays.push(DisplayBrightness {
+                            id: target.id(),
+                            name: target.name(),
+                            brightness,
+                        });
+                    }
+                }
+
+                if last.as_ref() != Some(&displays) {
+                    last = Some(displays.clone());
+                    send_async!(tx, ModuleUpdateEvent::Update(displays));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.set_no_show_all(true);
+
+        {
+            let tx = context.tx.clone();
+
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        if self.scroll_step > 0.0 {
+            let tx = context.controller_tx.clone();
+            let step = self.scroll_step;
+
+            button.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+            button.connect_scroll_event(move |_, event| {
+                let delta = match event.direction() {
+                    ScrollDirection::Up => step,
+                    ScrollDirection::Down => -step,
+                    ScrollDirection::Smooth => {
+                        if event.scroll_deltas().unwrap_or_default().1 > 0.0 {
+                            -step
+                        } else {
+                            step
+                        }
+                    }
+                    _ => return Propagation::Proceed,
+                };
+
+                try_send!(tx, BrightnessCommand::Step(delta));
+                Propagation::Stop
+            });
+        }
+
+        {
+            let rx = context.subscribe();
+            let button = button.clone();
+            let format = self.format.clone();
+            let icon = self.icon.clone();
+
+            glib_recv!(rx, displays => {
+                // the built-in display is always first if there is one
+                if let Some(display) = displays.first() {
+                    let label = format
+                        .replace("{icon}", &icon)
+                        .replace("{percentage}", &display.percentage().to_string())
+                        .replace("{name}", &display.name);
+
+                    button.set_label(&label);
+                    button.show();
+                } else {
+                    button.hide();
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        // set while a slider is being dragged,
+        // so that it is not moved back by a pending update.
+        let dragging = Rc::new(Cell::new(false));
+        let mut sliders: Vec<(String, AnimatedValue<Scale>)> = vec![];
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, displays => {
+                let changed = sliders.len() != displays.len()
+                    || sliders.iter().zip(&displays).any(|((id, _), display)| id != &display.id);
+
+                if changed {
+                    container.foreach(|child| container.remove(child));
+                    sliders.clear();
+
+                    for display in &displays {
+                        let (row, slider) = make_display_row(display, &tx, &dragging);
+                        container.add(&row);
+                        sliders.push((display.id.clone(), AnimatedValue::new(&slider)));
+                    }
+
+                    container.show_all();
+                } else if !dragging.get() {
+                    for ((_, slider), display) in sliders.iter().zip(&displays) {
+                        slider.set(display.percentage());
+                    }
+                }
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for a display,
+/// containing its name and a brightness slider.
+fn make_display_row(
+    display: &DisplayBrightness,
+    tx: &mpsc::Sender<BrightnessCommand>,
+    dragging: &Rc<Cell<bool>>,
+) -> (gtk::Box, Scale) {
+    let row = gtk::Box::new(Orientation::Vertical, 0);
+    row.add_class("display");
+
+    let label = Label::new(Some(&display.name));
+    label.add_class("name");
+    row.add(&label);
+
+    let slider = Scale::with_range(Orientation::Horizontal, 0.0, 100.0, 1.0);
+    slider.add_class("slider");
+    slider.set_value(display.percentage());
+    row.add(&slider);
+
+    {
+        let dragging = dragging.clone();
+        slider.connect_button_press_event(move |_, _| {
+            dragging.set(true);
+            Propagation::Proceed
+        });
+    }
+
+    {
+        let id = display.id.clone();
+        let tx = tx.clone();
+        let dragging = dragging.clone();
+
+        // DDC/CI writes are slow, so only set the brightness once the slider is released.
+        slider.connect_button_release_event(move |scale, _| {
+            dragging.set(false);
+
+            // GTK will send values outside min/max range
+            let value = scale.value().clamp(0.0, 100.0);
+            try_send!(tx, BrightnessCommand::Set(id.clone(), value / 100.0));
+
+            Propagation::Proceed
+        });
+    }
+
+    (row, slider)
+}
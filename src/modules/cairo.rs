@@ -14,7 +14,6 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::Duration;
 use tokio::sync::mpsc::Receiver;
-use tokio::time::sleep;
 use tracing::{debug, error};
 
 #[derive(Debug, Clone, Deserialize)]
@@ -101,9 +100,7 @@ impl Module<gtk::Box> for CairoModule {
                 .expect("Failed to start lua file watcher");
 
             // avoid watcher from dropping
-            loop {
-                sleep(Duration::from_secs(1)).await;
-            }
+            std::future::pending::<()>().await;
         });
 
         // Lua needs to run synchronously with the GTK updates,
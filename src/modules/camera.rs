@@ -0,0 +1,247 @@
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, try_send};
+use color_eyre::Result;
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation};
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CameraModule {
+    /// The icon to show on the widget.
+    ///
+    /// **Default**: `󰄀`
+    #[serde(default = "default_icon")]
+    icon: String,
+
+    /// The name or serial of the PipeWire camera node to preview.
+    /// If not set, the default camera is used.
+    ///
+    /// **Default**: `null`
+    device: Option<String>,
+
+    /// The width of the preview, in pixels.
+    ///
+    /// **Default**: `320`
+    #[serde(default = "default_width")]
+    width: i32,
+
+    /// The height of the preview, in pixels.
+    ///
+    /// **Default**: `240`
+    #[serde(default = "default_height")]
+    height: i32,
+
+    /// Whether to flip the preview horizontally,
+    /// so it behaves like a mirror.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    mirror: bool,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon() -> String {
+    String::from("󰄀")
+}
+
+const fn default_width() -> i32 {
+    320
+}
+
+const fn default_height() -> i32 {
+    240
+}
+
+impl CameraModule {
+    /// Creates the preview pipeline and its output widget.
+    ///
+    /// The pipeline is created in the `Null` state,
+    /// so the camera is not opened until it is started.
+    fn create_pipeline(&self) -> Result<(gst::Pipeline, gtk::Widget)> {
+        gst::init()?;
+
+        let pipeline = gst::Pipeline::new();
+
+        let source = gst::ElementFactory::make("pipewiresrc").build()?;
+        if let Some(device) = &self.device {
+            source.set_property("target-object", device);
+        }
+
+        let convert = gst::ElementFactory::make("videoconvert").build()?;
+
+        // scale down early, before frames reach the sink
+        let scale = gst::ElementFactory::make("videoscale").build()?;
+        let caps = gst::Caps::builder("video/x-raw")
+            .field("width", self.width)
+            .field("height", self.height)
+            .build();
+        let filter = gst::ElementFactory::make("capsfilter")
+            .property("caps", caps)
+            .build()?;
+
+        let flip = gst::ElementFactory::make("videoflip")
+            .property_from_str(
+                "method",
+                if self.mirror {
+                    "horizontal-flip"
+                } else {
+                    "none"
+                },
+            )
+            .build()?;
+
+        let sink = gst::ElementFactory::make("gtksink").build()?;
+
+        let elements = [&source, &convert, &scale, &filter, &flip, &sink];
+        pipeline.add_many(elements)?;
+        gst::Element::link_many(elements)?;
+
+        let widget = sink.property::<gtk::Widget>("widget");
+        widget.set_size_request(self.width, self.height);
+
+        Ok((pipeline, widget))
+    }
+}
+
+impl Module<Button> for CameraModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("camera");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let icon = Label::new(Some(&self.icon));
+        icon.add_class("icon");
+        button.add(&icon);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        _rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let status = Label::new(Some("Camera unavailable"));
+        status.add_class("status");
+        container.add(&status);
+
+        let (pipeline, preview) = match self.create_pipeline() {
+            Ok(parts) => parts,
+            Err(err) => {
+                error!("Failed to create camera pipeline: {err:?}");
+                container.show_all();
+                return Some(container);
+            }
+        };
+
+        preview.add_class("preview");
+        container.add(&preview);
+
+        container.show_all();
+        status.hide();
+
+        let bus = pipeline.bus().expect("pipeline to have a bus");
+
+        let watch = {
+            let pipeline = pipeline.clone();
+            let preview = preview.clone();
+            let status = status.clone();
+
+            bus.add_watch_local(move |_, message| {
+                if let gst::MessageView::Error(err) = message.view() {
+                    error!("Camera error: {} ({:?})", err.error(), err.debug());
+
+                    let _ = pipeline.set_state(gst::State::Null);
+                    preview.hide();
+                    status.show();
+                }
+
+                glib::ControlFlow::Continue
+            })
+        };
+
+        match watch {
+            // the watch is removed when the guard is dropped
+            Ok(watch) => {
+                container.connect_destroy(move |_| {
+                    let _ = &watch;
+                });
+            }
+            Err(err) => error!("Failed to watch camera pipeline: {err:?}"),
+        }
+
+        // the popup container is only mapped while the popup is open,
+        // so the camera is only in use while the preview is visible.
+        {
+            let pipeline = pipeline.clone();
+
+            container.connect_map(move |_| {
+                debug!("Starting camera preview");
+
+                status.hide();
+                preview.show();
+
+                if let Err(err) = pipeline.set_state(gst::State::Playing) {
+                    error!("Failed to start camera preview: {err:?}");
+                }
+            });
+        }
+
+        container.connect_unmap(move |_| {
+            debug!("Stopping camera preview");
+
+            if let Err(err) = pipeline.set_state(gst::State::Null) {
+                error!("Failed to stop camera preview: {err:?}");
+            }
+        });
+
+        Some(container)
+    }
+}
@@ -0,0 +1,365 @@
+use crate::clients::chat::{matrix, weechat, Account, Room};
+use crate::clients::wayland;
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AccountConfig {
+    /// A WeeChat instance, using the relay `api` protocol.
+    #[serde(rename = "weechat")]
+    WeeChat {
+        /// The name to show in the popup.
+        ///
+        /// **Default**: `WeeChat`
+        name: Option<String>,
+        /// Base URL of the relay, such as `http://localhost:9000`.
+        url: String,
+        /// The relay password.
+        ///
+        /// **Default**: `null`
+        password: Option<String>,
+        /// The app ID or title of the window to focus when a room is clicked.
+        ///
+        /// **Default**: `null`
+        window: Option<String>,
+    },
+    /// A Matrix account.
+    Matrix {
+        /// The name to show in the popup.
+        ///
+        /// **Default**: `Matrix`
+        name: Option<String>,
+        /// Base URL of the homeserver, such as `https://matrix.org`.
+        homeserver: String,
+        /// An access token for the account.
+        token: String,
+        /// The app ID or title of the window to focus when a room is clicked.
+        ///
+        /// **Default**: `null`
+        window: Option<String>,
+    },
+}
+
+impl AccountConfig {
+    fn create(&self) -> (String, Account) {
+        match self.clone() {
+            Self::WeeChat {
+                name,
+                url,
+                password,
+                ..
+            } => (
+                name.unwrap_or_else(|| String::from("WeeChat")),
+                Account::WeeChat(weechat::Account::new(&url, password)),
+            ),
+            Self::Matrix {
+                name,
+                homeserver,
+                token,
+                ..
+            } => (
+                name.unwrap_or_else(|| String::from("Matrix")),
+                Account::Matrix(matrix::Account::new(&homeserver, token)),
+            ),
+        }
+    }
+
+    fn window(&self) -> Option<&str> {
+        match self {
+            Self::WeeChat { window, .. } | Self::Matrix { window, .. } => window.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChatModule {
+    /// The accounts to watch.
+    ///
+    /// See [accounts](#accounts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    accounts: Vec<AccountConfig>,
+
+    /// The number of milliseconds between each check for unread messages.
+    ///
+    /// **Default**: `10000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰭹 {unread}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The maximum number of rooms to list in the popup.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_max_rooms")]
+    max_rooms: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    10_000
+}
+
+fn default_format() -> String {
+    String::from("󰭹 {unread}")
+}
+
+const fn default_max_rooms() -> usize {
+    10
+}
+
+/// The rooms with unread messages on a configured account.
+#[derive(Debug, Clone)]
+pub struct AccountState {
+    name: String,
+    rooms: Vec<Room>,
+}
+
+impl Module<Button> for ChatModule {
+    type SendMessage = Snapshot<Vec<AccountState>>;
+    /// Index of the account whose window to focus.
+    type ReceiveMessage = usize;
+
+    module_impl!("chat");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let mut accounts = self
+            .accounts
+            .iter()
+            .map(AccountConfig::create)
+            .collect::<Vec<_>>();
+
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let http = reqwest::Client::new();
+
+            let mut states = accounts
+                .iter()
+                .map(|(name, _)| AccountState {
+                    name: name.clone(),
+                    rooms: vec![],
+                })
+                .collect::<Vec<_>>();
+
+            loop {
+                for ((name, account), state) in accounts.iter_mut().zip(&mut states) {
+                    match account.rooms(&http).await {
+                        Ok(rooms) => state.rooms = rooms,
+                        Err(err) => error!("Failed to get unread messages for '{name}': {err:?}"),
+                    }
+                }
+
+                send_async!(tx, ModuleUpdateEvent::snapshot(states.clone()));
+                polling::sleep(interval).await;
+            }
+        });
+
+        let windows = self
+            .accounts
+            .iter()
+            .map(|account| account.window().map(str::to_string))
+            .collect::<Vec<_>>();
+
+        let wl = context.client::<wayland::Client>();
+
+        spawn(async move {
+            while let Some(index) = rx.recv().await {
+                let Some(Some(window)) = windows.get(index) else {
+                    continue;
+                };
+
+                let toplevel = wl
+                    .toplevel_info_all()
+                    .into_iter()
+                    .find(|info| &info.app_id == window || info.title.contains(window.as_str()));
+
+                if let Some(toplevel) = toplevel {
+                    debug!("Focusing chat window {}: {}", toplevel.id, toplevel.title);
+                    wl.toplevel_focus(toplevel.id);
+                } else {
+                    debug!("No window matches '{window}'");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::new(None);
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, states => {
+                let rooms = states.iter().flat_map(|state| &state.rooms);
+
+                let unread = rooms.clone().map(|room| room.unread).sum::<u32>();
+                let highlights = rooms.clone().map(|room| room.highlights).sum::<u32>();
+
+                let text = format
+                    .replace("{unread}", &unread.to_string())
+                    .replace("{highlights}", &highlights.to_string())
+                    .replace("{rooms}", &rooms.count().to_string());
+
+                label.set_label(&text);
+
+                if unread > 0 {
+                    button.add_class("unread");
+                } else {
+                    button.remove_class("unread");
+                }
+
+                if highlights > 0 {
+                    button.add_class("highlight");
+                } else {
+                    button.remove_class("highlight");
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        let max_rooms = self.max_rooms;
+
+        {
+            let container = container.clone();
+            glib_recv!(rx, states => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                // rooms from all accounts share one list,
+                // so that the most important are always shown.
+                let mut rooms = states
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, state)| state.rooms.iter().map(move |room| (index, state, room)))
+                    .collect::<Vec<_>>();
+
+                rooms.sort_by(|(_, _, a), (_, _, b)| {
+                    b.highlights.cmp(&a.highlights).then(b.unread.cmp(&a.unread))
+                });
+
+                if rooms.is_empty() {
+                    let label = Label::new(Some("No unread messages"));
+                    label.add_class("empty");
+                    container.add(&label);
+                }
+
+                for (index, state, room) in rooms.into_iter().take(max_rooms) {
+                    let button = make_room_button(room, &state.name);
+
+                    let tx = tx.clone();
+                    let popup_tx = context.tx.clone();
+                    button.connect_clicked(move |_| {
+                        try_send!(tx, index);
+                        try_send!(popup_tx, ModuleUpdateEvent::ClosePopup);
+                    });
+
+                    container.add(&button);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup button for a room,
+/// showing its name and unread count.
+fn make_room_button(room: &Room, account_name: &str) -> Button {
+    let button = Button::new();
+    button.add_class("room");
+    button.set_tooltip_text(Some(account_name));
+
+    if room.highlights > 0 {
+        button.add_class("highlight");
+    }
+
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+
+    let name = Label::builder()
+        .label(&room.name)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    name.add_class("name");
+
+    let count = Label::new(Some(&room.unread.to_string()));
+    count.add_class("count");
+
+    row.add(&name);
+    row.add(&count);
+    button.add(&row);
+
+    button
+}
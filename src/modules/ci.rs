@@ -0,0 +1,449 @@
+use crate::clients::ci::{github, gitlab, jenkins, Pipeline, Run, Status};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::sleep;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum PipelineConfig {
+    /// A GitHub Actions workflow.
+    #[serde(rename = "github")]
+    GitHub {
+        /// The name to show in the popup.
+        ///
+        /// **Default**: The repository name.
+        name: Option<String>,
+        /// The repository, in `owner/name` form.
+        repo: String,
+        /// The workflow file name or ID.
+        /// If not set, the latest run of any workflow is used.
+        ///
+        /// **Default**: `null`
+        workflow: Option<String>,
+        /// Only consider runs on this branch.
+        ///
+        /// **Default**: `null`
+        branch: Option<String>,
+        /// A personal access token.
+        /// Required for private repositories and re-running.
+        ///
+        /// **Default**: `null`
+        token: Option<String>,
+    },
+    /// A GitLab CI project.
+    #[serde(rename = "gitlab")]
+    GitLab {
+        /// The name to show in the popup.
+        ///
+        /// **Default**: The project path.
+        name: Option<String>,
+        /// The project ID or path, in `group/name` form.
+        project: String,
+        /// Base URL of the GitLab instance.
+        ///
+        /// **Default**: `https://gitlab.com`
+        #[serde(default = "default_gitlab_url")]
+        url: String,
+        /// Only consider pipelines for this branch or tag.
+        ///
+        /// **Default**: `null`
+        #[serde(rename = "ref")]
+        git_ref: Option<String>,
+        /// A personal or project access token.
+        /// Required for private projects and re-running.
+        ///
+        /// **Default**: `null`
+        token: Option<String>,
+    },
+    /// A Jenkins job.
+    Jenkins {
+        /// The name to show in the popup.
+        ///
+        /// **Default**: The job URL.
+        name: Option<String>,
+        /// The full URL of the job.
+        url: String,
+        /// The username to authenticate as.
+        /// Requires `token` to be set.
+        ///
+        /// **Default**: `null`
+        user: Option<String>,
+        /// An API token for `user`.
+        ///
+        /// **Default**: `null`
+        token: Option<String>,
+    },
+}
+
+fn default_gitlab_url() -> String {
+    String::from("https://gitlab.com")
+}
+
+impl PipelineConfig {
+    fn create(&self) -> (String, Pipeline) {
+        match self.clone() {
+            Self::GitHub {
+                name,
+                repo,
+                workflow,
+                branch,
+                token,
+            } => (
+                name.unwrap_or_else(|| repo.clone()),
+                Pipeline::GitHub(github::Pipeline::new(repo, workflow, branch, token)),
+            ),
+            Self::GitLab {
+                name,
+                project,
+                url,
+                git_ref,
+                token,
+            } => (
+                name.unwrap_or_else(|| project.clone()),
+                Pipeline::GitLab(gitlab::Pipeline::new(&url, &project, git_ref, token)),
+            ),
+            Self::Jenkins {
+                name,
+                url,
+                user,
+                token,
+            } => (
+                name.unwrap_or_else(|| url.clone()),
+                Pipeline::Jenkins(jenkins::Pipeline::new(&url, user.zip(token))),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CiModule {
+    /// The pipelines to watch.
+    ///
+    /// See [pipelines](#pipelines).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pipelines: Vec<PipelineConfig>,
+
+    /// The number of milliseconds between each check of the pipelines.
+    ///
+    /// **Default**: `60000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Pipeline status icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    60_000
+}
+
+fn default_format() -> String {
+    String::from("{icon}")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show when pipelines have passed.
+    ///
+    /// **Default**: `󰄬`
+    #[serde(default = "default_icon_success")]
+    success: String,
+
+    /// Icon to show when any pipeline has failed.
+    ///
+    /// **Default**: `󰅖`
+    #[serde(default = "default_icon_failure")]
+    failure: String,
+
+    /// Icon to show when any pipeline is running.
+    ///
+    /// **Default**: `󰑮`
+    #[serde(default = "default_icon_running")]
+    running: String,
+
+    /// Icon to show when no pipeline status is known.
+    ///
+    /// **Default**: `󰘥`
+    #[serde(default = "default_icon_unknown")]
+    unknown: String,
+}
+
+impl Icons {
+    fn status_icon(&self, status: Status) -> &str {
+        match status {
+            Status::Success => &self.success,
+            Status::Failure => &self.failure,
+            Status::Running => &self.running,
+            Status::Unknown => &self.unknown,
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            success: default_icon_success(),
+            failure: default_icon_failure(),
+            running: default_icon_running(),
+            unknown: default_icon_unknown(),
+        }
+    }
+}
+
+fn default_icon_success() -> String {
+    String::from("󰄬")
+}
+
+fn default_icon_failure() -> String {
+    String::from("󰅖")
+}
+
+fn default_icon_running() -> String {
+    String::from("󰑮")
+}
+
+fn default_icon_unknown() -> String {
+    String::from("󰘥")
+}
+
+/// The latest known state of a configured pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineState {
+    name: String,
+    run: Option<Run>,
+}
+
+impl PipelineState {
+    fn status(&self) -> Status {
+        self.run.as_ref().map_or(Status::Unknown, |run| run.status)
+    }
+}
+
+/// Time to wait after re-running a pipeline before refreshing,
+/// to give the service a chance to queue it.
+const RERUN_REFRESH_DELAY: Duration = Duration::from_secs(3);
+
+impl Module<Button> for CiModule {
+    type SendMessage = Snapshot<Vec<PipelineState>>;
+    /// Index of the pipeline to re-run.
+    type ReceiveMessage = usize;
+
+    module_impl!("ci");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let pipelines = self
+            .pipelines
+            .iter()
+            .map(PipelineConfig::create)
+            .collect::<Vec<_>>();
+
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let http = reqwest::Client::new();
+
+            let mut states = pipelines
+                .iter()
+                .map(|(name, _)| PipelineState {
+                    name: name.clone(),
+                    run: None,
+                })
+                .collect::<Vec<_>>();
+
+            loop {
+                for ((name, pipeline), state) in pipelines.iter().zip(&mut states) {
+                    match pipeline.latest(&http).await {
+                        Ok(run) => state.run = run,
+                        Err(err) => error!("Failed to get status of pipeline '{name}': {err:?}"),
+                    }
+                }
+
+                send_async!(tx, ModuleUpdateEvent::snapshot(states.clone()));
+
+                tokio::select! {
+                    () = polling::sleep(interval) => {}
+                    Some(index) = rx.recv() => {
+                        let (Some((name, pipeline)), Some(run)) =
+                            (pipelines.get(index), states.get(index).and_then(|state| state.run.as_ref()))
+                        else {
+                            continue;
+                        };
+
+                        match pipeline.rerun(&http, run).await {
+                            Ok(()) => sleep(RERUN_REFRESH_DELAY).await,
+                            Err(err) => error!("Failed to re-run pipeline '{name}': {err:?}"),
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let icons = self.icons.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, states => {
+                let status = Status::aggregate(states.iter().map(PipelineState::status));
+                let count = |status| states.iter().filter(|state| state.status() == status).count().to_string();
+
+                let text = format
+                    .replace("{icon}", icons.status_icon(status))
+                    .replace("{passing}", &count(Status::Success))
+                    .replace("{failing}", &count(Status::Failure))
+                    .replace("{running}", &count(Status::Running))
+                    .replace("{total}", &states.len().to_string());
+
+                label.set_label(&text);
+
+                for other in [Status::Success, Status::Failure, Status::Running, Status::Unknown] {
+                    button.remove_class(other.as_str());
+                }
+                button.add_class(status.as_str());
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        let icons = self.icons;
+
+        {
+            let container = container.clone();
+            glib_recv!(rx, states => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                for (index, state) in states.iter().enumerate() {
+                    let status = state.status();
+
+                    let row = gtk::Box::new(Orientation::Horizontal, 10);
+                    row.add_class("pipeline");
+                    row.add_class(status.as_str());
+
+                    let icon = Label::new(Some(icons.status_icon(status)));
+                    icon.add_class("icon");
+
+                    let name = Label::builder()
+                        .label(&state.name)
+                        .halign(Align::Start)
+                        .hexpand(true)
+                        .build();
+                    name.add_class("name");
+
+                    row.add(&icon);
+                    row.add(&name);
+
+                    if let Some(run) = state.run.clone() {
+                        let open = Button::with_label("Open");
+                        open.add_class("open");
+
+                        open.connect_clicked(move |_| {
+                            if let Err(err) = gtk::show_uri_on_window(
+                                None::<&gtk::Window>,
+                                &run.url,
+                                gtk::current_event_time(),
+                            ) {
+                                error!("Failed to open '{}': {err:?}", run.url);
+                            }
+                        });
+
+                        let rerun = Button::with_label("Rerun");
+                        rerun.add_class("rerun");
+
+                        let tx = tx.clone();
+                        rerun.connect_clicked(move |_| {
+                            try_send!(tx, index);
+                        });
+
+                        row.add(&open);
+                        row.add(&rerun);
+                    }
+
+                    container.add(&row);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
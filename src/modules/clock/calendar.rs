@@ -0,0 +1,512 @@
+//! Minimal iCalendar (RFC 5545) reader and writer,
+//! supporting enough of the spec to display upcoming events
+//! and create simple new ones.
+//!
+//! Timezone identifiers are not resolved:
+//! times with a `TZID` are treated as local time,
+//! and UTC times are converted to local time.
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use color_eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use tracing::{debug, error};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventTime {
+    /// An all-day date.
+    Date(NaiveDate),
+    /// A date and time, in local time.
+    DateTime(NaiveDateTime),
+}
+
+impl EventTime {
+    pub fn date(self) -> NaiveDate {
+        match self {
+            Self::Date(date) => date,
+            Self::DateTime(date_time) => date_time.date(),
+        }
+    }
+
+    pub fn time(self) -> Option<NaiveTime> {
+        match self {
+            Self::Date(_) => None,
+            Self::DateTime(date_time) => Some(date_time.time()),
+        }
+    }
+
+    fn parse(value: &str, date_only: bool) -> Option<Self> {
+        if date_only || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(Self::Date);
+        }
+
+        match value.strip_suffix('Z') {
+            Some(value) => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(|date_time| {
+                    DateTime::<Utc>::from_naive_utc_and_offset(date_time, Utc)
+                        .with_timezone(&Local)
+                        .naive_local()
+                }),
+            None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+        }
+        .map(Self::DateTime)
+    }
+
+    /// Formats the time as a content line for the property `name`.
+    ///
+    /// Date-times are written as floating local times,
+    /// which calendar clients show in the user's own timezone.
+    fn to_property(self, name: &str) -> String {
+        match self {
+            Self::Date(date) => format!("{name};VALUE=DATE:{}", date.format("%Y%m%d")),
+            Self::DateTime(date_time) => format!("{name}:{}", date_time.format("%Y%m%dT%H%M%S")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A simple recurrence rule.
+/// Only the `FREQ`, `INTERVAL`, `COUNT` and `UNTIL` parts are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<NaiveDate>,
+}
+
+impl Recurrence {
+    fn parse(value: &str) -> Option<Self> {
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+
+        for (key, value) in value.split(';').filter_map(|part| part.split_once('=')) {
+            match key {
+                "FREQ" => {
+                    frequency = match value {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        "YEARLY" => Some(Frequency::Yearly),
+                        _ => None,
+                    }
+                }
+                "INTERVAL" => interval = value.parse().unwrap_or(1).max(1),
+                "COUNT" => count = value.parse().ok(),
+                "UNTIL" => {
+                    until = value
+                        .get(..8)
+                        .and_then(|value| EventTime::parse(value, true))
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            frequency: frequency?,
+            interval,
+            count,
+            until: until.map(EventTime::date),
+        })
+    }
+
+    /// Checks whether an occurrence starting on `start` repeats on `date`.
+    fn matches(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+
+        let interval = i64::from(self.interval);
+
+        let periods = match self.frequency {
+            Frequency::Daily => (date - start).num_days(),
+            Frequency::Weekly => {
+                let days = (date - start).num_days();
+                if days % 7 != 0 {
+                    return false;
+                }
+                days / 7
+            }
+            Frequency::Monthly => {
+                if date.day() != start.day() {
+                    return false;
+                }
+                i64::from(date.year() - start.year()) * 12 + i64::from(date.month0())
+                    - i64::from(start.month0())
+            }
+            Frequency::Yearly => {
+                if date.month() != start.month() || date.day() != start.day() {
+                    return false;
+                }
+                i64::from(date.year() - start.year())
+            }
+        };
+
+        if periods < 0 || periods % interval != 0 {
+            return false;
+        }
+
+        self.count
+            .is_none_or(|count| periods / interval < i64::from(count))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: EventTime,
+    pub end: Option<EventTime>,
+    recurrence: Option<Recurrence>,
+}
+
+impl Event {
+    /// Checks whether any part of the event,
+    /// or any of its recurrences, falls on the date.
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        let start = self.start.date();
+
+        if date < start {
+            return false;
+        }
+
+        match self.recurrence {
+            Some(recurrence) => recurrence.matches(start, date),
+            None => date == start || date < self.end_date(),
+        }
+    }
+
+    /// Gets the first date the event no longer occupies.
+    fn end_date(&self) -> NaiveDate {
+        match self.end {
+            Some(EventTime::Date(date)) => date,
+            // events ending at midnight do not spill into the next day
+            Some(EventTime::DateTime(date_time)) if date_time.time() == NaiveTime::MIN => {
+                date_time.date()
+            }
+            Some(EventTime::DateTime(date_time)) => date_time.date() + chrono::Days::new(1),
+            None => self.start.date(),
+        }
+    }
+}
+
+/// An event to be added to a calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewEvent {
+    /// A globally unique identifier for the event.
+    pub uid: String,
+    pub summary: String,
+    pub start: EventTime,
+    pub end: EventTime,
+}
+
+impl NewEvent {
+    pub fn new(summary: String, start: EventTime, end: EventTime) -> Self {
+        let now = Utc::now();
+
+        Self {
+            uid: format!(
+                "{}-{}-{}@ironbar",
+                now.format("%Y%m%dT%H%M%S"),
+                now.timestamp_subsec_nanos(),
+                process::id()
+            ),
+            summary,
+            start,
+            end,
+        }
+    }
+
+    /// Serializes the event as a `VEVENT` component.
+    fn to_vevent(&self) -> String {
+        let lines = [
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}", self.uid),
+            format!("DTSTAMP:{}", Utc::now().format("%Y%m%dT%H%M%SZ")),
+            self.start.to_property("DTSTART"),
+            self.end.to_property("DTEND"),
+            format!("SUMMARY:{}", escape(&self.summary)),
+            String::from("END:VEVENT"),
+        ];
+
+        lines.map(|line| line + "\r\n").concat()
+    }
+
+    /// Serializes the event as a complete iCalendar document.
+    pub fn to_ics(&self) -> String {
+        [
+            "BEGIN:VCALENDAR\r\n",
+            "VERSION:2.0\r\n",
+            "PRODID:-//Ironbar//Ironbar//EN\r\n",
+            &self.to_vevent(),
+            "END:VCALENDAR\r\n",
+        ]
+        .concat()
+    }
+}
+
+/// Parses all events from an iCalendar document.
+/// Malformed events are skipped.
+pub fn parse(ics: &str) -> Vec<Event> {
+    let mut events = vec![];
+
+    let mut in_event = false;
+    // depth of components nested inside the event, such as alarms
+    let mut nested = 0;
+    let mut summary = None;
+    let mut location = None;
+    let mut start = None;
+    let mut end = None;
+    let mut recurrence = None;
+
+    for line in unfold(ics) {
+        let Some((name, params, value)) = split_property(&line) else {
+            continue;
+        };
+
+        let date_only = params.contains(&"VALUE=DATE");
+
+        match (name, value) {
+            ("BEGIN", "VEVENT") => {
+                in_event = true;
+                summary = None;
+                location = None;
+                start = None;
+                end = None;
+                recurrence = None;
+            }
+            ("BEGIN", _) if in_event => nested += 1,
+            ("END", _) if nested > 0 => nested -= 1,
+            ("END", "VEVENT") if in_event => {
+                in_event = false;
+
+                if let Some(start) = start {
+                    events.push(Event {
+                        summary: summary.take().unwrap_or_default(),
+                        location: location.take(),
+                        start,
+                        end,
+                        recurrence,
+                    });
+                }
+            }
+            _ if !in_event || nested > 0 => {}
+            ("SUMMARY", value) => summary = Some(unescape(value)),
+            ("LOCATION", value) => location = Some(unescape(value)).filter(|loc| !loc.is_empty()),
+            ("DTSTART", value) => start = EventTime::parse(value, date_only),
+            ("DTEND", value) => end = EventTime::parse(value, date_only),
+            ("RRULE", value) => recurrence = Recurrence::parse(value),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Joins folded content lines,
+/// where continuation lines begin with whitespace.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+/// Splits a content line into its name, parameters and value.
+fn split_property(line: &str) -> Option<(&str, Vec<&str>, &str)> {
+    // parameter values may contain quoted colons
+    let mut quoted = false;
+    let split = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..split], &line[split + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?;
+
+    Some((name, parts.collect(), value))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => {}
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+
+    unescaped
+}
+
+/// Loads all events from the paths.
+/// Directories are searched recursively for `.ics` files,
+/// such as those synced by `vdirsyncer` for `khal`.
+pub fn load(paths: &[PathBuf]) -> Vec<Event> {
+    let mut events = vec![];
+
+    for path in paths {
+        let path = expand_home(path);
+
+        let files = WalkDir::new(&path)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(walkdir::DirEntry::into_path)
+            .filter(|file| file == &path || file.extension().is_some_and(|ext| ext == "ics"));
+
+        for file in files {
+            match fs::read_to_string(&file) {
+                Ok(ics) => events.append(&mut parse(&ics)),
+                Err(err) => error!("Failed to read calendar '{}': {err:?}", file.display()),
+            }
+        }
+    }
+
+    debug!("Loaded {} calendar events", events.len());
+    events
+}
+
+/// Saves a new event to the path.
+///
+/// If the path is a directory, such as a `vdirsyncer` storage,
+/// the event is written to a new file inside it.
+/// Otherwise, the event is added to the file,
+/// which is created if it does not exist.
+pub fn save(path: &Path, event: &NewEvent) -> Result<()> {
+    let path = expand_home(path);
+
+    if path.is_dir() {
+        return Ok(fs::write(
+            path.join(format!("{}.ics", event.uid)),
+            event.to_ics(),
+        )?);
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(ics) => Ok(fs::write(&path, insert_event(&ics, event))?),
+        Err(_) if !path.exists() => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            Ok(fs::write(&path, event.to_ics())?)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Adds an event to the end of an existing iCalendar document.
+fn insert_event(ics: &str, event: &NewEvent) -> String {
+    match ics.rfind("END:VCALENDAR") {
+        Some(index) => {
+            let (head, tail) = ics.split_at(index);
+
+            // the new event always starts on its own line
+            let separator = if head.is_empty() || head.ends_with('\n') {
+                ""
+            } else {
+                "\r\n"
+            };
+
+            format!("{head}{separator}{}{tail}", event.to_vevent())
+        }
+        // not a valid calendar, so start a new one after it
+        None => format!("{ics}{}", event.to_ics()),
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(path), Some(home)) => home.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_event_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let event = NewEvent::new(
+            String::from("Lunch, with Sam; bring notes"),
+            EventTime::DateTime(date.and_hms_opt(12, 30, 0).unwrap()),
+            EventTime::DateTime(date.and_hms_opt(13, 30, 0).unwrap()),
+        );
+
+        let events = parse(&event.to_ics());
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, event.summary);
+        assert_eq!(events[0].start, event.start);
+        assert_eq!(events[0].end, Some(event.end));
+    }
+
+    #[test]
+    fn test_insert_event() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let existing = NewEvent::new(
+            String::from("Existing"),
+            EventTime::Date(date),
+            EventTime::Date(date.succ_opt().unwrap()),
+        );
+        let event = NewEvent::new(
+            String::from("New"),
+            EventTime::Date(date),
+            EventTime::Date(date.succ_opt().unwrap()),
+        );
+
+        let ics = insert_event(&existing.to_ics(), &event);
+        let summaries = parse(&ics)
+            .into_iter()
+            .map(|event| event.summary)
+            .collect::<Vec<_>>();
+
+        assert_eq!(summaries, ["Existing", "New"]);
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
@@ -0,0 +1,981 @@
+mod calendar;
+mod timezone;
+
+use std::cell::RefCell;
+use std::env;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Locale, NaiveDate, NaiveTime};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::pango::EllipsizeMode;
+use gtk::prelude::*;
+use gtk::{Align, Button, Calendar, Entry, Grid, Label, Orientation};
+use gtk_layer_shell::LayerShell;
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error, warn};
+
+use self::calendar::{Event, EventTime, NewEvent};
+use self::timezone::Timezone;
+
+use crate::config::{default_false, CommonConfig, ModuleOrientation};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, spawn_blocking, try_send};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClockModule {
+    /// The format string to use for the date/time shown on the bar.
+    /// Pango markup is supported.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%d/%m/%Y %H:%M`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the date/time shown in the popup header.
+    /// Pango markup is supported.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%H:%M:%S`
+    #[serde(default = "default_popup_format")]
+    format_popup: String,
+
+    /// The locale to use when formatting dates.
+    ///
+    /// Note this will not control the calendar -
+    /// for that you must set `LC_TIME`.
+    ///
+    /// **Valid options**: See [here](https://docs.rs/pure-rust-locales/0.8.1/pure_rust_locales/enum.Locale.html#variants)
+    /// <br>
+    /// **Default**: `$LC_TIME` or `$LANG` or `'POSIX'`
+    #[serde(default = "default_locale")]
+    locale: String,
+
+    /// The orientation to display the widget contents.
+    /// Setting to vertical will rotate text 90 degrees.
+    ///
+    /// **Valid options**: `horizontal`, `vertical`
+    /// <br>
+    /// **Default**: `horizontal`
+    #[serde(default)]
+    orientation: ModuleOrientation,
+
+    /// Whether to check the system clock's NTP synchronization status.
+    ///
+    /// When the clock is not synchronized,
+    /// a warning badge is shown on the bar
+    /// and details are shown in the popup.
+    ///
+    /// The status is read from `systemd-timedated`.
+    /// If `chronyc` is available, the clock offset is also shown.
+    ///
+    /// **Default**: `false`
+    #[serde(default = "default_false")]
+    sync_status: bool,
+
+    /// The number of milliseconds between each synchronization status check.
+    ///
+    /// **Default**: `60000`
+    #[serde(default = "default_sync_interval")]
+    sync_interval: u64,
+
+    /// The text to show on the badge when the clock is not synchronized.
+    /// Pango markup is supported.
+    ///
+    /// **Default**: `⚠`
+    #[serde(default = "default_sync_badge")]
+    sync_badge: String,
+
+    /// Paths to iCalendar (`.ics`) files, or directories containing them,
+    /// to load events from.
+    ///
+    /// Days with events are marked on the popup calendar,
+    /// and the selected day's events are listed beneath it.
+    ///
+    /// Directories are searched recursively,
+    /// so this can point to a calendar store used by `khal` (via `vdirsyncer`)
+    /// or Evolution Data Server.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    calendars: Vec<PathBuf>,
+
+    /// The number of milliseconds between each reload of calendar events.
+    ///
+    /// **Default**: `300000`
+    #[serde(default = "default_calendar_interval")]
+    calendar_interval: u64,
+
+    /// The format string to use for event start times in the popup agenda.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%H:%M`
+    #[serde(default = "default_agenda_format")]
+    format_agenda: String,
+
+    /// Where to save events created from the popup.
+    ///
+    /// When set, a form to add an event to the selected day
+    /// is shown beneath the agenda.
+    /// See [creating events](#creating-events).
+    ///
+    /// **Default**: `null`
+    new_events: Option<EventTarget>,
+
+    /// The length of new events in minutes,
+    /// when only a start time is given.
+    ///
+    /// **Default**: `60`
+    #[serde(default = "default_event_duration")]
+    event_duration: u32,
+
+    /// Additional timezones to show the time in.
+    ///
+    /// Each entry is either an IANA timezone name, such as `America/New_York`,
+    /// or an object with a `timezone` and a `label` to show instead of the city name.
+    ///
+    /// The times are listed in the popup,
+    /// along with the day offset from local time where it differs.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    timezones: Vec<WorldClock>,
+
+    /// The format string to use for times in other timezones.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%H:%M`
+    #[serde(default = "default_timezone_format")]
+    format_timezone: String,
+
+    /// Whether to rotate the bar label between the local time
+    /// and the time in each of the `timezones`.
+    ///
+    /// **Default**: `false`
+    #[serde(default = "default_false")]
+    rotate_timezones: bool,
+
+    /// The number of milliseconds to show each time for while rotating.
+    ///
+    /// **Default**: `5000`
+    #[serde(default = "default_rotate_interval")]
+    rotate_interval: u64,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for ClockModule {
+    fn default() -> Self {
+        ClockModule {
+            format: default_format(),
+            format_popup: default_popup_format(),
+            locale: default_locale(),
+            orientation: ModuleOrientation::Horizontal,
+            sync_status: false,
+            sync_interval: default_sync_interval(),
+            sync_badge: default_sync_badge(),
+            calendars: vec![],
+            calendar_interval: default_calendar_interval(),
+            format_agenda: default_agenda_format(),
+            new_events: None,
+            event_duration: default_event_duration(),
+            timezones: vec![],
+            format_timezone: default_timezone_format(),
+            rotate_timezones: false,
+            rotate_interval: default_rotate_interval(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+fn default_format() -> String {
+    String::from("%d/%m/%Y %H:%M")
+}
+
+fn default_popup_format() -> String {
+    String::from("%H:%M:%S")
+}
+
+const fn default_sync_interval() -> u64 {
+    60_000
+}
+
+fn default_sync_badge() -> String {
+    String::from("⚠")
+}
+
+const fn default_calendar_interval() -> u64 {
+    300_000
+}
+
+fn default_agenda_format() -> String {
+    String::from("%H:%M")
+}
+
+const fn default_event_duration() -> u32 {
+    60
+}
+
+fn default_timezone_format() -> String {
+    String::from("%H:%M")
+}
+
+const fn default_rotate_interval() -> u64 {
+    5000
+}
+
+fn default_locale() -> String {
+    env::var("LC_TIME")
+        .or_else(|_| env::var("LANG"))
+        .map_or_else(|_| "POSIX".to_string(), strip_tail)
+}
+
+fn strip_tail(string: String) -> String {
+    string
+        .split_once('.')
+        .map(|(head, _)| head.to_string())
+        .unwrap_or(string)
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum WorldClock {
+    Timezone(String),
+    Labelled { timezone: String, label: String },
+}
+
+impl WorldClock {
+    fn timezone(&self) -> &str {
+        match self {
+            Self::Timezone(timezone) | Self::Labelled { timezone, .. } => timezone,
+        }
+    }
+
+    /// Gets the configured label,
+    /// or the city name from the timezone name.
+    fn label(&self) -> String {
+        match self {
+            Self::Timezone(timezone) => timezone
+                .rsplit('/')
+                .next()
+                .unwrap_or(timezone)
+                .replace('_', " "),
+            Self::Labelled { label, .. } => label.clone(),
+        }
+    }
+}
+
+/// Loads each configured timezone with its label.
+/// Invalid timezones are logged and skipped.
+fn load_timezones(clocks: &[WorldClock]) -> Vec<(String, Timezone)> {
+    clocks
+        .iter()
+        .filter_map(|clock| match Timezone::load(clock.timezone()) {
+            Ok(timezone) => Some((clock.label(), timezone)),
+            Err(err) => {
+                error!("{err:?}");
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum EventTarget {
+    /// A local iCalendar file, which is created if it does not exist,
+    /// or a directory to write a new file per event into.
+    Ics { path: PathBuf },
+    /// Imports events using `khal import`.
+    Khal {
+        /// The khal calendar to add events to.
+        /// If not set, khal's default calendar is used.
+        ///
+        /// **Default**: `null`
+        calendar: Option<String>,
+    },
+    /// Uploads events to a CalDAV calendar collection.
+    #[cfg(feature = "http")]
+    Caldav {
+        /// The URL of the calendar collection,
+        /// such as `https://example.com/dav/calendars/user/personal/`.
+        url: String,
+        /// The username to use for basic authentication.
+        ///
+        /// **Default**: `null`
+        username: Option<String>,
+        /// The password to use for basic authentication.
+        ///
+        /// **Default**: `null`
+        password: Option<String>,
+    },
+}
+
+impl EventTarget {
+    /// Adds the event to the calendar.
+    async fn create(&self, event: &NewEvent) -> Result<()> {
+        match self {
+            Self::Ics { path } => {
+                let path = path.clone();
+                let event = event.clone();
+                spawn_blocking(move || calendar::save(&path, &event)).await?
+            }
+            Self::Khal { calendar } => khal_import(calendar.as_deref(), event).await,
+            #[cfg(feature = "http")]
+            Self::Caldav {
+                url,
+                username,
+                password,
+            } => {
+                let url = format!("{}/{}.ics", url.trim_end_matches('/'), event.uid);
+
+                let mut request = reqwest::Client::new()
+                    .put(url)
+                    .header("Content-Type", "text/calendar; charset=utf-8")
+                    // never overwrite an existing event
+                    .header("If-None-Match", "*")
+                    .body(event.to_ics());
+
+                if let Some(username) = username {
+                    request = request.basic_auth(username, password.as_ref());
+                }
+
+                request.send().await?.error_for_status()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Imports the event into khal, via a temporary file.
+async fn khal_import(calendar: Option<&str>, event: &NewEvent) -> Result<()> {
+    let path = env::temp_dir().join(format!("ironbar-{}.ics", event.uid));
+    tokio::fs::write(&path, event.to_ics()).await?;
+
+    let mut command = Command::new("khal");
+    command.args(["import", "--batch"]);
+
+    if let Some(calendar) = calendar {
+        command.args(["--include-calendar", calendar]);
+    }
+
+    let output = command.arg(&path).output().await;
+    let _ = tokio::fs::remove_file(&path).await;
+
+    let output = output?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "khal exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Parses the time entered for a new event on `date`.
+///
+/// This is empty for an all-day event,
+/// a start time such as `14:00`,
+/// or a start and end time such as `14:00-15:30`.
+fn parse_event_time(input: &str, date: NaiveDate, duration: u32) -> Option<(EventTime, EventTime)> {
+    let input = input.trim();
+
+    if input.is_empty() {
+        return Some((EventTime::Date(date), EventTime::Date(date.succ_opt()?)));
+    }
+
+    let parse = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").ok();
+
+    let (start, end) = match input.split_once('-') {
+        Some((start, end)) => (parse(start)?, Some(parse(end)?)),
+        None => (parse(input)?, None),
+    };
+
+    let start = date.and_time(start);
+    let end = match end {
+        // events ending before they start finish the next day
+        Some(end) if end <= start.time() => date.succ_opt()?.and_time(end),
+        Some(end) => date.and_time(end),
+        None => start + chrono::Duration::minutes(i64::from(duration)),
+    };
+
+    Some((EventTime::DateTime(start), EventTime::DateTime(end)))
+}
+
+#[derive(Debug, Clone)]
+pub enum ClockUpdate {
+    Time(DateTime<Local>),
+    Sync(SyncStatus),
+    Events(Vec<Event>),
+    /// Whether a new event was saved successfully.
+    EventSaved(bool),
+}
+
+/// The system clock's NTP synchronization state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncStatus {
+    synchronized: bool,
+    /// Offset of the system clock from true time, in seconds.
+    /// Only available when chrony is running.
+    offset: Option<f64>,
+}
+
+impl SyncStatus {
+    async fn read() -> Result<Self> {
+        let dbus = zbus::Connection::system().await?;
+        let timedate = TimedateProxy::new(&dbus).await?;
+
+        Ok(Self {
+            synchronized: timedate.ntp_synchronized().await?,
+            offset: chrony_offset().await,
+        })
+    }
+
+    fn details(&self) -> String {
+        let mut details = String::from("Clock not synchronized");
+
+        if let Some(offset) = self.offset {
+            details.push_str(&format!("\nOffset: {offset:+.3}s"));
+        }
+
+        details
+    }
+}
+
+/// Gets the system clock offset from `chronyc`,
+/// if it is installed and the daemon is running.
+async fn chrony_offset() -> Option<f64> {
+    let output = Command::new("chronyc")
+        .args(["-c", "tracking"])
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    // CSV output, where the fifth field is the system time offset.
+    // Chrony reports a positive value when the clock is slow,
+    // so this is negated to match the intuitive direction.
+    String::from_utf8_lossy(&output.stdout)
+        .split(',')
+        .nth(4)
+        .and_then(|offset| offset.trim().parse::<f64>().ok())
+        .map(|offset| -offset)
+}
+
+#[zbus::dbus_proxy(
+    default_service = "org.freedesktop.timedate1",
+    interface = "org.freedesktop.timedate1",
+    default_path = "/org/freedesktop/timedate1"
+)]
+trait Timedate {
+    #[dbus_proxy(property, name = "NTPSynchronized")]
+    fn ntp_synchronized(&self) -> zbus::Result<bool>;
+}
+
+impl Module<Button> for ClockModule {
+    type SendMessage = ClockUpdate;
+    type ReceiveMessage = NewEvent;
+
+    module_impl!("clock");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        spawn(async move {
+            loop {
+                let date = Local::now();
+                send_async!(tx, ModuleUpdateEvent::Update(ClockUpdate::Time(date)));
+                polling::sleep(tokio::time::Duration::from_millis(500)).await;
+            }
+        });
+
+        if self.sync_status {
+            let tx = context.tx.clone();
+            let interval = Duration::from_millis(self.sync_interval);
+
+            spawn(async move {
+                let mut last = None;
+
+                loop {
+                    match SyncStatus::read().await {
+                        Ok(status) if last != Some(status) => {
+                            debug!("Clock sync status: {status:?}");
+                            last = Some(status);
+                            send_async!(tx, ModuleUpdateEvent::Update(ClockUpdate::Sync(status)));
+                        }
+                        Ok(_) => {}
+                        Err(err) => warn!("Failed to read clock sync status: {err:?}"),
+                    }
+
+                    polling::sleep(interval).await;
+                }
+            });
+        }
+
+        // reloads calendars early, so that new events show straight away
+        let (reload_tx, mut reload_rx) = mpsc::channel(1);
+
+        if !self.calendars.is_empty() {
+            let tx = context.tx.clone();
+            let paths = self.calendars.clone();
+            let interval = Duration::from_millis(self.calendar_interval);
+
+            spawn(async move {
+                loop {
+                    let paths = paths.clone();
+                    match spawn_blocking(move || calendar::load(&paths)).await {
+                        Ok(events) => {
+                            send_async!(tx, ModuleUpdateEvent::Update(ClockUpdate::Events(events)));
+                        }
+                        Err(err) => error!("{err:?}"),
+                    }
+
+                    tokio::select! {
+                        () = polling::sleep(interval) => {}
+                        Some(()) = reload_rx.recv() => {}
+                    }
+                }
+            });
+        }
+
+        if let Some(target) = self.new_events.clone() {
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                while let Some(event) = rx.recv().await {
+                    debug!("Creating calendar event: {event:?}");
+
+                    let saved = match target.create(&event).await {
+                        Ok(()) => {
+                            // there is nothing to reload if no calendars are shown
+                            let _ = reload_tx.try_send(());
+                            true
+                        }
+                        Err(err) => {
+                            error!("Failed to create calendar event: {err:?}");
+                            false
+                        }
+                    };
+
+                    send_async!(
+                        tx,
+                        ModuleUpdateEvent::Update(ClockUpdate::EventSaved(saved))
+                    );
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let container = gtk::Box::new(self.orientation.into(), 0);
+
+        let label = Label::builder()
+            .angle(self.orientation.to_angle())
+            .use_markup(true)
+            .build();
+        container.add(&label);
+
+        let badge = Label::builder()
+            .angle(self.orientation.to_angle())
+            .use_markup(true)
+            .label(&self.sync_badge)
+            .build();
+        badge.add_class("sync-badge");
+        container.add(&badge);
+
+        button.add(&container);
+        container.show_all();
+        badge.hide();
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        let format = self.format.clone();
+        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
+
+        let timezones = if self.rotate_timezones {
+            load_timezones(&self.timezones)
+        } else {
+            vec![]
+        };
+        let timezone_format = self.format_timezone.clone();
+        let rotate_interval = i64::try_from(self.rotate_interval.max(1)).unwrap_or(i64::MAX);
+
+        let rx = context.subscribe();
+        {
+            let button = button.clone();
+            glib_recv!(rx, update => match update {
+                ClockUpdate::Time(date) => {
+                    // the local time is shown first, followed by each timezone
+                    let index = (date.timestamp_millis() / rotate_interval)
+                        .rem_euclid(timezones.len() as i64 + 1) as usize;
+
+                    let date_string = match index.checked_sub(1).and_then(|i| timezones.get(i)) {
+                        Some((name, timezone)) => {
+                            label.add_class("timezone");
+                            let time = timezone.now().format_localized(&timezone_format, locale);
+                            format!("{name} {time}")
+                        }
+                        None => {
+                            label.remove_class("timezone");
+                            format!("{}", date.format_localized(&format, locale))
+                        }
+                    };
+
+                    label.set_label(&date_string);
+                }
+                ClockUpdate::Sync(status) => {
+                    badge.set_visible(!status.synchronized);
+
+                    if status.synchronized {
+                        button.remove_class("unsynchronized");
+                    } else {
+                        button.add_class("unsynchronized");
+                    }
+                }
+                ClockUpdate::Events(_) | ClockUpdate::EventSaved(_) => {}
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let clock = Label::builder()
+            .halign(Align::Center)
+            .use_markup(true)
+            .build();
+        clock.add_class("calendar-clock");
+
+        container.add(&clock);
+
+        let calendar = Calendar::new();
+        calendar.add_class("calendar");
+        container.add(&calendar);
+
+        let agenda = gtk::Box::new(Orientation::Vertical, 0);
+        agenda.add_class("agenda");
+        container.add(&agenda);
+
+        let event_form = self
+            .new_events
+            .is_some()
+            .then(|| new_event_form(&calendar, &tx, self.event_duration));
+
+        if let Some(form) = &event_form {
+            container.add(&form.container);
+
+            // entries need keyboard focus, which is only given to the popup while it is open.
+            let window = context.popup.window.clone();
+            container.connect_map(move |_| window.set_keyboard_interactivity(true));
+
+            let window = context.popup.window.clone();
+            container.connect_unmap(move |_| window.set_keyboard_interactivity(false));
+        }
+
+        let timezones = load_timezones(&self.timezones);
+
+        let world_clock = Grid::builder().column_spacing(10).build();
+        world_clock.add_class("timezones");
+        container.add(&world_clock);
+
+        let world_clock_rows = timezones
+            .into_iter()
+            .zip(0..)
+            .map(|((name, timezone), row)| {
+                let name = Label::builder().label(name).halign(Align::Start).build();
+                name.add_class("label");
+                world_clock.attach(&name, 0, row, 1, 1);
+
+                let time = Label::builder().halign(Align::End).build();
+                time.add_class("time");
+                world_clock.attach(&time, 1, row, 1, 1);
+
+                let day_offset = Label::builder().halign(Align::Start).build();
+                day_offset.add_class("day-offset");
+                world_clock.attach(&day_offset, 2, row, 1, 1);
+
+                (timezone, time, day_offset)
+            })
+            .collect::<Vec<_>>();
+
+        let sync_status = Label::builder().halign(Align::Center).build();
+        sync_status.add_class("sync-status");
+        container.add(&sync_status);
+
+        let format = self.format_popup;
+        let timezone_format = self.format_timezone;
+        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
+
+        container.show_all();
+        sync_status.hide();
+        agenda.hide();
+        world_clock.set_visible(!world_clock_rows.is_empty());
+
+        let events = Rc::new(RefCell::new(vec![]));
+        let agenda_format = Rc::new(self.format_agenda);
+
+        {
+            let agenda = agenda.clone();
+            let events = events.clone();
+            let agenda_format = agenda_format.clone();
+
+            let update = move |calendar: &Calendar| {
+                update_calendar(calendar, &agenda, &events.borrow(), &agenda_format);
+            };
+
+            calendar.connect_month_changed(update.clone());
+            calendar.connect_day_selected(update);
+        }
+
+        glib_recv!(rx, update => match update {
+            ClockUpdate::Time(date) => {
+                let date_string = format!("{}", date.format_localized(&format, locale));
+                clock.set_label(&date_string);
+
+                for (timezone, time, day_offset) in &world_clock_rows {
+                    let now = timezone.now();
+                    time.set_label(&now.format_localized(&timezone_format, locale).to_string());
+
+                    let days = (now.date_naive() - date.date_naive()).num_days();
+                    let days = if days == 0 { String::new() } else { format!("{days:+}") };
+                    day_offset.set_label(&days);
+                }
+            }
+            ClockUpdate::Sync(status) => {
+                sync_status.set_label(&status.details());
+                sync_status.set_visible(!status.synchronized);
+            }
+            ClockUpdate::Events(new_events) => {
+                *events.borrow_mut() = new_events;
+                update_calendar(&calendar, &agenda, &events.borrow(), &agenda_format);
+            }
+            ClockUpdate::EventSaved(saved) => {
+                if let Some(form) = &event_form {
+                    form.finish(saved);
+                }
+            }
+        });
+
+        Some(container)
+    }
+}
+
+/// The popup form for adding an event to the selected day.
+struct NewEventForm {
+    container: gtk::Box,
+    summary: Entry,
+    time: Entry,
+}
+
+impl NewEventForm {
+    /// Resets the form after an event is saved,
+    /// or marks it as failed so the details can be retried.
+    fn finish(&self, saved: bool) {
+        self.container.set_sensitive(true);
+
+        if saved {
+            self.container.remove_class("error");
+            self.summary.set_text("");
+            self.time.set_text("");
+        } else {
+            self.container.add_class("error");
+        }
+    }
+}
+
+/// Creates the form for adding events,
+/// which sends each new event to `tx`.
+fn new_event_form(calendar: &Calendar, tx: &mpsc::Sender<NewEvent>, duration: u32) -> NewEventForm {
+    let container = gtk::Box::new(Orientation::Horizontal, 5);
+    container.add_class("new-event");
+
+    let summary = Entry::builder()
+        .placeholder_text("New event")
+        .hexpand(true)
+        .build();
+    summary.add_class("summary");
+    container.add(&summary);
+
+    let time = Entry::builder()
+        .placeholder_text("All day")
+        .tooltip_text("Start time, or start and end time, such as 14:00 or 14:00-15:30")
+        .width_chars(11)
+        .build();
+    time.add_class("time");
+    container.add(&time);
+
+    let add = Button::with_label("Add");
+    add.add_class("add");
+    container.add(&add);
+
+    let submit = {
+        let calendar = calendar.clone();
+        let container = container.clone();
+        let summary = summary.clone();
+        let time = time.clone();
+        let tx = tx.clone();
+
+        Rc::new(move || {
+            let text = summary.text().trim().to_string();
+            if text.is_empty() {
+                summary.grab_focus();
+                return;
+            }
+
+            let (year, month, day) = calendar.date();
+            let date = i32::try_from(year)
+                .ok()
+                // GTK months are zero-indexed
+                .and_then(|year| NaiveDate::from_ymd_opt(year, month + 1, day));
+
+            let Some((start, end)) =
+                date.and_then(|date| parse_event_time(&time.text(), date, duration))
+            else {
+                time.add_class("error");
+                time.grab_focus();
+                return;
+            };
+
+            time.remove_class("error");
+
+            // disabled until saved, to avoid adding the event twice
+            container.set_sensitive(false);
+            try_send!(tx, NewEvent::new(text, start, end));
+        })
+    };
+
+    {
+        let submit = submit.clone();
+        summary.connect_activate(move |_| submit());
+    }
+
+    {
+        let submit = submit.clone();
+        time.connect_activate(move |_| submit());
+    }
+
+    add.connect_clicked(move |_| submit());
+
+    NewEventForm {
+        container,
+        summary,
+        time,
+    }
+}
+
+/// Marks days with events in the calendar's visible month,
+/// and lists the events for the selected day in the agenda.
+fn update_calendar(calendar: &Calendar, agenda: &gtk::Box, events: &[Event], time_format: &str) {
+    let (year, month, day) = calendar.date();
+    let Ok(year) = i32::try_from(year) else {
+        return;
+    };
+
+    // GTK months are zero-indexed
+    let month = month + 1;
+
+    calendar.clear_marks();
+    for day in 1..=31 {
+        if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+            if events.iter().any(|event| event.occurs_on(date)) {
+                calendar.mark_day(day);
+            }
+        }
+    }
+
+    agenda.foreach(|child| agenda.remove(child));
+
+    let Some(date) = NaiveDate::from_ymd_opt(year, month, day) else {
+        return;
+    };
+
+    let mut day_events = events
+        .iter()
+        .filter(|event| event.occurs_on(date))
+        .collect::<Vec<_>>();
+
+    // all-day events first
+    day_events.sort_by_key(|event| event.start.time());
+
+    for event in &day_events {
+        let row = gtk::Box::new(Orientation::Horizontal, 5);
+        row.add_class("event");
+
+        let time = event.start.time().map_or_else(
+            || String::from("All day"),
+            |time| time.format(time_format).to_string(),
+        );
+
+        let time = Label::new(Some(&time));
+        time.add_class("time");
+        row.add(&time);
+
+        let summary = Label::builder()
+            .label(&event.summary)
+            .halign(Align::Start)
+            .ellipsize(EllipsizeMode::End)
+            .build();
+        summary.add_class("summary");
+        summary.set_tooltip_text(event.location.as_deref());
+        row.add(&summary);
+
+        agenda.add(&row);
+    }
+
+    agenda.show_all();
+    agenda.set_visible(!day_events.is_empty());
+}
@@ -0,0 +1,497 @@
+//! Minimal reader for the system timezone database (`TZif` files),
+//! used to show the time in other timezones.
+//!
+//! Offsets are taken from the file's transition table,
+//! falling back to its POSIX TZ rule for times after the last transition.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc, Weekday};
+use color_eyre::eyre::{eyre, Report};
+use color_eyre::Result;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const HOUR: i32 = 3600;
+
+#[derive(Debug, Clone)]
+pub struct Timezone {
+    /// UTC timestamps at which the offset changes.
+    transitions: Vec<i64>,
+    /// Index into `offsets` for the offset starting at each transition.
+    transition_types: Vec<usize>,
+    /// Offsets from UTC, in seconds.
+    offsets: Vec<i32>,
+    /// Rule for times after the last transition.
+    rule: Option<PosixRule>,
+}
+
+impl Timezone {
+    /// Loads a timezone by its IANA name, such as `Europe/London`,
+    /// from `$TZDIR` or `/usr/share/zoneinfo`.
+    pub fn load(name: &str) -> Result<Self> {
+        if name.is_empty() || name.split('/').any(|part| part == "..") {
+            return Err(eyre!("Invalid timezone name '{name}'"));
+        }
+
+        let dir = env::var_os("TZDIR")
+            .map_or_else(|| PathBuf::from("/usr/share/zoneinfo"), PathBuf::from);
+
+        let data = fs::read(dir.join(name))
+            .map_err(|err| eyre!("Failed to read timezone '{name}': {err}"))?;
+
+        Self::parse(&data).map_err(|err| eyre!("Failed to parse timezone '{name}': {err}"))
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader(data);
+
+        let header = Header::read(&mut reader)?;
+        if header.version == 0 {
+            return header.read_block(&mut reader, 4, None);
+        }
+
+        // version 2+ files repeat the data with 64-bit times,
+        // followed by a POSIX TZ rule.
+        reader.skip(header.block_len(4))?;
+        let header = Header::read(&mut reader)?;
+        let block = header.block_len(8);
+
+        let footer = reader
+            .0
+            .get(block..)
+            .map(|footer| String::from_utf8_lossy(footer).trim().to_string())
+            .filter(|footer| !footer.is_empty());
+
+        let rule = footer.as_deref().and_then(PosixRule::parse);
+        header.read_block(&mut reader, 8, rule)
+    }
+
+    /// Gets the current time in this timezone.
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        let now = Utc::now();
+        let offset = FixedOffset::east_opt(self.offset_at(now.timestamp()))
+            .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset to be valid"));
+
+        now.with_timezone(&offset)
+    }
+
+    /// Gets the offset from UTC in seconds at a UTC timestamp.
+    fn offset_at(&self, timestamp: i64) -> i32 {
+        let index = self.transitions.partition_point(|&time| time <= timestamp);
+
+        if index == self.transitions.len() {
+            if let Some(rule) = &self.rule {
+                return rule.offset_at(timestamp);
+            }
+        }
+
+        index
+            .checked_sub(1)
+            .and_then(|index| self.transition_types.get(index))
+            .and_then(|&offset| self.offsets.get(offset))
+            .or_else(|| self.offsets.first())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        if self.0.len() < len {
+            return Err(eyre!("Unexpected end of file"));
+        }
+
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn count(&mut self) -> Result<usize> {
+        usize::try_from(self.i32()?).map_err(Report::new)
+    }
+
+    fn time(&mut self, size: usize) -> Result<i64> {
+        if size == 4 {
+            return self.i32().map(i64::from);
+        }
+
+        let bytes = self.take(8)?;
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        Ok(i64::from_be_bytes(buf))
+    }
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Header {
+    fn read(reader: &mut Reader) -> Result<Self> {
+        if reader.take(4)? != b"TZif" {
+            return Err(eyre!("Not a TZif file"));
+        }
+
+        let version = match reader.u8()? {
+            0 => 0,
+            version @ b'2'..=b'4' => version - b'0',
+            version => return Err(eyre!("Unsupported TZif version {version:#04x}")),
+        };
+        reader.skip(15)?;
+
+        Ok(Self {
+            version,
+            isutcnt: reader.count()?,
+            isstdcnt: reader.count()?,
+            leapcnt: reader.count()?,
+            timecnt: reader.count()?,
+            typecnt: reader.count()?,
+            charcnt: reader.count()?,
+        })
+    }
+
+    /// Gets the length of the data block following the header,
+    /// where times are `time_size` bytes.
+    const fn block_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+
+    fn read_block(
+        &self,
+        reader: &mut Reader,
+        time_size: usize,
+        rule: Option<PosixRule>,
+    ) -> Result<Timezone> {
+        let transitions = (0..self.timecnt)
+            .map(|_| reader.time(time_size))
+            .collect::<Result<Vec<_>>>()?;
+
+        let transition_types = (0..self.timecnt)
+            .map(|_| reader.u8().map(usize::from))
+            .collect::<Result<Vec<_>>>()?;
+
+        let offsets = (0..self.typecnt)
+            .map(|_| {
+                let offset = reader.i32()?;
+                // dst flag and abbreviation index
+                reader.skip(2)?;
+                Ok(offset)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Timezone {
+            transitions,
+            transition_types,
+            offsets,
+            rule,
+        })
+    }
+}
+
+/// A POSIX TZ rule, such as `CET-1CEST,M3.5.0,M10.5.0/3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PosixRule {
+    /// Standard offset from UTC, in seconds.
+    std_offset: i32,
+    dst: Option<DstRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DstRule {
+    /// Daylight saving offset from UTC, in seconds.
+    offset: i32,
+    /// Start date and local time (in standard time).
+    start: (DateRule, i32),
+    /// End date and local time (in daylight saving time).
+    end: (DateRule, i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateRule {
+    /// `Jn`: day of the year from 1 to 365, ignoring leap days.
+    Julian(u32),
+    /// `n`: day of the year from 0 to 365, counting leap days.
+    Ordinal(u32),
+    /// `Mm.w.d`: day `d` (0 is Sunday) of week `w` (5 is the last) of month `m`.
+    Month { month: u32, week: u32, weekday: u32 },
+}
+
+impl PosixRule {
+    fn parse(rule: &str) -> Option<Self> {
+        let mut parser = Parser(rule);
+
+        parser.name()?;
+        // POSIX offsets are positive west of UTC
+        let std_offset = -parser.time()?;
+
+        if parser.0.is_empty() {
+            return Some(Self {
+                std_offset,
+                dst: None,
+            });
+        }
+
+        parser.name()?;
+        let offset = if parser.0.starts_with(',') {
+            std_offset + HOUR
+        } else {
+            -parser.time()?
+        };
+
+        // rules default to those of the United States
+        let (start, end) = if parser.0.is_empty() {
+            (
+                (
+                    DateRule::Month {
+                        month: 3,
+                        week: 2,
+                        weekday: 0,
+                    },
+                    2 * HOUR,
+                ),
+                (
+                    DateRule::Month {
+                        month: 11,
+                        week: 1,
+                        weekday: 0,
+                    },
+                    2 * HOUR,
+                ),
+            )
+        } else {
+            parser.expect(',')?;
+            let start = parser.transition()?;
+            parser.expect(',')?;
+            let end = parser.transition()?;
+            (start, end)
+        };
+
+        Some(Self {
+            std_offset,
+            dst: Some(DstRule { offset, start, end }),
+        })
+    }
+
+    fn offset_at(&self, timestamp: i64) -> i32 {
+        let Some(dst) = self.dst else {
+            return self.std_offset;
+        };
+
+        let Some(year) = DateTime::from_timestamp(timestamp + i64::from(self.std_offset), 0)
+            .map(|date_time| date_time.year())
+        else {
+            return self.std_offset;
+        };
+
+        let (Some(start), Some(end)) = (dst.start.0.date(year), dst.end.0.date(year)) else {
+            return self.std_offset;
+        };
+
+        let start = local_timestamp(start, dst.start.1) - i64::from(self.std_offset);
+        let end = local_timestamp(end, dst.end.1) - i64::from(dst.offset);
+
+        let in_dst = if start <= end {
+            (start..end).contains(&timestamp)
+        } else {
+            // southern hemisphere, where daylight saving time spans the new year
+            !(end..start).contains(&timestamp)
+        };
+
+        if in_dst {
+            dst.offset
+        } else {
+            self.std_offset
+        }
+    }
+}
+
+impl DateRule {
+    fn date(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Self::Julian(day) => {
+                let leap = NaiveDate::from_ymd_opt(year, 2, 29).is_some();
+                let day = if leap && day >= 60 { day + 1 } else { day };
+                NaiveDate::from_yo_opt(year, day)
+            }
+            Self::Ordinal(day) => NaiveDate::from_yo_opt(year, day + 1),
+            Self::Month {
+                month,
+                week,
+                weekday,
+            } => {
+                let weekday = Weekday::try_from(u8::try_from((weekday + 6) % 7).ok()?).ok()?;
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(week).ok()?)
+                    // the fifth week means the last, which may be the fourth
+                    .or_else(|| NaiveDate::from_weekday_of_month_opt(year, month, weekday, 4))
+            }
+        }
+    }
+}
+
+fn local_timestamp(date: NaiveDate, time: i32) -> i64 {
+    date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp() + i64::from(time)
+}
+
+struct Parser<'a>(&'a str);
+
+impl Parser<'_> {
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.0 = self.0.strip_prefix(c)?;
+        Some(())
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let end = self.0.find(|c| !predicate(c)).unwrap_or(self.0.len());
+        let (head, tail) = self.0.split_at(end);
+        self.0 = tail;
+        head
+    }
+
+    /// Skips a timezone abbreviation, such as `CET` or `<+0530>`.
+    fn name(&mut self) -> Option<()> {
+        if self.expect('<').is_some() {
+            self.take_while(|c| c != '>');
+            self.expect('>')
+        } else {
+            let name = self.take_while(|c| c.is_ascii_alphabetic());
+            (name.len() >= 3).then_some(())
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        self.take_while(|c| c.is_ascii_digit()).parse().ok()
+    }
+
+    /// Parses a signed `hh[:mm[:ss]]` time into seconds.
+    fn time(&mut self) -> Option<i32> {
+        let sign = if self.expect('-').is_some() {
+            -1
+        } else {
+            let _ = self.expect('+');
+            1
+        };
+
+        let mut seconds = i32::try_from(self.number()?).ok()? * HOUR;
+
+        for unit in [60, 1] {
+            if self.expect(':').is_none() {
+                break;
+            }
+            seconds += i32::try_from(self.number()?).ok()? * unit;
+        }
+
+        Some(sign * seconds)
+    }
+
+    /// Parses a date rule and optional `/time`, which defaults to 02:00.
+    fn transition(&mut self) -> Option<(DateRule, i32)> {
+        let date = if self.expect('J').is_some() {
+            DateRule::Julian(self.number()?)
+        } else if self.expect('M').is_some() {
+            let month = self.number()?;
+            self.expect('.')?;
+            let week = self.number()?;
+            self.expect('.')?;
+            let weekday = self.number()?;
+
+            DateRule::Month {
+                month,
+                week,
+                weekday,
+            }
+        } else {
+            DateRule::Ordinal(self.number()?)
+        };
+
+        let time = if self.expect('/').is_some() {
+            self.time()?
+        } else {
+            2 * HOUR
+        };
+
+        Some((date, time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(date: &str) -> i64 {
+        DateTime::parse_from_rfc3339(date)
+            .expect("valid test date")
+            .timestamp()
+    }
+
+    #[test]
+    fn test_fixed_rule() {
+        let rule = PosixRule::parse("<+0530>-5:30").expect("valid rule");
+        assert_eq!(rule.offset_at(0), 5 * HOUR + 30 * 60);
+
+        let rule = PosixRule::parse("<-03>3").expect("valid rule");
+        assert_eq!(rule.offset_at(0), -3 * HOUR);
+    }
+
+    #[test]
+    fn test_dst_rule() {
+        let rule = PosixRule::parse("GMT0BST,M3.5.0/1,M10.5.0").expect("valid rule");
+
+        // clocks go forward at 01:00 UTC on 30 March 2025
+        assert_eq!(rule.offset_at(timestamp("2025-03-30T00:59:59Z")), 0);
+        assert_eq!(rule.offset_at(timestamp("2025-03-30T01:00:00Z")), HOUR);
+        // and back at 01:00 UTC on 26 October 2025
+        assert_eq!(rule.offset_at(timestamp("2025-10-26T00:59:59Z")), HOUR);
+        assert_eq!(rule.offset_at(timestamp("2025-10-26T01:00:00Z")), 0);
+    }
+
+    #[test]
+    fn test_header_version() {
+        let header = |version: u8| {
+            let mut data = b"TZif".to_vec();
+            data.push(version);
+            data.resize(44, 0);
+            Header::read(&mut Reader(&data)).map(|header| header.version)
+        };
+
+        assert_eq!(header(0).ok(), Some(0));
+        assert_eq!(header(b'2').ok(), Some(2));
+        assert_eq!(header(b'4').ok(), Some(4));
+        assert!(header(b'1').is_err());
+        assert!(header(b'5').is_err());
+    }
+
+    #[test]
+    fn test_southern_dst_rule() {
+        let rule = PosixRule::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").expect("valid rule");
+
+        assert_eq!(rule.offset_at(timestamp("2025-01-15T00:00:00Z")), 11 * HOUR);
+        assert_eq!(rule.offset_at(timestamp("2025-07-15T00:00:00Z")), 10 * HOUR);
+    }
+}
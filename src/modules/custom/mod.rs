@@ -12,6 +12,8 @@ use self::slider::SliderWidget;
 use crate::config::{CommonConfig, ModuleConfig};
 use crate::modules::custom::button::ButtonWidget;
 use crate::modules::custom::progress::ProgressWidget;
+#[cfg(feature = "workspaces")]
+use crate::modules::ModuleFactory;
 use crate::modules::{
     wrap_widget, AnyModuleFactory, BarModuleFactory, Module, ModuleInfo, ModuleParts, ModulePopup,
     ModuleUpdateEvent, PopupButton, PopupModuleFactory, WidgetContext,
@@ -137,6 +139,11 @@ pub fn set_length<W: WidgetExt>(widget: &W, length: i32, bar_orientation: Orient
 
 impl WidgetOrModule {
     fn add_to(self, parent: &gtk::Box, context: &CustomWidgetContext, common: CommonConfig) {
+        #[cfg(feature = "workspaces")]
+        if common.has_workspace_filter() {
+            crate::event_bus::watch_workspaces(context.module_factory.ironbar());
+        }
+
         match self {
             WidgetOrModule::Widget(widget) => widget.add_to(parent, context, common),
             WidgetOrModule::Module(config) => {
@@ -151,6 +158,11 @@ impl WidgetOrModule {
 impl Widget {
     /// Creates this widget and adds it to the parent container
     fn add_to(self, parent: &gtk::Box, context: &CustomWidgetContext, common: CommonConfig) {
+        #[cfg(feature = "workspaces")]
+        if common.has_workspace_filter() {
+            crate::event_bus::watch_workspaces(context.module_factory.ironbar());
+        }
+
         macro_rules! create {
             ($widget:expr) => {
                 wrap_widget(
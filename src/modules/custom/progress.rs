@@ -4,6 +4,7 @@ use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
 
+use crate::animation::AnimatedValue;
 use crate::config::ModuleOrientation;
 use crate::dynamic_value::dynamic_string;
 use crate::modules::custom::set_length;
@@ -95,7 +96,8 @@ impl CustomWidget for ProgressWidget {
                     .await;
             });
 
-            glib_recv_mpsc!(rx, value => progress.set_fraction(value / self.max));
+            let fraction = AnimatedValue::new(&progress);
+            glib_recv_mpsc!(rx, value => fraction.set(value / self.max));
         }
 
         if let Some(text) = self.label {
@@ -0,0 +1,373 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use gtk::prelude::*;
+use gtk::Label;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::zvariant::{OwnedValue, Structure, Value};
+use zbus::{MatchRule, MessageStream, MessageType};
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum BusType {
+    /// The user's session bus.
+    #[default]
+    Session,
+    /// The system-wide bus.
+    System,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DbusModule {
+    /// The bus to connect to.
+    ///
+    /// **Valid options**: `session`, `system`
+    /// <br>
+    /// **Default**: `session`
+    #[serde(default)]
+    bus: BusType,
+
+    /// The bus name of the service which owns the object,
+    /// such as `org.freedesktop.UPower`.
+    /// Only used when watching a property.
+    ///
+    /// **Default**: `null`
+    service: Option<String>,
+
+    /// The object path, such as `/org/freedesktop/UPower/devices/DisplayDevice`.
+    ///
+    /// **Required**
+    path: String,
+
+    /// The interface of the property or signal,
+    /// such as `org.freedesktop.UPower.Device`.
+    ///
+    /// **Required**
+    interface: String,
+
+    /// The name of the property to watch.
+    /// Either this or `signal` must be set.
+    ///
+    /// **Default**: `null`
+    property: Option<String>,
+
+    /// The name of the signal to watch.
+    /// Either this or `property` must be set.
+    ///
+    /// **Default**: `null`
+    signal: Option<String>,
+
+    /// The format string to use for the label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{value}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{value}")
+}
+
+impl Module<Label> for DbusModule {
+    /// The property value, or each argument of the last signal.
+    type SendMessage = Vec<OwnedValue>;
+    type ReceiveMessage = ();
+
+    module_impl!("dbus");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let module = self.clone();
+
+        match (&self.property, &self.signal) {
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(eyre!(
+                    "The dbus module requires exactly one of `property` or `signal`"
+                ))
+            }
+            (Some(_), None) if self.service.is_none() => {
+                return Err(eyre!(
+                    "The dbus module requires `service` to watch a property"
+                ))
+            }
+            _ => {}
+        }
+
+        spawn(async move {
+            if let Err(err) = watch(&module, tx).await {
+                error!("Failed to watch D-Bus object '{}': {err:?}", module.path);
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Label>> {
+        let label = Label::builder().use_markup(true).build();
+
+        {
+            let label = label.clone();
+            let format = self.format;
+
+            glib_recv!(context.subscribe(), values => {
+                let text = render(&format, &values);
+                label.set_label_escaped(&text);
+                label.set_visible(!text.is_empty());
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: label,
+            popup: None,
+        })
+    }
+}
+
+async fn watch(
+    module: &DbusModule,
+    tx: mpsc::Sender<ModuleUpdateEvent<Vec<OwnedValue>>>,
+) -> Result<()> {
+    let dbus = match module.bus {
+        BusType::Session => zbus::Connection::session().await?,
+        BusType::System => zbus::Connection::system().await?,
+    };
+
+    if let (Some(service), Some(property)) = (&module.service, &module.property) {
+        watch_property(
+            &dbus,
+            service,
+            &module.path,
+            &module.interface,
+            property,
+            tx,
+        )
+        .await
+    } else if let Some(signal) = &module.signal {
+        watch_signal(&dbus, &module.path, &module.interface, signal, tx).await
+    } else {
+        Ok(())
+    }
+}
+
+/// Sends the current value of a property,
+/// followed by its new value each time it changes.
+async fn watch_property(
+    dbus: &zbus::Connection,
+    service: &str,
+    path: &str,
+    interface: &str,
+    property: &str,
+    tx: mpsc::Sender<ModuleUpdateEvent<Vec<OwnedValue>>>,
+) -> Result<()> {
+    let proxy = PropertiesProxy::builder(dbus)
+        .destination(service.to_string())?
+        .path(path.to_string())?
+        .build()
+        .await?;
+
+    let interface = InterfaceName::try_from(interface.to_string())?;
+
+    // the service may not be running yet,
+    // in which case the value is sent once it starts and sets the property.
+    match proxy.get(interface.clone(), property).await {
+        Ok(value) => send_async!(tx, ModuleUpdateEvent::Update(vec![value])),
+        Err(err) => debug!("Failed to get property '{property}': {err:?}"),
+    }
+
+    let mut changes = proxy.receive_properties_changed().await?;
+
+    while let Some(signal) = changes.next().await {
+        let args = signal.args()?;
+        if args.interface_name != interface {
+            continue;
+        }
+
+        if let Some(value) = args.changed_properties.get(property) {
+            let value = OwnedValue::from(value.clone());
+            send_async!(tx, ModuleUpdateEvent::Update(vec![value]));
+        } else if args.invalidated_properties.contains(&property) {
+            // invalidated properties are not sent with the signal
+            let value = proxy.get(interface.clone(), property).await?;
+            send_async!(tx, ModuleUpdateEvent::Update(vec![value]));
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends the arguments of a signal each time it is emitted.
+async fn watch_signal(
+    dbus: &zbus::Connection,
+    path: &str,
+    interface: &str,
+    signal: &str,
+    tx: mpsc::Sender<ModuleUpdateEvent<Vec<OwnedValue>>>,
+) -> Result<()> {
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .path(path)?
+        .interface(interface)?
+        .member(signal)?
+        .build();
+
+    let mut stream = MessageStream::for_match_rule(rule, dbus, None).await?;
+
+    while let Some(message) = stream.next().await {
+        let message = message?;
+
+        let args = if message
+            .body_signature()
+            .map_or(true, |sig| sig.as_str().is_empty())
+        {
+            vec![]
+        } else {
+            match message.body::<Structure>() {
+                Ok(body) => body
+                    .fields()
+                    .iter()
+                    .cloned()
+                    .map(OwnedValue::from)
+                    .collect(),
+                Err(err) => {
+                    error!("Failed to read arguments of signal '{signal}': {err:?}");
+                    continue;
+                }
+            }
+        };
+
+        send_async!(tx, ModuleUpdateEvent::Update(args));
+    }
+
+    Ok(())
+}
+
+/// Replaces the tokens in `format` with the given values.
+///
+/// `{value}` is the first value, and `{argN}` is the value at index `N`.
+/// Fields of dictionary values can be accessed with `{value[key]}` or `{argN[key]}`.
+fn render(format: &str, values: &[OwnedValue]) -> String {
+    let mut output = String::with_capacity(format.len());
+    let mut rest = format;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        match resolve_token(&rest[1..end], values) {
+            Some(value) => output.push_str(&value),
+            // not a token, so keep it as-is
+            None => output.push_str(&rest[..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Gets the string value of a token, without its braces.
+fn resolve_token(token: &str, values: &[OwnedValue]) -> Option<String> {
+    let (name, key) = match token.split_once('[') {
+        Some((name, key)) => (name, Some(key.strip_suffix(']')?)),
+        None => (token, None),
+    };
+
+    let index = match name {
+        "value" => 0,
+        _ => name.strip_prefix("arg")?.parse().ok()?,
+    };
+
+    let Some(value) = values.get(index) else {
+        return Some(String::new());
+    };
+
+    match key {
+        Some(key) => Some(
+            dict_entries(value)
+                .and_then(|entries| entries.get(key).map(|value| format_value(value)))
+                .unwrap_or_default(),
+        ),
+        None => Some(format_value(value)),
+    }
+}
+
+/// Gets the entries of a dictionary value with string keys,
+/// such as the `a{sv}` maps used by many services.
+fn dict_entries(value: &Value) -> Option<HashMap<String, OwnedValue>> {
+    match value {
+        Value::Value(value) => dict_entries(value),
+        Value::Dict(dict) => HashMap::try_from(dict.clone()).ok(),
+        _ => None,
+    }
+}
+
+/// Converts a D-Bus value into a string for display.
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::U8(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::I16(value) => value.to_string(),
+        Value::U16(value) => value.to_string(),
+        Value::I32(value) => value.to_string(),
+        Value::U32(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::F64(value) => value.to_string(),
+        Value::Str(value) => value.to_string(),
+        Value::Signature(value) => value.to_string(),
+        Value::ObjectPath(value) => value.to_string(),
+        Value::Value(value) => format_value(value),
+        Value::Array(array) => array
+            .get()
+            .iter()
+            .map(format_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Structure(structure) => structure
+            .fields()
+            .iter()
+            .map(format_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Dict(_) => dict_entries(value).map_or_else(String::new, |entries| {
+            let mut entries = entries
+                .iter()
+                .map(|(key, value)| format!("{key}: {}", format_value(value)))
+                .collect::<Vec<_>>();
+
+            entries.sort();
+            entries.join(", ")
+        }),
+        value => format!("{value:?}"),
+    }
+}
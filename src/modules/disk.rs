@@ -0,0 +1,500 @@
+use crate::config::{Alert, Alerts, CommonConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::shared::shell_quote;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::script::Script;
+use crate::{glib_recv, module_impl, polling, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, ProgressBar};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use sysinfo::{DiskExt, RefreshKind, System, SystemExt};
+use tokio::sync::{broadcast, mpsc};
+use tracing::debug;
+
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+
+/// `/proc/diskstats` always counts 512-byte sectors,
+/// regardless of the device's real sector size.
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiskModule {
+    /// The mount points to show on the bar, each as its own label.
+    /// The popup lists every mount.
+    ///
+    /// **Default**: `["/"]`
+    #[serde(default = "default_mounts")]
+    mounts: Vec<String>,
+
+    /// The format string to use for each mount's label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰋊 {free} GB`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The command to run when clicking a mount in the popup.
+    /// `{path}` is replaced with the quoted mount point.
+    ///
+    /// **Default**: `xdg-open {path}`
+    #[serde(default = "default_open_command")]
+    open_command: String,
+
+    /// Classes to add and scripts to run when the percentage of space used on a mount
+    /// crosses a threshold. Each mount's label is checked separately.
+    /// See [alerts](alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<Alert>,
+
+    /// Number of seconds between refresh.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_mounts() -> Vec<String> {
+    vec![String::from("/")]
+}
+
+fn default_format() -> String {
+    String::from("󰋊 {free} GB")
+}
+
+fn default_open_command() -> String {
+    String::from("xdg-open {path}")
+}
+
+const fn default_interval() -> u64 {
+    5
+}
+
+/// A snapshot of a single mounted filesystem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mount {
+    path: PathBuf,
+    /// The block device, such as `/dev/nvme0n1p2`.
+    device: String,
+    /// Total space, in bytes.
+    total: u64,
+    /// Space available to unprivileged users, in bytes.
+    available: u64,
+    /// Read throughput of the device, in bytes per second.
+    /// `None` for filesystems which are not backed by a block device.
+    read: Option<f64>,
+    /// Write throughput of the device, in bytes per second.
+    write: Option<f64>,
+}
+
+impl Mount {
+    fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used() as f64 / self.total as f64
+        }
+    }
+
+    /// Replaces the formatting tokens in `format`.
+    ///
+    /// Throughput tokens are left as-is for mounts without a block device.
+    fn format(&self, format: &str) -> String {
+        let mut label = format
+            .replace("{mount}", &self.path.to_string_lossy())
+            .replace("{device}", &self.device)
+            .replace(
+                "{free}",
+                &format!("{:.1}", bytes_to_gigabytes(self.available)),
+            )
+            .replace("{used}", &format!("{:.1}", bytes_to_gigabytes(self.used())))
+            .replace("{total}", &format!("{:.1}", bytes_to_gigabytes(self.total)))
+            .replace("{percent}", &format!("{:0>2.0}", self.fraction() * 100.0));
+
+        if let Some(read) = self.read {
+            label = label.replace("{read}", &format!("{:.1}", bytes_to_megabytes(read)));
+        }
+
+        if let Some(write) = self.write {
+            label = label.replace("{write}", &format!("{:.1}", bytes_to_megabytes(write)));
+        }
+
+        label
+    }
+}
+
+/// Total bytes read and written by a block device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DiskIo {
+    read: u64,
+    written: u64,
+}
+
+/// Parses `/proc/diskstats` into the I/O totals of each block device, by name.
+fn parse_diskstats(contents: &str) -> HashMap<String, DiskIo> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            // see https://www.kernel.org/doc/Documentation/ABI/testing/procfs-diskstats
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+
+            let name = fields.get(2)?;
+            let sectors_read = fields.get(5)?.parse::<u64>().ok()?;
+            let sectors_written = fields.get(9)?.parse::<u64>().ok()?;
+
+            Some((
+                (*name).to_string(),
+                DiskIo {
+                    read: sectors_read * SECTOR_SIZE,
+                    written: sectors_written * SECTOR_SIZE,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Gets the kernel name of a block device, as used in `/proc/diskstats`.
+///
+/// Device mapper paths such as `/dev/mapper/root` are symlinks,
+/// so are resolved to their `dm-<n>` device first.
+fn kernel_name(device: &str) -> Option<String> {
+    let path = fs::canonicalize(device).ok()?;
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Tracks I/O totals between refreshes to calculate throughput.
+#[derive(Debug, Default)]
+struct Throughput {
+    previous: HashMap<String, DiskIo>,
+    last_refresh: Option<Instant>,
+}
+
+impl Throughput {
+    /// Reads the current totals and returns the read and write rates
+    /// of each device since the last refresh.
+    fn refresh(&mut self) -> HashMap<String, (f64, f64)> {
+        let current = fs::read_to_string(DISKSTATS_PATH)
+            .map(|contents| parse_diskstats(&contents))
+            .unwrap_or_default();
+
+        let now = Instant::now();
+        let elapsed = self
+            .last_refresh
+            .map(|last| now.duration_since(last).as_secs_f64())
+            .filter(|elapsed| *elapsed > 0.0);
+
+        let rates = current
+            .iter()
+            .map(|(name, io)| {
+                let rate = |current: u64, previous: u64| {
+                    elapsed.map_or(0.0, |elapsed| {
+                        current.saturating_sub(previous) as f64 / elapsed
+                    })
+                };
+
+                let previous = self.previous.get(name).copied().unwrap_or(*io);
+
+                (
+                    name.clone(),
+                    (
+                        rate(io.read, previous.read),
+                        rate(io.written, previous.written),
+                    ),
+                )
+            })
+            .collect();
+
+        self.previous = current;
+        self.last_refresh = Some(now);
+
+        rates
+    }
+}
+
+/// Reads the usage and throughput of each mounted filesystem.
+fn read_mounts(sys: &mut System, throughput: &mut Throughput) -> Vec<Mount> {
+    // removable drives come and go, so the list is reloaded every time.
+    sys.refresh_disks_list();
+    sys.refresh_disks();
+
+    let rates = throughput.refresh();
+
+    let mut mounts = sys
+        .disks()
+        .iter()
+        .map(|disk| {
+            let device = disk.name().to_string_lossy().into_owned();
+            let rate = kernel_name(&device).and_then(|name| rates.get(&name).copied());
+
+            Mount {
+                path: disk.mount_point().to_path_buf(),
+                device,
+                total: disk.total_space(),
+                available: disk.available_space(),
+                read: rate.map(|(read, _)| read),
+                write: rate.map(|(_, write)| write),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    mounts.sort_by(|a, b| a.path.cmp(&b.path));
+    mounts
+}
+
+impl Module<Button> for DiskModule {
+    type SendMessage = Snapshot<Vec<Mount>>;
+    type ReceiveMessage = ();
+
+    module_impl!("disk");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let interval = Duration::from_secs(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut sys = System::new_with_specifics(RefreshKind::new());
+            let mut throughput = Throughput::default();
+
+            loop {
+                let mounts = read_mounts(&mut sys, &mut throughput);
+                send_async!(tx, ModuleUpdateEvent::snapshot(mounts));
+
+                polling::sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let container = gtk::Box::new(Orientation::Horizontal, 10);
+        button.add(&container);
+
+        let labels = self
+            .mounts
+            .iter()
+            .map(|_| {
+                let label = Label::builder().use_markup(true).build();
+                label.add_class("label");
+                container.add(&label);
+                label
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let paths = self.mounts.clone();
+            let format = self.format.clone();
+            let mut alerts = paths
+                .iter()
+                .map(|_| Alerts::new(self.alerts.clone()))
+                .collect::<Vec<_>>();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, mounts => {
+                for ((path, label), alerts) in paths.iter().zip(&labels).zip(&mut alerts) {
+                    match mounts.iter().find(|mount| mount.path == Path::new(path)) {
+                        Some(mount) => {
+                            label.set_label_escaped(&mount.format(&format));
+                            alerts.update(label, mount.fraction() * 100.0);
+                            label.show();
+                        }
+                        // not currently mounted
+                        None => {
+                            alerts.clear(label);
+                            label.hide();
+                        }
+                    }
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        {
+            let container = container.clone();
+            let open_command = self.open_command;
+            let tx = context.tx.clone();
+
+            glib_recv!(rx, mounts => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                for mount in mounts.iter() {
+                    let row = mount_row(mount);
+
+                    let command = open_command
+                        .replace("{path}", &shell_quote(&mount.path.to_string_lossy()));
+                    let tx = tx.clone();
+
+                    row.connect_clicked(move |_| {
+                        debug!("executing command: '{command}'");
+                        Script::from(command.as_str()).run_as_oneshot(None);
+                        try_send!(tx, ModuleUpdateEvent::ClosePopup);
+                    });
+
+                    container.add(&row);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for a mount.
+fn mount_row(mount: &Mount) -> Button {
+    let button = Button::new();
+    button.add_class("mount");
+
+    let container = gtk::Box::new(Orientation::Vertical, 2);
+
+    let header = gtk::Box::new(Orientation::Horizontal, 10);
+
+    let path = Label::builder()
+        .label(&*mount.path.to_string_lossy())
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    path.add_class("path");
+    header.add(&path);
+
+    let device = Label::new(Some(&mount.device));
+    device.add_class("device");
+    header.add(&device);
+
+    container.add(&header);
+
+    let progress = ProgressBar::new();
+    progress.set_fraction(mount.fraction());
+    progress.add_class("progress");
+    container.add(&progress);
+
+    let usage = Label::builder()
+        .label(mount.format("{used} / {total} GB ({percent}%)"))
+        .halign(Align::Start)
+        .build();
+    usage.add_class("usage");
+    container.add(&usage);
+
+    if mount.read.is_some() {
+        let io = Label::builder()
+            .label(mount.format("󰁅 {read} MB/s 󰁝 {write} MB/s"))
+            .halign(Align::Start)
+            .build();
+        io.add_class("io");
+        container.add(&io);
+    }
+
+    button.add(&container);
+    button
+}
+
+fn bytes_to_gigabytes(b: u64) -> f64 {
+    const BYTES_IN_GIGABYTE: f64 = 1_000_000_000.0;
+    b as f64 / BYTES_IN_GIGABYTE
+}
+
+fn bytes_to_megabytes(b: f64) -> f64 {
+    const BYTES_IN_MEGABYTE: f64 = 1_000_000.0;
+    b / BYTES_IN_MEGABYTE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diskstats() {
+        let contents = "\
+ 259       0 nvme0n1 5432 12 1024 300 876 3 2048 900 0 1200 1200 0 0 0 0
+ 259       1 nvme0n1p1 20 0 16 1 0 0 0 0 0 4 1 0 0 0 0
+ 254       0 dm-0 100 0 8 10 50 0 4 20 0 30 30 0 0 0 0";
+
+        let stats = parse_diskstats(contents);
+
+        assert_eq!(stats.len(), 3);
+        assert_eq!(
+            stats["nvme0n1"],
+            DiskIo {
+                read: 1024 * 512,
+                written: 2048 * 512
+            }
+        );
+        assert_eq!(stats["dm-0"].written, 4 * 512);
+    }
+
+    #[test]
+    fn test_format() {
+        let mount = Mount {
+            path: PathBuf::from("/home"),
+            device: String::from("/dev/sda1"),
+            total: 500_000_000_000,
+            available: 125_000_000_000,
+            read: None,
+            write: None,
+        };
+
+        assert_eq!(
+            mount.format("{mount} {free}/{total} GB {percent}% {read}"),
+            "/home 125.0/500.0 GB 75% {read}"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::clients::dnd::{self, Status};
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DndModule {
+    /// The notification daemon to control.
+    ///
+    /// **Valid options**: `auto`, `swaync`, `mako`, `dunst`
+    /// <br>
+    /// **Default**: `auto`
+    #[serde(default)]
+    backend: dnd::Backend,
+
+    /// Icon to show when do-not-disturb is enabled.
+    ///
+    /// **Default**: `󰂛`
+    #[serde(default = "default_icon_on")]
+    icon_on: String,
+
+    /// Icon to show when do-not-disturb is disabled.
+    ///
+    /// **Default**: `󰂚`
+    #[serde(default = "default_icon_off")]
+    icon_off: String,
+
+    /// Whether to show the number of pending notifications
+    /// next to the icon.
+    /// The count is hidden while there are none.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    show_count: bool,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon_on() -> String {
+    String::from("󰂛")
+}
+
+fn default_icon_off() -> String {
+    String::from("󰂚")
+}
+
+impl Module<Button> for DndModule {
+    type SendMessage = Status;
+    /// Whether to enable do-not-disturb.
+    type ReceiveMessage = bool;
+
+    module_impl!("dnd");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.ironbar.clients.borrow_mut().dnd(self.backend)?;
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut status_rx = client.subscribe();
+
+                match client.status().await {
+                    Ok(status) => send_async!(tx, ModuleUpdateEvent::Update(status)),
+                    Err(err) => error!("Failed to get do-not-disturb status: {err:?}"),
+                }
+
+                while let Ok(status) = status_rx.recv().await {
+                    send_async!(tx, ModuleUpdateEvent::Update(status));
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(enabled) = rx.recv().await {
+                if let Err(err) = client.set_dnd(enabled).await {
+                    error!("Failed to set do-not-disturb: {err:?}");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_class("inactive");
+
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+        button.add(&container);
+
+        let icon = Label::new(Some(&self.icon_off));
+        icon.add_class("icon");
+        container.add(&icon);
+
+        let count = Label::new(None);
+        count.add_class("count");
+        count.set_no_show_all(true);
+        container.add(&count);
+
+        let enabled = Rc::new(Cell::new(false));
+
+        {
+            let tx = context.controller_tx.clone();
+            let enabled = enabled.clone();
+
+            button.connect_clicked(move |_| {
+                try_send!(tx, !enabled.get());
+            });
+        }
+
+        {
+            let button = button.clone();
+
+            glib_recv!(context.subscribe(), status => {
+                enabled.set(status.dnd);
+
+                if status.dnd {
+                    icon.set_label(&self.icon_on);
+                    button.remove_class("inactive");
+                    button.add_class("active");
+                } else {
+                    icon.set_label(&self.icon_off);
+                    button.remove_class("active");
+                    button.add_class("inactive");
+                }
+
+                count.set_label(&status.count.to_string());
+                count.set_visible(self.show_count && status.count > 0);
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
@@ -0,0 +1,189 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Label;
+use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FileModule {
+    /// The path to the file to display.
+    /// This can be absolute, or relative to the working directory.
+    ///
+    /// **Required**
+    path: PathBuf,
+
+    /// The format string to use for the label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{content}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The text to show while the file does not exist.
+    /// The label is hidden if this is empty.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    missing: String,
+
+    /// The maximum number of bytes to read from the file.
+    ///
+    /// **Default**: `4096`
+    #[serde(default = "default_max_size")]
+    max_size: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{content}")
+}
+
+const fn default_max_size() -> usize {
+    4096
+}
+
+impl FileModule {
+    /// Reads the file and formats its contents,
+    /// or returns the `missing` text if the file does not exist.
+    fn read(&self) -> String {
+        let content = match fs::read(&self.path) {
+            Ok(mut bytes) => {
+                bytes.truncate(self.max_size);
+                String::from_utf8_lossy(&bytes).into_owned()
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => return self.missing.clone(),
+            Err(err) => {
+                error!("Failed to read '{}': {err:?}", self.path.display());
+                return self.missing.clone();
+            }
+        };
+
+        let content = content.trim_end();
+
+        let mut text = self.format.replace("{content}", content);
+
+        // only replace line tokens which are used, since files can be long
+        for (index, line) in content.lines().enumerate() {
+            let token = format!("{{line{}}}", index + 1);
+            if !text.contains(&token) {
+                continue;
+            }
+
+            text = text.replace(&token, line.trim_end());
+        }
+
+        text
+    }
+}
+
+impl Module<Label> for FileModule {
+    type SendMessage = String;
+    type ReceiveMessage = ();
+
+    module_impl!("file");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let module = self.clone();
+        let tx = context.tx.clone();
+
+        // files are often replaced rather than written to,
+        // which removes any watch on the file itself,
+        // so the parent directory is watched instead.
+        let parent = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), PathBuf::from);
+
+        let file_name = self
+            .path
+            .file_name()
+            .ok_or_else(|| eyre!("Path '{}' is not a file", self.path.display()))?
+            .to_os_string();
+
+        let (changed_tx, mut changed_rx) = mpsc::channel(1);
+
+        let mut watcher = recommended_watcher(move |res: notify::Result<Event>| match res {
+            Ok(event)
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) =>
+            {
+                let is_file = event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == Some(file_name.as_os_str()));
+
+                if is_file {
+                    // channel only needs to hold a single pending change
+                    let _ = changed_tx.try_send(());
+                }
+            }
+            Err(err) => error!("Error occurred when watching file: {err:?}"),
+            _ => {}
+        })?;
+
+        watcher.watch(&parent, RecursiveMode::NonRecursive)?;
+        debug!("Watching '{}' for changes", module.path.display());
+
+        spawn(async move {
+            // avoid watcher from dropping
+            let _watcher = watcher;
+
+            let mut last = module.read();
+            send_async!(tx, ModuleUpdateEvent::Update(last.clone()));
+
+            while changed_rx.recv().await.is_some() {
+                let text = module.read();
+
+                if text != last {
+                    last = text.clone();
+                    send_async!(tx, ModuleUpdateEvent::Update(text));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Label>> {
+        let label = Label::builder().use_markup(true).build();
+
+        {
+            let label = label.clone();
+            glib_recv!(context.subscribe(), text => {
+                label.set_label_escaped(&text);
+                label.set_visible(!text.is_empty());
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: label,
+            popup: None,
+        })
+    }
+}
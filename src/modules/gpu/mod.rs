@@ -0,0 +1,401 @@
+mod nvml;
+mod sysfs;
+
+use self::nvml::Nvml;
+use crate::config::{Alert, Alerts, CommonConfig, ModuleOrientation};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, polling, send_async, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Label;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GpuModule {
+    /// List of strings including formatting tokens.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `["󰢮 {gpu_percent}%"]`
+    #[serde(default = "default_format")]
+    format: Vec<String>,
+
+    /// Number of seconds between refresh.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The GPU used by tokens without a GPU index.
+    /// This is either the index of the GPU, starting from `0`,
+    /// or part of its name, such as `RTX`.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    device: DeviceSelector,
+
+    /// The backends to read GPUs from, in order.
+    /// GPU indices count through the GPUs of each backend in turn.
+    ///
+    /// **Valid options**: `nvml`, `sysfs`
+    /// <br>
+    /// **Default**: `["nvml", "sysfs"]`
+    #[serde(default = "default_backends")]
+    backends: Vec<GpuBackend>,
+
+    /// The orientation of text for the labels.
+    ///
+    /// **Valid options**: `horizontal`, `vertical`, `h`, `v`
+    /// <br>
+    /// **Default** : `horizontal`
+    #[serde(default)]
+    orientation: ModuleOrientation,
+
+    /// The orientation by which the labels are laid out.
+    ///
+    /// **Valid options**: `horizontal`, `vertical`, `h`, `v`
+    /// <br>
+    /// **Default** : `horizontal`
+    direction: Option<ModuleOrientation>,
+
+    /// Classes to add and scripts to run when a token's value crosses a threshold.
+    /// See [alerts](#alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<GpuAlert>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GpuAlert {
+    /// The token to check, without braces,
+    /// such as `gpu_percent` or `temp_c:1`.
+    ///
+    /// **Required**
+    token: String,
+
+    #[serde(flatten)]
+    alert: Alert,
+}
+
+fn default_format() -> Vec<String> {
+    vec![String::from("󰢮 {gpu_percent}%")]
+}
+
+const fn default_interval() -> u64 {
+    5
+}
+
+fn default_backends() -> Vec<GpuBackend> {
+    vec![GpuBackend::Nvml, GpuBackend::Sysfs]
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DeviceSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl Default for DeviceSelector {
+    fn default() -> Self {
+        Self::Index(0)
+    }
+}
+
+impl DeviceSelector {
+    fn find<'a>(&self, gpus: &'a [GpuStats]) -> Option<&'a GpuStats> {
+        match self {
+            Self::Index(index) => gpus.get(*index),
+            Self::Name(name) => {
+                let name = name.to_lowercase();
+                gpus.iter()
+                    .find(|gpu| gpu.name.to_lowercase().contains(&name))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum GpuBackend {
+    /// NVIDIA GPUs, using the NVIDIA Management Library
+    /// which is installed with the proprietary driver.
+    Nvml,
+    /// GPUs whose driver reports utilization in sysfs, such as `amdgpu`.
+    Sysfs,
+}
+
+/// A snapshot of a single GPU's stats.
+/// Stats which the driver does not report are `None`.
+#[derive(Debug, Clone, Default)]
+pub struct GpuStats {
+    name: String,
+    /// Utilization, as a percentage.
+    utilization: Option<u32>,
+    /// VRAM in use, in bytes.
+    vram_used: Option<u64>,
+    /// Total VRAM, in bytes.
+    vram_total: Option<u64>,
+    /// Temperature, in celsius.
+    temperature: Option<f64>,
+    /// Power draw, in watts.
+    power: Option<f64>,
+}
+
+impl Module<gtk::Box> for GpuModule {
+    type SendMessage = HashMap<String, String>;
+    type ReceiveMessage = ();
+
+    module_impl!("gpu");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let interval = Duration::from_secs(self.interval);
+        let device = self.device.clone();
+        let backends = self.backends.clone();
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            // most systems do not have an NVIDIA GPU, so failing to load is expected.
+            let nvml = if backends.contains(&GpuBackend::Nvml) {
+                Nvml::load()
+                    .inspect_err(|err| debug!("NVML is not available: {err:?}"))
+                    .ok()
+            } else {
+                None
+            };
+
+            let sysfs_devices = if backends.contains(&GpuBackend::Sysfs) {
+                sysfs::find_devices()
+            } else {
+                vec![]
+            };
+
+            if nvml.is_none() && sysfs_devices.is_empty() {
+                warn!("No supported GPUs found");
+                return;
+            }
+
+            loop {
+                let mut gpus = vec![];
+                for backend in &backends {
+                    match backend {
+                        GpuBackend::Nvml => {
+                            if let Some(nvml) = &nvml {
+                                gpus.extend(nvml.stats());
+                            }
+                        }
+                        GpuBackend::Sysfs => {
+                            gpus.extend(sysfs_devices.iter().map(|device| sysfs::stats(device)));
+                        }
+                    }
+                }
+
+                let mut tokens = HashMap::new();
+
+                for (index, gpu) in gpus.iter().enumerate() {
+                    insert_tokens(&mut tokens, gpu, &format!(":{index}"));
+                }
+
+                if let Some(gpu) = device.find(&gpus) {
+                    insert_tokens(&mut tokens, gpu, "");
+                }
+
+                send_async!(tx, ModuleUpdateEvent::Update(tokens));
+                polling::sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let re = Regex::new(r"\{([^}]+)}")?;
+
+        let layout = match self.direction {
+            Some(orientation) => orientation,
+            None => self.orientation,
+        };
+
+        let container = gtk::Box::new(layout.into(), 10);
+
+        let mut labels = Vec::new();
+
+        for format in &self.format {
+            let label = Label::builder().label(format).use_markup(true).build();
+
+            label.add_class("item");
+            label.set_angle(self.orientation.to_angle());
+
+            container.add(&label);
+            labels.push(label);
+        }
+
+        // alerts apply to each label whose format contains their token
+        let mut alerts = vec![];
+        for (format, label) in self.format.iter().zip(&labels) {
+            for alert in &self.alerts {
+                if format.contains(&format!("{{{}}}", alert.token)) {
+                    alerts.push((
+                        label.clone(),
+                        alert.token.clone(),
+                        Alerts::new(vec![alert.alert.clone()]),
+                    ));
+                }
+            }
+        }
+
+        {
+            let formats = self.format;
+            glib_recv!(context.subscribe(), tokens => {
+                for (format, label) in formats.iter().zip(&labels) {
+                    let format_compiled = re.replace_all(format, |caps: &Captures| {
+                        tokens.get(&caps[1])
+                            .unwrap_or(&caps[0].to_string())
+                            .to_string()
+                    });
+
+                    label.set_label_escaped(format_compiled.as_ref());
+                }
+
+                // tokens are left out while the driver does not report them
+                for (label, token, alerts) in &mut alerts {
+                    match tokens.get(token.as_str()).and_then(|value| value.parse().ok()) {
+                        Some(value) => alerts.update(label, value),
+                        None => alerts.clear(label),
+                    }
+                }
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: container,
+            popup: None,
+        })
+    }
+}
+
+/// Adds the formatting tokens for a GPU,
+/// with each token name ending in `suffix`.
+///
+/// Tokens for stats which the GPU does not report are left out.
+fn insert_tokens(tokens: &mut HashMap<String, String>, gpu: &GpuStats, suffix: &str) {
+    let mut insert = |name: &str, value: String| {
+        tokens.insert(format!("{name}{suffix}"), value);
+    };
+
+    insert("name", gpu.name.clone());
+
+    if let Some(utilization) = gpu.utilization {
+        insert("gpu_percent", format!("{utilization:0>2}"));
+    }
+
+    if let Some(used) = gpu.vram_used {
+        insert("vram_used", format!("{:.1}", bytes_to_gigabytes(used)));
+    }
+
+    if let Some(total) = gpu.vram_total {
+        insert("vram_total", format!("{:.1}", bytes_to_gigabytes(total)));
+    }
+
+    if let (Some(used), Some(total)) = (gpu.vram_used, gpu.vram_total) {
+        if total > 0 {
+            insert(
+                "vram_percent",
+                format!("{:0>2.0}", used as f64 / total as f64 * 100.0),
+            );
+        }
+    }
+
+    if let Some(temperature) = gpu.temperature {
+        insert("temp_c", format!("{temperature:.0}"));
+        insert("temp_f", format!("{:.0}", c_to_f(temperature)));
+    }
+
+    if let Some(power) = gpu.power {
+        insert("power", format!("{power:.0}"));
+    }
+}
+
+/// Converts celsius to fahrenheit.
+fn c_to_f(c: f64) -> f64 {
+    c * 9.0 / 5.0 + 32.0
+}
+
+fn bytes_to_gigabytes(b: u64) -> f64 {
+    const BYTES_IN_GIGABYTE: f64 = 1_000_000_000.0;
+    b as f64 / BYTES_IN_GIGABYTE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_tokens() {
+        let gpu = GpuStats {
+            name: String::from("Radeon RX 7800 XT"),
+            utilization: Some(7),
+            vram_used: Some(4_300_000_000),
+            vram_total: Some(17_000_000_000),
+            temperature: Some(50.0),
+            power: None,
+        };
+
+        let mut tokens = HashMap::new();
+        insert_tokens(&mut tokens, &gpu, ":1");
+
+        assert_eq!(tokens["name:1"], "Radeon RX 7800 XT");
+        assert_eq!(tokens["gpu_percent:1"], "07");
+        assert_eq!(tokens["vram_used:1"], "4.3");
+        assert_eq!(tokens["vram_total:1"], "17.0");
+        assert_eq!(tokens["vram_percent:1"], "25");
+        assert_eq!(tokens["temp_f:1"], "122");
+        assert!(!tokens.contains_key("power:1"));
+    }
+
+    #[test]
+    fn test_device_selector() {
+        let gpus = [
+            GpuStats {
+                name: String::from("AMD Radeon Graphics"),
+                ..GpuStats::default()
+            },
+            GpuStats {
+                name: String::from("NVIDIA GeForce RTX 4070"),
+                ..GpuStats::default()
+            },
+        ];
+
+        let name = |selector: DeviceSelector| selector.find(&gpus).map(|gpu| gpu.name.as_str());
+
+        assert_eq!(name(DeviceSelector::Index(0)), Some("AMD Radeon Graphics"));
+        assert_eq!(name(DeviceSelector::Index(2)), None);
+        assert_eq!(
+            name(DeviceSelector::Name(String::from("rtx"))),
+            Some("NVIDIA GeForce RTX 4070")
+        );
+    }
+}
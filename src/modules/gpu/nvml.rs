@@ -0,0 +1,186 @@
+//! Minimal bindings to the NVIDIA Management Library,
+//! which is loaded at runtime so that Ironbar does not depend on the NVIDIA driver.
+
+use super::GpuStats;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use libloading::Library;
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
+use std::ptr;
+
+const LIBRARY_NAME: &str = "libnvidia-ml.so.1";
+
+/// `NVML_SUCCESS`
+const SUCCESS: c_int = 0;
+
+/// `NVML_TEMPERATURE_GPU`
+const TEMPERATURE_GPU: c_int = 0;
+
+/// `NVML_DEVICE_NAME_V2_BUFFER_SIZE`
+const NAME_BUFFER_SIZE: usize = 96;
+
+type Device = *mut c_void;
+
+// fields must match `nvml.h`, even if unused.
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct Utilization {
+    gpu: c_uint,
+    memory: c_uint,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct Memory {
+    total: u64,
+    free: u64,
+    used: u64,
+}
+
+type InitFn = unsafe extern "C" fn() -> c_int;
+type ShutdownFn = unsafe extern "C" fn() -> c_int;
+type GetCountFn = unsafe extern "C" fn(*mut c_uint) -> c_int;
+type GetHandleFn = unsafe extern "C" fn(c_uint, *mut Device) -> c_int;
+type GetNameFn = unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int;
+type GetUtilizationFn = unsafe extern "C" fn(Device, *mut Utilization) -> c_int;
+type GetMemoryFn = unsafe extern "C" fn(Device, *mut Memory) -> c_int;
+type GetTemperatureFn = unsafe extern "C" fn(Device, c_int, *mut c_uint) -> c_int;
+type GetPowerFn = unsafe extern "C" fn(Device, *mut c_uint) -> c_int;
+
+/// A loaded and initialized instance of NVML.
+///
+/// NVML is shut down again when this is dropped.
+pub struct Nvml {
+    init: InitFn,
+    shutdown: ShutdownFn,
+    get_count: GetCountFn,
+    get_handle: GetHandleFn,
+    get_name: GetNameFn,
+    get_utilization: GetUtilizationFn,
+    get_memory: GetMemoryFn,
+    get_temperature: GetTemperatureFn,
+    get_power: GetPowerFn,
+
+    /// Kept loaded for as long as the function pointers above are in use.
+    _library: Library,
+}
+
+impl Nvml {
+    /// Loads and initializes NVML.
+    ///
+    /// This fails if the NVIDIA driver is not installed.
+    pub fn load() -> Result<Self> {
+        // SAFETY: NVML does not run any initialization code on load.
+        let library = unsafe { Library::new(LIBRARY_NAME) }?;
+
+        // SAFETY: the types match the signatures in `nvml.h`.
+        let nvml = unsafe {
+            Self {
+                init: symbol(&library, "nvmlInit_v2")?,
+                shutdown: symbol(&library, "nvmlShutdown")?,
+                get_count: symbol(&library, "nvmlDeviceGetCount_v2")?,
+                get_handle: symbol(&library, "nvmlDeviceGetHandleByIndex_v2")?,
+                get_name: symbol(&library, "nvmlDeviceGetName")?,
+                get_utilization: symbol(&library, "nvmlDeviceGetUtilizationRates")?,
+                get_memory: symbol(&library, "nvmlDeviceGetMemoryInfo")?,
+                get_temperature: symbol(&library, "nvmlDeviceGetTemperature")?,
+                get_power: symbol(&library, "nvmlDeviceGetPowerUsage")?,
+                _library: library,
+            }
+        };
+
+        // SAFETY: the library is loaded, and the function takes no arguments.
+        let code = unsafe { (nvml.init)() };
+
+        // dropping calls `nvmlShutdown`, which just returns an error if not initialized.
+        if code != SUCCESS {
+            return Err(eyre!("Failed to initialize NVML (error {code})"));
+        }
+
+        Ok(nvml)
+    }
+
+    /// Gets the current stats of each NVIDIA GPU.
+    pub fn stats(&self) -> Vec<GpuStats> {
+        let mut count = 0;
+
+        // SAFETY: all out-pointers passed to NVML below point to valid, initialized values,
+        // and device handles are only used while NVML is initialized.
+        unsafe {
+            if (self.get_count)(&mut count) != SUCCESS {
+                return vec![];
+            }
+
+            (0..count)
+                .filter_map(|index| {
+                    let mut device = ptr::null_mut();
+                    if (self.get_handle)(index, &mut device) != SUCCESS {
+                        return None;
+                    }
+
+                    Some(self.device_stats(device))
+                })
+                .collect()
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `device` must be a valid handle.
+    unsafe fn device_stats(&self, device: Device) -> GpuStats {
+        let mut name = [0 as c_char; NAME_BUFFER_SIZE];
+        let name =
+            if (self.get_name)(device, name.as_mut_ptr(), NAME_BUFFER_SIZE as c_uint) == SUCCESS {
+                CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned()
+            } else {
+                String::from("NVIDIA GPU")
+            };
+
+        let mut utilization = Utilization::default();
+        let utilization =
+            ((self.get_utilization)(device, &mut utilization) == SUCCESS).then_some(utilization);
+
+        let mut memory = Memory::default();
+        let memory = ((self.get_memory)(device, &mut memory) == SUCCESS).then_some(memory);
+
+        let mut temperature = 0;
+        let temperature = ((self.get_temperature)(device, TEMPERATURE_GPU, &mut temperature)
+            == SUCCESS)
+            .then_some(f64::from(temperature));
+
+        // reported in milliwatts
+        let mut power = 0;
+        let power =
+            ((self.get_power)(device, &mut power) == SUCCESS).then_some(f64::from(power) / 1000.0);
+
+        GpuStats {
+            name,
+            utilization: utilization.map(|utilization| utilization.gpu),
+            vram_used: memory.as_ref().map(|memory| memory.used),
+            vram_total: memory.map(|memory| memory.total),
+            temperature,
+            power,
+        }
+    }
+}
+
+/// Gets a function from the library.
+///
+/// # Safety
+///
+/// `T` must match the type of the symbol.
+unsafe fn symbol<T: Copy>(library: &Library, name: &str) -> Result<T> {
+    let name = format!("{name}\0");
+    Ok(*library.get::<T>(name.as_bytes())?)
+}
+
+impl Drop for Nvml {
+    fn drop(&mut self) {
+        // SAFETY: NVML was initialized in `load`.
+        unsafe {
+            (self.shutdown)();
+        }
+    }
+}
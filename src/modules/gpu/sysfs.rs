@@ -0,0 +1,104 @@
+//! Reads GPU stats from sysfs, for drivers which expose utilization there,
+//! such as `amdgpu`.
+
+use super::GpuStats;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const DRM_DIR: &str = "/sys/class/drm";
+
+/// Finds the device directories of GPUs which report their utilization,
+/// sorted by card number.
+pub fn find_devices() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(DRM_DIR) else {
+        return vec![];
+    };
+
+    let mut cards = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            // connectors are also listed, as `card<n>-<connector>`
+            let number = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("card")?
+                .parse::<u32>()
+                .ok()?;
+
+            let device = entry.path().join("device");
+            device
+                .join("gpu_busy_percent")
+                .exists()
+                .then_some((number, device))
+        })
+        .collect::<Vec<_>>();
+
+    cards.sort_by_key(|(number, _)| *number);
+    cards.into_iter().map(|(_, device)| device).collect()
+}
+
+/// Reads the current stats of the GPU at `device`.
+pub fn stats(device: &Path) -> GpuStats {
+    let hwmon = find_hwmon(device);
+    let hwmon = hwmon.as_deref();
+
+    let name = fs::read_to_string(device.join("product_name"))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| fallback_name(device));
+
+    // power is reported in microwatts, and temperature in millidegrees.
+    let power = hwmon
+        .and_then(|hwmon| {
+            read_value::<f64>(&hwmon.join("power1_average"))
+                .or_else(|| read_value(&hwmon.join("power1_input")))
+        })
+        .map(|power| power / 1_000_000.0);
+
+    let temperature = hwmon
+        .and_then(|hwmon| read_value::<f64>(&hwmon.join("temp1_input")))
+        .map(|temperature| temperature / 1000.0);
+
+    GpuStats {
+        name,
+        utilization: read_value(&device.join("gpu_busy_percent")),
+        vram_used: read_value(&device.join("mem_info_vram_used")),
+        vram_total: read_value(&device.join("mem_info_vram_total")),
+        temperature,
+        power,
+    }
+}
+
+/// Finds the hardware monitor directory for a device.
+fn find_hwmon(device: &Path) -> Option<PathBuf> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .next()
+}
+
+/// Gets a name for devices which do not report their product name,
+/// using the driver and card, such as `amdgpu (card1)`.
+fn fallback_name(device: &Path) -> String {
+    let file_name = |path: PathBuf| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    };
+
+    let driver = fs::read_link(device.join("driver"))
+        .ok()
+        .and_then(file_name)
+        .unwrap_or_else(|| String::from("GPU"));
+
+    match device.parent().map(Path::to_path_buf).and_then(file_name) {
+        Some(card) => format!("{driver} ({card})"),
+        None => driver,
+    }
+}
+
+fn read_value<T: FromStr>(path: &Path) -> Option<T> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,275 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::{glib_recv, module_impl, read_lock, send_async, spawn, try_send, write_lock, Ironbar};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{cairo, Button, DrawingArea, Label, Orientation, StateFlags};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GraphModule {
+    /// The name of the history series to show,
+    /// such as `sysinfo.cpu_percent`.
+    /// Use `ironbar history` to list the recorded series.
+    ///
+    /// **Required**
+    series: String,
+
+    /// The width of the graph in the bar, in pixels.
+    ///
+    /// **Default**: `60`
+    #[serde(default = "default_width")]
+    width: i32,
+
+    /// The value at the bottom of the graph.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    min: f64,
+
+    /// The value at the top of the graph.
+    /// If not set, the highest recorded value is used.
+    ///
+    /// **Default**: `null`
+    max: Option<f64>,
+
+    /// The size of the graph in the popup, in pixels.
+    ///
+    /// **Default**: `[300, 100]`
+    #[serde(default = "default_popup_size")]
+    popup_size: (i32, i32),
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_width() -> i32 {
+    60
+}
+
+const fn default_popup_size() -> (i32, i32) {
+    (300, 100)
+}
+
+impl Module<Button> for GraphModule {
+    /// The recorded values, oldest first.
+    type SendMessage = Snapshot<Vec<f64>>;
+    type ReceiveMessage = ();
+
+    module_impl!("graph");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        if !read_lock!(Ironbar::history()).is_enabled() {
+            warn!(
+                "History is not enabled, so the graph for '{}' will be empty",
+                self.series
+            );
+        }
+
+        let series = self.series.clone();
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut rx = write_lock!(Ironbar::history()).subscribe(&series);
+
+            loop {
+                let values = read_lock!(Ironbar::history())
+                    .get(&series)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|sample| sample.value)
+                    .collect::<Vec<_>>();
+
+                send_async!(tx, ModuleUpdateEvent::snapshot(values));
+
+                match rx.recv().await {
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
+        let values = Rc::new(RefCell::new(Snapshot::default()));
+
+        let area = graph_area(&values, self.min, self.max);
+        area.set_size_request(self.width, -1);
+        button.add(&area);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let series = self.series.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, new_values => {
+                match new_values.last() {
+                    Some(value) => button.set_tooltip_text(Some(&format!("{series}: {value}"))),
+                    None => button.set_tooltip_text(Some(&series)),
+                }
+
+                values.replace(new_values);
+                area.queue_draw();
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let title = Label::new(Some(&self.series));
+        title.add_class("title");
+        container.add(&title);
+
+        let values = Rc::new(RefCell::new(Snapshot::default()));
+
+        let area = graph_area(&values, self.min, self.max);
+        area.set_size_request(self.popup_size.0, self.popup_size.1);
+        container.add(&area);
+
+        let stats = Label::new(None);
+        stats.add_class("stats");
+        container.add(&stats);
+
+        glib_recv!(rx, new_values => {
+            let text = if new_values.is_empty() {
+                String::from("No samples")
+            } else {
+                let min = new_values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = new_values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                #[allow(clippy::cast_precision_loss)]
+                let avg = new_values.iter().sum::<f64>() / new_values.len() as f64;
+                let last = new_values.last().copied().unwrap_or_default();
+
+                format!("Now {last:.1} · Min {min:.1} · Avg {avg:.1} · Max {max:.1}")
+            };
+
+            stats.set_label(&text);
+
+            values.replace(new_values);
+            area.queue_draw();
+        });
+
+        Some(container)
+    }
+}
+
+/// Creates a drawing area which plots `values` as a line graph,
+/// using the widget's foreground colour.
+fn graph_area(values: &Rc<RefCell<Snapshot<Vec<f64>>>>, min: f64, max: Option<f64>) -> DrawingArea {
+    let area = DrawingArea::new();
+    area.add_class("graph");
+
+    let values = values.clone();
+    area.connect_draw(move |area, cr| {
+        if let Err(err) = draw_graph(area, cr, &values.borrow(), min, max) {
+            error!("Failed to draw graph: {err}");
+        }
+
+        Propagation::Proceed
+    });
+
+    area
+}
+
+fn draw_graph(
+    area: &DrawingArea,
+    cr: &cairo::Context,
+    values: &[f64],
+    min: f64,
+    max: Option<f64>,
+) -> Result<(), cairo::Error> {
+    if values.len() < 2 {
+        return Ok(());
+    }
+
+    let width = f64::from(area.allocated_width());
+    let height = f64::from(area.allocated_height());
+
+    let max = max.unwrap_or_else(|| values.iter().copied().fold(min, f64::max));
+    let range = if max > min { max - min } else { 1.0 };
+
+    #[allow(clippy::cast_precision_loss)]
+    let step = width / (values.len() - 1) as f64;
+
+    let point = |(i, value): (usize, &f64)| {
+        #[allow(clippy::cast_precision_loss)]
+        let x = i as f64 * step;
+        let y = height - ((value - min) / range).clamp(0.0, 1.0) * height;
+        (x, y)
+    };
+
+    let color = area.style_context().color(StateFlags::NORMAL);
+
+    cr.move_to(0.0, height);
+    for (x, y) in values.iter().enumerate().map(point) {
+        cr.line_to(x, y);
+    }
+    cr.line_to(width, height);
+    cr.close_path();
+
+    cr.set_source_rgba(
+        color.red(),
+        color.green(),
+        color.blue(),
+        color.alpha() * 0.25,
+    );
+    cr.fill()?;
+
+    for (x, y) in values.iter().enumerate().map(point) {
+        cr.line_to(x, y);
+    }
+
+    cr.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+    cr.set_line_width(1.5);
+    cr.stroke()
+}
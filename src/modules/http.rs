@@ -0,0 +1,351 @@
+use crate::config::{Alert, Alerts, CommonConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+#[cfg(feature = "history")]
+use crate::history;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Label;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Method {
+    #[default]
+    Get,
+    Post,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HttpModule {
+    /// The URL to fetch.
+    ///
+    /// **Required**
+    url: String,
+
+    /// The HTTP method to use.
+    ///
+    /// **Valid options**: `get`, `post`
+    /// <br>
+    /// **Default**: `get`
+    #[serde(default)]
+    method: Method,
+
+    /// Headers to send with each request.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    headers: HashMap<String, String>,
+
+    /// The body to send with each request.
+    ///
+    /// **Default**: `null`
+    body: Option<String>,
+
+    /// The username to use for basic authentication.
+    ///
+    /// **Default**: `null`
+    username: Option<String>,
+
+    /// The password to use for basic authentication.
+    ///
+    /// **Default**: `null`
+    password: Option<String>,
+
+    /// The token to use for bearer authentication.
+    /// Takes precedence over `username` and `password`.
+    ///
+    /// **Default**: `null`
+    token: Option<String>,
+
+    /// Number of milliseconds to wait between requests.
+    ///
+    /// **Default**: `60000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// Number of milliseconds to wait for a response before failing.
+    ///
+    /// **Default**: `10000`
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+
+    /// Path to the value to extract from a JSON response,
+    /// such as `$.data.items[0].count`.
+    ///
+    /// **Default**: `null`
+    json_path: Option<String>,
+
+    /// Regular expression to extract the value from the response,
+    /// after `json_path` is applied.
+    /// The first capture group is used if there is one, otherwise the whole match.
+    ///
+    /// **Default**: `null`
+    regex: Option<String>,
+
+    /// The format string to use for the label.
+    /// `{value}` is replaced with the extracted value.
+    ///
+    /// **Default**: `{value}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the label when a request fails.
+    /// `{error}` is replaced with the error message.
+    ///
+    /// **Default**: `󰀦`
+    #[serde(default = "default_error_format")]
+    error_format: String,
+
+    /// Classes to add and scripts to run when the value is a number which crosses a threshold.
+    /// See [alerts](alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<Alert>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    60_000
+}
+
+const fn default_timeout() -> u64 {
+    10_000
+}
+
+fn default_format() -> String {
+    String::from("{value}")
+}
+
+fn default_error_format() -> String {
+    String::from("󰀦")
+}
+
+#[derive(Debug, Clone)]
+pub enum HttpResponse {
+    /// The value extracted from a successful response.
+    Value(String),
+    /// The reason a request or extraction failed.
+    Error(String),
+}
+
+impl HttpModule {
+    /// Sends the configured request, returning the response body.
+    async fn fetch(&self, http: &reqwest::Client) -> Result<String> {
+        let mut request = match self.method {
+            Method::Get => http.get(&self.url),
+            Method::Post => http.post(&self.url),
+        };
+
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        } else if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+
+        Ok(request.send().await?.error_for_status()?.text().await?)
+    }
+
+    /// Extracts the value from a response body
+    /// using the configured JSON path and regex.
+    fn extract(&self, body: String, regex: Option<&Regex>) -> Result<String> {
+        let mut value = match &self.json_path {
+            Some(path) => {
+                let json = serde_json::from_str(&body)?;
+                match json_path(&json, path) {
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    Some(serde_json::Value::Null) => String::new(),
+                    Some(value) => value.to_string(),
+                    None => return Err(eyre!("Response has no value at '{path}'")),
+                }
+            }
+            None => body,
+        };
+
+        if let Some(regex) = regex {
+            let captures = regex
+                .captures(&value)
+                .ok_or_else(|| eyre!("Response does not match '{regex}'"))?;
+
+            value = captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|capture| capture.as_str().to_string())
+                .unwrap_or_default();
+        }
+
+        Ok(value.trim().to_string())
+    }
+}
+
+impl Module<Label> for HttpModule {
+    type SendMessage = HttpResponse;
+    type ReceiveMessage = ();
+
+    module_impl!("http");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let module = self.clone();
+        let tx = context.tx.clone();
+
+        #[cfg(feature = "history")]
+        let history_name = context.name.clone();
+
+        let regex = self.regex.as_deref().map(Regex::new).transpose()?;
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(self.timeout))
+            .build()?;
+
+        spawn(async move {
+            loop {
+                let response = match module.fetch(&http).await {
+                    Ok(body) => module.extract(body, regex.as_ref()),
+                    Err(err) => Err(err),
+                };
+
+                let response = match response {
+                    Ok(value) => {
+                        #[cfg(feature = "history")]
+                        if let Ok(number) = value.parse() {
+                            history::record(&history_name, number);
+                        }
+
+                        HttpResponse::Value(value)
+                    }
+                    Err(err) => {
+                        debug!("Request to '{}' failed: {err:?}", module.url);
+                        HttpResponse::Error(err.to_string())
+                    }
+                };
+
+                send_async!(tx, ModuleUpdateEvent::Update(response));
+                polling::sleep(Duration::from_millis(module.interval)).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Label>> {
+        let label = Label::builder().use_markup(true).build();
+
+        {
+            let label = label.clone();
+            let mut alerts = Alerts::new(self.alerts.clone());
+
+            glib_recv!(context.subscribe(), response => {
+                label.remove_class("error");
+
+                let text = match response {
+                    HttpResponse::Value(value) => {
+                        label.set_tooltip_text(None);
+
+                        match value.parse::<f64>() {
+                            Ok(number) => alerts.update(&label, number),
+                            Err(_) => alerts.clear(&label),
+                        }
+
+                        self.format.replace("{value}", &value)
+                    }
+                    HttpResponse::Error(err) => {
+                        label.add_class("error");
+                        label.set_tooltip_text(Some(&err));
+                        alerts.clear(&label);
+
+                        self.error_format.replace("{error}", &err)
+                    }
+                };
+
+                label.set_label_escaped(&text);
+                label.set_visible(!text.is_empty());
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: label,
+            popup: None,
+        })
+    }
+}
+
+/// Gets the value at a simple JSON path,
+/// made up of object keys and array indexes such as `$.data.items[0].count`.
+/// The leading `$` is optional.
+pub fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, indexes) = segment.split_once('[').unwrap_or((segment, ""));
+
+        if !key.is_empty() {
+            current = match key.parse::<usize>() {
+                Ok(index) if current.is_array() => current.get(index)?,
+                _ => current.get(key)?,
+            };
+        }
+
+        if !indexes.is_empty() {
+            for index in indexes.split('[') {
+                let index = index.strip_suffix(']')?.parse::<usize>().ok()?;
+                current = current.get(index)?;
+            }
+        }
+    }
+
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path() {
+        let value = json!({
+            "data": { "items": [{ "count": 3 }, { "count": 5 }] },
+            "grid": [[1, 2], [3, 4]]
+        });
+
+        assert_eq!(json_path(&value, "$.data.items[1].count"), Some(&json!(5)));
+        assert_eq!(json_path(&value, "data.items.0.count"), Some(&json!(3)));
+        assert_eq!(json_path(&value, "$.grid[1][0]"), Some(&json!(3)));
+        assert_eq!(json_path(&value, "$"), Some(&value));
+
+        assert_eq!(json_path(&value, "$.data.missing"), None);
+        assert_eq!(json_path(&value, "$.data.items[x]"), None);
+    }
+}
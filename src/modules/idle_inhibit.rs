@@ -0,0 +1,174 @@
+use crate::clients::logind;
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{ApplicationInhibitFlags, Button};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+const INHIBIT_REASON: &str = "Idle inhibitor enabled";
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IdleInhibitModule {
+    /// Icon to show when the idle inhibitor is active.
+    ///
+    /// **Default**: `󰅶`
+    #[serde(default = "default_icon_on")]
+    icon_on: String,
+
+    /// Icon to show when the idle inhibitor is inactive.
+    ///
+    /// **Default**: `󰛊`
+    #[serde(default = "default_icon_off")]
+    icon_off: String,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon_on() -> String {
+    String::from("󰅶")
+}
+
+fn default_icon_off() -> String {
+    String::from("󰛊")
+}
+
+/// How idle is currently being inhibited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inhibitor {
+    None,
+    /// GTK inhibitor, holding its cookie.
+    /// On Wayland, this uses the idle inhibit protocol on the bar surface.
+    Gtk(u32),
+    /// logind inhibitor lock, held by the controller.
+    Logind,
+}
+
+impl Module<Button> for IdleInhibitModule {
+    /// Whether the idle inhibitor is active.
+    type SendMessage = bool;
+    /// Whether to hold a logind inhibitor lock.
+    type ReceiveMessage = bool;
+
+    module_impl!("idle_inhibit");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            // the lock is released when the fd is dropped
+            let mut lock = None;
+
+            while let Some(enabled) = rx.recv().await {
+                if !enabled {
+                    lock.take();
+                    continue;
+                }
+
+                match logind::inhibit_idle(INHIBIT_REASON).await {
+                    Ok(fd) => lock = Some(fd),
+                    Err(err) => {
+                        error!("Failed to inhibit idle: {err:?}");
+                        send_async!(tx, ModuleUpdateEvent::Update(false));
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::with_label(&self.icon_off);
+        button.add_class("inactive");
+
+        let inhibitor = Rc::new(Cell::new(Inhibitor::None));
+
+        let update = {
+            let button = button.clone();
+            let icon_on = self.icon_on.clone();
+            let icon_off = self.icon_off.clone();
+
+            move |active: bool| {
+                if active {
+                    button.set_label(&icon_on);
+                    button.remove_class("inactive");
+                    button.add_class("active");
+                } else {
+                    button.set_label(&icon_off);
+                    button.remove_class("active");
+                    button.add_class("inactive");
+                }
+            }
+        };
+
+        {
+            let app = info.app.clone();
+            let tx = context.controller_tx.clone();
+            let inhibitor = inhibitor.clone();
+            let update = update.clone();
+
+            button.connect_clicked(move |button| {
+                let next = match inhibitor.get() {
+                    Inhibitor::None => {
+                        let window = button.toplevel().and_downcast::<gtk::Window>();
+
+                        let cookie = app.inhibit(
+                            window.as_ref(),
+                            ApplicationInhibitFlags::IDLE,
+                            Some(INHIBIT_REASON),
+                        );
+
+                        // GTK returns 0 if it has no way to inhibit idle
+                        if cookie == 0 {
+                            debug!("GTK could not inhibit idle, falling back to logind");
+                            try_send!(tx, true);
+                            Inhibitor::Logind
+                        } else {
+                            Inhibitor::Gtk(cookie)
+                        }
+                    }
+                    Inhibitor::Gtk(cookie) => {
+                        app.uninhibit(cookie);
+                        Inhibitor::None
+                    }
+                    Inhibitor::Logind => {
+                        try_send!(tx, false);
+                        Inhibitor::None
+                    }
+                };
+
+                inhibitor.set(next);
+                update(next != Inhibitor::None);
+            });
+        }
+
+        // the controller only sends updates when taking the logind lock fails
+        glib_recv!(context.subscribe(), active => {
+            if !active && inhibitor.get() == Inhibitor::Logind {
+                inhibitor.set(Inhibitor::None);
+                update(false);
+            }
+        });
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
@@ -0,0 +1,311 @@
+use crate::clients::input_method::{Backend, Client, Engine};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::ImageProvider;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, text, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::{BUTTON_PRIMARY, BUTTON_SECONDARY};
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InputMethodModule {
+    /// The input method framework to use.
+    /// `auto` uses Fcitx5 if it is running, otherwise IBus.
+    ///
+    /// **Valid options**: `auto`, `fcitx5`, `ibus`
+    /// <br>
+    /// **Default**: `auto`
+    #[serde(default)]
+    backend: Backend,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{label}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The number of milliseconds between checks for engine changes.
+    ///
+    /// **Default**: `500`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The size to render engine icons at in the popup.
+    ///
+    /// **Default**: `16`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{label}")
+}
+
+const fn default_interval() -> u64 {
+    500
+}
+
+const fn default_icon_size() -> i32 {
+    16
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMethodState {
+    /// The unique name of the active engine.
+    current: String,
+    /// The configured engines.
+    engines: Vec<Engine>,
+}
+
+impl InputMethodState {
+    fn current_engine(&self) -> Option<&Engine> {
+        self.engines.iter().find(|engine| engine.id == self.current)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InputMethodCommand {
+    Toggle,
+    /// Switches to the engine with the given unique name.
+    Set(String),
+}
+
+impl Module<Button> for InputMethodModule {
+    type SendMessage = InputMethodState;
+    type ReceiveMessage = InputMethodCommand;
+
+    module_impl!("input_method");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let backend = self.backend;
+        let interval = Duration::from_millis(self.interval);
+
+        spawn(async move {
+            let client = match Client::connect(backend).await {
+                Ok(client) => client,
+                Err(err) => {
+                    error!("Failed to connect to input method framework: {err:?}");
+                    return;
+                }
+            };
+
+            let mut interval = polling::interval(interval);
+            let mut last = None::<InputMethodState>;
+
+            loop {
+                tokio::select! {
+                    () = interval.tick() => {}
+                    Some(command) = rx.recv() => {
+                        let res = match command {
+                            InputMethodCommand::Toggle => client.toggle().await,
+                            InputMethodCommand::Set(id) => client.set_engine(&id).await,
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to switch input method: {err:?}");
+                        }
+                    }
+                }
+
+                let current = match client.current().await {
+                    Ok(current) => current,
+                    Err(err) => {
+                        error!("Failed to get current input method: {err:?}");
+                        continue;
+                    }
+                };
+
+                if last.as_ref().is_some_and(|last| last.current == current) {
+                    continue;
+                }
+
+                // the engine list is large to fetch,
+                // so is only refreshed when the engine changes.
+                let engines = match client.engines().await {
+                    Ok(engines) => engines,
+                    Err(err) => {
+                        error!("Failed to get input methods: {err:?}");
+                        vec![]
+                    }
+                };
+
+                let state = InputMethodState { current, engines };
+                last = Some(state.clone());
+                send_async!(tx, ModuleUpdateEvent::Update(state));
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::new(None);
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let controller_tx = context.controller_tx.clone();
+            let tx = context.tx.clone();
+
+            button.connect_button_press_event(move |button, event| {
+                match event.button() {
+                    BUTTON_PRIMARY => {
+                        try_send!(controller_tx, InputMethodCommand::Toggle);
+                    }
+                    BUTTON_SECONDARY => {
+                        try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                    }
+                    _ => return Propagation::Proceed,
+                }
+
+                Propagation::Stop
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, state => {
+                let (name, engine_label) = state
+                    .current_engine()
+                    .map_or((state.current.as_str(), state.current.as_str()), |engine| {
+                        (engine.name.as_str(), engine.label.as_str())
+                    });
+
+                label.set_label(
+                    &format
+                        .replace("{label}", engine_label)
+                        .replace("{name}", &text::isolate(name))
+                        .replace("{id}", &state.current),
+                );
+                button.set_tooltip_text(Some(name));
+            });
+        }
+
+        let rx = context.subscribe();
+        let popup = self
+            .into_popup(context.controller_tx.clone(), rx, context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let icon_theme = info.icon_theme.clone();
+        let mut engines = vec![];
+        let mut buttons = vec![];
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, state => {
+                // engines can be added or removed in the framework's settings
+                if state.engines != engines {
+                    container.foreach(|child| container.remove(child));
+                    buttons.clear();
+
+                    for engine in &state.engines {
+                        let button = make_engine_button(engine, &icon_theme, self.icon_size);
+
+                        {
+                            let tx = tx.clone();
+                            let popup_tx = context.tx.clone();
+                            let id = engine.id.clone();
+
+                            button.connect_clicked(move |_| {
+                                try_send!(tx, InputMethodCommand::Set(id.clone()));
+                                try_send!(popup_tx, ModuleUpdateEvent::ClosePopup);
+                            });
+                        }
+
+                        container.add(&button);
+                        buttons.push((engine.id.clone(), button));
+                    }
+
+                    container.show_all();
+                    engines = state.engines.clone();
+                }
+
+                for (id, button) in &buttons {
+                    if id == &state.current {
+                        button.add_class("active");
+                    } else {
+                        button.remove_class("active");
+                    }
+                }
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup button for an engine,
+/// showing its icon and name.
+fn make_engine_button(engine: &Engine, icon_theme: &gtk::IconTheme, icon_size: i32) -> Button {
+    let button = Button::new();
+    button.add_class("engine");
+
+    let container = gtk::Box::new(Orientation::Horizontal, 5);
+
+    if let Some(icon) = &engine.icon {
+        let image = gtk::Image::new();
+        image.add_class("icon");
+
+        if let Some(provider) = ImageProvider::parse(icon, icon_theme, false, icon_size) {
+            if let Err(err) = provider.load_into_image(&image) {
+                error!("{err:?}");
+            }
+        }
+
+        container.add(&image);
+    }
+
+    let label = Label::new(Some(&engine.name));
+    label.add_class("name");
+    label.set_halign(Align::Start);
+    container.add(&label);
+
+    button.add(&container);
+    button
+}
@@ -1,14 +1,23 @@
 use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::{BUTTON_PRIMARY, BUTTON_SECONDARY};
 use gtk::prelude::*;
+use gtk::{Button, Orientation};
 use serde::Deserialize;
-use tokio::sync::mpsc;
-
-use super::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+use super::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::clients::compositor::{KeyboardLayoutClient, KeyboardLayoutUpdate};
 use crate::clients::libinput::{Event, Key, KeyEvent};
 use crate::config::CommonConfig;
 use crate::gtk_helpers::IronbarGtkExt;
-use crate::image::IconLabel;
-use crate::{glib_recv, module_impl, module_update, send_async, spawn};
+use crate::image::{new_icon_button, IconLabel};
+use crate::{glib_recv, module_impl, module_update, rc_mut, send_async, spawn, try_send};
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -31,6 +40,33 @@ pub struct KeysModule {
     #[serde(default = "crate::config::default_true")]
     show_scroll: bool,
 
+    /// Whether to show the current keyboard layout.
+    /// Left-clicking it switches to the next layout,
+    /// and right-clicking opens a popup listing all layouts.
+    ///
+    /// This is currently only supported on Sway and Hyprland.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    show_layout: bool,
+
+    /// A map of keyboard layout names to their display labels/images.
+    /// Layouts use their actual name if not present in the map.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    layout_map: HashMap<String, String>,
+
+    /// Whether to remember the keyboard layout used in each window,
+    /// switching back to it when the window is focused.
+    /// Windows which have not been focused before use the first layout.
+    ///
+    /// When disabled, the layout is global.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    per_window_layout: bool,
+
     /// Size to render the icons at, in pixels (image icons only).
     ///
     /// **Default** `32`
@@ -128,9 +164,22 @@ fn default_icon_scroll() -> String {
     String::from("")
 }
 
+impl KeysModule {
+    fn layout_label<'a>(&'a self, layout: &'a str) -> &'a str {
+        self.layout_map.get(layout).map_or(layout, String::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum KeysUpdate {
+    Key(KeyEvent),
+    Layout(KeyboardLayoutUpdate),
+}
+
 impl Module<gtk::Box> for KeysModule {
-    type SendMessage = KeyEvent;
-    type ReceiveMessage = ();
+    type SendMessage = KeysUpdate;
+    /// The index of a keyboard layout to switch to.
+    type ReceiveMessage = usize;
 
     module_impl!("keys");
 
@@ -138,8 +187,55 @@ impl Module<gtk::Box> for KeysModule {
         &self,
         _info: &ModuleInfo,
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
+        if self.show_layout {
+            let client = context.try_client::<dyn KeyboardLayoutClient>()?;
+            let tx = context.tx.clone();
+            let per_window = self.per_window_layout;
+
+            spawn(async move {
+                let mut layout_rx = client.subscribe_keyboard_layout();
+                let mut focus_rx = per_window.then(|| client.subscribe_window_focus());
+
+                // the layout last used in each window, when per-window layouts are enabled
+                let mut windows = HashMap::<String, usize>::new();
+                let mut focused = None::<String>;
+                let mut active = None;
+
+                loop {
+                    tokio::select! {
+                        Ok(update) = layout_rx.recv() => {
+                            active = update.active;
+
+                            if let (Some(window), Some(active)) = (&focused, active) {
+                                windows.insert(window.clone(), active);
+                            }
+
+                            module_update!(tx, KeysUpdate::Layout(update));
+                        }
+                        Ok(window) = recv_focus(focus_rx.as_mut()) => {
+                            let layout = windows.get(&window).copied().unwrap_or_default();
+
+                            if active.is_some_and(|active| active != layout) {
+                                if let Err(err) = client.set_keyboard_layout(layout) {
+                                    error!("Couldn't restore keyboard layout: {err:?}");
+                                }
+                            }
+
+                            focused = Some(window);
+                        }
+                        Some(index) = rx.recv() => {
+                            if let Err(err) = client.set_keyboard_layout(index) {
+                                error!("Couldn't set keyboard layout: {err:?}");
+                            }
+                        }
+                        else => break,
+                    }
+                }
+            });
+        }
+
         let client = context.ironbar.clients.borrow_mut().libinput(&self.seat);
 
         let tx = context.tx.clone();
@@ -151,16 +247,17 @@ impl Module<gtk::Box> for KeysModule {
                         for key in [Key::Caps, Key::Num, Key::Scroll] {
                             module_update!(
                                 tx,
-                                KeyEvent {
+                                KeysUpdate::Key(KeyEvent {
                                     key: Key::Caps,
                                     state: client.get_state(key)
-                                }
+                                })
                             );
                         }
                     }
                     Event::Key(ev) => {
-                        send_async!(tx, ModuleUpdateEvent::Update(ev));
+                        send_async!(tx, ModuleUpdateEvent::Update(KeysUpdate::Key(ev)));
                     }
+                    Event::MediaKey(_) => {}
                 }
             }
         });
@@ -197,8 +294,73 @@ impl Module<gtk::Box> for KeysModule {
             container.add(&*scroll);
         }
 
+        let layout_button = Button::new();
+
+        if self.show_layout {
+            layout_button.add_class("layout");
+            // hidden until the layout is known
+            layout_button.set_no_show_all(true);
+
+            let label = IconLabel::new("", info.icon_theme, self.icon_size);
+            label.add_class("label");
+            label.show();
+            layout_button.add(&*label);
+
+            container.add(&layout_button);
+
+            let current = rc_mut!(None::<KeyboardLayoutUpdate>);
+
+            {
+                let controller_tx = context.controller_tx.clone();
+                let tx = context.tx.clone();
+                let current = current.clone();
+
+                layout_button.connect_button_press_event(move |button, event| {
+                    match event.button() {
+                        BUTTON_PRIMARY => {
+                            if let Some(current) = &*current.borrow() {
+                                let next = current
+                                    .active
+                                    .map_or(0, |active| (active + 1) % current.layouts.len());
+                                try_send!(controller_tx, next);
+                            }
+                        }
+                        BUTTON_SECONDARY => {
+                            try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                        }
+                        _ => return Propagation::Proceed,
+                    }
+
+                    Propagation::Stop
+                });
+            }
+
+            let button = layout_button.clone();
+            let layout_map = self.layout_map.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, update => {
+                if let KeysUpdate::Layout(update) = update {
+                    if let Some(name) = update.active_name() {
+                        label.set_label(Some(layout_map.get(name).map_or(name, String::as_str)));
+                        button.set_tooltip_text(Some(name));
+                        button.show();
+                    }
+
+                    *current.borrow_mut() = Some(update);
+                }
+            });
+        }
+
+        // the popup is created last, as it takes the context
+        let layout_popup = self.show_layout.then(|| self.clone());
+
         let icons = self.icons;
-        let handle_event = move |ev: KeyEvent| {
+        let handle_event = move |ev: KeysUpdate| {
+            let KeysUpdate::Key(ev) = ev else {
+                return;
+            };
+
             let parts = match (ev.key, ev.state) {
                 (Key::Caps, true) if self.show_caps => Some((&caps, icons.caps_on.as_str())),
                 (Key::Caps, false) if self.show_caps => Some((&caps, icons.caps_off.as_str())),
@@ -224,7 +386,93 @@ impl Module<gtk::Box> for KeysModule {
             }
         };
 
-        glib_recv!(context.subscribe(), handle_event);
-        Ok(ModuleParts::new(container, None))
+        let rx = context.subscribe();
+        glib_recv!(rx, handle_event);
+
+        let popup = if let Some(module) = layout_popup {
+            let rx = context.subscribe();
+            module
+                .into_popup(context.controller_tx.clone(), rx, context, info)
+                .into_popup_parts(vec![&layout_button])
+        } else {
+            None
+        };
+
+        Ok(ModuleParts::new(container, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let icon_theme = info.icon_theme.clone();
+        let mut layouts = vec![];
+        let mut buttons = vec![];
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, update => {
+                let KeysUpdate::Layout(update) = update else {
+                    continue;
+                };
+
+                // layouts can change when the compositor config is reloaded
+                if update.layouts != layouts {
+                    container.foreach(|child| container.remove(child));
+                    buttons.clear();
+
+                    for (index, layout) in update.layouts.iter().enumerate() {
+                        let button =
+                            new_icon_button(self.layout_label(layout), &icon_theme, self.icon_size);
+                        button.add_class("layout");
+                        button.set_tooltip_text(Some(layout));
+
+                        {
+                            let tx = tx.clone();
+                            let popup_tx = context.tx.clone();
+
+                            button.connect_clicked(move |_| {
+                                try_send!(tx, index);
+                                try_send!(popup_tx, ModuleUpdateEvent::ClosePopup);
+                            });
+                        }
+
+                        container.add(&button);
+                        buttons.push(button);
+                    }
+
+                    container.show_all();
+                    layouts = update.layouts;
+                }
+
+                for (index, button) in buttons.iter().enumerate() {
+                    if update.active == Some(index) {
+                        button.add_class("active");
+                    } else {
+                        button.remove_class("active");
+                    }
+                }
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Receives the ID of the next focused window,
+/// or waits forever if focus is not being tracked.
+async fn recv_focus(rx: Option<&mut broadcast::Receiver<String>>) -> Result<String, RecvError> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
     }
 }
@@ -0,0 +1,237 @@
+use crate::clients::compositor::LayoutClient;
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::{new_icon_button, IconLabel};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, rc_mut, send_async, spawn, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::{BUTTON_PRIMARY, BUTTON_SECONDARY};
+use gtk::prelude::*;
+use gtk::{Button, Orientation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LayoutModule {
+    /// A map of layout names to their display labels/images.
+    /// Layouts use their actual name if not present in the map.
+    /// See [here](images) for information on images.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    name_map: HashMap<String, String>,
+
+    /// The layouts to cycle through when clicking the widget,
+    /// and to list in the popup.
+    ///
+    /// If empty, all layouts supported by the compositor are used.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    layouts: Vec<String>,
+
+    /// The size to render icons at (image icons only).
+    ///
+    /// **Default**: `32`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_icon_size() -> i32 {
+    32
+}
+
+impl LayoutModule {
+    fn label<'a>(&'a self, layout: &'a str) -> &'a str {
+        self.name_map.get(layout).map_or(layout, String::as_str)
+    }
+}
+
+impl Module<Button> for LayoutModule {
+    type SendMessage = String;
+    type ReceiveMessage = String;
+
+    module_impl!("layout");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.try_client::<dyn LayoutClient>()?;
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut srx = client.subscribe_layout_change();
+
+                while let Ok(layout) = srx.recv().await {
+                    debug!("Received layout: {layout}");
+                    send_async!(tx, ModuleUpdateEvent::Update(layout));
+                }
+            });
+        }
+
+        let tx = context.tx.clone();
+
+        // Handle UI layout requests
+        spawn(async move {
+            while let Some(layout) = rx.recv().await {
+                match client.set_layout(&layout) {
+                    // compositors do not reliably emit an event for layout changes,
+                    // so update the UI directly.
+                    Ok(()) => send_async!(tx, ModuleUpdateEvent::Update(layout)),
+                    Err(err) => error!("Couldn't set layout '{layout}': {err:?}"),
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let layouts = if self.layouts.is_empty() {
+            context.try_client::<dyn LayoutClient>()?.layouts()
+        } else {
+            self.layouts.clone()
+        };
+
+        let button = Button::new();
+        button.add_class("button");
+
+        let label = IconLabel::new("", info.icon_theme, self.icon_size);
+        label.add_class("label");
+        button.add(&*label);
+
+        let current = rc_mut!(String::new());
+
+        {
+            let controller_tx = context.controller_tx.clone();
+            let tx = context.tx.clone();
+            let current = current.clone();
+            let layouts = layouts.clone();
+
+            button.connect_button_press_event(move |button, event| {
+                match event.button() {
+                    BUTTON_PRIMARY => {
+                        if let Some(next) = next_layout(&layouts, &current.borrow()) {
+                            try_send!(controller_tx, next.to_string());
+                        }
+                    }
+                    BUTTON_SECONDARY => {
+                        try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                    }
+                    _ => return Propagation::Proceed,
+                }
+
+                Propagation::Stop
+            });
+        }
+
+        {
+            let button = button.clone();
+            let name_map = self.name_map.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, layout => {
+                let display = name_map.get(&layout).unwrap_or(&layout);
+                label.set_label(Some(display));
+                button.set_tooltip_text(Some(&layout));
+
+                *current.borrow_mut() = layout;
+            });
+        }
+
+        let rx = context.subscribe();
+        let popup = self
+            .into_popup(context.controller_tx.clone(), rx, context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let layouts = if self.layouts.is_empty() {
+            context.try_client::<dyn LayoutClient>().ok()?.layouts()
+        } else {
+            self.layouts.clone()
+        };
+
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let mut buttons = HashMap::new();
+
+        for layout in layouts {
+            let button = new_icon_button(self.label(&layout), info.icon_theme, self.icon_size);
+            button.add_class("layout");
+            button.set_tooltip_text(Some(&layout));
+
+            {
+                let tx = tx.clone();
+                let popup_tx = context.tx.clone();
+                let layout = layout.clone();
+
+                button.connect_clicked(move |_| {
+                    try_send!(tx, layout.clone());
+                    try_send!(popup_tx, ModuleUpdateEvent::ClosePopup);
+                });
+            }
+
+            container.add(&button);
+            buttons.insert(layout, button);
+        }
+
+        glib_recv!(rx, layout => {
+            for (name, button) in &buttons {
+                if name == &layout {
+                    button.add_class("active");
+                } else {
+                    button.remove_class("active");
+                }
+            }
+        });
+
+        container.show_all();
+
+        Some(container)
+    }
+}
+
+/// Gets the layout following `current` in `layouts`,
+/// wrapping back to the start.
+///
+/// If `current` is unknown, the first layout is returned.
+fn next_layout<'a>(layouts: &'a [String], current: &str) -> Option<&'a str> {
+    let next = layouts
+        .iter()
+        .position(|layout| layout == current)
+        .map_or(0, |index| (index + 1) % layouts.len());
+
+    layouts.get(next).map(String::as_str)
+}
@@ -0,0 +1,230 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Label;
+use nix::sys::stat::Mode;
+use nix::unistd::mkfifo;
+use serde::Deserialize;
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::path::PathBuf;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::net::unix::pipe;
+use tokio::net::UnixListener;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ListenKind {
+    /// A named pipe, which can be written to with `echo`.
+    #[default]
+    Fifo,
+    /// A Unix socket, which accepts any number of clients at once.
+    Socket,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ListenModule {
+    /// The path to create the pipe or socket at.
+    /// Any existing pipe or socket at this path is replaced.
+    ///
+    /// **Required**
+    path: PathBuf,
+
+    /// The type of file to listen on.
+    ///
+    /// **Valid options**: `fifo`, `socket`
+    /// <br>
+    /// **Default**: `fifo`
+    #[serde(default)]
+    kind: ListenKind,
+
+    /// Whether each message is a JSON object, rather than plain text.
+    /// See [below](#json-messages) for the format.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    json: bool,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+/// A single update to the label.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct Message {
+    #[serde(default)]
+    text: String,
+    tooltip: Option<String>,
+    class: Option<String>,
+}
+
+impl ListenModule {
+    /// Parses a single line received from a client.
+    fn parse(&self, line: &str) -> Option<Message> {
+        if !self.json {
+            return Some(Message {
+                text: line.to_string(),
+                ..Message::default()
+            });
+        }
+
+        match serde_json::from_str(line) {
+            Ok(message) => Some(message),
+            Err(err) => {
+                error!(
+                    "Received invalid message on '{}': {err}",
+                    self.path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Removes a pipe or socket left over at the path,
+    /// refusing to replace any other type of file.
+    fn remove_existing(&self) -> Result<()> {
+        match fs::symlink_metadata(&self.path) {
+            Ok(metadata) => {
+                let file_type = metadata.file_type();
+                if file_type.is_fifo() || file_type.is_socket() {
+                    fs::remove_file(&self.path)?;
+                    Ok(())
+                } else {
+                    Err(eyre!(
+                        "'{}' already exists and is not a pipe or socket",
+                        self.path.display()
+                    ))
+                }
+            }
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+impl Module<Label> for ListenModule {
+    type SendMessage = Message;
+    type ReceiveMessage = ();
+
+    module_impl!("listen");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let module = self.clone();
+        let tx = context.tx.clone();
+
+        self.remove_existing()?;
+
+        match self.kind {
+            ListenKind::Fifo => {
+                mkfifo(&self.path, Mode::S_IRUSR | Mode::S_IWUSR)?;
+
+                // opening for writing too prevents reads ending
+                // each time the last writer closes the pipe.
+                let receiver = pipe::OpenOptions::new()
+                    .read_write(true)
+                    .open_receiver(&self.path)?;
+
+                debug!("Listening on pipe '{}'", self.path.display());
+
+                spawn(async move {
+                    read_messages(&module, BufReader::new(receiver), &tx).await;
+                });
+            }
+            ListenKind::Socket => {
+                let listener = UnixListener::bind(&self.path)?;
+
+                debug!("Listening on socket '{}'", self.path.display());
+
+                spawn(async move {
+                    loop {
+                        match listener.accept().await {
+                            Ok((stream, _addr)) => {
+                                let module = module.clone();
+                                let tx = tx.clone();
+
+                                spawn(async move {
+                                    read_messages(&module, BufReader::new(stream), &tx).await;
+                                });
+                            }
+                            Err(err) => error!("{err:?}"),
+                        }
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Label>> {
+        let label = Label::builder().use_markup(true).build();
+
+        {
+            let label = label.clone();
+            let mut class = None::<String>;
+
+            glib_recv!(context.subscribe(), message => {
+                label.set_label_escaped(&message.text);
+                label.set_visible(!message.text.is_empty());
+                label.set_tooltip_text(message.tooltip.as_deref());
+
+                if let Some(class) = &class {
+                    label.remove_class(class);
+                }
+
+                if let Some(new_class) = &message.class {
+                    label.add_class(new_class);
+                }
+
+                class = message.class;
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: label,
+            popup: None,
+        })
+    }
+}
+
+/// Reads newline-delimited messages from `reader` until it closes,
+/// sending each one to the widget.
+async fn read_messages<R: AsyncBufRead + Unpin>(
+    module: &ListenModule,
+    reader: R,
+    tx: &mpsc::Sender<ModuleUpdateEvent<Message>>,
+) {
+    let mut lines = reader.lines();
+
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                if let Some(message) = module.parse(line.trim_end()) {
+                    send_async!(tx, ModuleUpdateEvent::Update(message));
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                error!("Failed to read from '{}': {err:?}", module.path.display());
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use color_eyre::Result;
+use gtk::gdk::Keymap;
+use gtk::prelude::*;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use super::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::IconLabel;
+use crate::module_impl;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LockKeysModule {
+    /// Whether to show the caps lock indicator.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    show_caps: bool,
+
+    /// Whether to show the num lock indicator.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    show_num: bool,
+
+    /// Whether to show the scroll lock indicator.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    show_scroll: bool,
+
+    /// Size to render the icons at, in pixels (image icons only).
+    ///
+    /// **Default** `32`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// Lock key state icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+struct Icons {
+    /// Icon to show when caps lock is enabled.
+    ///
+    /// **Default**: `󰪛`
+    #[serde(default = "default_icon_caps")]
+    caps_on: String,
+
+    /// Icon to show when caps lock is disabled.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    caps_off: String,
+
+    /// Icon to show when num lock is enabled.
+    ///
+    /// **Default**: ``
+    #[serde(default = "default_icon_num")]
+    num_on: String,
+
+    /// Icon to show when num lock is disabled.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    num_off: String,
+
+    /// Icon to show when scroll lock is enabled.
+    ///
+    /// **Default**: ``
+    #[serde(default = "default_icon_scroll")]
+    scroll_on: String,
+
+    /// Icon to show when scroll lock is disabled.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    scroll_off: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            caps_on: default_icon_caps(),
+            caps_off: String::new(),
+            num_on: default_icon_num(),
+            num_off: String::new(),
+            scroll_on: default_icon_scroll(),
+            scroll_off: String::new(),
+        }
+    }
+}
+
+const fn default_icon_size() -> i32 {
+    32
+}
+
+fn default_icon_caps() -> String {
+    String::from("󰪛")
+}
+
+fn default_icon_num() -> String {
+    String::from("")
+}
+
+fn default_icon_scroll() -> String {
+    String::from("")
+}
+
+/// A single lock key indicator.
+struct Indicator {
+    label: IconLabel,
+    on: String,
+    off: String,
+}
+
+impl Indicator {
+    fn new(name: &str, on: String, off: String, info: &ModuleInfo, icon_size: i32) -> Self {
+        let label = IconLabel::new(&off, info.icon_theme, icon_size);
+        label.add_class("key");
+        label.add_class(name);
+
+        Self { label, on, off }
+    }
+
+    fn set_state(&self, enabled: bool) {
+        if enabled {
+            self.label.set_label(Some(&self.on));
+            self.label.add_class("enabled");
+        } else {
+            self.label.set_label(Some(&self.off));
+            self.label.remove_class("enabled");
+        }
+    }
+}
+
+impl Module<gtk::Box> for LockKeysModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("lock_keys");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        // the keymap belongs to the GTK main thread,
+        // so state is read directly by the widget.
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(info.bar_position.orientation(), 5);
+
+        let icons = self.icons;
+        let caps = self
+            .show_caps
+            .then(|| Indicator::new("caps", icons.caps_on, icons.caps_off, info, self.icon_size));
+        let num = self
+            .show_num
+            .then(|| Indicator::new("num", icons.num_on, icons.num_off, info, self.icon_size));
+        let scroll = self.show_scroll.then(|| {
+            Indicator::new(
+                "scroll",
+                icons.scroll_on,
+                icons.scroll_off,
+                info,
+                self.icon_size,
+            )
+        });
+
+        for indicator in [&caps, &num, &scroll].into_iter().flatten() {
+            container.add(&*indicator.label);
+        }
+
+        let Some(keymap) = Keymap::for_display(&container.display()) else {
+            warn!("Couldn't get keymap, lock key states will not be shown");
+            return Ok(ModuleParts::new(container, None));
+        };
+
+        let update = move |keymap: &Keymap| {
+            let states = [
+                (&caps, keymap.is_caps_locked()),
+                (&num, keymap.is_num_locked()),
+                (&scroll, keymap.is_scroll_locked()),
+            ];
+
+            for (indicator, enabled) in states {
+                if let Some(indicator) = indicator {
+                    indicator.set_state(enabled);
+                }
+            }
+        };
+
+        update(&keymap);
+        keymap.connect_state_changed(update);
+
+        Ok(ModuleParts::new(container, None))
+    }
+}
@@ -1,24 +1,41 @@
 use color_eyre::eyre::Report;
 use color_eyre::Result;
-use freedesktop_entry_parser::Entry;
+use freedesktop_entry_parser::{AttrSelector, Entry};
 use glib::Propagation;
+use gtk::gdk::keys::constants as keys;
+use gtk::gdk::{EventButton, EventKey};
 use gtk::{prelude::*, IconTheme};
-use gtk::{Align, Button, Label, Orientation};
+use gtk::{Align, ApplicationWindow, Button, Label, Orientation};
+use gtk_layer_shell::{Edge, LayerShell};
 use indexmap::IndexMap;
+use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
 use serde::Deserialize;
-use std::process::{Command, Stdio};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::{broadcast, mpsc};
-use unicode_segmentation::UnicodeSegmentation;
+use tokio::time::sleep;
 
-use crate::config::{BarPosition, CommonConfig};
-use crate::desktop_file::find_desktop_files;
+use crate::clients::logind::{ManagerProxy, SessionProxy};
+use crate::config::{default_true, BarPosition, CommonConfig, EllipsizeMode};
+use crate::desktop_file::{
+    find_application_dirs, find_desktop_files_async, is_executable, launch, read_desktop_files,
+};
+use crate::gtk_helpers::IronbarGtkExt;
 use crate::image::ImageProvider;
+use crate::modules::shared::{connect_popup_key_press, default_terminal, shell_quote};
 use crate::modules::{
-    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
 };
 use crate::script::Script;
-use crate::{glib_recv, module_impl, spawn, try_send};
-use tracing::{debug, error};
+use crate::{glib_recv, glib_recv_mpsc, module_impl, rc_mut, send_async, spawn, text, try_send};
+use tracing::{debug, error, warn};
 
 use super::ModuleLocation;
 
@@ -26,6 +43,75 @@ const fn default_length() -> usize {
     25
 }
 
+/// Gets the names of the current desktop from `$XDG_CURRENT_DESKTOP`,
+/// used to evaluate `OnlyShowIn` and `NotShowIn`.
+fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .map(|desktops| desktops.split(':').map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Gets the locale used for localized desktop file keys
+/// from `$LC_ALL`, `$LC_MESSAGES` or `$LANG`, in that order.
+///
+/// Returns the locale names to look up, from most to least specific.
+fn current_locales() -> Vec<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+        .map(|locale| locale_variants(&locale))
+        .unwrap_or_default()
+}
+
+/// Splits a locale in the form `lang_COUNTRY.ENCODING@MODIFIER`
+/// into the names to match against localized keys,
+/// in the order given by the desktop entry spec.
+/// The encoding is never used for matching.
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (locale, modifier) = locale
+        .split_once('@')
+        .map_or((locale, None), |(locale, modifier)| {
+            (locale, Some(modifier))
+        });
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (lang, country) = locale
+        .split_once('_')
+        .map_or((locale, None), |(lang, country)| (lang, Some(country)));
+
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return vec![];
+    }
+
+    let mut variants = vec![];
+
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{lang}@{modifier}"));
+    }
+    variants.push(lang.to_string());
+
+    variants
+}
+
+/// Gets the value of a key for the first matching locale,
+/// falling back to the unlocalized value.
+fn localized_attr<S: AsRef<str>>(
+    section: &AttrSelector<S>,
+    key: &str,
+    locales: &[String],
+) -> Option<String> {
+    locales
+        .iter()
+        .find_map(|locale| section.attr_with_param(key, locale))
+        .or_else(|| section.attr(key))
+        .map(str::to_string)
+}
+
 fn default_menu_popup_label() -> Option<String> {
     Some("≡".to_string())
 }
@@ -34,6 +120,14 @@ const fn default_menu_popup_icon_size() -> i32 {
     16
 }
 
+const fn default_grid_columns() -> u32 {
+    4
+}
+
+const fn default_grid_icon_size() -> i32 {
+    48
+}
+
 const OTHER_LABEL: &str = "Other";
 
 #[derive(Debug, Deserialize, Clone)]
@@ -42,6 +136,10 @@ pub enum MenuConfig {
     XdgEntry(XdgEntry),
     XdgOther,
     Custom(CustomEntry),
+    Power(PowerEntry),
+    Separator,
+    Heading { label: String },
+    Run(RunEntry),
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -52,13 +150,139 @@ pub struct XdgEntry {
     icon: Option<String>,
 
     categories: Vec<String>,
+
+    #[serde(default)]
+    max_visible: Option<usize>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct CustomEntry {
     icon: Option<String>,
     label: String,
-    on_click: String,
+
+    #[serde(default)]
+    on_click: Option<String>,
+
+    /// Child entries, shown in a sub-menu when the entry is clicked.
+    #[serde(default)]
+    entries: Vec<CustomEntry>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PowerEntry {
+    #[serde(default = "default_power_label")]
+    label: String,
+
+    #[serde(default = "default_power_icon")]
+    icon: Option<String>,
+
+    #[serde(default = "default_power_actions")]
+    actions: Vec<PowerAction>,
+
+    #[serde(default = "default_power_confirm")]
+    confirm: Vec<PowerAction>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RunEntry {
+    #[serde(default = "default_run_placeholder")]
+    placeholder: String,
+}
+
+fn default_run_placeholder() -> String {
+    "Run a command...".to_string()
+}
+
+fn default_power_label() -> String {
+    "Power".to_string()
+}
+
+fn default_power_icon() -> Option<String> {
+    Some("system-shutdown".to_string())
+}
+
+fn default_power_actions() -> Vec<PowerAction> {
+    PowerAction::ALL.to_vec()
+}
+
+fn default_power_confirm() -> Vec<PowerAction> {
+    vec![
+        PowerAction::Logout,
+        PowerAction::Reboot,
+        PowerAction::Shutdown,
+    ]
+}
+
+/// An action in a power entry, performed through logind.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerAction {
+    Lock,
+    Logout,
+    Suspend,
+    Hibernate,
+    Reboot,
+    Shutdown,
+}
+
+impl PowerAction {
+    const ALL: [Self; 6] = [
+        Self::Lock,
+        Self::Logout,
+        Self::Suspend,
+        Self::Hibernate,
+        Self::Reboot,
+        Self::Shutdown,
+    ];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Lock => "Lock",
+            Self::Logout => "Log Out",
+            Self::Suspend => "Suspend",
+            Self::Hibernate => "Hibernate",
+            Self::Reboot => "Reboot",
+            Self::Shutdown => "Shut Down",
+        }
+    }
+
+    const fn icon(self) -> &'static str {
+        match self {
+            Self::Lock => "system-lock-screen",
+            Self::Logout => "system-log-out",
+            Self::Suspend => "system-suspend",
+            Self::Hibernate => "system-hibernate",
+            Self::Reboot => "system-reboot",
+            Self::Shutdown => "system-shutdown",
+        }
+    }
+
+    const fn class(self) -> &'static str {
+        match self {
+            Self::Lock => "lock",
+            Self::Logout => "logout",
+            Self::Suspend => "suspend",
+            Self::Hibernate => "hibernate",
+            Self::Reboot => "reboot",
+            Self::Shutdown => "shutdown",
+        }
+    }
+
+    async fn run(self) -> Result<()> {
+        let dbus = zbus::Connection::system().await?;
+
+        // interactive allows polkit to prompt for authentication if required.
+        match self {
+            Self::Lock => SessionProxy::new(&dbus).await?.lock().await?,
+            Self::Logout => SessionProxy::new(&dbus).await?.terminate().await?,
+            Self::Suspend => ManagerProxy::new(&dbus).await?.suspend(true).await?,
+            Self::Hibernate => ManagerProxy::new(&dbus).await?.hibernate(true).await?,
+            Self::Reboot => ManagerProxy::new(&dbus).await?.reboot(true).await?,
+            Self::Shutdown => ManagerProxy::new(&dbus).await?.power_off(true).await?,
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -66,17 +290,776 @@ pub struct XdgSection {
     label: String,
     icon: Option<String>,
     applications: IndexMap<String, MenuApplication>,
+    /// The number of applications to show before the rest are collapsed.
+    max_visible: Option<usize>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct MenuApplication {
     label: String,
+    /// The application's `Name`, which is used as the label
+    /// unless a generic name is preferred.
+    name: String,
+    /// The application's `Comment`, shown as the button tooltip.
+    description: Option<String>,
     file_name: String,
+    path: PathBuf,
+    /// The raw `Exec` line of the desktop file.
+    exec: Option<String>,
     categories: Vec<String>,
+    actions: Vec<DesktopAction>,
+}
+
+/// An additional action provided by an application,
+/// from a `[Desktop Action]` section of its desktop file.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DesktopAction {
+    id: String,
+    label: String,
+}
+
+/// A main menu button which opens a sub-menu,
+/// tracked to allow keyboard navigation between the two.
+#[derive(Debug, Clone)]
+struct SubMenu {
+    /// How deeply the sub-menu is nested, starting from `1`.
+    depth: usize,
+    button: Button,
+    /// The widget which is shown when the sub-menu is open.
+    /// This is the scroll container when the menu has a fixed height.
+    wrapper: gtk::Widget,
+    /// The box containing the application buttons.
+    entries: gtk::Box,
+}
+
+type SubMenus = Rc<RefCell<Vec<SubMenu>>>;
+
+/// Tag set on the button of a nested custom entry, holding its sub-menu.
+const NESTED_SUB_MENU_TAG: &str = "menu-nested-sub-menu";
+
+/// Tag set on the menu container, holding the state needed by application context menus.
+const CONTEXT_MENU_TAG: &str = "menu-context-menu";
+
+/// State shared with the context menu of each application button.
+#[derive(Clone)]
+struct ContextMenuConfig {
+    favorites: Rc<RefCell<Favorites>>,
+    uninstall_command: Option<String>,
+}
+
+impl ContextMenuConfig {
+    /// Gets the config from the nearest ancestor of `widget` which has one.
+    fn find(widget: &impl IsA<gtk::Widget>) -> Option<Self> {
+        std::iter::successors(widget.parent(), WidgetExt::parent)
+            .find_map(|ancestor| ancestor.get_tag::<Self>(CONTEXT_MENU_TAG).cloned())
+    }
+}
+
+/// Events sent from application buttons.
+#[derive(Debug, Clone)]
+enum MenuEvent {
+    /// Pins or unpins the application with the given desktop file name.
+    ToggleFavorite(String),
+    /// Launches the application with the given desktop file,
+    /// or one of its desktop actions.
+    Launch {
+        path: PathBuf,
+        action: Option<String>,
+    },
+    /// Performs a power action.
+    Power(PowerAction),
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuSort {
+    /// Sort applications by name.
+    #[default]
+    Alphabetical,
+    /// Sort applications by how often and how recently they were launched.
+    Frecency,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuMode {
+    /// Show the menu in a popup attached to the bar.
+    #[default]
+    Popup,
+    /// Show a fullscreen launcher with a search box and grid of applications.
+    Overlay,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuView {
+    /// Show applications as a vertical list of buttons.
+    #[default]
+    List,
+    /// Show applications as a grid of large icons.
+    Grid,
+}
+
+/// The layout of application sub-menus in grid view.
+#[derive(Debug, Clone, Copy)]
+struct GridLayout {
+    columns: u32,
+    icon_size: i32,
+}
+
+/// Where an application was installed from.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationSource {
+    /// Installed by the system package manager, or manually.
+    Native,
+    Flatpak,
+    Snap,
+}
+
+impl ApplicationSource {
+    /// Detects the source of a desktop file
+    /// from the keys added by Flatpak and Snap, or its location.
+    fn detect<S: AsRef<str>>(path: &Path, desktop: &AttrSelector<S>) -> Self {
+        let path = path.to_string_lossy();
+
+        if desktop.attr("X-Flatpak").is_some() || path.contains("/flatpak/exports/") {
+            Self::Flatpak
+        } else if desktop.attr("X-SnapInstanceName").is_some() || path.contains("/snapd/desktop/") {
+            Self::Snap
+        } else {
+            Self::Native
+        }
+    }
+}
+
+fn default_source_priority() -> Vec<ApplicationSource> {
+    vec![
+        ApplicationSource::Native,
+        ApplicationSource::Flatpak,
+        ApplicationSource::Snap,
+    ]
+}
+
+/// Rewrites the categories of applications before they are matched to sections.
+///
+/// Keys are either a category, which is replaced by the mapped categories wherever it appears,
+/// or a desktop file name ending in `.desktop`, whose categories are replaced entirely.
+#[derive(Debug, Default, Clone)]
+struct CategoryMap(HashMap<String, Vec<String>>);
+
+impl CategoryMap {
+    /// Merges the mappings set in the config with those in `file`.
+    /// Mappings set in the config take precedence.
+    fn load(configured: &HashMap<String, Vec<String>>, file: Option<&Path>) -> Self {
+        let mut map = file.map(Self::read_file).unwrap_or_default();
+        map.extend(configured.clone());
+
+        Self(map)
+    }
+
+    /// Reads a file of `Key=Category;Category;` lines,
+    /// using the same list syntax as desktop files.
+    /// Empty lines and lines starting with `#` are ignored.
+    fn read_file(path: &Path) -> HashMap<String, Vec<String>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!("Failed to read category map '{}': {err:?}", path.display());
+                return HashMap::new();
+            }
+        };
+
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let Some((key, value)) = line.split_once('=') else {
+                    warn!("Ignoring invalid line in category map: '{line}'");
+                    return None;
+                };
+
+                let categories = value
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|category| !category.is_empty())
+                    .map(str::to_string)
+                    .collect();
+
+                Some((key.trim().to_string(), categories))
+            })
+            .collect()
+    }
+
+    fn apply(&self, file_name: &str, categories: Vec<String>) -> Vec<String> {
+        if self.0.is_empty() {
+            return categories;
+        }
+
+        if let Some(categories) = self.0.get(file_name) {
+            return categories.clone();
+        }
+
+        let mut mapped = Vec::<String>::with_capacity(categories.len());
+        for category in categories {
+            let replacements = self
+                .0
+                .get(&category)
+                .cloned()
+                .unwrap_or_else(|| vec![category]);
+
+            for category in replacements {
+                if !mapped.contains(&category) {
+                    mapped.push(category);
+                }
+            }
+        }
+
+        mapped
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HistoryEntry {
+    /// The number of times the application has been launched.
+    count: u32,
+    /// The time of the last launch, as a Unix timestamp.
+    last_launch: u64,
+}
+
+/// Options for loading applications from desktop files.
+#[derive(Debug)]
+struct LoadOptions {
+    max_label_length: usize,
+    prefer_generic_name: bool,
+    desktops: Vec<String>,
+    category_map: CategoryMap,
+    source_priority: Option<Vec<ApplicationSource>>,
+}
+
+/// How often partial results are sent while applications are first loaded.
+const LOAD_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+type ParsedApplication = (String, ApplicationSource, MenuApplication);
+
+/// Reads all desktop files for applications which should be shown in the menu,
+/// sending the applications to `tx`.
+///
+/// Desktop files are read in parallel in the background.
+/// If `incremental` is set, the applications parsed so far are also sent
+/// every [`LOAD_UPDATE_INTERVAL`] while loading,
+/// so the menu fills in without waiting for every file.
+/// Otherwise only the complete list is sent,
+/// which avoids the menu briefly shrinking when reloading.
+///
+/// See [`parse_application`] for which applications are included.
+async fn load_applications(
+    options: &Arc<LoadOptions>,
+    incremental: bool,
+    tx: &mpsc::Sender<ModuleUpdateEvent<Snapshot<Vec<MenuApplication>>>>,
+) {
+    // user files take precedence over system files with the same name,
+    // including hiding them, so must be seen first.
+    let mut seen = HashSet::new();
+    let files = find_desktop_files_async()
+        .await
+        .into_iter()
+        .rev()
+        .filter(|file_path| seen.insert(desktop_file_name(file_path)))
+        .collect::<Vec<_>>();
+
+    let mut parsed = {
+        let options = options.clone();
+        let locales = current_locales();
+
+        read_desktop_files(files, move |file_path, contents| {
+            parse_application(file_path, contents, &options, &locales)
+        })
+    };
+
+    // keyed by file index, so the result does not depend on which files were read first.
+    let mut applications = BTreeMap::new();
+    let mut last_update = Instant::now();
+
+    while let Some((index, application)) = parsed.recv().await {
+        applications.insert(index, application);
+
+        if incremental && last_update.elapsed() >= LOAD_UPDATE_INTERVAL {
+            let partial = applications.values().cloned().collect();
+            send_async!(
+                tx,
+                ModuleUpdateEvent::snapshot(finish_applications(partial, options))
+            );
+            last_update = Instant::now();
+        }
+    }
+
+    let applications = applications.into_values().collect();
+    send_async!(
+        tx,
+        ModuleUpdateEvent::snapshot(finish_applications(applications, options))
+    );
+}
+
+fn desktop_file_name(file_path: &Path) -> String {
+    file_path
+        .file_name()
+        .expect("find_desktop_files returned empty pathbuf")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Parses the contents of a desktop file into an application,
+/// along with its deduplication key and source.
+///
+/// Entries which are hidden, not shown in any of the configured desktops,
+/// or whose `TryExec` program is not installed are skipped.
+///
+/// Names and comments are localized for `locales`.
+/// Categories are rewritten using the category map.
+fn parse_application(
+    file_path: &Path,
+    contents: Vec<u8>,
+    options: &LoadOptions,
+    locales: &[String],
+) -> Option<ParsedApplication> {
+    let LoadOptions {
+        prefer_generic_name,
+        desktops,
+        category_map,
+        ..
+    } = options;
+
+    let file_name = desktop_file_name(file_path);
+
+    let entry = Entry::parse(contents).ok()?;
+    let desktop = entry.section("Desktop Entry");
+    let typ = desktop.attr("Type").unwrap_or("N/A");
+    if typ != "Application" {
+        return None;
+    }
+
+    if desktop.attr("Hidden") == Some("true") {
+        return None;
+    }
+
+    let in_desktops = |key: &str| {
+        desktop.attr(key).map(|value| {
+            value
+                .split(';')
+                .any(|desktop| desktops.iter().any(|current| current == desktop))
+        })
+    };
+
+    if in_desktops("OnlyShowIn") == Some(false) || in_desktops("NotShowIn") == Some(true) {
+        return None;
+    }
+
+    if let Some(try_exec) = desktop.attr("TryExec") {
+        if !is_executable(try_exec) {
+            debug!("Skipping '{file_name}' as '{try_exec}' is not installed");
+            return None;
+        }
+    }
+
+    let raw_cats = desktop.attr("Categories").unwrap_or("Misc");
+    let categories = raw_cats
+        .trim_end_matches(';')
+        .split(';')
+        .map(|s| s.to_string())
+        .collect();
+    let categories = category_map.apply(&file_name, categories);
+    let name = localized_attr(&desktop, "Name", locales)?;
+    let label = prefer_generic_name
+        .then(|| localized_attr(&desktop, "GenericName", locales))
+        .flatten()
+        .unwrap_or_else(|| name.clone());
+    let description = localized_attr(&desktop, "Comment", locales);
+
+    // the window class is the most reliable way to match the same application,
+    // since packagers often change the name.
+    let key = desktop
+        .attr("StartupWMClass")
+        .or_else(|| desktop.attr("Name"))
+        .unwrap_or_default()
+        .to_lowercase();
+    let source = ApplicationSource::detect(file_path, &desktop);
+
+    // Some .desktop files are only for associating mimetypes
+    if desktop.attr("NoDisplay") == Some("true") {
+        return None;
+    }
+    let actions = desktop
+        .attr("Actions")
+        .unwrap_or_default()
+        .split(';')
+        .filter(|id| !id.is_empty())
+        .filter_map(|id| {
+            let action = entry.section(format!("Desktop Action {id}"));
+
+            // actions without a command cannot be launched
+            action.attr("Exec")?;
+
+            Some(DesktopAction {
+                id: id.to_string(),
+                label: localized_attr(&action, "Name", locales)?,
+            })
+        })
+        .collect();
+
+    let application = MenuApplication {
+        label,
+        name,
+        description,
+        file_name,
+        path: file_path.to_path_buf(),
+        exec: desktop.attr("Exec").map(str::to_string),
+        categories,
+        actions,
+    };
+
+    Some((key, source, application))
+}
+
+/// Builds the final list of applications from those parsed so far.
+///
+/// If `prefer_generic_name` is set, the `GenericName` is used as the label where present,
+/// unless it is shared with another application.
+///
+/// If `source_priority` is set, applications installed from several sources
+/// are only included once, from the first source in the list.
+fn finish_applications(
+    mut applications: Vec<ParsedApplication>,
+    options: &LoadOptions,
+) -> Vec<MenuApplication> {
+    let prefer_generic_name = options.prefer_generic_name;
+    let max_label_length = options.max_label_length;
+
+    if let Some(priority) = &options.source_priority {
+        applications = deduplicate(applications, priority);
+    }
+
+    let mut applications = applications
+        .into_iter()
+        .map(|(_, _, application)| application)
+        .collect::<Vec<_>>();
+
+    // applications are keyed by label,
+    // so shared generic names would hide all but one of them.
+    if prefer_generic_name {
+        let mut counts = HashMap::<String, usize>::new();
+        for application in &applications {
+            *counts.entry(application.label.clone()).or_default() += 1;
+        }
+
+        for application in &mut applications {
+            if counts.get(&application.label).copied().unwrap_or_default() > 1 {
+                application.label = application.name.clone();
+            }
+        }
+    }
+
+    for application in &mut applications {
+        if let Cow::Owned(label) =
+            text::truncate(&application.label, max_label_length, EllipsizeMode::End)
+        {
+            application.label = label;
+        }
+    }
+
+    applications
+}
+
+/// Removes applications which are installed from more than one source,
+/// keeping the one whose source is earliest in `priority`.
+/// Sources which are not in `priority` are kept last.
+fn deduplicate(
+    applications: Vec<ParsedApplication>,
+    priority: &[ApplicationSource],
+) -> Vec<ParsedApplication> {
+    let rank = |source: ApplicationSource| {
+        priority
+            .iter()
+            .position(|&other| other == source)
+            .unwrap_or(priority.len())
+    };
+
+    let mut unique: Vec<ParsedApplication> = vec![];
+    let mut indices = HashMap::<String, usize>::new();
+
+    for (key, source, application) in applications {
+        let existing = indices
+            .get(&key)
+            .copied()
+            // only the same application from a different source is a duplicate
+            .filter(|&index| unique[index].1 != source);
+
+        let Some(index) = existing else {
+            indices.entry(key.clone()).or_insert(unique.len());
+            unique.push((key, source, application));
+            continue;
+        };
+
+        let existing = &mut unique[index];
+        if rank(source) < rank(existing.1) {
+            debug!(
+                "Preferring '{}' over duplicate '{}'",
+                application.file_name, existing.2.file_name
+            );
+            *existing = (key, source, application);
+        } else {
+            debug!(
+                "Preferring '{}' over duplicate '{}'",
+                existing.2.file_name, application.file_name
+            );
+        }
+    }
+
+    unique
+}
+
+/// Launch history for applications in the menu,
+/// persisted to the state directory.
+///
+/// The file contains one line per application,
+/// with the desktop file name, launch count and last launch time separated by tabs.
+#[derive(Debug)]
+struct History {
+    entries: IndexMap<String, HistoryEntry>,
+}
+
+impl History {
+    fn load() -> Self {
+        let entries = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(|line| {
+                        let mut parts = line.split('\t');
+                        let name = parts.next()?.to_string();
+                        let count = parts.next()?.parse().ok()?;
+                        let last_launch = parts.next()?.parse().ok()?;
+
+                        Some((name, HistoryEntry { count, last_launch }))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { entries }
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::state_dir().map(|dir| dir.join("ironbar").join("menu_history"))
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+
+    /// Records a launch of the application and writes the history to disk.
+    fn record(&mut self, file_name: &str) {
+        let name = Favorites::normalize(file_name).to_string();
+
+        let entry = self.entries.entry(name).or_insert(HistoryEntry {
+            count: 0,
+            last_launch: 0,
+        });
+
+        entry.count += 1;
+        entry.last_launch = Self::now();
+
+        self.save();
+    }
+
+    /// Gets the frecency score for an application.
+    ///
+    /// This is the launch count, weighted by how recently the last launch was.
+    fn score(&self, file_name: &str, now: u64) -> u32 {
+        const DAY: u64 = 24 * 60 * 60;
+
+        self.entries
+            .get(Favorites::normalize(file_name))
+            .map_or(0, |entry| {
+                let weight = match now.saturating_sub(entry.last_launch) / DAY {
+                    0..=3 => 100,
+                    4..=13 => 70,
+                    14..=30 => 50,
+                    31..=89 => 30,
+                    _ => 10,
+                };
+
+                entry.count * weight
+            })
+    }
+
+    /// Sorts a section's applications by frecency, highest first.
+    /// Applications with equal scores are sorted by name.
+    fn sort(&self, applications: &mut IndexMap<String, MenuApplication>) {
+        let now = Self::now();
+
+        applications.sort_by(|label_a, a, label_b, b| {
+            self.score(&b.file_name, now)
+                .cmp(&self.score(&a.file_name, now))
+                .then_with(|| label_a.cmp(label_b))
+        });
+    }
+
+    /// Gets the `count` most recently launched applications.
+    fn recent(&self, count: usize) -> Vec<&str> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_launch));
+
+        entries
+            .into_iter()
+            .take(count)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("Failed to locate state directory, menu history will not be saved");
+            return;
+        };
+
+        let contents = self
+            .entries
+            .iter()
+            .map(|(name, entry)| format!("{name}\t{}\t{}", entry.count, entry.last_launch))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let res = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, contents));
+
+        if let Err(err) = res {
+            error!("Failed to save menu history: {err:?}");
+        }
+    }
+}
+
+/// Applications shown in the favorites section at the top of the menu.
+///
+/// Entries are desktop file names, with or without the `.desktop` suffix.
+#[derive(Debug)]
+struct Favorites {
+    /// Favorites set in the config, which cannot be unpinned.
+    configured: Vec<String>,
+    /// Favorites pinned from the popup, persisted to the state directory.
+    pinned: Vec<String>,
+}
+
+impl Favorites {
+    fn load(configured: Vec<String>) -> Self {
+        let configured = configured
+            .iter()
+            .map(|name| Self::normalize(name).to_string())
+            .collect();
+
+        let pinned = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { configured, pinned }
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::state_dir().map(|dir| dir.join("ironbar").join("menu_favorites"))
+    }
+
+    fn normalize(file_name: &str) -> &str {
+        file_name.trim_end_matches(".desktop")
+    }
+
+    /// Gets all favorites, in order, without duplicates.
+    fn all(&self) -> Vec<&str> {
+        let mut all = Vec::<&str>::new();
+
+        for name in self.configured.iter().chain(&self.pinned) {
+            if !all.contains(&name.as_str()) {
+                all.push(name);
+            }
+        }
+
+        all
+    }
+
+    /// Checks whether the application is a favorite, either configured or pinned.
+    fn contains(&self, file_name: &str) -> bool {
+        let name = Self::normalize(file_name);
+        self.configured
+            .iter()
+            .chain(&self.pinned)
+            .any(|favorite| favorite == name)
+    }
+
+    /// Checks whether the application is a favorite set in the config.
+    fn is_configured(&self, file_name: &str) -> bool {
+        let name = Self::normalize(file_name);
+        self.configured.iter().any(|configured| configured == name)
+    }
+
+    /// Pins the application if it is not pinned, or unpins it if it is,
+    /// and writes the pinned list to disk.
+    ///
+    /// Favorites from the config are left unchanged.
+    fn toggle(&mut self, file_name: &str) {
+        let name = Self::normalize(file_name);
+
+        if self.is_configured(name) {
+            debug!("'{name}' is a configured favorite, not unpinning");
+            return;
+        }
+
+        if let Some(index) = self.pinned.iter().position(|pinned| pinned == name) {
+            self.pinned.remove(index);
+        } else {
+            self.pinned.push(name.to_string());
+        }
+
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("Failed to locate state directory, favorites will not be saved");
+            return;
+        };
+
+        let res = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, self.pinned.join("\n")));
+
+        if let Err(err) = res {
+            error!("Failed to save menu favorites: {err:?}");
+        }
+    }
 }
+
 enum MenuEntry {
     Xdg(XdgSection),
     Custom(CustomEntry),
+    Power(PowerEntry),
+    Separator,
+    Heading(String),
+    Run(RunEntry),
 }
 
 impl MenuEntry {
@@ -84,12 +1067,17 @@ impl MenuEntry {
         match self {
             Self::Xdg(entry) => entry.label.clone(),
             Self::Custom(entry) => entry.label.clone(),
+            Self::Power(entry) => entry.label.clone(),
+            Self::Separator | Self::Run(_) => String::new(),
+            Self::Heading(label) => label.clone(),
         }
     }
     fn icon(&self) -> Option<String> {
         match self {
             Self::Xdg(entry) => entry.icon.clone(),
             Self::Custom(entry) => entry.icon.clone(),
+            Self::Power(entry) => entry.icon.clone(),
+            Self::Separator | Self::Heading(_) | Self::Run(_) => None,
         }
     }
 }
@@ -114,6 +1102,51 @@ pub struct MenuModule {
     #[serde(default = "default_length")]
     max_label_length: usize,
 
+    #[serde(default)]
+    prefer_generic_name: bool,
+
+    #[serde(default = "default_true")]
+    deduplicate: bool,
+
+    #[serde(default = "default_source_priority")]
+    source_priority: Vec<ApplicationSource>,
+
+    #[serde(default)]
+    category_map: HashMap<String, Vec<String>>,
+
+    #[serde(default)]
+    category_map_file: Option<PathBuf>,
+
+    #[serde(default)]
+    favorites: Vec<String>,
+
+    #[serde(default)]
+    sort: MenuSort,
+
+    #[serde(default)]
+    mode: MenuMode,
+
+    #[serde(default)]
+    view: MenuView,
+
+    #[serde(default = "default_grid_columns")]
+    grid_columns: u32,
+
+    #[serde(default = "default_grid_icon_size")]
+    grid_icon_size: i32,
+
+    #[serde(default)]
+    recent: usize,
+
+    #[serde(default)]
+    terminal: Option<String>,
+
+    #[serde(default)]
+    uninstall_command: Option<String>,
+
+    #[serde(default)]
+    desktop: Option<String>,
+
     #[serde(default = "default_menu_popup_label")]
     label: Option<String>,
 
@@ -136,6 +1169,21 @@ impl Default for MenuModule {
             height: None,
             width: None,
             max_label_length: default_length(),
+            prefer_generic_name: false,
+            deduplicate: true,
+            source_priority: default_source_priority(),
+            category_map: HashMap::new(),
+            category_map_file: None,
+            favorites: vec![],
+            sort: MenuSort::default(),
+            mode: MenuMode::default(),
+            view: MenuView::default(),
+            grid_columns: default_grid_columns(),
+            grid_icon_size: default_grid_icon_size(),
+            recent: 0,
+            terminal: None,
+            uninstall_command: None,
+            desktop: None,
             label: default_menu_popup_label(),
             label_icon: None,
             label_icon_size: default_menu_popup_icon_size(),
@@ -150,6 +1198,7 @@ fn default_menu() -> Vec<MenuConfig> {
             label: "Settings".to_string(),
             icon: Some("preferences-system".to_string()),
             categories: vec!["Settings".to_string(), "Screensaver".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Accessories".to_string(),
@@ -160,26 +1209,31 @@ fn default_menu() -> Vec<MenuConfig> {
                 "Legacy".to_string(),
                 "Utility".to_string(),
             ],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Development".to_string(),
             icon: Some("applications-development".to_string()),
             categories: vec!["Development".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Education".to_string(),
             icon: Some("applications-education".to_string()),
             categories: vec!["Education".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Games".to_string(),
             icon: Some("applications-games".to_string()),
             categories: vec!["Game".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Graphics".to_string(),
             icon: Some("applications-graphics".to_string()),
             categories: vec!["Graphics".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Multimedia".to_string(),
@@ -189,26 +1243,31 @@ fn default_menu() -> Vec<MenuConfig> {
                 "Video".to_string(),
                 "AudioVideo".to_string(),
             ],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Network".to_string(),
             icon: Some("applications-internet".to_string()),
             categories: vec!["Network".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Office".to_string(),
             icon: Some("applications-office".to_string()),
             categories: vec!["Office".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "Science".to_string(),
             icon: Some("applications-science".to_string()),
             categories: vec!["Science".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgEntry(XdgEntry {
             label: "System".to_string(),
             icon: Some("applications-system".to_string()),
             categories: vec!["Emulator".to_string(), "System".to_string()],
+            max_visible: None,
         }),
         MenuConfig::XdgOther,
     ]
@@ -224,9 +1283,13 @@ fn parse_config(
     mut sections_by_cat: IndexMap<String, Vec<String>>,
 ) -> (IndexMap<String, MenuEntry>, IndexMap<String, Vec<String>>) {
     let mut entries = IndexMap::<String, MenuEntry>::new();
+    // entries are sorted within each group,
+    // with separators and headings starting a new group.
+    let mut group = IndexMap::<String, MenuEntry>::new();
     section_config
         .iter()
-        .for_each(|entry_config| match entry_config {
+        .enumerate()
+        .for_each(|(index, entry_config)| match entry_config {
             MenuConfig::XdgEntry(entry) => {
                 entry.categories.iter().for_each(|cat| {
                     let existing = sections_by_cat.get_mut(cat);
@@ -236,58 +1299,527 @@ fn parse_config(
                         sections_by_cat.insert(cat.clone(), vec![entry.label.clone()]);
                     }
                 });
-                let _ = entries.insert_sorted(
+                let _ = group.insert_sorted(
                     entry.label.clone(),
                     MenuEntry::Xdg(XdgSection {
                         label: entry.label.clone(),
                         icon: entry.icon.clone(),
                         applications: IndexMap::new(),
+                        max_visible: entry.max_visible,
                     }),
                 );
             }
             MenuConfig::XdgOther => {
-                let _ = entries.insert_sorted(
+                let _ = group.insert_sorted(
                     OTHER_LABEL.to_string(),
                     MenuEntry::Xdg(XdgSection {
                         label: OTHER_LABEL.to_string(),
                         icon: Some("applications-other".to_string()),
                         applications: IndexMap::new(),
+                        max_visible: None,
                     }),
                 );
             }
             MenuConfig::Custom(entry) => {
-                let _ = entries.insert_sorted(
-                    entry.label.clone(),
-                    MenuEntry::Custom(CustomEntry {
-                        icon: entry.icon.clone(),
-                        label: entry.label.clone(),
-                        on_click: entry.on_click.clone(),
-                    }),
+                let _ = group.insert_sorted(entry.label.clone(), MenuEntry::Custom(entry.clone()));
+            }
+            MenuConfig::Power(entry) => {
+                let _ = group.insert_sorted(entry.label.clone(), MenuEntry::Power(entry.clone()));
+            }
+            MenuConfig::Separator => {
+                entries.extend(group.drain(..));
+                entries.insert(format!("#separator-{index}"), MenuEntry::Separator);
+            }
+            MenuConfig::Heading { label } => {
+                entries.extend(group.drain(..));
+                entries.insert(
+                    format!("#heading-{index}"),
+                    MenuEntry::Heading(label.clone()),
                 );
             }
+            MenuConfig::Run(entry) => {
+                entries.extend(group.drain(..));
+                entries.insert(format!("#run-{index}"), MenuEntry::Run(entry.clone()));
+            }
         });
+    entries.extend(group);
     (entries, sections_by_cat)
 }
 
-fn make_entry<R: Clone + 'static>(
-    entry: &MenuEntry,
-    tx: mpsc::Sender<ModuleUpdateEvent<R>>,
-    icon_theme: IconTheme,
-) -> (Button, Option<gtk::Box>) {
+/// Creates a button which launches `application` when clicked,
+/// and toggles it as a favorite when right-clicked.
+///
+/// Launches are recorded in the history.
+fn make_application_button<R: Clone + 'static>(
+    application: &MenuApplication,
+    icon_theme: &IconTheme,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) -> Button {
+    let button = Button::builder().label(application.label.clone()).build();
+    button.set_tooltip_text(application.description.as_deref());
+
+    let icon_name = application.file_name.trim_end_matches(".desktop");
+    let gtk_image = gtk::Image::new();
+    let image = ImageProvider::parse(icon_name, icon_theme, true, 16);
+    if let Some(image) = image {
+        button.set_image(Some(&gtk_image));
+        button.set_always_show_image(true);
+
+        if let Err(err) = image.load_into_image(&gtk_image) {
+            error!("{err:?}");
+        }
+    };
+    button.foreach(|child| {
+        child.set_halign(Align::Start);
+    });
+
+    connect_application_button(&button, application, tx, events_tx);
+    button
+}
+
+/// Creates a grid cell button for `application`,
+/// with a large icon above its label.
+///
+/// Behaves the same as the list button when clicked.
+fn make_grid_button<R: Clone + 'static>(
+    application: &MenuApplication,
+    icon_theme: &IconTheme,
+    icon_size: i32,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) -> Button {
+    let button = Button::new();
+    button.add_class("grid-item");
+    button.set_tooltip_text(Some(
+        application
+            .description
+            .as_deref()
+            .unwrap_or(&application.name),
+    ));
+
+    let container = gtk::Box::new(Orientation::Vertical, 4);
+
+    let icon_name = application.file_name.trim_end_matches(".desktop");
+    let gtk_image = gtk::Image::new();
+    gtk_image.add_class("icon");
+    if let Some(image) = ImageProvider::parse(icon_name, icon_theme, true, icon_size) {
+        if let Err(err) = image.load_into_image(&gtk_image) {
+            error!("{err:?}");
+        }
+    }
+    container.add(&gtk_image);
+
+    let label = Label::new(Some(&application.label));
+    label.add_class("label");
+    label.set_justify(gtk::Justification::Center);
+    label.set_line_wrap(true);
+    label.set_line_wrap_mode(gtk::pango::WrapMode::WordChar);
+    label.set_max_width_chars(12);
+    container.add(&label);
+
+    button.add(&container);
+
+    connect_application_button(&button, application, tx, events_tx);
+    button
+}
+
+/// Launches `application` when `button` is clicked,
+/// and toggles it as a favorite when right-clicked.
+fn connect_application_button<R: Clone + 'static>(
+    button: &Button,
+    application: &MenuApplication,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) {
+    {
+        let path = application.path.clone();
+        let tx = tx.clone();
+        let events_tx = events_tx.clone();
+        button.connect_clicked(move |_button| {
+            try_send!(
+                events_tx,
+                MenuEvent::Launch {
+                    path: path.clone(),
+                    action: None,
+                }
+            );
+            try_send!(tx, ModuleUpdateEvent::ClosePopup);
+        });
+    }
+
+    {
+        let application = application.clone();
+        let tx = tx.clone();
+        let events_tx = events_tx.clone();
+        button.connect_button_press_event(move |button, event| {
+            if event.button() == 3 {
+                show_context_menu(button, event, &application, &tx, &events_tx);
+                Propagation::Stop
+            } else {
+                Propagation::Proceed
+            }
+        });
+    }
+}
+
+/// Shows the right-click menu for an application button.
+fn show_context_menu<R: Clone + 'static>(
+    button: &Button,
+    event: &EventButton,
+    application: &MenuApplication,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) {
+    let Some(config) = ContextMenuConfig::find(button) else {
+        return;
+    };
+
+    let menu = gtk::Menu::new();
+    menu.add_class("menu-context");
+    menu.set_attach_widget(Some(button));
+
+    let (label, sensitive) = {
+        let favorites = config.favorites.borrow();
+        if favorites.is_configured(&application.file_name) {
+            ("Remove from favorites", false)
+        } else if favorites.contains(&application.file_name) {
+            ("Remove from favorites", true)
+        } else {
+            ("Add to favorites", true)
+        }
+    };
+
+    let favorite = gtk::MenuItem::with_label(label);
+    favorite.set_sensitive(sensitive);
+    {
+        let file_name = application.file_name.clone();
+        let events_tx = events_tx.clone();
+        favorite.connect_activate(move |_| {
+            try_send!(events_tx, MenuEvent::ToggleFavorite(file_name.clone()));
+        });
+    }
+    menu.add(&favorite);
+
+    menu.add(&gtk::SeparatorMenuItem::new());
+
+    let open_location = gtk::MenuItem::with_label("Open desktop file location");
+    {
+        let path = application.path.clone();
+        let tx = tx.clone();
+        open_location.connect_activate(move |_| {
+            if let Some(dir) = path.parent() {
+                run_custom_command(&format!("xdg-open {}", shell_quote(&dir.to_string_lossy())));
+            }
+
+            try_send!(tx, ModuleUpdateEvent::ClosePopup);
+        });
+    }
+    menu.add(&open_location);
+
+    let copy_exec = gtk::MenuItem::with_label("Copy command");
+    copy_exec.set_sensitive(application.exec.is_some());
+    {
+        let exec = application.exec.clone();
+        copy_exec.connect_activate(move |_| {
+            if let Some(exec) = &exec {
+                gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).set_text(exec);
+            }
+        });
+    }
+    menu.add(&copy_exec);
+
+    if let Some(command) = &config.uninstall_command {
+        let command = command
+            .replace("{path}", &shell_quote(&application.path.to_string_lossy()))
+            .replace("{file_name}", &shell_quote(&application.file_name))
+            .replace(
+                "{app_id}",
+                &shell_quote(application.file_name.trim_end_matches(".desktop")),
+            );
+
+        let uninstall = gtk::MenuItem::with_label("Uninstall");
+        uninstall.add_class("uninstall");
+        {
+            let tx = tx.clone();
+            uninstall.connect_activate(move |_| {
+                run_custom_command(&command);
+                try_send!(tx, ModuleUpdateEvent::ClosePopup);
+            });
+        }
+
+        menu.add(&gtk::SeparatorMenuItem::new());
+        menu.add(&uninstall);
+    }
+
+    menu.show_all();
+    menu.popup_at_pointer(Some(&**event));
+}
+
+/// Creates a grid of application buttons,
+/// filling each row of `layout.columns` cells before starting the next.
+///
+/// Desktop actions are not shown in the grid.
+fn make_application_grid<R: Clone + 'static>(
+    applications: &IndexMap<String, MenuApplication>,
+    layout: GridLayout,
+    sub_menu: &gtk::Box,
+    icon_theme: &IconTheme,
+    max_visible: Option<usize>,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) -> (gtk::Grid, Vec<gtk::Widget>) {
+    let grid = gtk::Grid::new();
+    grid.add_class("grid");
+    grid.set_column_homogeneous(true);
+
+    let columns = layout.columns.max(1);
+    let mut hidden = vec![];
+
+    for (index, application) in applications.values().enumerate() {
+        let button = make_grid_button(application, icon_theme, layout.icon_size, tx, events_tx);
+
+        if max_visible.is_some_and(|max_visible| index >= max_visible) {
+            hidden.push(button.clone().upcast());
+        }
+
+        {
+            let sub_menu = sub_menu.clone();
+            button.connect_clicked(move |_button| {
+                sub_menu.hide();
+            });
+        }
+
+        let index = index as u32;
+        grid.attach(
+            &button,
+            (index % columns) as i32,
+            (index / columns) as i32,
+            1,
+            1,
+        );
+    }
+
+    (grid, hidden)
+}
+
+/// Hides `hidden`, and adds a button to the end of `sub_menu` which shows them again.
+fn add_show_more(sub_menu: &gtk::Box, hidden: Vec<gtk::Widget>) {
+    if hidden.is_empty() {
+        return;
+    }
+
+    for widget in &hidden {
+        widget.set_no_show_all(true);
+        widget.hide();
+    }
+
+    let button = Button::with_label("Show more…");
+    button.add_class("show-more");
+    button.set_tooltip_text(Some(&format!("{} more", hidden.len())));
+    sub_menu.add(&button);
+    button.show();
+
+    button.connect_clicked(move |button| {
+        for widget in &hidden {
+            widget.set_no_show_all(false);
+            widget.show_all();
+        }
+
+        // move focus to the first revealed entry before the button disappears
+        if let Some(first) = hidden.first().and_then(first_button) {
+            first.grab_focus();
+        }
+
+        button.hide();
+    });
+}
+
+/// Gets the first button in a widget, which may be the widget itself.
+fn first_button(widget: &gtk::Widget) -> Option<Button> {
+    visible_buttons(widget).into_iter().next()
+}
+
+/// Creates the button for an application, along with a chevron
+/// which reveals buttons for each of its desktop actions.
+/// Applications without any actions only get the application button.
+///
+/// Returns the widget to add to the menu, and the application button.
+fn make_application_row<R: Clone + 'static>(
+    application: &MenuApplication,
+    icon_theme: &IconTheme,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) -> (gtk::Widget, Button) {
+    let button = make_application_button(application, icon_theme, tx, events_tx);
+
+    if application.actions.is_empty() {
+        return (button.clone().upcast(), button);
+    }
+
+    let row = gtk::Box::new(Orientation::Vertical, 0);
+    row.add_class("application");
+
+    let header = gtk::Box::new(Orientation::Horizontal, 0);
+    header.pack_start(&button, true, true, 0);
+
+    let chevron = Button::with_label("›");
+    chevron.add_class("actions-toggle");
+    header.add(&chevron);
+    row.add(&header);
+
+    let actions = gtk::Box::new(Orientation::Vertical, 0);
+    actions.add_class("actions");
+    actions.set_no_show_all(true);
+
+    for action in &application.actions {
+        let button = Button::with_label(&action.label);
+        button.add_class("action");
+        button.foreach(|child| {
+            child.set_halign(Align::Start);
+        });
+
+        let path = application.path.clone();
+        let id = action.id.clone();
+        let tx = tx.clone();
+        let events_tx = events_tx.clone();
+        button.connect_clicked(move |_button| {
+            try_send!(
+                events_tx,
+                MenuEvent::Launch {
+                    path: path.clone(),
+                    action: Some(id.clone()),
+                }
+            );
+            try_send!(tx, ModuleUpdateEvent::ClosePopup);
+        });
+
+        button.show_all();
+        actions.add(&button);
+    }
+
+    row.add(&actions);
+
+    chevron.connect_clicked(move |chevron| {
+        let open = !actions.is_visible();
+        actions.set_visible(open);
+        chevron.set_label(if open { "⌄" } else { "›" });
+    });
+
+    (row.upcast(), button)
+}
+
+/// Rebuilds a section of application buttons from a list of desktop file names,
+/// skipping any which are not installed.
+fn build_application_section<R: Clone + 'static>(
+    section: &gtk::Box,
+    names: &[&str],
+    class: &str,
+    applications: &[MenuApplication],
+    icon_theme: &IconTheme,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) {
+    section.foreach(|child| {
+        section.remove(child);
+    });
+
+    for name in names {
+        let Some(application) = applications
+            .iter()
+            .find(|application| Favorites::normalize(&application.file_name) == *name)
+        else {
+            continue;
+        };
+
+        let (row, button) = make_application_row(application, icon_theme, tx, events_tx);
+        button.add_class(class);
+        section.add(&row);
+    }
+
+    section.show_all();
+}
+
+/// Creates a button which performs a power action when clicked.
+///
+/// Actions which require confirmation change the button label on the first click,
+/// and are only performed on the second.
+/// The confirmation is cancelled when the button is hidden.
+fn make_power_button<R: Clone + 'static>(
+    action: PowerAction,
+    confirm: bool,
+    icon_theme: &IconTheme,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+) -> Button {
+    let button = Button::new();
+    button.add_class("power-action");
+    button.add_class(action.class());
+
+    let button_container = gtk::Box::new(Orientation::Horizontal, 4);
+    button.add(&button_container);
+
+    let gtk_image = gtk::Image::new();
+    if let Some(image) = ImageProvider::parse(action.icon(), icon_theme, true, 16) {
+        button_container.add(&gtk_image);
+
+        if let Err(err) = image.load_into_image(&gtk_image) {
+            error!("{err:?}");
+        }
+    }
+
+    let label = Label::new(Some(action.label()));
+    button_container.add(&label);
+    button_container.foreach(|child| {
+        child.set_halign(Align::Start);
+    });
+
+    {
+        let label = label.clone();
+        let tx = tx.clone();
+        let events_tx = events_tx.clone();
+        button.connect_clicked(move |button| {
+            if confirm && !button.style_context().has_class("confirm") {
+                button.add_class("confirm");
+                label.set_label(&format!("Confirm {}?", action.label().to_lowercase()));
+                return;
+            }
+
+            try_send!(events_tx, MenuEvent::Power(action));
+            try_send!(tx, ModuleUpdateEvent::ClosePopup);
+        });
+    }
+
+    button.connect_unmap(move |button| {
+        button.remove_class("confirm");
+        label.set_label(action.label());
+    });
+
+    button
+}
+
+/// Creates the button for a menu entry,
+/// with an arrow if it opens a sub-menu.
+fn make_entry_button(
+    label: &str,
+    icon: Option<&str>,
+    has_sub_menu: bool,
+    icon_theme: &IconTheme,
+) -> Button {
     let button = Button::new();
     let button_container = gtk::Box::new(Orientation::Horizontal, 4);
-    let label = Label::builder().label(entry.label()).build();
+    let label = Label::builder().label(label).build();
     label.set_halign(Align::Start);
     button.add(&button_container);
 
-    if let Some(icon_name) = entry.icon() {
+    if let Some(icon_name) = icon {
         let gtk_image = gtk::Image::new();
         gtk_image.set_halign(Align::Start);
-        let image = ImageProvider::parse(&icon_name, &icon_theme, true, 16);
+        let image = ImageProvider::parse(icon_name, icon_theme, true, 16);
         if let Some(image) = image {
             button_container.add(&gtk_image);
 
-            if let Err(err) = image.load_into_image(gtk_image) {
+            if let Err(err) = image.load_into_image(&gtk_image) {
                 error!("{err:?}");
             }
         };
@@ -296,63 +1828,250 @@ fn make_entry<R: Clone + 'static>(
     button_container.foreach(|child| {
         child.set_halign(Align::Start);
     });
-    if let MenuEntry::Xdg(_) = entry {
+    if has_sub_menu {
         let right_arrow = Label::builder().label("🢒").build();
         right_arrow.set_halign(Align::End);
         button_container.pack_end(&right_arrow, false, false, 0);
     }
 
+    button
+}
+
+/// Runs the `on_click` command of a custom entry.
+fn run_custom_command(on_click: &str) {
+    let script = Script::from(on_click);
+    debug!("executing command: '{}'", script.cmd);
+
+    let args = Vec::new();
+
+    spawn(async move {
+        if let Err(err) = script.get_output(Some(&args)).await {
+            error!("{err:?}");
+        }
+    });
+}
+
+/// Creates the sub-menu for a custom entry with child entries.
+///
+/// Children with entries of their own get a nested sub-menu,
+/// which is stored on their button to be added to the popup by `add_entries`.
+fn make_custom_sub_menu<R: Clone + 'static>(
+    entries: &[CustomEntry],
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+    icon_theme: &IconTheme,
+) -> gtk::Box {
+    let sub_menu = gtk::Box::new(Orientation::Vertical, 0);
+
+    for entry in entries {
+        let has_children = !entry.entries.is_empty();
+        let button = make_entry_button(
+            &entry.label,
+            entry.icon.as_deref(),
+            has_children,
+            icon_theme,
+        );
+
+        if has_children {
+            let nested = make_custom_sub_menu(&entry.entries, tx, icon_theme);
+            nested.add_class("menu-popup_sub-menu");
+            button.set_tag(NESTED_SUB_MENU_TAG, nested);
+        } else if let Some(on_click) = entry.on_click.clone() {
+            let tx = tx.clone();
+            button.connect_clicked(move |_button| {
+                run_custom_command(&on_click);
+                try_send!(tx, ModuleUpdateEvent::ClosePopup);
+            });
+        }
+
+        button.show_all();
+        sub_menu.add(&button);
+    }
+
+    sub_menu
+}
+
+/// Creates the widget for a separator, heading or run prompt entry,
+/// which are shown in place of a button.
+fn make_decoration<R: Clone + 'static>(
+    entry: &MenuEntry,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+) -> Option<gtk::Widget> {
+    match entry {
+        MenuEntry::Separator => {
+            let separator = gtk::Separator::new(Orientation::Horizontal);
+            separator.add_class("separator");
+            Some(separator.upcast())
+        }
+        MenuEntry::Heading(label) => {
+            let label = Label::new(Some(label));
+            label.add_class("heading");
+            label.set_halign(Align::Start);
+            Some(label.upcast())
+        }
+        MenuEntry::Run(entry) => Some(make_run_prompt(entry, tx).upcast()),
+        _ => None,
+    }
+}
+
+/// Creates a text input which runs the entered command when enter is pressed.
+fn make_run_prompt<R: Clone + 'static>(
+    entry: &RunEntry,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+) -> gtk::Entry {
+    let prompt = gtk::Entry::new();
+    prompt.add_class("run");
+    prompt.set_placeholder_text(Some(&entry.placeholder));
+
+    let tx = tx.clone();
+    prompt.connect_activate(move |prompt| {
+        let command = prompt.text();
+        let command = command.trim();
+
+        if command.is_empty() {
+            return;
+        }
+
+        run_custom_command(command);
+        prompt.set_text("");
+        try_send!(tx, ModuleUpdateEvent::ClosePopup);
+    });
+
+    prompt
+}
+
+/// Finds the first run prompt in the main menu, if there is one.
+fn find_run_prompt(widget: &gtk::Widget) -> Option<gtk::Entry> {
+    if let Some(prompt) = widget.downcast_ref::<gtk::Entry>() {
+        Some(prompt.clone())
+    } else if let Some(container) = widget.downcast_ref::<gtk::Container>() {
+        container.children().iter().find_map(find_run_prompt)
+    } else {
+        None
+    }
+}
+
+fn make_entry<R: Clone + 'static>(
+    entry: &MenuEntry,
+    tx: mpsc::Sender<ModuleUpdateEvent<R>>,
+    events_tx: &mpsc::Sender<MenuEvent>,
+    icon_theme: IconTheme,
+    grid: Option<GridLayout>,
+) -> (Button, Option<gtk::Box>) {
+    let has_sub_menu = match entry {
+        MenuEntry::Xdg(_) | MenuEntry::Power(_) => true,
+        MenuEntry::Custom(entry) => !entry.entries.is_empty(),
+        MenuEntry::Separator | MenuEntry::Heading(_) | MenuEntry::Run(_) => false,
+    };
+
+    let button = make_entry_button(
+        &entry.label(),
+        entry.icon().as_deref(),
+        has_sub_menu,
+        &icon_theme,
+    );
+
     button.show_all();
 
     let sub_menu = match entry {
         MenuEntry::Xdg(entry) => {
             let sub_menu = gtk::Box::new(Orientation::Vertical, 0);
-            entry.applications.values().for_each(|sub_entry| {
-                let mut button = Button::builder();
-                button = button.label(sub_entry.label.clone());
-                let button = button.build();
-
-                let icon_name = sub_entry.file_name.trim_end_matches(".desktop");
-                let gtk_image = gtk::Image::new();
-                let image = ImageProvider::parse(icon_name, &icon_theme, true, 16);
-                if let Some(image) = image {
-                    button.set_image(Some(&gtk_image));
-                    button.set_always_show_image(true);
-
-                    if let Err(err) = image.load_into_image(gtk_image) {
-                        error!("{err:?}");
+
+            let hidden = if let Some(layout) = grid {
+                let (grid, hidden) = make_application_grid(
+                    &entry.applications,
+                    layout,
+                    &sub_menu,
+                    &icon_theme,
+                    entry.max_visible,
+                    &tx,
+                    events_tx,
+                );
+                sub_menu.add(&grid);
+                grid.show_all();
+                hidden
+            } else {
+                let mut hidden = vec![];
+
+                for (index, sub_entry) in entry.applications.values().enumerate() {
+                    let (row, button) =
+                        make_application_row(sub_entry, &icon_theme, &tx, events_tx);
+                    sub_menu.add(&row);
+
+                    {
+                        let sub_menu = sub_menu.clone();
+                        button.connect_clicked(move |_button| {
+                            sub_menu.hide();
+                        });
                     }
-                };
-                button.foreach(|child| {
-                    child.set_halign(Align::Start);
-                });
-                sub_menu.add(&button);
 
-                {
-                    let sub_menu = sub_menu.clone();
-                    let file_name = sub_entry.file_name.clone();
-                    let tx = tx.clone();
-                    button.connect_clicked(move |_button| {
-                        let _ = Command::new("gtk-launch")
-                            .arg(file_name.clone())
-                            .stdout(Stdio::null())
-                            .stderr(Stdio::null())
-                            .spawn();
-                        sub_menu.hide();
-                        try_send!(tx, ModuleUpdateEvent::ClosePopup);
-                    });
+                    row.show_all();
+
+                    if entry
+                        .max_visible
+                        .is_some_and(|max_visible| index >= max_visible)
+                    {
+                        hidden.push(row);
+                    }
                 }
 
+                hidden
+            };
+
+            add_show_more(&sub_menu, hidden);
+
+            Some(sub_menu)
+        }
+        MenuEntry::Power(entry) => {
+            let sub_menu = gtk::Box::new(Orientation::Vertical, 0);
+            sub_menu.add_class("power");
+
+            for &action in &entry.actions {
+                let button = make_power_button(
+                    action,
+                    entry.confirm.contains(&action),
+                    &icon_theme,
+                    &tx,
+                    events_tx,
+                );
+                sub_menu.add(&button);
                 button.show_all();
-            });
+            }
+
             Some(sub_menu)
         }
-        MenuEntry::Custom(_) => None,
+        MenuEntry::Custom(entry) if !entry.entries.is_empty() => {
+            Some(make_custom_sub_menu(&entry.entries, &tx, &icon_theme))
+        }
+        MenuEntry::Custom(_) | MenuEntry::Separator | MenuEntry::Heading(_) | MenuEntry::Run(_) => {
+            None
+        }
     };
 
     (button, sub_menu)
 }
 
+/// Adds a sub-menu to the popup container,
+/// wrapping it in a scroll container if the menu has a fixed height.
+///
+/// Returns the widget to show to open the sub-menu.
+fn wrap_sub_menu(sub_menu: &gtk::Box, container: &gtk::Box, height: Option<i32>) -> gtk::Widget {
+    if let Some(height) = height {
+        container.set_height_request(height);
+        let scrolled = gtk::ScrolledWindow::builder()
+            .max_content_height(height)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .build();
+        sub_menu.show();
+        scrolled.add(sub_menu);
+        container.add(&scrolled);
+
+        scrolled.upcast()
+    } else {
+        container.add(sub_menu);
+        sub_menu.clone().upcast()
+    }
+}
+
 fn add_entries(
     entry: &MenuEntry,
     button: Button,
@@ -360,22 +2079,16 @@ fn add_entries(
     main_menu: gtk::Box,
     container: gtk::Box,
     height: Option<i32>,
+    sub_menus: &SubMenus,
 ) {
     let container1 = container.clone();
     main_menu.add(&button);
 
     if let Some(sub_menu) = sub_menu {
-        if let Some(height) = height {
-            container.set_height_request(height);
-            let scrolled = gtk::ScrolledWindow::builder()
-                .max_content_height(height)
-                .hscrollbar_policy(gtk::PolicyType::Never)
-                .build();
-            sub_menu.show();
-            scrolled.add(&sub_menu);
-            container.add(&scrolled);
+        let wrapper = wrap_sub_menu(&sub_menu, &container, height);
 
-            let sub_menu1 = scrolled.clone();
+        {
+            let wrapper = wrapper.clone();
             let sub_menu_popup_container = sub_menu.clone();
             button.connect_clicked(move |_button| {
                 container1.children().iter().skip(1).for_each(|sub_menu| {
@@ -383,49 +2096,491 @@ fn add_entries(
                         sub_menu.hide();
                     }
                 });
-                sub_menu1.show();
+                wrapper.show();
                 // Reset scroll to top.
-                if let Some(w) = sub_menu_popup_container.children().first() {
-                    w.set_has_focus(true)
-                }
-            });
-        } else {
-            container.add(&sub_menu);
-            let sub_menu1 = sub_menu.clone();
-            button.connect_clicked(move |_button| {
-                container1.children().iter().skip(1).for_each(|sub_menu| {
-                    if sub_menu.get_visible() {
-                        sub_menu.hide();
+                if height.is_some() {
+                    if let Some(w) = sub_menu_popup_container.children().first() {
+                        w.set_has_focus(true)
                     }
-                });
-                sub_menu1.show();
+                }
             });
         }
+
+        sub_menus.borrow_mut().push(SubMenu {
+            depth: 1,
+            button: button.clone(),
+            wrapper,
+            entries: sub_menu.clone(),
+        });
+
+        add_nested_sub_menus(&sub_menu, 1, &container, height, sub_menus);
     }
-    if let MenuEntry::Custom(entry) = entry {
-        let label = entry.on_click.clone();
+    if let MenuEntry::Custom(CustomEntry {
+        on_click: Some(on_click),
+        ..
+    }) = entry
+    {
+        let on_click = on_click.clone();
         let container = container.clone();
         button.connect_clicked(move |_button| {
             container.children().iter().skip(1).for_each(|sub_menu| {
                 sub_menu.hide();
             });
-            let script = Script::from(label.as_str());
-            debug!("executing command: '{}'", script.cmd);
+            run_custom_command(&on_click);
+        });
+    }
+    main_menu.show_all();
+}
+
+/// Adds the nested sub-menus of custom entries inside `parent` to the popup,
+/// recursing into any sub-menus nested inside those.
+///
+/// Opening a nested sub-menu closes any others at the same depth or deeper,
+/// leaving its parents open.
+fn add_nested_sub_menus(
+    parent: &gtk::Box,
+    depth: usize,
+    container: &gtk::Box,
+    height: Option<i32>,
+    sub_menus: &SubMenus,
+) {
+    let depth = depth + 1;
+
+    for button in parent
+        .children()
+        .iter()
+        .filter_map(|widget| widget.downcast_ref::<Button>())
+    {
+        let Some(nested) = button.get_tag::<gtk::Box>(NESTED_SUB_MENU_TAG).cloned() else {
+            continue;
+        };
+
+        nested.set_valign(parent.valign());
+        nested.set_width_request(parent.width_request());
+
+        let wrapper = wrap_sub_menu(&nested, container, height);
+
+        {
+            let sub_menus = sub_menus.clone();
+            let wrapper = wrapper.clone();
+            button.connect_clicked(move |_button| {
+                for sub_menu in sub_menus
+                    .borrow()
+                    .iter()
+                    .filter(|sub_menu| sub_menu.depth >= depth)
+                {
+                    sub_menu.wrapper.hide();
+                }
+                wrapper.show();
+            });
+        }
+
+        sub_menus.borrow_mut().push(SubMenu {
+            depth,
+            button: button.clone(),
+            wrapper,
+            entries: nested.clone(),
+        });
+
+        add_nested_sub_menus(&nested, depth, container, height, sub_menus);
+    }
+}
+
+/// Gets the buttons in each section of the main menu, in order.
+fn main_menu_buttons(main_menu: &gtk::Box) -> Vec<Button> {
+    main_menu
+        .children()
+        .iter()
+        .filter_map(|section| section.downcast_ref::<gtk::Box>())
+        .flat_map(|section| section.children())
+        .flat_map(|widget| visible_buttons(&widget))
+        .collect()
+}
+
+/// Gets the buttons in a sub-menu, in order.
+fn sub_menu_buttons(sub_menu: &SubMenu) -> Vec<Button> {
+    sub_menu
+        .entries
+        .children()
+        .iter()
+        .flat_map(visible_buttons)
+        .collect()
+}
+
+/// Gets the visible buttons in a menu entry, in order.
+/// Applications with desktop actions contain several buttons.
+fn visible_buttons(widget: &gtk::Widget) -> Vec<Button> {
+    if !widget.is_visible() {
+        vec![]
+    } else if let Some(button) = widget.downcast_ref::<Button>() {
+        vec![button.clone()]
+    } else if let Some(container) = widget.downcast_ref::<gtk::Container>() {
+        container
+            .children()
+            .iter()
+            .flat_map(visible_buttons)
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Moves focus to the next or previous button in `buttons`,
+/// wrapping around at either end.
+fn move_focus(buttons: &[Button], focus: Option<&gtk::Widget>, forward: bool) {
+    if buttons.is_empty() {
+        return;
+    }
+
+    let current = focus.and_then(|focus| buttons.iter().position(|button| button == focus));
+
+    let next = match (current, forward) {
+        (Some(index), true) => (index + 1) % buttons.len(),
+        (Some(index), false) => (index + buttons.len() - 1) % buttons.len(),
+        (None, true) => 0,
+        (None, false) => buttons.len() - 1,
+    };
+
+    buttons[next].grab_focus();
+}
+
+/// Opens the sub-menu belonging to `button`, if it has one,
+/// and moves focus to its first entry.
+fn enter_sub_menu(sub_menus: &SubMenus, button: &Button) -> bool {
+    let sub_menu = sub_menus
+        .borrow()
+        .iter()
+        .find(|sub_menu| &sub_menu.button == button)
+        .cloned();
+
+    let Some(sub_menu) = sub_menu else {
+        return false;
+    };
+
+    if !sub_menu.wrapper.is_visible() {
+        button.clicked();
+    }
+
+    if let Some(first) = sub_menu_buttons(&sub_menu).first() {
+        first.grab_focus();
+    }
+
+    true
+}
+
+/// Handles arrow key, enter and escape presses in the popup.
+///
+/// Up/down move between entries in the focused column,
+/// right/left enter and leave sub-menus,
+/// enter activates the focused entry and escape closes the popup.
+fn handle_key_press<R: Clone + 'static>(
+    event: &EventKey,
+    window: &ApplicationWindow,
+    main_menu: &gtk::Box,
+    sub_menus: &SubMenus,
+    tx: &mpsc::Sender<ModuleUpdateEvent<R>>,
+) -> Propagation {
+    let focus = window.focused_widget();
+
+    // the open sub-menu, if focus is currently inside it
+    let focused_sub_menu = sub_menus
+        .borrow()
+        .iter()
+        .filter(|sub_menu| sub_menu.wrapper.is_visible())
+        .find(|sub_menu| {
+            focus
+                .as_ref()
+                .is_some_and(|focus| focus.is_ancestor(&sub_menu.entries))
+        })
+        .cloned();
+
+    let focused_button = focus
+        .as_ref()
+        .and_then(|focus| focus.downcast_ref::<Button>())
+        .cloned();
+
+    // the run prompt needs left, right and enter for editing and running its command
+    let prompt_focused = focus.as_ref().is_some_and(|focus| focus.is::<gtk::Entry>());
+
+    match event.keyval() {
+        keys::Left | keys::Right | keys::Return | keys::KP_Enter if prompt_focused => {
+            return Propagation::Proceed;
+        }
+        keys::Up | keys::Down => {
+            let buttons = focused_sub_menu
+                .as_ref()
+                .map_or_else(|| main_menu_buttons(main_menu), sub_menu_buttons);
+
+            move_focus(&buttons, focus.as_ref(), event.keyval() == keys::Down);
+        }
+        keys::Right => {
+            if let Some(button) = &focused_button {
+                enter_sub_menu(sub_menus, button);
+            }
+        }
+        keys::Left => {
+            if let Some(sub_menu) = focused_sub_menu {
+                for open in sub_menus
+                    .borrow()
+                    .iter()
+                    .filter(|open| open.depth >= sub_menu.depth)
+                {
+                    open.wrapper.hide();
+                }
+
+                sub_menu.button.grab_focus();
+            }
+        }
+        keys::Return | keys::KP_Enter => {
+            if let Some(button) = focused_button {
+                if !enter_sub_menu(sub_menus, &button) {
+                    button.clicked();
+                }
+            }
+        }
+        keys::Escape => {
+            for sub_menu in sub_menus.borrow().iter() {
+                sub_menu.wrapper.hide();
+            }
+
+            try_send!(tx, ModuleUpdateEvent::ClosePopup);
+        }
+        _ => return Propagation::Proceed,
+    }
+
+    Propagation::Stop
+}
 
-            let args = Vec::new();
+/// Handles an event sent by a button in the menu.
+fn handle_event(
+    event: MenuEvent,
+    favorites: &RefCell<Favorites>,
+    history: &RefCell<History>,
+    terminal: &str,
+) {
+    match event {
+        MenuEvent::ToggleFavorite(file_name) => favorites.borrow_mut().toggle(&file_name),
+        MenuEvent::Launch { path, action } => {
+            debug!("Launching '{}' (action: {action:?})", path.display());
+
+            if let Err(err) = launch(&path, action.as_deref(), terminal) {
+                error!("{err:?}");
+            }
+
+            if let Some(file_name) = path.file_name() {
+                history.borrow_mut().record(&file_name.to_string_lossy());
+            }
+        }
+        MenuEvent::Power(action) => {
+            debug!("Performing power action: {action:?}");
 
             spawn(async move {
-                if let Err(err) = script.get_output(Some(&args)).await {
-                    error!("{err:?}");
+                if let Err(err) = action.run().await {
+                    error!("Failed to perform power action {action:?}: {err:?}");
+                }
+            });
+        }
+    }
+}
+
+/// A fullscreen application launcher,
+/// shown instead of the popup when `mode` is `overlay`.
+#[derive(Clone)]
+struct MenuOverlay {
+    window: ApplicationWindow,
+    search: gtk::SearchEntry,
+}
+
+impl MenuOverlay {
+    fn toggle(&self) {
+        if self.window.is_visible() {
+            self.window.hide();
+        } else {
+            self.search.set_text("");
+            self.window.show_all();
+            self.search.grab_focus();
+        }
+    }
+}
+
+impl MenuModule {
+    /// Creates the overlay window, containing a search box
+    /// above a grid of every application.
+    fn into_overlay(
+        self,
+        rx: broadcast::Receiver<Snapshot<Vec<MenuApplication>>>,
+        info: &ModuleInfo,
+    ) -> MenuOverlay {
+        let window = ApplicationWindow::builder().application(info.app).build();
+
+        window.init_layer_shell();
+        window.set_layer(gtk_layer_shell::Layer::Overlay);
+        window.set_namespace(concat!(env!("CARGO_PKG_NAME"), "-menu"));
+        window.set_monitor(info.monitor);
+        window.set_keyboard_interactivity(true);
+
+        // cover the whole output, including the bar
+        window.set_exclusive_zone(-1);
+        for edge in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+            window.set_anchor(edge, true);
+        }
+
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+        container.add_class("menu-overlay");
+
+        let search = gtk::SearchEntry::new();
+        search.add_class("search");
+        search.set_halign(Align::Center);
+        container.add(&search);
+
+        let flow_box = gtk::FlowBox::builder()
+            .homogeneous(true)
+            .selection_mode(gtk::SelectionMode::None)
+            .valign(Align::Start)
+            .build();
+        flow_box.add_class("grid");
+
+        let scrolled = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .build();
+        scrolled.add(&flow_box);
+        container.add(&scrolled);
+
+        window.add(&container);
+
+        let overlay = MenuOverlay { window, search };
+
+        // buttons close the popup when clicked,
+        // which is the overlay window here.
+        let (tx, close_rx) = mpsc::channel::<ModuleUpdateEvent<()>>(8);
+        let (events_tx, events_rx) = mpsc::channel(8);
+
+        {
+            let window = overlay.window.clone();
+            glib_recv_mpsc!(close_rx, event => {
+                if matches!(event, ModuleUpdateEvent::ClosePopup) {
+                    window.hide();
+                }
+            });
+        }
+
+        {
+            let favorites = rc_mut!(Favorites::load(self.favorites.clone()));
+            let history = RefCell::new(History::load());
+
+            container.set_tag(
+                CONTEXT_MENU_TAG,
+                ContextMenuConfig {
+                    favorites: favorites.clone(),
+                    uninstall_command: self.uninstall_command.clone(),
+                },
+            );
+            let terminal = self.terminal.clone().unwrap_or_else(default_terminal);
+
+            glib_recv_mpsc!(events_rx, event => {
+                handle_event(event, &favorites, &history, &terminal);
+            });
+        }
+
+        {
+            let search = overlay.search.clone();
+            flow_box.set_filter_func(Some(Box::new(move |child| {
+                let query = search.text().to_lowercase();
+
+                query.is_empty()
+                    || child
+                        .get_tag::<String>("search")
+                        .is_some_and(|text| text.contains(&query))
+            })));
+        }
+
+        {
+            let flow_box = flow_box.clone();
+            overlay.search.connect_search_changed(move |_| {
+                flow_box.invalidate_filter();
+            });
+        }
+
+        // enter launches the first match
+        {
+            let flow_box = flow_box.clone();
+            overlay.search.connect_activate(move |_| {
+                let first = flow_box
+                    .children()
+                    .into_iter()
+                    .find(WidgetExt::is_child_visible)
+                    .and_then(|child| child.downcast::<gtk::FlowBoxChild>().ok());
+
+                if let Some(child) = first {
+                    activate_overlay_child(&child);
                 }
             });
+        }
+
+        flow_box.connect_child_activated(|_, child| activate_overlay_child(child));
+
+        overlay.window.connect_key_press_event(|window, event| {
+            if event.keyval() == keys::Escape {
+                window.hide();
+                Propagation::Stop
+            } else {
+                Propagation::Proceed
+            }
         });
+
+        {
+            let icon_theme = info.icon_theme.clone();
+            let icon_size = self.grid_icon_size;
+
+            glib_recv!(rx, applications => {
+                flow_box.foreach(|child| {
+                    flow_box.remove(child);
+                });
+
+                let mut applications = applications.to_vec();
+                applications.sort_by_key(|application| application.label.to_lowercase());
+
+                for application in &applications {
+                    let button = make_grid_button(application, &icon_theme, icon_size, &tx, &events_tx);
+
+                    let child = gtk::FlowBoxChild::new();
+                    child.set_tag(
+                        "search",
+                        format!(
+                            "{}\n{}\n{}\n{}",
+                            application.label,
+                            application.name,
+                            application.file_name,
+                            application.description.as_deref().unwrap_or_default()
+                        )
+                        .to_lowercase(),
+                    );
+
+                    child.add(&button);
+                    flow_box.add(&child);
+                }
+
+                flow_box.show_all();
+            });
+        }
+
+        overlay
+    }
+}
+
+/// Clicks the application button inside an overlay grid item.
+fn activate_overlay_child(child: &gtk::FlowBoxChild) {
+    if let Some(button) = child
+        .child()
+        .and_then(|child| child.downcast::<Button>().ok())
+    {
+        button.clicked();
     }
-    main_menu.show_all();
 }
 
 impl Module<Button> for MenuModule {
-    type SendMessage = Vec<MenuApplication>;
+    type SendMessage = Snapshot<Vec<MenuApplication>>;
     type ReceiveMessage = ();
 
     module_impl!("menu");
@@ -437,54 +2592,64 @@ impl Module<Button> for MenuModule {
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        let max_label_length = self.max_label_length;
+        let options = Arc::new(LoadOptions {
+            max_label_length: self.max_label_length,
+            prefer_generic_name: self.prefer_generic_name,
+            desktops: self
+                .desktop
+                .clone()
+                .map_or_else(current_desktops, |desktop| {
+                    desktop.split(':').map(str::to_string).collect()
+                }),
+            category_map: CategoryMap::load(&self.category_map, self.category_map_file.as_deref()),
+            source_priority: self.deduplicate.then(|| self.source_priority.clone()),
+        });
 
         spawn(async move {
-            let files = find_desktop_files();
-            let apps = files
-                .iter()
-                .filter_map(|file_path| {
-                    let file_name = file_path
-                        .as_path()
-                        .file_name()
-                        .expect("find_desktop_files returned empty pathbuf")
-                        .to_string_lossy()
-                        .into_owned();
-                    // TODO filter out results that fail TryExec
-                    let entry = Entry::parse_file(file_path).ok()?;
-                    let desktop = entry.section("Desktop Entry");
-                    let typ = desktop.attr("Type").unwrap_or("N/A");
-                    if typ != "Application" {
-                        return None;
-                    }
-                    let raw_cats = desktop.attr("Categories").unwrap_or("Misc");
-                    let categories = raw_cats
-                        .trim_end_matches(';')
-                        .split(';')
-                        .map(|s| s.to_string())
-                        .collect();
-                    let mut name = desktop.attr("Name")?.to_string();
-
-                    if name.graphemes(true).count() > max_label_length {
-                        name = name
-                            .graphemes(true)
-                            .take(max_label_length - 1)
-                            .collect::<String>();
-                        name += "…";
-                    }
+            load_applications(&options, true, &tx).await;
+
+            let (changed_tx, mut changed_rx) = mpsc::channel(1);
 
-                    // Some .desktop files are only for associating mimetypes
-                    if desktop.attr("NoDisplay") == Some("true") {
-                        return None;
+            let mut watcher = recommended_watcher(move |res: notify::Result<Event>| match res {
+                Ok(event)
+                    if matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    ) =>
+                {
+                    let is_desktop_file = event
+                        .paths
+                        .iter()
+                        .any(|path| path.extension().is_some_and(|ext| ext == "desktop"));
+
+                    if is_desktop_file {
+                        // channel only needs to hold a single pending change
+                        let _ = changed_tx.try_send(());
                     }
-                    Some(MenuApplication {
-                        label: name,
-                        file_name,
-                        categories,
-                    })
-                })
-                .collect::<Vec<MenuApplication>>();
-            try_send!(tx, ModuleUpdateEvent::Update(apps));
+                }
+                Err(err) => error!("Error occurred when watching application directories: {err:?}"),
+                _ => {}
+            })?;
+
+            for dir in find_application_dirs() {
+                if let Err(err) = watcher.watch(&dir, RecursiveMode::Recursive) {
+                    warn!(
+                        "Failed to watch '{}' for desktop files: {err:?}",
+                        dir.display()
+                    );
+                }
+            }
+
+            while changed_rx.recv().await.is_some() {
+                // package managers tend to write several files at once,
+                // so wait for things to settle before reloading.
+                sleep(Duration::from_millis(500)).await;
+                while changed_rx.try_recv().is_ok() {}
+
+                debug!("Desktop files changed, reloading applications");
+                load_applications(&options, false, &tx).await;
+            }
+
             Ok::<(), Report>(())
         });
 
@@ -510,12 +2675,19 @@ impl Module<Button> for MenuModule {
                 button.set_image(Some(&gtk_image));
                 button.set_always_show_image(true);
 
-                if let Err(err) = image.load_into_image(gtk_image) {
+                if let Err(err) = image.load_into_image(&gtk_image) {
                     error!("{err:?}");
                 }
             };
         }
 
+        if self.mode == MenuMode::Overlay {
+            let overlay = self.into_overlay(context.subscribe(), info);
+            button.connect_clicked(move |_| overlay.toggle());
+
+            return Ok(ModuleParts::new(button, None));
+        }
+
         let tx = context.tx.clone();
         button.connect_clicked(move |button| {
             try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
@@ -584,11 +2756,128 @@ impl Module<Button> for MenuModule {
         let (mut center_entries, sections_by_cat) = parse_config(self.center, sections_by_cat);
         let (mut end_entries, sections_by_cat) = parse_config(self.end, sections_by_cat);
 
+        let sub_menus: SubMenus = rc_mut!(vec![]);
+
+        let favorites = rc_mut!(Favorites::load(self.favorites.clone()));
+        let history = rc_mut!(History::load());
+        let all_applications = rc_mut!(Snapshot::<Vec<MenuApplication>>::default());
+
+        container.set_tag(
+            CONTEXT_MENU_TAG,
+            ContextMenuConfig {
+                favorites: favorites.clone(),
+                uninstall_command: self.uninstall_command.clone(),
+            },
+        );
+        let (events_tx, events_rx) = mpsc::channel(8);
+
+        let favorites_section = gtk::Box::new(Orientation::Vertical, 0);
+        favorites_section.add_class("menu-popup_main_favorites");
+
+        let recent_section = gtk::Box::new(Orientation::Vertical, 0);
+        recent_section.add_class("menu-popup_main_recent");
+
+        let recent_count = self.recent;
+        let sort = self.sort;
+        let grid = (self.view == MenuView::Grid).then_some(GridLayout {
+            columns: self.grid_columns,
+            icon_size: self.grid_icon_size,
+        });
+
+        // rebuilds the favorites and recent sections
+        let build_sections = {
+            let favorites = favorites.clone();
+            let history = history.clone();
+            let all_applications = all_applications.clone();
+            let favorites_section = favorites_section.clone();
+            let recent_section = recent_section.clone();
+            let icon_theme = icon_theme.clone();
+            let tx = context.tx.clone();
+            let events_tx = events_tx.clone();
+
+            move || {
+                let applications = all_applications.borrow();
+
+                build_application_section(
+                    &favorites_section,
+                    &favorites.borrow().all(),
+                    "favorite",
+                    &applications,
+                    &icon_theme,
+                    &tx,
+                    &events_tx,
+                );
+
+                build_application_section(
+                    &recent_section,
+                    &history.borrow().recent(recent_count),
+                    "recent",
+                    &applications,
+                    &icon_theme,
+                    &tx,
+                    &events_tx,
+                );
+            }
+        };
+
+        let build_sections = Rc::new(build_sections);
+
+        {
+            let favorites = favorites.clone();
+            let history = history.clone();
+            let build_sections = build_sections.clone();
+            let terminal = self.terminal.clone().unwrap_or_else(default_terminal);
+
+            glib_recv_mpsc!(events_rx, event => {
+                handle_event(event, &favorites, &history, &terminal);
+                build_sections();
+            });
+        }
+
+        // take keyboard focus while the menu is open,
+        // so that it can be navigated using the keyboard.
+        {
+            let window = context.popup.window.clone();
+            let main_menu = main_menu.clone();
+
+            container.connect_map(move |_| {
+                window.set_keyboard_interactivity(true);
+
+                if let Some(prompt) = find_run_prompt(main_menu.upcast_ref()) {
+                    prompt.grab_focus();
+                } else if let Some(first) = main_menu_buttons(&main_menu).first() {
+                    first.grab_focus();
+                }
+            });
+        }
+
+        {
+            let main_menu = main_menu.clone();
+            let sub_menus = sub_menus.clone();
+            let tx = context.tx.clone();
+
+            connect_popup_key_press(&context.popup, &container, move |window, event| {
+                handle_key_press(event, window, &main_menu, &sub_menus, &tx)
+            });
+        }
+
         let container2 = container.clone();
         {
             let main_menu = main_menu.clone();
             let container = container.clone();
             glib_recv!(rx, applications => {
+                // applications are reloaded whenever desktop files change,
+                // so start from empty sections each time.
+                for entry in start_entries
+                    .values_mut()
+                    .chain(center_entries.values_mut())
+                    .chain(end_entries.values_mut())
+                {
+                    if let MenuEntry::Xdg(section) = entry {
+                        section.applications.clear();
+                    }
+                }
+
                 for application in applications.iter() {
                     let mut inserted = false;
                     for category in application.categories.iter() {
@@ -621,14 +2910,44 @@ impl Module<Button> for MenuModule {
                 main_menu.foreach(|child| {
                     main_menu.remove(child);
                 });
+
+                for sub_menu in sub_menus.borrow_mut().drain(..) {
+                    container.remove(&sub_menu.wrapper);
+                }
+
+                if sort == MenuSort::Frecency {
+                    let history = history.borrow();
+
+                    for entry in start_entries
+                        .values_mut()
+                        .chain(center_entries.values_mut())
+                        .chain(end_entries.values_mut())
+                    {
+                        if let MenuEntry::Xdg(section) = entry {
+                            history.sort(&mut section.applications);
+                        }
+                    }
+                }
+
+                all_applications.replace(applications);
+                main_menu.add(&favorites_section);
+                main_menu.add(&recent_section);
+                build_sections();
+
                 let start_section = gtk::Box::new(Orientation::Vertical, 0);
                 start_section.style_context().add_class("menu-popup_main_start");
                 main_menu.add(&start_section);
                 for entry in start_entries.values() {
+                    if let Some(widget) = make_decoration(entry, &context.tx) {
+                        start_section.add(&widget);
+                        widget.show();
+                        continue;
+                    }
+
                     let container1 = container.clone();
                     let start_section = start_section.clone();
                     let tx = context.tx.clone();
-                    let (button, sub_menu) = make_entry(entry, tx, icon_theme.clone());
+                    let (button, sub_menu) = make_entry(entry, tx, &events_tx, icon_theme.clone(), grid);
                     if let Some(sub_menu) = sub_menu.clone() {
                         sub_menu.set_valign(alignment);
                         sub_menu.style_context().add_class("menu-popup_sub-menu");
@@ -636,16 +2955,22 @@ impl Module<Button> for MenuModule {
                             sub_menu.set_width_request(width / 2);
                         }
                     }
-                    add_entries(entry, button, sub_menu, start_section, container1, self.height);
+                    add_entries(entry, button, sub_menu, start_section, container1, self.height, &sub_menus);
                 };
                 let center_section = gtk::Box::new(Orientation::Vertical, 0);
                 center_section.style_context().add_class("menu-popup_main_center");
                 main_menu.add(&center_section);
                 for entry in center_entries.values() {
+                    if let Some(widget) = make_decoration(entry, &context.tx) {
+                        center_section.add(&widget);
+                        widget.show();
+                        continue;
+                    }
+
                     let container1 = container.clone();
                     let center_section = center_section.clone();
                     let tx = context.tx.clone();
-                    let (button, sub_menu) = make_entry(entry, tx, icon_theme.clone());
+                    let (button, sub_menu) = make_entry(entry, tx, &events_tx, icon_theme.clone(), grid);
                     if let Some(sub_menu) = sub_menu.clone() {
                         sub_menu.set_valign(alignment);
                         sub_menu.style_context().add_class("menu-popup_sub-menu");
@@ -653,16 +2978,22 @@ impl Module<Button> for MenuModule {
                             sub_menu.set_width_request(width / 2);
                         }
                     }
-                    add_entries(entry, button, sub_menu, center_section, container1, self.height);
+                    add_entries(entry, button, sub_menu, center_section, container1, self.height, &sub_menus);
                 };
                 let end_section = gtk::Box::new(Orientation::Vertical, 0);
                 end_section.style_context().add_class("menu-popup_main_end");
                 main_menu.add(&end_section);
                 for entry in end_entries.values() {
+                    if let Some(widget) = make_decoration(entry, &context.tx) {
+                        end_section.add(&widget);
+                        widget.show();
+                        continue;
+                    }
+
                     let container1 = container.clone();
                     let end_section = end_section.clone();
                     let tx = context.tx.clone();
-                    let (button, sub_menu) = make_entry(entry, tx, icon_theme.clone());
+                    let (button, sub_menu) = make_entry(entry, tx, &events_tx, icon_theme.clone(), grid);
                     if let Some(sub_menu) = sub_menu.clone() {
                         sub_menu.set_valign(alignment);
                         sub_menu.style_context().add_class("menu-popup_sub-menu");
@@ -670,7 +3001,7 @@ impl Module<Button> for MenuModule {
                             sub_menu.set_width_request(width / 2);
                         }
                     }
-                    add_entries(entry, button, sub_menu, end_section, container1, self.height);
+                    add_entries(entry, button, sub_menu, end_section, container1, self.height, &sub_menus);
                 };
             });
 
@@ -720,3 +3051,179 @@ impl Module<Button> for MenuModule {
         Some(container)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_favorites() {
+        let favorites = Favorites {
+            configured: vec![String::from("firefox"), String::from("foot")],
+            pinned: vec![String::from("foot"), String::from("code")],
+        };
+
+        assert_eq!(favorites.all(), vec!["firefox", "foot", "code"]);
+
+        assert!(favorites.contains("firefox.desktop"));
+        assert!(favorites.contains("code"));
+        assert!(!favorites.contains("gimp.desktop"));
+
+        assert!(favorites.is_configured("foot.desktop"));
+        assert!(!favorites.is_configured("code"));
+    }
+
+    #[test]
+    fn test_history() {
+        const DAY: u64 = 24 * 60 * 60;
+        let now = 100 * DAY;
+
+        let history = History {
+            entries: IndexMap::from([
+                (
+                    String::from("firefox"),
+                    HistoryEntry {
+                        count: 10,
+                        last_launch: now - 60 * DAY,
+                    },
+                ),
+                (
+                    String::from("foot"),
+                    HistoryEntry {
+                        count: 4,
+                        last_launch: now - DAY,
+                    },
+                ),
+                (
+                    String::from("code"),
+                    HistoryEntry {
+                        count: 2,
+                        last_launch: now - 5 * DAY,
+                    },
+                ),
+            ]),
+        };
+
+        // recent launches outweigh older, more frequent ones
+        assert_eq!(history.score("firefox.desktop", now), 300);
+        assert_eq!(history.score("foot", now), 400);
+        assert_eq!(history.score("code", now), 140);
+        assert_eq!(history.score("gimp", now), 0);
+
+        assert_eq!(history.recent(2), vec!["foot", "code"]);
+    }
+
+    #[test]
+    fn test_locale_variants() {
+        assert_eq!(
+            locale_variants("de_DE.UTF-8@euro"),
+            vec!["de_DE@euro", "de_DE", "de@euro", "de"]
+        );
+        assert_eq!(locale_variants("fr_CA.UTF-8"), vec!["fr_CA", "fr"]);
+        assert_eq!(locale_variants("sr@latin"), vec!["sr@latin", "sr"]);
+
+        assert!(locale_variants("C.UTF-8").is_empty());
+        assert!(locale_variants("POSIX").is_empty());
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let application = |file_name: &str| MenuApplication {
+            label: file_name.to_string(),
+            name: file_name.to_string(),
+            description: None,
+            file_name: file_name.to_string(),
+            path: PathBuf::from(file_name),
+            exec: None,
+            categories: vec![],
+            actions: vec![],
+        };
+
+        let applications = vec![
+            (
+                String::from("firefox"),
+                ApplicationSource::Flatpak,
+                application("org.mozilla.firefox.desktop"),
+            ),
+            (
+                String::from("firefox"),
+                ApplicationSource::Native,
+                application("firefox.desktop"),
+            ),
+            (
+                String::from("gimp"),
+                ApplicationSource::Flatpak,
+                application("org.gimp.GIMP.desktop"),
+            ),
+            // the same application from the same source is not a duplicate
+            (
+                String::from("gimp"),
+                ApplicationSource::Flatpak,
+                application("org.gimp.GIMP.beta.desktop"),
+            ),
+        ];
+
+        let file_names = |priority: &[ApplicationSource]| {
+            deduplicate(applications.clone(), priority)
+                .into_iter()
+                .map(|(_, _, application)| application.file_name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            file_names(&default_source_priority()),
+            vec![
+                "firefox.desktop",
+                "org.gimp.GIMP.desktop",
+                "org.gimp.GIMP.beta.desktop"
+            ]
+        );
+
+        // sources missing from the priority list come last
+        assert_eq!(
+            file_names(&[ApplicationSource::Flatpak]),
+            vec![
+                "org.mozilla.firefox.desktop",
+                "org.gimp.GIMP.desktop",
+                "org.gimp.GIMP.beta.desktop"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_category_map() {
+        let categories = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let map = CategoryMap::load(
+            &HashMap::from([
+                (String::from("Network"), categories(&["Internet"])),
+                (String::from("WebBrowser"), categories(&["Internet"])),
+                (String::from("Settings"), vec![]),
+                (String::from("code.desktop"), categories(&["Development"])),
+            ]),
+            None,
+        );
+
+        // mapped categories are only included once
+        assert_eq!(
+            map.apply(
+                "firefox.desktop",
+                categories(&["Network", "WebBrowser", "GTK"])
+            ),
+            categories(&["Internet", "GTK"])
+        );
+        assert_eq!(
+            map.apply(
+                "gnome-control-center.desktop",
+                categories(&["Settings", "GNOME"])
+            ),
+            categories(&["GNOME"])
+        );
+
+        // desktop file names replace all categories
+        assert_eq!(
+            map.apply("code.desktop", categories(&["Utility", "TextEditor"])),
+            categories(&["Development"])
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use crate::config::{default_false, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label, LevelBar, Orientation};
+use serde::Deserialize;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, ChildStdout, Command};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, error};
+
+/// Capture sample rate, in Hz.
+/// This only needs to be high enough to catch peaks.
+const SAMPLE_RATE: usize = 16_000;
+
+/// Lowest level shown on the meter, in dBFS.
+const MIN_DB: f64 = -60.0;
+
+/// Sample value at or above which the input is considered clipping.
+const CLIP_THRESHOLD: f32 = 0.99;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MicMeterModule {
+    /// The name of the source to capture from.
+    /// If not set, the default source is used.
+    ///
+    /// **Default**: `null`
+    source: Option<String>,
+
+    /// The icon to show on the widget.
+    ///
+    /// **Default**: `󰍬`
+    #[serde(default = "default_icon")]
+    icon: String,
+
+    /// Whether to start metering immediately.
+    /// Otherwise, click the widget to toggle metering.
+    ///
+    /// Note that other applications may report the microphone as in use
+    /// while metering.
+    ///
+    /// **Default**: `false`
+    #[serde(default = "default_false")]
+    always_on: bool,
+
+    /// The number of milliseconds between each meter update.
+    ///
+    /// **Default**: `50`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The number of milliseconds to keep showing the clipping indicator
+    /// after the input clips.
+    ///
+    /// **Default**: `1000`
+    #[serde(default = "default_clip_hold")]
+    clip_hold: u64,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon() -> String {
+    String::from("󰍬")
+}
+
+const fn default_interval() -> u64 {
+    50
+}
+
+const fn default_clip_hold() -> u64 {
+    1000
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MeterUpdate {
+    Active(bool),
+    Level {
+        /// Peak level between `0.0` and `1.0`, on a dB scale.
+        peak: f64,
+        /// RMS level between `0.0` and `1.0`, on a dB scale.
+        rms: f64,
+        clipping: bool,
+    },
+}
+
+/// A running `parec` capture process.
+///
+/// This works with both PulseAudio and PipeWire (through `pipewire-pulse`).
+struct Capture {
+    _child: Child,
+    stdout: ChildStdout,
+}
+
+impl Capture {
+    fn start(source: Option<&str>, interval: u64) -> Result<Self> {
+        let mut command = Command::new("parec");
+        command
+            .args(["--format=float32le", "--channels=1"])
+            .arg(format!("--rate={SAMPLE_RATE}"))
+            .arg(format!("--latency-msec={interval}"))
+            .arg(concat!("--client-name=", env!("CARGO_PKG_NAME")))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        if let Some(source) = source {
+            command.arg(format!("--device={source}"));
+        }
+
+        let mut child = command.spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| color_eyre::Report::msg("Failed to open capture output"))?;
+
+        Ok(Self {
+            _child: child,
+            stdout,
+        })
+    }
+}
+
+/// Reads a full buffer of samples from the capture,
+/// or waits forever if there is no capture running.
+async fn read_samples(capture: &mut Option<Capture>, buf: &mut [u8]) -> std::io::Result<()> {
+    match capture {
+        Some(capture) => capture.stdout.read_exact(buf).await.map(|_| ()),
+        None => std::future::pending().await,
+    }
+}
+
+/// Converts a linear amplitude to a meter value between `0.0` and `1.0`.
+fn to_meter(amplitude: f32) -> f64 {
+    let db = 20.0 * f64::from(amplitude).log10();
+    ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0)
+}
+
+impl Module<Button> for MicMeterModule {
+    type SendMessage = MeterUpdate;
+    /// Toggles metering.
+    type ReceiveMessage = ();
+
+    module_impl!("mic_meter");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let module = self.clone();
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let samples = SAMPLE_RATE * module.interval as usize / 1000;
+            let mut buf = vec![0; samples.max(1) * 4];
+
+            let clip_hold = Duration::from_millis(module.clip_hold);
+            let mut clipped_at: Option<Instant> = None;
+
+            let mut active = module.always_on;
+            let mut capture = None;
+
+            loop {
+                if active && capture.is_none() {
+                    match Capture::start(module.source.as_deref(), module.interval) {
+                        Ok(new) => capture = Some(new),
+                        Err(err) => {
+                            error!("Failed to start capture: {err:?}");
+                            active = false;
+                        }
+                    }
+
+                    send_async!(tx, ModuleUpdateEvent::Update(MeterUpdate::Active(active)));
+                }
+
+                tokio::select! {
+                    Some(()) = rx.recv() => {
+                        active = !active;
+
+                        if !active {
+                            // dropping kills the process
+                            capture = None;
+                            send_async!(tx, ModuleUpdateEvent::Update(MeterUpdate::Active(false)));
+                        }
+                    }
+                    res = read_samples(&mut capture, &mut buf) => {
+                        if let Err(err) = res {
+                            debug!("Capture ended: {err:?}");
+                            capture = None;
+                            active = false;
+                            send_async!(tx, ModuleUpdateEvent::Update(MeterUpdate::Active(false)));
+                            continue;
+                        }
+
+                        let (peak, sum) = buf
+                            .chunks_exact(4)
+                            .map(|bytes| {
+                                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).abs()
+                            })
+                            .fold((0.0_f32, 0.0_f32), |(peak, sum), sample| {
+                                (peak.max(sample), sum + sample * sample)
+                            });
+
+                        let rms = (sum / samples.max(1) as f32).sqrt();
+
+                        if peak >= CLIP_THRESHOLD {
+                            clipped_at = Some(Instant::now());
+                        }
+
+                        let clipping = clipped_at.is_some_and(|time| time.elapsed() < clip_hold);
+
+                        let update = MeterUpdate::Level {
+                            peak: to_meter(peak),
+                            rms: to_meter(rms),
+                            clipping,
+                        };
+
+                        send_async!(tx, ModuleUpdateEvent::Update(update));
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+
+        let icon = Label::new(Some(&self.icon));
+        icon.add_class("icon");
+        container.add(&icon);
+
+        let meters = gtk::Box::new(Orientation::Vertical, 0);
+        meters.add_class("meter");
+
+        let rms = LevelBar::for_interval(0.0, 1.0);
+        rms.add_class("rms");
+
+        let peak = LevelBar::for_interval(0.0, 1.0);
+        peak.add_class("peak");
+
+        meters.add(&rms);
+        meters.add(&peak);
+        container.add(&meters);
+
+        button.add(&container);
+        container.show_all();
+        meters.hide();
+
+        {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, ());
+            });
+        }
+
+        {
+            let button = button.clone();
+
+            glib_recv!(context.subscribe(), update => match update {
+                MeterUpdate::Active(active) => {
+                    meters.set_visible(active);
+                    rms.set_value(0.0);
+                    peak.set_value(0.0);
+
+                    if active {
+                        button.add_class("active");
+                    } else {
+                        button.remove_class("active");
+                        button.remove_class("clipping");
+                    }
+                }
+                MeterUpdate::Level { peak: peak_level, rms: rms_level, clipping } => {
+                    rms.set_value(rms_level);
+                    peak.set_value(peak_level);
+
+                    if clipping {
+                        button.add_class("clipping");
+                    } else {
+                        button.remove_class("clipping");
+                    }
+                }
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
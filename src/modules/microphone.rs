@@ -0,0 +1,269 @@
+use crate::clients::volume::{self, Source};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, lock, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::{EventMask, ScrollDirection};
+use gtk::prelude::*;
+use gtk::Button;
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MicrophoneModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {percentage}%`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Maximum value to allow the volume to reach when scrolling.
+    ///
+    /// **Default**: `100`
+    #[serde(default = "default_max_volume")]
+    max_volume: f64,
+
+    /// The amount to change the volume by
+    /// when scrolling over the widget button.
+    /// Set to `0` to disable.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_scroll_step")]
+    scroll_step: f64,
+
+    /// Microphone state icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{icon} {percentage}%")
+}
+
+const fn default_max_volume() -> f64 {
+    100.0
+}
+
+const fn default_scroll_step() -> f64 {
+    5.0
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show when the microphone is not muted.
+    ///
+    /// **Default**: `󰍬`
+    #[serde(default = "default_icon_unmuted")]
+    unmuted: String,
+
+    /// Icon to show when the microphone is muted,
+    /// or there is no input device.
+    ///
+    /// **Default**: `󰍭`
+    #[serde(default = "default_icon_muted")]
+    muted: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            unmuted: default_icon_unmuted(),
+            muted: default_icon_muted(),
+        }
+    }
+}
+
+fn default_icon_unmuted() -> String {
+    String::from("󰍬")
+}
+
+fn default_icon_muted() -> String {
+    String::from("󰍭")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicrophoneState {
+    /// The default input device.
+    source: Option<Source>,
+    /// The names of applications currently capturing from an input device.
+    applications: Vec<String>,
+}
+
+impl MicrophoneState {
+    fn from_client(client: &volume::Client) -> Self {
+        let source = lock!(client.sources())
+            .iter()
+            .find(|source| source.active)
+            .cloned();
+
+        Self {
+            source,
+            applications: client.capturing_applications(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MicrophoneCommand {
+    ToggleMute,
+    VolumeStep(f64),
+}
+
+impl Module<Button> for MicrophoneModule {
+    type SendMessage = MicrophoneState;
+    type ReceiveMessage = MicrophoneCommand;
+
+    module_impl!("microphone");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.client::<volume::Client>();
+
+        {
+            let client = client.clone();
+            let mut rx = client.subscribe();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut last = None;
+
+                loop {
+                    // playback events do not affect the state
+                    let state = MicrophoneState::from_client(&client);
+                    if last.as_ref() != Some(&state) {
+                        last = Some(state.clone());
+                        send_async!(tx, ModuleUpdateEvent::Update(state));
+                    }
+
+                    match rx.recv().await {
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        let max_volume = self.max_volume;
+
+        spawn(async move {
+            while let Some(command) = rx.recv().await {
+                let Some(source) = MicrophoneState::from_client(&client).source else {
+                    continue;
+                };
+
+                match command {
+                    MicrophoneCommand::ToggleMute => {
+                        client.set_source_muted(&source.name, !source.muted);
+                    }
+                    MicrophoneCommand::VolumeStep(delta) => {
+                        let volume = (source.volume + delta).clamp(0.0, max_volume);
+                        client.set_source_volume(&source.name, volume);
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
+        {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, MicrophoneCommand::ToggleMute);
+            });
+        }
+
+        if self.scroll_step > 0.0 {
+            let tx = context.controller_tx.clone();
+            let step = self.scroll_step;
+
+            button.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+            button.connect_scroll_event(move |_, event| {
+                let delta = match event.direction() {
+                    ScrollDirection::Up => step,
+                    ScrollDirection::Down => -step,
+                    ScrollDirection::Smooth => {
+                        if event.scroll_deltas().unwrap_or_default().1 > 0.0 {
+                            -step
+                        } else {
+                            step
+                        }
+                    }
+                    _ => return Propagation::Proceed,
+                };
+
+                try_send!(tx, MicrophoneCommand::VolumeStep(delta));
+                Propagation::Stop
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format;
+            let icons = self.icons;
+
+            glib_recv!(context.subscribe(), state => {
+                let muted = state.source.as_ref().is_none_or(|source| source.muted);
+                let recording = !state.applications.is_empty();
+
+                let label = format
+                    .replace("{icon}", if muted { &icons.muted } else { &icons.unmuted })
+                    .replace(
+                        "{percentage}",
+                        &state.source.as_ref().map(|source| source.volume).unwrap_or_default().to_string(),
+                    )
+                    .replace(
+                        "{name}",
+                        state.source.as_ref().map_or("", |source| source.description.as_str()),
+                    )
+                    .replace("{applications}", &state.applications.len().to_string());
+
+                button.set_label(&label);
+
+                let tooltip = if recording {
+                    format!("In use by {}", state.applications.join(", "))
+                } else {
+                    String::from("Not in use")
+                };
+                button.set_tooltip_text(Some(&tooltip));
+
+                for (class, active) in [
+                    ("muted", muted),
+                    ("recording", recording),
+                    ("unavailable", state.source.is_none()),
+                ] {
+                    if active {
+                        button.add_class(class);
+                    } else {
+                        button.remove_class(class);
+                    }
+                }
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
@@ -1,23 +1,44 @@
+use std::cell::Cell;
 use std::fmt::Debug;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use color_eyre::Result;
-use glib::IsA;
+use glib::{IsA, Propagation};
 use gtk::gdk::{EventMask, Monitor};
 use gtk::prelude::*;
-use gtk::{Application, Button, EventBox, IconTheme, Orientation, Revealer, Widget};
+use gtk::{
+    Application, Button, Container, EventBox, IconTheme, Label, Orientation, PolicyType, Revealer,
+    ScrolledWindow, Widget,
+};
 use tokio::sync::{broadcast, mpsc};
-use tracing::debug;
+use tracing::{debug, error};
 
 use crate::clients::{ClientResult, ProvidesClient, ProvidesFallibleClient};
-use crate::config::{BarPosition, CommonConfig, TransitionType};
-use crate::gtk_helpers::{IronbarGtkExt, WidgetGeometry};
+use crate::config::{BarPosition, CommonConfig, DeferStart, TransitionType};
+use crate::display_mode::DisplayModeController;
+use crate::dynamic_value::dynamic_string;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt, WidgetGeometry};
+use crate::overflow;
 use crate::popup::Popup;
 use crate::{glib_recv_mpsc, send, Ironbar};
 
+#[cfg(feature = "accessibility")]
+pub mod accessibility;
+#[cfg(feature = "backup")]
+pub mod backup;
+#[cfg(feature = "bluetooth")]
+pub mod bluetooth;
+#[cfg(feature = "brightness")]
+pub mod brightness;
 #[cfg(feature = "cairo")]
 pub mod cairo;
+#[cfg(feature = "camera")]
+pub mod camera;
+#[cfg(feature = "chat")]
+pub mod chat;
+#[cfg(feature = "ci")]
+pub mod ci;
 #[cfg(feature = "clipboard")]
 pub mod clipboard;
 /// Displays the current date and time.
@@ -29,32 +50,104 @@ pub mod clipboard;
 #[cfg(feature = "clock")]
 pub mod clock;
 pub mod custom;
+#[cfg(feature = "dbus")]
+pub mod dbus;
+#[cfg(feature = "disk")]
+pub mod disk;
+#[cfg(feature = "dnd")]
+pub mod dnd;
+pub mod file;
 #[cfg(feature = "focused")]
 pub mod focused;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "graph")]
+pub mod graph;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "idle_inhibit")]
+pub mod idle_inhibit;
+#[cfg(feature = "input_method")]
+pub mod input_method;
 #[cfg(feature = "keys")]
 pub mod keys;
 pub mod label;
 #[cfg(feature = "launcher")]
 pub mod launcher;
+#[cfg(feature = "layout")]
+pub mod layout;
+#[cfg(feature = "listen")]
+pub mod listen;
+#[cfg(feature = "lock_keys")]
+pub mod lock_keys;
 #[cfg(feature = "menu")]
 pub mod menu;
+#[cfg(feature = "mic_meter")]
+pub mod mic_meter;
+#[cfg(feature = "microphone")]
+pub mod microphone;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
 #[cfg(feature = "music")]
 pub mod music;
+#[cfg(feature = "net_speed")]
+pub mod net_speed;
 #[cfg(feature = "network_manager")]
 pub mod networkmanager;
+#[cfg(feature = "night_light")]
+pub mod night_light;
 #[cfg(feature = "notifications")]
 pub mod notifications;
+#[cfg(feature = "palette")]
+pub mod palette;
+#[cfg(feature = "presentation")]
+pub mod presentation;
+#[cfg(feature = "privacy")]
+pub mod privacy;
+#[cfg(feature = "pro_audio")]
+pub mod pro_audio;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+#[cfg(feature = "public_ip")]
+pub mod public_ip;
+#[cfg(feature = "reminders")]
+pub mod reminders;
 pub mod script;
+pub mod separator;
+#[cfg(any(
+    feature = "disk",
+    feature = "menu",
+    feature = "palette",
+    feature = "ssh"
+))]
+pub mod shared;
+pub mod spacer;
+#[cfg(feature = "ssh")]
+pub mod ssh;
 #[cfg(feature = "sway")]
 pub mod sway;
 #[cfg(feature = "sys_info")]
 pub mod sysinfo;
+#[cfg(feature = "systemd")]
+pub mod systemd;
+#[cfg(feature = "temperature")]
+pub mod temperature;
+#[cfg(feature = "timer")]
+pub mod timer;
+#[cfg(feature = "timetracker")]
+pub mod timetracker;
 #[cfg(feature = "tray")]
 pub mod tray;
+#[cfg(feature = "updates")]
+pub mod updates;
 #[cfg(feature = "upower")]
 pub mod upower;
+#[cfg(feature = "vm")]
+pub mod vm;
 #[cfg(feature = "volume")]
 pub mod volume;
+#[cfg(feature = "weather")]
+pub mod weather;
 #[cfg(feature = "workspaces")]
 pub mod workspaces;
 
@@ -75,6 +168,14 @@ pub struct ModuleInfo<'a> {
     pub icon_theme: &'a IconTheme,
 }
 
+/// Immutable state shared between a module's controller and its widgets.
+///
+/// Updates are cloned for each subscriber as they are broadcast,
+/// so modules which send large lists should use a snapshot as their `SendMessage`,
+/// which only clones the pointer.
+/// A new snapshot is sent whenever the state changes.
+pub type Snapshot<T> = Arc<T>;
+
 #[derive(Debug, Clone)]
 pub enum ModuleUpdateEvent<T: Clone> {
     /// Sends an update to the module UI.
@@ -85,17 +186,29 @@ pub enum ModuleUpdateEvent<T: Clone> {
     /// Force sets the popup open.
     /// Takes the button ID.
     OpenPopup(usize),
-    #[cfg(feature = "launcher")]
+    /// Force sets the popup open,
+    /// positioning it relative to the provided geometry.
     OpenPopupAt(WidgetGeometry),
     /// Force sets the popup closed.
     ClosePopup,
 }
 
+impl<T> ModuleUpdateEvent<Snapshot<T>> {
+    /// Sends an update to the module UI,
+    /// wrapping the new state in a [`Snapshot`].
+    pub fn snapshot(state: T) -> Self {
+        Self::Update(Arc::new(state))
+    }
+}
+
 pub struct WidgetContext<TSend, TReceive>
 where
     TSend: Clone,
 {
     pub id: usize,
+    /// The configured name of the module instance,
+    /// or the module type if no name is set.
+    pub name: String,
     pub ironbar: Rc<Ironbar>,
     pub popup: Rc<Popup>,
     pub tx: mpsc::Sender<ModuleUpdateEvent<TSend>>,
@@ -136,6 +249,22 @@ where
     pub fn subscribe(&self) -> broadcast::Receiver<TSend> {
         self.update_tx.subscribe()
     }
+
+    /// Creates a second context for the same module instance,
+    /// sharing all of its channels.
+    fn duplicate(&self) -> Self {
+        Self {
+            id: self.id,
+            name: self.name.clone(),
+            ironbar: self.ironbar.clone(),
+            popup: self.popup.clone(),
+            tx: self.tx.clone(),
+            update_tx: self.update_tx.clone(),
+            controller_tx: self.controller_tx.clone(),
+            button_id: self.button_id,
+            _update_rx: self.update_tx.subscribe(),
+        }
+    }
 }
 
 pub struct ModuleParts<W: IsA<Widget>> {
@@ -239,6 +368,8 @@ pub trait Module<W>
 where
     W: IsA<Widget>,
 {
+    /// The type of update sent from the controller to the widgets.
+    /// This is cloned for each widget, so large state should be sent as a [`Snapshot`].
     type SendMessage;
     type ReceiveMessage;
 
@@ -286,9 +417,10 @@ pub trait ModuleFactory {
         info: &ModuleInfo,
     ) -> Result<()>
     where
-        TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev>,
+        TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev> + Clone + 'static,
         TWidget: IsA<Widget>,
         TSend: Debug + Clone + Send + 'static,
+        TRev: 'static,
     {
         let id = Ironbar::unique_id();
         let common = module.take_common();
@@ -300,8 +432,15 @@ pub trait ModuleFactory {
 
         let (tx, rx) = broadcast::channel(64);
 
+        let module_name = TModule::name();
+        let instance_name = common
+            .name
+            .clone()
+            .unwrap_or_else(|| module_name.to_string());
+
         let context = WidgetContext {
             id,
+            name: instance_name.clone(),
             ironbar: self.ironbar().clone(),
             popup: self.popup().clone(),
             tx: ui_tx,
@@ -311,13 +450,18 @@ pub trait ModuleFactory {
             button_id: usize::MAX, // hack :(
         };
 
-        module.spawn_controller(info, &context, controller_rx)?;
-
-        let module_name = TModule::name();
-        let instance_name = common
-            .name
-            .clone()
-            .unwrap_or_else(|| module_name.to_string());
+        let deferred = match common.defer_start {
+            Some(defer_start) => Some((
+                defer_start,
+                module.clone(),
+                context.duplicate(),
+                controller_rx,
+            )),
+            None => {
+                module.spawn_controller(info, &context, controller_rx)?;
+                None
+            }
+        };
 
         let module_parts = module.into_widget(context, info)?;
         module_parts.widget.add_class("widget");
@@ -337,6 +481,11 @@ pub trait ModuleFactory {
 
         module_parts.setup_identifiers(&common);
 
+        #[cfg(feature = "workspaces")]
+        if common.has_workspace_filter() {
+            crate::event_bus::watch_workspaces(self.ironbar());
+        }
+
         let ev_container = wrap_widget(
             &module_parts.widget,
             common,
@@ -344,6 +493,17 @@ pub trait ModuleFactory {
         );
         container.add(&ev_container);
 
+        if let Some((defer_start, module, context, controller_rx)) = deferred {
+            defer_controller::<TModule, TWidget, TSend, TRev>(
+                defer_start,
+                &ev_container,
+                module,
+                context,
+                info,
+                controller_rx,
+            );
+        }
+
         Ok(())
     }
 
@@ -403,7 +563,6 @@ impl ModuleFactory for BarModuleFactory {
                     popup.hide();
                     popup.show(id, button_id);
                 }
-                #[cfg(feature = "launcher")]
                 ModuleUpdateEvent::OpenPopupAt(geometry) if !disable_popup => {
                     debug!("Opening popup for {} [#{}]", name, id);
 
@@ -476,7 +635,6 @@ impl ModuleFactory for PopupModuleFactory {
                     popup.hide();
                     popup.show(id, button_id);
                 }
-                #[cfg(feature = "launcher")]
                 ModuleUpdateEvent::OpenPopupAt(geometry) if !disable_popup => {
                     debug!("Opening popup for {} [#{}]", name, id);
 
@@ -555,7 +713,7 @@ impl From<PopupModuleFactory> for AnyModuleFactory {
 /// The event box container is returned.
 pub fn wrap_widget<W: IsA<Widget>>(
     widget: &W,
-    common: CommonConfig,
+    mut common: CommonConfig,
     orientation: Orientation,
 ) -> EventBox {
     let transition_type = common
@@ -569,7 +727,12 @@ pub fn wrap_widget<W: IsA<Widget>>(
         .transition_duration(common.transition_duration.unwrap_or(250))
         .build();
 
-    revealer.add(widget);
+    let content = match common.secondary_label.take() {
+        Some(secondary_label) => stack_secondary_label(widget, &secondary_label).upcast(),
+        None => widget.clone().upcast(),
+    };
+
+    revealer.add(&apply_size(content, &common, orientation));
     revealer.set_reveal_child(true);
 
     let container = EventBox::new();
@@ -578,7 +741,171 @@ pub fn wrap_widget<W: IsA<Widget>>(
     container.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
     container.add(&revealer);
 
+    DisplayModeController::install(
+        &container,
+        widget,
+        common.display_mode,
+        common.display_priority,
+    );
+    overflow::set_priority(&container, common.display_priority);
+
     common.install_events(&container, &revealer);
 
     container
 }
+
+/// Starts the module's controller once the `defer_start` condition is met.
+fn defer_controller<TModule, TWidget, TSend, TRev>(
+    defer_start: DeferStart,
+    container: &EventBox,
+    module: TModule,
+    context: WidgetContext<TSend, TRev>,
+    info: &ModuleInfo,
+    rx: mpsc::Receiver<TRev>,
+) where
+    TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev> + 'static,
+    TWidget: IsA<Widget>,
+    TSend: Clone + 'static,
+    TRev: 'static,
+{
+    let app = info.app.clone();
+    let location = info.location.clone();
+    let bar_position = info.bar_position;
+    let monitor = info.monitor.clone();
+    let output_name = info.output_name.to_string();
+    let icon_theme = info.icon_theme.clone();
+
+    let start = move || {
+        debug!("starting deferred controller for {}", TModule::name());
+
+        let info = ModuleInfo {
+            app: &app,
+            location,
+            bar_position,
+            monitor: &monitor,
+            output_name: &output_name,
+            icon_theme: &icon_theme,
+        };
+
+        if let Err(err) = module.spawn_controller(&info, &context, rx) {
+            error!("Failed to start {} controller: {err:?}", TModule::name());
+        }
+    };
+
+    // signal handlers can run more than once, so only the first call starts it
+    let start = Rc::new(Cell::new(Some(start)));
+    let start_once = move || {
+        if let Some(start) = start.take() {
+            start();
+        }
+    };
+
+    match defer_start {
+        DeferStart::Delay(delay) => {
+            glib::timeout_add_local_once(delay, start_once);
+        }
+        DeferStart::OnFirstView => {
+            container.connect_map(move |_| start_once());
+        }
+        DeferStart::OnDemand => {
+            container.add_events(EventMask::ENTER_NOTIFY_MASK);
+            container.connect_enter_notify_event(move |_, _| {
+                start_once();
+                Propagation::Proceed
+            });
+        }
+    }
+}
+
+/// Applies the `width`, `min_width` and `justify` options to a module's content.
+/// The widget to place inside the module container is returned.
+fn apply_size(content: Widget, common: &CommonConfig, orientation: Orientation) -> Widget {
+    if common.width.is_none() && common.min_width.is_none() {
+        return content;
+    }
+
+    let horizontal = orientation == Orientation::Horizontal;
+
+    if horizontal {
+        content.set_halign(common.justify.into());
+    } else {
+        content.set_valign(common.justify.into());
+    }
+
+    let wrapper: Container = if let Some(width) = common.width {
+        let (hscrollbar_policy, vscrollbar_policy) = if horizontal {
+            (PolicyType::External, PolicyType::Never)
+        } else {
+            (PolicyType::Never, PolicyType::External)
+        };
+
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(hscrollbar_policy)
+            .vscrollbar_policy(vscrollbar_policy)
+            .build();
+
+        if horizontal {
+            scrolled.set_min_content_width(width);
+            scrolled.set_max_content_width(width);
+        } else {
+            scrolled.set_min_content_height(width);
+            scrolled.set_max_content_height(width);
+        }
+
+        // scrolled windows consume scroll events even with scrolling disabled,
+        // so pass them on to the module container instead.
+        scrolled.connect_scroll_event(|scrolled, event| {
+            let mut parent = scrolled.parent();
+            while let Some(widget) = parent {
+                if widget.event(event) {
+                    break;
+                }
+
+                parent = widget.parent();
+            }
+
+            Propagation::Stop
+        });
+
+        scrolled.upcast()
+    } else {
+        gtk::Box::new(orientation, 0).upcast()
+    };
+
+    if let Some(min_width) = common.min_width {
+        if horizontal {
+            wrapper.set_width_request(min_width);
+        } else {
+            wrapper.set_height_request(min_width);
+        }
+    }
+
+    wrapper.add_class("sized");
+    wrapper.add(&content);
+
+    wrapper.upcast()
+}
+
+/// Stacks `widget` above a new label showing the dynamic string `secondary_label`.
+/// The containing box is returned.
+fn stack_secondary_label<W: IsA<Widget>>(widget: &W, secondary_label: &str) -> gtk::Box {
+    let container = gtk::Box::new(Orientation::Vertical, 0);
+    container.add_class("rows");
+    container.set_valign(gtk::Align::Center);
+
+    widget.add_class("primary");
+    container.add(widget);
+
+    let label = Label::builder().use_markup(true).build();
+    label.add_class("secondary");
+    container.add(&label);
+
+    {
+        let label = label.clone();
+        dynamic_string(secondary_label, move |string| {
+            label.set_label_escaped(&string);
+        });
+    }
+
+    container
+}
@@ -0,0 +1,276 @@
+use crate::clients::mqtt::{topic_matches, Broker};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MqttModule {
+    /// The hostname or IP address of the MQTT broker.
+    ///
+    /// **Default**: `localhost`
+    #[serde(default = "default_host")]
+    host: String,
+
+    /// The port of the MQTT broker.
+    ///
+    /// **Default**: `1883`
+    #[serde(default = "default_port")]
+    port: u16,
+
+    /// The username to authenticate with.
+    /// Requires `password` to be set.
+    ///
+    /// **Default**: `null`
+    username: Option<String>,
+
+    /// The password to authenticate with.
+    ///
+    /// **Default**: `null`
+    password: Option<String>,
+
+    /// Values to subscribe to, keyed by the token name
+    /// used to insert them into `format`.
+    ///
+    /// See [values](#values).
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    values: HashMap<String, MqttValue>,
+
+    /// The format string to use for the widget label.
+    /// Use `{name}` to insert the latest value of `name`.
+    /// Pango markup is supported.
+    ///
+    /// **Required**
+    format: String,
+
+    /// A message to publish when the widget is clicked.
+    ///
+    /// **Default**: `null`
+    publish_on_click: Option<Publish>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_host() -> String {
+    String::from("localhost")
+}
+
+const fn default_port() -> u16 {
+    1883
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MqttValue {
+    /// The topic to subscribe to.
+    /// The `+` and `#` wildcards are supported.
+    ///
+    /// **Required**
+    topic: String,
+
+    /// A path to extract from JSON payloads,
+    /// with keys and array indexes separated by a `.`.
+    /// For example `attributes.temperature` or `$.sensors.0.value`.
+    ///
+    /// If not set, the raw payload is used.
+    ///
+    /// **Default**: `null`
+    path: Option<String>,
+
+    /// The value to show before a message is received.
+    ///
+    /// **Default**: `''`
+    #[serde(default)]
+    default: String,
+}
+
+impl MqttValue {
+    /// Gets the value to display for `payload`.
+    fn extract(&self, payload: &str) -> Option<String> {
+        let Some(path) = &self.path else {
+            return Some(payload.trim().to_string());
+        };
+
+        let json = serde_json::from_str::<serde_json::Value>(payload).ok()?;
+
+        let value = path
+            .trim_start_matches('$')
+            .split('.')
+            .filter(|key| !key.is_empty())
+            .try_fold(&json, |value, key| match value {
+                serde_json::Value::Array(array) => array.get(key.parse::<usize>().ok()?),
+                _ => value.get(key),
+            })?;
+
+        Some(match value {
+            serde_json::Value::String(string) => string.clone(),
+            value => value.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Publish {
+    /// The topic to publish to.
+    ///
+    /// **Required**
+    topic: String,
+
+    /// The message payload.
+    ///
+    /// **Default**: `''`
+    #[serde(default)]
+    payload: String,
+
+    /// Whether the broker should retain the message.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    retain: bool,
+}
+
+impl MqttModule {
+    fn broker(&self) -> Broker {
+        Broker {
+            host: self.host.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }
+    }
+
+    fn render(&self, values: &HashMap<String, String>) -> String {
+        values
+            .iter()
+            .fold(self.format.clone(), |format, (name, value)| {
+                format.replace(
+                    &format!("{{{name}}}"),
+                    glib::markup_escape_text(value).as_str(),
+                )
+            })
+    }
+}
+
+impl Module<Button> for MqttModule {
+    type SendMessage = String;
+    type ReceiveMessage = ();
+
+    module_impl!("mqtt");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.ironbar.clients.borrow_mut().mqtt(&self.broker());
+
+        {
+            let module = self.clone();
+            let client = client.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut messages = client.subscribe_messages();
+
+                for value in module.values.values() {
+                    if let Err(err) = client.subscribe(&value.topic).await {
+                        error!("Failed to subscribe to '{}': {err:?}", value.topic);
+                    }
+                }
+
+                let mut values = module
+                    .values
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.default.clone()))
+                    .collect::<HashMap<_, _>>();
+
+                send_async!(tx, ModuleUpdateEvent::Update(module.render(&values)));
+
+                loop {
+                    let message = match messages.recv().await {
+                        Ok(message) => message,
+                        // busy brokers can send more than the channel holds
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+
+                    let mut changed = false;
+
+                    for (name, value) in &module.values {
+                        if !topic_matches(&value.topic, &message.topic) {
+                            continue;
+                        }
+
+                        match value.extract(&message.payload) {
+                            Some(new) => {
+                                if values.get(name) != Some(&new) {
+                                    values.insert(name.clone(), new);
+                                    changed = true;
+                                }
+                            }
+                            None => debug!(
+                                "Failed to extract '{name}' from payload on '{}'",
+                                message.topic
+                            ),
+                        }
+                    }
+
+                    if changed {
+                        send_async!(tx, ModuleUpdateEvent::Update(module.render(&values)));
+                    }
+                }
+            });
+        }
+
+        if let Some(publish) = self.publish_on_click.clone() {
+            spawn(async move {
+                while rx.recv().await.is_some() {
+                    if let Err(err) = client
+                        .publish(&publish.topic, &publish.payload, publish.retain)
+                        .await
+                    {
+                        error!("Failed to publish to '{}': {err:?}", publish.topic);
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        if self.publish_on_click.is_some() {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, ());
+            });
+        }
+
+        glib_recv!(context.subscribe(), text => label.set_label(&text));
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
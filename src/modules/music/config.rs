@@ -53,6 +53,12 @@ pub struct Icons {
     /// **Default**: `󰠃`
     #[serde(default = "default_icon_artist")]
     pub(crate) artist: String,
+
+    /// Icon to display on the button to remove a track from the queue.
+    ///
+    /// **Default**: `󰅖`
+    #[serde(default = "default_icon_remove")]
+    pub(crate) remove: String,
 }
 
 impl Default for Icons {
@@ -66,24 +72,20 @@ impl Default for Icons {
             track: default_icon_track(),
             album: default_icon_album(),
             artist: default_icon_artist(),
+            remove: default_icon_remove(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum PlayerType {
     Mpd,
+    #[default]
     Mpris,
 }
 
-impl Default for PlayerType {
-    fn default() -> Self {
-        Self::Mpris
-    }
-}
-
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MusicModule {
@@ -124,6 +126,13 @@ pub struct MusicModule {
     #[serde(default = "default_cover_image_size")]
     pub(crate) cover_image_size: i32,
 
+    /// The number of seconds to seek forwards or backwards by
+    /// when scrolling over the widget on the bar.
+    /// Leave unset to disable seeking on scroll.
+    ///
+    /// **Default**: `null`
+    pub(crate) seek_step: Option<u32>,
+
     // -- MPD --
     /// *[MPD Only]*
     /// TCP or Unix socket address of the MPD server.
@@ -141,6 +150,14 @@ pub struct MusicModule {
     #[serde(default = "default_music_dir")]
     pub(crate) music_dir: PathBuf,
 
+    /// *[MPD Only]*
+    /// Whether to show the play queue inside the popup.
+    /// Clicking a track plays it.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    pub(crate) show_queue: bool,
+
     // -- Common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -192,6 +209,10 @@ fn default_icon_artist() -> String {
     String::from("󰠃")
 }
 
+fn default_icon_remove() -> String {
+    String::from("󰅖")
+}
+
 fn default_music_dir() -> PathBuf {
     audio_dir().unwrap_or_else(|| home_dir().map(|dir| dir.join("Music")).unwrap_or_default())
 }
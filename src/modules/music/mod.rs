@@ -6,14 +6,16 @@ use std::time::Duration;
 
 use color_eyre::Result;
 use glib::{Propagation, PropertySet};
+use gtk::gdk::{EventMask, ScrollDirection};
+use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
-use gtk::{Button, IconTheme, Label, Orientation, Scale};
+use gtk::{Button, IconTheme, Label, Orientation, PolicyType, Scale, ScrolledWindow};
 use regex::Regex;
 use tokio::sync::{broadcast, mpsc};
 use tracing::error;
 
 use crate::clients::music::{
-    self, MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, Track,
+    self, MusicClient, PlayerState, PlayerUpdate, ProgressTick, QueueItem, Status, Track,
 };
 use crate::clients::Clients;
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
@@ -22,7 +24,7 @@ use crate::modules::PopupButton;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
 };
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, module_impl, send_async, spawn, text, try_send};
 
 pub use self::config::MusicModule;
 use self::config::PlayerType;
@@ -37,6 +39,10 @@ pub enum PlayerCommand {
     Next,
     Volume(u8),
     Seek(Duration),
+    /// Seeks by the number of seconds from the current position.
+    SeekRelative(i64),
+    PlayQueueItem(u64),
+    RemoveQueueItem(u64),
 }
 
 /// Formats a duration given in seconds
@@ -60,6 +66,7 @@ fn get_tokens(re: &Regex, format_string: &str) -> Vec<String> {
 pub enum ControllerEvent {
     Update(Option<SongUpdate>),
     UpdateProgress(ProgressTick),
+    Queue(Vec<QueueItem>),
 }
 
 #[derive(Clone, Debug)]
@@ -147,6 +154,10 @@ impl Module<Button> for MusicModule {
                                     progress_tick
                                 ))
                             ),
+                            PlayerUpdate::Queue(queue) => send_async!(
+                                tx,
+                                ModuleUpdateEvent::Update(ControllerEvent::Queue(queue))
+                            ),
                         }
                     }
                 }
@@ -164,6 +175,9 @@ impl Module<Button> for MusicModule {
                         PlayerCommand::Next => client.next(),
                         PlayerCommand::Volume(vol) => client.set_volume_percent(vol),
                         PlayerCommand::Seek(duration) => client.seek(duration),
+                        PlayerCommand::SeekRelative(offset) => client.seek_relative(offset),
+                        PlayerCommand::PlayQueueItem(id) => client.play_queue_item(id),
+                        PlayerCommand::RemoveQueueItem(id) => client.remove_queue_item(id),
                     };
 
                     if let Err(err) = res {
@@ -211,6 +225,31 @@ impl Module<Button> for MusicModule {
             });
         }
 
+        if let Some(step) = self.seek_step {
+            let tx = context.controller_tx.clone();
+            let step = i64::from(step);
+
+            button.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+            button.connect_scroll_event(move |_, event| {
+                let offset = match event.direction() {
+                    ScrollDirection::Up | ScrollDirection::Right => step,
+                    ScrollDirection::Down | ScrollDirection::Left => -step,
+                    ScrollDirection::Smooth => {
+                        let (_, dy) = event.delta();
+                        match dy {
+                            dy if dy < 0.0 => step,
+                            dy if dy > 0.0 => -step,
+                            _ => return Propagation::Stop,
+                        }
+                    }
+                    _ => return Propagation::Proceed,
+                };
+
+                try_send!(tx, PlayerCommand::SeekRelative(offset));
+                Propagation::Stop
+            });
+        }
+
         {
             let button = button.clone();
 
@@ -377,6 +416,17 @@ impl Module<Button> for MusicModule {
         progress_box.add(&progress_label);
         container.add(&progress_box);
 
+        let queue = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .max_content_height(200)
+            .propagate_natural_height(true)
+            .build();
+        queue.add_class("queue");
+
+        let queue_list = gtk::Box::new(Orientation::Vertical, 0);
+        queue.add(&queue_list);
+        container.add(&queue);
+
         let drag_lock = Arc::new(AtomicBool::new(false));
         {
             let drag_lock = drag_lock.clone();
@@ -388,6 +438,8 @@ impl Module<Button> for MusicModule {
 
         {
             let drag_lock = drag_lock.clone();
+            let tx = tx.clone();
+
             progress.connect_button_release_event(move |scale, _| {
                 let value = scale.value();
                 try_send!(tx, PlayerCommand::Seek(Duration::from_secs_f64(value)));
@@ -398,12 +450,18 @@ impl Module<Button> for MusicModule {
         }
 
         container.show_all();
+        queue.hide();
 
         {
             let icon_theme = icon_theme.clone();
             let image_size = self.cover_image_size;
 
+            let show_queue = self.show_queue;
+            let icon_remove = icons.remove;
+
             let mut prev_cover = None;
+            let mut playlist_position = 0;
+
             glib_recv!(rx, event =>  {
                 match event {
                     ControllerEvent::Update(Some(update)) => {
@@ -453,6 +511,9 @@ impl Module<Button> for MusicModule {
                             }
                         }
 
+                        playlist_position = update.status.playlist_position;
+                        set_current_queue_item(&queue_list, playlist_position);
+
                         let enable_prev = update.status.playlist_position > 0;
 
                         let enable_next =
@@ -487,6 +548,21 @@ impl Module<Button> for MusicModule {
                             progress_box.hide();
                         }
                     }
+                    ControllerEvent::Queue(items) => {
+                        for child in queue_list.children() {
+                            queue_list.remove(&child);
+                        }
+
+                        for item in &items {
+                            let row = create_queue_row(item, &icon_remove, &tx);
+                            queue_list.add(&row);
+                        }
+
+                        set_current_queue_item(&queue_list, playlist_position);
+
+                        queue_list.show_all();
+                        queue.set_visible(show_queue && !items.is_empty());
+                    }
                     _ => {}
                 };
             });
@@ -496,6 +572,66 @@ impl Module<Button> for MusicModule {
     }
 }
 
+/// Creates a row for a track in the popup queue list.
+/// Clicking the track plays it.
+fn create_queue_row(
+    item: &QueueItem,
+    icon_remove: &str,
+    tx: &mpsc::Sender<PlayerCommand>,
+) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 5);
+    row.add_class("queue-item");
+
+    let text = match (&item.artist, &item.title) {
+        (Some(artist), Some(title)) => format!("{artist} - {title}"),
+        (None, Some(title)) => title.clone(),
+        (_, None) => format!("Track {}", item.position + 1),
+    };
+
+    let label = Label::new(Some(&text));
+    label.set_ellipsize(EllipsizeMode::End);
+    label.set_halign(gtk::Align::Start);
+
+    let btn_play = Button::new();
+    btn_play.add(&label);
+    btn_play.add_class("btn-play-item");
+
+    let btn_remove = Button::with_label(icon_remove);
+    btn_remove.add_class("btn-remove");
+
+    {
+        let tx = tx.clone();
+        let id = item.id;
+        btn_play.connect_clicked(move |_| {
+            try_send!(tx, PlayerCommand::PlayQueueItem(id));
+        });
+    }
+
+    {
+        let tx = tx.clone();
+        let id = item.id;
+        btn_remove.connect_clicked(move |_| {
+            try_send!(tx, PlayerCommand::RemoveQueueItem(id));
+        });
+    }
+
+    row.pack_start(&btn_play, true, true, 0);
+    row.pack_end(&btn_remove, false, false, 0);
+
+    row
+}
+
+/// Marks the queue row at the playlist position as the current track.
+fn set_current_queue_item(queue_list: &gtk::Box, position: u32) {
+    for (i, row) in queue_list.children().iter().enumerate() {
+        if i == position as usize {
+            row.add_class("current");
+        } else {
+            row.remove_class("current");
+        }
+    }
+}
+
 fn update_popup_metadata_label(text: Option<String>, label: &IconPrefixedLabel) {
     match text {
         Some(value) => {
@@ -514,7 +650,8 @@ fn replace_tokens(format_string: &str, tokens: &Vec<String>, song: &Track) -> St
     let mut compiled_string = format_string.to_string();
     for token in tokens {
         let value = get_token_value(song, token);
-        compiled_string = compiled_string.replace(format!("{{{token}}}").as_str(), value.as_str());
+        compiled_string =
+            compiled_string.replace(format!("{{{token}}}").as_str(), &text::isolate(&value));
     }
     compiled_string
 }
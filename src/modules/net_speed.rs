@@ -0,0 +1,523 @@
+use crate::config::{Alert, Alerts, CommonConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::{glib_recv, module_impl, polling, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+
+const NET_DEV_PATH: &str = "/proc/net/dev";
+const ROUTE_PATH: &str = "/proc/net/route";
+
+const BYTE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+const BIT_UNITS: [&str; 5] = ["b", "Kb", "Mb", "Gb", "Tb"];
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NetSpeedModule {
+    /// The network interface to show on the bar, such as `wlan0`.
+    /// If not set, the interface of the default route is used,
+    /// which follows changes such as switching from Wi-Fi to ethernet.
+    ///
+    /// **Default**: `null`
+    interface: Option<String>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰁅 {down} 󰁝 {up}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Whether to list the loopback interface in the popup.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    show_loopback: bool,
+
+    /// Classes to add and scripts to run when the download or upload rate
+    /// of the interface on the bar crosses a threshold.
+    /// See [alerts](#alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<NetSpeedAlert>,
+
+    /// Number of seconds between refresh.
+    ///
+    /// **Default**: `2`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Rate {
+    Down,
+    Up,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NetSpeedAlert {
+    /// The rate to check.
+    /// Thresholds are in megabits per second.
+    ///
+    /// **Valid options**: `down`, `up`
+    /// <br>
+    /// **Required**
+    rate: Rate,
+
+    #[serde(flatten)]
+    alert: Alert,
+}
+
+fn default_format() -> String {
+    String::from("󰁅 {down} 󰁝 {up}")
+}
+
+const fn default_interval() -> u64 {
+    2
+}
+
+/// A snapshot of a single network interface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    name: String,
+    /// Total bytes received since boot.
+    received: u64,
+    /// Total bytes transmitted since boot.
+    transmitted: u64,
+    /// Download rate, in bytes per second.
+    down: f64,
+    /// Upload rate, in bytes per second.
+    up: f64,
+}
+
+impl Interface {
+    /// Gets a rate in megabits per second, to match the sysinfo module.
+    fn megabits(&self, rate: Rate) -> f64 {
+        let bytes = match rate {
+            Rate::Down => self.down,
+            Rate::Up => self.up,
+        };
+
+        bytes * 8.0 / 1_000_000.0
+    }
+
+    /// Replaces the formatting tokens in `format`.
+    fn format(&self, format: &str) -> String {
+        format
+            .replace("{interface}", &self.name)
+            .replace("{down_bits}", &format_rate(self.down * 8.0, &BIT_UNITS))
+            .replace("{up_bits}", &format_rate(self.up * 8.0, &BIT_UNITS))
+            .replace("{down}", &format_rate(self.down, &BYTE_UNITS))
+            .replace("{up}", &format_rate(self.up, &BYTE_UNITS))
+            .replace("{total_down}", &format_size(self.received as f64))
+            .replace("{total_up}", &format_size(self.transmitted as f64))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetSpeed {
+    /// All interfaces, sorted by name.
+    interfaces: Vec<Interface>,
+    /// The name of the interface shown on the bar,
+    /// or `None` if there is no default route.
+    active: Option<String>,
+}
+
+impl NetSpeed {
+    fn active(&self) -> Option<&Interface> {
+        let active = self.active.as_ref()?;
+        self.interfaces
+            .iter()
+            .find(|interface| &interface.name == active)
+    }
+}
+
+/// Total bytes received and transmitted by an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Traffic {
+    received: u64,
+    transmitted: u64,
+}
+
+/// Parses `/proc/net/dev` into the traffic totals of each interface, by name.
+fn parse_net_dev(contents: &str) -> HashMap<String, Traffic> {
+    contents
+        .lines()
+        // the first two lines are headers
+        .skip(2)
+        .filter_map(|line| {
+            let (name, stats) = line.split_once(':')?;
+            let fields = stats.split_whitespace().collect::<Vec<_>>();
+
+            let received = fields.first()?.parse().ok()?;
+            let transmitted = fields.get(8)?.parse().ok()?;
+
+            Some((
+                name.trim().to_string(),
+                Traffic {
+                    received,
+                    transmitted,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Parses `/proc/net/route` to find the interface of the default route.
+/// If there are several, the one with the lowest metric is used.
+fn parse_default_route(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+
+            let name = *fields.first()?;
+            let destination = *fields.get(1)?;
+            let metric = fields.get(6)?.parse::<u32>().ok()?;
+            let mask = *fields.get(7)?;
+
+            (destination == "00000000" && mask == "00000000").then_some((name, metric))
+        })
+        .min_by_key(|(_, metric)| *metric)
+        .map(|(name, _)| name.to_string())
+}
+
+/// Tracks traffic totals between refreshes to calculate rates.
+#[derive(Debug, Default)]
+struct Rates {
+    previous: HashMap<String, Traffic>,
+    last_refresh: Option<Instant>,
+}
+
+impl Rates {
+    /// Reads the current totals and calculates the rates
+    /// of each interface since the last refresh.
+    fn refresh(&mut self, show_loopback: bool) -> Vec<Interface> {
+        let current = fs::read_to_string(NET_DEV_PATH)
+            .map(|contents| parse_net_dev(&contents))
+            .unwrap_or_default();
+
+        let now = Instant::now();
+        let elapsed = self
+            .last_refresh
+            .map(|last| now.duration_since(last).as_secs_f64())
+            .filter(|elapsed| *elapsed > 0.0);
+
+        let rate = |current: u64, previous: u64| {
+            elapsed.map_or(0.0, |elapsed| {
+                current.saturating_sub(previous) as f64 / elapsed
+            })
+        };
+
+        let mut interfaces = current
+            .iter()
+            .filter(|(name, _)| show_loopback || name.as_str() != "lo")
+            .map(|(name, traffic)| {
+                let previous = self.previous.get(name).copied().unwrap_or(*traffic);
+
+                Interface {
+                    name: name.clone(),
+                    received: traffic.received,
+                    transmitted: traffic.transmitted,
+                    down: rate(traffic.received, previous.received),
+                    up: rate(traffic.transmitted, previous.transmitted),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.previous = current;
+        self.last_refresh = Some(now);
+
+        interfaces
+    }
+}
+
+/// Scales `value` to the largest unit it exceeds,
+/// where each unit is 1000 times the last.
+fn scale(value: f64, units: &[&'static str]) -> (f64, &'static str) {
+    let mut value = value;
+    let mut unit = 0;
+
+    while value >= 1000.0 && unit < units.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    (value, units[unit])
+}
+
+/// Formats a rate per second, such as `1.5 MB/s`.
+fn format_rate(value: f64, units: &[&'static str]) -> String {
+    let (value, unit) = scale(value, units);
+
+    if unit == units[0] {
+        format!("{value:.0} {unit}/s")
+    } else {
+        format!("{value:.1} {unit}/s")
+    }
+}
+
+/// Formats a number of bytes, such as `2.3 GB`.
+fn format_size(bytes: f64) -> String {
+    let (value, unit) = scale(bytes, &BYTE_UNITS);
+
+    if unit == BYTE_UNITS[0] {
+        format!("{value:.0} {unit}")
+    } else {
+        format!("{value:.1} {unit}")
+    }
+}
+
+impl Module<Button> for NetSpeedModule {
+    type SendMessage = Snapshot<NetSpeed>;
+    type ReceiveMessage = ();
+
+    module_impl!("net_speed");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let interval = Duration::from_secs(self.interval);
+        let interface = self.interface.clone();
+        let show_loopback = self.show_loopback;
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut rates = Rates::default();
+
+            loop {
+                let interfaces = rates.refresh(show_loopback);
+
+                // the default route changes when switching networks,
+                // so is looked up every time.
+                let active = interface.clone().or_else(|| {
+                    fs::read_to_string(ROUTE_PATH)
+                        .ok()
+                        .and_then(|contents| parse_default_route(&contents))
+                });
+
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::snapshot(NetSpeed { interfaces, active })
+                );
+
+                polling::sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let mut alerts = self
+                .alerts
+                .iter()
+                .map(|alert| (alert.rate, Alerts::new(vec![alert.alert.clone()])))
+                .collect::<Vec<_>>();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, state => {
+                if let Some(interface) = state.active() {
+                    label.set_label_escaped(&interface.format(&format));
+                    button.remove_class("disconnected");
+
+                    for (rate, alerts) in &mut alerts {
+                        alerts.update(&button, interface.megabits(*rate));
+                    }
+                } else {
+                    for (_, alerts) in &mut alerts {
+                        alerts.clear(&button);
+                    }
+
+                    let interface = Interface {
+                        name: String::new(),
+                        received: 0,
+                        transmitted: 0,
+                        down: 0.0,
+                        up: 0.0,
+                    };
+
+                    label.set_label_escaped(&interface.format(&format));
+                    button.add_class("disconnected");
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, state => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                if state.interfaces.is_empty() {
+                    let label = Label::new(Some("No network interfaces"));
+                    label.add_class("empty");
+                    container.add(&label);
+                }
+
+                for interface in &state.interfaces {
+                    let row = interface_row(interface);
+
+                    if state.active.as_ref() == Some(&interface.name) {
+                        row.add_class("active");
+                    }
+
+                    container.add(&row);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for an interface.
+fn interface_row(interface: &Interface) -> gtk::Box {
+    let container = gtk::Box::new(Orientation::Vertical, 2);
+    container.add_class("interface");
+
+    let header = gtk::Box::new(Orientation::Horizontal, 10);
+
+    let name = Label::builder()
+        .label(&interface.name)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    name.add_class("name");
+    header.add(&name);
+
+    let rate = Label::new(Some(&interface.format("󰁅 {down} 󰁝 {up}")));
+    rate.add_class("rate");
+    header.add(&rate);
+
+    container.add(&header);
+
+    let totals = Label::builder()
+        .label(interface.format("Total: 󰁅 {total_down} 󰁝 {total_up}"))
+        .halign(Align::Start)
+        .build();
+    totals.add_class("totals");
+    container.add(&totals);
+
+    container
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_net_dev() {
+        let contents = "\
+Inter-|   Receive                                  |  Transmit
+ face |bytes packets errs drop fifo frame compressed multicast|bytes packets errs drop ...
+    lo: 184370 1920 0 0 0 0 0 0 184370 1920 0 0 0 0 0 0
+ wlan0: 912834712 712345 0 0 0 0 0 1234 48213984 301233 0 0 0 0 0 0";
+
+        let traffic = parse_net_dev(contents);
+
+        assert_eq!(traffic.len(), 2);
+        assert_eq!(
+            traffic["wlan0"],
+            Traffic {
+                received: 912_834_712,
+                transmitted: 48_213_984
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_default_route() {
+        let contents = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+enp3s0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0";
+
+        assert_eq!(parse_default_route(contents).as_deref(), Some("enp3s0"));
+        assert_eq!(parse_default_route(""), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let interface = Interface {
+            name: String::from("wlan0"),
+            received: 2_345_678_901,
+            transmitted: 512,
+            down: 1_520_000.0,
+            up: 820.0,
+        };
+
+        assert_eq!(
+            interface.format("{interface} {down} {up_bits} {total_down} {total_up}"),
+            "wlan0 1.5 MB/s 6.6 Kb/s 2.3 GB 512 B"
+        );
+    }
+}
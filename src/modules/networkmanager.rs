@@ -1,17 +1,26 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
 use color_eyre::Result;
 use futures_lite::StreamExt;
 use futures_signals::signal::SignalExt;
-use gtk::prelude::ContainerExt;
-use gtk::{Box as GtkBox, Image};
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Button, Entry, Image, Label, Orientation, Switch};
 use serde::Deserialize;
-use tokio::sync::mpsc::Receiver;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
 
-use crate::clients::networkmanager::{Client, ClientState};
+use crate::clients::networkmanager::{AccessPoint, Client, ClientState, NetworkState};
 use crate::config::CommonConfig;
 use crate::gtk_helpers::IronbarGtkExt;
 use crate::image::ImageProvider;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -19,6 +28,13 @@ pub struct NetworkManagerModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// The number of milliseconds between each refresh
+    /// of the signal strength, Wi-Fi networks and VPNs.
+    ///
+    /// **Default**: `5000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
 }
@@ -27,25 +43,90 @@ const fn default_icon_size() -> i32 {
     24
 }
 
-impl Module<GtkBox> for NetworkManagerModule {
-    type SendMessage = ClientState;
-    type ReceiveMessage = ();
+const fn default_interval() -> u64 {
+    5000
+}
+
+#[derive(Debug, Clone)]
+pub enum NetworkManagerUpdate {
+    State(ClientState),
+    Networks(NetworkState),
+}
+
+#[derive(Debug, Clone)]
+pub enum NetworkManagerCommand {
+    Connect {
+        ssid: String,
+        password: Option<String>,
+    },
+    SetAirplaneMode(bool),
+}
+
+impl Module<Button> for NetworkManagerModule {
+    type SendMessage = NetworkManagerUpdate;
+    type ReceiveMessage = NetworkManagerCommand;
 
     module_impl!("network_manager");
 
     fn spawn_controller(
         &self,
         _: &ModuleInfo,
-        context: &WidgetContext<ClientState, ()>,
-        _: Receiver<()>,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let client = context.try_client::<Client>()?;
-        let mut client_signal = client.subscribe().to_stream();
-        let widget_transmitter = context.tx.clone();
+
+        {
+            let mut client_signal = client.subscribe().to_stream();
+            let widget_transmitter = context.tx.clone();
+
+            spawn(async move {
+                while let Some(state) = client_signal.next().await {
+                    send_async!(
+                        widget_transmitter,
+                        ModuleUpdateEvent::Update(NetworkManagerUpdate::State(state))
+                    );
+                }
+            });
+        }
+
+        let tx = context.tx.clone();
+        let interval = Duration::from_millis(self.interval);
 
         spawn(async move {
-            while let Some(state) = client_signal.next().await {
-                send_async!(widget_transmitter, ModuleUpdateEvent::Update(state));
+            let mut last = None;
+
+            loop {
+                match client.network_state().await {
+                    Ok(state) => {
+                        if last.as_ref() != Some(&state) {
+                            last = Some(state.clone());
+                            send_async!(
+                                tx,
+                                ModuleUpdateEvent::Update(NetworkManagerUpdate::Networks(state))
+                            );
+                        }
+                    }
+                    Err(err) => debug!("Failed to get network state: {err:?}"),
+                }
+
+                tokio::select! {
+                    () = polling::sleep(interval) => {}
+                    Some(command) = rx.recv() => {
+                        let res = match &command {
+                            NetworkManagerCommand::Connect { ssid, password } => {
+                                client.connect_wifi(ssid, password.as_deref()).await
+                            }
+                            NetworkManagerCommand::SetAirplaneMode(enabled) => {
+                                client.set_airplane_mode(*enabled).await
+                            }
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to run {command:?}: {err:?}");
+                        }
+                    }
+                }
             }
         });
 
@@ -54,13 +135,22 @@ impl Module<GtkBox> for NetworkManagerModule {
 
     fn into_widget(
         self,
-        context: WidgetContext<ClientState, ()>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         info: &ModuleInfo,
-    ) -> Result<ModuleParts<GtkBox>> {
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
         let container = GtkBox::new(info.bar_position.orientation(), 0);
         let icon = Image::new();
         icon.add_class("icon");
         container.add(&icon);
+        button.add(&container);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
 
         let icon_theme = info.icon_theme.clone();
 
@@ -68,21 +158,259 @@ impl Module<GtkBox> for NetworkManagerModule {
         ImageProvider::parse(initial_icon_name, &icon_theme, false, self.icon_size)
             .map(|provider| provider.load_into_image(&icon));
 
-        let widget_receiver = context.subscribe();
-        glib_recv!(widget_receiver, state => {
-            let icon_name = match state {
-                ClientState::WiredConnected => "network-wired-symbolic",
-                ClientState::WifiConnected => "network-wireless-symbolic",
-                ClientState::CellularConnected => "network-cellular-symbolic",
-                ClientState::VpnConnected => "network-vpn-symbolic",
-                ClientState::WifiDisconnected => "network-wireless-acquiring-symbolic",
-                ClientState::Offline => "network-wireless-disabled-symbolic",
-                ClientState::Unknown => "dialog-question-symbolic",
+        {
+            let button = button.clone();
+            let icon_size = self.icon_size;
+
+            let mut state = ClientState::Unknown;
+            let mut strength = None;
+
+            let widget_receiver = context.subscribe();
+            glib_recv!(widget_receiver, update => {
+                match update {
+                    NetworkManagerUpdate::State(new_state) => state = new_state,
+                    NetworkManagerUpdate::Networks(networks) => {
+                        strength = networks.strength;
+
+                        if networks.vpns.is_empty() {
+                            button.remove_class("vpn");
+                            button.set_tooltip_text(None);
+                        } else {
+                            button.add_class("vpn");
+                            button.set_tooltip_text(Some(&networks.vpns.join("\n")));
+                        }
+                    }
+                }
+
+                let icon_name = match state {
+                    ClientState::WiredConnected => "network-wired-symbolic",
+                    ClientState::WifiConnected => wifi_icon(strength),
+                    ClientState::CellularConnected => "network-cellular-symbolic",
+                    ClientState::VpnConnected => "network-vpn-symbolic",
+                    ClientState::WifiDisconnected => "network-wireless-acquiring-symbolic",
+                    ClientState::Offline => "network-wireless-disabled-symbolic",
+                    ClientState::Unknown => "dialog-question-symbolic",
+                };
+                ImageProvider::parse(icon_name, &icon_theme, false, icon_size)
+                    .map(|provider| provider.load_into_image(&icon));
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<GtkBox> {
+        let container = GtkBox::new(Orientation::Vertical, 5);
+
+        let header = GtkBox::new(Orientation::Horizontal, 10);
+        header.add_class("airplane-mode");
+
+        let title = Label::builder()
+            .label("Airplane mode")
+            .halign(Align::Start)
+            .hexpand(true)
+            .build();
+        title.add_class("title");
+
+        let airplane_mode = Switch::builder().valign(Align::Center).build();
+        airplane_mode.add_class("toggle");
+
+        header.add(&title);
+        header.add(&airplane_mode);
+        container.add(&header);
+
+        let vpns = GtkBox::new(Orientation::Vertical, 5);
+        vpns.add_class("vpns");
+        container.add(&vpns);
+
+        let networks = GtkBox::new(Orientation::Vertical, 5);
+        networks.add_class("networks");
+        container.add(&networks);
+
+        // the last known airplane mode state,
+        // used to ignore switch changes made when updating it
+        let airplane_mode_enabled = Rc::new(Cell::new(false));
+
+        {
+            let tx = tx.clone();
+            let enabled = airplane_mode_enabled.clone();
+
+            airplane_mode.connect_state_set(move |_, state| {
+                if state != enabled.get() {
+                    enabled.set(state);
+                    try_send!(tx, NetworkManagerCommand::SetAirplaneMode(state));
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        // the network list is not rebuilt while a password is being entered
+        let editing = Rc::new(Cell::new(false));
+
+        {
+            let editing = editing.clone();
+            container.connect_unmap(move |_| editing.set(false));
+        }
+
+        glib_recv!(rx, update => {
+            let NetworkManagerUpdate::Networks(state) = update else {
+                continue;
             };
-            ImageProvider::parse(icon_name, &icon_theme, false, self.icon_size)
-                .map(|provider| provider.load_into_image(&icon));
+
+            airplane_mode_enabled.set(state.airplane_mode);
+            airplane_mode.set_active(state.airplane_mode);
+
+            for child in vpns.children() {
+                vpns.remove(&child);
+            }
+
+            for name in &state.vpns {
+                let label = Label::builder().label(name).halign(Align::Start).build();
+                label.add_class("vpn");
+                vpns.add(&label);
+            }
+
+            vpns.show_all();
+            vpns.set_visible(!state.vpns.is_empty());
+
+            if editing.get() {
+                continue;
+            }
+
+            for child in networks.children() {
+                networks.remove(&child);
+            }
+
+            for access_point in state.access_points {
+                networks.add(&network_row(access_point, &tx, &editing));
+            }
+
+            networks.show_all();
         });
 
-        Ok(ModuleParts::new(container, None))
+        Some(container)
+    }
+}
+
+/// Gets the Wi-Fi icon for a signal strength percentage.
+fn wifi_icon(strength: Option<u8>) -> &'static str {
+    match strength {
+        None => "network-wireless-symbolic",
+        Some(80..) => "network-wireless-signal-excellent-symbolic",
+        Some(55..) => "network-wireless-signal-good-symbolic",
+        Some(30..) => "network-wireless-signal-ok-symbolic",
+        Some(5..) => "network-wireless-signal-weak-symbolic",
+        Some(_) => "network-wireless-signal-none-symbolic",
+    }
+}
+
+/// Creates the popup row for a Wi-Fi network.
+///
+/// Secured networks without a saved connection
+/// show a password entry when clicked.
+fn network_row(
+    access_point: AccessPoint,
+    tx: &mpsc::Sender<NetworkManagerCommand>,
+    editing: &Rc<Cell<bool>>,
+) -> GtkBox {
+    let row = GtkBox::new(Orientation::Vertical, 5);
+    row.add_class("network");
+
+    if access_point.active {
+        row.add_class("active");
     }
+
+    if access_point.secured {
+        row.add_class("secured");
+    }
+
+    let button = Button::new();
+    button.add_class("connect");
+    button.set_sensitive(!access_point.active);
+
+    let content = GtkBox::new(Orientation::Horizontal, 10);
+
+    let ssid = Label::builder()
+        .label(&access_point.ssid)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    ssid.add_class("ssid");
+
+    let strength = Label::new(Some(&format!("{}%", access_point.strength)));
+    strength.add_class("strength");
+
+    content.add(&ssid);
+    content.add(&strength);
+    button.add(&content);
+    row.add(&button);
+
+    if access_point.needs_password && !access_point.saved {
+        let entry = Entry::builder()
+            .visibility(false)
+            .placeholder_text("Password")
+            .build();
+        entry.add_class("password");
+        entry.set_no_show_all(true);
+        row.add(&entry);
+
+        {
+            let entry = entry.clone();
+            let editing = editing.clone();
+
+            button.connect_clicked(move |_| {
+                editing.set(true);
+                entry.show();
+                entry.grab_focus();
+            });
+        }
+
+        let tx = tx.clone();
+        let editing = editing.clone();
+        let ssid = access_point.ssid;
+
+        entry.connect_activate(move |entry| {
+            editing.set(false);
+            entry.hide();
+
+            try_send!(
+                tx,
+                NetworkManagerCommand::Connect {
+                    ssid: ssid.clone(),
+                    password: Some(entry.text().to_string()),
+                }
+            );
+        });
+    } else {
+        let tx = tx.clone();
+        let ssid = access_point.ssid;
+
+        button.connect_clicked(move |_| {
+            try_send!(
+                tx,
+                NetworkManagerCommand::Connect {
+                    ssid: ssid.clone(),
+                    password: None,
+                }
+            );
+        });
+    }
+
+    row
 }
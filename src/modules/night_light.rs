@@ -0,0 +1,492 @@
+use crate::clients::wayland::{self, NEUTRAL_TEMPERATURE};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Timelike};
+use color_eyre::eyre::{eyre, WrapErr};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{Button, Orientation, Scale};
+use serde::Deserialize;
+use std::f64::consts::PI;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+/// The number of minutes in a day.
+const DAY_MINUTES: f64 = 1440.0;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NightLightModule {
+    /// The colour temperature to tint the screen to at night, in Kelvin.
+    /// This can be changed using the popup slider.
+    ///
+    /// **Default**: `4000`
+    #[serde(default = "default_temperature")]
+    temperature: u32,
+
+    /// The lowest colour temperature the popup slider allows, in Kelvin.
+    ///
+    /// **Default**: `1000`
+    #[serde(default = "default_min_temperature")]
+    min_temperature: u32,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The icon to show in the `{icon}` token.
+    ///
+    /// **Default**: `󰖔`
+    #[serde(default = "default_icon")]
+    icon: String,
+
+    /// When to automatically tint the screen.
+    /// If set, the module starts in automatic mode.
+    ///
+    /// See [schedule](#schedule).
+    ///
+    /// **Default**: `null`
+    schedule: Option<ScheduleConfig>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_temperature() -> u32 {
+    4000
+}
+
+const fn default_min_temperature() -> u32 {
+    1000
+}
+
+fn default_format() -> String {
+    String::from("{icon}")
+}
+
+fn default_icon() -> String {
+    String::from("󰖔")
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ScheduleConfig {
+    /// The time to tint the screen at, as `HH:MM`.
+    /// If not set, sunset is calculated from `latitude` and `longitude`.
+    ///
+    /// **Default**: `null`
+    sunset: Option<String>,
+
+    /// The time to stop tinting the screen at, as `HH:MM`.
+    /// If not set, sunrise is calculated from `latitude` and `longitude`.
+    ///
+    /// **Default**: `null`
+    sunrise: Option<String>,
+
+    /// Latitude in decimal degrees, used to calculate sunrise and sunset.
+    /// North is positive.
+    ///
+    /// **Default**: `null`
+    latitude: Option<f64>,
+
+    /// Longitude in decimal degrees, used to calculate sunrise and sunset.
+    /// East is positive.
+    ///
+    /// **Default**: `null`
+    longitude: Option<f64>,
+
+    /// The number of minutes to fade between temperatures over,
+    /// centred on sunrise and sunset.
+    ///
+    /// **Default**: `30`
+    #[serde(default = "default_transition")]
+    transition: u32,
+}
+
+const fn default_transition() -> u32 {
+    30
+}
+
+/// When night starts and ends.
+#[derive(Debug, Clone, Copy)]
+enum Times {
+    /// Fixed times of day.
+    Fixed {
+        sunrise: NaiveTime,
+        sunset: NaiveTime,
+    },
+    /// Sunrise and sunset at a location.
+    Solar { latitude: f64, longitude: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Schedule {
+    times: Times,
+    /// Length of each transition, in minutes.
+    transition: f64,
+}
+
+impl TryFrom<ScheduleConfig> for Schedule {
+    type Error = color_eyre::Report;
+
+    fn try_from(config: ScheduleConfig) -> Result<Self> {
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .wrap_err_with(|| format!("Invalid schedule time '{time}', expected HH:MM"))
+        };
+
+        let times = match (config.sunrise, config.sunset) {
+            (Some(sunrise), Some(sunset)) => Times::Fixed {
+                sunrise: parse(&sunrise)?,
+                sunset: parse(&sunset)?,
+            },
+            (None, None) => match (config.latitude, config.longitude) {
+                (Some(latitude), Some(longitude)) => Times::Solar {
+                    latitude,
+                    longitude,
+                },
+                _ => return Err(eyre!("Schedule needs either times or a location")),
+            },
+            _ => return Err(eyre!("Schedule needs both `sunrise` and `sunset`")),
+        };
+
+        Ok(Self {
+            times,
+            transition: f64::from(config.transition.max(1)),
+        })
+    }
+}
+
+impl Schedule {
+    /// Gets how far into night it is,
+    /// from `0` during the day to `1` at night.
+    fn night_amount(&self, now: DateTime<Local>) -> f64 {
+        let (sunrise, sunset) = match self.times {
+            Times::Fixed { sunrise, sunset } => (minute_of_day(sunrise), minute_of_day(sunset)),
+            Times::Solar {
+                latitude,
+                longitude,
+            } => match sun_times(now.date_naive(), latitude, longitude) {
+                SunTimes::Normal { sunrise, sunset } => {
+                    let offset = f64::from(now.offset().local_minus_utc()) / 60.0;
+                    (sunrise + offset, sunset + offset)
+                }
+                SunTimes::Day => return 0.0,
+                SunTimes::Night => return 1.0,
+            },
+        };
+
+        let transition = self.transition;
+
+        // progress through a transition centred on `edge`
+        let progress =
+            |minute: f64, edge: f64| ((minute - edge) / transition + 0.5).clamp(0.0, 1.0);
+
+        // measure from sunrise, so that night never wraps past midnight
+        let now = (minute_of_day(now.time()) - sunrise).rem_euclid(DAY_MINUTES);
+        let sunset = (sunset - sunrise).rem_euclid(DAY_MINUTES);
+
+        if now < sunset / 2.0 {
+            1.0 - progress(now, 0.0)
+        } else if now < (sunset + DAY_MINUTES) / 2.0 {
+            progress(now, sunset)
+        } else {
+            1.0 - progress(now - DAY_MINUTES, 0.0)
+        }
+    }
+}
+
+fn minute_of_day(time: NaiveTime) -> f64 {
+    f64::from(time.num_seconds_from_midnight()) / 60.0
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SunTimes {
+    /// Sunrise and sunset, in minutes from midnight UTC.
+    Normal { sunrise: f64, sunset: f64 },
+    /// The sun does not set.
+    Day,
+    /// The sun does not rise.
+    Night,
+}
+
+/// Calculates sunrise and sunset on the date,
+/// using NOAA's general solar position equations.
+fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> SunTimes {
+    // fractional year at midday, in radians
+    let year = 2.0 * PI / 365.0 * f64::from(date.ordinal0());
+
+    // equation of time, in minutes
+    let eqtime = 229.18
+        * (0.000_075 + 0.001_868 * year.cos()
+            - 0.032_077 * year.sin()
+            - 0.014_615 * (2.0 * year).cos()
+            - 0.040_849 * (2.0 * year).sin());
+
+    // solar declination, in radians
+    let declination = 0.006_918 - 0.399_912 * year.cos() + 0.070_257 * year.sin()
+        - 0.006_758 * (2.0 * year).cos()
+        + 0.000_907 * (2.0 * year).sin()
+        - 0.002_697 * (3.0 * year).cos()
+        + 0.001_48 * (3.0 * year).sin();
+
+    // the zenith at sunrise and sunset allows for refraction and the size of the sun
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = 90.833_f64.to_radians().cos() / (latitude.cos() * declination.cos())
+        - latitude.tan() * declination.tan();
+
+    if cos_hour_angle < -1.0 {
+        return SunTimes::Day;
+    }
+
+    if cos_hour_angle > 1.0 {
+        return SunTimes::Night;
+    }
+
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    SunTimes::Normal {
+        sunrise: 720.0 - 4.0 * (longitude + hour_angle) - eqtime,
+        sunset: 720.0 - 4.0 * (longitude - hour_angle) - eqtime,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The screen is never tinted.
+    Off,
+    /// The screen is always tinted.
+    On,
+    /// The screen is tinted according to the schedule.
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightLightState {
+    mode: Mode,
+    /// The night temperature, set by the slider.
+    temperature: u32,
+    /// The temperature the screen is currently tinted to,
+    /// or `None` if it is not tinted.
+    current: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NightLightCommand {
+    SetMode(Mode),
+    SetTemperature(u32),
+}
+
+impl Module<Button> for NightLightModule {
+    type SendMessage = NightLightState;
+    type ReceiveMessage = NightLightCommand;
+
+    module_impl!("night_light");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let schedule = self.schedule.clone().map(Schedule::try_from).transpose()?;
+
+        let tx = context.tx.clone();
+        let client = context.client::<wayland::Client>();
+
+        let mut state = NightLightState {
+            mode: if schedule.is_some() {
+                Mode::Auto
+            } else {
+                Mode::Off
+            },
+            temperature: self.temperature,
+            current: None,
+        };
+
+        spawn(async move {
+            // re-check the schedule every minute
+            let mut interval = polling::interval(Duration::from_secs(60));
+            let mut last = None;
+
+            loop {
+                tokio::select! {
+                    () = interval.tick() => {}
+                    Some(command) = rx.recv() => match command {
+                        NightLightCommand::SetMode(mode) => state.mode = mode,
+                        NightLightCommand::SetTemperature(temperature) => {
+                            state.temperature = temperature;
+                        }
+                    }
+                }
+
+                let target = match (state.mode, schedule) {
+                    (Mode::On, _) => Some(state.temperature),
+                    (Mode::Auto, Some(schedule)) => {
+                        blend(state.temperature, schedule.night_amount(Local::now()))
+                    }
+                    _ => None,
+                };
+
+                if target != state.current {
+                    match client.set_color_temperature(target) {
+                        Ok(()) => state.current = target,
+                        Err(err) => error!("{err:?}"),
+                    }
+                }
+
+                if last != Some(state) {
+                    last = Some(state);
+                    send_async!(tx, ModuleUpdateEvent::Update(state));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
+        {
+            let tx = context.tx.clone();
+
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let rx = context.subscribe();
+            let button = button.clone();
+            let format = self.format.clone();
+            let icon = self.icon.clone();
+
+            glib_recv!(rx, state => {
+                let temperature = state.current.unwrap_or(NEUTRAL_TEMPERATURE);
+                let label = format
+                    .replace("{icon}", &icon)
+                    .replace("{temperature}", &temperature.to_string());
+
+                button.set_label(&label);
+
+                if state.current.is_some() {
+                    button.add_class("active");
+                } else {
+                    button.remove_class("active");
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let modes_box = gtk::Box::new(Orientation::Horizontal, 0);
+        modes_box.add_class("modes");
+        container.add(&modes_box);
+
+        let mut modes = vec![(Mode::Off, "Off"), (Mode::On, "On")];
+        if self.schedule.is_some() {
+            modes.push((Mode::Auto, "Auto"));
+        }
+
+        let buttons = modes
+            .into_iter()
+            .map(|(mode, name)| {
+                let button = Button::with_label(name);
+                button.add_class("mode");
+                modes_box.add(&button);
+
+                let tx = tx.clone();
+                button.connect_clicked(move |_| {
+                    try_send!(tx, NightLightCommand::SetMode(mode));
+                });
+
+                (mode, button)
+            })
+            .collect::<Vec<_>>();
+
+        let min = f64::from(self.min_temperature.min(NEUTRAL_TEMPERATURE));
+        let slider = Scale::with_range(
+            Orientation::Horizontal,
+            min,
+            f64::from(NEUTRAL_TEMPERATURE),
+            100.0,
+        );
+        slider.add_class("slider");
+        slider.set_value(f64::from(self.temperature));
+        container.add(&slider);
+
+        // only sent on user input, so updates from the controller do not loop back
+        slider.connect_change_value(move |_, _, value| {
+            // GTK will send values outside min/max range
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let value = value.clamp(min, f64::from(NEUTRAL_TEMPERATURE)).round() as u32;
+
+            try_send!(tx, NightLightCommand::SetTemperature(value));
+
+            Propagation::Proceed
+        });
+
+        glib_recv!(rx, state => {
+            for (mode, button) in &buttons {
+                if *mode == state.mode {
+                    button.add_class("active");
+                } else {
+                    button.remove_class("active");
+                }
+            }
+
+            slider.set_value(f64::from(state.temperature));
+        });
+
+        Some(container)
+    }
+}
+
+/// Blends from the neutral temperature to the night temperature.
+/// Returns `None` when the screen should not be tinted.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn blend(temperature: u32, night_amount: f64) -> Option<u32> {
+    if night_amount <= 0.0 {
+        return None;
+    }
+
+    let neutral = f64::from(NEUTRAL_TEMPERATURE);
+    let temperature = neutral - (neutral - f64::from(temperature)) * night_amount;
+
+    Some(temperature.round() as u32)
+}
@@ -1,14 +1,37 @@
+use crate::clients::notification_daemon::{self, Notification, Urgency};
 use crate::clients::swaync;
 use crate::config::CommonConfig;
 use crate::gtk_helpers::IronbarGtkExt;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::image::ImageProvider;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
 use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use glib::Propagation;
 use gtk::prelude::*;
-use gtk::{Align, Button, Label, Overlay};
+use gtk::{
+    Align, Button, EventBox, IconTheme, Label, Orientation, Overlay, PolicyType, ScrolledWindow,
+};
 use serde::Deserialize;
-use tokio::sync::mpsc::Receiver;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
 use tracing::error;
 
+/// The action invoked by clicking a notification.
+const DEFAULT_ACTION: &str = "default";
+
+const ICON_CLOSE: &str = "󰅖";
+
+/// The maximum height of the notification center list,
+/// after which it scrolls.
+const MAX_LIST_HEIGHT: i32 = 500;
+
+/// The maximum width of notification text,
+/// after which it wraps.
+const MAX_WIDTH_CHARS: i32 = 40;
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NotificationsModule {
@@ -18,7 +41,38 @@ pub struct NotificationsModule {
     #[serde(default = "crate::config::default_true")]
     show_count: bool,
 
-    /// SwayNC state icons.
+    /// Whether to run Ironbar's own notification daemon,
+    /// instead of showing the state of SwayNC.
+    ///
+    /// When enabled, clicking the widget opens a notification center popup,
+    /// and incoming notifications open the popup.
+    /// This cannot be used while another notification daemon is running.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    daemon: bool,
+
+    /// The number of milliseconds to show the popup for
+    /// when a notification is received,
+    /// if the notification does not set its own timeout.
+    /// Set to `0` to disable opening the popup for notifications.
+    ///
+    /// This only applies when `daemon` is enabled.
+    ///
+    /// **Default**: `5000`
+    #[serde(default = "default_popup_timeout")]
+    popup_timeout: u64,
+
+    /// The size of notification icons in the notification center.
+    ///
+    /// This only applies when `daemon` is enabled.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// State icons.
+    /// When `daemon` is enabled, only the `closed` icons are used.
     ///
     /// See [icons](#icons).
     #[serde(default)]
@@ -29,6 +83,14 @@ pub struct NotificationsModule {
     pub common: Option<CommonConfig>,
 }
 
+const fn default_popup_timeout() -> u64 {
+    5000
+}
+
+const fn default_icon_size() -> i32 {
+    24
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct Icons {
@@ -109,8 +171,8 @@ fn default_icon_open_dnd() -> String {
 }
 
 impl Icons {
-    fn icon(&self, value: swaync::Event) -> &str {
-        match (value.cc_open, value.count > 0, value.dnd) {
+    fn icon(&self, value: Status) -> &str {
+        match (value.open, value.count > 0, value.dnd) {
             (true, _, true) => &self.open_dnd,
             (true, true, false) => &self.open_some,
             (true, false, false) => &self.open_none,
@@ -122,13 +184,52 @@ impl Icons {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The state shown on the widget button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// The number of notifications in SwayNC,
+    /// or the number of unread notifications when using the built-in daemon.
+    count: u32,
+    dnd: bool,
+    /// Whether the SwayNC panel is open.
+    open: bool,
+}
+
+impl From<swaync::Event> for Status {
+    fn from(event: swaync::Event) -> Self {
+        Self {
+            count: event.count,
+            dnd: event.dnd,
+            open: event.cc_open,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationsEvent {
+    Status(Status),
+    /// The notifications held by the built-in daemon, oldest first.
+    Notifications(Vec<Notification>),
+    /// A notification was received by the built-in daemon,
+    /// so the popup should be opened for the given number of milliseconds.
+    Popup(u64),
+}
+
+#[derive(Debug, Clone)]
 pub enum UiEvent {
+    /// Toggles the SwayNC panel.
     ToggleVisibility,
+    /// Marks all notifications as read.
+    MarkRead,
+    ToggleDnd,
+    Dismiss(u32),
+    DismissAll,
+    /// Invokes the action with the key on a notification.
+    Invoke(u32, String),
 }
 
 impl Module<Overlay> for NotificationsModule {
-    type SendMessage = swaync::Event;
+    type SendMessage = NotificationsEvent;
     type ReceiveMessage = UiEvent;
 
     module_impl!("notifications");
@@ -137,11 +238,227 @@ impl Module<Overlay> for NotificationsModule {
         &self,
         _info: &ModuleInfo,
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        mut rx: Receiver<Self::ReceiveMessage>,
+        rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> color_eyre::Result<()>
     where
         <Self as Module<Overlay>>::SendMessage: Clone,
     {
+        if self.daemon {
+            self.spawn_daemon_controller(context, rx)
+        } else {
+            Self::spawn_swaync_controller(context, rx)
+        }
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<Overlay>>
+    where
+        <Self as Module<Overlay>>::SendMessage: Clone,
+    {
+        let overlay = Overlay::new();
+        let button = Button::with_label(&self.icons.closed_none);
+        overlay.add(&button);
+
+        let label = Label::builder()
+            .label("0")
+            .halign(Align::End)
+            .valign(Align::Start)
+            .build();
+
+        if self.show_count {
+            label.add_class("count");
+            overlay.add_overlay(&label);
+            overlay.set_overlay_pass_through(&label, true);
+        }
+
+        // incremented whenever the popup is opened,
+        // so that only the latest notification popup is closed by its timeout
+        let generation = Rc::new(Cell::new(0_u32));
+
+        let ctx = context.controller_tx.clone();
+        if self.daemon {
+            let tx = context.tx.clone();
+            let generation = generation.clone();
+
+            button.connect_clicked(move |button| {
+                generation.set(generation.get().wrapping_add(1));
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                try_send!(ctx, UiEvent::MarkRead);
+            });
+        } else {
+            button.connect_clicked(move |_| {
+                try_send!(ctx, UiEvent::ToggleVisibility);
+            });
+        }
+
+        {
+            let button = button.clone();
+            let icons = self.icons.clone();
+            let show_count = self.show_count;
+
+            let tx = context.tx.clone();
+            let popup = context.popup.clone();
+            let id = context.id;
+
+            let rx = context.subscribe();
+            glib_recv!(rx, ev => {
+                match ev {
+                    NotificationsEvent::Status(status) => {
+                        let icon = icons.icon(status);
+                        button.set_label(icon);
+
+                        label.set_label(&status.count.to_string());
+                        label.set_visible(show_count && status.count > 0);
+                    }
+                    // do not replace another module's popup
+                    NotificationsEvent::Popup(timeout)
+                        if !popup.visible() || popup.current_widget() == Some(id) =>
+                    {
+                        try_send!(tx, ModuleUpdateEvent::OpenPopup(button.popup_id()));
+
+                        generation.set(generation.get().wrapping_add(1));
+                        let current = generation.get();
+
+                        let generation = generation.clone();
+                        let popup = popup.clone();
+
+                        glib::timeout_add_local_once(Duration::from_millis(timeout), move || {
+                            if generation.get() == current && popup.current_widget() == Some(id) {
+                                popup.hide();
+                            }
+                        });
+                    }
+                    NotificationsEvent::Popup(_) | NotificationsEvent::Notifications(_) => {}
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(overlay, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        if !self.daemon {
+            return None;
+        }
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 5);
+        header.add_class("header");
+        container.add(&header);
+
+        let title = Label::new(Some("Notifications"));
+        title.add_class("title");
+        title.set_halign(Align::Start);
+        title.set_hexpand(true);
+        header.add(&title);
+
+        let dnd_button = Button::with_label(&self.icons.closed_dnd);
+        dnd_button.add_class("dnd");
+        dnd_button.set_tooltip_text(Some("Do not disturb"));
+        header.add(&dnd_button);
+
+        let clear_button = Button::with_label("Clear all");
+        clear_button.add_class("clear");
+        header.add(&clear_button);
+
+        {
+            let tx = tx.clone();
+            dnd_button.connect_clicked(move |_| {
+                try_send!(tx, UiEvent::ToggleDnd);
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            clear_button.connect_clicked(move |_| {
+                try_send!(tx, UiEvent::DismissAll);
+            });
+        }
+
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(MAX_LIST_HEIGHT)
+            .build();
+        container.add(&scrolled);
+
+        let list = gtk::Box::new(Orientation::Vertical, 5);
+        list.add_class("list");
+        scrolled.add(&list);
+
+        let icon_theme = info.icon_theme.clone();
+        let icon_size = self.icon_size;
+
+        glib_recv!(rx, ev => {
+            match ev {
+                NotificationsEvent::Status(status) => {
+                    if status.dnd {
+                        dnd_button.add_class("active");
+                    } else {
+                        dnd_button.remove_class("active");
+                    }
+                }
+                NotificationsEvent::Notifications(notifications) => {
+                    list.foreach(|child| list.remove(child));
+
+                    if notifications.is_empty() {
+                        let label = Label::new(Some("No notifications"));
+                        label.add_class("empty");
+                        list.add(&label);
+                    }
+
+                    for (app_name, group) in group_by_app(&notifications) {
+                        let group_box = gtk::Box::new(Orientation::Vertical, 5);
+                        group_box.add_class("group");
+
+                        let label = Label::new(Some(app_name));
+                        label.add_class("app-name");
+                        label.set_halign(Align::Start);
+                        group_box.add(&label);
+
+                        for notification in group {
+                            let widget =
+                                make_notification(notification, &tx, &icon_theme, icon_size);
+                            group_box.add(&widget);
+                        }
+
+                        list.add(&group_box);
+                    }
+
+                    list.show_all();
+                }
+                NotificationsEvent::Popup(_) => {}
+            }
+        });
+
+        Some(container)
+    }
+}
+
+impl NotificationsModule {
+    fn spawn_swaync_controller(
+        context: &WidgetContext<NotificationsEvent, UiEvent>,
+        mut rx: mpsc::Receiver<UiEvent>,
+    ) -> color_eyre::Result<()> {
         let client = context.try_client::<swaync::Client>()?;
 
         {
@@ -153,20 +470,26 @@ impl Module<Overlay> for NotificationsModule {
                 let initial_state = client.state().await;
 
                 match initial_state {
-                    Ok(ev) => send_async!(tx, ModuleUpdateEvent::Update(ev)),
+                    Ok(ev) => send_async!(
+                        tx,
+                        ModuleUpdateEvent::Update(NotificationsEvent::Status(ev.into()))
+                    ),
                     Err(err) => error!("{err:?}"),
                 };
 
                 while let Ok(ev) = rx.recv().await {
-                    send_async!(tx, ModuleUpdateEvent::Update(ev));
+                    send_async!(
+                        tx,
+                        ModuleUpdateEvent::Update(NotificationsEvent::Status(ev.into()))
+                    );
                 }
             });
         }
 
         spawn(async move {
             while let Some(event) = rx.recv().await {
-                match event {
-                    UiEvent::ToggleVisibility => client.toggle_visibility().await,
+                if let UiEvent::ToggleVisibility = event {
+                    client.toggle_visibility().await;
                 }
             }
         });
@@ -174,50 +497,216 @@ impl Module<Overlay> for NotificationsModule {
         Ok(())
     }
 
-    fn into_widget(
-        self,
-        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _info: &ModuleInfo,
-    ) -> color_eyre::Result<ModuleParts<Overlay>>
-    where
-        <Self as Module<Overlay>>::SendMessage: Clone,
-    {
-        let overlay = Overlay::new();
-        let button = Button::with_label(&self.icons.closed_none);
-        overlay.add(&button);
+    fn spawn_daemon_controller(
+        &self,
+        context: &WidgetContext<NotificationsEvent, UiEvent>,
+        mut rx: mpsc::Receiver<UiEvent>,
+    ) -> color_eyre::Result<()> {
+        let client = context.try_client::<notification_daemon::Client>()?;
+        let tx = context.tx.clone();
+        let popup_timeout = self.popup_timeout;
 
-        let label = Label::builder()
-            .label("0")
-            .halign(Align::End)
-            .valign(Align::Start)
-            .build();
+        spawn(async move {
+            let mut events = client.subscribe();
+            let mut notifications = client.notifications();
+            let mut unread = 0_usize;
+            let mut dnd = false;
+            let mut popup = None;
+
+            loop {
+                let status = Status {
+                    count: u32::try_from(unread).unwrap_or(u32::MAX),
+                    dnd,
+                    open: false,
+                };
 
-        if self.show_count {
-            label.add_class("count");
-            overlay.add_overlay(&label);
-            overlay.set_overlay_pass_through(&label, true);
-        }
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(NotificationsEvent::Status(status))
+                );
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(NotificationsEvent::Notifications(
+                        notifications.clone()
+                    ))
+                );
+
+                if let Some(timeout) = popup.take() {
+                    send_async!(
+                        tx,
+                        ModuleUpdateEvent::Update(NotificationsEvent::Popup(timeout))
+                    );
+                }
 
-        let ctx = context.controller_tx.clone();
-        button.connect_clicked(move |_| {
-            try_send!(ctx, UiEvent::ToggleVisibility);
+                tokio::select! {
+                    Ok(event) = events.recv() => match event {
+                        notification_daemon::Event::Add(notification) => {
+                            let show = !dnd || notification.urgency == Urgency::Critical;
+                            if show && popup_timeout > 0 {
+                                popup = Some(popup_duration(&notification, popup_timeout));
+                            }
+
+                            match notifications.iter_mut().find(|n| n.id == notification.id) {
+                                Some(existing) => *existing = notification,
+                                None => {
+                                    notifications.push(notification);
+                                    unread += 1;
+                                }
+                            }
+                        }
+                        notification_daemon::Event::Remove(id) => {
+                            notifications.retain(|notification| notification.id != id);
+                            unread = unread.min(notifications.len());
+                        }
+                    },
+                    Some(event) = rx.recv() => match event {
+                        UiEvent::ToggleVisibility | UiEvent::MarkRead => unread = 0,
+                        UiEvent::ToggleDnd => dnd = !dnd,
+                        UiEvent::Dismiss(id) => client.dismiss(id).await,
+                        UiEvent::DismissAll => client.dismiss_all().await,
+                        UiEvent::Invoke(id, action) => client.invoke_action(id, &action).await,
+                    },
+                    else => break,
+                }
+            }
         });
 
+        Ok(())
+    }
+}
+
+/// Gets the number of milliseconds to open the popup for a notification.
+fn popup_duration(notification: &Notification, default: u64) -> u64 {
+    u64::try_from(notification.timeout)
+        .ok()
+        .filter(|&timeout| timeout > 0)
+        .unwrap_or(default)
+}
+
+/// Groups notifications by the app which sent them,
+/// with the most recent first.
+fn group_by_app(notifications: &[Notification]) -> Vec<(&str, Vec<&Notification>)> {
+    let mut groups: Vec<(&str, Vec<&Notification>)> = vec![];
+
+    for notification in notifications.iter().rev() {
+        match groups
+            .iter_mut()
+            .find(|(app_name, _)| *app_name == notification.app_name)
         {
-            let button = button.clone();
+            Some((_, group)) => group.push(notification),
+            None => groups.push((&notification.app_name, vec![notification])),
+        }
+    }
+
+    groups
+}
+
+/// Creates the notification center entry for a notification,
+/// showing its icon, summary, body and actions.
+fn make_notification(
+    notification: &Notification,
+    tx: &mpsc::Sender<UiEvent>,
+    icon_theme: &IconTheme,
+    icon_size: i32,
+) -> EventBox {
+    let event_box = EventBox::new();
+
+    let container = gtk::Box::new(Orientation::Horizontal, 5);
+    container.add_class("notification");
+    event_box.add(&container);
+
+    match notification.urgency {
+        Urgency::Low => container.add_class("low"),
+        Urgency::Critical => container.add_class("critical"),
+        Urgency::Normal => {}
+    }
+
+    if let Some(icon) = &notification.icon {
+        let image = gtk::Image::new();
+        image.add_class("icon");
+        image.set_valign(Align::Start);
+
+        if let Some(provider) = ImageProvider::parse(icon, icon_theme, false, icon_size) {
+            if let Err(err) = provider.load_into_image(&image) {
+                error!("{err:?}");
+            }
+        }
 
-            glib_recv!(context.subscribe(), ev => {
-                let icon = self.icons.icon(ev);
-                button.set_label(icon);
+        container.add(&image);
+    }
+
+    let content = gtk::Box::new(Orientation::Vertical, 2);
+    content.set_hexpand(true);
+    container.add(&content);
+
+    let summary = make_text_label(&notification.summary);
+    summary.add_class("summary");
+    content.add(&summary);
+
+    if !notification.body.is_empty() {
+        let body = make_text_label(&notification.body);
+        body.add_class("body");
+        content.add(&body);
+    }
 
-                label.set_label(&ev.count.to_string());
-                label.set_visible(self.show_count && ev.count > 0);
+    let id = notification.id;
+
+    let actions = notification
+        .actions
+        .iter()
+        .filter(|(key, _)| key != DEFAULT_ACTION)
+        .collect::<Vec<_>>();
+
+    if !actions.is_empty() {
+        let actions_box = gtk::Box::new(Orientation::Horizontal, 5);
+        actions_box.add_class("actions");
+        content.add(&actions_box);
+
+        for (key, label) in actions {
+            let button = Button::with_label(label);
+            button.add_class("action");
+            actions_box.add(&button);
+
+            let tx = tx.clone();
+            let key = key.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, UiEvent::Invoke(id, key.clone()));
             });
         }
+    }
 
-        Ok(ModuleParts {
-            widget: overlay,
-            popup: None,
-        })
+    let close = Button::with_label(ICON_CLOSE);
+    close.add_class("close");
+    close.set_valign(Align::Start);
+    container.add(&close);
+
+    {
+        let tx = tx.clone();
+        close.connect_clicked(move |_| {
+            try_send!(tx, UiEvent::Dismiss(id));
+        });
+    }
+
+    if notification
+        .actions
+        .iter()
+        .any(|(key, _)| key == DEFAULT_ACTION)
+    {
+        let tx = tx.clone();
+        event_box.connect_button_press_event(move |_, _| {
+            try_send!(tx, UiEvent::Invoke(id, DEFAULT_ACTION.to_string()));
+            Propagation::Stop
+        });
     }
+
+    event_box
+}
+
+/// Creates a left-aligned label which wraps long text.
+fn make_text_label(text: &str) -> Label {
+    let label = Label::new(Some(text));
+    label.set_xalign(0.0);
+    label.set_line_wrap(true);
+    label.set_max_width_chars(MAX_WIDTH_CHARS);
+    label
 }
@@ -0,0 +1,419 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::shared::{connect_popup_key_press, move_selection, shell_quote};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::script::{Script, ScriptInput};
+use crate::{module_impl, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::keys::constants as keys;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, ListBox, ListBoxRow, Orientation, SearchEntry};
+use gtk_layer_shell::LayerShell;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PaletteModule {
+    /// The text to show on the widget button.
+    ///
+    /// **Default**: `󰘳`
+    #[serde(default = "default_label")]
+    label: String,
+
+    /// The actions to list in the popup.
+    /// See [actions](#actions).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    actions: Vec<PaletteAction>,
+
+    /// The maximum number of actions to list at once.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_label() -> String {
+    String::from("󰘳")
+}
+
+const fn default_max_results() -> usize {
+    10
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PaletteAction {
+    /// The name of the action, shown in the list and matched when searching.
+    name: String,
+
+    /// Extra text shown below the name, which is also matched when searching.
+    ///
+    /// **Default**: `null`
+    description: Option<String>,
+
+    /// A [script](scripts) to run.
+    ///
+    /// **Supported script types**: `oneshot`.
+    /// <br>
+    /// **Default**: `null`
+    script: Option<ScriptInput>,
+
+    /// An Ironbar [IPC command](controlling-ironbar) to run,
+    /// written as the arguments to the `ironbar` command,
+    /// such as `bar main toggle-popup clock`.
+    ///
+    /// **Default**: `null`
+    ipc: Option<String>,
+
+    /// A URL to open in its default application.
+    ///
+    /// **Default**: `null`
+    url: Option<String>,
+}
+
+impl PaletteAction {
+    /// Runs each of the script, IPC command and URL which are set.
+    fn run(&self) {
+        debug!("Running palette action '{}'", self.name);
+
+        if let Some(script) = &self.script {
+            Script::new_polling(script.clone()).run_as_oneshot(None);
+        }
+
+        if let Some(args) = &self.ipc {
+            // the running executable is used,
+            // as `ironbar` may not be on the path.
+            match std::env::current_exe() {
+                Ok(exe) => {
+                    let command = format!("{} {args}", shell_quote(&exe.to_string_lossy()));
+                    Script::from(command.as_str()).run_as_oneshot(None);
+                }
+                Err(err) => error!("Failed to locate the Ironbar executable: {err:?}"),
+            }
+        }
+
+        if let Some(url) = &self.url {
+            if let Err(err) =
+                gtk::show_uri_on_window(None::<&gtk::Window>, url, gtk::current_event_time())
+            {
+                error!("Failed to open '{url}': {err:?}");
+            }
+        }
+    }
+}
+
+/// Scores how well `query` fuzzy-matches `text`, ignoring case.
+///
+/// Every character of the query must appear in the text in order,
+/// but not necessarily next to each other.
+/// Runs of consecutive characters and matches at the start of words score higher.
+///
+/// Returns `None` if the query does not match.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+
+    for char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = position + text[position..].iter().position(|&c| c == char)?;
+
+        score += 1;
+
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        previous = Some(index);
+        position = index + 1;
+    }
+
+    // prefer shorter, more specific matches
+    Some(score * 100 - i32::try_from(text.len()).unwrap_or(i32::MAX).min(99))
+}
+
+/// Gets the indices of the actions matching `query`, best match first.
+/// Every action matches an empty query, keeping the configured order.
+fn filter_actions(actions: &[PaletteAction], query: &str) -> Vec<usize> {
+    if query.trim().is_empty() {
+        return (0..actions.len()).collect();
+    }
+
+    let mut matches = actions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, action)| {
+            let name = fuzzy_score(query, &action.name);
+
+            // matches in the name count for more than the description
+            let description = action
+                .description
+                .as_deref()
+                .and_then(|description| fuzzy_score(query, description))
+                .map(|score| score / 2);
+
+            name.max(description).map(|score| (index, score))
+        })
+        .collect::<Vec<_>>();
+
+    // the sort is stable, so equal scores keep the configured order
+    matches.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(index, _)| index).collect()
+}
+
+impl Module<Button> for PaletteModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("palette");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::with_label(&self.label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        _rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let search = SearchEntry::new();
+        search.add_class("search");
+        container.add(&search);
+
+        let list = ListBox::new();
+        list.add_class("actions");
+        container.add(&list);
+
+        let empty = Label::new(Some("No matching actions"));
+        empty.add_class("empty");
+        container.add(&empty);
+
+        container.show_all();
+
+        let actions = Rc::new(self.actions);
+        let max_results = self.max_results;
+
+        // the action shown in each row, by row index
+        let shown = Rc::new(RefCell::new(Vec::<usize>::new()));
+
+        let refresh = {
+            let list = list.clone();
+            let search = search.clone();
+            let actions = actions.clone();
+            let shown = shown.clone();
+
+            move || {
+                for child in list.children() {
+                    list.remove(&child);
+                }
+
+                let mut matches = filter_actions(&actions, &search.text());
+                matches.truncate(max_results);
+
+                for &index in &matches {
+                    list.add(&action_row(&actions[index]));
+                }
+
+                list.show_all();
+                list.select_row(list.row_at_index(0).as_ref());
+                empty.set_visible(matches.is_empty());
+
+                *shown.borrow_mut() = matches;
+            }
+        };
+
+        let refresh = Rc::new(refresh);
+
+        {
+            let refresh = refresh.clone();
+            search.connect_search_changed(move |_| refresh());
+        }
+
+        let activate = {
+            let actions = actions.clone();
+            let tx = context.tx.clone();
+
+            move |row: &ListBoxRow| {
+                let index = usize::try_from(row.index())
+                    .ok()
+                    .and_then(|index| shown.borrow().get(index).copied());
+
+                if let Some(index) = index {
+                    try_send!(tx, ModuleUpdateEvent::ClosePopup);
+                    actions[index].run();
+                }
+            }
+        };
+
+        let activate = Rc::new(activate);
+
+        {
+            let activate = activate.clone();
+            list.connect_row_activated(move |_, row| activate(row));
+        }
+
+        // enter runs the selected action
+        {
+            let list = list.clone();
+            search.connect_activate(move |_| {
+                if let Some(row) = list.selected_row() {
+                    activate(&row);
+                }
+            });
+        }
+
+        // take keyboard focus while the palette is open,
+        // so that it can be searched straight away.
+        {
+            let window = context.popup.window.clone();
+            let search = search.clone();
+
+            container.connect_map(move |_| {
+                window.set_keyboard_interactivity(true);
+
+                search.set_text("");
+                refresh();
+                search.grab_focus();
+            });
+        }
+
+        // the arrow keys must be caught before they reach the search box.
+        {
+            let tx = context.tx.clone();
+
+            connect_popup_key_press(&context.popup, &container, move |_, event| {
+                match event.keyval() {
+                    keys::Up | keys::Down => {
+                        move_selection(&list, event.keyval() == keys::Down);
+                        Propagation::Stop
+                    }
+                    keys::Escape => {
+                        try_send!(tx, ModuleUpdateEvent::ClosePopup);
+                        Propagation::Stop
+                    }
+                    _ => Propagation::Proceed,
+                }
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for an action.
+fn action_row(action: &PaletteAction) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    row.add_class("action");
+
+    let container = gtk::Box::new(Orientation::Vertical, 2);
+
+    let name = Label::builder()
+        .label(&action.name)
+        .halign(Align::Start)
+        .build();
+    name.add_class("name");
+    container.add(&name);
+
+    if let Some(description) = &action.description {
+        let description = Label::builder()
+            .label(description)
+            .halign(Align::Start)
+            .build();
+        description.add_class("description");
+        container.add(&description);
+    }
+
+    row.add(&container);
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(name: &str, description: Option<&str>) -> PaletteAction {
+        PaletteAction {
+            name: name.to_string(),
+            description: description.map(str::to_string),
+            script: None,
+            ipc: None,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("scr", "Screenshot").is_some());
+        assert!(fuzzy_score("sst", "Screenshot").is_some());
+        assert_eq!(fuzzy_score("xyz", "Screenshot"), None);
+
+        // consecutive matches beat scattered ones
+        assert!(fuzzy_score("lock", "Lock screen") > fuzzy_score("lock", "Reload config keys"));
+    }
+
+    #[test]
+    fn test_filter_actions() {
+        let actions = [
+            action("Reload config", None),
+            action("Lock screen", Some("Locks the session")),
+            action("Screenshot", Some("Capture the screen")),
+        ];
+
+        assert_eq!(filter_actions(&actions, ""), [0, 1, 2]);
+        assert_eq!(filter_actions(&actions, "scre"), [2, 1]);
+        assert_eq!(filter_actions(&actions, "capture"), [2]);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::config::CommonConfig;
+use crate::event_bus::BusEvent;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send, Ironbar};
+use gtk::prelude::*;
+use gtk::Button;
+use serde::Deserialize;
+use tokio::sync::mpsc::Receiver;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PresentationModule {
+    /// Icon to show when presentation mode is enabled.
+    ///
+    /// **Default**: `󰐯`
+    #[serde(default = "default_icon_on")]
+    icon_on: String,
+
+    /// Icon to show when presentation mode is disabled.
+    ///
+    /// **Default**: `󰐰`
+    #[serde(default = "default_icon_off")]
+    icon_off: String,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon_on() -> String {
+    String::from("󰐯")
+}
+
+fn default_icon_off() -> String {
+    String::from("󰐰")
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    Toggle,
+}
+
+impl Module<Button> for PresentationModule {
+    type SendMessage = bool;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("presentation");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: Receiver<Self::ReceiveMessage>,
+    ) -> color_eyre::Result<()> {
+        let event_bus = Ironbar::event_bus();
+
+        {
+            let mut bus_rx = event_bus.subscribe();
+            let tx = context.tx.clone();
+            let initial = event_bus.state().presentation;
+
+            spawn(async move {
+                send_async!(tx, ModuleUpdateEvent::Update(initial));
+
+                while let Ok(event) = bus_rx.recv().await {
+                    if let Some(enabled) = event.presentation() {
+                        send_async!(tx, ModuleUpdateEvent::Update(enabled));
+                    }
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::Toggle => {
+                        let enabled = event_bus.state().presentation;
+                        event_bus.publish(BusEvent::Presentation(!enabled));
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<Button>> {
+        let button = Button::with_label(&self.icon_off);
+
+        let tx = context.controller_tx.clone();
+        button.connect_clicked(move |_| {
+            try_send!(tx, UiEvent::Toggle);
+        });
+
+        {
+            let button = button.clone();
+
+            glib_recv!(context.subscribe(), enabled => {
+                if enabled {
+                    button.set_label(&self.icon_on);
+                    button.add_class("enabled");
+                } else {
+                    button.set_label(&self.icon_off);
+                    button.remove_class("enabled");
+                }
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: button,
+            popup: None,
+        })
+    }
+}
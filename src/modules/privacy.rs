@@ -0,0 +1,485 @@
+use crate::clients::volume;
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, spawn_blocking};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Label, Orientation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PrivacyModule {
+    /// Whether to show when an application is capturing from a microphone.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    microphone: bool,
+
+    /// Whether to show when an application has a camera open.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    camera: bool,
+
+    /// Whether to show when the screen is being shared.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    screenshare: bool,
+
+    /// Indicator icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// Number of seconds between checking for camera and screen sharing use.
+    /// The microphone is watched for changes, so it is always updated immediately.
+    ///
+    /// **Default**: `2`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// Names of applications to ignore.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    ignore: Vec<String>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    2
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show while a microphone is in use.
+    ///
+    /// **Default**: `󰍬`
+    #[serde(default = "default_icon_microphone")]
+    microphone: String,
+
+    /// Icon to show while a camera is in use.
+    ///
+    /// **Default**: `󰄀`
+    #[serde(default = "default_icon_camera")]
+    camera: String,
+
+    /// Icon to show while the screen is being shared.
+    ///
+    /// **Default**: `󰍹`
+    #[serde(default = "default_icon_screenshare")]
+    screenshare: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            microphone: default_icon_microphone(),
+            camera: default_icon_camera(),
+            screenshare: default_icon_screenshare(),
+        }
+    }
+}
+
+fn default_icon_microphone() -> String {
+    String::from("󰍬")
+}
+
+fn default_icon_camera() -> String {
+    String::from("󰄀")
+}
+
+fn default_icon_screenshare() -> String {
+    String::from("󰍹")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Microphone,
+    Camera,
+    Screenshare,
+}
+
+impl Indicator {
+    const ALL: [Self; 3] = [Self::Microphone, Self::Camera, Self::Screenshare];
+
+    const fn class(self) -> &'static str {
+        match self {
+            Self::Microphone => "microphone",
+            Self::Camera => "camera",
+            Self::Screenshare => "screenshare",
+        }
+    }
+
+    const fn description(self) -> &'static str {
+        match self {
+            Self::Microphone => "Microphone",
+            Self::Camera => "Camera",
+            Self::Screenshare => "Screen",
+        }
+    }
+}
+
+/// The names of the applications using a device,
+/// which is in use if there are any.
+#[derive(Debug, Clone)]
+pub struct PrivacyEvent {
+    indicator: Indicator,
+    applications: Vec<String>,
+}
+
+/// Gets the names of processes with a video device open,
+/// by checking the open files of every process.
+///
+/// Only processes owned by the current user can be checked.
+/// When a camera is accessed through PipeWire, the PipeWire process is found.
+fn find_camera_users() -> Vec<String> {
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+
+    let mut applications = processes
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter(|entry| {
+            fs::read_dir(entry.path().join("fd")).is_ok_and(|files| {
+                files.flatten().any(|file| {
+                    fs::read_link(file.path())
+                        .is_ok_and(|target| target.to_string_lossy().starts_with("/dev/video"))
+                })
+            })
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+
+    applications.sort();
+    applications.dedup();
+    applications
+}
+
+/// Gets the names of applications receiving a screen cast.
+async fn find_screencast_users() -> Result<Vec<String>> {
+    let output = Command::new("pw-dump")
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(eyre!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        ));
+    }
+
+    let objects = serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout)?;
+    Ok(parse_screencasts(&objects))
+}
+
+/// Finds the applications linked to screen cast nodes in the output of `pw-dump`.
+///
+/// Portal backends create a video source node for each screen cast,
+/// which, unlike cameras, is not backed by a device.
+/// If nothing is linked to a screen cast yet, the name of its node is used instead.
+fn parse_screencasts(objects: &[serde_json::Value]) -> Vec<String> {
+    let get = |object: &serde_json::Value, key: &str| {
+        object
+            .pointer(&format!("/info/props/{key}"))
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    };
+
+    let nodes = objects
+        .iter()
+        .filter(|object| {
+            object.get("type").and_then(serde_json::Value::as_str)
+                == Some("PipeWire:Interface:Node")
+        })
+        .filter_map(|object| Some((object.get("id")?.as_u64()?, object)))
+        .collect::<HashMap<_, _>>();
+
+    let name = |node: &serde_json::Value| {
+        get(node, "application.name")
+            .or_else(|| get(node, "node.description"))
+            .or_else(|| get(node, "node.name"))
+            .unwrap_or_default()
+    };
+
+    let mut applications = vec![];
+
+    for (id, node) in &nodes {
+        let class = get(node, "media.class");
+        let is_screencast = matches!(
+            class.as_deref(),
+            Some("Video/Source" | "Stream/Output/Video")
+        ) && node.pointer("/info/props/device.id").is_none();
+
+        if !is_screencast {
+            continue;
+        }
+
+        let consumers = objects
+            .iter()
+            .filter(|object| {
+                object
+                    .pointer("/info/output-node-id")
+                    .and_then(serde_json::Value::as_u64)
+                    == Some(*id)
+            })
+            .filter_map(|link| link.pointer("/info/input-node-id")?.as_u64())
+            .filter_map(|id| nodes.get(&id).copied())
+            .map(name)
+            .collect::<Vec<_>>();
+
+        if consumers.is_empty() {
+            applications.push(name(node));
+        } else {
+            applications.extend(consumers);
+        }
+    }
+
+    applications.sort();
+    applications.dedup();
+    applications
+}
+
+impl Module<gtk::Box> for PrivacyModule {
+    type SendMessage = PrivacyEvent;
+    type ReceiveMessage = ();
+
+    module_impl!("privacy");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let ignore = self.ignore.clone();
+        let filter = move |mut applications: Vec<String>| {
+            applications.retain(|application| !ignore.contains(application));
+            applications
+        };
+
+        if self.microphone {
+            let client = context.client::<volume::Client>();
+            let mut rx = client.subscribe();
+            let tx = context.tx.clone();
+            let filter = filter.clone();
+
+            spawn(async move {
+                let mut last = None;
+
+                loop {
+                    let applications = filter(client.capturing_applications());
+                    if last.as_ref() != Some(&applications) {
+                        last = Some(applications.clone());
+
+                        let event = PrivacyEvent {
+                            indicator: Indicator::Microphone,
+                            applications,
+                        };
+                        send_async!(tx, ModuleUpdateEvent::Update(event));
+                    }
+
+                    match rx.recv().await {
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        if self.camera || self.screenshare {
+            let camera = self.camera;
+            let screenshare = self.screenshare;
+            let interval = Duration::from_secs(self.interval);
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut last_camera = None;
+                let mut last_screenshare = None;
+                let mut failed = false;
+
+                loop {
+                    let mut events = vec![];
+
+                    if camera {
+                        let applications =
+                            spawn_blocking(find_camera_users).await.unwrap_or_default();
+
+                        let applications = filter(applications);
+                        if last_camera.as_ref() != Some(&applications) {
+                            last_camera = Some(applications.clone());
+                            events.push((Indicator::Camera, applications));
+                        }
+                    }
+
+                    if screenshare {
+                        match find_screencast_users().await {
+                            Ok(applications) => {
+                                failed = false;
+
+                                let applications = filter(applications);
+                                if last_screenshare.as_ref() != Some(&applications) {
+                                    last_screenshare = Some(applications.clone());
+                                    events.push((Indicator::Screenshare, applications));
+                                }
+                            }
+                            // only warn once, rather than on every check
+                            Err(err) if !failed => {
+                                failed = true;
+                                warn!("Failed to check for screen sharing: {err:?}");
+                            }
+                            Err(err) => debug!("Failed to check for screen sharing: {err:?}"),
+                        }
+                    }
+
+                    for (indicator, applications) in events {
+                        let event = PrivacyEvent {
+                            indicator,
+                            applications,
+                        };
+                        send_async!(tx, ModuleUpdateEvent::Update(event));
+                    }
+
+                    polling::sleep(interval).await;
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+
+        let labels = Indicator::ALL.map(|indicator| {
+            let icon = match indicator {
+                Indicator::Microphone => &self.icons.microphone,
+                Indicator::Camera => &self.icons.camera,
+                Indicator::Screenshare => &self.icons.screenshare,
+            };
+
+            let label = Label::new(Some(icon));
+            label.add_class(indicator.class());
+            label.set_no_show_all(true);
+            container.add(&label);
+
+            (indicator, label)
+        });
+
+        {
+            let container = container.clone();
+
+            glib_recv!(context.subscribe(), event => {
+                let Some((_, label)) = labels
+                    .iter()
+                    .find(|(indicator, _)| *indicator == event.indicator)
+                else {
+                    continue;
+                };
+
+                let active = !event.applications.is_empty();
+                label.set_visible(active);
+
+                if active {
+                    let tooltip = format!(
+                        "{} in use by {}",
+                        event.indicator.description(),
+                        event.applications.join(", ")
+                    );
+                    label.set_tooltip_text(Some(&tooltip));
+                }
+
+                let any_active = labels.iter().any(|(_, label)| label.is_visible());
+                container.set_visible(any_active);
+
+                if any_active {
+                    container.add_class("active");
+                } else {
+                    container.remove_class("active");
+                }
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: container,
+            popup: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_screencasts() {
+        let camera = json!({
+            "id": 40,
+            "type": "PipeWire:Interface:Node",
+            "info": { "props": {
+                "media.class": "Video/Source",
+                "device.id": 38,
+                "node.name": "v4l2_input.pci-0000_00_14.0-usb-0_6_1.0"
+            } }
+        });
+
+        let screencast = json!({
+            "id": 90,
+            "type": "PipeWire:Interface:Node",
+            "info": { "props": { "media.class": "Video/Source", "node.name": "xdpw_stream" } }
+        });
+
+        let consumer = json!({
+            "id": 95,
+            "type": "PipeWire:Interface:Node",
+            "info": { "props": {
+                "media.class": "Stream/Input/Video",
+                "application.name": "Firefox"
+            } }
+        });
+
+        let link = json!({
+            "id": 96,
+            "type": "PipeWire:Interface:Link",
+            "info": { "output-node-id": 90, "input-node-id": 95 }
+        });
+
+        assert!(parse_screencasts(std::slice::from_ref(&camera)).is_empty());
+
+        assert_eq!(
+            parse_screencasts(&[camera.clone(), screencast.clone()]),
+            ["xdpw_stream"]
+        );
+
+        assert_eq!(
+            parse_screencasts(&[camera, screencast, consumer, link]),
+            ["Firefox"]
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::{Report, Result};
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProAudioModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{rate}Hz / {quantum}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The number of milliseconds between each refresh.
+    ///
+    /// Note that gathering statistics takes around a second,
+    /// so values lower than this have no effect.
+    ///
+    /// **Default**: `2000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The quantum (buffer size) values to offer in the popup.
+    ///
+    /// **Default**: `[64, 128, 256, 512, 1024, 2048]`
+    #[serde(default = "default_quantums")]
+    quantums: Vec<u32>,
+
+    /// The sample rates to offer in the popup.
+    ///
+    /// **Default**: `[44100, 48000, 88200, 96000]`
+    #[serde(default = "default_rates")]
+    rates: Vec<u32>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{rate}Hz / {quantum}")
+}
+
+const fn default_interval() -> u64 {
+    2000
+}
+
+fn default_quantums() -> Vec<u32> {
+    vec![64, 128, 256, 512, 1024, 2048]
+}
+
+fn default_rates() -> Vec<u32> {
+    vec![44100, 48000, 88200, 96000]
+}
+
+/// The state of the PipeWire graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioState {
+    /// Sample rate of the running driver.
+    rate: Option<u32>,
+    /// Quantum of the running driver.
+    quantum: Option<u32>,
+    /// Total xruns across all nodes.
+    xruns: u64,
+    /// Forced sample rate, or `0` if not forced.
+    force_rate: u32,
+    /// Forced quantum, or `0` if not forced.
+    force_quantum: u32,
+}
+
+impl AudioState {
+    async fn read() -> Result<Self> {
+        let settings = read_settings().await?;
+        let setting = |key: &str| -> u32 {
+            settings
+                .get(key)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default()
+        };
+
+        let mut state = read_graph().await?;
+        state.force_rate = setting("clock.force-rate");
+        state.force_quantum = setting("clock.force-quantum");
+
+        Ok(state)
+    }
+
+    /// Gets the latency of the current quantum, in milliseconds.
+    fn latency(&self) -> Option<f64> {
+        match (self.rate, self.quantum) {
+            (Some(rate), Some(quantum)) if rate > 0 => {
+                Some(f64::from(quantum) / f64::from(rate) * 1000.0)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ProAudioCommand {
+    /// Forces the quantum, or stops forcing it if `0`.
+    SetQuantum(u32),
+    /// Forces the sample rate, or stops forcing it if `0`.
+    SetRate(u32),
+}
+
+async fn run(command: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(command).args(args).output().await?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(Report::msg(format!(
+            "{command} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Reads the `settings` metadata, which controls the graph clock.
+async fn read_settings() -> Result<HashMap<String, String>> {
+    let output = run("pw-metadata", &["-n", "settings"]).await?;
+    Ok(parse_settings(&output))
+}
+
+/// Parses the output of `pw-metadata`.
+///
+/// Lines are in the form `update: id:0 key:'clock.rate' value:'48000' type:''`.
+fn parse_settings(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once("key:'")?;
+            let (key, rest) = rest.split_once('\'')?;
+            let (_, rest) = rest.split_once("value:'")?;
+            let (value, _) = rest.split_once('\'')?;
+
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Reads driver and xrun statistics from `pw-top`.
+async fn read_graph() -> Result<AudioState> {
+    // the first iteration does not include timing information
+    let output = run("pw-top", &["--batch-mode", "--iterations", "2"]).await?;
+    Ok(parse_graph(&output))
+}
+
+/// Parses the output of `pw-top --batch-mode`, using its last iteration.
+fn parse_graph(output: &str) -> AudioState {
+    let lines = output.lines().collect::<Vec<_>>();
+
+    // each iteration starts with a header line.
+    // this is found by its `ID` column, as suspended nodes also start with `S`.
+    let start = lines
+        .iter()
+        .rposition(|line| line.split_whitespace().nth(1) == Some("ID"))
+        .map_or(0, |header| header + 1);
+
+    let mut state = AudioState::default();
+
+    // columns are: S ID QUANT RATE WAIT BUSY W/Q B/Q ERR FORMAT NAME,
+    // where follower node names are prefixed with `+`.
+    for line in &lines[start..] {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() < 9 {
+            continue;
+        }
+
+        state.xruns += fields[8].parse::<u64>().unwrap_or_default();
+
+        let is_driver = !line.contains(" + ");
+        if is_driver && fields[0] == "R" && state.rate.is_none() {
+            state.quantum = fields[2].parse().ok().filter(|&quantum| quantum > 0);
+            state.rate = fields[3].parse().ok().filter(|&rate| rate > 0);
+        }
+    }
+
+    state
+}
+
+async fn set_setting(key: &str, value: u32) -> Result<()> {
+    run(
+        "pw-metadata",
+        &["-n", "settings", "0", key, &value.to_string()],
+    )
+    .await?;
+    Ok(())
+}
+
+impl Module<Button> for ProAudioModule {
+    type SendMessage = AudioState;
+    type ReceiveMessage = ProAudioCommand;
+
+    module_impl!("pro_audio");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut last = None;
+
+            loop {
+                match AudioState::read().await {
+                    Ok(state) if last.as_ref() != Some(&state) => {
+                        last = Some(state.clone());
+                        send_async!(tx, ModuleUpdateEvent::Update(state));
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("Failed to read PipeWire state: {err:?}"),
+                }
+
+                tokio::select! {
+                    () = polling::sleep(interval) => {}
+                    Some(command) = rx.recv() => {
+                        let res = match command {
+                            ProAudioCommand::SetQuantum(quantum) => {
+                                set_setting("clock.force-quantum", quantum).await
+                            }
+                            ProAudioCommand::SetRate(rate) => {
+                                set_setting("clock.force-rate", rate).await
+                            }
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to update PipeWire settings: {err:?}");
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, state => {
+                let rate = display_or_unknown(state.rate);
+                let quantum = display_or_unknown(state.quantum);
+                let latency = state
+                    .latency()
+                    .map_or_else(|| String::from("-"), |latency| format!("{latency:.1}"));
+
+                let text = format
+                    .replace("{rate}", &rate)
+                    .replace("{quantum}", &quantum)
+                    .replace("{latency}", &latency)
+                    .replace("{xruns}", &state.xruns.to_string());
+
+                label.set_label(&text);
+
+                if state.xruns > 0 {
+                    button.add_class("xruns");
+                } else {
+                    button.remove_class("xruns");
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let details = Label::new(None);
+        details.add_class("details");
+        container.add(&details);
+
+        let (quantum_box, quantum_buttons) = option_buttons(
+            "Quantum",
+            "quantum",
+            &self.quantums,
+            &tx,
+            ProAudioCommand::SetQuantum,
+        );
+        container.add(&quantum_box);
+
+        let (rate_box, rate_buttons) =
+            option_buttons("Rate", "rate", &self.rates, &tx, ProAudioCommand::SetRate);
+        container.add(&rate_box);
+
+        container.show_all();
+
+        glib_recv!(rx, state => {
+            let latency = state
+                .latency()
+                .map_or_else(String::new, |latency| format!(" ({latency:.1}ms)"));
+
+            details.set_label(&format!(
+                "{} Hz, {} samples{latency}\n{} xruns",
+                display_or_unknown(state.rate),
+                display_or_unknown(state.quantum),
+                state.xruns
+            ));
+
+            set_active(&quantum_buttons, state.force_quantum);
+            set_active(&rate_buttons, state.force_rate);
+        });
+
+        Some(container)
+    }
+}
+
+fn display_or_unknown(value: Option<u32>) -> String {
+    value.map_or_else(|| String::from("-"), |value| value.to_string())
+}
+
+/// Creates a row of buttons to force each of `values`,
+/// plus an `Auto` button to stop forcing.
+fn option_buttons(
+    title: &str,
+    class: &str,
+    values: &[u32],
+    tx: &mpsc::Sender<ProAudioCommand>,
+    command: fn(u32) -> ProAudioCommand,
+) -> (gtk::Box, Vec<(u32, Button)>) {
+    let container = gtk::Box::new(Orientation::Vertical, 0);
+    container.add_class(class);
+
+    let title = Label::new(Some(title));
+    title.add_class("title");
+    container.add(&title);
+
+    let buttons_box = gtk::Box::new(Orientation::Horizontal, 0);
+    buttons_box.add_class("options");
+    container.add(&buttons_box);
+
+    let mut buttons = vec![];
+
+    for value in std::iter::once(0).chain(values.iter().copied()) {
+        let label = if value == 0 {
+            String::from("Auto")
+        } else {
+            value.to_string()
+        };
+
+        let button = Button::with_label(&label);
+        button.add_class("option");
+
+        let tx = tx.clone();
+        button.connect_clicked(move |_| {
+            try_send!(tx, command(value));
+        });
+
+        buttons_box.add(&button);
+        buttons.push((value, button));
+    }
+
+    (container, buttons)
+}
+
+fn set_active(buttons: &[(u32, Button)], active: u32) {
+    for (value, button) in buttons {
+        if *value == active {
+            button.add_class("active");
+        } else {
+            button.remove_class("active");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        let output = "Found \"settings\" metadata 31\n\
+            update: id:0 key:'clock.rate' value:'48000' type:''\n\
+            update: id:0 key:'clock.force-quantum' value:'256' type:''\n";
+
+        let settings = parse_settings(output);
+        assert_eq!(settings.len(), 2);
+        assert_eq!(
+            settings.get("clock.rate").map(String::as_str),
+            Some("48000")
+        );
+        assert_eq!(
+            settings.get("clock.force-quantum").map(String::as_str),
+            Some("256")
+        );
+    }
+
+    #[test]
+    fn test_parse_graph() {
+        let iteration = |quantum: u32, errors: u64| {
+            format!(
+                "S   ID  QUANT   RATE    WAIT    BUSY   W/Q   B/Q  ERR FORMAT           NAME\n\
+                 R   30  {quantum:5}  48000  12.3us  45.6us  0.01  0.02    0    S32LE 2 48000 alsa_output.pci\n\
+                 R   45      0      0   5.0us  10.0us  0.00  0.00  {errors:3}    F32LE 2 48000  + Firefox\n\
+                 S   28      0      0    ---     ---   ---   ---     0                  Dummy-Driver\n"
+            )
+        };
+
+        let output = iteration(1024, 7) + &iteration(256, 3);
+
+        assert_eq!(
+            parse_graph(&output),
+            AudioState {
+                rate: Some(48000),
+                quantum: Some(256),
+                xruns: 3,
+                ..AudioState::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_latency() {
+        let state = AudioState {
+            rate: Some(48000),
+            quantum: Some(480),
+            ..AudioState::default()
+        };
+        assert_eq!(state.latency(), Some(10.0));
+
+        assert_eq!(AudioState::default().latency(), None);
+    }
+}
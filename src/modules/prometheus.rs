@@ -0,0 +1,413 @@
+use crate::config::{Alert, Alerts, CommonConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+#[cfg(feature = "history")]
+use crate::history;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Label;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Unit {
+    /// A plain number.
+    #[default]
+    None,
+    /// A percentage, followed by `%`.
+    Percent,
+    /// A number of bytes, scaled by powers of 1024, such as `1.5 GiB`.
+    Bytes,
+    /// A number scaled by powers of 1000, such as `1.5k`.
+    Si,
+    /// A number of seconds, shown as a duration such as `1h 30m`.
+    Seconds,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PrometheusModule {
+    /// The base URL of the Prometheus server,
+    /// such as `http://localhost:9090`.
+    ///
+    /// **Required**
+    url: String,
+
+    /// The PromQL expression to evaluate.
+    /// It must return a scalar or an instant vector,
+    /// in which case the first series is used.
+    ///
+    /// **Required**
+    query: String,
+
+    /// Headers to send with each request, such as for authentication.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    headers: HashMap<String, String>,
+
+    /// Number of milliseconds to wait between queries.
+    ///
+    /// **Default**: `30000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// Number to multiply the result by before it is displayed,
+    /// such as `100` to show a ratio as a percentage.
+    ///
+    /// **Default**: `1`
+    #[serde(default = "default_scale")]
+    scale: f64,
+
+    /// The unit of the result, used to format it.
+    ///
+    /// **Valid options**: `none`, `percent`, `bytes`, `si`, `seconds`
+    /// <br>
+    /// **Default**: `none`
+    #[serde(default)]
+    unit: Unit,
+
+    /// Number of decimal places to show.
+    ///
+    /// **Default**: `1`
+    #[serde(default = "default_precision")]
+    precision: usize,
+
+    /// The format string to use for the label.
+    /// `{value}` is replaced with the formatted result.
+    ///
+    /// **Default**: `{value}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the label when a query fails.
+    /// `{error}` is replaced with the error message.
+    ///
+    /// **Default**: `󰀦`
+    #[serde(default = "default_error_format")]
+    error_format: String,
+
+    /// Classes to add and scripts to run when the result crosses a threshold.
+    /// See [alerts](alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<Alert>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    30_000
+}
+
+const fn default_scale() -> f64 {
+    1.0
+}
+
+const fn default_precision() -> usize {
+    1
+}
+
+fn default_format() -> String {
+    String::from("{value}")
+}
+
+fn default_error_format() -> String {
+    String::from("󰀦")
+}
+
+#[derive(Debug, Clone)]
+pub enum QueryResult {
+    /// The scaled result of the query.
+    Value(f64),
+    /// The reason the query failed.
+    Error(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryResponse {
+    status: String,
+    error: Option<String>,
+    data: Option<QueryData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "resultType", content = "result", rename_all = "snake_case")]
+enum QueryData {
+    Scalar(Sample),
+    Vector(Vec<Series>),
+    // unsupported, but parsed to give a clearer error
+    #[allow(dead_code)]
+    Matrix(IgnoredAny),
+    #[allow(dead_code)]
+    String(IgnoredAny),
+}
+
+#[derive(Debug, Deserialize)]
+struct Series {
+    value: Sample,
+}
+
+/// A timestamp and value pair.
+/// Prometheus sends values as strings, to allow for `NaN` and infinity.
+type Sample = (f64, String);
+
+impl PrometheusModule {
+    /// Evaluates the query, returning its result.
+    async fn query(&self, http: &reqwest::Client) -> Result<f64> {
+        let mut request = http
+            .get(format!("{}/api/v1/query", self.url.trim_end_matches('/')))
+            .query(&[("query", &self.query)]);
+
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        // errors in the query are sent with a 400 status and a json body
+        let body = request.send().await?.text().await?;
+        let response = serde_json::from_str::<QueryResponse>(&body)?;
+
+        if response.status != "success" {
+            return Err(eyre!(
+                "{}",
+                response
+                    .error
+                    .unwrap_or_else(|| String::from("Query failed"))
+            ));
+        }
+
+        let sample = match response.data {
+            Some(QueryData::Scalar(sample)) => sample,
+            Some(QueryData::Vector(series)) => {
+                series
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| eyre!("Query returned no series"))?
+                    .value
+            }
+            Some(QueryData::Matrix(_) | QueryData::String(_)) => {
+                return Err(eyre!("Query must return a scalar or instant vector"))
+            }
+            None => return Err(eyre!("Query returned no data")),
+        };
+
+        Ok(sample.1.parse::<f64>()? * self.scale)
+    }
+
+    /// Formats a result using the configured unit and precision.
+    fn format_value(&self, value: f64) -> String {
+        let precision = self.precision;
+
+        match self.unit {
+            Unit::None => format!("{value:.precision$}"),
+            Unit::Percent => format!("{value:.precision$}%"),
+            Unit::Bytes => {
+                let (value, prefix) =
+                    scale_value(value, 1024.0, &["", "Ki", "Mi", "Gi", "Ti", "Pi"]);
+                format!("{value:.precision$} {prefix}B")
+            }
+            Unit::Si => {
+                let (value, prefix) = scale_value(value, 1000.0, &["", "k", "M", "G", "T", "P"]);
+                format!("{value:.precision$}{prefix}")
+            }
+            Unit::Seconds => format_duration(value),
+        }
+    }
+}
+
+impl Module<Label> for PrometheusModule {
+    type SendMessage = QueryResult;
+    type ReceiveMessage = ();
+
+    module_impl!("prometheus");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let module = self.clone();
+        let tx = context.tx.clone();
+
+        #[cfg(feature = "history")]
+        let history_name = context.name.clone();
+
+        // queries should never take longer than the interval
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(self.interval))
+            .build()?;
+
+        spawn(async move {
+            loop {
+                let result = match module.query(&http).await {
+                    Ok(value) => {
+                        #[cfg(feature = "history")]
+                        history::record(&history_name, value);
+
+                        QueryResult::Value(value)
+                    }
+                    Err(err) => {
+                        debug!("Prometheus query '{}' failed: {err:?}", module.query);
+                        QueryResult::Error(err.to_string())
+                    }
+                };
+
+                send_async!(tx, ModuleUpdateEvent::Update(result));
+                polling::sleep(Duration::from_millis(module.interval)).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Label>> {
+        let label = Label::builder().use_markup(true).build();
+
+        {
+            let label = label.clone();
+            let mut alerts = Alerts::new(self.alerts.clone());
+
+            glib_recv!(context.subscribe(), result => {
+                label.remove_class("error");
+
+                let text = match result {
+                    QueryResult::Value(value) => {
+                        label.set_tooltip_text(None);
+
+                        alerts.update(&label, value);
+
+                        self.format.replace("{value}", &self.format_value(value))
+                    }
+                    QueryResult::Error(err) => {
+                        label.add_class("error");
+                        label.set_tooltip_text(Some(&err));
+                        alerts.clear(&label);
+
+                        self.error_format.replace("{error}", &err)
+                    }
+                };
+
+                label.set_label_escaped(&text);
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: label,
+            popup: None,
+        })
+    }
+}
+
+/// Divides `value` by `base` until it is below `base`,
+/// returning the result and the matching prefix.
+fn scale_value(mut value: f64, base: f64, prefixes: &[&'static str]) -> (f64, &'static str) {
+    let mut prefix = prefixes[0];
+
+    for next in &prefixes[1..] {
+        if value.abs() < base {
+            break;
+        }
+
+        value /= base;
+        prefix = next;
+    }
+
+    (value, prefix)
+}
+
+/// Formats a number of seconds as its two largest units, such as `1h 30m`.
+fn format_duration(seconds: f64) -> String {
+    if !seconds.is_finite() {
+        return seconds.to_string();
+    }
+
+    let sign = if seconds < 0.0 { "-" } else { "" };
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let seconds = seconds.abs().round() as u64;
+
+    let units = [
+        (seconds / 86400, "d"),
+        (seconds / 3600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+
+    let parts = units
+        .iter()
+        .skip_while(|(amount, _)| *amount == 0)
+        .take(2)
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        String::from("0s")
+    } else {
+        format!("{sign}{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let body = r#"{"status":"success","data":{"resultType":"vector","result":[{"metric":{},"value":[1718000000.123,"0.25"]}]}}"#;
+        let response = serde_json::from_str::<QueryResponse>(body).expect("should parse vector");
+        assert!(
+            matches!(response.data, Some(QueryData::Vector(series)) if series[0].value.1 == "0.25")
+        );
+
+        let body = r#"{"status":"success","data":{"resultType":"matrix","result":[]}}"#;
+        let response = serde_json::from_str::<QueryResponse>(body).expect("should parse matrix");
+        assert!(matches!(response.data, Some(QueryData::Matrix(_))));
+
+        let body = r#"{"status":"error","errorType":"bad_data","error":"parse error"}"#;
+        let response = serde_json::from_str::<QueryResponse>(body).expect("should parse error");
+        assert_eq!(response.error.as_deref(), Some("parse error"));
+    }
+
+    #[test]
+    fn test_scale_value() {
+        let prefixes = ["", "Ki", "Mi"];
+
+        assert_eq!(scale_value(512.0, 1024.0, &prefixes), (512.0, ""));
+        assert_eq!(scale_value(1536.0, 1024.0, &prefixes), (1.5, "Ki"));
+        assert_eq!(scale_value(-2048.0, 1024.0, &prefixes), (-2.0, "Ki"));
+        // stops at the largest prefix
+        assert_eq!(
+            scale_value(1024.0 * 1024.0 * 1024.0, 1024.0, &prefixes),
+            (1024.0, "Mi")
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.0), "0s");
+        assert_eq!(format_duration(42.4), "42s");
+        assert_eq!(format_duration(5400.0), "1h 30m");
+        assert_eq!(format_duration(3601.0), "1h");
+        assert_eq!(format_duration(90061.0), "1d 1h");
+        assert_eq!(format_duration(-90.0), "-1m 30s");
+        assert_eq!(format_duration(f64::NAN), "NaN");
+    }
+}
@@ -0,0 +1,413 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::http::json_path;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::polling;
+use crate::script::{Script, ScriptInput};
+use crate::{glib_recv, module_impl, send_async, spawn};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PublicIpModule {
+    /// The URL to fetch the public IP address from.
+    ///
+    /// This can return either JSON, read using `ip_path` and `country_path`,
+    /// or the address as plain text.
+    ///
+    /// **Default**: `https://ipinfo.io/json`
+    #[serde(default = "default_url")]
+    url: String,
+
+    /// Headers to send with each request, such as for authentication.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    headers: HashMap<String, String>,
+
+    /// Path to the IP address in a JSON response.
+    ///
+    /// **Default**: `ip`
+    #[serde(default = "default_ip_path")]
+    ip_path: String,
+
+    /// Path to the country in a JSON response.
+    ///
+    /// **Default**: `country`
+    #[serde(default = "default_country_path")]
+    country_path: String,
+
+    /// The format string to use for the label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰩠 {ip}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the label when a request fails.
+    /// `{error}` is replaced with the error message.
+    ///
+    /// **Default**: `󰩠 󰀦`
+    #[serde(default = "default_error_format")]
+    error_format: String,
+
+    /// Whether the address starts masked.
+    /// Clicking the widget toggles this.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    masked: bool,
+
+    /// Number of milliseconds to wait between requests.
+    ///
+    /// **Default**: `300000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// Number of milliseconds to wait for a response before failing.
+    ///
+    /// **Default**: `10000`
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+
+    /// Number of milliseconds to keep the `.changed` class
+    /// after the address changes.
+    ///
+    /// **Default**: `60000`
+    #[serde(default = "default_changed_duration")]
+    changed_duration: u64,
+
+    /// A [script](scripts) to run when the address changes.
+    /// The new and previous addresses are passed as arguments.
+    ///
+    /// **Supported script types**: `oneshot`.
+    /// <br>
+    /// **Default**: `null`
+    on_change: Option<ScriptInput>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_url() -> String {
+    String::from("https://ipinfo.io/json")
+}
+
+fn default_ip_path() -> String {
+    String::from("ip")
+}
+
+fn default_country_path() -> String {
+    String::from("country")
+}
+
+fn default_format() -> String {
+    String::from("󰩠 {ip}")
+}
+
+fn default_error_format() -> String {
+    String::from("󰩠 󰀦")
+}
+
+const fn default_interval() -> u64 {
+    300_000
+}
+
+const fn default_timeout() -> u64 {
+    10_000
+}
+
+const fn default_changed_duration() -> u64 {
+    60_000
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    ip: String,
+    country: Option<String>,
+    /// The previous address, if it changed since the last request.
+    previous: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PublicIpEvent {
+    /// The address from a successful request.
+    Address(Address),
+    /// The reason a request failed.
+    Error(String),
+}
+
+impl PublicIpModule {
+    /// Requests the public address.
+    async fn fetch(&self, http: &reqwest::Client) -> Result<Address> {
+        let mut request = http.get(&self.url);
+
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let body = request.send().await?.error_for_status()?.text().await?;
+        self.parse(&body)
+    }
+
+    /// Reads the address and country from a response body.
+    /// Bodies which are not JSON are taken to be the address on its own.
+    fn parse(&self, body: &str) -> Result<Address> {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+            let ip = body.trim();
+            return if ip.is_empty() {
+                Err(eyre!("Response is empty"))
+            } else {
+                Ok(Address {
+                    ip: ip.to_string(),
+                    country: None,
+                    previous: None,
+                })
+            };
+        };
+
+        let ip = json_path(&json, &self.ip_path)
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| eyre!("Response has no address at '{}'", self.ip_path))?;
+
+        let country = json_path(&json, &self.country_path)
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+
+        Ok(Address {
+            ip: ip.to_string(),
+            country,
+            previous: None,
+        })
+    }
+}
+
+impl Address {
+    fn format(&self, format: &str, masked: bool) -> String {
+        let ip = if masked {
+            mask_ip(&self.ip)
+        } else {
+            self.ip.clone()
+        };
+
+        format
+            .replace("{ip}", &ip)
+            .replace("{country}", self.country.as_deref().unwrap_or_default())
+    }
+}
+
+/// Hides all but the first part of an address,
+/// such as `203.•.•.•` for an IPv4 address.
+fn mask_ip(ip: &str) -> String {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => format!("{}.•.•.•", ip.octets()[0]),
+        Ok(IpAddr::V6(ip)) => format!("{:x}:•••", ip.segments()[0]),
+        Err(_) => ip
+            .chars()
+            .map(|c| if c.is_alphanumeric() { '•' } else { c })
+            .collect(),
+    }
+}
+
+impl Module<Button> for PublicIpModule {
+    type SendMessage = PublicIpEvent;
+    type ReceiveMessage = ();
+
+    module_impl!("public_ip");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let module = self.clone();
+        let tx = context.tx.clone();
+
+        let on_change = self.on_change.clone().map(Script::new_polling);
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(self.timeout))
+            .build()?;
+
+        spawn(async move {
+            let mut current = None::<String>;
+
+            loop {
+                let event = match module.fetch(&http).await {
+                    Ok(mut address) => {
+                        // the first address is not a change
+                        if current.as_ref().is_some_and(|ip| ip != &address.ip) {
+                            info!(
+                                "Public IP address changed from {} to {}",
+                                current.as_deref().unwrap_or_default(),
+                                address.ip
+                            );
+
+                            if let Some(script) = &on_change {
+                                let args =
+                                    [address.ip.clone(), current.clone().unwrap_or_default()];
+                                script.run_as_oneshot(Some(&args));
+                            }
+
+                            address.previous = current.take();
+                        }
+
+                        current = Some(address.ip.clone());
+                        PublicIpEvent::Address(address)
+                    }
+                    Err(err) => {
+                        debug!("Request to '{}' failed: {err:?}", module.url);
+                        PublicIpEvent::Error(err.to_string())
+                    }
+                };
+
+                send_async!(tx, ModuleUpdateEvent::Update(event));
+                polling::sleep(Duration::from_millis(module.interval)).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        let masked = Rc::new(Cell::new(self.masked));
+        let latest = Rc::new(RefCell::new(None::<PublicIpEvent>));
+
+        let render = {
+            let button = button.clone();
+            let masked = masked.clone();
+            let latest = latest.clone();
+            let format = self.format.clone();
+            let error_format = self.error_format.clone();
+
+            move || {
+                let masked = masked.get();
+
+                if masked {
+                    button.add_class("masked");
+                } else {
+                    button.remove_class("masked");
+                }
+
+                match latest.borrow().as_ref() {
+                    Some(PublicIpEvent::Address(address)) => {
+                        button.remove_class("error");
+                        label.set_label_escaped(&address.format(&format, masked));
+
+                        let tooltip = if address.country.is_some() {
+                            address.format("{ip} ({country})", masked)
+                        } else {
+                            address.format("{ip}", masked)
+                        };
+                        button.set_tooltip_text(Some(&tooltip));
+                    }
+                    Some(PublicIpEvent::Error(err)) => {
+                        button.add_class("error");
+                        label.set_label_escaped(&error_format.replace("{error}", err));
+                        button.set_tooltip_text(Some(err));
+                    }
+                    None => {}
+                }
+            }
+        };
+
+        let render = Rc::new(render);
+
+        {
+            let render = render.clone();
+            button.connect_clicked(move |_| {
+                masked.set(!masked.get());
+                render();
+            });
+        }
+
+        {
+            let button = button.clone();
+            let changed_duration = self.changed_duration;
+            let pending = Rc::new(RefCell::new(None::<glib::SourceId>));
+
+            glib_recv!(context.subscribe(), event => {
+                if let PublicIpEvent::Address(Address { previous: Some(_), .. }) = &event {
+                    button.add_class("changed");
+
+                    let source = {
+                        let button = button.clone();
+                        let pending = pending.clone();
+
+                        glib::timeout_add_local_once(
+                            Duration::from_millis(changed_duration),
+                            move || {
+                                pending.take();
+                                button.remove_class("changed");
+                            },
+                        )
+                    };
+
+                    if let Some(previous) = pending.replace(Some(source)) {
+                        previous.remove();
+                    }
+                }
+
+                latest.replace(Some(event));
+                render();
+            });
+        }
+
+        Ok(ModuleParts {
+            widget: button,
+            popup: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_ip() {
+        assert_eq!(mask_ip("203.0.113.7"), "203.•.•.•");
+        assert_eq!(mask_ip("2001:db8::1"), "2001:•••");
+        assert_eq!(mask_ip("unknown"), "•••••••");
+    }
+
+    #[test]
+    fn test_parse() {
+        let module: PublicIpModule = serde_json::from_str("{}").unwrap();
+
+        let address = module
+            .parse(r#"{ "ip": "203.0.113.7", "country": "NL" }"#)
+            .unwrap();
+        assert_eq!(address.ip, "203.0.113.7");
+        assert_eq!(address.country.as_deref(), Some("NL"));
+
+        let address = module.parse("203.0.113.7\n").unwrap();
+        assert_eq!(address.ip, "203.0.113.7");
+        assert_eq!(address.country, None);
+
+        assert!(module.parse(r#"{ "query": "203.0.113.7" }"#).is_err());
+        assert!(module.parse("").is_err());
+    }
+}
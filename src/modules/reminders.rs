@@ -0,0 +1,610 @@
+use crate::clients::activity::Activity;
+use crate::clients::reminders::{self, Event, Reminder};
+use crate::clients::timer::{format_remaining, TimerState};
+use crate::clients::wayland;
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::script::{Script, ScriptInput};
+use crate::{glib_recv, module_impl, send_async, spawn, text, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, CheckButton, Label, Orientation};
+use serde::Deserialize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::interval;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RemindersModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰀠 {count}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for due times.
+    /// See [chrono](https://docs.rs/chrono/latest/chrono/format/strftime/index.html) for available tokens.
+    ///
+    /// **Default**: `%H:%M`
+    #[serde(default = "default_time_format")]
+    time_format: String,
+
+    /// Whether to show a desktop notification when a reminder is due.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    notify: bool,
+
+    /// A script to run when a reminder is due.
+    /// The reminder message is passed as the first argument.
+    ///
+    /// **Default**: `null`
+    on_due: Option<ScriptInput>,
+
+    /// Focus mode settings.
+    ///
+    /// See [focus mode](#focus-mode).
+    #[serde(default)]
+    focus: FocusConfig,
+
+    /// Daily activity statistics settings.
+    ///
+    /// See [statistics](#statistics).
+    #[serde(default)]
+    stats: StatsConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("󰀠 {count}")
+}
+
+fn default_time_format() -> String {
+    String::from("%H:%M")
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FocusConfig {
+    /// The length of a focus session started from the popup,
+    /// or using the `focus start` command without a duration.
+    /// Accepts the same values as the `remind` command.
+    ///
+    /// **Default**: `25m`
+    #[serde(default = "default_focus_duration")]
+    duration: String,
+
+    /// The format string to use for the widget button label
+    /// while a focus session is running.
+    /// This replaces the regular `format`.
+    ///
+    /// **Default**: `󰔛 {remaining}`
+    #[serde(default = "default_focus_format")]
+    format: String,
+
+    /// A script to run when a focus session starts.
+    /// This can be used to enable compositor window rules,
+    /// change the keyboard backlight, etc.
+    ///
+    /// **Default**: `null`
+    on_enter: Option<ScriptInput>,
+
+    /// A script to run when a focus session ends.
+    /// Either `completed` or `stopped` is passed as the first argument.
+    ///
+    /// **Default**: `null`
+    on_exit: Option<ScriptInput>,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            duration: default_focus_duration(),
+            format: default_focus_format(),
+            on_enter: None,
+            on_exit: None,
+        }
+    }
+}
+
+fn default_focus_duration() -> String {
+    String::from("25m")
+}
+
+fn default_focus_format() -> String {
+    String::from("󰔛 {remaining}")
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StatsConfig {
+    /// Whether to record daily activity statistics
+    /// and show a summary in the popup.
+    /// Statistics are only ever stored locally.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    enabled: bool,
+
+    /// How long without any input before you are counted as idle.
+    /// Accepts the same values as the `remind` command.
+    ///
+    /// **Default**: `5m`
+    #[serde(default = "default_idle_timeout")]
+    idle_timeout: String,
+
+    /// Whether to record how long each application is focused.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    apps: bool,
+
+    /// The maximum number of applications to list in the popup.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_top_apps")]
+    top_apps: usize,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_timeout: default_idle_timeout(),
+            apps: true,
+            top_apps: default_top_apps(),
+        }
+    }
+}
+
+fn default_idle_timeout() -> String {
+    String::from("5m")
+}
+
+const fn default_top_apps() -> usize {
+    5
+}
+
+#[derive(Debug, Clone)]
+pub struct ReminderState {
+    /// Upcoming reminders, sorted by due time.
+    reminders: Vec<Reminder>,
+    /// The current focus session, if there is one.
+    focus: Option<TimerState>,
+    /// Today's activity statistics, if enabled.
+    activity: Option<Activity>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    /// Cancels the reminder with the given ID.
+    Cancel(usize),
+    StartFocus,
+    StopFocus,
+    /// Pauses or resumes recording activity statistics.
+    SetTracking(bool),
+}
+
+/// Formats a number of seconds as `1h 05m`,
+/// or just minutes for less than an hour.
+fn format_duration(seconds: u64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+impl Module<Button> for RemindersModule {
+    type SendMessage = ReminderState;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("reminders");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.ironbar.clients.borrow_mut().reminders();
+
+        match reminders::parse_time(&self.focus.duration) {
+            Ok(duration) => client.set_focus_duration(duration),
+            Err(err) => error!("Invalid focus duration: {err:?}"),
+        }
+
+        if self.stats.enabled {
+            match reminders::parse_time(&self.stats.idle_timeout) {
+                Ok(idle_timeout) => client.start_tracking(
+                    context.client::<wayland::Client>(),
+                    idle_timeout,
+                    self.stats.apps,
+                ),
+                Err(err) => error!("Invalid idle timeout: {err:?}"),
+            }
+        }
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+            let notify = self.notify;
+            let script = self.on_due.clone().map(Script::new_polling);
+            let on_enter = self.focus.on_enter.clone().map(Script::new_polling);
+            let on_exit = self.focus.on_exit.clone().map(Script::new_polling);
+
+            spawn(async move {
+                let mut events = client.subscribe();
+
+                let mut state = ReminderState {
+                    reminders: client.reminders(),
+                    focus: client.focus(),
+                    activity: client.activity(),
+                };
+
+                send_async!(tx, ModuleUpdateEvent::Update(state.clone()));
+
+                // refreshes the remaining time while focusing
+                let mut ticker = interval(Duration::from_secs(1));
+
+                loop {
+                    let focusing = state.focus.as_ref().is_some_and(TimerState::is_running);
+
+                    let event = tokio::select! {
+                        event = events.recv() => match event {
+                            Ok(event) => event,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        },
+                        _ = ticker.tick(), if focusing => {
+                            send_async!(tx, ModuleUpdateEvent::Update(state.clone()));
+                            continue;
+                        }
+                    };
+
+                    match event {
+                        Event::Reminders(reminders) => {
+                            state.reminders = reminders;
+                        }
+                        Event::Due(reminder, handled) => {
+                            // another module has already handled this
+                            if handled.swap(true, Ordering::Relaxed) {
+                                continue;
+                            }
+
+                            if notify {
+                                if let Err(err) = reminders::notify(&reminder).await {
+                                    error!("Failed to send reminder notification: {err:?}");
+                                }
+                            }
+
+                            if let Some(script) = &script {
+                                script.run_as_oneshot(Some(&[reminder.message]));
+                            }
+
+                            continue;
+                        }
+                        Event::Focus(focus) => {
+                            state.focus = focus;
+                        }
+                        Event::FocusStarted(handled) => {
+                            if !handled.swap(true, Ordering::Relaxed) {
+                                if let Some(script) = &on_enter {
+                                    script.run_as_oneshot(None);
+                                }
+                            }
+
+                            continue;
+                        }
+                        Event::FocusEnded(completed, handled) => {
+                            if !handled.swap(true, Ordering::Relaxed) {
+                                if let Some(script) = &on_exit {
+                                    let reason = if completed { "completed" } else { "stopped" };
+                                    script.run_as_oneshot(Some(&[reason.to_string()]));
+                                }
+                            }
+
+                            continue;
+                        }
+                        Event::Activity(activity) => {
+                            state.activity = Some(activity);
+                        }
+                    }
+
+                    send_async!(tx, ModuleUpdateEvent::Update(state.clone()));
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::Cancel(id) => client.cancel(id),
+                    UiEvent::StartFocus => {
+                        client.start_focus(None);
+                    }
+                    UiEvent::StopFocus => client.stop_focus(),
+                    UiEvent::SetTracking(enabled) => client.set_tracking(enabled),
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let focus_format = self.focus.format.clone();
+            let time_format = self.time_format.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, state => {
+                let reminders = state.reminders;
+
+                let (next, next_time) = reminders.first().map_or_else(
+                    || (String::new(), String::new()),
+                    |reminder| {
+                        (
+                            reminder.message.clone(),
+                            reminder.due.format(&time_format).to_string(),
+                        )
+                    },
+                );
+
+                let text = if let Some(focus) = &state.focus {
+                    button.add_class("focus");
+                    focus_format.replace("{remaining}", &format_remaining(focus.remaining()))
+                } else {
+                    button.remove_class("focus");
+                    format.clone()
+                };
+
+                let text = text
+                    .replace("{count}", &reminders.len().to_string())
+                    .replace("{next}", &text::isolate(&glib::markup_escape_text(&next)))
+                    .replace("{next_time}", &next_time);
+
+                label.set_label(&text);
+
+                if reminders.is_empty() {
+                    button.remove_class("pending");
+                } else {
+                    button.add_class("pending");
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let focus_button = Button::new();
+        focus_button.add_class("focus");
+        container.add(&focus_button);
+
+        let reminders_container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add(&reminders_container);
+
+        let stats_container = gtk::Box::new(Orientation::Vertical, 5);
+        stats_container.add_class("stats");
+
+        if self.stats.enabled {
+            container.add(&stats_container);
+        }
+
+        let stats_title = Label::builder().label("Today").halign(Align::Start).build();
+        stats_title.add_class("title");
+        stats_container.add(&stats_title);
+
+        let stats_rows = gtk::Box::new(Orientation::Vertical, 5);
+        stats_container.add(&stats_rows);
+
+        let tracking_button = CheckButton::with_label("Record activity");
+        tracking_button.add_class("tracking");
+        stats_container.add(&tracking_button);
+
+        {
+            let tx = tx.clone();
+            tracking_button.connect_toggled(move |button| {
+                try_send!(tx, UiEvent::SetTracking(button.is_active()));
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            focus_button.connect_clicked(move |button| {
+                let event = if button.style_context().has_class("active") {
+                    UiEvent::StopFocus
+                } else {
+                    UiEvent::StartFocus
+                };
+
+                try_send!(tx, event);
+            });
+        }
+
+        let mut current = None;
+        let mut current_activity = None;
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, state => {
+                if let Some(focus) = &state.focus {
+                    let remaining = format_remaining(focus.remaining());
+                    focus_button.set_label(&format!("Stop focus ({remaining})"));
+                    focus_button.add_class("active");
+                } else {
+                    focus_button.set_label("Start focus");
+                    focus_button.remove_class("active");
+                }
+
+                if current_activity != state.activity {
+                    current_activity.clone_from(&state.activity);
+
+                    for child in stats_rows.children() {
+                        stats_rows.remove(&child);
+                    }
+
+                    if let Some(activity) = state.activity {
+                        // the client ignores this if it is unchanged
+                        tracking_button.set_active(!activity.paused);
+
+                        let today = &activity.today;
+                        let focus = format!(
+                            "{} sessions, {}",
+                            today.focus_sessions,
+                            format_duration(today.focus_time)
+                        );
+
+                        add_stat(&stats_rows, "active", "Active", &format_duration(today.active));
+                        add_stat(
+                            &stats_rows,
+                            "streak",
+                            "Longest streak",
+                            &format_duration(today.longest_streak),
+                        );
+                        add_stat(&stats_rows, "focus", "Focus", &focus);
+
+                        for (app, time) in today.top_apps(self.stats.top_apps) {
+                            add_stat(&stats_rows, "app", app, &format_duration(time));
+                        }
+
+                        stats_container.show_all();
+                    }
+                }
+
+                // focus updates arrive every second,
+                // so avoid rebuilding the list unless it changed.
+                if current.as_ref() == Some(&state.reminders) {
+                    continue;
+                }
+
+                let reminders = state.reminders;
+                current = Some(reminders.clone());
+
+                for child in reminders_container.children() {
+                    reminders_container.remove(&child);
+                }
+
+                if reminders.is_empty() {
+                    let label = Label::new(Some("No upcoming reminders"));
+                    label.add_class("empty");
+                    reminders_container.add(&label);
+                }
+
+                for reminder in reminders {
+                    let row = gtk::Box::new(Orientation::Horizontal, 10);
+                    row.add_class("reminder");
+
+                    let time = Label::new(Some(&reminder.due.format(&self.time_format).to_string()));
+                    time.add_class("time");
+
+                    let message = Label::builder()
+                        .label(&reminder.message)
+                        .halign(Align::Start)
+                        .hexpand(true)
+                        .build();
+                    message.add_class("message");
+
+                    let cancel = Button::with_label("Cancel");
+                    cancel.add_class("cancel");
+
+                    let tx = tx.clone();
+                    cancel.connect_clicked(move |_| {
+                        try_send!(tx, UiEvent::Cancel(reminder.id));
+                    });
+
+                    row.add(&time);
+                    row.add(&message);
+                    row.add(&cancel);
+                    reminders_container.add(&row);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Adds a row to the statistics section of the popup.
+fn add_stat(container: &gtk::Box, class: &str, name: &str, value: &str) {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_class("stat");
+    row.add_class(class);
+
+    let name = Label::builder()
+        .label(name)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    name.add_class("name");
+
+    let value = Label::new(Some(value));
+    value.add_class("value");
+
+    row.add(&name);
+    row.add(&value);
+    container.add(&row);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0m");
+        assert_eq!(format_duration(25 * 60 + 59), "25m");
+        assert_eq!(format_duration(3600 + 5 * 60), "1h 05m");
+    }
+}
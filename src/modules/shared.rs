@@ -0,0 +1,78 @@
+//! Helpers shared by modules which run commands from their popup.
+
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+use crate::popup::Popup;
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+use glib::Propagation;
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+use gtk::gdk::EventKey;
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+use gtk::prelude::*;
+#[cfg(any(feature = "palette", feature = "ssh"))]
+use gtk::ListBox;
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+use gtk::{ApplicationWindow, Widget};
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+use gtk_layer_shell::LayerShell;
+
+/// Wraps `value` in single quotes for use as a single shell argument.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// The command used to run programs in a terminal.
+/// Uses `$TERMINAL` if it is set, otherwise falls back to `xterm`.
+#[cfg(any(feature = "menu", feature = "ssh"))]
+pub fn default_terminal() -> String {
+    let terminal = std::env::var("TERMINAL").unwrap_or_else(|_| "xterm".to_string());
+    format!("{terminal} -e")
+}
+
+/// Calls `handler` for key presses on the popup window while `container` is shown,
+/// and releases the keyboard once it is hidden.
+///
+/// The popup window is shared with other modules,
+/// so key presses must be caught on the window itself,
+/// before they reach the focused widget.
+#[cfg(any(feature = "menu", feature = "palette", feature = "ssh"))]
+pub fn connect_popup_key_press<F>(popup: &Popup, container: &impl IsA<Widget>, handler: F)
+where
+    F: Fn(&ApplicationWindow, &EventKey) -> Propagation + 'static,
+{
+    {
+        let window = popup.window.clone();
+        container.connect_unmap(move |_| {
+            window.set_keyboard_interactivity(false);
+        });
+    }
+
+    let container = container.clone().upcast::<Widget>();
+    popup.window.connect_key_press_event(move |window, event| {
+        if container.is_mapped() {
+            handler(window, event)
+        } else {
+            Propagation::Proceed
+        }
+    });
+}
+
+/// Selects the next or previous row, wrapping around at either end.
+#[cfg(any(feature = "palette", feature = "ssh"))]
+pub fn move_selection(list: &ListBox, forward: bool) {
+    let count = i32::try_from(list.children().len()).unwrap_or_default();
+    if count == 0 {
+        return;
+    }
+
+    let current = list.selected_row().map_or(-1, |row| row.index());
+    let next = if forward {
+        (current + 1) % count
+    } else {
+        (current - 1).rem_euclid(count)
+    };
+
+    // focus stays in the search box, so that typing continues to filter
+    if let Some(row) = list.row_at_index(next) {
+        list.select_row(Some(&row));
+    }
+}
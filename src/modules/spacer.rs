@@ -0,0 +1,74 @@
+use crate::config::CommonConfig;
+use crate::module_impl;
+use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Orientation;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SpacerModule {
+    /// The size of the gap in pixels.
+    /// This is the width on horizontal bars, and the height on vertical bars.
+    ///
+    /// When `expand` is set, this is the minimum size.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_size")]
+    size: i32,
+
+    /// Whether to grow the spacer to fill all free space in its group,
+    /// pushing the modules either side of it apart.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    expand: bool,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_size() -> i32 {
+    10
+}
+
+impl Module<gtk::Box> for SpacerModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("spacer");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let orientation = info.bar_position.orientation();
+        let spacer = gtk::Box::new(orientation, 0);
+
+        if orientation == Orientation::Horizontal {
+            spacer.set_width_request(self.size);
+            spacer.set_hexpand(self.expand);
+        } else {
+            spacer.set_height_request(self.size);
+            spacer.set_vexpand(self.expand);
+        }
+
+        Ok(ModuleParts {
+            widget: spacer,
+            popup: None,
+        })
+    }
+}
@@ -0,0 +1,649 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::shared::{
+    connect_popup_key_press, default_terminal, move_selection, shell_quote,
+};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::script::Script;
+use crate::{module_impl, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::keys::constants as keys;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, ListBox, ListBoxRow, Orientation, SearchEntry};
+use gtk_layer_shell::LayerShell;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error, warn};
+
+/// Maximum depth of nested `Include` directives,
+/// which matches the limit used by OpenSSH.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SshModule {
+    /// The text to show on the widget button.
+    ///
+    /// **Default**: `󰣀`
+    #[serde(default = "default_label")]
+    label: String,
+
+    /// The path to the SSH config file to read hosts from.
+    /// Files added with `Include` are read too.
+    ///
+    /// **Default**: `~/.ssh/config`
+    #[serde(default = "default_config")]
+    config: PathBuf,
+
+    /// The command used to open a terminal, which `ssh <host>` is appended to.
+    /// Uses `$TERMINAL -e` if not set, falling back to `xterm -e`.
+    ///
+    /// **Default**: `null`
+    terminal: Option<String>,
+
+    /// The maximum number of hosts to list at once.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_label() -> String {
+    String::from("󰣀")
+}
+
+fn default_config() -> PathBuf {
+    PathBuf::from("~/.ssh/config")
+}
+
+const fn default_max_results() -> usize {
+    10
+}
+
+/// A host from the SSH config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Host {
+    /// The alias from the `Host` line, which is passed to `ssh`.
+    name: String,
+    hostname: Option<String>,
+    user: Option<String>,
+    port: Option<String>,
+}
+
+impl Host {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            hostname: None,
+            user: None,
+            port: None,
+        }
+    }
+
+    /// Gets the address the host connects to, such as `user@example.com:2222`,
+    /// if it is set in the config.
+    fn address(&self) -> Option<String> {
+        if self.hostname.is_none() && self.user.is_none() && self.port.is_none() {
+            return None;
+        }
+
+        let mut address = self.hostname.clone().unwrap_or_else(|| self.name.clone());
+
+        if let Some(user) = &self.user {
+            address = format!("{user}@{address}");
+        }
+
+        if let Some(port) = &self.port {
+            address = format!("{address}:{port}");
+        }
+
+        Some(address)
+    }
+
+    /// Checks whether the host name or address contains `query`, ignoring case.
+    fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+
+        self.name.to_lowercase().contains(&query)
+            || self
+                .hostname
+                .as_ref()
+                .is_some_and(|hostname| hostname.to_lowercase().contains(&query))
+    }
+}
+
+/// Splits a config line into its lowercase keyword and its arguments.
+/// Keywords and arguments may be separated by whitespace or `=`.
+fn split_line(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (keyword, args) = line
+        .split_once(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or((line, ""));
+
+    let args = args
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+        .trim();
+
+    Some((keyword.to_lowercase(), args))
+}
+
+/// Reads the hosts from the contents of an SSH config file, adding them to `hosts`.
+///
+/// Host patterns containing wildcards or negations are skipped,
+/// as they cannot be connected to directly.
+/// `include` is called with the arguments of each `Include` directive.
+fn parse_hosts(
+    contents: &str,
+    hosts: &mut Vec<Host>,
+    include: &mut dyn FnMut(&str, &mut Vec<Host>),
+) {
+    // indices of the hosts the current block applies to
+    let mut current = vec![];
+
+    for line in contents.lines() {
+        let Some((keyword, args)) = split_line(line) else {
+            continue;
+        };
+
+        let value = args.trim_matches('"').to_string();
+
+        match keyword.as_str() {
+            "host" => {
+                current.clear();
+
+                for name in args.split_whitespace().map(|name| name.trim_matches('"')) {
+                    if name.contains(['*', '?', '!']) {
+                        continue;
+                    }
+
+                    if let Some(index) = hosts.iter().position(|host| host.name == name) {
+                        current.push(index);
+                    } else {
+                        current.push(hosts.len());
+                        hosts.push(Host::new(name));
+                    }
+                }
+            }
+            "match" => current.clear(),
+            "include" => include(args, hosts),
+            // the first value set for an option is used
+            "hostname" | "user" | "port" => {
+                for &index in &current {
+                    let host = &mut hosts[index];
+                    let field = match keyword.as_str() {
+                        "hostname" => &mut host.hostname,
+                        "user" => &mut host.user,
+                        _ => &mut host.port,
+                    };
+
+                    field.get_or_insert_with(|| value.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads the hosts from an SSH config file and any files it includes.
+fn read_hosts(path: &Path, hosts: &mut Vec<Host>, depth: usize) {
+    if depth > MAX_INCLUDE_DEPTH {
+        warn!("Too many nested includes in '{}'", path.display());
+        return;
+    }
+
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            debug!("Failed to read SSH config '{}': {err:?}", path.display());
+            return;
+        }
+    };
+
+    // relative includes are in the user's ssh directory
+    let ssh_dir = dirs::home_dir().unwrap_or_default().join(".ssh");
+
+    parse_hosts(&contents, hosts, &mut |args, hosts| {
+        for pattern in args.split_whitespace() {
+            for path in expand_include(&ssh_dir.join(expand_home(Path::new(pattern)))) {
+                read_hosts(&path, hosts, depth + 1);
+            }
+        }
+    });
+}
+
+/// Gets the files matching an include path,
+/// which may contain wildcards in its file name.
+fn expand_include(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(pattern)) = (path.parent(), path.file_name()) else {
+        return vec![];
+    };
+
+    let pattern = pattern.to_string_lossy();
+    if !pattern.contains(['*', '?']) {
+        return vec![path.to_path_buf()];
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut paths = entries
+        .flatten()
+        .filter(|entry| wildcard_match(&pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    // includes are read in alphabetical order
+    paths.sort();
+    paths
+}
+
+/// Checks whether `text` matches a pattern,
+/// where `*` matches any number of characters and `?` matches one.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match (pattern.first(), text.first()) {
+            (None, None) => true,
+            (Some('*'), _) => {
+                matches(&pattern[1..], text) || (!text.is_empty() && matches(pattern, &text[1..]))
+            }
+            (Some('?'), Some(_)) => matches(&pattern[1..], &text[1..]),
+            (Some(p), Some(t)) if p == t => matches(&pattern[1..], &text[1..]),
+            _ => false,
+        }
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    matches(&pattern, &text)
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(path), Some(home)) => home.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// When each host was last connected to,
+/// persisted to the state directory.
+#[derive(Debug, Default)]
+struct History {
+    last_used: HashMap<String, u64>,
+}
+
+impl History {
+    fn load() -> Self {
+        let last_used = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(|line| {
+                        let (name, time) = line.split_once('\t')?;
+                        Some((name.to_string(), time.parse().ok()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { last_used }
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::state_dir().map(|dir| dir.join("ironbar").join("ssh_history"))
+    }
+
+    /// Records a connection to the host and writes the history to disk.
+    fn record(&mut self, name: &str) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        self.last_used.insert(name.to_string(), now);
+        self.save();
+    }
+
+    /// Sorts the most recently used hosts to the top.
+    /// Hosts which have not been used keep their order from the config.
+    fn sort(&self, hosts: &mut [Host]) {
+        hosts.sort_by_key(|host| {
+            std::cmp::Reverse(self.last_used.get(&host.name).copied().unwrap_or_default())
+        });
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("Failed to locate state directory, SSH history will not be saved");
+            return;
+        };
+
+        let contents = self
+            .last_used
+            .iter()
+            .map(|(name, time)| format!("{name}\t{time}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let res = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, contents));
+
+        if let Err(err) = res {
+            error!("Failed to save SSH history: {err:?}");
+        }
+    }
+}
+
+impl Module<Button> for SshModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("ssh");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::with_label(&self.label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        _rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let search = SearchEntry::new();
+        search.add_class("search");
+        container.add(&search);
+
+        let list = ListBox::new();
+        list.add_class("hosts");
+        container.add(&list);
+
+        let empty = Label::new(Some("No matching hosts"));
+        empty.add_class("empty");
+        container.add(&empty);
+
+        container.show_all();
+
+        let config = expand_home(&self.config);
+        let terminal = self.terminal.unwrap_or_else(default_terminal);
+        let max_results = self.max_results;
+
+        let history = Rc::new(RefCell::new(History::load()));
+
+        // every host from the config, most recently used first
+        let hosts = Rc::new(RefCell::new(Vec::<Host>::new()));
+
+        // the host shown in each row, by row index
+        let shown = Rc::new(RefCell::new(Vec::<Host>::new()));
+
+        let refresh = {
+            let list = list.clone();
+            let search = search.clone();
+            let hosts = hosts.clone();
+            let shown = shown.clone();
+
+            move || {
+                for child in list.children() {
+                    list.remove(&child);
+                }
+
+                let query = search.text();
+                let matches = hosts
+                    .borrow()
+                    .iter()
+                    .filter(|host| host.matches(&query))
+                    .take(max_results)
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                for host in &matches {
+                    list.add(&host_row(host));
+                }
+
+                list.show_all();
+                list.select_row(list.row_at_index(0).as_ref());
+                empty.set_visible(matches.is_empty());
+
+                *shown.borrow_mut() = matches;
+            }
+        };
+
+        let refresh = Rc::new(refresh);
+
+        {
+            let refresh = refresh.clone();
+            search.connect_search_changed(move |_| refresh());
+        }
+
+        let activate = {
+            let history = history.clone();
+            let tx = context.tx.clone();
+
+            move |row: &ListBoxRow| {
+                let host = usize::try_from(row.index())
+                    .ok()
+                    .and_then(|index| shown.borrow().get(index).cloned());
+
+                let Some(host) = host else {
+                    return;
+                };
+
+                try_send!(tx, ModuleUpdateEvent::ClosePopup);
+
+                debug!("Connecting to SSH host '{}'", host.name);
+                history.borrow_mut().record(&host.name);
+
+                let command = format!("{terminal} ssh {}", shell_quote(&host.name));
+                Script::from(command.as_str()).run_as_oneshot(None);
+            }
+        };
+
+        let activate = Rc::new(activate);
+
+        {
+            let activate = activate.clone();
+            list.connect_row_activated(move |_, row| activate(row));
+        }
+
+        // enter connects to the selected host
+        {
+            let list = list.clone();
+            search.connect_activate(move |_| {
+                if let Some(row) = list.selected_row() {
+                    activate(&row);
+                }
+            });
+        }
+
+        // the config is re-read each time the popup opens,
+        // so that changes to it are picked up.
+        {
+            let window = context.popup.window.clone();
+            let search = search.clone();
+
+            container.connect_map(move |_| {
+                window.set_keyboard_interactivity(true);
+
+                let mut new_hosts = vec![];
+                read_hosts(&config, &mut new_hosts, 0);
+                history.borrow().sort(&mut new_hosts);
+                *hosts.borrow_mut() = new_hosts;
+
+                search.set_text("");
+                refresh();
+                search.grab_focus();
+            });
+        }
+
+        // the arrow keys must be caught before they reach the search box.
+        {
+            let tx = context.tx.clone();
+
+            connect_popup_key_press(&context.popup, &container, move |_, event| {
+                match event.keyval() {
+                    keys::Up | keys::Down => {
+                        move_selection(&list, event.keyval() == keys::Down);
+                        Propagation::Stop
+                    }
+                    keys::Escape => {
+                        try_send!(tx, ModuleUpdateEvent::ClosePopup);
+                        Propagation::Stop
+                    }
+                    _ => Propagation::Proceed,
+                }
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for a host.
+fn host_row(host: &Host) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    row.add_class("host");
+
+    let container = gtk::Box::new(Orientation::Vertical, 2);
+
+    let name = Label::builder()
+        .label(&host.name)
+        .halign(Align::Start)
+        .build();
+    name.add_class("name");
+    container.add(&name);
+
+    if let Some(address) = host.address() {
+        let address = Label::builder().label(address).halign(Align::Start).build();
+        address.add_class("address");
+        container.add(&address);
+    }
+
+    row.add(&container);
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hosts() {
+        let config = "
+            # personal
+            Host server srv
+                HostName example.com
+                User jake
+                Port 2222
+
+            Host *.internal !bastion
+                User admin
+
+            Host=pi
+              hostname = raspberrypi.local
+              HostName ignored.local
+
+            Match host server
+                User other
+
+            Include config.d/*
+        ";
+
+        let mut hosts = vec![];
+        let mut includes = vec![];
+
+        parse_hosts(config, &mut hosts, &mut |args, _| {
+            includes.push(args.to_string());
+        });
+
+        let names = hosts
+            .iter()
+            .map(|host| host.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["server", "srv", "pi"]);
+
+        assert_eq!(hosts[0].address().as_deref(), Some("jake@example.com:2222"));
+        assert_eq!(hosts[2].address().as_deref(), Some("raspberrypi.local"));
+        assert_eq!(includes, ["config.d/*"]);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "work"));
+        assert!(wildcard_match("*.conf", "work.conf"));
+        assert!(wildcard_match("host?", "host1"));
+        assert!(!wildcard_match("*.conf", "work.conf.bak"));
+        assert!(!wildcard_match("host?", "host"));
+    }
+
+    #[test]
+    fn test_history_sort() {
+        let mut hosts = vec![Host::new("a"), Host::new("b"), Host::new("c")];
+
+        let history = History {
+            last_used: HashMap::from([(String::from("c"), 20), (String::from("b"), 10)]),
+        };
+
+        history.sort(&mut hosts);
+
+        let names = hosts
+            .iter()
+            .map(|host| host.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["c", "b", "a"]);
+    }
+}
@@ -1,7 +1,10 @@
-use crate::config::{CommonConfig, ModuleOrientation};
+use crate::config::{Alert, Alerts, CommonConfig, ModuleOrientation};
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::polling;
 use crate::{glib_recv, module_impl, send_async, spawn};
+#[cfg(feature = "history")]
+use crate::{write_lock, Ironbar};
 use color_eyre::Result;
 use gtk::prelude::*;
 use gtk::Label;
@@ -11,7 +14,6 @@ use std::collections::HashMap;
 use std::time::Duration;
 use sysinfo::{ComponentExt, CpuExt, DiskExt, NetworkExt, RefreshKind, System, SystemExt};
 use tokio::sync::mpsc;
-use tokio::time::sleep;
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -46,11 +48,31 @@ pub struct SysInfoModule {
     /// **Default** : `horizontal`
     direction: Option<ModuleOrientation>,
 
+    /// Classes to add and scripts to run when a token's value crosses a threshold.
+    /// See [alerts](#alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<SysInfoAlert>,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SysInfoAlert {
+    /// The token to check, without braces,
+    /// such as `cpu_percent` or `disk_percent:/`.
+    ///
+    /// **Required**
+    token: String,
+
+    #[serde(flatten)]
+    alert: Alert,
+}
+
 #[derive(Debug, Deserialize, Copy, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Intervals {
@@ -163,6 +185,21 @@ enum RefreshType {
     System,
 }
 
+#[cfg(feature = "history")]
+impl RefreshType {
+    /// Gets the prefixes of the tokens updated by this refresh.
+    const fn token_prefixes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Memory => &["memory_", "swap_"],
+            Self::Cpu => &["cpu_"],
+            Self::Temps => &["temp_"],
+            Self::Disks => &["disk_"],
+            Self::Network => &["net_"],
+            Self::System => &["load_average:"],
+        }
+    }
+}
+
 impl Module<gtk::Box> for SysInfoModule {
     type SendMessage = HashMap<String, String>;
     type ReceiveMessage = ();
@@ -194,7 +231,7 @@ impl Module<gtk::Box> for SysInfoModule {
                 spawn(async move {
                     loop {
                         send_async!(tx, $refresh_type);
-                        sleep(Duration::from_secs(interval.$func())).await;
+                        polling::sleep(Duration::from_secs(interval.$func())).await;
                     }
                 });
             }};
@@ -208,6 +245,10 @@ impl Module<gtk::Box> for SysInfoModule {
         spawn_refresh!(RefreshType::System, system);
 
         let tx = context.tx.clone();
+
+        #[cfg(feature = "history")]
+        let history_name = context.name.clone();
+
         spawn(async move {
             let mut format_info = HashMap::new();
 
@@ -223,6 +264,9 @@ impl Module<gtk::Box> for SysInfoModule {
                     RefreshType::System => refresh_system_tokens(&mut format_info, &sys),
                 };
 
+                #[cfg(feature = "history")]
+                record_history(&history_name, &refresh, &format_info);
+
                 send_async!(tx, ModuleUpdateEvent::Update(format_info.clone()));
             }
         });
@@ -256,6 +300,20 @@ impl Module<gtk::Box> for SysInfoModule {
             labels.push(label);
         }
 
+        // alerts apply to each label whose format contains their token
+        let mut alerts = vec![];
+        for (format, label) in self.format.iter().zip(&labels) {
+            for alert in &self.alerts {
+                if format.contains(&format!("{{{}}}", alert.token)) {
+                    alerts.push((
+                        label.clone(),
+                        alert.token.clone(),
+                        Alerts::new(vec![alert.alert.clone()]),
+                    ));
+                }
+            }
+        }
+
         {
             let formats = self.format;
             glib_recv!(context.subscribe(), info => {
@@ -268,6 +326,13 @@ impl Module<gtk::Box> for SysInfoModule {
 
                     label.set_label_escaped(format_compiled.as_ref());
                 }
+
+                for (label, token, alerts) in &mut alerts {
+                    let value = info.get(token.as_str()).and_then(|value| value.parse().ok());
+                    if let Some(value) = value {
+                        alerts.update(label, value);
+                    }
+                }
             });
         }
 
@@ -435,6 +500,29 @@ fn refresh_system_tokens(format_info: &mut HashMap<String, String>, sys: &System
     );
 }
 
+/// Records the numeric tokens updated by a refresh,
+/// as series named `<module>.<token>`.
+#[cfg(feature = "history")]
+fn record_history(name: &str, refresh: &RefreshType, format_info: &HashMap<String, String>) {
+    let history = Ironbar::history();
+    let mut history = write_lock!(history);
+    if !history.is_enabled() {
+        return;
+    }
+
+    let prefixes = refresh.token_prefixes();
+
+    for (token, value) in format_info {
+        if !prefixes.iter().any(|prefix| token.starts_with(prefix)) {
+            continue;
+        }
+
+        if let Ok(value) = value.parse() {
+            history.record(&format!("{name}.{token}"), value);
+        }
+    }
+}
+
 /// Converts celsius to fahrenheit.
 fn c_to_f(c: f32) -> f32 {
     c * 9.0 / 5.0 + 32.0
@@ -0,0 +1,435 @@
+use crate::clients::systemd::{ManagerProxy, UnitProxy};
+use crate::config::{default_true, CommonConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+use zbus::Connection;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum UnitConfig {
+    /// The name of a system unit.
+    Name(String),
+    Detailed {
+        /// The name of the unit, such as `docker.service`.
+        name: String,
+        /// Whether this is a user unit rather than a system unit.
+        ///
+        /// **Default**: `false`
+        #[serde(default)]
+        user: bool,
+        /// The name to show in the popup.
+        /// If not set, the unit's description is used.
+        ///
+        /// **Default**: `null`
+        label: Option<String>,
+    },
+}
+
+impl UnitConfig {
+    fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Detailed { name, .. } => name,
+        }
+    }
+
+    const fn user(&self) -> bool {
+        match self {
+            Self::Name(_) => false,
+            Self::Detailed { user, .. } => *user,
+        }
+    }
+
+    fn label(&self) -> Option<&str> {
+        match self {
+            Self::Name(_) => None,
+            Self::Detailed { label, .. } => label.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SystemdModule {
+    /// The units to monitor.
+    /// Each is either a system unit name, or an object.
+    /// See [units](#units).
+    ///
+    /// **Required**
+    units: Vec<UnitConfig>,
+
+    /// The format string to use for the widget button label
+    /// while no units have failed.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰒓`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the widget button label
+    /// while any unit has failed.
+    ///
+    /// **Default**: `󰒓 {failed}`
+    #[serde(default = "default_format_failed")]
+    format_failed: String,
+
+    /// Whether to show start, stop and restart buttons for each unit in the popup.
+    /// Controlling system units may prompt for a password through your polkit agent.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    controls: bool,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("󰒓")
+}
+
+fn default_format_failed() -> String {
+    String::from("󰒓 {failed}")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitState {
+    name: String,
+    label: String,
+    /// The unit's active state, such as `active` or `failed`,
+    /// or `unknown` if it could not be read.
+    state: String,
+}
+
+impl UnitState {
+    fn is_active(&self) -> bool {
+        matches!(self.state.as_str(), "active" | "reloading" | "activating")
+    }
+
+    fn is_failed(&self) -> bool {
+        self.state == "failed"
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Start,
+    Stop,
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UiEvent {
+    /// The index of the unit in the config.
+    unit: usize,
+    action: Action,
+}
+
+/// Connects to the system or user service manager,
+/// subscribing to unit changes.
+async fn connect(user: bool) -> Result<ManagerProxy<'static>> {
+    let connection = if user {
+        Connection::session().await?
+    } else {
+        Connection::system().await?
+    };
+
+    let manager = ManagerProxy::new(&connection).await?;
+    manager.subscribe().await?;
+
+    Ok(manager)
+}
+
+/// Loads a unit, and spawns a task to send its changes to `tx`.
+async fn watch_unit(
+    manager: &ManagerProxy<'static>,
+    unit: &UnitConfig,
+    index: usize,
+    tx: mpsc::Sender<(usize, String)>,
+) -> Result<UnitState> {
+    let path = manager.load_unit(unit.name()).await?;
+    let proxy = UnitProxy::builder(manager.connection())
+        .path(path)?
+        .build()
+        .await?;
+
+    let label = match unit.label() {
+        Some(label) => label.to_string(),
+        None => proxy
+            .description()
+            .await
+            .ok()
+            .filter(|description| !description.is_empty())
+            .unwrap_or_else(|| unit.name().to_string()),
+    };
+
+    let state = UnitState {
+        name: unit.name().to_string(),
+        label,
+        state: proxy.active_state().await?,
+    };
+
+    spawn(async move {
+        let mut changes = proxy.receive_active_state_changed().await;
+
+        while let Some(change) = changes.next().await {
+            if let Ok(state) = change.get().await {
+                send_async!(tx, (index, state));
+            }
+        }
+    });
+
+    Ok(state)
+}
+
+impl Module<Button> for SystemdModule {
+    type SendMessage = Snapshot<Vec<UnitState>>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("systemd");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let units = self.units.clone();
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut system = None;
+            let mut user = None;
+
+            for unit in &units {
+                let manager = if unit.user() { &mut user } else { &mut system };
+                if manager.is_none() {
+                    match connect(unit.user()).await {
+                        Ok(proxy) => *manager = Some(proxy),
+                        Err(err) => error!("Failed to connect to systemd: {err:?}"),
+                    }
+                }
+            }
+
+            let manager = |unit: &UnitConfig| {
+                if unit.user() {
+                    user.as_ref()
+                } else {
+                    system.as_ref()
+                }
+            };
+
+            let (state_tx, mut state_rx) = mpsc::channel(16);
+            let mut states = Vec::with_capacity(units.len());
+
+            for (index, unit) in units.iter().enumerate() {
+                let state = match manager(unit) {
+                    Some(manager) => watch_unit(manager, unit, index, state_tx.clone()).await,
+                    None => Err(eyre!("Not connected to systemd")),
+                };
+
+                states.push(state.unwrap_or_else(|err| {
+                    error!("Failed to load unit '{}': {err:?}", unit.name());
+                    UnitState {
+                        name: unit.name().to_string(),
+                        label: unit.label().unwrap_or(unit.name()).to_string(),
+                        state: String::from("unknown"),
+                    }
+                }));
+            }
+
+            send_async!(tx, ModuleUpdateEvent::snapshot(states.clone()));
+
+            loop {
+                tokio::select! {
+                    Some((index, state)) = state_rx.recv() => {
+                        debug!("Unit '{}' is now {state}", states[index].name);
+                        states[index].state = state;
+                        send_async!(tx, ModuleUpdateEvent::snapshot(states.clone()));
+                    }
+                    Some(event) = rx.recv() => {
+                        let unit = &units[event.unit];
+                        let Some(manager) = manager(unit) else {
+                            continue;
+                        };
+
+                        let name = unit.name();
+                        let res = match event.action {
+                            Action::Start => manager.start_unit(name, "replace").await,
+                            Action::Stop => manager.stop_unit(name, "replace").await,
+                            Action::Restart => manager.restart_unit(name, "replace").await,
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to {:?} unit '{name}': {err:?}", event.action);
+                        }
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let format_failed = self.format_failed.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, units => {
+                let failed = units.iter().filter(|unit| unit.is_failed()).count();
+                let active = units.iter().filter(|unit| unit.is_active()).count();
+
+                let format = if failed > 0 {
+                    button.remove_class("ok");
+                    button.add_class("failed");
+                    &format_failed
+                } else {
+                    button.remove_class("failed");
+                    button.add_class("ok");
+                    &format
+                };
+
+                label.set_label_escaped(
+                    &format
+                        .replace("{failed}", &failed.to_string())
+                        .replace("{active}", &active.to_string())
+                        .replace("{total}", &units.len().to_string()),
+                );
+
+                let tooltip = units
+                    .iter()
+                    .map(|unit| format!("{}: {}", unit.label, unit.state))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                button.set_tooltip_text(Some(&tooltip));
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, units => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                for (index, unit) in units.iter().enumerate() {
+                    container.add(&unit_row(index, unit, self.controls, &tx));
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for a unit.
+fn unit_row(
+    index: usize,
+    unit: &UnitState,
+    controls: bool,
+    tx: &mpsc::Sender<UiEvent>,
+) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_class("unit");
+    row.add_class(&unit.state);
+    row.set_tooltip_text(Some(&unit.name));
+
+    let name = Label::builder()
+        .label(&unit.label)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    name.add_class("name");
+    row.add(&name);
+
+    let state = Label::new(Some(&unit.state));
+    state.add_class("state");
+    row.add(&state);
+
+    if controls {
+        let active = unit.is_active();
+
+        for (action, text, class, sensitive) in [
+            (Action::Start, "Start", "start", !active),
+            (Action::Stop, "Stop", "stop", active),
+            (Action::Restart, "Restart", "restart", active),
+        ] {
+            let button = Button::with_label(text);
+            button.add_class(class);
+            button.set_sensitive(sensitive);
+
+            let tx = tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(
+                    tx,
+                    UiEvent {
+                        unit: index,
+                        action
+                    }
+                );
+            });
+
+            row.add(&button);
+        }
+    }
+
+    row
+}
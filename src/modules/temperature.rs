@@ -0,0 +1,458 @@
+use crate::config::{Alert, Alerts, CommonConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::{glib_recv, module_impl, polling, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::warn;
+
+const HWMON_DIR: &str = "/sys/class/hwmon";
+
+/// Chips used when no sensor is configured, in order of preference.
+/// These report the CPU temperature.
+const DEFAULT_CHIPS: &[&str] = &["k10temp", "zenpower", "coretemp", "cpu_thermal", "acpitz"];
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TemperatureModule {
+    /// The sensor to show on the bar, as `chip/label`, such as `k10temp/Tctl`.
+    /// Only the chip can be given to use its first sensor.
+    /// The popup lists all sensors with their chip and label.
+    ///
+    /// If not set, a CPU sensor is used where one is found.
+    ///
+    /// **Default**: `null`
+    sensor: Option<String>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰔏 {temp_c}°C`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Classes to add and scripts to run when the temperature in celsius crosses a threshold.
+    /// See [alerts](alerts).
+    ///
+    /// If not set, the `warning` class is added above the sensor's own maximum
+    /// and the `critical` class above its own critical temperature,
+    /// if it reports them.
+    ///
+    /// **Default**: `null`
+    alerts: Option<Vec<Alert>>,
+
+    /// The number of milliseconds between reading the sensors.
+    ///
+    /// **Default**: `5000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("󰔏 {temp_c}°C")
+}
+
+const fn default_interval() -> u64 {
+    5000
+}
+
+/// A single hwmon temperature sensor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensor {
+    /// The name of the chip the sensor belongs to, such as `k10temp`.
+    chip: String,
+    /// The sensor's label, or its input name, such as `temp1`, if it has no label.
+    label: String,
+    /// The temperature in celsius.
+    temp: f64,
+    /// The maximum temperature reported by the driver, in celsius.
+    max: Option<f64>,
+    /// The critical temperature reported by the driver, in celsius.
+    crit: Option<f64>,
+}
+
+impl Sensor {
+    /// Gets the alerts used when none are configured,
+    /// based on the limits reported by the driver.
+    fn default_alerts(&self) -> Vec<Alert> {
+        [(self.max, "warning"), (self.crit, "critical")]
+            .into_iter()
+            .filter_map(|(limit, class)| limit.map(|limit| Alert::above(limit, class)))
+            .collect()
+    }
+
+    /// Gets the classes of the alerts which are active for the sensor,
+    /// falling back to its default alerts if `alerts` is not set.
+    fn alert_classes(&self, alerts: Option<&[Alert]>) -> Vec<String> {
+        let defaults = self.default_alerts();
+
+        alerts
+            .unwrap_or(&defaults)
+            .iter()
+            .filter(|alert| alert.is_active(self.temp))
+            .filter_map(Alert::class)
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn format(&self, format: &str) -> String {
+        format
+            .replace("{temp_c}", &format!("{:.0}", self.temp))
+            .replace("{temp_f}", &format!("{:.0}", c_to_f(self.temp)))
+            .replace("{chip}", &self.chip)
+            .replace("{label}", &self.label)
+    }
+}
+
+/// Finds the sensor to show on the bar.
+///
+/// `selector` is either a chip name, or `chip/label`.
+/// Both are matched case-insensitively.
+fn select<'a>(sensors: &'a [Sensor], selector: Option<&str>) -> Option<&'a Sensor> {
+    let Some(selector) = selector else {
+        return DEFAULT_CHIPS
+            .iter()
+            .find_map(|chip| sensors.iter().find(|sensor| sensor.chip == *chip))
+            .or_else(|| sensors.first());
+    };
+
+    let (chip, label) = selector
+        .split_once('/')
+        .map_or((selector, None), |(chip, label)| (chip, Some(label)));
+
+    sensors.iter().find(|sensor| {
+        sensor.chip.eq_ignore_ascii_case(chip)
+            && label.is_none_or(|label| sensor.label.eq_ignore_ascii_case(label))
+    })
+}
+
+/// Reads every temperature sensor exposed through hwmon,
+/// sorted by chip then sensor number.
+fn read_sensors() -> Vec<Sensor> {
+    let Ok(entries) = fs::read_dir(HWMON_DIR) else {
+        return vec![];
+    };
+
+    let mut chips = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let number = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("hwmon")?
+                .parse::<u32>()
+                .ok()?;
+
+            Some((number, entry.path()))
+        })
+        .collect::<Vec<_>>();
+
+    chips.sort_by_key(|(number, _)| *number);
+
+    chips.iter().flat_map(|(_, path)| read_chip(path)).collect()
+}
+
+/// Reads the temperature sensors of a single hwmon chip.
+fn read_chip(path: &Path) -> Vec<Sensor> {
+    let Some(chip) = read_string(&path.join("name")) else {
+        return vec![];
+    };
+
+    let Ok(entries) = fs::read_dir(path) else {
+        return vec![];
+    };
+
+    let mut inputs = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let number = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("temp")?
+                .strip_suffix("_input")?
+                .parse::<u32>()
+                .ok()?;
+
+            Some(number)
+        })
+        .collect::<Vec<_>>();
+
+    inputs.sort_unstable();
+
+    // values are reported in millidegrees
+    let read_temp = |number: u32, name: &str| {
+        read_string(&path.join(format!("temp{number}_{name}")))
+            .and_then(|value| value.parse::<f64>().ok())
+            .map(|value| value / 1000.0)
+    };
+
+    inputs
+        .into_iter()
+        .filter_map(|number| {
+            Some(Sensor {
+                chip: chip.clone(),
+                label: read_string(&path.join(format!("temp{number}_label")))
+                    .unwrap_or_else(|| format!("temp{number}")),
+                temp: read_temp(number, "input")?,
+                // drivers without a real limit sometimes report zero
+                max: read_temp(number, "max").filter(|max| *max > 0.0),
+                crit: read_temp(number, "crit").filter(|crit| *crit > 0.0),
+            })
+        })
+        .collect()
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Converts celsius to fahrenheit.
+fn c_to_f(c: f64) -> f64 {
+    c * 9.0 / 5.0 + 32.0
+}
+
+impl Module<Button> for TemperatureModule {
+    type SendMessage = Snapshot<Vec<Sensor>>;
+    type ReceiveMessage = ();
+
+    module_impl!("temperature");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut previous = None;
+
+            loop {
+                let sensors = read_sensors();
+
+                if sensors.is_empty() && previous.is_none() {
+                    warn!("No temperature sensors found");
+                }
+
+                // readings often stay the same between polls
+                if previous.as_ref() != Some(&sensors) {
+                    send_async!(tx, ModuleUpdateEvent::snapshot(sensors.clone()));
+                    previous = Some(sensors);
+                }
+
+                polling::sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let sensor = self.sensor.clone();
+            let format = self.format.clone();
+            let configured = self.alerts.is_some();
+            let mut alerts = Alerts::new(self.alerts.clone().unwrap_or_default());
+
+            // the default alerts depend on the limits of the selected sensor
+            let mut limits = None;
+
+            let rx = context.subscribe();
+            glib_recv!(rx, sensors => {
+                let Some(sensor) = select(&sensors, sensor.as_deref()) else {
+                    button.hide();
+                    continue;
+                };
+
+                label.set_label_escaped(&sensor.format(&format));
+                button.set_tooltip_text(Some(&format!("{}/{}", sensor.chip, sensor.label)));
+
+                if !configured && limits != Some((sensor.max, sensor.crit)) {
+                    alerts.clear(&button);
+                    alerts = Alerts::new(sensor.default_alerts());
+                    limits = Some((sensor.max, sensor.crit));
+                }
+
+                alerts.update(&button, sensor.temp);
+
+                button.show();
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, sensors => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                if sensors.is_empty() {
+                    let label = Label::new(Some("No sensors found"));
+                    label.add_class("empty");
+                    container.add(&label);
+                }
+
+                let mut chip = None;
+
+                for sensor in sensors.iter() {
+                    // sensors are sorted by chip, so each chip gets one header
+                    if chip != Some(&sensor.chip) {
+                        let header = Label::builder()
+                            .label(&sensor.chip)
+                            .halign(Align::Start)
+                            .build();
+                        header.add_class("chip");
+                        container.add(&header);
+
+                        chip = Some(&sensor.chip);
+                    }
+
+                    container.add(&sensor_row(sensor, self.alerts.as_deref()));
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+/// Creates the popup row for a sensor.
+fn sensor_row(sensor: &Sensor, alerts: Option<&[Alert]>) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_class("sensor");
+
+    for class in sensor.alert_classes(alerts) {
+        row.add_class(&class);
+    }
+
+    let label = Label::builder()
+        .label(&sensor.label)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    label.add_class("name");
+    row.add(&label);
+
+    let temp = Label::new(Some(&format!("{:.0}°C", sensor.temp)));
+    temp.add_class("temp");
+    row.add(&temp);
+
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(chip: &str, label: &str, temp: f64) -> Sensor {
+        Sensor {
+            chip: chip.to_string(),
+            label: label.to_string(),
+            temp,
+            max: None,
+            crit: Some(95.0),
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let sensors = [
+            sensor("nvme", "Composite", 40.0),
+            sensor("k10temp", "Tctl", 55.0),
+            sensor("k10temp", "Tccd1", 50.0),
+        ];
+
+        let label = |selector| select(&sensors, selector).map(|sensor| sensor.label.as_str());
+
+        assert_eq!(label(None), Some("Tctl"));
+        assert_eq!(label(Some("nvme")), Some("Composite"));
+        assert_eq!(label(Some("k10temp/tccd1")), Some("Tccd1"));
+        assert_eq!(label(Some("coretemp")), None);
+    }
+
+    #[test]
+    fn test_alert_classes() {
+        let sensor = Sensor {
+            max: Some(80.0),
+            ..sensor("k10temp", "Tctl", 85.0)
+        };
+
+        assert_eq!(sensor.alert_classes(None), vec!["warning"]);
+        assert_eq!(
+            Sensor {
+                temp: 96.0,
+                ..sensor.clone()
+            }
+            .alert_classes(None),
+            vec!["warning", "critical"]
+        );
+
+        let alerts = [Alert::above(90.0, "hot")];
+        assert!(sensor.alert_classes(Some(&alerts)).is_empty());
+        assert!(Sensor {
+            crit: None,
+            max: None,
+            ..sensor
+        }
+        .alert_classes(None)
+        .is_empty());
+    }
+}
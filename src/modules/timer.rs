@@ -0,0 +1,262 @@
+use crate::clients::timer::{self, format_remaining, Event, TimerState};
+use crate::config::{default_false, default_true, parse_duration, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::script::{Script, ScriptInput};
+use crate::{glib_recv, module_impl, send_async, spawn, text, try_send};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::gdk::{BUTTON_PRIMARY, BUTTON_SECONDARY};
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use serde::Deserialize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::interval;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TimerModule {
+    /// The format string to use for the widget button label
+    /// while the timer is running or paused.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰔛 {remaining}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the widget button label
+    /// while the timer is stopped.
+    ///
+    /// **Default**: `󰔛`
+    #[serde(default = "default_format_idle")]
+    format_idle: String,
+
+    /// The length of a work phase.
+    /// Either a duration such as `25m` or `1h30m`, or a number of minutes.
+    ///
+    /// **Default**: `25m`
+    #[serde(default = "default_work_duration")]
+    work_duration: String,
+
+    /// The length of a break phase.
+    /// Accepts the same values as `work_duration`.
+    ///
+    /// **Default**: `5m`
+    #[serde(default = "default_break_duration")]
+    break_duration: String,
+
+    /// Whether to start the next phase automatically when one ends.
+    /// If disabled, the next phase waits, paused, until the widget is clicked.
+    ///
+    /// **Default**: `false`
+    #[serde(default = "default_false")]
+    auto_continue: bool,
+
+    /// Whether to show a desktop notification when a phase ends.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    notify: bool,
+
+    /// A script to run when a phase ends.
+    /// The phase name (`work`, `break`, `focus` or `countdown`) is passed as the first argument.
+    ///
+    /// **Default**: `null`
+    on_finish: Option<ScriptInput>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("󰔛 {remaining}")
+}
+
+fn default_format_idle() -> String {
+    String::from("󰔛")
+}
+
+fn default_work_duration() -> String {
+    String::from("25m")
+}
+
+fn default_break_duration() -> String {
+    String::from("5m")
+}
+
+#[derive(Debug, Clone)]
+pub enum TimerUpdate {
+    State(Option<TimerState>),
+    /// A phase has ended.
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    /// Starts, pauses or resumes the timer.
+    Toggle,
+    Stop,
+}
+
+impl Module<Button> for TimerModule {
+    type SendMessage = TimerUpdate;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("timer");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.ironbar.clients.borrow_mut().timer();
+
+        match (
+            parse_duration(&self.work_duration),
+            parse_duration(&self.break_duration),
+        ) {
+            (Ok(work), Ok(rest)) => client.configure(work, rest, self.auto_continue),
+            (Err(err), _) | (_, Err(err)) => error!("Invalid timer duration: {err:?}"),
+        }
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+            let notify = self.notify;
+            let script = self.on_finish.clone().map(Script::new_polling);
+
+            spawn(async move {
+                let mut events = client.subscribe();
+                let mut state = client.state();
+
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(TimerUpdate::State(state.clone()))
+                );
+
+                // refreshes the remaining time while running
+                let mut ticker = interval(Duration::from_secs(1));
+
+                loop {
+                    let running = state.as_ref().is_some_and(TimerState::is_running);
+
+                    let event = tokio::select! {
+                        event = events.recv() => match event {
+                            Ok(event) => event,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        },
+                        _ = ticker.tick(), if running => {
+                            let update = TimerUpdate::State(state.clone());
+                            send_async!(tx, ModuleUpdateEvent::Update(update));
+                            continue;
+                        }
+                    };
+
+                    match event {
+                        Event::State(new_state) => {
+                            state = new_state;
+                            let update = TimerUpdate::State(state.clone());
+                            send_async!(tx, ModuleUpdateEvent::Update(update));
+                        }
+                        Event::Finished(phase, handled) => {
+                            send_async!(tx, ModuleUpdateEvent::Update(TimerUpdate::Finished));
+
+                            // another module has already handled this
+                            if handled.swap(true, Ordering::Relaxed) {
+                                continue;
+                            }
+
+                            if notify {
+                                if let Err(err) = timer::notify(&phase).await {
+                                    error!("Failed to send timer notification: {err:?}");
+                                }
+                            }
+
+                            if let Some(script) = &script {
+                                script.run_as_oneshot(Some(&[phase.name().to_string()]));
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::Toggle => client.toggle(),
+                    UiEvent::Stop => client.stop(),
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.controller_tx.clone();
+
+            button.connect_button_press_event(move |button, event| {
+                // clicking acknowledges a finished phase
+                button.remove_class("urgent");
+
+                match event.button() {
+                    BUTTON_PRIMARY => try_send!(tx, UiEvent::Toggle),
+                    BUTTON_SECONDARY => try_send!(tx, UiEvent::Stop),
+                    _ => return Propagation::Proceed,
+                }
+
+                Propagation::Stop
+            });
+        }
+
+        {
+            let button = button.clone();
+
+            glib_recv!(context.subscribe(), update => match update {
+                TimerUpdate::State(state) => {
+                    for class in ["work", "break", "focus", "countdown", "running", "paused"] {
+                        button.remove_class(class);
+                    }
+
+                    let text = if let Some(state) = state {
+                        button.add_class(state.phase.name());
+                        button.add_class(if state.is_running() { "running" } else { "paused" });
+                        button.set_tooltip_text(Some(state.phase.label()));
+
+                        self.format
+                            .replace("{remaining}", &format_remaining(state.remaining()))
+                            .replace(
+                                "{phase}",
+                                &text::isolate(&glib::markup_escape_text(state.phase.label())),
+                            )
+                    } else {
+                        button.set_tooltip_text(None);
+                        self.format_idle.clone()
+                    };
+
+                    label.set_label(&text);
+                }
+                TimerUpdate::Finished => button.add_class("urgent"),
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
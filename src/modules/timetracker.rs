@@ -0,0 +1,361 @@
+use crate::clients::timetracker::{self, activitywatch, timewarrior, toggl, Activity};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, spawn, text, try_send};
+use chrono::Utc;
+use color_eyre::Result;
+use glib::markup_escape_text;
+use gtk::prelude::*;
+use gtk::{Align, Button, Entry, Label, Orientation};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{interval, Instant};
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// Uses the `timew` CLI.
+    /// Tasks are split on whitespace into interval tags.
+    #[default]
+    Timewarrior,
+    /// Uses the Toggl Track API.
+    Toggl {
+        /// Your Toggl Track API token,
+        /// found on your profile page.
+        api_token: String,
+    },
+    /// Uses the ActivityWatch stopwatch.
+    #[serde(rename = "activitywatch")]
+    ActivityWatch {
+        /// The URL of the ActivityWatch server.
+        ///
+        /// **Default**: `http://localhost:5600`
+        #[serde(default = "default_activitywatch_url")]
+        url: String,
+    },
+}
+
+fn default_activitywatch_url() -> String {
+    String::from("http://localhost:5600")
+}
+
+impl Backend {
+    fn create_client(&self) -> timetracker::Client {
+        match self.clone() {
+            Self::Timewarrior => timetracker::Client::Timewarrior(timewarrior::Client),
+            Self::Toggl { api_token } => timetracker::Client::Toggl(toggl::Client::new(api_token)),
+            Self::ActivityWatch { url } => {
+                timetracker::Client::ActivityWatch(activitywatch::Client::new(url))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TimeTrackerModule {
+    /// The time tracking service to use.
+    ///
+    /// **Default**: `{ type = "timewarrior" }`
+    #[serde(default)]
+    backend: Backend,
+
+    /// The format string to use for the widget button label
+    /// while a task is running.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{task} {elapsed}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The label to show when no task is running.
+    ///
+    /// **Default**: `No task`
+    #[serde(default = "default_format_stopped")]
+    format_stopped: String,
+
+    /// The number of milliseconds between each check of the backend.
+    /// The elapsed time updates every second regardless.
+    ///
+    /// **Default**: `10000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The maximum number of recent tasks to list in the popup.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_max_recent")]
+    max_recent: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{task} {elapsed}")
+}
+
+fn default_format_stopped() -> String {
+    String::from("No task")
+}
+
+const fn default_interval() -> u64 {
+    10_000
+}
+
+const fn default_max_recent() -> usize {
+    5
+}
+
+#[derive(Debug, Clone)]
+pub enum TimeTrackerUpdate {
+    /// The running activity.
+    /// Sent every second so the elapsed time stays current.
+    Activity(Option<Activity>),
+    /// Recently tracked task names.
+    Recent(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum TimeTrackerCommand {
+    Start(String),
+    Stop,
+}
+
+/// Formats the time since `activity` started as `H:MM:SS`.
+fn elapsed(activity: &Activity) -> String {
+    let seconds = (Utc::now() - activity.start).num_seconds().max(0);
+
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
+
+impl Module<Button> for TimeTrackerModule {
+    type SendMessage = TimeTrackerUpdate;
+    type ReceiveMessage = TimeTrackerCommand;
+
+    module_impl!("timetracker");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = Arc::new(self.backend.create_client());
+        let poll_interval = Duration::from_millis(self.interval);
+        let max_recent = self.max_recent;
+
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut tick = interval(Duration::from_secs(1));
+            let mut last_poll: Option<Instant> = None;
+            let mut activity = None;
+
+            loop {
+                tokio::select! {
+                    _ = tick.tick() => {}
+                    Some(command) = rx.recv() => {
+                        let res = match command {
+                            TimeTrackerCommand::Start(task) => client.start(&task).await,
+                            TimeTrackerCommand::Stop => client.stop().await,
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to update time tracker: {err:?}");
+                        }
+
+                        // force a refresh to pick up the change
+                        last_poll = None;
+                    }
+                }
+
+                if !matches!(last_poll, Some(time) if time.elapsed() < poll_interval) {
+                    last_poll = Some(Instant::now());
+
+                    match client.current().await {
+                        Ok(current) => activity = current,
+                        Err(err) => error!("Failed to get current activity: {err:?}"),
+                    }
+
+                    match client.recent(max_recent).await {
+                        Ok(recent) => {
+                            send_async!(
+                                tx,
+                                ModuleUpdateEvent::Update(TimeTrackerUpdate::Recent(recent))
+                            );
+                        }
+                        Err(err) => error!("Failed to get recent tasks: {err:?}"),
+                    }
+                }
+
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(TimeTrackerUpdate::Activity(activity.clone()))
+                );
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let format_stopped = self.format_stopped.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, update => {
+                if let TimeTrackerUpdate::Activity(activity) = update {
+                    if let Some(activity) = activity {
+                        // the format may contain markup, but task names are user input
+                        let task = markup_escape_text(&activity.task);
+                        let text = format
+                            .replace("{task}", &text::isolate(&task))
+                            .replace("{elapsed}", &elapsed(&activity));
+
+                        label.set_label(&text);
+                        button.add_class("running");
+                    } else {
+                        label.set_label(&format_stopped);
+                        button.remove_class("running");
+                    }
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let current = gtk::Box::new(Orientation::Horizontal, 10);
+        current.add_class("current");
+
+        let task = Label::builder().halign(Align::Start).hexpand(true).build();
+        task.add_class("task");
+
+        let elapsed_label = Label::new(None);
+        elapsed_label.add_class("elapsed");
+
+        let stop = Button::with_label("Stop");
+        stop.add_class("stop");
+
+        {
+            let tx = tx.clone();
+            stop.connect_clicked(move |_| {
+                try_send!(tx, TimeTrackerCommand::Stop);
+            });
+        }
+
+        current.add(&task);
+        current.add(&elapsed_label);
+        current.add(&stop);
+        container.add(&current);
+
+        let entry = Entry::builder()
+            .placeholder_text("Start a new task")
+            .build();
+        entry.add_class("entry");
+
+        {
+            let tx = tx.clone();
+            entry.connect_activate(move |entry| {
+                let task = entry.text().trim().to_string();
+                if !task.is_empty() {
+                    try_send!(tx, TimeTrackerCommand::Start(task));
+                    entry.set_text("");
+                }
+            });
+        }
+
+        container.add(&entry);
+
+        let recent = gtk::Box::new(Orientation::Vertical, 0);
+        recent.add_class("recent");
+        container.add(&recent);
+
+        container.show_all();
+
+        glib_recv!(rx, update => match update {
+            TimeTrackerUpdate::Activity(activity) => {
+                if let Some(activity) = activity {
+                    task.set_label(&activity.task);
+                    elapsed_label.set_label(&elapsed(&activity));
+                    stop.set_sensitive(true);
+                } else {
+                    task.set_label("No task running");
+                    elapsed_label.set_label("");
+                    stop.set_sensitive(false);
+                }
+            }
+            TimeTrackerUpdate::Recent(tasks) => {
+                for child in recent.children() {
+                    recent.remove(&child);
+                }
+
+                for task in tasks {
+                    let button = Button::with_label(&task);
+                    button.add_class("task");
+
+                    let tx = tx.clone();
+                    button.connect_clicked(move |_| {
+                        try_send!(tx, TimeTrackerCommand::Start(task.clone()));
+                    });
+
+                    recent.add(&button);
+                }
+
+                recent.show_all();
+            }
+        });
+
+        Some(container)
+    }
+}
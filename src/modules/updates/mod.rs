@@ -0,0 +1,766 @@
+mod transaction;
+
+use self::transaction::{Transaction, Watcher};
+use crate::config::CommonConfig;
+use crate::desktop_file::is_executable;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, Snapshot,
+    WidgetContext,
+};
+use crate::polling;
+use crate::script::{OutputStream, Script};
+use crate::{glib_recv, lock, module_impl, send_async, spawn, try_send};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, PolicyType, ScrolledWindow};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+/// Maximum height of the package list in the popup, in pixels.
+/// The list scrolls beyond this.
+const MAX_LIST_HEIGHT: i32 = 400;
+
+/// How often to check for a running package transaction.
+const TRANSACTION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// Arch Linux repository packages,
+    /// using `checkupdates` from `pacman-contrib`.
+    Pacman,
+    /// AUR packages, using an AUR helper.
+    Aur {
+        /// The AUR helper to use.
+        /// This must support `-Qua`, as `paru` and `yay` do.
+        ///
+        /// **Default**: `paru`
+        #[serde(default = "default_aur_helper")]
+        helper: String,
+    },
+    /// Debian and Ubuntu packages, using `apt`.
+    Apt,
+    /// Fedora packages, using `dnf`.
+    Dnf,
+    /// Flatpak applications and runtimes, using `flatpak`.
+    Flatpak,
+    /// Runs a custom command, which prints one pending update per line.
+    Custom {
+        /// The name shown in the popup.
+        name: String,
+        /// The shell command to run.
+        /// Each line of output is a package name,
+        /// optionally followed by whitespace and its new version.
+        command: String,
+    },
+}
+
+fn default_aur_helper() -> String {
+    String::from("paru")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl Package {
+    /// Parses a package from the first two whitespace-separated columns of `line`.
+    fn from_columns(line: &str) -> Option<Self> {
+        let mut columns = line.split_whitespace();
+
+        Some(Self {
+            name: columns.next()?.to_string(),
+            version: columns.next().map(ToString::to_string),
+        })
+    }
+}
+
+impl Backend {
+    /// Gets the backends for every supported package manager which is installed.
+    fn detect() -> Vec<Self> {
+        let mut backends = vec![];
+
+        if is_executable("checkupdates") {
+            backends.push(Self::Pacman);
+        }
+
+        if let Some(helper) = ["paru", "yay"]
+            .into_iter()
+            .find(|helper| is_executable(helper))
+        {
+            backends.push(Self::Aur {
+                helper: helper.to_string(),
+            });
+        }
+
+        if is_executable("apt") {
+            backends.push(Self::Apt);
+        }
+
+        if is_executable("dnf") {
+            backends.push(Self::Dnf);
+        }
+
+        if is_executable("flatpak") {
+            backends.push(Self::Flatpak);
+        }
+
+        backends
+    }
+
+    /// Gets the name used for the popup and CSS classes.
+    fn name(&self) -> &str {
+        match self {
+            Self::Pacman => "pacman",
+            Self::Aur { .. } => "aur",
+            Self::Apt => "apt",
+            Self::Dnf => "dnf",
+            Self::Flatpak => "flatpak",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Gets the list of pending updates.
+    async fn check(&self) -> Result<Vec<Package>> {
+        let packages = match self {
+            Self::Pacman => {
+                // exits with 2 when there are no updates
+                let output = run(Command::new("checkupdates"), &[2]).await?;
+                parse_arrow_lines(&output)
+            }
+            Self::Aur { helper } => {
+                // exits with 1 when there are no updates
+                let mut command = Command::new(helper);
+                command.arg("-Qua");
+
+                let output = run(command, &[1]).await?;
+                parse_arrow_lines(&output)
+            }
+            Self::Apt => {
+                let mut command = Command::new("apt");
+                command.args(["list", "--upgradable"]);
+
+                let output = run(command, &[]).await?;
+                parse_apt(&output)
+            }
+            Self::Dnf => {
+                // exits with 100 when there are updates
+                let mut command = Command::new("dnf");
+                command.args(["check-update", "--quiet"]);
+
+                let output = run(command, &[100]).await?;
+                parse_dnf(&output)
+            }
+            Self::Flatpak => {
+                let mut command = Command::new("flatpak");
+                command.args(["remote-ls", "--updates", "--columns=application,version"]);
+
+                let output = run(command, &[]).await?;
+                output.lines().filter_map(Package::from_columns).collect()
+            }
+            Self::Custom { command, .. } => {
+                let mut shell = Command::new("sh");
+                shell.args(["-c", command]);
+
+                let output = run(shell, &[]).await?;
+                output.lines().filter_map(Package::from_columns).collect()
+            }
+        };
+
+        Ok(packages)
+    }
+}
+
+/// Runs `command`, returning its stdout if it succeeds
+/// or exits with one of `ok_codes`,
+/// or its stderr as the error if not.
+async fn run(mut command: Command, ok_codes: &[i32]) -> Result<String> {
+    let output = command
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    let ok = output.status.success()
+        || output
+            .status
+            .code()
+            .is_some_and(|code| ok_codes.contains(&code));
+
+    if ok {
+        Ok(String::from_utf8(output.stdout)?)
+    } else {
+        Err(eyre!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        ))
+    }
+}
+
+/// Parses `name old -> new` lines,
+/// as printed by `checkupdates` and AUR helpers.
+fn parse_arrow_lines(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+
+            Some(Package {
+                name: columns.next()?.to_string(),
+                version: columns.last().map(ToString::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Parses `name/suite version arch [upgradable from: old]` lines,
+/// as printed by `apt list --upgradable`.
+fn parse_apt(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        // skips the `Listing...` header
+        .filter(|line| line.contains("[upgradable"))
+        .filter_map(|line| {
+            let (name, rest) = line.split_once('/')?;
+
+            Some(Package {
+                name: name.to_string(),
+                version: rest.split_whitespace().nth(1).map(ToString::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Parses `name.arch version repo` lines,
+/// as printed by `dnf check-update`.
+fn parse_dnf(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        // packages being obsoleted are listed after the updates
+        .take_while(|line| !line.starts_with("Obsoleting"))
+        .filter_map(|line| {
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+            let [name, version, _repo] = columns[..] else {
+                return None;
+            };
+
+            let name = name.rsplit_once('.').map_or(name, |(name, _arch)| name);
+
+            Some(Package {
+                name: name.to_string(),
+                version: Some(version.to_string()),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UpdatesModule {
+    /// The package managers to check for updates.
+    /// See [backends](#backends).
+    ///
+    /// If not set, every supported package manager which is installed is checked.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    backends: Vec<Backend>,
+
+    /// Number of milliseconds to wait between checks.
+    ///
+    /// **Default**: `3600000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰏔 {count}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Command to run from the popup's upgrade button.
+    /// Upgrades usually need a terminal to ask for a password,
+    /// for example `foot -e sudo pacman -Syu`.
+    /// Updates are checked again once it exits.
+    ///
+    /// **Default**: `null`
+    upgrade_command: Option<String>,
+
+    /// Whether to show the progress of package transactions while they run,
+    /// such as upgrades through pacman, apt or PackageKit.
+    /// Updates are checked again once a transaction finishes.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    transactions: bool,
+
+    /// The format string to use for the widget button label
+    /// while a package transaction is running.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰏔 {progress}`
+    #[serde(default = "default_transaction_format")]
+    transaction_format: String,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    3_600_000
+}
+
+fn default_format() -> String {
+    String::from("󰏔 {count}")
+}
+
+fn default_transaction_format() -> String {
+    String::from("󰏔 {progress}")
+}
+
+/// The result of checking a single backend.
+#[derive(Debug, Clone)]
+pub struct BackendUpdates {
+    name: String,
+    packages: Result<Vec<Package>, String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum UpdatesEvent {
+    /// Updates are being checked.
+    Checking,
+    /// The upgrade command is running.
+    Upgrading,
+    /// The results of the latest check, for each backend.
+    Updates(Snapshot<Vec<BackendUpdates>>),
+    /// A package transaction started, progressed or finished.
+    Transaction(Option<Transaction>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    /// Checks for updates immediately.
+    Check,
+    /// Runs the upgrade command.
+    Upgrade,
+}
+
+impl Module<Button> for UpdatesModule {
+    type SendMessage = UpdatesEvent;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("updates");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let backends = self.backends.clone();
+        let upgrade_command = self.upgrade_command.clone();
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        // pending update counts for each backend, used to estimate transaction progress
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let (finished_tx, mut finished_rx) = mpsc::channel(1);
+
+        if self.transactions {
+            let tx = context.tx.clone();
+            let pending = pending.clone();
+
+            spawn(async move {
+                let mut watcher = Watcher::new().await;
+                let mut last = None;
+
+                loop {
+                    let pending = lock!(pending).clone();
+                    let transaction = watcher.poll(&pending).await;
+
+                    if transaction != last {
+                        if last.is_some() && transaction.is_none() {
+                            debug!("Package transaction finished");
+                            // a full channel means a check is already queued
+                            let _ = finished_tx.try_send(());
+                        }
+
+                        last = transaction;
+                        send_async!(
+                            tx,
+                            ModuleUpdateEvent::Update(UpdatesEvent::Transaction(transaction))
+                        );
+                    }
+
+                    polling::sleep(TRANSACTION_POLL_INTERVAL).await;
+                }
+            });
+        }
+
+        spawn(async move {
+            let backends = if backends.is_empty() {
+                let backends = Backend::detect();
+                debug!(
+                    "Detected update backends: {:?}",
+                    backends.iter().map(Backend::name).collect::<Vec<_>>()
+                );
+                backends
+            } else {
+                backends
+            };
+
+            loop {
+                // the check below covers any transactions which finished before it
+                while finished_rx.try_recv().is_ok() {}
+
+                send_async!(tx, ModuleUpdateEvent::Update(UpdatesEvent::Checking));
+
+                let mut updates = Vec::with_capacity(backends.len());
+
+                for backend in &backends {
+                    let packages = backend.check().await.map_err(|err| {
+                        error!("Failed to check {} updates: {err:?}", backend.name());
+                        err.to_string()
+                    });
+
+                    updates.push(BackendUpdates {
+                        name: backend.name().to_string(),
+                        packages,
+                    });
+                }
+
+                *lock!(pending) = updates
+                    .iter()
+                    .filter_map(|backend| {
+                        let packages = backend.packages.as_ref().ok()?;
+                        Some((backend.name.clone(), packages.len()))
+                    })
+                    .collect::<HashMap<_, _>>();
+
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(UpdatesEvent::Updates(Snapshot::new(updates)))
+                );
+
+                tokio::select! {
+                    () = polling::sleep(interval) => {}
+                    Some(()) = finished_rx.recv() => {}
+                    Some(event) = rx.recv() => {
+                        let (UiEvent::Upgrade, Some(command)) = (event, &upgrade_command) else {
+                            continue;
+                        };
+
+                        debug!("Starting upgrade");
+                        send_async!(tx, ModuleUpdateEvent::Update(UpdatesEvent::Upgrading));
+
+                        match Script::from(command.as_str()).get_output(None).await {
+                            Ok((OutputStream::Stderr(err), _)) => {
+                                error!("Upgrade command failed: {err}");
+                            }
+                            Err(err) => error!("{err:?}"),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let transaction_format = self.transaction_format.clone();
+
+            // the count label is restored once a transaction finishes
+            let mut count_label = String::new();
+            let mut in_transaction = false;
+
+            let rx = context.subscribe();
+            glib_recv!(rx, event => {
+                match event {
+                    UpdatesEvent::Checking => button.add_class("checking"),
+                    UpdatesEvent::Upgrading => button.add_class("upgrading"),
+                    UpdatesEvent::Updates(updates) => {
+                        button.remove_class("checking");
+                        button.remove_class("upgrading");
+
+                        let count = updates
+                            .iter()
+                            .filter_map(|backend| backend.packages.as_ref().ok())
+                            .map(Vec::len)
+                            .sum::<usize>();
+
+                        if count > 0 {
+                            button.add_class("pending");
+                        } else {
+                            button.remove_class("pending");
+                        }
+
+                        if updates.iter().any(|backend| backend.packages.is_err()) {
+                            button.add_class("error");
+                        } else {
+                            button.remove_class("error");
+                        }
+
+                        let tooltip = updates
+                            .iter()
+                            .map(|backend| match &backend.packages {
+                                Ok(packages) => format!("{}: {}", backend.name, packages.len()),
+                                Err(_) => format!("{}: failed to check", backend.name),
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n");
+
+                        button.set_tooltip_text(Some(&tooltip));
+
+                        count_label = format.replace("{count}", &count.to_string());
+                        if !in_transaction {
+                            label.set_label_escaped(&count_label);
+                        }
+                    }
+                    UpdatesEvent::Transaction(Some(transaction)) => {
+                        in_transaction = true;
+                        button.add_class("transaction");
+                        label.set_label_escaped(&transaction.format(&transaction_format));
+                    }
+                    UpdatesEvent::Transaction(None) => {
+                        in_transaction = false;
+                        button.remove_class("transaction");
+                        label.set_label_escaped(&count_label);
+                    }
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .vscrollbar_policy(PolicyType::Automatic)
+            .max_content_height(MAX_LIST_HEIGHT)
+            .propagate_natural_height(true)
+            .build();
+        container.add(&scrolled);
+
+        let list = gtk::Box::new(Orientation::Vertical, 5);
+        list.add_class("packages");
+        scrolled.add(&list);
+
+        let buttons = gtk::Box::new(Orientation::Horizontal, 5);
+        buttons.add_class("buttons");
+        container.add(&buttons);
+
+        let check_button = Button::with_label("Check now");
+        check_button.add_class("check");
+        buttons.add(&check_button);
+
+        {
+            let tx = tx.clone();
+            check_button.connect_clicked(move |_| try_send!(tx, UiEvent::Check));
+        }
+
+        let upgrade_button = self.upgrade_command.as_ref().map(|_| {
+            let button = Button::with_label("Upgrade");
+            button.add_class("upgrade");
+            buttons.add(&button);
+
+            let tx = tx.clone();
+            button.connect_clicked(move |_| try_send!(tx, UiEvent::Upgrade));
+
+            button
+        });
+
+        {
+            let container = container.clone();
+
+            glib_recv!(rx, event => {
+                if matches!(event, UpdatesEvent::Transaction(_)) {
+                    continue;
+                }
+
+                let busy = !matches!(event, UpdatesEvent::Updates(_));
+                check_button.set_sensitive(!busy);
+                if let Some(button) = &upgrade_button {
+                    button.set_sensitive(!busy);
+                }
+
+                let UpdatesEvent::Updates(updates) = event else {
+                    continue;
+                };
+
+                for child in list.children() {
+                    list.remove(&child);
+                }
+
+                let up_to_date = updates
+                    .iter()
+                    .all(|backend| backend.packages.as_ref().is_ok_and(Vec::is_empty));
+
+                if up_to_date {
+                    let label = Label::new(Some("Up to date"));
+                    label.add_class("empty");
+                    list.add(&label);
+                }
+
+                for backend in updates.iter() {
+                    // skip backends with nothing to show
+                    if backend.packages.as_ref().is_ok_and(Vec::is_empty) {
+                        continue;
+                    }
+
+                    let section = gtk::Box::new(Orientation::Vertical, 0);
+                    section.add_class("backend");
+                    section.add_class(&backend.name);
+
+                    let header = Label::builder().halign(Align::Start).build();
+                    header.add_class("name");
+                    section.add(&header);
+
+                    match &backend.packages {
+                        Ok(packages) => {
+                            header.set_label(&format!("{} ({})", backend.name, packages.len()));
+
+                            for package in packages {
+                                section.add(&package_row(package));
+                            }
+                        }
+                        Err(err) => {
+                            header.set_label(&backend.name);
+
+                            let error = Label::builder()
+                                .label(err)
+                                .halign(Align::Start)
+                                .wrap(true)
+                                .build();
+                            error.add_class("error");
+                            section.add(&error);
+                        }
+                    }
+
+                    list.add(&section);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
+
+fn package_row(package: &Package) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_class("package");
+
+    let name = Label::builder()
+        .label(&package.name)
+        .halign(Align::Start)
+        .hexpand(true)
+        .build();
+    name.add_class("name");
+    row.add(&name);
+
+    if let Some(version) = &package.version {
+        let version = Label::new(Some(version));
+        version.add_class("version");
+        row.add(&version);
+    }
+
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_arrow_lines() {
+        let output = "linux 6.9.1.arch1-1 -> 6.9.2.arch1-1\nmesa 1:24.1.0-1 -> 1:24.1.1-1\n";
+
+        assert_eq!(
+            parse_arrow_lines(output),
+            vec![
+                package("linux", "6.9.2.arch1-1"),
+                package("mesa", "1:24.1.1-1")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_apt() {
+        let output = "Listing...\n\
+            curl/jammy-updates 7.81.0-1ubuntu1.16 amd64 [upgradable from: 7.81.0-1ubuntu1.15]\n";
+
+        assert_eq!(
+            parse_apt(output),
+            vec![package("curl", "7.81.0-1ubuntu1.16")]
+        );
+    }
+
+    #[test]
+    fn test_parse_dnf() {
+        let output = "\nkernel.x86_64    6.8.9-300.fc40    updates\n\
+            Obsoleting Packages\n\
+            grub2-tools.x86_64    1:2.06-121.fc40    updates\n";
+
+        assert_eq!(parse_dnf(output), vec![package("kernel", "6.8.9-300.fc40")]);
+    }
+}
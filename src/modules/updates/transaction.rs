@@ -0,0 +1,396 @@
+use color_eyre::Result;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::debug;
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::CacheProperties;
+
+const PACKAGEKIT_SERVICE: &str = "org.freedesktop.PackageKit";
+
+/// PackageKit's transaction percentage when the progress is unknown.
+const PACKAGEKIT_UNKNOWN_PERCENTAGE: u32 = 101;
+
+/// PackageKit roles for transactions which change installed packages:
+/// install files, install packages, remove packages,
+/// update packages, upgrade system and repair system.
+const PACKAGEKIT_CHANGE_ROLES: [u32; 6] = [10, 11, 14, 22, 29, 30];
+
+/// Only exists while pacman is running a transaction.
+const PACMAN_LOCK: &str = "/var/lib/pacman/db.lck";
+const PACMAN_LOG: &str = "/var/log/pacman.log";
+
+/// Always exists, but is only locked while dpkg is running,
+/// including when run by `apt`.
+const DPKG_LOCK: &str = "/var/lib/dpkg/lock";
+const DPKG_LOG: &str = "/var/log/dpkg.log";
+
+#[zbus::dbus_proxy(
+    default_service = "org.freedesktop.PackageKit",
+    interface = "org.freedesktop.PackageKit",
+    default_path = "/org/freedesktop/PackageKit"
+)]
+trait PackageKit {
+    fn get_transaction_list(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::dbus_proxy(
+    default_service = "org.freedesktop.PackageKit",
+    interface = "org.freedesktop.PackageKit.Transaction"
+)]
+trait PackageKitTransaction {
+    #[dbus_proxy(property)]
+    fn role(&self) -> zbus::Result<u32>;
+
+    #[dbus_proxy(property)]
+    fn percentage(&self) -> zbus::Result<u32>;
+}
+
+/// A package manager whose transactions can be detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Manager {
+    Pacman,
+    Apt,
+    PackageKit,
+}
+
+impl Manager {
+    /// Gets the name used for the `{manager}` token.
+    /// For pacman and apt, this matches the name of their backend.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Pacman => "pacman",
+            Self::Apt => "apt",
+            Self::PackageKit => "packagekit",
+        }
+    }
+
+    /// Gets the log which each changed package is written to.
+    const fn log_path(self) -> Option<&'static str> {
+        match self {
+            Self::Pacman => Some(PACMAN_LOG),
+            Self::Apt => Some(DPKG_LOG),
+            Self::PackageKit => None,
+        }
+    }
+
+    /// Checks whether a line of the manager's log records a package upgrade.
+    fn is_upgrade_line(self, line: &str) -> bool {
+        match self {
+            // `[date] [ALPM] upgraded name (old -> new)`
+            Self::Pacman => line.contains("[ALPM] upgraded "),
+            // `date time upgrade name:arch old new`
+            Self::Apt => line.split_whitespace().nth(2) == Some("upgrade"),
+            Self::PackageKit => false,
+        }
+    }
+}
+
+/// A package transaction which is in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transaction {
+    pub manager: Manager,
+    /// The percentage complete, if known.
+    pub progress: Option<u32>,
+}
+
+impl Transaction {
+    /// Replaces the `{manager}` and `{progress}` tokens in `format`.
+    pub fn format(&self, format: &str) -> String {
+        let progress = self
+            .progress
+            .map_or_else(|| String::from("…"), |progress| format!("{progress}%"));
+
+        format
+            .replace("{manager}", self.manager.name())
+            .replace("{progress}", &progress)
+    }
+}
+
+/// Detects package transactions as they run.
+pub struct Watcher {
+    dbus: Option<zbus::Connection>,
+    /// The manager of the running transaction,
+    /// and the length of its log when the transaction was first seen.
+    current: Option<(Manager, u64)>,
+}
+
+impl Watcher {
+    pub async fn new() -> Self {
+        let dbus = zbus::Connection::system()
+            .await
+            .map_err(|err| debug!("PackageKit transactions will not be shown: {err:?}"))
+            .ok();
+
+        Self {
+            dbus,
+            current: None,
+        }
+    }
+
+    /// Gets the transaction which is currently running, if any.
+    ///
+    /// PackageKit reports its own progress.
+    /// For pacman and apt, progress is estimated from the packages upgraded so far
+    /// against the number of `pending` updates for their backend.
+    pub async fn poll(&mut self, pending: &HashMap<String, usize>) -> Option<Transaction> {
+        if let Some(dbus) = &self.dbus {
+            match packagekit_transaction(dbus).await {
+                Ok(Some(transaction)) => {
+                    self.current = None;
+                    return Some(transaction);
+                }
+                Ok(None) => {}
+                // the transaction may finish while its properties are being read
+                Err(err) => debug!("Failed to check PackageKit transactions: {err:?}"),
+            }
+        }
+
+        let manager = if Path::new(PACMAN_LOCK).exists() {
+            Manager::Pacman
+        } else if dpkg_locked().await {
+            Manager::Apt
+        } else {
+            self.current = None;
+            return None;
+        };
+
+        let start = match self.current {
+            Some((current, start)) if current == manager => start,
+            _ => {
+                let start = log_len(manager).await;
+                self.current = Some((manager, start));
+                start
+            }
+        };
+
+        let progress = match count_upgrades(manager, start).await {
+            Ok(upgraded) => pending
+                .get(manager.name())
+                .and_then(|&total| estimate_progress(upgraded, total)),
+            Err(err) => {
+                debug!("Failed to read {} log: {err:?}", manager.name());
+                None
+            }
+        };
+
+        Some(Transaction { manager, progress })
+    }
+}
+
+/// Gets the first running PackageKit transaction which changes packages.
+async fn packagekit_transaction(dbus: &zbus::Connection) -> Result<Option<Transaction>> {
+    // checked first to avoid starting PackageKit through D-Bus activation
+    let running = DBusProxy::new(dbus)
+        .await?
+        .name_has_owner(BusName::try_from(PACKAGEKIT_SERVICE)?)
+        .await?;
+
+    if !running {
+        return Ok(None);
+    }
+
+    let packagekit = PackageKitProxy::new(dbus).await?;
+
+    for path in packagekit.get_transaction_list().await? {
+        let transaction = PackageKitTransactionProxy::builder(dbus)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        if !PACKAGEKIT_CHANGE_ROLES.contains(&transaction.role().await?) {
+            continue;
+        }
+
+        let percentage = transaction.percentage().await?;
+
+        return Ok(Some(Transaction {
+            manager: Manager::PackageKit,
+            progress: (percentage != PACKAGEKIT_UNKNOWN_PERCENTAGE).then_some(percentage),
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Checks whether dpkg's lock file is currently locked.
+async fn dpkg_locked() -> bool {
+    let Ok(metadata) = tokio::fs::metadata(DPKG_LOCK).await else {
+        return false;
+    };
+
+    let device = tokio::fs::read_to_string("/proc/self/mountinfo")
+        .await
+        .ok()
+        .and_then(|mounts| mount_device(&mounts, Path::new(DPKG_LOCK)));
+
+    tokio::fs::read_to_string("/proc/locks")
+        .await
+        .is_ok_and(|locks| is_locked(&locks, device, metadata.ino()))
+}
+
+/// Gets the `(major, minor)` device of the filesystem mounted at `path`,
+/// from a list of mounts in the format of `/proc/self/mountinfo`.
+///
+/// This is the device listed in `/proc/locks`,
+/// which does not always match the device from `stat`, such as on Btrfs subvolumes.
+fn mount_device(mounts: &str, path: &Path) -> Option<(u32, u32)> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            // `id parent major:minor root mount_point ...`
+            let mut columns = line.split_whitespace();
+            let (major, minor) = columns.nth(2)?.split_once(':')?;
+            let mount_point = columns.nth(1)?;
+
+            Some((mount_point, (major.parse().ok()?, minor.parse().ok()?)))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        // later mounts hide earlier ones at the same point
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, device)| device)
+}
+
+/// Checks whether any lock listed in `locks`,
+/// in the format of `/proc/locks`, is on the file with `inode` on `device`.
+///
+/// If the device is unknown, only the inode is compared.
+fn is_locked(locks: &str, device: Option<(u32, u32)>, inode: u64) -> bool {
+    locks
+        .lines()
+        .filter_map(|line| line.split_whitespace().find_map(parse_lock_file))
+        .any(|(lock_device, lock_inode)| {
+            lock_inode == inode && device.is_none_or(|device| device == lock_device)
+        })
+}
+
+/// Parses the file column of a `/proc/locks` line,
+/// in the format `major:minor:inode` with the device numbers in hex.
+fn parse_lock_file(column: &str) -> Option<((u32, u32), u64)> {
+    let mut parts = column.splitn(3, ':');
+
+    let major = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let minor = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let inode = parts.next()?.parse().ok()?;
+
+    Some(((major, minor), inode))
+}
+
+/// Gets the current length of the manager's log, in bytes.
+async fn log_len(manager: Manager) -> u64 {
+    let Some(path) = manager.log_path() else {
+        return 0;
+    };
+
+    tokio::fs::metadata(path)
+        .await
+        .map_or(0, |metadata| metadata.len())
+}
+
+/// Counts the packages upgraded in the manager's log
+/// after the first `start` bytes.
+async fn count_upgrades(manager: Manager, start: u64) -> Result<usize> {
+    let Some(path) = manager.log_path() else {
+        return Ok(0);
+    };
+
+    let mut file = File::open(path).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+
+    let mut log = vec![];
+    file.read_to_end(&mut log).await?;
+
+    Ok(String::from_utf8_lossy(&log)
+        .lines()
+        .filter(|line| manager.is_upgrade_line(line))
+        .count())
+}
+
+/// Estimates the percentage complete from the number of packages `upgraded`
+/// out of the `total` pending updates.
+///
+/// This stays below 100%, as the transaction may include
+/// packages which were not pending when updates were last checked.
+fn estimate_progress(upgraded: usize, total: usize) -> Option<u32> {
+    if upgraded == 0 || total == 0 {
+        return None;
+    }
+
+    u32::try_from((upgraded * 100 / total).min(99)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_upgrade_line() {
+        assert!(Manager::Pacman.is_upgrade_line(
+            "[2024-05-20T10:00:00+0100] [ALPM] upgraded linux (6.9.1.arch1-1 -> 6.9.2.arch1-1)"
+        ));
+        assert!(!Manager::Pacman
+            .is_upgrade_line("[2024-05-20T10:00:00+0100] [ALPM] installed linux-headers (6.9.2)"));
+
+        assert!(Manager::Apt.is_upgrade_line(
+            "2024-05-20 10:00:00 upgrade curl:amd64 7.81.0-1ubuntu1.15 7.81.0-1ubuntu1.16"
+        ));
+        assert!(!Manager::Apt.is_upgrade_line("2024-05-20 10:00:00 status installed curl:amd64"));
+    }
+
+    #[test]
+    fn test_mount_device() {
+        let mounts = "22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw\n\
+            35 22 0:31 / /var rw,relatime shared:2 - btrfs /dev/sda3 rw\n\
+            36 22 0:32 / /var/lib/docker rw,relatime shared:3 - tmpfs tmpfs rw\n";
+
+        assert_eq!(
+            mount_device(mounts, Path::new("/var/lib/dpkg/lock")),
+            Some((0, 31))
+        );
+        assert_eq!(mount_device(mounts, Path::new("/etc/hosts")), Some((8, 2)));
+        assert_eq!(mount_device("", Path::new("/var/lib/dpkg/lock")), None);
+    }
+
+    #[test]
+    fn test_is_locked() {
+        let locks = "1: POSIX  ADVISORY  WRITE 1234 08:02:131090 0 EOF\n\
+            2: FLOCK  ADVISORY  WRITE 5678 00:1f:42 0 EOF\n";
+
+        assert!(is_locked(locks, Some((8, 2)), 131_090));
+        assert!(is_locked(locks, Some((0, 31)), 42));
+        assert!(!is_locked(locks, Some((0, 32)), 42));
+        assert!(!is_locked(locks, Some((8, 2)), 1234));
+
+        assert!(is_locked(locks, None, 42));
+        assert!(!is_locked(locks, None, 1234));
+    }
+
+    #[test]
+    fn test_estimate_progress() {
+        assert_eq!(estimate_progress(0, 10), None);
+        assert_eq!(estimate_progress(3, 0), None);
+        assert_eq!(estimate_progress(3, 10), Some(30));
+        assert_eq!(estimate_progress(12, 10), Some(99));
+    }
+
+    #[test]
+    fn test_format() {
+        let transaction = Transaction {
+            manager: Manager::Apt,
+            progress: Some(42),
+        };
+        assert_eq!(transaction.format("{manager} {progress}"), "apt 42%");
+
+        let transaction = Transaction {
+            manager: Manager::PackageKit,
+            progress: None,
+        };
+        assert_eq!(transaction.format("{progress}"), "…");
+    }
+}
@@ -1,26 +1,37 @@
 use color_eyre::Result;
 use futures_lite::stream::StreamExt;
 use gtk::{prelude::*, Button};
-use gtk::{Label, Orientation};
+use gtk::{IconTheme, Label, Orientation};
 use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc};
 use upower_dbus::BatteryState;
 use zbus;
 use zbus::fdo::PropertiesProxy;
 
-use crate::config::CommonConfig;
+use crate::clients::upower;
+use crate::config::{default_true, Alert, Alerts, CommonConfig};
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+#[cfg(feature = "history")]
+use crate::history;
 use crate::image::ImageProvider;
 use crate::modules::PopupButton;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
 };
 use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use tracing::error;
 
 const DAY: i64 = 24 * 60 * 60;
 const HOUR: i64 = 60 * 60;
 const MINUTE: i64 = 60;
 
+/// How often to refresh the device list,
+/// as devices other than the display device do not send change signals here.
+const DEVICE_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UpowerModule {
@@ -37,6 +48,36 @@ pub struct UpowerModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// The charge limit to set when conservation mode is enabled, as a percentage.
+    ///
+    /// Conservation mode is toggled from the popup,
+    /// and is only available for batteries which support a charge threshold.
+    ///
+    /// **Default**: `80`
+    #[serde(default = "default_charge_limit")]
+    charge_limit: u8,
+
+    /// The name of the battery to control the charge threshold of,
+    /// such as `BAT0`.
+    ///
+    /// **Default**: `null` (first battery with a charge threshold)
+    #[serde(default)]
+    battery: Option<String>,
+
+    /// Whether to list every battery-powered device known to UPower in the popup,
+    /// such as mice, headsets and phones.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    show_devices: bool,
+
+    /// Classes to add and scripts to run when the battery percentage crosses a threshold.
+    /// See [alerts](alerts).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    alerts: Vec<Alert>,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -50,6 +91,10 @@ const fn default_icon_size() -> i32 {
     24
 }
 
+const fn default_charge_limit() -> u8 {
+    80
+}
+
 #[derive(Clone, Debug)]
 pub struct UpowerProperties {
     percentage: f64,
@@ -57,11 +102,21 @@ pub struct UpowerProperties {
     state: BatteryState,
     time_to_full: i64,
     time_to_empty: i64,
+    /// The current charge threshold, if the battery supports one.
+    charge_threshold: Option<u8>,
+    /// Every battery-powered device, if `show_devices` is enabled.
+    devices: Vec<upower::Device>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UpowerCommand {
+    /// Enables or disables conservation mode.
+    SetConservation(bool),
 }
 
 impl Module<gtk::Button> for UpowerModule {
     type SendMessage = UpowerProperties;
-    type ReceiveMessage = ();
+    type ReceiveMessage = UpowerCommand;
 
     module_impl!("upower");
 
@@ -69,12 +124,24 @@ impl Module<gtk::Button> for UpowerModule {
         &self,
         _info: &ModuleInfo,
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
 
         let display_proxy = context.try_client::<PropertiesProxy>()?;
 
+        let events = if self.show_devices {
+            Some(context.ironbar.clients.borrow_mut().upower_events()?)
+        } else {
+            None
+        };
+
+        let threshold_path = upower::find_charge_threshold(self.battery.as_deref());
+        let charge_limit = self.charge_limit;
+
+        #[cfg(feature = "history")]
+        let history_name = context.name.clone();
+
         spawn(async move {
             let mut prop_changed_stream = display_proxy.receive_properties_changed().await?;
 
@@ -109,11 +176,68 @@ impl Module<gtk::Button> for UpowerModule {
                 state,
                 time_to_full,
                 time_to_empty,
+                charge_threshold: threshold_path
+                    .as_deref()
+                    .and_then(upower::read_charge_threshold),
+                devices: vec![],
+            };
+
+            let (mut device_added, mut device_removed) = match &events {
+                Some(events) => (
+                    Some(events.receive_device_added().await?),
+                    Some(events.receive_device_removed().await?),
+                ),
+                None => (None, None),
             };
 
+            let mut device_interval = tokio::time::interval(DEVICE_INTERVAL);
+
+            #[cfg(feature = "history")]
+            history::record(&history_name, properties.percentage);
+
             send_async!(tx, ModuleUpdateEvent::Update(properties.clone()));
 
-            while let Some(signal) = prop_changed_stream.next().await {
+            loop {
+                let signal = tokio::select! {
+                    Some(signal) = prop_changed_stream.next() => signal,
+                    Some(UpowerCommand::SetConservation(enabled)) = rx.recv() => {
+                        let Some(path) = &threshold_path else {
+                            continue;
+                        };
+
+                        let value = if enabled { charge_limit } else { 100 };
+                        if let Err(err) = upower::write_charge_threshold(path, value).await {
+                            error!("{err:?}");
+                        }
+
+                        properties.charge_threshold = upower::read_charge_threshold(path);
+                        send_async!(tx, ModuleUpdateEvent::Update(properties.clone()));
+
+                        continue;
+                    }
+                    Some(()) = next_device_change(
+                        &mut device_interval,
+                        device_added.as_mut(),
+                        device_removed.as_mut(),
+                    ), if events.is_some() => {
+                        let Some(events) = &events else {
+                            continue;
+                        };
+
+                        match upower::devices(events.inner().connection()).await {
+                            Ok(devices) if devices != properties.devices => {
+                                properties.devices = devices;
+                                send_async!(tx, ModuleUpdateEvent::Update(properties.clone()));
+                            }
+                            Ok(_) => {}
+                            Err(err) => error!("Failed to get UPower devices: {err:?}"),
+                        }
+
+                        continue;
+                    }
+                    else => break,
+                };
+
                 let args = signal.args().expect("Invalid signal arguments");
                 if args.interface_name != device_interface_name {
                     continue;
@@ -125,6 +249,9 @@ impl Module<gtk::Button> for UpowerModule {
                             properties.percentage = changed_value
                                 .downcast::<f64>()
                                 .expect("expected Percentage to be f64");
+
+                            #[cfg(feature = "history")]
+                            history::record(&history_name, properties.percentage);
                         }
                         "IconName" => {
                             properties.icon_name = changed_value
@@ -191,9 +318,13 @@ impl Module<gtk::Button> for UpowerModule {
         });
 
         let format = self.format.clone();
+        let mut alerts = Alerts::new(self.alerts.clone());
 
         let rx = context.subscribe();
+        let alert_button = button.clone();
         glib_recv!(rx, properties => {
+            alerts.update(&alert_button, properties.percentage);
+
             let state = properties.state;
             let is_charging = state == BatteryState::Charging || state == BatteryState::PendingCharge;
             let time_remaining = if is_charging {
@@ -225,23 +356,76 @@ impl Module<gtk::Button> for UpowerModule {
 
     fn into_popup(
         self,
-        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
         rx: broadcast::Receiver<Self::SendMessage>,
         _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _info: &ModuleInfo,
+        info: &ModuleInfo,
     ) -> Option<gtk::Box>
     where
         Self: Sized,
     {
+        let icon_theme = info.icon_theme.clone();
+
         let container = gtk::Box::builder()
-            .orientation(Orientation::Horizontal)
+            .orientation(Orientation::Vertical)
             .build();
 
         let label = Label::builder().use_markup(true).build();
         label.add_class("upower-details");
         container.add(&label);
 
+        let devices = gtk::Box::new(Orientation::Vertical, 0);
+        devices.add_class("devices");
+        container.add(&devices);
+
+        let conservation = Button::new();
+        conservation.add_class("conservation");
+        conservation.set_no_show_all(true);
+        container.add(&conservation);
+
+        let enabled = Rc::new(Cell::new(false));
+
+        {
+            let enabled = enabled.clone();
+            conservation.connect_clicked(move |_| {
+                try_send!(tx, UpowerCommand::SetConservation(!enabled.get()));
+            });
+        }
+
+        let charge_limit = self.charge_limit;
+        let icon_size = self.icon_size;
+        let mut last_devices = vec![];
+
         glib_recv!(rx, properties => {
+            if properties.devices != last_devices {
+                devices.foreach(|child| devices.remove(child));
+
+                for device in &properties.devices {
+                    devices.add(&device_row(device, &icon_theme, icon_size));
+                }
+
+                devices.show_all();
+                last_devices.clone_from(&properties.devices);
+            }
+
+            if let Some(threshold) = properties.charge_threshold {
+                let is_enabled = threshold <= charge_limit;
+                enabled.set(is_enabled);
+
+                conservation.set_label(&format!(
+                    "Conservation mode ({charge_limit}%): {}",
+                    if is_enabled { "on" } else { "off" }
+                ));
+
+                if is_enabled {
+                    conservation.add_class("enabled");
+                } else {
+                    conservation.remove_class("enabled");
+                }
+
+                conservation.show();
+            }
+
             let state = properties.state;
             let format = match state {
                 BatteryState::Charging | BatteryState::PendingCharge => {
@@ -272,6 +456,58 @@ impl Module<gtk::Button> for UpowerModule {
     }
 }
 
+/// Creates the popup row for a device,
+/// containing its icon, name and battery level.
+fn device_row(device: &upower::Device, icon_theme: &IconTheme, icon_size: i32) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 5);
+    row.add_class("device");
+
+    let icon = gtk::Image::new();
+    icon.add_class("icon");
+    let icon_name = format!("icon:{}", device.icon_name);
+    if let Some(provider) = ImageProvider::parse(&icon_name, icon_theme, false, icon_size) {
+        if let Err(err) = provider.load_into_image(&icon) {
+            error!("{err:?}");
+        }
+    }
+    row.add(&icon);
+
+    let name = Label::new(Some(&device.name));
+    name.add_class("name");
+    name.set_hexpand(true);
+    name.set_xalign(0.0);
+    row.add(&name);
+
+    let state = u32_to_battery_state(device.state).unwrap_or(BatteryState::Unknown);
+    let percentage = Label::new(Some(&format!("{}%", device.percentage)));
+    percentage.add_class("percentage");
+    if state == BatteryState::Charging || state == BatteryState::PendingCharge {
+        percentage.add_class("charging");
+    }
+    row.add(&percentage);
+
+    row
+}
+
+/// Waits until the device list should be refreshed,
+/// either because a device was added or removed,
+/// or the refresh interval has passed.
+async fn next_device_change(
+    interval: &mut tokio::time::Interval,
+    added: Option<&mut upower::DeviceAddedStream<'static>>,
+    removed: Option<&mut upower::DeviceRemovedStream<'static>>,
+) -> Option<()> {
+    let (Some(added), Some(removed)) = (added, removed) else {
+        return None;
+    };
+
+    tokio::select! {
+        _ = interval.tick() => Some(()),
+        Some(_) = added.next() => Some(()),
+        Some(_) = removed.next() => Some(()),
+    }
+}
+
 fn seconds_to_string(seconds: i64) -> String {
     let mut time_string = String::new();
     let days = seconds / (DAY);
@@ -0,0 +1,229 @@
+use crate::clients::libvirt::{Client, Domain};
+use crate::config::{default_false, CommonConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VmModule {
+    /// Whether to list user session domains (`qemu:///session`)
+    /// instead of system domains (`qemu:///system`).
+    ///
+    /// **Default**: `false`
+    #[serde(default = "default_false")]
+    session: bool,
+
+    /// The number of milliseconds between each refresh of the domain list.
+    ///
+    /// **Default**: `5000`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰍹 {running}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    5000
+}
+
+fn default_format() -> String {
+    String::from("󰍹 {running}")
+}
+
+#[derive(Debug, Clone)]
+pub enum VmCommand {
+    Start(String),
+    Shutdown(String),
+}
+
+impl Module<Button> for VmModule {
+    /// The current domains,
+    /// or `None` if libvirt is unavailable.
+    type SendMessage = Option<Vec<Domain>>;
+    type ReceiveMessage = VmCommand;
+
+    module_impl!("vm");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let session = self.session;
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let client = match Client::new(session).await {
+                Ok(client) => client,
+                Err(err) => {
+                    error!("Failed to connect to D-Bus: {err:?}");
+                    send_async!(tx, ModuleUpdateEvent::Update(None));
+                    return;
+                }
+            };
+
+            let mut last = None;
+
+            loop {
+                let domains = match client.domains().await {
+                    Ok(domains) => Some(domains),
+                    Err(err) => {
+                        debug!("libvirt unavailable: {err:?}");
+                        None
+                    }
+                };
+
+                if domains != last {
+                    last.clone_from(&domains);
+                    send_async!(tx, ModuleUpdateEvent::Update(domains));
+                }
+
+                tokio::select! {
+                    () = polling::sleep(interval) => {}
+                    Some(command) = rx.recv() => {
+                        let res = match &command {
+                            VmCommand::Start(path) => client.start(path).await,
+                            VmCommand::Shutdown(path) => client.shutdown(path).await,
+                        };
+
+                        if let Err(err) = res {
+                            error!("Failed to run {command:?}: {err:?}");
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+
+            let rx = context.subscribe();
+            glib_recv!(rx, domains => {
+                if let Some(domains) = domains {
+                    let running = domains.iter().filter(|domain| domain.active).count();
+
+                    let text = format
+                        .replace("{running}", &running.to_string())
+                        .replace("{total}", &domains.len().to_string());
+
+                    label.set_label(&text);
+                    button.show();
+                } else {
+                    button.hide();
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        {
+            let container = container.clone();
+            glib_recv!(rx, domains => {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                for domain in domains.unwrap_or_default() {
+                    let row = gtk::Box::new(Orientation::Horizontal, 10);
+                    row.add_class("domain");
+                    row.add_class(if domain.active { "running" } else { "stopped" });
+
+                    let name = Label::builder()
+                        .label(&domain.name)
+                        .halign(Align::Start)
+                        .hexpand(true)
+                        .build();
+                    name.add_class("name");
+
+                    let state = Label::new(Some(if domain.active { "Running" } else { "Stopped" }));
+                    state.add_class("state");
+
+                    let (action, command) = if domain.active {
+                        ("Shut down", VmCommand::Shutdown(domain.path))
+                    } else {
+                        ("Start", VmCommand::Start(domain.path))
+                    };
+
+                    let button = Button::with_label(action);
+                    button.add_class("action");
+
+                    let tx = tx.clone();
+                    button.connect_clicked(move |_| {
+                        try_send!(tx, command.clone());
+                    });
+
+                    row.add(&name);
+                    row.add(&state);
+                    row.add(&button);
+                    container.add(&row);
+                }
+
+                container.show_all();
+            });
+        }
+
+        Some(container)
+    }
+}
@@ -1,11 +1,13 @@
-use crate::clients::volume::{self, Event};
+use crate::animation::AnimatedValue;
+use crate::clients::volume::{self, Event, Sink};
 use crate::config::CommonConfig;
 use crate::gtk_helpers::IronbarGtkExt;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
 };
-use crate::{glib_recv, lock, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, lock, module_impl, send_async, spawn, text, try_send};
 use glib::Propagation;
+use gtk::gdk::{EventMask, ScrollDirection, BUTTON_MIDDLE};
 use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
 use gtk::{Button, CellRendererText, ComboBoxText, Label, Orientation, Scale, ToggleButton};
@@ -30,6 +32,14 @@ pub struct VolumeModule {
     #[serde(default = "default_max_volume")]
     max_volume: f64,
 
+    /// The amount to change the volume by
+    /// when scrolling over the widget button.
+    /// Set to `0` to disable.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_scroll_step")]
+    scroll_step: f64,
+
     /// Volume state icons.
     ///
     /// See [icons](#icons).
@@ -98,6 +108,10 @@ const fn default_max_volume() -> f64 {
     100.0
 }
 
+const fn default_scroll_step() -> f64 {
+    5.0
+}
+
 fn default_icon_volume_high() -> String {
     String::from("󰕾")
 }
@@ -122,6 +136,11 @@ pub enum Update {
 
     InputVolume(u32, f64),
     InputMute(u32, bool),
+
+    /// Changes the active sink volume by the given amount.
+    ActiveVolumeStep(f64),
+    /// Toggles mute on the active sink.
+    ActiveMuteToggle,
 }
 
 impl Module<Button> for VolumeModule {
@@ -178,6 +197,8 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        let max_volume = self.max_volume;
+
         // ui events
         spawn(async move {
             while let Some(update) = rx.recv().await {
@@ -187,6 +208,17 @@ impl Module<Button> for VolumeModule {
                     Update::SinkMute(name, muted) => client.set_sink_muted(&name, muted),
                     Update::InputVolume(index, volume) => client.set_input_volume(index, volume),
                     Update::InputMute(index, muted) => client.set_input_muted(index, muted),
+                    Update::ActiveVolumeStep(delta) => {
+                        if let Some(sink) = active_sink(&client) {
+                            let volume = (sink.volume + delta).clamp(0.0, max_volume);
+                            client.set_sink_volume(&sink.name, volume);
+                        }
+                    }
+                    Update::ActiveMuteToggle => {
+                        if let Some(sink) = active_sink(&client) {
+                            client.set_sink_muted(&sink.name, !sink.muted);
+                        }
+                    }
                 }
             }
         });
@@ -212,6 +244,43 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        {
+            let tx = context.controller_tx.clone();
+
+            button.connect_button_press_event(move |_, event| {
+                if event.button() == BUTTON_MIDDLE {
+                    try_send!(tx, Update::ActiveMuteToggle);
+                    Propagation::Stop
+                } else {
+                    Propagation::Proceed
+                }
+            });
+        }
+
+        if self.scroll_step > 0.0 {
+            let tx = context.controller_tx.clone();
+            let step = self.scroll_step;
+
+            button.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+            button.connect_scroll_event(move |_, event| {
+                let delta = match event.direction() {
+                    ScrollDirection::Up => step,
+                    ScrollDirection::Down => -step,
+                    ScrollDirection::Smooth => {
+                        if event.scroll_deltas().unwrap_or_default().1 > 0.0 {
+                            -step
+                        } else {
+                            step
+                        }
+                    }
+                    _ => return Propagation::Proceed,
+                };
+
+                try_send!(tx, Update::ActiveVolumeStep(delta));
+                Propagation::Stop
+            });
+        }
+
         {
             let rx = context.subscribe();
             let icons = self.icons.clone();
@@ -225,7 +294,7 @@ impl Module<Button> for VolumeModule {
                         let label = format
                             .replace("{icon}", if sink.muted { &icons.muted } else { icons.volume_icon(sink.volume) })
                             .replace("{percentage}", &sink.volume.to_string())
-                            .replace("{name}", &sink.description);
+                            .replace("{name}", &text::isolate(&sink.description));
 
                         button.set_label(&label);
                     },
@@ -304,6 +373,8 @@ impl Module<Button> for VolumeModule {
         slider.set_value(50.0);
         sink_container.add(&slider);
 
+        let slider_value = AnimatedValue::new(&slider);
+
         {
             let tx = tx.clone();
             let selector = sink_selector.clone();
@@ -351,7 +422,7 @@ impl Module<Button> for VolumeModule {
 
                         if info.active {
                             sink_selector.set_active(Some(sinks.len() as u32));
-                            slider.set_value(info.volume);
+                            slider_value.set(info.volume);
 
                             btn_mute.set_active(info.muted);
                             btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
@@ -359,15 +430,13 @@ impl Module<Button> for VolumeModule {
 
                         sinks.push(info);
                     }
-                    Event::UpdateSink(info) => {
-                        if info.active {
-                            if let Some(pos) = sinks.iter().position(|s| s.name == info.name) {
-                                sink_selector.set_active(Some(pos as u32));
-                                slider.set_value(info.volume);
+                    Event::UpdateSink(info) if info.active => {
+                        if let Some(pos) = sinks.iter().position(|s| s.name == info.name) {
+                            sink_selector.set_active(Some(pos as u32));
+                            slider_value.set(info.volume);
 
-                                btn_mute.set_active(info.muted);
-                                btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
-                            }
+                            btn_mute.set_active(info.muted);
+                            btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
                         }
                     }
                     Event::RemoveSink(name) => {
@@ -426,6 +495,7 @@ impl Module<Button> for VolumeModule {
                         inputs.insert(info.index, InputUi {
                             container: item_container,
                             label,
+                            slider_value: AnimatedValue::new(&slider),
                             slider,
                             btn_mute
                         });
@@ -433,7 +503,7 @@ impl Module<Button> for VolumeModule {
                     Event::UpdateInput(info) => {
                         if let Some(ui) = inputs.get(&info.index) {
                             ui.label.set_label(&info.name);
-                            ui.slider.set_value(info.volume);
+                            ui.slider_value.set(info.volume);
                             ui.slider.set_sensitive(info.can_set_volume);
                             ui.btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
                         }
@@ -443,6 +513,7 @@ impl Module<Button> for VolumeModule {
                             input_container.remove(&ui.container);
                         }
                     }
+                    _ => {}
                 }
             });
         }
@@ -451,9 +522,17 @@ impl Module<Button> for VolumeModule {
     }
 }
 
+/// Gets the sink which is currently playing, if any.
+fn active_sink(client: &volume::Client) -> Option<Sink> {
+    let sinks = client.sinks();
+    let sinks = lock!(sinks);
+    sinks.iter().find(|sink| sink.active).cloned()
+}
+
 struct InputUi {
     container: gtk::Box,
     label: Label,
     slider: Scale,
+    slider_value: AnimatedValue<Scale>,
     btn_mute: ToggleButton,
 }
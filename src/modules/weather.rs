@@ -0,0 +1,407 @@
+use crate::clients::weather::{geoclue, Condition, Coordinates, Provider, Units, Weather};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::polling;
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::eyre::WrapErr;
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Grid, Label, Orientation};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ProviderConfig {
+    /// [Open-Meteo](https://open-meteo.com).
+    /// Does not require an API key.
+    #[default]
+    OpenMeteo,
+    /// [OpenWeatherMap](https://openweathermap.org).
+    #[serde(rename = "openweathermap")]
+    OpenWeatherMap {
+        /// Your OpenWeatherMap API key.
+        api_key: String,
+    },
+}
+
+impl From<ProviderConfig> for Provider {
+    fn from(config: ProviderConfig) -> Self {
+        match config {
+            ProviderConfig::OpenMeteo => Self::OpenMeteo,
+            ProviderConfig::OpenWeatherMap { api_key } => Self::OpenWeatherMap { api_key },
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum UnitsConfig {
+    /// Celsius.
+    #[default]
+    Metric,
+    /// Fahrenheit.
+    Imperial,
+}
+
+impl From<UnitsConfig> for Units {
+    fn from(config: UnitsConfig) -> Self {
+        match config {
+            UnitsConfig::Metric => Self::Metric,
+            UnitsConfig::Imperial => Self::Imperial,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LocationConfig {
+    /// Latitude in decimal degrees. North is positive.
+    latitude: f64,
+    /// Longitude in decimal degrees. East is positive.
+    longitude: f64,
+}
+
+impl From<LocationConfig> for Coordinates {
+    fn from(config: LocationConfig) -> Self {
+        Self {
+            latitude: config.latitude,
+            longitude: config.longitude,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WeatherModule {
+    /// The service to fetch weather from.
+    ///
+    /// See [providers](#providers).
+    ///
+    /// **Default**: `{ "type": "open_meteo" }`
+    #[serde(default)]
+    provider: ProviderConfig,
+
+    /// The location to show the weather for.
+    /// If not set, the location is found automatically using GeoClue.
+    ///
+    /// **Default**: `null`
+    location: Option<LocationConfig>,
+
+    /// The units to show temperatures in.
+    ///
+    /// **Valid options**: `metric`, `imperial`
+    /// <br>
+    /// **Default**: `metric`
+    #[serde(default)]
+    units: UnitsConfig,
+
+    /// The number of milliseconds between each weather update.
+    ///
+    /// **Default**: `900000` (15 minutes)
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {temperature}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The number of hours to show in the popup forecast.
+    /// Set to `0` to hide the hourly forecast.
+    ///
+    /// **Default**: `8`
+    #[serde(default = "default_hours")]
+    hours: usize,
+
+    /// The number of days to show in the popup forecast.
+    /// Set to `0` to hide the daily forecast.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_days")]
+    days: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_interval() -> u64 {
+    900_000
+}
+
+fn default_format() -> String {
+    String::from("{icon} {temperature}")
+}
+
+const fn default_hours() -> usize {
+    8
+}
+
+const fn default_days() -> usize {
+    5
+}
+
+#[derive(Debug, Clone)]
+pub enum WeatherUpdate {
+    Weather(Weather),
+    /// The reason the update failed.
+    Error(String),
+}
+
+/// Gets the Nerd Font icon for a condition.
+const fn condition_icon(condition: Condition, is_day: bool) -> &'static str {
+    match (condition, is_day) {
+        (Condition::Clear, true) => "",
+        (Condition::Clear, false) => "",
+        (Condition::PartlyCloudy, true) => "",
+        (Condition::PartlyCloudy, false) => "",
+        (Condition::Cloudy, _) => "",
+        (Condition::Fog, _) => "",
+        (Condition::Drizzle, _) => "",
+        (Condition::Rain, _) => "",
+        (Condition::Snow, _) => "",
+        (Condition::Thunderstorm, _) => "",
+        (Condition::Unknown, _) => "",
+    }
+}
+
+/// Formats a temperature to the nearest degree, with its unit.
+fn format_temperature(temperature: f64, units: Units) -> String {
+    // avoid showing `-0`
+    let temperature = temperature.round() + 0.0;
+    format!("{temperature:.0}{}", units.temperature_symbol())
+}
+
+impl Module<Button> for WeatherModule {
+    type SendMessage = WeatherUpdate;
+    type ReceiveMessage = ();
+
+    module_impl!("weather");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let provider = Provider::from(self.provider.clone());
+        let units = Units::from(self.units);
+        let mut location = self.location.map(Coordinates::from);
+
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let http = reqwest::Client::new();
+
+            loop {
+                // the location is only looked up once it is found,
+                // as it is unlikely to change enough to affect the weather
+                let coordinates = match location {
+                    Some(coordinates) => Ok(coordinates),
+                    None => geoclue::locate()
+                        .await
+                        .wrap_err("Failed to get location from GeoClue"),
+                };
+
+                let result = match coordinates {
+                    Ok(coordinates) => {
+                        location = Some(coordinates);
+                        provider.fetch(&http, coordinates, units).await
+                    }
+                    Err(err) => Err(err),
+                };
+
+                let update = match result {
+                    Ok(weather) => WeatherUpdate::Weather(weather),
+                    Err(err) => {
+                        error!("Failed to update weather: {err:?}");
+                        WeatherUpdate::Error(err.to_string())
+                    }
+                };
+
+                send_async!(tx, ModuleUpdateEvent::Update(update));
+                polling::sleep(interval).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::new(None);
+        label.add_class("label");
+        button.add(&label);
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let units = Units::from(self.units);
+
+            let mut condition = None;
+
+            let rx = context.subscribe();
+            glib_recv!(rx, update => {
+                // keep showing the last known weather if an update fails
+                let weather = match update {
+                    WeatherUpdate::Weather(weather) => {
+                        button.remove_class("error");
+                        weather
+                    }
+                    WeatherUpdate::Error(err) => {
+                        button.add_class("error");
+                        button.set_tooltip_text(Some(&err));
+                        continue;
+                    }
+                };
+
+                let current = weather.current;
+
+                let text = format
+                    .replace("{icon}", condition_icon(current.condition, current.is_day))
+                    .replace("{temperature}", &format_temperature(current.temperature, units))
+                    .replace("{condition}", current.condition.description());
+
+                label.set_label(&text);
+                button.set_tooltip_text(Some(current.condition.description()));
+
+                if let Some(condition) = condition.replace(current.condition) {
+                    button.remove_class(condition.as_str());
+                }
+                button.add_class(current.condition.as_str());
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+        let units = Units::from(self.units);
+        let (hours, days) = (self.hours, self.days);
+
+        let current = Label::new(None);
+        current.add_class("current");
+        container.add(&current);
+
+        let hourly = Grid::builder().column_spacing(10).row_spacing(2).build();
+        hourly.add_class("hourly");
+        container.add(&hourly);
+
+        let daily = Grid::builder().column_spacing(10).row_spacing(2).build();
+        daily.add_class("daily");
+        container.add(&daily);
+
+        let error = Label::new(None);
+        error.add_class("error");
+        error.set_no_show_all(true);
+        container.add(&error);
+
+        let cell = |text: &str, class: &str, halign: Align| {
+            let label = Label::builder().label(text).halign(halign).build();
+            label.add_class(class);
+            label
+        };
+
+        glib_recv!(rx, update => {
+            let weather = match update {
+                WeatherUpdate::Weather(weather) => {
+                    error.hide();
+                    weather
+                }
+                WeatherUpdate::Error(err) => {
+                    error.set_label(&err);
+                    error.show();
+                    continue;
+                }
+            };
+
+            current.set_label(&format!(
+                "{} {} · {}",
+                condition_icon(weather.current.condition, weather.current.is_day),
+                format_temperature(weather.current.temperature, units),
+                weather.current.condition.description()
+            ));
+
+            for child in hourly.children() {
+                hourly.remove(&child);
+            }
+
+            // one column per hour
+            for (column, hour) in (0..).zip(weather.hourly.iter().take(hours)) {
+                let time = hour.time.format("%H:%M").to_string();
+                let icon = condition_icon(hour.condition, hour.is_day);
+                let temperature = format_temperature(hour.temperature, units);
+
+                hourly.attach(&cell(&time, "time", Align::Center), column, 0, 1, 1);
+                hourly.attach(&cell(icon, "icon", Align::Center), column, 1, 1, 1);
+                hourly.attach(&cell(&temperature, "temperature", Align::Center), column, 2, 1, 1);
+
+                if let Some(precipitation) = hour.precipitation {
+                    let text = format!("{precipitation:.0}%");
+                    let precipitation = cell(&text, "precipitation", Align::Center);
+                    hourly.attach(&precipitation, column, 3, 1, 1);
+                }
+            }
+
+            for child in daily.children() {
+                daily.remove(&child);
+            }
+
+            // one row per day
+            for (row, day) in (0..).zip(weather.daily.iter().take(days)) {
+                let date = day.date.format("%a").to_string();
+                let icon = condition_icon(day.condition, true);
+                let min = format_temperature(day.min, units);
+                let max = format_temperature(day.max, units);
+
+                daily.attach(&cell(&date, "day", Align::Start), 0, row, 1, 1);
+                daily.attach(&cell(icon, "icon", Align::Center), 1, row, 1, 1);
+                daily.attach(&cell(&min, "min", Align::End), 2, row, 1, 1);
+                daily.attach(&cell(&max, "max", Align::End), 3, row, 1, 1);
+            }
+
+            hourly.show_all();
+            daily.show_all();
+        });
+
+        Some(container)
+    }
+}
@@ -1,8 +1,11 @@
 use super::open_state::OpenState;
 use crate::gtk_helpers::IronbarGtkExt;
 use crate::image::new_icon_button;
-use crate::modules::workspaces::WorkspaceItemContext;
+use crate::modules::workspaces::{WorkspaceAction, WorkspaceItemContext};
+use crate::modules::ModuleUpdateEvent;
 use crate::try_send;
+use glib::Propagation;
+use gtk::gdk::BUTTON_SECONDARY;
 use gtk::prelude::*;
 use gtk::Button as GtkButton;
 
@@ -24,9 +27,28 @@ impl Button {
 
         let name = name.to_string();
         button.connect_clicked(move |_item| {
-            try_send!(tx, name.clone());
+            try_send!(tx, WorkspaceAction::Focus(name.clone()));
         });
 
+        if let Some(overview_tx) = context.overview_tx.clone() {
+            let tx = context.tx.clone();
+            let orientation = context.orientation;
+
+            button.connect_button_press_event(move |button, event| {
+                if event.button() != BUTTON_SECONDARY {
+                    return Propagation::Proceed;
+                }
+
+                try_send!(tx, WorkspaceAction::RefreshWindows);
+                try_send!(
+                    overview_tx,
+                    ModuleUpdateEvent::OpenPopupAt(button.geometry(orientation))
+                );
+
+                Propagation::Stop
+            });
+        }
+
         let btn = Self {
             button,
             workspace_id: id,
@@ -1,21 +1,27 @@
 mod button;
 mod button_map;
 mod open_state;
+mod overview;
 
 use self::button::Button;
-use crate::clients::compositor::{Workspace, WorkspaceClient, WorkspaceUpdate};
+use crate::clients::compositor::{Window, Workspace, WorkspaceClient, WorkspaceUpdate};
 use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
 use crate::modules::workspaces::button_map::{ButtonMap, Identifier};
 use crate::modules::workspaces::open_state::OpenState;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::modules::workspaces::overview::OverviewContext;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
+};
 use crate::{glib_recv, module_impl, send_async, spawn};
 use color_eyre::{Report, Result};
 use gtk::prelude::*;
-use gtk::IconTheme;
+use gtk::{IconTheme, Orientation};
+use indexmap::IndexMap;
 use serde::Deserialize;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, trace, warn};
 
 #[derive(Debug, Deserialize, Default, Clone, Copy, Eq, PartialEq)]
@@ -126,6 +132,22 @@ pub struct WorkspacesModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// Whether to enable the overview popup.
+    ///
+    /// When enabled, right-clicking a workspace button opens a popup
+    /// listing every workspace alongside its open windows.
+    /// Windows can be dragged onto another workspace to move them there.
+    ///
+    /// **Default**: `false`
+    #[serde(default = "crate::config::default_false")]
+    overview: bool,
+
+    /// The size to render application icons at in the overview popup.
+    ///
+    /// **Default**: `16`
+    #[serde(default = "default_overview_icon_size")]
+    overview_icon_size: i32,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -135,12 +157,51 @@ const fn default_icon_size() -> i32 {
     32
 }
 
+const fn default_overview_icon_size() -> i32 {
+    16
+}
+
+#[derive(Debug, Clone)]
+pub enum WorkspaceEvent {
+    /// A workspace update from the compositor.
+    Workspace(WorkspaceUpdate),
+    /// The latest list of open windows, for the overview popup.
+    Windows(Vec<Window>),
+}
+
+#[derive(Debug, Clone)]
+pub enum WorkspaceAction {
+    /// Focuses the workspace with this name.
+    Focus(String),
+    /// Moves the window with `id` to the workspace with this name.
+    MoveWindow { id: String, workspace: String },
+    /// Requests the current window list is sent to the overview popup.
+    RefreshWindows,
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkspaceItemContext {
     name_map: HashMap<String, String>,
     icon_theme: IconTheme,
     icon_size: i32,
-    tx: mpsc::Sender<String>,
+    tx: mpsc::Sender<WorkspaceAction>,
+    /// Set when the overview popup is enabled,
+    /// used to open the popup on right-click.
+    overview_tx: Option<mpsc::Sender<ModuleUpdateEvent<WorkspaceEvent>>>,
+    orientation: Orientation,
+}
+
+/// Compares two workspace names or labels.
+///
+/// Workspaces are sorted numerically first,
+/// and named workspaces are added to the end in alphabetical order.
+fn compare_names(a: &str, b: &str) -> Ordering {
+    match (a.parse::<i32>(), b.parse::<i32>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
 }
 
 /// Re-orders the container children alphabetically,
@@ -166,14 +227,7 @@ fn reorder_workspaces(container: &gtk::Box, sort_order: SortOrder) {
         })
         .collect::<Vec<_>>();
 
-    buttons.sort_by(|(label_a, _), (label_b, _a)| {
-        match (label_a.parse::<i32>(), label_b.parse::<i32>()) {
-            (Ok(a), Ok(b)) => a.cmp(&b),
-            (Ok(_), Err(_)) => Ordering::Less,
-            (Err(_), Ok(_)) => Ordering::Greater,
-            (Err(_), Err(_)) => label_a.cmp(label_b),
-        }
-    });
+    buttons.sort_by(|(label_a, _), (label_b, _a)| compare_names(label_a, label_b));
 
     for (i, (_, button)) in buttons.into_iter().enumerate() {
         container.reorder_child(&button, i as i32);
@@ -181,8 +235,8 @@ fn reorder_workspaces(container: &gtk::Box, sort_order: SortOrder) {
 }
 
 impl Module<gtk::Box> for WorkspacesModule {
-    type SendMessage = WorkspaceUpdate;
-    type ReceiveMessage = String;
+    type SendMessage = WorkspaceEvent;
+    type ReceiveMessage = WorkspaceAction;
 
     module_impl!("workspaces");
 
@@ -202,20 +256,49 @@ impl Module<gtk::Box> for WorkspacesModule {
 
             while let Ok(payload) = srx.recv().await {
                 debug!("Received update: {payload:?}");
-                send_async!(tx, ModuleUpdateEvent::Update(payload));
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(WorkspaceEvent::Workspace(payload))
+                );
             }
         });
 
         let client = context.try_client::<dyn WorkspaceClient>()?;
+        let tx = context.tx.clone();
 
-        // Change workspace focus
+        // Handle UI actions
         spawn(async move {
             trace!("Setting up UI event handler");
 
-            while let Some(name) = rx.recv().await {
-                if let Err(e) = client.focus(name.clone()) {
-                    warn!("Couldn't focus workspace '{name}': {e:#}");
-                };
+            while let Some(action) = rx.recv().await {
+                // focus changes do not affect the window list
+                let refresh_windows = !matches!(action, WorkspaceAction::Focus(_));
+
+                match action {
+                    WorkspaceAction::Focus(name) => {
+                        if let Err(e) = client.focus(name.clone()) {
+                            warn!("Couldn't focus workspace '{name}': {e:#}");
+                        };
+                    }
+                    WorkspaceAction::MoveWindow { id, workspace } => {
+                        if let Err(e) = client.move_window(&id, &workspace) {
+                            warn!("Couldn't move window '{id}' to workspace '{workspace}': {e:#}");
+                        }
+                    }
+                    WorkspaceAction::RefreshWindows => {}
+                }
+
+                if !refresh_windows {
+                    continue;
+                }
+
+                match client.windows() {
+                    Ok(windows) => send_async!(
+                        tx,
+                        ModuleUpdateEvent::Update(WorkspaceEvent::Windows(windows))
+                    ),
+                    Err(e) => warn!("Couldn't get windows: {e:#}"),
+                }
             }
 
             Ok::<(), Report>(())
@@ -240,12 +323,14 @@ impl Module<gtk::Box> for WorkspacesModule {
             icon_theme: info.icon_theme.clone(),
             icon_size: self.icon_size,
             tx: context.controller_tx.clone(),
+            overview_tx: self.overview.then(|| context.tx.clone()),
+            orientation: info.bar_position.orientation(),
         };
 
         // setup favorites
-        let favorites = match self.favorites {
+        let favorites = match &self.favorites {
             Favorites::ByMonitor(map) => map.get(info.output_name).cloned(),
-            Favorites::Global(vec) => Some(vec),
+            Favorites::Global(vec) => Some(vec.clone()),
         }
         .unwrap_or_default();
 
@@ -258,6 +343,9 @@ impl Module<gtk::Box> for WorkspacesModule {
         {
             let container = container.clone();
             let output_name = info.output_name.to_string();
+            let hidden = self.hidden.clone();
+            let all_monitors = self.all_monitors;
+            let sort = self.sort;
 
             // keep track of whether init event has fired previously
             // since it fires for every workspace subscriber
@@ -308,8 +396,8 @@ impl Module<gtk::Box> for WorkspacesModule {
 
             macro_rules! reorder {
                 () => {
-                    if self.sort != SortOrder::Added {
-                        reorder_workspaces(&container, self.sort);
+                    if sort != SortOrder::Added {
+                        reorder_workspaces(&container, sort);
                     }
                 };
             }
@@ -324,8 +412,8 @@ impl Module<gtk::Box> for WorkspacesModule {
 
                     for workspace in workspaces
                         .into_iter()
-                        .filter(|w| self.all_monitors || w.monitor == output_name)
-                        .filter(|w| !self.hidden.contains(&w.name))
+                        .filter(|w| all_monitors || w.monitor == output_name)
+                        .filter(|w| !hidden.contains(&w.name))
                     {
                         add_workspace(workspace, &mut button_map);
                     }
@@ -335,8 +423,8 @@ impl Module<gtk::Box> for WorkspacesModule {
                     has_initialized = true;
                 }
                 WorkspaceUpdate::Add(workspace) => {
-                    if !self.hidden.contains(&workspace.name)
-                        && (self.all_monitors || workspace.monitor == output_name)
+                    if !hidden.contains(&workspace.name)
+                        && (all_monitors || workspace.monitor == output_name)
                     {
                         add_workspace(workspace, &mut button_map);
                     }
@@ -345,11 +433,11 @@ impl Module<gtk::Box> for WorkspacesModule {
                 }
                 WorkspaceUpdate::Remove(id) => remove_workspace(id, &mut button_map),
                 WorkspaceUpdate::Move(workspace) => {
-                    if self.all_monitors {
+                    if all_monitors {
                         return;
                     }
 
-                    if workspace.monitor == output_name && !self.hidden.contains(&workspace.name) {
+                    if workspace.monitor == output_name && !hidden.contains(&workspace.name) {
                         add_workspace(workspace, &mut button_map);
                         reorder!();
                     } else {
@@ -398,12 +486,80 @@ impl Module<gtk::Box> for WorkspacesModule {
                 WorkspaceUpdate::Unknown => warn!("received unknown type workspace event"),
             };
 
-            glib_recv!(context.subscribe(), handle_event);
+            let rx = context.subscribe();
+            glib_recv!(rx, event => {
+                if let WorkspaceEvent::Workspace(update) = event {
+                    handle_event(update);
+                }
+            });
         }
 
+        let popup = if self.overview {
+            let rx = context.subscribe();
+            self.into_popup(context.controller_tx.clone(), rx, context, info)
+                .into_popup_parts(vec![]) // since workspace buttons are dynamic, they pass their geometry directly
+        } else {
+            None
+        };
+
         Ok(ModuleParts {
             widget: container,
-            popup: None,
+            popup,
         })
     }
+
+    fn into_popup(
+        self,
+        controller_tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+        container.add_class("overview");
+
+        let hidden = self.hidden;
+        let all_monitors = self.all_monitors;
+        let output_name = info.output_name.to_string();
+
+        let overview_context = OverviewContext {
+            name_map: self.name_map.unwrap_or_default(),
+            sort: self.sort,
+            icon_theme: info.icon_theme.clone(),
+            icon_size: self.overview_icon_size,
+            controller_tx,
+            tx: context.tx.clone(),
+        };
+
+        let mut workspaces = IndexMap::<i64, Workspace>::new();
+        let mut windows = vec![];
+
+        {
+            let container = container.clone();
+            glib_recv!(rx, event => {
+                let render = match event {
+                    WorkspaceEvent::Workspace(update) => {
+                        overview::apply_update(&mut workspaces, update);
+                        container.is_visible()
+                    }
+                    WorkspaceEvent::Windows(new_windows) => {
+                        windows = new_windows;
+                        true
+                    }
+                };
+
+                if render {
+                    let workspaces = workspaces
+                        .values()
+                        .filter(|w| all_monitors || w.monitor == output_name)
+                        .filter(|w| !hidden.contains(&w.name))
+                        .collect::<Vec<_>>();
+
+                    overview::render(&container, &workspaces, &windows, &overview_context);
+                }
+            });
+        }
+
+        Some(container)
+    }
 }
@@ -0,0 +1,298 @@
+use super::{compare_names, SortOrder, WorkspaceAction};
+use crate::clients::compositor::{Visibility, Window, Workspace, WorkspaceUpdate};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::ImageProvider;
+use crate::modules::ModuleUpdateEvent;
+use crate::try_send;
+use gtk::gdk::{DragAction, ModifierType};
+use gtk::pango::EllipsizeMode;
+use gtk::prelude::*;
+use gtk::{Button, DestDefaults, IconTheme, Label, Orientation, TargetEntry, TargetFlags};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::slice;
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// Drag target used when dragging windows between workspaces.
+/// Only accepted within Ironbar itself.
+const DRAG_TARGET: &str = "application/x-ironbar-window";
+
+/// Maximum width (in characters) of window titles in the overview.
+const MAX_TITLE_LENGTH: i32 = 30;
+
+pub struct OverviewContext<T: Clone> {
+    pub name_map: HashMap<String, String>,
+    pub sort: SortOrder,
+    pub icon_theme: IconTheme,
+    pub icon_size: i32,
+    pub controller_tx: mpsc::Sender<WorkspaceAction>,
+    pub tx: mpsc::Sender<ModuleUpdateEvent<T>>,
+}
+
+impl<T: Clone> OverviewContext<T> {
+    fn label<'a>(&'a self, workspace: &'a Workspace) -> &'a str {
+        self.name_map
+            .get(&workspace.name)
+            .map_or(workspace.name.as_str(), String::as_str)
+    }
+}
+
+/// Applies a workspace update to the overview's own copy
+/// of the compositor workspace state.
+pub fn apply_update(workspaces: &mut IndexMap<i64, Workspace>, update: WorkspaceUpdate) {
+    match update {
+        WorkspaceUpdate::Init(new) => {
+            *workspaces = new.into_iter().map(|w| (w.id, w)).collect();
+        }
+        WorkspaceUpdate::Add(workspace) | WorkspaceUpdate::Move(workspace) => {
+            workspaces.insert(workspace.id, workspace);
+        }
+        WorkspaceUpdate::Remove(id) => {
+            workspaces.shift_remove(&id);
+        }
+        WorkspaceUpdate::Focus { old, new } => {
+            if let Some(old) = old {
+                if let Some(workspace) = workspaces.get_mut(&old.id) {
+                    workspace.visibility = if old.monitor == new.monitor {
+                        Visibility::Hidden
+                    } else {
+                        Visibility::visible()
+                    };
+                }
+            }
+
+            workspaces.entry(new.id).or_insert(new).visibility = Visibility::focused();
+        }
+        WorkspaceUpdate::Rename { id, name } => {
+            if let Some(workspace) = workspaces.get_mut(&id) {
+                workspace.name = name;
+            }
+        }
+        WorkspaceUpdate::Urgent { .. } | WorkspaceUpdate::Unknown => {}
+    }
+}
+
+/// Groups windows by the ID of the workspace they are on,
+/// keeping the order the compositor returned them in.
+fn group_windows(windows: &[Window]) -> HashMap<i64, Vec<&Window>> {
+    let mut groups = HashMap::<_, Vec<_>>::new();
+    for window in windows {
+        groups.entry(window.workspace_id).or_default().push(window);
+    }
+    groups
+}
+
+/// Clears the overview container
+/// and re-creates a column for each workspace,
+/// containing a button for each of its windows.
+pub fn render<T: Clone + Debug + 'static>(
+    container: &gtk::Box,
+    workspaces: &[&Workspace],
+    windows: &[Window],
+    context: &OverviewContext<T>,
+) {
+    for child in container.children() {
+        container.remove(&child);
+    }
+
+    let mut workspaces = workspaces.to_vec();
+    match context.sort {
+        SortOrder::Added => {}
+        SortOrder::Label => {
+            workspaces.sort_by(|a, b| compare_names(context.label(a), context.label(b)));
+        }
+        SortOrder::Name => workspaces.sort_by(|a, b| compare_names(&a.name, &b.name)),
+    }
+
+    let target = TargetEntry::new(DRAG_TARGET, TargetFlags::SAME_APP, 0);
+    let windows = group_windows(windows);
+
+    for workspace in workspaces {
+        let column = gtk::Box::new(Orientation::Vertical, 0);
+        column.add_class("workspace");
+        if workspace.visibility.is_focused() {
+            column.add_class("focused");
+        }
+
+        let header = Button::with_label(context.label(workspace));
+        header.add_class("header");
+        {
+            let name = workspace.name.clone();
+            let controller_tx = context.controller_tx.clone();
+            let tx = context.tx.clone();
+            header.connect_clicked(move |_| {
+                try_send!(controller_tx, WorkspaceAction::Focus(name.clone()));
+                try_send!(tx, ModuleUpdateEvent::ClosePopup);
+            });
+        }
+        column.add(&header);
+
+        for window in windows.get(&workspace.id).into_iter().flatten() {
+            column.add(&window_button(window, &target, context));
+        }
+
+        column.drag_dest_set(
+            DestDefaults::ALL,
+            slice::from_ref(&target),
+            DragAction::MOVE,
+        );
+        {
+            let name = workspace.name.clone();
+            let controller_tx = context.controller_tx.clone();
+            column.connect_drag_data_received(move |_, _, _, _, data, _, _| {
+                if let Some(id) = data.text() {
+                    try_send!(
+                        controller_tx,
+                        WorkspaceAction::MoveWindow {
+                            id: id.to_string(),
+                            workspace: name.clone(),
+                        }
+                    );
+                }
+            });
+        }
+
+        container.add(&column);
+    }
+
+    container.show_all();
+}
+
+fn window_button<T: Clone>(
+    window: &Window,
+    target: &TargetEntry,
+    context: &OverviewContext<T>,
+) -> Button {
+    let button = Button::new();
+    button.add_class("window");
+
+    let contents = gtk::Box::new(Orientation::Horizontal, 5);
+
+    let image = gtk::Image::new();
+    image.add_class("icon");
+    if let Some(provider) =
+        ImageProvider::parse(&window.app_id, &context.icon_theme, true, context.icon_size)
+    {
+        if let Err(err) = provider.load_into_image(&image) {
+            error!("{err:?}");
+        }
+    }
+    contents.add(&image);
+
+    let label = Label::builder()
+        .label(&window.title)
+        .ellipsize(EllipsizeMode::End)
+        .max_width_chars(MAX_TITLE_LENGTH)
+        .build();
+    label.add_class("title");
+    contents.add(&label);
+
+    button.add(&contents);
+    button.set_tooltip_text(Some(&window.title));
+
+    button.drag_source_set(
+        ModifierType::BUTTON1_MASK,
+        slice::from_ref(target),
+        DragAction::MOVE,
+    );
+
+    {
+        let id = window.id.clone();
+        button.connect_drag_data_get(move |_, _, data, _, _| {
+            data.set_text(&id);
+        });
+    }
+
+    button
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(id: i64, monitor: &str, visibility: Visibility) -> Workspace {
+        Workspace {
+            id,
+            name: id.to_string(),
+            monitor: monitor.to_string(),
+            visibility,
+        }
+    }
+
+    fn window(id: &str, workspace_id: i64) -> Window {
+        Window {
+            id: id.to_string(),
+            title: id.to_string(),
+            app_id: String::new(),
+            workspace_id,
+        }
+    }
+
+    #[test]
+    fn test_group_windows() {
+        let windows = [window("a", 1), window("b", 2), window("c", 1)];
+        let groups = group_windows(&windows);
+
+        let ids = |id| {
+            groups[&id]
+                .iter()
+                .map(|w| w.id.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(ids(1), ["a", "c"]);
+        assert_eq!(ids(2), ["b"]);
+        assert!(!groups.contains_key(&3));
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let mut workspaces = IndexMap::new();
+        apply_update(
+            &mut workspaces,
+            WorkspaceUpdate::Init(vec![
+                workspace(1, "DP-1", Visibility::focused()),
+                workspace(2, "DP-1", Visibility::Hidden),
+                workspace(3, "DP-2", Visibility::visible()),
+            ]),
+        );
+        assert_eq!(workspaces.keys().copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+        apply_update(
+            &mut workspaces,
+            WorkspaceUpdate::Focus {
+                old: Some(workspace(1, "DP-1", Visibility::focused())),
+                new: workspace(2, "DP-1", Visibility::Hidden),
+            },
+        );
+        assert!(matches!(workspaces[&1].visibility, Visibility::Hidden));
+        assert!(workspaces[&2].visibility.is_focused());
+
+        apply_update(
+            &mut workspaces,
+            WorkspaceUpdate::Focus {
+                old: Some(workspace(2, "DP-1", Visibility::focused())),
+                new: workspace(3, "DP-2", Visibility::visible()),
+            },
+        );
+        assert!(matches!(
+            workspaces[&2].visibility,
+            Visibility::Visible { focused: false }
+        ));
+        assert!(workspaces[&3].visibility.is_focused());
+
+        apply_update(
+            &mut workspaces,
+            WorkspaceUpdate::Rename {
+                id: 3,
+                name: String::from("web"),
+            },
+        );
+        apply_update(&mut workspaces, WorkspaceUpdate::Remove(1));
+        assert_eq!(workspaces.keys().copied().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(workspaces[&3].name, "web");
+    }
+}
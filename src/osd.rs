@@ -0,0 +1,460 @@
+use crate::animation::AnimatedValue;
+use crate::clients::backlight::Backlight;
+use crate::config::default_true;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::ImageProvider;
+use crate::polling;
+use crate::{glib_recv_mpsc, rc_mut, spawn, spawn_blocking, Ironbar};
+use glib::SourceId;
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, IconTheme, Image, Label, Orientation, ProgressBar};
+use gtk_layer_shell::LayerShell;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+/// How often to check the backlight for changes, in milliseconds.
+const BACKLIGHT_POLL_INTERVAL: u64 = 250;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OsdConfig {
+    /// The length of time to show the OSD for after the last change, in milliseconds.
+    ///
+    /// **Default**: `1500`
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+
+    /// The size to render the OSD icon at, in pixels.
+    ///
+    /// **Default**: `48`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// Whether to show the OSD when the default sink's volume or mute state changes.
+    ///
+    /// Requires the `volume` feature.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    volume: bool,
+
+    /// Whether to show the OSD when the screen backlight brightness changes.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    brightness: bool,
+
+    /// Whether to show the OSD when caps lock is toggled.
+    ///
+    /// Requires the `keys` or `lock_keys` feature.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    caps_lock: bool,
+
+    /// Whether to show the OSD when num lock is toggled.
+    ///
+    /// Requires the `keys` or `lock_keys` feature.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    num_lock: bool,
+
+    /// Whether to show the OSD when scroll lock is toggled.
+    ///
+    /// Requires the `keys` or `lock_keys` feature.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    scroll_lock: bool,
+
+    /// The Wayland seat to listen for lock key changes on.
+    ///
+    /// **Default**: `seat0`
+    #[serde(default = "default_seat")]
+    seat: String,
+}
+
+const fn default_timeout() -> u64 {
+    1500
+}
+
+const fn default_icon_size() -> i32 {
+    48
+}
+
+fn default_seat() -> String {
+    String::from("seat0")
+}
+
+/// A change to display on the OSD.
+#[derive(Debug, Clone)]
+struct OsdEvent {
+    /// CSS class added to the OSD while showing this event.
+    kind: &'static str,
+    /// Icon name to display.
+    icon: &'static str,
+    label: String,
+    /// Progress bar value between `0.0` and `1.0`.
+    /// The progress bar is hidden if not set.
+    value: Option<f64>,
+}
+
+/// Transient overlay shown in the centre of the screen
+/// when a watched value changes.
+#[derive(Debug, Clone)]
+struct Osd {
+    window: ApplicationWindow,
+    container: gtk::Box,
+    icon: Image,
+    label: Label,
+    progress: ProgressBar,
+    progress_value: AnimatedValue<ProgressBar>,
+
+    icon_theme: IconTheme,
+    icon_size: i32,
+    timeout: Duration,
+
+    kind: Rc<RefCell<Option<&'static str>>>,
+    hide_source: Rc<RefCell<Option<SourceId>>>,
+}
+
+impl Osd {
+    fn new(app: &Application, config: &OsdConfig) -> Self {
+        let window = ApplicationWindow::builder().application(app).build();
+
+        // no anchors or monitor means the compositor
+        // centres the surface on the focused output.
+        window.init_layer_shell();
+        window.set_layer(gtk_layer_shell::Layer::Overlay);
+        window.set_namespace(concat!(env!("CARGO_PKG_NAME"), "-osd"));
+
+        // never intercept the pointer
+        window.input_shape_combine_region(Some(&gtk::cairo::Region::create()));
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add_class("osd");
+
+        let icon = Image::new();
+        icon.add_class("icon");
+
+        let label = Label::new(None);
+        label.add_class("label");
+
+        let progress = ProgressBar::new();
+        progress.add_class("progress");
+
+        container.add(&icon);
+        container.add(&label);
+        container.add(&progress);
+
+        window.add(&container);
+        container.show_all();
+
+        Self {
+            window,
+            container,
+            icon,
+            label,
+            progress_value: AnimatedValue::new(&progress),
+            progress,
+            icon_theme: IconTheme::new(),
+            icon_size: config.icon_size,
+            timeout: Duration::from_millis(config.timeout),
+            kind: rc_mut!(None),
+            hide_source: rc_mut!(None),
+        }
+    }
+
+    /// Shows the OSD with the event's contents,
+    /// restarting the hide timeout.
+    fn show(&self, event: &OsdEvent) {
+        if let Some(kind) = self.kind.replace(Some(event.kind)) {
+            self.container.remove_class(kind);
+        }
+        self.container.add_class(event.kind);
+
+        let icon = format!("icon:{}", event.icon);
+        if let Some(provider) = ImageProvider::parse(&icon, &self.icon_theme, false, self.icon_size)
+        {
+            if let Err(err) = provider.load_into_image(&self.icon) {
+                error!("{err:?}");
+            }
+        }
+
+        self.label.set_label(&event.label);
+
+        if let Some(value) = event.value {
+            self.progress_value.set(value.clamp(0.0, 1.0));
+            self.progress.show();
+        } else {
+            self.progress.hide();
+        }
+
+        self.window.show();
+
+        if let Some(source) = self.hide_source.take() {
+            source.remove();
+        }
+
+        let window = self.window.clone();
+        let hide_source = self.hide_source.clone();
+        let source = glib::timeout_add_local_once(self.timeout, move || {
+            // source is automatically removed once complete
+            hide_source.take();
+            window.hide();
+        });
+
+        self.hide_source.replace(Some(source));
+    }
+}
+
+/// Creates the OSD window and starts listening for each enabled event source.
+///
+/// This is independent of any modules,
+/// so works even if no volume or keys modules are on a bar.
+pub fn start(app: &Application, ironbar: &Rc<Ironbar>) {
+    let Some(config) = ironbar.config.borrow().osd.clone() else {
+        return;
+    };
+
+    debug!("Starting OSD");
+
+    let osd = Osd::new(app, &config);
+    let (tx, rx) = mpsc::channel(16);
+
+    #[cfg(feature = "volume")]
+    if config.volume {
+        let client = ironbar.clients.borrow_mut().volume();
+        watch_volume(&client, tx.clone());
+    }
+
+    #[cfg(feature = "keys")]
+    if config.caps_lock || config.num_lock || config.scroll_lock {
+        let client = ironbar.clients.borrow_mut().libinput(&config.seat);
+        watch_lock_keys(&client, &config, tx.clone());
+    }
+
+    // without libinput, fall back to the keymap,
+    // which is only updated while ironbar has keyboard focus.
+    #[cfg(all(feature = "lock_keys", not(feature = "keys")))]
+    if config.caps_lock || config.num_lock || config.scroll_lock {
+        watch_keymap(&config, tx.clone());
+    }
+
+    if config.brightness {
+        watch_brightness(tx);
+    }
+
+    glib_recv_mpsc!(rx, event => osd.show(&event));
+}
+
+#[cfg(feature = "volume")]
+fn watch_volume(client: &crate::clients::volume::Client, tx: mpsc::Sender<OsdEvent>) {
+    use crate::clients::volume::Event;
+
+    let mut rx = client.subscribe();
+
+    spawn(async move {
+        // tracks the last known state of the default sink,
+        // so that unrelated sink updates do not trigger the OSD.
+        let mut last = None;
+
+        while let Ok(event) = rx.recv().await {
+            match event {
+                Event::AddSink(sink) if sink.active => {
+                    last = Some((sink.volume, sink.muted));
+                }
+                Event::UpdateSink(sink) if sink.active => {
+                    let state = Some((sink.volume, sink.muted));
+                    if state == last {
+                        continue;
+                    }
+
+                    let is_first = last.is_none();
+                    last = state;
+
+                    if is_first {
+                        continue;
+                    }
+
+                    let icon = if sink.muted {
+                        "audio-volume-muted-symbolic"
+                    } else if sink.volume < 33.0 {
+                        "audio-volume-low-symbolic"
+                    } else if sink.volume < 66.0 {
+                        "audio-volume-medium-symbolic"
+                    } else {
+                        "audio-volume-high-symbolic"
+                    };
+
+                    let label = if sink.muted {
+                        String::from("Muted")
+                    } else {
+                        format!("{}%", sink.volume.round())
+                    };
+
+                    let event = OsdEvent {
+                        kind: "volume",
+                        icon,
+                        label,
+                        value: Some(sink.volume / 100.0),
+                    };
+
+                    if tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[cfg(any(feature = "keys", feature = "lock_keys"))]
+#[derive(Debug, Clone, Copy)]
+enum LockKey {
+    Caps,
+    Num,
+    Scroll,
+}
+
+#[cfg(any(feature = "keys", feature = "lock_keys"))]
+impl LockKey {
+    /// Creates the OSD event for the key being toggled.
+    fn event(self, state: bool) -> OsdEvent {
+        let (kind, name, icon) = match self {
+            Self::Caps => (
+                "caps-lock",
+                "Caps Lock",
+                if state {
+                    "caps-lock-enabled-symbolic"
+                } else {
+                    "caps-lock-disabled-symbolic"
+                },
+            ),
+            Self::Num => ("num-lock", "Num Lock", "input-keyboard-symbolic"),
+            Self::Scroll => ("scroll-lock", "Scroll Lock", "input-keyboard-symbolic"),
+        };
+
+        OsdEvent {
+            kind,
+            icon,
+            label: format!("{name} {}", if state { "on" } else { "off" }),
+            value: None,
+        }
+    }
+}
+
+#[cfg(feature = "keys")]
+fn watch_lock_keys(
+    client: &crate::clients::libinput::Client,
+    config: &OsdConfig,
+    tx: mpsc::Sender<OsdEvent>,
+) {
+    use crate::clients::libinput::{Event, Key};
+
+    let mut rx = client.subscribe();
+
+    let (caps_lock, num_lock, scroll_lock) =
+        (config.caps_lock, config.num_lock, config.scroll_lock);
+
+    spawn(async move {
+        while let Ok(event) = rx.recv().await {
+            let Event::Key(event) = event else {
+                continue;
+            };
+
+            let key = match event.key {
+                Key::Caps if caps_lock => LockKey::Caps,
+                Key::Num if num_lock => LockKey::Num,
+                Key::Scroll if scroll_lock => LockKey::Scroll,
+                _ => continue,
+            };
+
+            if tx.send(key.event(event.state)).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(all(feature = "lock_keys", not(feature = "keys")))]
+fn watch_keymap(config: &OsdConfig, tx: mpsc::Sender<OsdEvent>) {
+    use gtk::gdk::{Display, Keymap};
+    use std::cell::Cell;
+
+    let Some(keymap) = Display::default().as_ref().and_then(Keymap::for_display) else {
+        error!("Couldn't get keymap, lock key changes will not be shown");
+        return;
+    };
+
+    let states = |keymap: &Keymap| {
+        [
+            (LockKey::Caps, keymap.is_caps_locked()),
+            (LockKey::Num, keymap.is_num_locked()),
+            (LockKey::Scroll, keymap.is_scroll_locked()),
+        ]
+    };
+
+    let enabled = [config.caps_lock, config.num_lock, config.scroll_lock];
+    let last = Cell::new(states(&keymap));
+
+    // the signal is also emitted for other modifiers, such as shift
+    keymap.connect_state_changed(move |keymap| {
+        let current = states(keymap);
+
+        for ((&(key, state), (_, last)), enabled) in current.iter().zip(last.get()).zip(enabled) {
+            if enabled && state != last {
+                if let Err(err) = tx.try_send(key.event(state)) {
+                    error!("{err:?}");
+                }
+            }
+        }
+
+        last.set(current);
+    });
+}
+
+fn watch_brightness(tx: mpsc::Sender<OsdEvent>) {
+    let Some(backlight) = Backlight::find() else {
+        debug!("No backlight device found, brightness OSD disabled");
+        return;
+    };
+
+    spawn(async move {
+        let mut interval = polling::interval(Duration::from_millis(BACKLIGHT_POLL_INTERVAL));
+        let mut last = None;
+
+        loop {
+            interval.tick().await;
+
+            // sysfs reads block, so keep them off the async runtime
+            let backlight = backlight.clone();
+            let Ok(Some(brightness)) = spawn_blocking(move || backlight.brightness()).await else {
+                continue;
+            };
+
+            // skip the initial reading
+            let previous = last.replace(brightness);
+            if previous.is_none() || previous == Some(brightness) {
+                continue;
+            }
+
+            let event = OsdEvent {
+                kind: "brightness",
+                icon: "display-brightness-symbolic",
+                label: format!("{}%", (brightness * 100.0).round()),
+                value: Some(brightness),
+            };
+
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+}
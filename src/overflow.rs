@@ -0,0 +1,181 @@
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{ModulePopupParts, PopupButton};
+use crate::popup::Popup;
+use crate::Ironbar;
+use gtk::prelude::*;
+use gtk::{Button, Orientation, Widget};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+/// Tag set on a module's container, holding its priority.
+const PRIORITY_TAG: &str = "overflow-priority";
+
+/// Sets the priority used to decide which modules overflow first.
+pub fn set_priority(container: &impl IsA<Widget>, priority: i32) {
+    container.set_tag(PRIORITY_TAG, priority);
+}
+
+fn priority(widget: &Widget) -> i32 {
+    widget
+        .get_tag::<i32>(PRIORITY_TAG)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// A module moved off the bar into the overflow popup,
+/// along with where to put it back.
+#[derive(Debug)]
+struct HiddenModule {
+    widget: Widget,
+    parent: gtk::Box,
+    position: i32,
+    /// The size of the module when it was on the bar.
+    size: i32,
+}
+
+/// Moves modules which do not fit onto the bar
+/// into the popup of a `»` button at the end of the bar.
+#[derive(Debug, Clone)]
+pub struct Overflow {
+    id: usize,
+    orientation: Orientation,
+
+    /// The start, center and end containers of the bar.
+    groups: Vec<gtk::Box>,
+    button: Button,
+    container: gtk::Box,
+    popup: Rc<Popup>,
+
+    /// Hidden modules, in the order they were hidden.
+    hidden: Rc<RefCell<Vec<HiddenModule>>>,
+}
+
+impl Overflow {
+    /// Creates the overflow button, adding it to the end of `end`.
+    /// The button is hidden until a module overflows.
+    pub fn new(groups: &[&gtk::Box], end: &gtk::Box, popup: Rc<Popup>) -> Self {
+        let orientation = end.orientation();
+
+        let button = Button::with_label("»");
+        button.add_class("overflow");
+        button.set_no_show_all(true);
+        end.add(&button);
+
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+        container.add_class("overflow-popup");
+
+        let id = Ironbar::unique_id();
+        popup.register_content(
+            id,
+            "overflow".to_string(),
+            ModulePopupParts {
+                container: container.clone(),
+                buttons: vec![button.clone()],
+            },
+        );
+
+        {
+            let popup = popup.clone();
+            button.connect_clicked(move |button| {
+                if popup.visible() && popup.current_widget().unwrap_or_default() == id {
+                    popup.hide();
+                } else {
+                    popup.show(id, button.popup_id());
+                }
+            });
+        }
+
+        Self {
+            id,
+            orientation,
+            groups: groups.iter().map(|&group| group.clone()).collect(),
+            button,
+            container,
+            popup,
+            hidden: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// Moves the visible module with the lowest priority into the overflow popup.
+    /// Where priorities are equal, the module furthest along the bar is moved first.
+    ///
+    /// Returns whether a module was moved.
+    pub fn collapse(&self) -> bool {
+        let modules = self
+            .groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .children()
+                    .into_iter()
+                    .map(move |widget| (group.clone(), widget))
+            })
+            .filter(|(_, widget)| {
+                widget.is_visible() && widget != self.button.upcast_ref::<Widget>()
+            })
+            .collect::<Vec<_>>();
+
+        let Some((parent, widget)) = modules
+            .into_iter()
+            .rev()
+            .min_by_key(|(_, widget)| priority(widget))
+        else {
+            return false;
+        };
+
+        let allocation = widget.allocation();
+        let size = if self.orientation == Orientation::Horizontal {
+            allocation.width()
+        } else {
+            allocation.height()
+        };
+
+        debug!("Moving module into overflow ({size}px)");
+
+        let position = parent.child_position(&widget);
+        parent.remove(&widget);
+        self.container.add(&widget);
+
+        self.hidden.borrow_mut().push(HiddenModule {
+            widget,
+            parent,
+            position,
+            size,
+        });
+
+        self.button.show();
+        true
+    }
+
+    /// Moves the most recently hidden module back onto the bar,
+    /// if there is room for its full size.
+    ///
+    /// Returns whether any modules were in the overflow popup.
+    pub fn expand(&self, required: i32, available: i32) -> bool {
+        let mut hidden = self.hidden.borrow_mut();
+
+        let Some(module) = hidden.last() else {
+            return false;
+        };
+
+        if required + module.size <= available {
+            let module = hidden.pop().expect("to exist");
+            debug!("Moving module out of overflow");
+
+            self.container.remove(&module.widget);
+            module.parent.add(&module.widget);
+            module.parent.reorder_child(&module.widget, module.position);
+        }
+
+        if hidden.is_empty() {
+            if self.popup.current_widget() == Some(self.id) {
+                self.popup.hide();
+            }
+
+            self.button.hide();
+        }
+
+        true
+    }
+}
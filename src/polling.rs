@@ -0,0 +1,161 @@
+//! Shared timing for modules which poll on an interval.
+//!
+//! Rather than each poller sleeping for its full interval after every poll,
+//! wake-ups are aligned to multiples of the interval on the system clock.
+//! Pollers with the same interval, or intervals which are multiples of each other,
+//! then wake together, so the system can stay idle for longer between wake-ups.
+
+use crate::config::default_true;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Global switch, set from the top-level `polling.align` option.
+static ALIGN: AtomicBool = AtomicBool::new(true);
+
+/// Maximum jitter in milliseconds, set from the top-level `polling.jitter` option.
+static JITTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PollingConfig {
+    /// Whether to align polling intervals to shared boundaries,
+    /// so that modules polling at the same rate wake up together.
+    ///
+    /// If disabled, each poller waits its full interval after every poll.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    pub align: bool,
+
+    /// Maximum number of milliseconds to randomly delay each poll by.
+    ///
+    /// This spreads out polls which would otherwise run at the same instant,
+    /// such as many scripts with the same interval,
+    /// at the cost of fewer shared wake-ups.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub jitter: u64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            align: true,
+            jitter: 0,
+        }
+    }
+}
+
+/// Applies the polling options for all pollers.
+pub fn configure(config: &PollingConfig) {
+    ALIGN.store(config.align, Ordering::Relaxed);
+    JITTER.store(config.jitter, Ordering::Relaxed);
+}
+
+/// Waits until the next poll for a poller running every `interval`.
+///
+/// When alignment is enabled, this is the next multiple of `interval`
+/// since the Unix epoch, so may be shorter than `interval`
+/// if the poll itself took some time.
+pub async fn sleep(interval: Duration) {
+    let delay = if ALIGN.load(Ordering::Relaxed) && !interval.is_zero() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        until_boundary(interval, now)
+    } else {
+        interval
+    };
+
+    let jitter = Duration::from_millis(JITTER.load(Ordering::Relaxed));
+    tokio::time::sleep(delay + random_jitter(jitter)).await;
+}
+
+/// Creates an [`Interval`] which ticks every `period`.
+pub const fn interval(period: Duration) -> Interval {
+    Interval {
+        period,
+        first: true,
+    }
+}
+
+/// Aligned equivalent of [`tokio::time::Interval`].
+///
+/// The first tick completes immediately,
+/// and following ticks wait using [`sleep`].
+#[derive(Debug)]
+pub struct Interval {
+    period: Duration,
+    first: bool,
+}
+
+impl Interval {
+    /// Waits until the next tick.
+    ///
+    /// This is cancel-safe, so can be used in `tokio::select!`.
+    pub async fn tick(&mut self) {
+        if std::mem::take(&mut self.first) {
+            return;
+        }
+
+        sleep(self.period).await;
+    }
+}
+
+/// Gets the time from `now` until the next multiple of `interval`.
+fn until_boundary(interval: Duration, now: Duration) -> Duration {
+    let interval = interval.as_nanos();
+    let remaining = interval - now.as_nanos() % interval;
+
+    Duration::from_nanos(u64::try_from(remaining).unwrap_or(u64::MAX))
+}
+
+/// Gets a random duration below `max`.
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+
+    // each `RandomState` is seeded differently,
+    // which is random enough to spread out wake-ups.
+    let random = RandomState::new().build_hasher().finish();
+    let max = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+
+    Duration::from_nanos(random % max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_until_boundary() {
+        let second = Duration::from_secs(1);
+
+        assert_eq!(
+            until_boundary(second, Duration::from_millis(10_250)),
+            Duration::from_millis(750)
+        );
+
+        // already on a boundary waits for the next one
+        assert_eq!(until_boundary(second, Duration::from_secs(10)), second);
+
+        assert_eq!(
+            until_boundary(Duration::from_secs(5), Duration::from_secs(12)),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn test_random_jitter() {
+        assert_eq!(random_jitter(Duration::ZERO), Duration::ZERO);
+
+        let max = Duration::from_millis(100);
+        assert!((0..100).all(|_| random_jitter(max) < max));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::clients::logind;
+use crate::config::default_true;
+use crate::{spawn, Ironbar};
+use serde::Deserialize;
+use tracing::{debug, error};
+use zbus::zvariant::OwnedFd;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PresentationConfig {
+    /// Whether to enable SwayNC's do-not-disturb mode
+    /// while presentation mode is active.
+    /// The previous state is restored when presentation mode is turned off.
+    ///
+    /// Requires the `notifications` feature.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    #[cfg_attr(not(feature = "notifications"), allow(dead_code))]
+    dnd: bool,
+
+    /// Whether to take a logind idle inhibitor lock
+    /// while presentation mode is active,
+    /// preventing the screen from locking or blanking.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    inhibit_idle: bool,
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        Self {
+            dnd: true,
+            inhibit_idle: true,
+        }
+    }
+}
+
+/// Starts listening for presentation mode changes on the event bus,
+/// applying and reverting the configured side effects.
+///
+/// Modules with `hide_in_presentation` set handle their own visibility.
+pub fn start(ironbar: &Ironbar) {
+    let config = ironbar
+        .config
+        .borrow()
+        .presentation
+        .clone()
+        .unwrap_or_default();
+
+    #[cfg(feature = "notifications")]
+    let notifications = if config.dnd {
+        match ironbar.clients.borrow_mut().notifications() {
+            Ok(client) => Some(client),
+            Err(err) => {
+                debug!("SwayNC unavailable, presentation mode will not toggle DnD: {err:?}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut rx = Ironbar::event_bus().subscribe();
+
+    spawn(async move {
+        // held for as long as presentation mode is active.
+        // the lock is released when the fd is dropped.
+        let mut inhibitor: Option<OwnedFd> = None;
+
+        #[cfg(feature = "notifications")]
+        let mut previous_dnd = None;
+
+        while let Ok(event) = rx.recv().await {
+            let Some(enabled) = event.presentation() else {
+                continue;
+            };
+            debug!("Presentation mode: {enabled}");
+
+            if config.inhibit_idle {
+                if enabled {
+                    match logind::inhibit_idle("Presentation mode").await {
+                        Ok(fd) => inhibitor = Some(fd),
+                        Err(err) => error!("Failed to inhibit idle: {err:?}"),
+                    }
+                } else {
+                    inhibitor.take();
+                }
+            }
+
+            #[cfg(feature = "notifications")]
+            if let Some(client) = &notifications {
+                let res = if enabled {
+                    match client.state().await {
+                        Ok(state) => {
+                            previous_dnd = Some(state.dnd);
+                            client.set_dnd(true).await
+                        }
+                        Err(err) => Err(err),
+                    }
+                } else {
+                    match previous_dnd.take() {
+                        Some(dnd) => client.set_dnd(dnd).await,
+                        None => Ok(()),
+                    }
+                };
+
+                if let Err(err) = res {
+                    error!("Failed to set DnD state: {err:?}");
+                }
+            }
+        }
+    });
+}
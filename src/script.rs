@@ -1,3 +1,4 @@
+use crate::polling;
 use crate::{send_async, spawn};
 use color_eyre::eyre::WrapErr;
 use color_eyre::{Report, Result};
@@ -9,7 +10,6 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::select;
 use tokio::sync::mpsc;
-use tokio::time::sleep;
 use tracing::{debug, error, trace, warn};
 
 #[derive(Debug, Deserialize, Clone)]
@@ -20,10 +20,11 @@ pub enum ScriptInput {
     Struct(Script),
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ScriptMode {
+    #[default]
     Poll,
     Watch,
 }
@@ -47,12 +48,6 @@ impl From<&str> for ScriptMode {
     }
 }
 
-impl Default for ScriptMode {
-    fn default() -> Self {
-        Self::Poll
-    }
-}
-
 impl Display for ScriptMode {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -218,7 +213,7 @@ impl Script {
                 },
             };
 
-            sleep(tokio::time::Duration::from_millis(self.interval)).await;
+            polling::sleep(tokio::time::Duration::from_millis(self.interval)).await;
         }
     }
 
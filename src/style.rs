@@ -7,9 +7,7 @@ use notify::event::ModifyKind;
 use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Result, Watcher};
 use std::env;
 use std::path::PathBuf;
-use std::time::Duration;
 use tokio::sync::mpsc;
-use tokio::time::sleep;
 use tracing::{debug, error, info};
 
 /// Attempts to load CSS file at the given path
@@ -67,9 +65,7 @@ pub fn load_css(style_path: PathBuf, application: Application) {
         debug!("Installed CSS file watcher on '{}'", style_path.display());
 
         // avoid watcher from dropping
-        loop {
-            sleep(Duration::from_secs(1)).await;
-        }
+        std::future::pending::<()>().await;
     });
 
     glib_recv_mpsc!(rx, path => {
@@ -0,0 +1,57 @@
+use crate::clients::compositor::{Visibility, Window, Workspace, WorkspaceClient, WorkspaceUpdate};
+use color_eyre::Result;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// A workspace client which only sends the updates it is given,
+/// standing in for a compositor's IPC.
+#[derive(Debug)]
+pub struct MockWorkspaceClient {
+    tx: broadcast::Sender<WorkspaceUpdate>,
+    _rx: broadcast::Receiver<WorkspaceUpdate>,
+}
+
+impl MockWorkspaceClient {
+    pub fn new() -> Arc<Self> {
+        let (tx, rx) = broadcast::channel(32);
+        Arc::new(Self { tx, _rx: rx })
+    }
+
+    /// Sends the update to all subscribers,
+    /// as if it came from the compositor.
+    pub fn send(&self, update: WorkspaceUpdate) {
+        self.tx.send(update).expect("to send workspace update");
+    }
+}
+
+impl WorkspaceClient for MockWorkspaceClient {
+    fn focus(&self, _name: String) -> Result<()> {
+        Ok(())
+    }
+
+    fn subscribe_workspace_change(&self) -> broadcast::Receiver<WorkspaceUpdate> {
+        self.tx.subscribe()
+    }
+
+    fn windows(&self) -> Result<Vec<Window>> {
+        Ok(vec![])
+    }
+
+    fn move_window(&self, _id: &str, _workspace: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates a workspace on the `test` monitor.
+pub fn workspace(id: i64, name: &str, focused: bool) -> Workspace {
+    Workspace {
+        id,
+        name: name.to_string(),
+        monitor: String::from("test"),
+        visibility: if focused {
+            Visibility::focused()
+        } else {
+            Visibility::Hidden
+        },
+    }
+}
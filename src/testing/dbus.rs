@@ -0,0 +1,24 @@
+use color_eyre::Result;
+use tokio::net::UnixStream;
+use zbus::{Connection, ConnectionBuilder, Guid};
+
+/// Creates a pair of connected peer-to-peer D-Bus connections,
+/// returned as `(server, client)`.
+///
+/// Mock services are served on the server's object server,
+/// and the client is passed to the code under test.
+pub async fn peer_connections() -> Result<(Connection, Connection)> {
+    let (server, client) = UnixStream::pair()?;
+    let guid = Guid::generate();
+
+    // the handshake requires both sides to be built together
+    let (server, client) = tokio::try_join!(
+        ConnectionBuilder::unix_stream(server)
+            .server(&guid)
+            .p2p()
+            .build(),
+        ConnectionBuilder::unix_stream(client).p2p().build(),
+    )?;
+
+    Ok((server, client))
+}
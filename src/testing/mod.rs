@@ -0,0 +1,45 @@
+//! Mock services and helpers for testing clients and controllers
+//! without a running compositor or system daemons.
+//!
+//! D-Bus services are served over a private peer-to-peer connection,
+//! so no bus daemon is required.
+
+#[cfg(feature = "workspaces")]
+pub mod compositor;
+#[cfg(feature = "zbus")]
+pub mod dbus;
+#[cfg(feature = "upower")]
+pub mod upower;
+
+use std::fmt::Debug;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+
+/// How long to wait for an expected event before failing.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Waits for the next event on the channel.
+///
+/// # Panics
+///
+/// If no event is received within a second,
+/// or the channel is closed.
+pub async fn recv<T: Clone>(rx: &mut broadcast::Receiver<T>) -> T {
+    timeout(TIMEOUT, rx.recv())
+        .await
+        .expect("timed out waiting for event")
+        .expect("channel to be open")
+}
+
+/// Checks no event is received on the channel
+/// within a short period.
+///
+/// # Panics
+///
+/// If an event is received.
+pub async fn assert_no_event<T: Clone + Debug>(rx: &mut broadcast::Receiver<T>) {
+    if let Ok(Ok(event)) = timeout(Duration::from_millis(100), rx.recv()).await {
+        panic!("unexpected event: {event:?}");
+    }
+}
@@ -0,0 +1,110 @@
+use super::dbus::peer_connections;
+use color_eyre::Result;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::{dbus_interface, Connection};
+
+const PATH: &str = "/org/freedesktop/UPower";
+
+/// The top-level `UPower` daemon object.
+#[derive(Debug)]
+struct MockUPower {
+    devices: Vec<OwnedObjectPath>,
+}
+
+#[dbus_interface(name = "org.freedesktop.UPower")]
+impl MockUPower {
+    fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+        self.devices.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn on_battery(&self) -> bool {
+        false
+    }
+}
+
+/// A device known to the mock `UPower` daemon.
+#[derive(Debug, Clone)]
+pub struct MockDevice {
+    pub kind: u32,
+    pub model: String,
+    pub percentage: f64,
+    pub state: u32,
+    pub icon_name: String,
+    pub is_present: bool,
+}
+
+impl MockDevice {
+    pub fn new(kind: u32, model: &str, percentage: f64) -> Self {
+        Self {
+            kind,
+            model: model.to_string(),
+            percentage,
+            state: 0,
+            icon_name: String::new(),
+            is_present: true,
+        }
+    }
+}
+
+#[dbus_interface(name = "org.freedesktop.UPower.Device")]
+impl MockDevice {
+    #[dbus_interface(property, name = "Type")]
+    fn kind(&self) -> u32 {
+        self.kind
+    }
+
+    #[dbus_interface(property)]
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn percentage(&self) -> f64 {
+        self.percentage
+    }
+
+    #[dbus_interface(property)]
+    fn state(&self) -> u32 {
+        self.state
+    }
+
+    #[dbus_interface(property)]
+    fn icon_name(&self) -> String {
+        self.icon_name.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn is_present(&self) -> bool {
+        self.is_present
+    }
+}
+
+/// Gets the object path the mock device at `index` is served at.
+pub fn device_path(index: usize) -> OwnedObjectPath {
+    ObjectPath::try_from(format!("{PATH}/devices/mock_{index}"))
+        .expect("valid object path")
+        .into()
+}
+
+/// Serves a mock `UPower` daemon with the devices,
+/// returning the `(server, client)` connections.
+pub async fn serve(devices: Vec<MockDevice>) -> Result<(Connection, Connection)> {
+    let (server, client) = peer_connections().await?;
+
+    let paths = (0..devices.len()).map(device_path).collect();
+
+    for (index, device) in devices.into_iter().enumerate() {
+        server
+            .object_server()
+            .at(device_path(index), device)
+            .await?;
+    }
+
+    server
+        .object_server()
+        .at(PATH, MockUPower { devices: paths })
+        .await?;
+
+    Ok((server, client))
+}
@@ -0,0 +1,147 @@
+//! Display-aware text helpers for labels.
+//!
+//! Widths are measured in terminal-style columns,
+//! where most characters take one column
+//! and East Asian wide characters (such as CJK) and emoji take two.
+//! Text is split into grapheme clusters so that combining marks,
+//! ZWJ emoji sequences and flags are never cut apart.
+
+#[cfg(feature = "menu")]
+use crate::config::EllipsizeMode;
+use std::borrow::Cow;
+use unicode_bidi::BidiClass;
+#[cfg(feature = "menu")]
+use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "menu")]
+use unicode_width::UnicodeWidthChar;
+
+#[cfg(feature = "menu")]
+const ELLIPSIS: &str = "…";
+
+/// First Strong Isolate.
+const FSI: char = '\u{2068}';
+/// Pop Directional Isolate.
+const PDI: char = '\u{2069}';
+
+/// Gets the number of columns taken by a single grapheme cluster.
+///
+/// The cluster takes the width of its first character,
+/// as any following characters combine with it.
+#[cfg(feature = "menu")]
+fn grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+
+    // control characters have no width
+    let width = first.width().unwrap_or_default();
+
+    // emoji presentation selector turns narrow symbols into emoji
+    if width > 0 && chars.any(|c| c == '\u{FE0F}') {
+        2
+    } else {
+        width
+    }
+}
+
+/// Gets the display width of the text, in columns.
+#[cfg(feature = "menu")]
+pub fn width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Truncates the text to at most `max_width` columns,
+/// replacing the removed part with an ellipsis
+/// at the position given by `mode`.
+///
+/// Text which already fits is returned unchanged.
+#[cfg(feature = "menu")]
+pub fn truncate(text: &str, max_width: usize, mode: EllipsizeMode) -> Cow<'_, str> {
+    if matches!(mode, EllipsizeMode::None) || width(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+
+    let available = max_width.saturating_sub(width(ELLIPSIS));
+    let graphemes = text.graphemes(true).collect::<Vec<_>>();
+
+    let (head, tail) = match mode {
+        EllipsizeMode::Start => (0, available),
+        EllipsizeMode::Middle => (available - available / 2, available / 2),
+        EllipsizeMode::End | EllipsizeMode::None => (available, 0),
+    };
+
+    let start = take_width(graphemes.iter().copied(), head);
+    let end = take_width(graphemes.iter().rev().copied(), tail);
+
+    let mut truncated = graphemes[..start].concat();
+    truncated.push_str(ELLIPSIS);
+    truncated.push_str(&graphemes[graphemes.len() - end..].concat());
+
+    Cow::Owned(truncated)
+}
+
+/// Gets the number of graphemes which fit in `max_width` columns.
+#[cfg(feature = "menu")]
+fn take_width<'a>(graphemes: impl Iterator<Item = &'a str>, max_width: usize) -> usize {
+    let mut width = 0;
+
+    graphemes
+        .take_while(|grapheme| {
+            width += grapheme_width(grapheme);
+            width <= max_width
+        })
+        .count()
+}
+
+/// Checks whether the text contains any right-to-left characters.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars()
+        .any(|c| matches!(unicode_bidi::bidi_class(c), BidiClass::R | BidiClass::AL))
+}
+
+/// Wraps a value substituted into a format string in a bidi isolate,
+/// so that right-to-left text (such as a Hebrew or Arabic song title)
+/// does not reorder the surrounding text and separators.
+///
+/// Text without any right-to-left characters is returned unchanged.
+pub fn isolate(text: &str) -> Cow<'_, str> {
+    if has_rtl(text) {
+        Cow::Owned(format!("{FSI}{text}{PDI}"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "menu")]
+    fn test_width() {
+        assert_eq!(width("hello"), 5);
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(width("👍🏽"), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "menu")]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 5, EllipsizeMode::End), "hello");
+        assert_eq!(truncate("hello world", 6, EllipsizeMode::End), "hello…");
+        assert_eq!(truncate("hello world", 6, EllipsizeMode::Start), "…world");
+        assert_eq!(truncate("hello world", 6, EllipsizeMode::Middle), "hel…ld");
+
+        // wide characters are never split across the limit
+        assert_eq!(truncate("日本語テキスト", 6, EllipsizeMode::End), "日本…");
+    }
+
+    #[test]
+    fn test_isolate() {
+        assert_eq!(isolate("abc"), "abc");
+        assert_eq!(isolate("שלום"), "\u{2068}שלום\u{2069}");
+    }
+}